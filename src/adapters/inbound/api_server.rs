@@ -3,22 +3,34 @@
 //! HTTP API for backends to register themselves and send heartbeats.
 //! Enables dynamic backend discovery without manual routing.db updates.
 
+use super::listener::ListenOptions;
+use crate::application::ProxyService;
 use crate::domain::entities::Backend;
 use crate::domain::value_objects::RegionCode;
+use crate::infrastructure::ShutdownController;
 use axum::{
-    extract::{Path, State},
+    extract::{
+        rejection::{JsonRejection, QueryRejection},
+        Path, Query, State,
+    },
     http::StatusCode,
-    response::IntoResponse,
+    response::{IntoResponse, Response},
     routing::{delete, get, post},
     Json, Router,
 };
 use dashmap::DashMap;
 use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tokio::net::TcpListener;
 use tower_http::trace::TraceLayer;
 
+/// Prefix marking a listen address as a Unix domain socket path.
+const UNIX_ADDR_PREFIX: &str = "unix:";
+
+/// Default permissions of the API's Unix socket (owner and group only).
+pub const DEFAULT_SOCKET_MODE: u32 = 0o660;
+
 /// Registration request from a backend.
 #[derive(Debug, Clone, Deserialize)]
 pub struct RegisterRequest {
@@ -35,6 +47,31 @@ pub struct RegisterRequest {
     pub soft_limit: u32,
     #[serde(default = "default_hard_limit")]
     pub hard_limit: u32,
+    /// Take the backend out of rotation for maintenance
+    #[serde(default)]
+    pub maintenance: bool,
+    /// SRV priority tier (lower is preferred)
+    #[serde(default)]
+    pub priority: u16,
+}
+
+impl RegisterRequest {
+    /// Check the fields deserialization can't, returning what is wrong.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.id.trim().is_empty() {
+            return Err("id must not be empty".to_string());
+        }
+        if self.app.trim().is_empty() {
+            return Err("app must not be empty".to_string());
+        }
+        if self.ip.parse::<IpAddr>().is_err() {
+            return Err(format!("ip {:?} is not an IP address", self.ip));
+        }
+        if self.port == 0 {
+            return Err("port must not be 0".to_string());
+        }
+        Ok(())
+    }
 }
 
 fn default_weight() -> u8 {
@@ -64,6 +101,8 @@ pub struct BackendStatus {
     pub ip: String,
     pub port: u16,
     pub healthy: bool,
+    /// Down for maintenance; reported apart from `healthy`
+    pub maintenance: bool,
     pub last_heartbeat_secs: u64,
     pub registered_secs: u64,
 }
@@ -83,6 +122,102 @@ pub struct HealthResponse {
     pub registered_backends: usize,
 }
 
+/// Query string for the routing dry run.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RouteQuery {
+    pub ip: String,
+    #[serde(default)]
+    pub app: Option<String>,
+}
+
+/// Query string accepted by `/api/v1/register`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RegisterQuery {
+    /// Overwrite an existing registration even if it conflicts
+    #[serde(default)]
+    pub force: bool,
+}
+
+/// Error response, serialized as `{"error": {"code": ..., "message": ...}}`.
+///
+/// `code` is stable and meant for clients to match on; `message` is for
+/// humans and may change. Some errors add a `details` object.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiError {
+    pub status: StatusCode,
+    pub code: &'static str,
+    pub message: String,
+    pub details: Option<serde_json::Value>,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            code,
+            message: message.into(),
+            details: None,
+        }
+    }
+
+    /// Attach machine-readable context to the error.
+    pub fn with_details(mut self, details: serde_json::Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+
+    fn backend_not_found(id: &str) -> Self {
+        Self::new(StatusCode::NOT_FOUND, "backend_not_found", "backend not found")
+            .with_details(serde_json::json!({ "id": id }))
+    }
+
+    fn routing_unavailable() -> Self {
+        Self::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "routing_unavailable",
+            "routing service not configured",
+        )
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let mut error = serde_json::json!({
+            "code": self.code,
+            "message": self.message,
+        });
+        if let Some(details) = self.details {
+            error["details"] = details;
+        }
+        (self.status, Json(serde_json::json!({ "error": error }))).into_response()
+    }
+}
+
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> Self {
+        Self::new(rejection.status(), "invalid_request", rejection.body_text())
+    }
+}
+
+impl From<QueryRejection> for ApiError {
+    fn from(rejection: QueryRejection) -> Self {
+        Self::new(rejection.status(), "invalid_request", rejection.body_text())
+    }
+}
+
+/// Result of registering a backend id.
+#[derive(Debug, Clone)]
+pub enum RegisterOutcome {
+    /// The id was not registered before
+    Created(RegisteredBackend),
+    /// The id was re-registered with the same app and address; counts as a heartbeat
+    Refreshed(RegisteredBackend),
+    /// A conflicting registration was overwritten because `force` was set
+    Replaced(RegisteredBackend),
+    /// The id is registered with a different app or address (holds the existing entry)
+    Conflict(RegisteredBackend),
+}
+
 /// Registered backend with metadata.
 #[derive(Debug, Clone)]
 pub struct RegisteredBackend {
@@ -98,6 +233,12 @@ pub struct ApiState {
     pub backends: Arc<DashMap<String, RegisteredBackend>>,
     /// Heartbeat TTL - backends removed after this time without heartbeat
     pub heartbeat_ttl: Duration,
+    /// Routing service answering `/route` dry runs (None disables the endpoint)
+    pub proxy_service: Option<Arc<ProxyService>>,
+    /// Drain state flipped by `/admin/drain` and reported by `/ready`
+    pub shutdown: ShutdownController,
+    /// Effective configuration served by `/config`, already redacted
+    pub config: Option<Arc<serde_json::Value>>,
 }
 
 impl ApiState {
@@ -105,6 +246,9 @@ impl ApiState {
         Self {
             backends: Arc::new(DashMap::new()),
             heartbeat_ttl: Duration::from_secs(heartbeat_ttl_secs),
+            proxy_service: None,
+            shutdown: ShutdownController::new(),
+            config: None,
         }
     }
 
@@ -138,6 +282,7 @@ impl ApiState {
                     ip: entry.backend.wg_ip.clone(),
                     port: entry.backend.port,
                     healthy,
+                    maintenance: entry.backend.maintenance,
                     last_heartbeat_secs: now.duration_since(entry.last_heartbeat).as_secs(),
                     registered_secs: now.duration_since(entry.registered_at).as_secs(),
                 }
@@ -148,11 +293,62 @@ impl ApiState {
     /// Register or update a backend.
     pub fn register(&self, req: RegisterRequest) -> RegisteredBackend {
         let now = Instant::now();
-        let region = RegionCode::from_str(&req.region);
+        let registered = RegisteredBackend {
+            backend: Self::backend_from_request(req),
+            registered_at: now,
+            last_heartbeat: now,
+        };
+
+        self.backends.insert(registered.backend.id.clone(), registered.clone());
+        registered
+    }
+
+    /// Register a backend, refusing to silently overwrite a conflicting entry.
+    ///
+    /// Re-registering with the same app, region, country and address refreshes
+    /// the heartbeat and picks up new weight, limits and maintenance flag. Any
+    /// other difference is a conflict unless `force` is set.
+    pub fn try_register(&self, req: RegisterRequest, force: bool) -> RegisterOutcome {
+        let now = Instant::now();
+        let backend = Self::backend_from_request(req);
+
+        match self.backends.entry(backend.id.clone()) {
+            dashmap::mapref::entry::Entry::Vacant(entry) => {
+                let registered = RegisteredBackend {
+                    backend,
+                    registered_at: now,
+                    last_heartbeat: now,
+                };
+                entry.insert(registered.clone());
+                RegisterOutcome::Created(registered)
+            }
+            dashmap::mapref::entry::Entry::Occupied(mut entry) => {
+                let existing = entry.get_mut();
+                if Self::same_identity(&existing.backend, &backend) {
+                    existing.backend = backend;
+                    existing.last_heartbeat = now;
+                    RegisterOutcome::Refreshed(existing.clone())
+                } else if force {
+                    let registered = RegisteredBackend {
+                        backend,
+                        registered_at: now,
+                        last_heartbeat: now,
+                    };
+                    *existing = registered.clone();
+                    RegisterOutcome::Replaced(registered)
+                } else {
+                    RegisterOutcome::Conflict(existing.clone())
+                }
+            }
+        }
+    }
+
+    fn backend_from_request(req: RegisterRequest) -> Backend {
+        let region = RegionCode::from_code(&req.region);
         // Use provided country or derive from region
         let country = req.country.unwrap_or_else(|| region.default_country().to_string());
-        let backend = Backend {
-            id: req.id.clone(),
+        Backend {
+            id: req.id,
             app: req.app,
             region,
             country,
@@ -162,16 +358,19 @@ impl ApiState {
             weight: req.weight,
             soft_limit: req.soft_limit,
             hard_limit: req.hard_limit,
-        };
-
-        let registered = RegisteredBackend {
-            backend,
-            registered_at: now,
-            last_heartbeat: now,
-        };
+            draining: false,
+            maintenance: req.maintenance,
+            priority: req.priority,
+        }
+    }
 
-        self.backends.insert(req.id.clone(), registered.clone());
-        registered
+    /// Whether two registrations describe the same backend (ignoring capacity tuning).
+    fn same_identity(a: &Backend, b: &Backend) -> bool {
+        a.app == b.app
+            && a.region == b.region
+            && a.country == b.country
+            && a.wg_ip == b.wg_ip
+            && a.port == b.port
     }
 
     /// Update heartbeat for a backend.
@@ -210,7 +409,10 @@ impl ApiState {
 
 /// API Server for Auto-Discovery.
 pub struct ApiServer {
+    /// TCP address, or `unix:/path/to.sock` for a Unix domain socket
     listen_addr: String,
+    socket_mode: u32,
+    listen_options: ListenOptions,
     state: ApiState,
 }
 
@@ -218,10 +420,45 @@ impl ApiServer {
     pub fn new(listen_addr: String, heartbeat_ttl_secs: u64) -> Self {
         Self {
             listen_addr,
+            socket_mode: DEFAULT_SOCKET_MODE,
+            listen_options: ListenOptions::default(),
             state: ApiState::new(heartbeat_ttl_secs),
         }
     }
 
+    /// Set the file permissions of the Unix socket (ignored for TCP).
+    pub fn with_socket_mode(mut self, mode: u32) -> Self {
+        self.socket_mode = mode;
+        self
+    }
+
+    /// Set socket options for the TCP listener (ignored for Unix sockets).
+    pub fn with_listen_options(mut self, listen_options: ListenOptions) -> Self {
+        self.listen_options = listen_options;
+        self
+    }
+
+    /// Answer `GET /route` dry runs with this proxy service.
+    pub fn with_proxy_service(mut self, proxy_service: Arc<ProxyService>) -> Self {
+        self.state.proxy_service = Some(proxy_service);
+        self
+    }
+
+    /// Drain through this controller, shared with the proxy listeners.
+    pub fn with_shutdown(mut self, shutdown: ShutdownController) -> Self {
+        self.state.shutdown = shutdown;
+        self
+    }
+
+    /// Serve this configuration on `GET /config`.
+    ///
+    /// The value is returned as is, so secrets must already be redacted
+    /// (see `Config::redacted`).
+    pub fn with_config(mut self, config: serde_json::Value) -> Self {
+        self.state.config = Some(Arc::new(config));
+        self
+    }
+
     /// Get shared state for use by other components.
     #[allow(dead_code)]
     pub fn state(&self) -> ApiState {
@@ -230,10 +467,77 @@ impl ApiServer {
 
     /// Run the API server.
     ///
+    /// Listens on a Unix domain socket when the address is
+    /// `unix:/path/to.sock`, on TCP otherwise.
     /// The final Ok(()) is excluded from coverage since axum::serve runs forever.
     #[cfg_attr(coverage_nightly, coverage(off))]
     pub async fn run(&self) -> anyhow::Result<()> {
-        let app = Router::new()
+        let app = self.router();
+
+        if let Some(path) = self.listen_addr.strip_prefix(UNIX_ADDR_PREFIX) {
+            return self.serve_unix(std::path::Path::new(path), app).await;
+        }
+
+        let listener = self.listen_options.bind_tcp(&self.listen_addr).await?;
+        tracing::info!("Auto-Discovery API listening on {}", self.listen_addr);
+
+        axum::serve(listener, app).await?;
+        Ok(())
+    }
+
+    /// Serve the API on a Unix domain socket at `path`.
+    ///
+    /// A socket left behind by an earlier run is replaced. axum 0.7 only
+    /// serves TCP listeners, so connections are handed to hyper directly.
+    #[cfg(unix)]
+    async fn serve_unix(&self, path: &std::path::Path, app: Router) -> anyhow::Result<()> {
+        use hyper_util::rt::TokioIo;
+        use hyper_util::service::TowerToHyperService;
+        use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+        use tokio::net::UnixListener;
+
+        if std::fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_socket()) {
+            std::fs::remove_file(path)?;
+        }
+
+        let listener = UnixListener::bind(path)?;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(self.socket_mode))?;
+        tracing::info!(
+            "Auto-Discovery API listening on {}{} (mode {:o})",
+            UNIX_ADDR_PREFIX,
+            path.display(),
+            self.socket_mode
+        );
+
+        loop {
+            let stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    tracing::warn!("API socket accept error: {:?}", e);
+                    continue;
+                }
+            };
+
+            let service = TowerToHyperService::new(app.clone());
+            tokio::spawn(async move {
+                if let Err(e) = hyper::server::conn::http1::Builder::new()
+                    .serve_connection(TokioIo::new(stream), service)
+                    .await
+                {
+                    tracing::debug!("API socket connection error: {:?}", e);
+                }
+            });
+        }
+    }
+
+    #[cfg(not(unix))]
+    async fn serve_unix(&self, path: &std::path::Path, _app: Router) -> anyhow::Result<()> {
+        anyhow::bail!("Unix socket {} is not supported on this platform", path.display())
+    }
+
+    /// Routes served by the API.
+    fn router(&self) -> Router {
+        Router::new()
             // Health endpoint
             .route("/health", get(health_handler))
             // Backend registration
@@ -246,14 +550,18 @@ impl ApiServer {
             .route("/api/v1/backends", get(list_backends_handler))
             // Get specific backend
             .route("/api/v1/backends/:id", get(get_backend_handler))
+            // Routing dry run
+            .route("/route", get(route_handler))
+            .route("/regions", get(regions_handler))
+            .route("/apps", get(apps_handler))
+            // Effective configuration (redacted)
+            .route("/config", get(config_handler))
+            // Readiness and drain control
+            .route("/ready", get(ready_handler))
+            .route("/admin/drain", post(drain_handler))
+            .route("/admin/undrain", post(undrain_handler))
             .layer(TraceLayer::new_for_http())
-            .with_state(self.state.clone());
-
-        let listener = TcpListener::bind(&self.listen_addr).await?;
-        tracing::info!("Auto-Discovery API listening on {}", self.listen_addr);
-
-        axum::serve(listener, app).await?;
-        Ok(())
+            .with_state(self.state.clone())
     }
 
     /// Start background cleanup task.
@@ -283,68 +591,123 @@ async fn health_handler(State(state): State<ApiState>) -> impl IntoResponse {
     Json(response)
 }
 
+async fn ready_handler(State(state): State<ApiState>) -> impl IntoResponse {
+    if state.shutdown.is_draining() {
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(serde_json::json!({ "status": "draining" })),
+        )
+    } else {
+        (StatusCode::OK, Json(serde_json::json!({ "status": "ready" })))
+    }
+}
+
+async fn drain_handler(State(state): State<ApiState>) -> impl IntoResponse {
+    state.shutdown.drain();
+    Json(serde_json::json!({
+        "draining": true,
+        "active_connections": state.shutdown.active_connections()
+    }))
+}
+
+async fn undrain_handler(State(state): State<ApiState>) -> Result<impl IntoResponse, ApiError> {
+    if state.shutdown.undrain() {
+        Ok(Json(serde_json::json!({ "draining": false })))
+    } else {
+        Err(ApiError::new(
+            StatusCode::CONFLICT,
+            "shutdown_in_progress",
+            "shutdown already in progress",
+        )
+        .with_details(serde_json::json!({ "draining": true })))
+    }
+}
+
 async fn register_handler(
     State(state): State<ApiState>,
-    Json(req): Json<RegisterRequest>,
-) -> impl IntoResponse {
+    query: Result<Query<RegisterQuery>, QueryRejection>,
+    req: Result<Json<RegisterRequest>, JsonRejection>,
+) -> Result<impl IntoResponse, ApiError> {
+    let Query(query) = query?;
+    let Json(req) = req?;
+    req.validate()
+        .map_err(|message| ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, "validation_failed", message))?;
     let id = req.id.clone();
-    let _registered = state.register(req);
 
-    tracing::info!("registered backend: {}", id);
+    let (status, message) = match state.try_register(req, query.force) {
+        RegisterOutcome::Created(_) => {
+            tracing::info!("registered backend: {}", id);
+            (StatusCode::CREATED, "Backend registered successfully")
+        }
+        RegisterOutcome::Refreshed(_) => {
+            tracing::debug!("refreshed registration: {}", id);
+            (StatusCode::OK, "Backend registration refreshed")
+        }
+        RegisterOutcome::Replaced(_) => {
+            tracing::warn!("forced re-registration of backend: {}", id);
+            (StatusCode::OK, "Backend registration replaced")
+        }
+        RegisterOutcome::Conflict(existing) => {
+            tracing::warn!(
+                "rejected conflicting registration of {} (registered as {} at {}:{})",
+                id,
+                existing.backend.app,
+                existing.backend.wg_ip,
+                existing.backend.port
+            );
+            return Err(ApiError::new(
+                StatusCode::CONFLICT,
+                "backend_conflict",
+                "backend id already registered with a different app or address",
+            )
+            .with_details(serde_json::json!({
+                "id": id,
+                "registered": {
+                    "app": existing.backend.app,
+                    "region": existing.backend.region.as_str(),
+                    "country": existing.backend.country,
+                    "ip": existing.backend.wg_ip,
+                    "port": existing.backend.port
+                }
+            })));
+        }
+    };
 
     let response = RegisterResponse {
         id,
         registered: true,
-        message: "Backend registered successfully".to_string(),
+        message: message.to_string(),
     };
-    (StatusCode::CREATED, Json(response))
+    Ok((status, Json(response)))
 }
 
 async fn heartbeat_handler(
     State(state): State<ApiState>,
     Path(id): Path<String>,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, ApiError> {
     if state.heartbeat(&id) {
         tracing::debug!("heartbeat from: {}", id);
-        (
-            StatusCode::OK,
-            Json(serde_json::json!({
-                "id": id,
-                "status": "ok"
-            })),
-        )
+        Ok(Json(serde_json::json!({
+            "id": id,
+            "status": "ok"
+        })))
     } else {
-        (
-            StatusCode::NOT_FOUND,
-            Json(serde_json::json!({
-                "id": id,
-                "error": "backend not registered"
-            })),
-        )
+        Err(ApiError::backend_not_found(&id))
     }
 }
 
 async fn deregister_handler(
     State(state): State<ApiState>,
     Path(id): Path<String>,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, ApiError> {
     if state.deregister(&id) {
         tracing::info!("deregistered backend: {}", id);
-        (
-            StatusCode::OK,
-            Json(serde_json::json!({
-                "id": id,
-                "deregistered": true
-            })),
-        )
+        Ok(Json(serde_json::json!({
+            "id": id,
+            "deregistered": true
+        })))
     } else {
-        (
-            StatusCode::NOT_FOUND,
-            Json(serde_json::json!({
-                "id": id,
-                "error": "backend not found"
-            })),
-        )
+        Err(ApiError::backend_not_found(&id))
     }
 }
 
@@ -357,7 +720,7 @@ async fn list_backends_handler(State(state): State<ApiState>) -> impl IntoRespon
 async fn get_backend_handler(
     State(state): State<ApiState>,
     Path(id): Path<String>,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, ApiError> {
     let now = Instant::now();
     if let Some(entry) = state.backends.get(&id) {
         let healthy =
@@ -369,24 +732,65 @@ async fn get_backend_handler(
             ip: entry.backend.wg_ip.clone(),
             port: entry.backend.port,
             healthy,
+            maintenance: entry.backend.maintenance,
             last_heartbeat_secs: now.duration_since(entry.last_heartbeat).as_secs(),
             registered_secs: now.duration_since(entry.registered_at).as_secs(),
         };
-        (StatusCode::OK, Json(serde_json::to_value(status).unwrap()))
+        Ok(Json(status))
     } else {
-        (
-            StatusCode::NOT_FOUND,
-            Json(serde_json::json!({
-                "error": "backend not found"
-            })),
-        )
+        Err(ApiError::backend_not_found(&id))
+    }
+}
+
+async fn regions_handler(State(state): State<ApiState>) -> Result<impl IntoResponse, ApiError> {
+    let proxy_service = state.proxy_service.as_ref().ok_or_else(ApiError::routing_unavailable)?;
+
+    let regions = proxy_service.region_summary().await;
+    Ok(Json(serde_json::json!({ "regions": regions })))
+}
+
+async fn apps_handler(State(state): State<ApiState>) -> Result<impl IntoResponse, ApiError> {
+    let proxy_service = state.proxy_service.as_ref().ok_or_else(ApiError::routing_unavailable)?;
+
+    let apps = proxy_service.app_summary().await;
+    Ok(Json(serde_json::json!({ "apps": apps })))
+}
+
+async fn config_handler(State(state): State<ApiState>) -> Result<impl IntoResponse, ApiError> {
+    match &state.config {
+        Some(config) => Ok(Json(config.as_ref().clone())),
+        None => Err(ApiError::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "config_unavailable",
+            "configuration not available",
+        )),
     }
 }
 
+async fn route_handler(
+    State(state): State<ApiState>,
+    query: Result<Query<RouteQuery>, QueryRejection>,
+) -> Result<impl IntoResponse, ApiError> {
+    let Query(query) = query?;
+    let proxy_service = state.proxy_service.as_ref().ok_or_else(ApiError::routing_unavailable)?;
+    let Ok(client_ip) = query.ip.parse::<IpAddr>() else {
+        return Err(
+            ApiError::new(StatusCode::BAD_REQUEST, "invalid_ip", "invalid ip address")
+                .with_details(serde_json::json!({ "ip": query.ip })),
+        );
+    };
+
+    let explanation = proxy_service
+        .explain_route(client_ip, query.app.as_deref())
+        .await;
+    Ok(Json(explanation))
+}
+
 #[cfg(test)]
 #[cfg_attr(coverage_nightly, coverage(off))]
 mod tests {
     use super::*;
+    use tokio::net::TcpListener;
 
     #[test]
     fn test_api_state_new() {
@@ -408,6 +812,8 @@ mod tests {
             weight: 2,
             soft_limit: 100,
             hard_limit: 150,
+            maintenance: false,
+            priority: 0,
         };
 
         state.register(req);
@@ -428,6 +834,8 @@ mod tests {
             weight: 2,
             soft_limit: 100,
             hard_limit: 150,
+            maintenance: false,
+            priority: 0,
         };
 
         state.register(req);
@@ -448,6 +856,8 @@ mod tests {
             weight: 2,
             soft_limit: 100,
             hard_limit: 150,
+            maintenance: false,
+            priority: 0,
         };
 
         state.register(req);
@@ -471,6 +881,8 @@ mod tests {
             weight: 2,
             soft_limit: 100,
             hard_limit: 150,
+            maintenance: false,
+            priority: 0,
         });
         state.register(RegisterRequest {
             id: "us-1".to_string(),
@@ -482,6 +894,8 @@ mod tests {
             weight: 2,
             soft_limit: 100,
             hard_limit: 150,
+            maintenance: false,
+            priority: 0,
         });
 
         let healthy = state.get_healthy_backends();
@@ -502,6 +916,8 @@ mod tests {
             weight: 2,
             soft_limit: 100,
             hard_limit: 150,
+            maintenance: false,
+            priority: 0,
         });
 
         let all = state.get_all_backends();
@@ -529,6 +945,8 @@ mod tests {
             weight: 2,
             soft_limit: 100,
             hard_limit: 150,
+            maintenance: false,
+            priority: 0,
         };
 
         let registered = state.register(req);
@@ -551,6 +969,8 @@ mod tests {
             weight: 2,
             soft_limit: 100,
             hard_limit: 150,
+            maintenance: false,
+            priority: 0,
         });
 
         // Register again with different IP
@@ -564,6 +984,8 @@ mod tests {
             weight: 5,
             soft_limit: 200,
             hard_limit: 300,
+            maintenance: false,
+            priority: 0,
         });
 
         assert_eq!(state.backends.len(), 1);
@@ -573,6 +995,85 @@ mod tests {
         assert_eq!(entry.backend.weight, 5);
     }
 
+    fn conflict_request(ip: &str, weight: u8) -> RegisterRequest {
+        RegisterRequest {
+            id: "test-1".to_string(),
+            app: "myapp".to_string(),
+            region: "eu".to_string(),
+            country: None,
+            ip: ip.to_string(),
+            port: 8080,
+            weight,
+            soft_limit: 100,
+            hard_limit: 150,
+            maintenance: false,
+            priority: 0,
+        }
+    }
+
+    #[test]
+    fn test_try_register_identical_refreshes() {
+        let state = ApiState::new(60);
+        assert!(matches!(
+            state.try_register(conflict_request("10.0.0.1", 2), false),
+            RegisterOutcome::Created(_)
+        ));
+        let registered_at = state.backends.get("test-1").unwrap().registered_at;
+
+        std::thread::sleep(Duration::from_millis(5));
+        match state.try_register(conflict_request("10.0.0.1", 5), false) {
+            RegisterOutcome::Refreshed(entry) => {
+                assert_eq!(entry.registered_at, registered_at);
+                assert!(entry.last_heartbeat > registered_at);
+                assert_eq!(entry.backend.weight, 5);
+            }
+            other => panic!("expected Refreshed, got {:?}", other),
+        }
+        assert_eq!(state.backends.len(), 1);
+    }
+
+    #[test]
+    fn test_try_register_maintenance_is_reported_apart_from_health() {
+        let state = ApiState::new(60);
+        state.try_register(conflict_request("10.0.0.1", 2), false);
+
+        let mut req = conflict_request("10.0.0.1", 2);
+        req.maintenance = true;
+        assert!(matches!(state.try_register(req, false), RegisterOutcome::Refreshed(_)));
+
+        // Still heartbeating, so not unhealthy, but out of rotation
+        let status = &state.get_all_backends()[0];
+        assert!(status.healthy);
+        assert!(status.maintenance);
+        let backends = state.get_healthy_backends();
+        assert!(backends[0].healthy);
+        assert!(!backends[0].accepts_new_connections());
+    }
+
+    #[test]
+    fn test_try_register_conflict_keeps_existing() {
+        let state = ApiState::new(60);
+        state.try_register(conflict_request("10.0.0.1", 2), false);
+
+        match state.try_register(conflict_request("10.0.0.99", 2), false) {
+            RegisterOutcome::Conflict(existing) => assert_eq!(existing.backend.wg_ip, "10.0.0.1"),
+            other => panic!("expected Conflict, got {:?}", other),
+        }
+        assert_eq!(state.backends.get("test-1").unwrap().backend.wg_ip, "10.0.0.1");
+    }
+
+    #[test]
+    fn test_try_register_force_replaces() {
+        let state = ApiState::new(60);
+        state.try_register(conflict_request("10.0.0.1", 2), false);
+
+        assert!(matches!(
+            state.try_register(conflict_request("10.0.0.99", 2), true),
+            RegisterOutcome::Replaced(_)
+        ));
+        assert_eq!(state.backends.get("test-1").unwrap().backend.wg_ip, "10.0.0.99");
+    }
+
     #[test]
     fn test_get_all_backends_with_multiple_regions() {
         let state = ApiState::new(60);
@@ -587,6 +1088,8 @@ mod tests {
             weight: 2,
             soft_limit: 100,
             hard_limit: 150,
+            maintenance: false,
+            priority: 0,
         });
 
         state.register(RegisterRequest {
@@ -599,6 +1102,8 @@ mod tests {
             weight: 3,
             soft_limit: 50,
             hard_limit: 75,
+            maintenance: false,
+            priority: 0,
         });
 
         let all = state.get_all_backends();
@@ -619,6 +1124,8 @@ mod tests {
             weight: 2,
             soft_limit: 100,
             hard_limit: 150,
+            maintenance: false,
+            priority: 0,
         });
 
         assert_eq!(state.backends.len(), 1);
@@ -644,6 +1151,8 @@ mod tests {
             weight: 2,
             soft_limit: 100,
             hard_limit: 150,
+            maintenance: false,
+            priority: 0,
         });
 
         let removed = state.cleanup_expired();
@@ -675,6 +1184,8 @@ mod tests {
             weight: 2,
             soft_limit: 100,
             hard_limit: 150,
+            maintenance: false,
+            priority: 0,
         });
 
         assert_eq!(state2.backends.len(), 1);
@@ -693,6 +1204,8 @@ mod tests {
             weight: 2,
             soft_limit: 100,
             hard_limit: 150,
+            maintenance: false,
+            priority: 0,
         };
 
         let registered = state.register(req);
@@ -712,6 +1225,8 @@ mod tests {
             weight: 2,
             soft_limit: 100,
             hard_limit: 150,
+            maintenance: false,
+            priority: 0,
         };
 
         let debug_str = format!("{:?}", req);
@@ -741,6 +1256,7 @@ mod tests {
             ip: "10.0.0.1".to_string(),
             port: 8080,
             healthy: true,
+            maintenance: false,
             last_heartbeat_secs: 0,
             registered_secs: 100,
         };
@@ -785,6 +1301,9 @@ mod tests {
             weight: 2,
             soft_limit: 100,
             hard_limit: 150,
+            draining: false,
+            maintenance: false,
+            priority: 0,
         };
 
         let registered = RegisteredBackend {
@@ -812,6 +1331,8 @@ mod tests {
             weight: 2,
             soft_limit: 100,
             hard_limit: 150,
+            maintenance: false,
+            priority: 0,
         });
 
         // Cloned state should share the same DashMap
@@ -833,6 +1354,8 @@ mod tests {
                 weight: 2,
                 soft_limit: 100,
                 hard_limit: 150,
+                maintenance: false,
+                priority: 0,
             });
         }
 
@@ -851,6 +1374,29 @@ mod tests {
         assert_eq!(ap.backend.region, RegionCode::AsiaPacific);
     }
 
+    #[test]
+    fn test_register_unknown_region_is_kept_as_unknown() {
+        let state = ApiState::new(60);
+        state.register(RegisterRequest {
+            id: "mars-1".to_string(),
+            app: "myapp".to_string(),
+            region: "mars".to_string(),
+            country: None,
+            ip: "10.0.0.1".to_string(),
+            port: 8080,
+            weight: 2,
+            soft_limit: 100,
+            hard_limit: 150,
+            maintenance: false,
+            priority: 0,
+        });
+
+        // Not silently moved to another region
+        let backend = state.backends.get("mars-1").unwrap().backend.clone();
+        assert_eq!(backend.region, RegionCode::Unknown);
+        assert_eq!(backend.region.as_str(), "unknown");
+    }
+
     #[test]
     fn test_backend_status_healthy_reflects_ttl() {
         let state = ApiState::new(3600);
@@ -865,6 +1411,8 @@ mod tests {
             weight: 2,
             soft_limit: 100,
             hard_limit: 150,
+            maintenance: false,
+            priority: 0,
         });
 
         let backends = state.get_all_backends();
@@ -888,6 +1436,13 @@ mod tests {
             .route("/api/v1/backends/:id", delete(deregister_handler))
             .route("/api/v1/backends", get(list_backends_handler))
             .route("/api/v1/backends/:id", get(get_backend_handler))
+            .route("/route", get(route_handler))
+            .route("/regions", get(regions_handler))
+            .route("/apps", get(apps_handler))
+            .route("/config", get(config_handler))
+            .route("/ready", get(ready_handler))
+            .route("/admin/drain", post(drain_handler))
+            .route("/admin/undrain", post(undrain_handler))
             .with_state(state)
     }
 
@@ -899,6 +1454,13 @@ mod tests {
             .route("/api/v1/backends/:id", delete(deregister_handler))
             .route("/api/v1/backends", get(list_backends_handler))
             .route("/api/v1/backends/:id", get(get_backend_handler))
+            .route("/route", get(route_handler))
+            .route("/regions", get(regions_handler))
+            .route("/apps", get(apps_handler))
+            .route("/config", get(config_handler))
+            .route("/ready", get(ready_handler))
+            .route("/admin/drain", post(drain_handler))
+            .route("/admin/undrain", post(undrain_handler))
             .with_state(state)
     }
 
@@ -938,6 +1500,140 @@ mod tests {
         assert_eq!(response.status(), HttpStatusCode::CREATED);
     }
 
+    async fn post_register(app: Router, uri: &str, ip: &str) -> (HttpStatusCode, serde_json::Value) {
+        let body = serde_json::json!({
+            "id": "backend-1",
+            "app": "myapp",
+            "region": "eu",
+            "ip": ip,
+            "port": 8080
+        });
+        let request = Request::builder()
+            .method("POST")
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(serde_json::to_string(&body).unwrap()))
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_register_handler_identical_reregister_refreshes() {
+        let state = ApiState::new(60);
+        let app = create_test_app_with_state(state.clone());
+
+        let (status, _) = post_register(app.clone(), "/api/v1/register", "10.0.0.1").await;
+        assert_eq!(status, HttpStatusCode::CREATED);
+
+        let (status, body) = post_register(app, "/api/v1/register", "10.0.0.1").await;
+        assert_eq!(status, HttpStatusCode::OK);
+        assert_eq!(body["registered"], true);
+        assert_eq!(state.backends.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_register_handler_conflicting_reregister_rejected() {
+        let state = ApiState::new(60);
+        let app = create_test_app_with_state(state.clone());
+
+        post_register(app.clone(), "/api/v1/register", "10.0.0.1").await;
+        let (status, body) = post_register(app, "/api/v1/register", "10.0.0.2").await;
+
+        assert_eq!(status, HttpStatusCode::CONFLICT);
+        assert_eq!(body["error"]["code"], "backend_conflict");
+        assert_eq!(body["error"]["details"]["registered"]["ip"], "10.0.0.1");
+        assert_eq!(state.backends.get("backend-1").unwrap().backend.wg_ip, "10.0.0.1");
+    }
+
+    async fn post_register_body(app: Router, body: &str) -> (HttpStatusCode, serde_json::Value) {
+        let request = Request::builder()
+            .method("POST")
+            .uri("/api/v1/register")
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_register_handler_validation_failure() {
+        let state = ApiState::new(60);
+        let app = create_test_app_with_state(state.clone());
+
+        let (status, body) = post_register(app.clone(), "/api/v1/register", "not-an-ip").await;
+        assert_eq!(status, HttpStatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["error"]["code"], "validation_failed");
+        assert!(body["error"]["message"].as_str().unwrap().contains("not-an-ip"));
+
+        let (status, body) = post_register_body(
+            app,
+            r#"{"id":"","app":"myapp","region":"eu","ip":"10.0.0.1","port":8080}"#,
+        )
+        .await;
+        assert_eq!(status, HttpStatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["error"]["code"], "validation_failed");
+        assert_eq!(body["error"]["message"], "id must not be empty");
+        assert!(state.backends.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_register_handler_malformed_body() {
+        let app = create_test_app();
+
+        let (status, body) = post_register_body(app.clone(), r#"{"id":"backend-1""#).await;
+        assert_eq!(status, HttpStatusCode::BAD_REQUEST);
+        assert_eq!(body["error"]["code"], "invalid_request");
+        assert!(body["error"]["message"].is_string());
+
+        let (status, body) = post_register_body(app, r#"{"id":"backend-1","app":"myapp"}"#).await;
+        assert_eq!(status, HttpStatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["error"]["code"], "invalid_request");
+    }
+
+    #[test]
+    fn test_register_request_validate() {
+        let request = |id: &str, app: &str, ip: &str, port: u16| RegisterRequest {
+            id: id.to_string(),
+            app: app.to_string(),
+            region: "eu".to_string(),
+            country: None,
+            ip: ip.to_string(),
+            port,
+            weight: 2,
+            soft_limit: 100,
+            hard_limit: 150,
+            maintenance: false,
+            priority: 0,
+        };
+
+        assert!(request("b1", "myapp", "10.0.0.1", 8080).validate().is_ok());
+        assert!(request("b1", "myapp", "fd00::1", 8080).validate().is_ok());
+        assert!(request(" ", "myapp", "10.0.0.1", 8080).validate().is_err());
+        assert!(request("b1", "", "10.0.0.1", 8080).validate().is_err());
+        assert!(request("b1", "myapp", "backend.local", 8080).validate().is_err());
+        assert!(request("b1", "myapp", "10.0.0.1", 0).validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_register_handler_force_overwrites() {
+        let state = ApiState::new(60);
+        let app = create_test_app_with_state(state.clone());
+
+        post_register(app.clone(), "/api/v1/register", "10.0.0.1").await;
+        let (status, _) = post_register(app, "/api/v1/register?force=true", "10.0.0.2").await;
+
+        assert_eq!(status, HttpStatusCode::OK);
+        assert_eq!(state.backends.get("backend-1").unwrap().backend.wg_ip, "10.0.0.2");
+    }
+
     #[tokio::test]
     async fn test_heartbeat_handler_success() {
         let state = ApiState::new(60);
@@ -951,6 +1647,8 @@ mod tests {
             weight: 2,
             soft_limit: 100,
             hard_limit: 150,
+            maintenance: false,
+            priority: 0,
         });
 
         let app = create_test_app_with_state(state);
@@ -977,6 +1675,18 @@ mod tests {
 
         let response = app.oneshot(request).await.unwrap();
         assert_eq!(response.status(), HttpStatusCode::NOT_FOUND);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "error": {
+                    "code": "backend_not_found",
+                    "message": "backend not found",
+                    "details": { "id": "nonexistent" }
+                }
+            })
+        );
     }
 
     #[tokio::test]
@@ -992,6 +1702,8 @@ mod tests {
             weight: 2,
             soft_limit: 100,
             hard_limit: 150,
+            maintenance: false,
+            priority: 0,
         });
 
         let app = create_test_app_with_state(state);
@@ -1046,6 +1758,8 @@ mod tests {
             weight: 2,
             soft_limit: 100,
             hard_limit: 150,
+            maintenance: false,
+            priority: 0,
         });
 
         let app = create_test_app_with_state(state);
@@ -1072,6 +1786,8 @@ mod tests {
             weight: 2,
             soft_limit: 100,
             hard_limit: 150,
+            maintenance: false,
+            priority: 0,
         });
 
         let app = create_test_app_with_state(state);
@@ -1098,6 +1814,305 @@ mod tests {
         assert_eq!(response.status(), HttpStatusCode::NOT_FOUND);
     }
 
+    // Routing dry-run helpers
+    use crate::adapters::outbound::{DashMapBindingRepository, DashMapMetricsStore};
+    use crate::domain::ports::BackendRepository;
+    use async_trait::async_trait;
+
+    struct MockBackendRepository {
+        backends: Vec<Backend>,
+    }
+
+    #[async_trait]
+    impl BackendRepository for MockBackendRepository {
+        async fn get_all(&self) -> Vec<Backend> {
+            self.backends.clone()
+        }
+
+        async fn get_by_id(&self, id: &str) -> Option<Backend> {
+            self.backends.iter().find(|b| b.id == id).cloned()
+        }
+
+        async fn get_healthy(&self) -> Vec<Backend> {
+            self.backends.iter().filter(|b| b.healthy).cloned().collect()
+        }
+
+        async fn get_version(&self) -> u64 {
+            1
+        }
+    }
+
+    fn create_route_state(backends: Vec<Backend>) -> ApiState {
+        let proxy_service = ProxyService::new(
+            Arc::new(MockBackendRepository { backends }),
+            Arc::new(DashMapBindingRepository::new()),
+            None,
+            Arc::new(DashMapMetricsStore::new()),
+            RegionCode::Europe,
+        );
+        let mut state = ApiState::new(60);
+        state.proxy_service = Some(Arc::new(proxy_service));
+        state
+    }
+
+    fn route_backend(id: &str, app: &str, healthy: bool) -> Backend {
+        Backend {
+            id: id.to_string(),
+            app: app.to_string(),
+            region: RegionCode::Europe,
+            country: "DE".to_string(),
+            wg_ip: "10.0.0.1".to_string(),
+            port: 8080,
+            healthy,
+            weight: 2,
+            soft_limit: 100,
+            hard_limit: 150,
+            draining: false,
+            maintenance: false,
+            priority: 0,
+        }
+    }
+
+    async fn get_json(app: Router, uri: &str) -> (HttpStatusCode, serde_json::Value) {
+        let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or_default())
+    }
+
+    #[tokio::test]
+    async fn test_route_handler_explains_choice() {
+        let state = create_route_state(vec![
+            route_backend("eu-1", "myapp", true),
+            route_backend("eu-2", "myapp", false),
+            route_backend("eu-3", "other", true),
+        ]);
+        let app = create_test_app_with_state(state);
+
+        let (status, body) = get_json(app, "/route?ip=10.1.2.3&app=myapp").await;
+        assert_eq!(status, HttpStatusCode::OK);
+        assert_eq!(body["chosen"], "eu-1");
+        assert_eq!(body["app"], "myapp");
+        assert_eq!(body["strategy"], "geo_load_score");
+        assert_eq!(body["local_region"], "eu");
+        assert_eq!(body["candidates"].as_array().unwrap().len(), 2);
+        assert_eq!(body["candidates"][1]["id"], "eu-2");
+        assert_eq!(body["candidates"][1]["excluded"], "unhealthy");
+    }
+
+    #[tokio::test]
+    async fn test_route_handler_invalid_ip() {
+        let app = create_test_app_with_state(create_route_state(vec![]));
+
+        let (status, body) = get_json(app, "/route?ip=not-an-ip").await;
+        assert_eq!(status, HttpStatusCode::BAD_REQUEST);
+        assert_eq!(body["error"]["code"], "invalid_ip");
+        assert_eq!(body["error"]["message"], "invalid ip address");
+        assert_eq!(body["error"]["details"]["ip"], "not-an-ip");
+    }
+
+    #[tokio::test]
+    async fn test_route_handler_missing_ip() {
+        let app = create_test_app_with_state(create_route_state(vec![]));
+
+        let (status, body) = get_json(app, "/route?app=myapp").await;
+        assert_eq!(status, HttpStatusCode::BAD_REQUEST);
+        assert_eq!(body["error"]["code"], "invalid_request");
+    }
+
+    #[tokio::test]
+    async fn test_route_handler_without_proxy_service() {
+        let app = create_test_app();
+
+        let (status, body) = get_json(app, "/route?ip=10.1.2.3").await;
+        assert_eq!(status, HttpStatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["error"]["code"], "routing_unavailable");
+    }
+
+    #[tokio::test]
+    async fn test_regions_handler_counts_backends() {
+        let mut us = route_backend("us-1", "myapp", true);
+        us.region = RegionCode::NorthAmerica;
+        let state = create_route_state(vec![
+            route_backend("eu-1", "myapp", true),
+            route_backend("eu-2", "myapp", false),
+            route_backend("eu-3", "other", true),
+            us,
+        ]);
+        let proxy_service = state.proxy_service.clone().unwrap();
+        proxy_service.record_connection_start("eu-1", "myapp");
+        proxy_service.record_connection_start("eu-3", "other");
+        proxy_service.record_connection_start("us-1", "myapp");
+        let app = create_test_app_with_state(state);
+
+        let (status, body) = get_json(app, "/regions").await;
+        assert_eq!(status, HttpStatusCode::OK);
+        let regions = body["regions"].as_array().unwrap();
+        assert_eq!(regions.len(), 4);
+
+        let eu = regions.iter().find(|r| r["region"] == "eu").unwrap();
+        assert_eq!(eu["total"], 3);
+        assert_eq!(eu["healthy"], 2);
+        assert_eq!(eu["active_connections"], 2);
+        let us = regions.iter().find(|r| r["region"] == "us").unwrap();
+        assert_eq!((us["total"].as_u64(), us["active_connections"].as_u64()), (Some(1), Some(1)));
+        let sa = regions.iter().find(|r| r["region"] == "sa").unwrap();
+        assert_eq!(sa["total"], 0);
+    }
+
+    #[tokio::test]
+    async fn test_apps_handler_lists_distinct_apps() {
+        let state = create_route_state(vec![
+            route_backend("eu-1", "myapp", true),
+            route_backend("eu-2", "myapp", false),
+            route_backend("eu-3", "other", true),
+            route_backend("eu-4", "billing", true),
+        ]);
+        let proxy_service = state.proxy_service.clone().unwrap();
+        proxy_service.record_connection_start("eu-1", "myapp");
+        proxy_service.record_connection_start("eu-2", "myapp");
+        let app = create_test_app_with_state(state);
+
+        let (status, body) = get_json(app, "/apps").await;
+        assert_eq!(status, HttpStatusCode::OK);
+        let apps = body["apps"].as_array().unwrap();
+        let names: Vec<&str> = apps.iter().map(|a| a["app"].as_str().unwrap()).collect();
+        assert_eq!(names, vec!["billing", "myapp", "other"]);
+
+        assert_eq!(apps[1]["total"], 2);
+        assert_eq!(apps[1]["healthy"], 1);
+        assert_eq!(apps[1]["active_connections"], 2);
+        assert_eq!((apps[0]["total"].as_u64(), apps[2]["total"].as_u64()), (Some(1), Some(1)));
+    }
+
+    #[tokio::test]
+    async fn test_apps_handler_without_proxy_service() {
+        let (status, _) = get_json(create_test_app(), "/apps").await;
+        assert_eq!(status, HttpStatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn test_regions_handler_without_proxy_service() {
+        let (status, _) = get_json(create_test_app(), "/regions").await;
+        assert_eq!(status, HttpStatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn test_config_handler_returns_redacted_config() {
+        use crate::config::{Config, REDACTED};
+
+        let cfg = Config {
+            region: "eu".to_string(),
+            api_enabled: true,
+            consul_token: Some("consul-token".to_string()),
+            replication_cluster_secret: Some("s3cret".to_string()),
+            tls_key_path: Some("/etc/edgeproxy/key.pem".to_string()),
+            ..Config::default()
+        };
+        let server = ApiServer::new("127.0.0.1:0".to_string(), 60).with_config(cfg.redacted());
+        let app = create_test_app_with_state(server.state());
+
+        let (status, body) = get_json(app, "/config").await;
+        assert_eq!(status, HttpStatusCode::OK);
+        assert_eq!(body["region"], "eu");
+        assert_eq!(body["api_enabled"], true);
+        assert_eq!(body["tls_key_path"], "/etc/edgeproxy/key.pem");
+        assert_eq!(body["consul_token"], REDACTED);
+        assert_eq!(body["replication_cluster_secret"], REDACTED);
+        assert!(!body.to_string().contains("s3cret"));
+    }
+
+    #[tokio::test]
+    async fn test_config_handler_without_config() {
+        let (status, _) = get_json(create_test_app(), "/config").await;
+        assert_eq!(status, HttpStatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[test]
+    fn test_api_server_with_proxy_service() {
+        let proxy_service = create_route_state(vec![]).proxy_service.unwrap();
+        let server = ApiServer::new("127.0.0.1:0".to_string(), 60);
+        assert!(server.state().proxy_service.is_none());
+
+        let server = server.with_proxy_service(proxy_service);
+        assert!(server.state().proxy_service.is_some());
+    }
+
+    // ===== Drain Tests =====
+
+    async fn post_json(app: Router, uri: &str) -> (HttpStatusCode, serde_json::Value) {
+        let request = Request::builder()
+            .method("POST")
+            .uri(uri)
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or_default())
+    }
+
+    #[tokio::test]
+    async fn test_drain_flips_readiness() {
+        let state = ApiState::new(60);
+        let app = create_test_app_with_state(state.clone());
+
+        let (status, body) = get_json(app.clone(), "/ready").await;
+        assert_eq!(status, HttpStatusCode::OK);
+        assert_eq!(body["status"], "ready");
+
+        let (status, body) = post_json(app.clone(), "/admin/drain").await;
+        assert_eq!(status, HttpStatusCode::OK);
+        assert_eq!(body["draining"], true);
+        assert_eq!(body["active_connections"], 0);
+        assert!(state.shutdown.is_draining());
+
+        let (status, body) = get_json(app.clone(), "/ready").await;
+        assert_eq!(status, HttpStatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["status"], "draining");
+
+        // Liveness is unaffected
+        let (status, _) = get_json(app.clone(), "/health").await;
+        assert_eq!(status, HttpStatusCode::OK);
+
+        let (status, body) = post_json(app.clone(), "/admin/undrain").await;
+        assert_eq!(status, HttpStatusCode::OK);
+        assert_eq!(body["draining"], false);
+
+        let (status, _) = get_json(app, "/ready").await;
+        assert_eq!(status, HttpStatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_undrain_refused_once_shutdown_started() {
+        let state = ApiState::new(60);
+        state.shutdown.shutdown();
+        let app = create_test_app_with_state(state);
+
+        let (status, body) = post_json(app.clone(), "/admin/undrain").await;
+        assert_eq!(status, HttpStatusCode::CONFLICT);
+        assert_eq!(body["error"]["code"], "shutdown_in_progress");
+        assert_eq!(body["error"]["message"], "shutdown already in progress");
+        assert_eq!(body["error"]["details"]["draining"], true);
+
+        let (status, _) = get_json(app, "/ready").await;
+        assert_eq!(status, HttpStatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[test]
+    fn test_api_server_with_shutdown() {
+        let shutdown = ShutdownController::new();
+        let server = ApiServer::new("127.0.0.1:0".to_string(), 60).with_shutdown(shutdown.clone());
+
+        server.state().shutdown.drain();
+        assert!(shutdown.is_draining());
+    }
+
     #[tokio::test]
     async fn test_start_cleanup_task_removes_expired() {
         use std::time::Duration;
@@ -1116,6 +2131,8 @@ mod tests {
             weight: 2,
             soft_limit: 100,
             hard_limit: 150,
+            maintenance: false,
+            priority: 0,
         });
 
         assert_eq!(server.state.backends.len(), 1);
@@ -1145,6 +2162,8 @@ mod tests {
             weight: 2,
             soft_limit: 100,
             hard_limit: 150,
+            maintenance: false,
+            priority: 0,
         });
 
         // cleanup should return 0 (no expired backends)
@@ -1187,6 +2206,50 @@ mod tests {
         server_handle.abort();
     }
 
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_api_server_run_on_unix_socket() {
+        use std::os::unix::fs::PermissionsExt;
+        use std::time::Duration;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("api.sock");
+
+        // Stale socket from an earlier run
+        drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+
+        let server = ApiServer::new(format!("unix:{}", path.display()), 60).with_socket_mode(0o600);
+        let server_handle = tokio::spawn(async move {
+            let _ = server.run().await;
+        });
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        let mut stream = tokio::net::UnixStream::connect(&path).await.unwrap();
+        stream
+            .write_all(b"GET /health HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+        assert!(response.contains("\"status\":\"ok\""), "{}", response);
+
+        server_handle.abort();
+    }
+
+    #[test]
+    fn test_api_server_default_socket_mode() {
+        let server = ApiServer::new("unix:/tmp/api.sock".to_string(), 60);
+        assert_eq!(server.socket_mode, DEFAULT_SOCKET_MODE);
+        assert_eq!(server.with_socket_mode(0o600).socket_mode, 0o600);
+    }
+
     #[tokio::test]
     async fn test_full_lifecycle() {
         use std::time::Duration;
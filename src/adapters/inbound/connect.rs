@@ -0,0 +1,275 @@
+//! HTTP CONNECT Handshake
+//!
+//! Lets the TCP listener act as a forward proxy: the client sends
+//! `CONNECT host:port`, the host picks the app, and once a backend is
+//! chosen the connection becomes a plain byte tunnel.
+
+use std::collections::HashMap;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// Largest request head accepted before the tunnel is refused.
+const MAX_HEAD_LEN: usize = 8 * 1024;
+
+/// Reply sent once the tunnel to the backend is up.
+pub const RESPONSE_ESTABLISHED: &[u8] = b"HTTP/1.1 200 Connection Established\r\n\r\n";
+/// Header naming the chosen backend when [`ConnectConfig::expose_backend`] is set.
+pub const BACKEND_HEADER: &str = "X-EdgeProxy-Backend";
+/// Reply to a request that isn't a well-formed CONNECT.
+pub const RESPONSE_BAD_REQUEST: &[u8] =
+    b"HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+/// Reply when the requested host doesn't map to an app.
+pub const RESPONSE_NOT_FOUND: &[u8] =
+    b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+/// Reply when the backend couldn't be reached.
+pub const RESPONSE_BAD_GATEWAY: &[u8] =
+    b"HTTP/1.1 502 Bad Gateway\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+/// Reply when the app has no backend available.
+pub const RESPONSE_UNAVAILABLE: &[u8] =
+    b"HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+/// Reply when every backend of the app is at its hard limit.
+pub const RESPONSE_OVERLOADED: &[u8] =
+    b"HTTP/1.1 503 Service Unavailable\r\nRetry-After: 1\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+
+/// CONNECT proxy mode configuration.
+#[derive(Debug, Clone)]
+pub struct ConnectConfig {
+    /// Domain suffix for the `app.<domain>` convention (e.g. "internal")
+    pub domain: String,
+    /// Explicit host -> app mappings, checked before the domain convention
+    pub hosts: HashMap<String, String>,
+    /// Name the chosen backend in an `X-EdgeProxy-Backend` header on the
+    /// `200` reply (for debugging; reveals topology to clients)
+    pub expose_backend: bool,
+}
+
+impl Default for ConnectConfig {
+    fn default() -> Self {
+        Self {
+            domain: "internal".to_string(),
+            hosts: HashMap::new(),
+            expose_backend: false,
+        }
+    }
+}
+
+impl ConnectConfig {
+    /// Parse `host=app` entries into a host table.
+    pub fn parse_hosts(entries: &[String]) -> anyhow::Result<HashMap<String, String>> {
+        entries
+            .iter()
+            .filter(|entry| !entry.trim().is_empty())
+            .map(|entry| {
+                let (host, app) = entry
+                    .split_once('=')
+                    .ok_or_else(|| anyhow::anyhow!("expected host=app, got {:?}", entry))?;
+                let (host, app) = (host.trim(), app.trim());
+                if host.is_empty() || app.is_empty() {
+                    anyhow::bail!("expected host=app, got {:?}", entry);
+                }
+                Ok((host.to_lowercase(), app.to_string()))
+            })
+            .collect()
+    }
+
+    /// Reply sent once the tunnel to `backend_id` is up.
+    ///
+    /// Control characters are dropped from the id so it can't break out
+    /// of the header line.
+    pub fn established_response(&self, backend_id: &str) -> Vec<u8> {
+        if !self.expose_backend {
+            return RESPONSE_ESTABLISHED.to_vec();
+        }
+        let id: String = backend_id.chars().filter(|c| !c.is_control()).collect();
+        format!(
+            "HTTP/1.1 200 Connection Established\r\n{}: {}\r\n\r\n",
+            BACKEND_HEADER, id
+        )
+        .into_bytes()
+    }
+
+    /// Map a requested host to an app name.
+    ///
+    /// The host table wins; otherwise `app.<domain>` maps to `app`.
+    pub fn app_for(&self, host: &str) -> Option<String> {
+        let host = host.trim_end_matches('.').to_lowercase();
+        if let Some(app) = self.hosts.get(&host) {
+            return Some(app.clone());
+        }
+
+        host.strip_suffix(&format!(".{}", self.domain))
+            .filter(|app| !app.is_empty())
+            .map(str::to_string)
+    }
+}
+
+/// Target of a CONNECT request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectRequest {
+    pub host: String,
+    pub port: u16,
+}
+
+impl ConnectRequest {
+    /// Parse a request head (everything up to the blank line).
+    pub fn parse(head: &[u8]) -> Option<Self> {
+        let head = std::str::from_utf8(head).ok()?;
+        let request_line = head.lines().next()?;
+
+        let mut parts = request_line.split_whitespace();
+        let (method, target, version) = (parts.next()?, parts.next()?, parts.next()?);
+        if method != "CONNECT" || !version.starts_with("HTTP/1.") || parts.next().is_some() {
+            return None;
+        }
+
+        // host:port, with IPv6 literals in brackets
+        let (host, port) = target.rsplit_once(':')?;
+        let host = host
+            .strip_prefix('[')
+            .and_then(|h| h.strip_suffix(']'))
+            .unwrap_or(host);
+        if host.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            host: host.to_string(),
+            port: port.parse().ok()?,
+        })
+    }
+}
+
+/// Read a request head from the client.
+///
+/// Returns the head and any bytes the client already sent past it, which
+/// belong to the tunnel. Returns None if the stream ends or the head is
+/// larger than [`MAX_HEAD_LEN`].
+pub async fn read_head<S>(stream: &mut S) -> std::io::Result<Option<(Vec<u8>, Vec<u8>)>>
+where
+    S: AsyncRead + Unpin,
+{
+    let mut buf = Vec::with_capacity(1024);
+    let mut chunk = [0u8; 1024];
+
+    loop {
+        if let Some(end) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            let rest = buf.split_off(end + 4);
+            return Ok(Some((buf, rest)));
+        }
+        if buf.len() > MAX_HEAD_LEN {
+            return Ok(None);
+        }
+
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Ok(None);
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_connect_request() {
+        let req = ConnectRequest::parse(b"CONNECT myapp.internal:443 HTTP/1.1\r\nHost: myapp.internal:443\r\n\r\n");
+        assert_eq!(
+            req,
+            Some(ConnectRequest {
+                host: "myapp.internal".to_string(),
+                port: 443
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_connect_request_ipv6_literal() {
+        let req = ConnectRequest::parse(b"CONNECT [::1]:8080 HTTP/1.0\r\n\r\n").unwrap();
+        assert_eq!(req.host, "::1");
+        assert_eq!(req.port, 8080);
+    }
+
+    #[test]
+    fn test_parse_rejects_malformed_requests() {
+        for head in [
+            &b"GET / HTTP/1.1\r\n\r\n"[..],
+            b"CONNECT myapp.internal HTTP/1.1\r\n\r\n",
+            b"CONNECT myapp.internal:http HTTP/1.1\r\n\r\n",
+            b"CONNECT :443 HTTP/1.1\r\n\r\n",
+            b"CONNECT myapp.internal:443\r\n\r\n",
+            b"CONNECT myapp.internal:443 SPDY/3\r\n\r\n",
+            b"\xff\xfe\r\n\r\n",
+            b"",
+        ] {
+            assert!(ConnectRequest::parse(head).is_none(), "accepted {:?}", head);
+        }
+    }
+
+    #[test]
+    fn test_app_for_domain_convention() {
+        let config = ConnectConfig::default();
+        assert_eq!(config.app_for("myapp.internal"), Some("myapp".to_string()));
+        assert_eq!(config.app_for("MyApp.Internal."), Some("myapp".to_string()));
+        assert_eq!(config.app_for("internal"), None);
+        assert_eq!(config.app_for("example.com"), None);
+    }
+
+    #[test]
+    fn test_app_for_host_table_wins() {
+        let config = ConnectConfig {
+            hosts: ConnectConfig::parse_hosts(&[
+                "api.example.com=api".to_string(),
+                "myapp.internal=other".to_string(),
+            ])
+            .unwrap(),
+            ..Default::default()
+        };
+
+        assert_eq!(config.app_for("API.example.com"), Some("api".to_string()));
+        assert_eq!(config.app_for("myapp.internal"), Some("other".to_string()));
+    }
+
+    #[test]
+    fn test_established_response() {
+        let mut config = ConnectConfig::default();
+        assert_eq!(config.established_response("eu-1"), RESPONSE_ESTABLISHED);
+
+        config.expose_backend = true;
+        assert_eq!(
+            config.established_response("eu-1"),
+            b"HTTP/1.1 200 Connection Established\r\nX-EdgeProxy-Backend: eu-1\r\n\r\n"
+        );
+        // A hostile id can't inject headers
+        assert_eq!(
+            config.established_response("eu-1\r\nSet-Cookie: x"),
+            b"HTTP/1.1 200 Connection Established\r\nX-EdgeProxy-Backend: eu-1Set-Cookie: x\r\n\r\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_hosts_invalid() {
+        assert!(ConnectConfig::parse_hosts(&["no-equals".to_string()]).is_err());
+        assert!(ConnectConfig::parse_hosts(&["=app".to_string()]).is_err());
+        assert!(ConnectConfig::parse_hosts(&["host=".to_string()]).is_err());
+        assert!(ConnectConfig::parse_hosts(&[String::new()]).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_read_head_keeps_bytes_past_head() {
+        let mut input = &b"CONNECT a.internal:1 HTTP/1.1\r\n\r\nearly"[..];
+        let (head, rest) = read_head(&mut input).await.unwrap().unwrap();
+        assert_eq!(head, b"CONNECT a.internal:1 HTTP/1.1\r\n\r\n");
+        assert_eq!(rest, b"early");
+    }
+
+    #[tokio::test]
+    async fn test_read_head_incomplete_or_oversized() {
+        let mut truncated = &b"CONNECT a.internal:1 HTTP/1.1\r\n"[..];
+        assert!(read_head(&mut truncated).await.unwrap().is_none());
+
+        let oversized = vec![b'a'; MAX_HEAD_LEN + 2048];
+        assert!(read_head(&mut &oversized[..]).await.unwrap().is_none());
+    }
+}
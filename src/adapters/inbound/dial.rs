@@ -0,0 +1,819 @@
+//! Backend Dial Socket Options
+//!
+//! Builds the TCP sockets inbound adapters use to reach backends,
+//! applying socket options that tokio's plain `connect` doesn't expose,
+//! and times each phase of the dial.
+//!
+//! Backends addressed by hostname are resolved through a short-lived
+//! cache, and their addresses are raced Happy Eyeballs style (RFC 8305):
+//! families alternate and each attempt gets a head start before the next
+//! one begins.
+//!
+//! Dials in progress are counted, so a monitor can report connect
+//! backpressure and warn when backends stay slow to accept.
+
+use super::listener::SocketBuffers;
+use crate::application::ProxyService;
+use crate::domain::value_objects::ConnectPhase;
+use dashmap::DashMap;
+use socket2::SockRef;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::net::{TcpSocket, TcpStream};
+use tokio::task::JoinSet;
+
+/// How long resolved backend addresses are reused by default.
+pub const DEFAULT_RESOLVE_TTL: Duration = Duration::from_secs(5);
+
+/// Head start each connection attempt gets before the next address is tried.
+const CONNECTION_ATTEMPT_DELAY: Duration = Duration::from_millis(250);
+
+/// How often the pressure monitor samples the dials in progress.
+const PRESSURE_SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Resolved backend addresses, reused for a short TTL.
+///
+/// Clones share the same entries.
+#[derive(Debug, Clone)]
+pub struct ResolveCache {
+    ttl: Duration,
+    entries: Arc<DashMap<String, (Instant, Vec<SocketAddr>)>>,
+}
+
+impl ResolveCache {
+    /// Create a cache keeping lookups for `ttl` (zero disables caching).
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Arc::new(DashMap::new()),
+        }
+    }
+
+    /// Resolve `host:port`, answering from the cache while the entry is fresh.
+    pub async fn resolve(&self, addr: &str) -> io::Result<Vec<SocketAddr>> {
+        if let Some(entry) = self.entries.get(addr) {
+            let (resolved_at, addrs) = entry.value();
+            if resolved_at.elapsed() < self.ttl {
+                return Ok(addrs.clone());
+            }
+        }
+
+        let addrs: Vec<SocketAddr> = tokio::net::lookup_host(addr).await?.collect();
+        if !self.ttl.is_zero() && !addrs.is_empty() {
+            self.entries
+                .insert(addr.to_string(), (Instant::now(), addrs.clone()));
+        }
+        Ok(addrs)
+    }
+}
+
+impl Default for ResolveCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_RESOLVE_TTL)
+    }
+}
+
+/// Backend dials in progress.
+///
+/// Clones share the same counts.
+#[derive(Debug, Clone, Default)]
+pub struct ConnectPressure {
+    /// Dials between start and result, name resolution included
+    in_flight: Arc<AtomicUsize>,
+    /// Addresses waiting for their turn in a Happy Eyeballs race
+    queued: Arc<AtomicUsize>,
+}
+
+impl ConnectPressure {
+    /// Number of backend dials in progress.
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::Relaxed)
+    }
+
+    /// Number of addresses queued behind the attempts in progress.
+    pub fn queued(&self) -> usize {
+        self.queued.load(Ordering::Relaxed)
+    }
+
+    /// Start the background task reporting the counts to `service`'s
+    /// metrics and warning when `alert` is exceeded.
+    pub fn start_monitor(&self, service: Arc<ProxyService>, alert: PressureAlert) {
+        let pressure = self.clone();
+        let mut monitor = PressureMonitor::new(alert);
+
+        tokio::spawn(async move {
+            loop {
+                let in_flight = pressure.in_flight();
+                service.set_connect_pressure(in_flight, pressure.queued());
+                match monitor.observe(in_flight, Instant::now()) {
+                    Some(PressureEvent::Saturated { in_flight, since }) => tracing::warn!(
+                        in_flight,
+                        threshold = alert.threshold,
+                        "backend connects saturated: over the threshold for {:?}",
+                        since
+                    ),
+                    Some(PressureEvent::Recovered) => {
+                        tracing::info!(in_flight, "backend connects back under the threshold")
+                    }
+                    None => {}
+                }
+
+                tokio::time::sleep(PRESSURE_SAMPLE_INTERVAL).await;
+            }
+        });
+    }
+}
+
+/// Holds `held` units of a gauge, giving back whatever is left on drop.
+struct GaugeHold<'a> {
+    gauge: &'a AtomicUsize,
+    held: usize,
+}
+
+impl<'a> GaugeHold<'a> {
+    fn new(gauge: &'a AtomicUsize, held: usize) -> Self {
+        gauge.fetch_add(held, Ordering::Relaxed);
+        Self { gauge, held }
+    }
+
+    /// Give one unit back early.
+    fn release_one(&mut self) {
+        if self.held > 0 {
+            self.held -= 1;
+            self.gauge.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+}
+
+impl Drop for GaugeHold<'_> {
+    fn drop(&mut self) {
+        self.gauge.fetch_sub(self.held, Ordering::Relaxed);
+    }
+}
+
+/// When to warn about backend connect backpressure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PressureAlert {
+    /// Dials in progress above which backends are considered saturated
+    /// (0 disables the warning)
+    pub threshold: usize,
+    /// How long the count must stay above the threshold before warning
+    pub sustain: Duration,
+}
+
+/// A change in connect backpressure worth reporting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PressureEvent {
+    /// Over the threshold for `since`, at `in_flight` dials
+    Saturated { in_flight: usize, since: Duration },
+    /// Back under the threshold after a warning
+    Recovered,
+}
+
+/// Tracks how long the dials in progress have stayed over the threshold.
+///
+/// Warns once per episode and reports the recovery, so a sustained
+/// saturation doesn't repeat the warning on every sample.
+struct PressureMonitor {
+    alert: PressureAlert,
+    above_since: Option<Instant>,
+    warned: bool,
+}
+
+impl PressureMonitor {
+    fn new(alert: PressureAlert) -> Self {
+        Self {
+            alert,
+            above_since: None,
+            warned: false,
+        }
+    }
+
+    /// Feed one sample, returning the event it triggers, if any.
+    fn observe(&mut self, in_flight: usize, now: Instant) -> Option<PressureEvent> {
+        if self.alert.threshold == 0 || in_flight <= self.alert.threshold {
+            self.above_since = None;
+            let warned = std::mem::take(&mut self.warned);
+            return warned.then_some(PressureEvent::Recovered);
+        }
+
+        let since = now.duration_since(*self.above_since.get_or_insert(now));
+        if self.warned || since < self.alert.sustain {
+            return None;
+        }
+        self.warned = true;
+        Some(PressureEvent::Saturated { in_flight, since })
+    }
+}
+
+/// Socket options applied when connecting to a backend.
+#[derive(Debug, Clone, Default)]
+pub struct DialOptions {
+    /// Use TCP Fast Open, sending the first bytes with the SYN to save a
+    /// round trip on repeat connections (Linux only; ignored elsewhere).
+    pub tcp_fast_open: bool,
+    /// Cache for backends addressed by hostname
+    pub resolver: ResolveCache,
+    /// Counts of the dials in progress
+    pub pressure: ConnectPressure,
+    /// Buffer sizes for backend sockets
+    pub buffers: SocketBuffers,
+}
+
+/// How long each phase of a backend dial took.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ConnectTimings {
+    /// Name resolution; `None` when the address was already an IP
+    pub resolve: Option<Duration>,
+    /// TCP connect, across every address tried
+    pub connect: Duration,
+}
+
+impl ConnectTimings {
+    /// Time spent dialing overall.
+    pub fn total(&self) -> Duration {
+        self.resolve.unwrap_or_default() + self.connect
+    }
+
+    /// Record the overall RTT and each phase against `backend_id`.
+    pub fn record(&self, service: &ProxyService, backend_id: &str) {
+        service.record_rtt(backend_id, self.total().as_millis() as u64);
+        if let Some(resolve) = self.resolve {
+            service.record_connect_phase(
+                backend_id,
+                ConnectPhase::Resolve,
+                resolve.as_millis() as u64,
+            );
+        }
+        service.record_connect_phase(
+            backend_id,
+            ConnectPhase::Connect,
+            self.connect.as_millis() as u64,
+        );
+    }
+}
+
+impl DialOptions {
+    /// Connect to `addr` (host names are resolved, each address tried in turn).
+    pub async fn connect(&self, addr: &str) -> io::Result<TcpStream> {
+        self.connect_timed(addr).await.map(|(stream, _)| stream)
+    }
+
+    /// Connect to `addr` like [`DialOptions::connect`], timing name
+    /// resolution and the TCP connect separately.
+    pub async fn connect_timed(&self, addr: &str) -> io::Result<(TcpStream, ConnectTimings)> {
+        let _in_flight = GaugeHold::new(&self.pressure.in_flight, 1);
+        let mut timings = ConnectTimings::default();
+        let addrs: Vec<SocketAddr> = match addr.parse::<SocketAddr>() {
+            Ok(addr) => vec![addr],
+            Err(_) => {
+                let t0 = Instant::now();
+                let addrs = self.resolver.resolve(addr).await?;
+                timings.resolve = Some(t0.elapsed());
+                addrs
+            }
+        };
+        if addrs.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("backend address {} did not resolve", addr),
+            ));
+        }
+
+        let t0 = Instant::now();
+        let stream = self.connect_any(interleave_families(addrs)).await?;
+        timings.connect = t0.elapsed();
+        Ok((stream, timings))
+    }
+
+    /// Race connections to `addrs` in order, starting the next one when the
+    /// previous fails or has had [`CONNECTION_ATTEMPT_DELAY`] to succeed.
+    ///
+    /// The first to connect wins; the others are aborted.
+    async fn connect_any(&self, addrs: Vec<SocketAddr>) -> io::Result<TcpStream> {
+        let mut queued = GaugeHold::new(&self.pressure.queued, addrs.len());
+        let mut pending = addrs.into_iter().peekable();
+        let mut attempts = JoinSet::new();
+        let mut last_err = None;
+
+        loop {
+            if let Some(addr) = pending.next() {
+                queued.release_one();
+                let options = self.clone();
+                attempts.spawn(async move { options.connect_addr(addr).await });
+            }
+            if attempts.is_empty() {
+                break;
+            }
+
+            let more = pending.peek().is_some();
+            tokio::select! {
+                Some(joined) = attempts.join_next() => match joined {
+                    Ok(Ok(stream)) => return Ok(stream),
+                    Ok(Err(e)) => last_err = Some(e),
+                    Err(e) => last_err = Some(io::Error::other(e)),
+                },
+                _ = tokio::time::sleep(CONNECTION_ATTEMPT_DELAY), if more => {}
+            }
+        }
+
+        Err(last_err.unwrap_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "no backend address to connect to")
+        }))
+    }
+
+    /// Connect to a single address with the options applied.
+    async fn connect_addr(&self, addr: SocketAddr) -> io::Result<TcpStream> {
+        if self.tcp_fast_open || self.buffers.is_set() {
+            self.socket_for(addr)?.connect(addr).await
+        } else {
+            TcpStream::connect(addr).await
+        }
+    }
+
+    /// Create an unconnected socket for `addr` with the options applied.
+    fn socket_for(&self, addr: SocketAddr) -> io::Result<TcpSocket> {
+        let socket = if addr.is_ipv4() {
+            TcpSocket::new_v4()?
+        } else {
+            TcpSocket::new_v6()?
+        };
+
+        if self.tcp_fast_open {
+            if let Err(e) = set_fast_open_connect(&socket) {
+                tracing::debug!("TCP Fast Open unavailable, connecting without it: {}", e);
+            }
+        }
+        self.buffers.apply(SockRef::from(&socket))?;
+        Ok(socket)
+    }
+}
+
+/// Order addresses so the families alternate, starting with the family of
+/// the first address (RFC 8305, section 4).
+fn interleave_families(addrs: Vec<SocketAddr>) -> Vec<SocketAddr> {
+    let Some(first) = addrs.first() else {
+        return addrs;
+    };
+    let first_is_v6 = first.is_ipv6();
+    let (preferred, other): (Vec<_>, Vec<_>) =
+        addrs.into_iter().partition(|a| a.is_ipv6() == first_is_v6);
+
+    let mut ordered = Vec::with_capacity(preferred.len() + other.len());
+    let mut preferred = preferred.into_iter();
+    let mut other = other.into_iter();
+    loop {
+        match (preferred.next(), other.next()) {
+            (None, None) => break,
+            (a, b) => ordered.extend(a.into_iter().chain(b)),
+        }
+    }
+    ordered
+}
+
+/// Enable `TCP_FASTOPEN_CONNECT`, deferring the SYN to the first write.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn set_fast_open_connect(socket: &TcpSocket) -> io::Result<()> {
+    use std::os::fd::AsRawFd;
+
+    let enable: libc::c_int = 1;
+    // SAFETY: the fd is owned by `socket` and open for the duration of the call,
+    // and `enable` outlives it with the size passed.
+    let rc = unsafe {
+        libc::setsockopt(
+            socket.as_raw_fd(),
+            libc::IPPROTO_TCP,
+            libc::TCP_FASTOPEN_CONNECT,
+            &enable as *const libc::c_int as *const libc::c_void,
+            std::mem::size_of::<libc::c_int>() as libc::socklen_t,
+        )
+    };
+    if rc == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn set_fast_open_connect(_socket: &TcpSocket) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "TCP Fast Open for outgoing connections is only supported on Linux",
+    ))
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    fn fast_open() -> DialOptions {
+        DialOptions {
+            tcp_fast_open: true,
+            ..Default::default()
+        }
+    }
+
+    async fn echo_once(options: DialOptions) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 4];
+            stream.read_exact(&mut buf).await.unwrap();
+            stream.write_all(&buf).await.unwrap();
+        });
+
+        let mut stream = options.connect(&addr.to_string()).await.unwrap();
+        stream.write_all(b"ping").await.unwrap();
+        let mut buf = [0u8; 4];
+        stream.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"ping");
+        server.await.unwrap();
+    }
+
+    #[test]
+    fn test_default_options() {
+        assert!(!DialOptions::default().tcp_fast_open);
+    }
+
+    #[tokio::test]
+    async fn test_connect_default() {
+        echo_once(DialOptions::default()).await;
+    }
+
+    #[tokio::test]
+    async fn test_connect_with_fast_open() {
+        echo_once(fast_open()).await;
+    }
+
+    #[tokio::test]
+    async fn test_connect_refused() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        drop(listener);
+
+        let options = fast_open();
+        assert!(options.connect(&addr).await.is_err());
+        assert!(options.connect("not-an-address").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_connect_timed_ip_skips_resolve() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+
+        let (_stream, timings) = DialOptions::default().connect_timed(&addr).await.unwrap();
+        assert!(timings.resolve.is_none());
+        assert_eq!(timings.total(), timings.connect);
+    }
+
+    #[tokio::test]
+    async fn test_connect_timed_hostname_resolves() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        for options in [DialOptions::default(), fast_open()] {
+            let (stream, timings) = options
+                .connect_timed(&format!("localhost:{}", port))
+                .await
+                .unwrap();
+            assert!(stream.peer_addr().unwrap().ip().is_loopback());
+            let resolve = timings.resolve.expect("hostname dial has a resolve phase");
+            assert_eq!(timings.total(), resolve + timings.connect);
+        }
+    }
+
+    // ===== Hostname Resolution Tests =====
+
+    #[tokio::test]
+    async fn test_hostname_backend_resolves_through_cache() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let known = listener.local_addr().unwrap();
+
+        // Seed the cache so the hostname resolves to a known address
+        let options = DialOptions::default();
+        let host = format!("backend.invalid:{}", known.port());
+        options
+            .resolver
+            .entries
+            .insert(host.clone(), (Instant::now(), vec![known]));
+
+        let (stream, timings) = options.connect_timed(&host).await.unwrap();
+        assert_eq!(stream.peer_addr().unwrap(), known);
+        assert!(timings.resolve.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_resolve_cache_expires_entries() {
+        let cache = ResolveCache::new(Duration::from_millis(10));
+        let stale = Instant::now() - Duration::from_secs(1);
+        cache.entries.insert(
+            "backend.invalid:80".to_string(),
+            (stale, vec!["127.0.0.1:80".parse().unwrap()]),
+        );
+
+        // Expired, so this goes to the system resolver, which can't find it
+        assert!(cache.resolve("backend.invalid:80").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_resolve_cache_stores_lookups() {
+        let cache = ResolveCache::default();
+        let addrs = cache.resolve("localhost:80").await.unwrap();
+        assert!(!addrs.is_empty());
+        assert_eq!(cache.entries.get("localhost:80").unwrap().1, addrs);
+
+        let uncached = ResolveCache::new(Duration::ZERO);
+        uncached.resolve("localhost:80").await.unwrap();
+        assert!(uncached.entries.is_empty());
+    }
+
+    // ===== Connect Pressure Tests =====
+
+    /// A listener that never accepts, its accept queue already full, so
+    /// further connects hang with their SYNs dropped.
+    async fn saturated_listener() -> (TcpListener, Vec<TcpStream>, SocketAddr) {
+        let socket = TcpSocket::new_v4().unwrap();
+        socket.bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let addr = socket.local_addr().unwrap();
+        let listener = socket.listen(1).unwrap();
+
+        let mut fill = Vec::new();
+        while let Ok(Ok(stream)) =
+            tokio::time::timeout(Duration::from_millis(200), TcpStream::connect(addr)).await
+        {
+            fill.push(stream);
+        }
+        (listener, fill, addr)
+    }
+
+    async fn wait_for(mut check: impl FnMut() -> bool) -> bool {
+        for _ in 0..200 {
+            if check() {
+                return true;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        false
+    }
+
+    #[tokio::test]
+    async fn test_pressure_counts_concurrent_slow_connects() {
+        let (_listener, _fill, addr) = saturated_listener().await;
+        let options = DialOptions::default();
+        assert_eq!(options.pressure.in_flight(), 0);
+
+        let dials: Vec<_> = (0..20)
+            .map(|_| {
+                let options = options.clone();
+                tokio::spawn(async move { options.connect(&addr.to_string()).await })
+            })
+            .collect();
+
+        let pressure = options.pressure.clone();
+        assert!(wait_for(|| pressure.in_flight() == 20).await);
+        assert_eq!(pressure.queued(), 0);
+
+        // Abandoned dials give their slot back
+        for dial in &dials {
+            dial.abort();
+        }
+        assert!(wait_for(|| pressure.in_flight() == 0).await);
+    }
+
+    #[tokio::test]
+    async fn test_pressure_counts_queued_addresses() {
+        let (_listener, _fill, addr) = saturated_listener().await;
+        let options = DialOptions::default();
+
+        let dial = {
+            let options = options.clone();
+            tokio::spawn(async move { options.connect_any(vec![addr, addr, addr]).await })
+        };
+
+        // The first attempt starts at once, the others wait their turn
+        let pressure = options.pressure.clone();
+        assert!(wait_for(|| pressure.queued() == 2).await);
+
+        dial.abort();
+        assert!(wait_for(|| pressure.queued() == 0).await);
+    }
+
+    #[tokio::test]
+    async fn test_pressure_released_after_dial() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let options = DialOptions::default();
+
+        let _stream = options.connect(&addr).await.unwrap();
+        drop(listener);
+        assert!(options.connect(&addr).await.is_err());
+
+        assert_eq!(options.pressure.in_flight(), 0);
+        assert_eq!(options.pressure.queued(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_pressure_monitor_reports_to_metrics() {
+        use crate::adapters::outbound::{
+            DashMapBindingRepository, DashMapMetricsStore, SqliteBackendRepository,
+        };
+        use crate::domain::ports::MetricsStore;
+        use crate::domain::value_objects::RegionCode;
+
+        let (_listener, _fill, addr) = saturated_listener().await;
+        let metrics = Arc::new(DashMapMetricsStore::new());
+        let service = Arc::new(ProxyService::new(
+            Arc::new(SqliteBackendRepository::new()),
+            Arc::new(DashMapBindingRepository::new()),
+            None,
+            metrics.clone(),
+            RegionCode::Europe,
+        ));
+
+        let options = DialOptions::default();
+        let dials: Vec<_> = (0..5)
+            .map(|_| {
+                let options = options.clone();
+                tokio::spawn(async move { options.connect(&addr.to_string()).await })
+            })
+            .collect();
+        let pressure = options.pressure.clone();
+        assert!(wait_for(|| pressure.in_flight() == 5).await);
+
+        pressure.start_monitor(
+            service,
+            PressureAlert {
+                threshold: 0,
+                sustain: Duration::ZERO,
+            },
+        );
+        assert!(wait_for(|| metrics.get_connect_pressure() == (5, 0)).await);
+
+        for dial in dials {
+            dial.abort();
+        }
+    }
+
+    #[test]
+    fn test_pressure_monitor_warns_once_after_sustain() {
+        let mut monitor = PressureMonitor::new(PressureAlert {
+            threshold: 10,
+            sustain: Duration::from_secs(5),
+        });
+        let t0 = Instant::now();
+        let at = |secs| t0 + Duration::from_secs(secs);
+
+        assert_eq!(monitor.observe(10, at(0)), None); // not above
+        assert_eq!(monitor.observe(11, at(1)), None); // above, starts the clock
+        assert_eq!(monitor.observe(50, at(5)), None); // only 4s
+        assert_eq!(
+            monitor.observe(40, at(6)),
+            Some(PressureEvent::Saturated {
+                in_flight: 40,
+                since: Duration::from_secs(5)
+            })
+        );
+        assert_eq!(monitor.observe(40, at(20)), None); // already warned
+        assert_eq!(monitor.observe(3, at(21)), Some(PressureEvent::Recovered));
+        assert_eq!(monitor.observe(3, at(22)), None);
+    }
+
+    #[test]
+    fn test_pressure_monitor_resets_when_dipping_under() {
+        let mut monitor = PressureMonitor::new(PressureAlert {
+            threshold: 10,
+            sustain: Duration::from_secs(5),
+        });
+        let t0 = Instant::now();
+        let at = |secs| t0 + Duration::from_secs(secs);
+
+        assert_eq!(monitor.observe(20, at(0)), None);
+        // A dip under the threshold restarts the clock, without a recovery event
+        assert_eq!(monitor.observe(5, at(4)), None);
+        assert_eq!(monitor.observe(20, at(6)), None);
+        assert_eq!(monitor.observe(20, at(10)), None);
+        assert!(monitor.observe(20, at(11)).is_some());
+    }
+
+    #[test]
+    fn test_pressure_monitor_disabled_at_zero_threshold() {
+        let mut monitor = PressureMonitor::new(PressureAlert {
+            threshold: 0,
+            sustain: Duration::ZERO,
+        });
+        let t0 = Instant::now();
+        assert_eq!(monitor.observe(1000, t0), None);
+        assert_eq!(monitor.observe(1000, t0 + Duration::from_secs(60)), None);
+    }
+
+    #[test]
+    fn test_interleave_families() {
+        let v4 = |n: u8| SocketAddr::from(([10, 0, 0, n], 80));
+        let v6 = |n: u16| SocketAddr::from(([0xfd00, 0, 0, 0, 0, 0, 0, n], 80));
+
+        assert_eq!(
+            interleave_families(vec![v6(1), v6(2), v6(3), v4(1), v4(2)]),
+            vec![v6(1), v4(1), v6(2), v4(2), v6(3)]
+        );
+        assert_eq!(
+            interleave_families(vec![v4(1), v4(2), v6(1)]),
+            vec![v4(1), v6(1), v4(2)]
+        );
+        assert!(interleave_families(Vec::new()).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_connect_any_falls_through_to_reachable_address() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let good = listener.local_addr().unwrap();
+        let closed = {
+            let l = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            l.local_addr().unwrap()
+        };
+
+        let stream = DialOptions::default()
+            .connect_any(vec![closed, good])
+            .await
+            .unwrap();
+        assert_eq!(stream.peer_addr().unwrap(), good);
+
+        assert!(DialOptions::default().connect_any(vec![closed]).await.is_err());
+        assert!(DialOptions::default().connect_any(Vec::new()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_connect_any_races_past_slow_address() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let good = listener.local_addr().unwrap();
+        // TEST-NET-1 is never routed: the attempt hangs (or fails fast where
+        // there's no route at all) and the next address gets its turn
+        let blackhole: SocketAddr = "192.0.2.1:80".parse().unwrap();
+
+        let stream = tokio::time::timeout(
+            Duration::from_secs(2),
+            DialOptions::default().connect_any(vec![blackhole, good]),
+        )
+        .await
+        .expect("second address should be tried after the attempt delay")
+        .unwrap();
+        assert_eq!(stream.peer_addr().unwrap(), good);
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[test]
+    fn test_fast_open_option_set_on_socket() {
+        use std::os::fd::AsRawFd;
+
+        let fast_open_connect = |socket: &TcpSocket| {
+            let mut value: libc::c_int = 0;
+            let mut len = std::mem::size_of::<libc::c_int>() as libc::socklen_t;
+            // SAFETY: valid fd, and `value`/`len` describe a writable c_int
+            let rc = unsafe {
+                libc::getsockopt(
+                    socket.as_raw_fd(),
+                    libc::IPPROTO_TCP,
+                    libc::TCP_FASTOPEN_CONNECT,
+                    &mut value as *mut libc::c_int as *mut libc::c_void,
+                    &mut len,
+                )
+            };
+            assert_eq!(rc, 0, "getsockopt: {}", io::Error::last_os_error());
+            value
+        };
+
+        let addr: SocketAddr = "127.0.0.1:9".parse().unwrap();
+        let enabled = fast_open().socket_for(addr).unwrap();
+        assert_eq!(fast_open_connect(&enabled), 1);
+
+        let disabled = DialOptions::default().socket_for(addr).unwrap();
+        assert_eq!(fast_open_connect(&disabled), 0);
+    }
+
+    // Linux doubles the requested size to leave room for bookkeeping
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_backend_sockets_get_buffer_sizes() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let options = DialOptions {
+            buffers: SocketBuffers {
+                recv: 32 * 1024,
+                send: 48 * 1024,
+            },
+            ..Default::default()
+        };
+
+        let stream = options.connect(&addr).await.unwrap();
+        let socket = SockRef::from(&stream);
+        assert_eq!(socket.recv_buffer_size().unwrap(), 2 * 32 * 1024);
+        assert_eq!(socket.send_buffer_size().unwrap(), 2 * 48 * 1024);
+    }
+}
@@ -1,120 +1,562 @@
 //! DNS Server Adapter
 //!
 //! Internal DNS resolver for .internal domain names.
-//! Resolves app.internal -> backend IP based on geo-routing.
+//! Resolves app.internal -> backend IP based on geo-routing, and answers
+//! SRV queries with every healthy backend, tiered by backend priority.
 
+use super::listener::ListenOptions;
+use super::public_ip::PublicIpGeo;
 use crate::application::ProxyService;
-use crate::domain::ports::GeoResolver;
-use hickory_proto::op::{Header, MessageType, OpCode, ResponseCode};
-use hickory_proto::rr::rdata::A;
+use crate::domain::entities::{Backend, GeoInfo};
+use crate::domain::services::WeightedRoundRobin;
+use crate::domain::value_objects::DnsQueryOutcome;
+use crate::infrastructure::ShutdownController;
+use hickory_proto::error::ProtoResult;
+use hickory_proto::op::{Edns, Header, Message, MessageType, OpCode, ResponseCode};
+use hickory_proto::rr::rdata::{A, AAAA, SRV};
 use hickory_proto::rr::{LowerName, Name, RData, Record, RecordType};
 use hickory_server::authority::MessageResponseBuilder;
 use hickory_server::server::{Request, RequestHandler, ResponseHandler, ResponseInfo};
-use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 use std::str::FromStr;
 use std::sync::Arc;
 use tokio::net::UdpSocket;
 
+/// Smallest UDP payload every DNS client must accept (RFC 1035).
+const MIN_UDP_PAYLOAD: u16 = 512;
+
+/// Default cap on UDP responses, the size recommended to avoid IP
+/// fragmentation (DNS Flag Day 2020).
+pub const DEFAULT_MAX_UDP_PAYLOAD: u16 = 1232;
+
 /// DNS Server configuration.
 #[derive(Clone)]
 pub struct DnsConfig {
-    /// Domain suffix (e.g., "internal")
-    pub domain: String,
+    /// Domain suffixes answered for (e.g., "internal"); a name under
+    /// several of them is matched against the longest
+    pub domains: Vec<String>,
     /// Default TTL for records
     pub ttl: u32,
+    /// Answer SERVFAIL instead of NXDOMAIN when an app has no healthy backend
+    pub servfail_on_unhealthy: bool,
+    /// NAT64 /96 prefix (e.g. `64:ff9b::`) used to answer A queries for
+    /// IPv6-only apps with the IPv4 address embedded in the backend address
+    pub nat64_prefix: Option<Ipv6Addr>,
+    /// Answer A queries with every healthy IPv4 backend in the selected
+    /// region, rotated by weight (smooth weighted round robin)
+    pub weighted_rotation: bool,
+    /// Apps answering for every name below them: with `myapp` listed,
+    /// `foo.myapp.internal` resolves to `myapp` instead of app `foo.myapp`
+    pub wildcard_apps: Vec<String>,
+    /// Largest UDP response we send, whatever size an EDNS0 client
+    /// advertises (never below 512 bytes)
+    pub max_udp_payload: u16,
+    /// Answer NXDOMAIN instead of REFUSED to every query while the node
+    /// is draining
+    pub nxdomain_when_draining: bool,
 }
 
 impl Default for DnsConfig {
     fn default() -> Self {
         Self {
-            domain: "internal".to_string(),
+            domains: vec!["internal".to_string()],
             ttl: 30,
+            servfail_on_unhealthy: false,
+            nat64_prefix: None,
+            weighted_rotation: false,
+            wildcard_apps: Vec::new(),
+            max_udp_payload: DEFAULT_MAX_UDP_PAYLOAD,
+            nxdomain_when_draining: false,
+        }
+    }
+}
+
+impl DnsConfig {
+    /// Parse a NAT64 prefix such as `64:ff9b::` or `64:ff9b::/96`.
+    ///
+    /// Only /96 prefixes are supported, so the low 32 bits must be zero.
+    pub fn parse_nat64_prefix(value: &str) -> anyhow::Result<Ipv6Addr> {
+        let (addr, len) = match value.split_once('/') {
+            Some((addr, len)) => (addr, len),
+            None => (value, "96"),
+        };
+        if len != "96" {
+            anyhow::bail!("unsupported NAT64 prefix length /{} (only /96)", len);
         }
+
+        let prefix: Ipv6Addr = addr.trim().parse()?;
+        if prefix.octets()[12..] != [0, 0, 0, 0] {
+            anyhow::bail!("NAT64 prefix {} has non-zero host bits", prefix);
+        }
+        Ok(prefix)
+    }
+}
+
+/// Extract the IPv4 address embedded in `addr` under a NAT64 /96 `prefix`.
+fn nat64_embedded_ipv4(prefix: &Ipv6Addr, addr: &Ipv6Addr) -> Option<Ipv4Addr> {
+    let (prefix, addr) = (prefix.octets(), addr.octets());
+    if prefix[..12] != addr[..12] {
+        return None;
     }
+    Some(Ipv4Addr::new(addr[12], addr[13], addr[14], addr[15]))
+}
+
+/// Drop the leading `_service._proto` labels of an SRV query name
+/// (e.g. `_http._tcp.myapp.internal` -> `myapp.internal`).
+fn srv_owner(name: &LowerName) -> Name {
+    let name = Name::from(name.clone());
+    let service_labels = name.iter().take_while(|label| label.starts_with(b"_")).count();
+    name.trim_to(name.num_labels() as usize - service_labels)
+}
+
+/// Outcome of resolving a query name.
+#[derive(Debug, Clone, PartialEq)]
+enum DnsResolution {
+    /// Healthy backend addresses to answer with, preferred first (never empty)
+    Found(Vec<IpAddr>),
+    /// The name is ours but can't be answered for the queried record type
+    NotFound,
+    /// The name is ours and served, but has no IPv6 address for an AAAA query
+    NoData,
+    /// The name is ours but no healthy backend serves it
+    NoHealthyBackends,
+    /// The name is outside our domain
+    NotAuthoritative,
 }
 
 /// DNS Request Handler.
 pub struct DnsHandler {
     proxy_service: Arc<ProxyService>,
-    geo_resolver: Option<Arc<dyn GeoResolver>>,
+    public_ip_geo: Arc<PublicIpGeo>,
     config: DnsConfig,
+    shutdown: ShutdownController,
+    /// Weighted rotation state per app (`""` for the bare domain)
+    rotations: Mutex<HashMap<String, WeightedRoundRobin>>,
 }
 
 impl DnsHandler {
-    pub fn new(
-        proxy_service: Arc<ProxyService>,
-        geo_resolver: Option<Arc<dyn GeoResolver>>,
-        config: DnsConfig,
-    ) -> Self {
+    pub fn new(proxy_service: Arc<ProxyService>, config: DnsConfig) -> Self {
         Self {
             proxy_service,
-            geo_resolver,
+            public_ip_geo: Arc::new(PublicIpGeo::default()),
             config,
+            shutdown: ShutdownController::new(),
+            rotations: Mutex::new(HashMap::new()),
         }
     }
 
+    /// Share a public IP geo lookup (used for loopback clients) with other listeners.
+    pub fn with_public_ip_geo(mut self, public_ip_geo: Arc<PublicIpGeo>) -> Self {
+        self.public_ip_geo = public_ip_geo;
+        self
+    }
+
+    /// Stop answering queries while `shutdown` is draining, so clients
+    /// fail over to other POPs.
+    pub fn with_shutdown(mut self, shutdown: ShutdownController) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+
     /// Resolve a DNS query.
+    #[allow(dead_code)]
     async fn resolve(&self, name: &LowerName, client_ip: IpAddr) -> Option<Ipv4Addr> {
-        // Parse the query name (e.g., "myapp.internal")
+        match self.resolve_query(name, client_ip, RecordType::A).await {
+            DnsResolution::Found(ips) => match ips.first() {
+                Some(IpAddr::V4(ip)) => Some(*ip),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Extract the app name from a query name (e.g., "myapp" from "myapp.internal").
+    ///
+    /// The longest configured domain the name falls under is stripped.
+    /// Returns `None` for names outside our domains and `Some(None)` for a
+    /// bare domain itself, which resolves to any backend.
+    fn parse_app_name(&self, name: &LowerName) -> Option<Option<String>> {
         let query_str = name.to_string();
         let query_str = query_str.trim_end_matches('.');
 
-        // Check if it's in our domain
-        let suffix = format!(".{}", self.config.domain);
-        if !query_str.ends_with(&suffix) && query_str != self.config.domain {
-            tracing::debug!("DNS query not in our domain: {}", query_str);
-            return None;
-        }
+        let (domain, app) = self
+            .config
+            .domains
+            .iter()
+            .filter_map(|domain| {
+                if query_str == domain {
+                    return Some((domain, None));
+                }
+                let app = query_str.strip_suffix(domain.as_str())?.strip_suffix('.')?;
+                (!app.is_empty()).then_some((domain, Some(app)))
+            })
+            .max_by_key(|(domain, _)| domain.len())?;
 
-        // Extract app name (e.g., "myapp" from "myapp.internal")
-        let app_name = if query_str == self.config.domain {
-            // Query for just "internal" - return any backend
-            None
-        } else {
-            Some(query_str.strip_suffix(&suffix).unwrap_or(query_str))
+        tracing::trace!("DNS query {} matched domain {}", query_str, domain);
+        Some(app.map(|app| self.wildcard_app(app).unwrap_or(app).to_string()))
+    }
+
+    /// The wildcard app `name` falls under (e.g. "myapp" for "a.myapp"),
+    /// preferring the longest match.
+    fn wildcard_app(&self, name: &str) -> Option<&str> {
+        self.config
+            .wildcard_apps
+            .iter()
+            .filter(|app| {
+                name.strip_suffix(app.as_str())
+                    .is_some_and(|prefix| prefix.len() > 1 && prefix.ends_with('.'))
+            })
+            .max_by_key(|app| app.len())
+            .map(String::as_str)
+    }
+
+    /// App label used for DNS query metrics.
+    ///
+    /// Root and out-of-domain queries share the `-` label to keep the
+    /// number of series bounded.
+    fn metrics_app_label(&self, name: &LowerName) -> String {
+        self.parse_app_name(name)
+            .flatten()
+            .unwrap_or_else(|| "-".to_string())
+    }
+
+    /// Resolve an A or AAAA query, distinguishing unknown names from unhealthy apps.
+    ///
+    /// Only backends reported by `get_healthy` are ever returned. A queries
+    /// prefer IPv4 backends and, when a NAT64 prefix is configured, fall back
+    /// to IPv6 backends inside that prefix.
+    async fn resolve_query(
+        &self,
+        name: &LowerName,
+        client_ip: IpAddr,
+        record_type: RecordType,
+    ) -> DnsResolution {
+        // Check if it's in our domain and extract the app name
+        let Some(app) = self.parse_app_name(name) else {
+            tracing::debug!("DNS query not in our domain: {}", name);
+            return DnsResolution::NotAuthoritative;
         };
+        let app_name = app.as_deref();
 
         tracing::debug!("DNS resolving: {:?} for client {}", app_name, client_ip);
 
-        // Resolve client geo
-        let client_geo = if client_ip.is_loopback() {
-            None
+        // Resolve client geo, locating loopback clients like the proxy listeners do
+        let client_geo = self.public_ip_geo.client_geo(&self.proxy_service, client_ip).await;
+
+        // Get best healthy backend of the right address family for this app
+        // (hostname backends have no address to answer with and are skipped)
+        let found = match record_type {
+            RecordType::AAAA => self
+                .proxy_service
+                .select_healthy_backend_where(app_name, client_geo.as_ref(), |b| {
+                    b.wg_ip.parse::<Ipv6Addr>().is_ok()
+                })
+                .await
+                .and_then(|b| b.wg_ip.parse::<Ipv6Addr>().ok())
+                .map(|ip| vec![IpAddr::V6(ip)]),
+            _ => self
+                .resolve_ipv4(app_name, client_geo.as_ref())
+                .await
+                .map(|ips| ips.into_iter().map(IpAddr::V4).collect()),
+        };
+
+        if let Some(ips) = found {
+            return DnsResolution::Found(ips);
+        }
+
+        // Whether the app has any healthy backend at all, without a second
+        // selection (and its metrics)
+        if !self
+            .proxy_service
+            .healthy_backends_where(app_name, |_| true)
+            .await
+            .is_empty()
+        {
+            tracing::debug!("no {} address for app {:?}", record_type, app_name);
+            match record_type {
+                RecordType::AAAA => DnsResolution::NoData,
+                _ => DnsResolution::NotFound,
+            }
         } else {
-            self.geo_resolver
-                .as_ref()
-                .and_then(|g| g.resolve(client_ip))
+            tracing::debug!("no healthy backend for app {:?}", app_name);
+            DnsResolution::NoHealthyBackends
+        }
+    }
+
+    /// Pick IPv4 answers: native IPv4 backends first, then NAT64-mapped IPv6 backends.
+    ///
+    /// NAT64 answers are always a single record.
+    async fn resolve_ipv4(&self, app: Option<&str>, client_geo: Option<&GeoInfo>) -> Option<Vec<Ipv4Addr>> {
+        if let Some(backend) = self
+            .proxy_service
+            .select_healthy_backend_where(app, client_geo, |b| b.wg_ip.parse::<Ipv4Addr>().is_ok())
+            .await
+        {
+            let ip: Ipv4Addr = backend.wg_ip.parse().ok()?;
+            if !self.config.weighted_rotation {
+                return Some(vec![ip]);
+            }
+            let rotated = self.rotate_ipv4(app, &backend).await;
+            return Some(if rotated.is_empty() { vec![ip] } else { rotated });
+        }
+
+        let prefix = self.config.nat64_prefix?;
+        let mapped = |b: &Backend| {
+            b.wg_ip
+                .parse::<Ipv6Addr>()
+                .ok()
+                .and_then(|addr| nat64_embedded_ipv4(&prefix, &addr))
         };
 
-        // Get best backend for this client
         let backend = self
             .proxy_service
-            .resolve_backend_with_geo(client_ip, client_geo)
+            .select_healthy_backend_where(app, client_geo, |b| mapped(b).is_some())
             .await?;
+        tracing::debug!("backend {} answered via NAT64 prefix {}", backend.id, prefix);
+        mapped(&backend).map(|ip| vec![ip])
+    }
+
+    /// Every healthy IPv4 backend in `selected`'s region, in weighted rotation order.
+    ///
+    /// The load balancer still picks the region (geo routing), the rotation
+    /// only decides the record order within it, advancing once per query.
+    /// Zero-weight backends are left out unless `selected` is one of them
+    /// (the zero-weight fallback).
+    async fn rotate_ipv4(&self, app: Option<&str>, selected: &Backend) -> Vec<Ipv4Addr> {
+        let tier = self
+            .proxy_service
+            .healthy_backends_where(app, |b| {
+                b.region == selected.region
+                    && b.accepts_new_connections()
+                    && (b.weight > 0 || selected.weight == 0)
+                    && b.wg_ip.parse::<Ipv4Addr>().is_ok()
+            })
+            .await;
+
+        let mut rotations = self.rotations.lock();
+        rotations
+            .entry(app.unwrap_or_default().to_string())
+            .or_default()
+            .order(&tier)
+            .into_iter()
+            .filter_map(|b| b.wg_ip.parse().ok())
+            .collect()
+    }
+
+    /// Backends to list in an SRV answer, lowest priority tier first.
+    ///
+    /// Unlike A/AAAA answers no backend is picked: every backend of the app
+    /// that accepts new connections is listed, and the client chooses by
+    /// priority, then weight. A tier whose backends are all down simply
+    /// drops out of the answer.
+    async fn resolve_srv(&self, name: &LowerName) -> Result<Vec<Backend>, DnsResolution> {
+        let Some(app) = self.parse_app_name(&LowerName::from(srv_owner(name))) else {
+            tracing::debug!("DNS SRV query not in our domain: {}", name);
+            return Err(DnsResolution::NotAuthoritative);
+        };
+
+        let mut backends = self
+            .proxy_service
+            .healthy_backends_where(app.as_deref(), Backend::accepts_new_connections)
+            .await;
+        if backends.is_empty() {
+            tracing::debug!("no healthy backend for SRV app {:?}", app);
+            return Err(DnsResolution::NoHealthyBackends);
+        }
+        backends.sort_by(|a, b| (a.priority, &a.id).cmp(&(b.priority, &b.id)));
+        Ok(backends)
+    }
+
+    /// SRV records for `backends`, plus the address records of their targets.
+    ///
+    /// The SRV priority is the backend's tier and the SRV weight its load
+    /// balancing weight. IP backends get a `<backend id>.<app>.<domain>`
+    /// target whose address goes in the additional section; hostname
+    /// backends point at their hostname.
+    fn srv_records(&self, name: &LowerName, backends: &[Backend]) -> (Vec<Record>, Vec<Record>) {
+        let owner = srv_owner(name);
+        let mut records = Vec::with_capacity(backends.len());
+        let mut additionals = Vec::new();
+
+        for backend in backends {
+            let target = match backend.ip() {
+                Some(_) => Name::from_ascii(&backend.id).and_then(|id| id.append_domain(&owner)),
+                None => Name::from_ascii(format!("{}.", backend.wg_ip.trim_end_matches('.'))),
+            };
+            let Ok(target) = target else {
+                tracing::debug!("backend {} has no valid SRV target name, skipped", backend.id);
+                continue;
+            };
+
+            if let Some(ip) = backend.ip() {
+                additionals.push(self.record(target.clone(), address_rdata(ip)));
+            }
+            let srv = SRV::new(backend.priority, backend.weight.into(), backend.port, target);
+            records.push(self.record(Name::from(name.clone()), RData::SRV(srv)));
+        }
+        (records, additionals)
+    }
+
+    /// A record for `name` with the configured TTL.
+    fn record(&self, name: Name, rdata: RData) -> Record {
+        let mut record = Record::new();
+        record.set_name(name);
+        record.set_ttl(self.config.ttl);
+        record.set_record_type(rdata.record_type());
+        record.set_data(Some(rdata));
+        record
+    }
+}
+
+/// A or AAAA record data for `ip`.
+fn address_rdata(ip: IpAddr) -> RData {
+    match ip {
+        IpAddr::V4(v4) => RData::A(A(v4)),
+        IpAddr::V6(v6) => RData::AAAA(AAAA(v6)),
+    }
+}
+
+/// Response to a single query, before it is encoded for a transport.
+struct DnsAnswer {
+    code: ResponseCode,
+    authoritative: bool,
+    records: Vec<Record>,
+    /// Glue for the answer records (SRV target addresses)
+    additionals: Vec<Record>,
+}
 
-        // If app_name is specified, filter by app
-        if let Some(app) = app_name {
-            if backend.app != app {
-                // Need to find a backend for the specific app
-                // For now, just check if the resolved backend matches
-                tracing::debug!(
-                    "backend {} is for app {}, not {}",
-                    backend.id,
-                    backend.app,
-                    app
-                );
-                // TODO: Filter backends by app in resolve_backend
+impl DnsHandler {
+    /// Answer one query, recording it in the DNS query metrics.
+    async fn answer(&self, name: &LowerName, query_type: RecordType, client_ip: IpAddr) -> DnsAnswer {
+        let app_label = self.metrics_app_label(name);
+        let reply = |outcome, code, records| {
+            self.proxy_service.record_dns_query(&app_label, outcome);
+            DnsAnswer {
+                code,
+                authoritative: outcome != DnsQueryOutcome::Refused,
+                records,
+                additionals: Vec::new(),
             }
+        };
+
+        if self.shutdown.is_draining() {
+            tracing::debug!("DNS query while draining: {}", name);
+            return if self.config.nxdomain_when_draining {
+                reply(DnsQueryOutcome::NxDomain, ResponseCode::NXDomain, Vec::new())
+            } else {
+                reply(DnsQueryOutcome::Refused, ResponseCode::Refused, Vec::new())
+            };
         }
 
-        // Parse backend IP
-        match backend.wg_ip.parse::<Ipv4Addr>() {
-            Ok(ip) => Some(ip),
+        let resolved = match query_type {
+            RecordType::A | RecordType::AAAA => match self.resolve_query(name, client_ip, query_type).await {
+                DnsResolution::Found(ips) => {
+                    tracing::info!("DNS resolved: {} -> {:?}", name, ips);
+                    let records = ips
+                        .iter()
+                        .map(|ip| self.record(Name::from(name.clone()), address_rdata(*ip)))
+                        .collect();
+                    Ok((records, Vec::new()))
+                }
+                unresolved => Err(unresolved),
+            },
+            RecordType::SRV => self.resolve_srv(name).await.map(|backends| {
+                tracing::info!("DNS SRV resolved: {} -> {} backends", name, backends.len());
+                self.srv_records(name, &backends)
+            }),
+            // Only A, AAAA and SRV queries are handled
+            _ => return reply(DnsQueryOutcome::NotImp, ResponseCode::NotImp, Vec::new()),
+        };
+
+        match resolved {
+            Ok((records, additionals)) => DnsAnswer {
+                additionals,
+                ..reply(DnsQueryOutcome::NoError, ResponseCode::NoError, records)
+            },
+            Err(DnsResolution::NoHealthyBackends) if self.config.servfail_on_unhealthy => {
+                // SERVFAIL - app known to be served here, but nothing healthy right now
+                tracing::debug!("DNS SERVFAIL (no healthy backends): {}", name);
+                reply(DnsQueryOutcome::ServFail, ResponseCode::ServFail, Vec::new())
+            }
+            Err(DnsResolution::NoData) => {
+                // NODATA - the name exists, just not with this record type
+                tracing::debug!("DNS NODATA ({}): {}", query_type, name);
+                reply(DnsQueryOutcome::NoError, ResponseCode::NoError, Vec::new())
+            }
+            Err(DnsResolution::NotAuthoritative) => {
+                // REFUSED - we aren't authoritative for names outside our domain
+                tracing::debug!("DNS REFUSED (not our domain): {}", name);
+                reply(DnsQueryOutcome::Refused, ResponseCode::Refused, Vec::new())
+            }
             Err(_) => {
-                tracing::warn!("backend {} has non-IPv4 address: {}", backend.id, backend.wg_ip);
-                None
+                tracing::debug!("DNS NXDOMAIN: {}", name);
+                reply(DnsQueryOutcome::NxDomain, ResponseCode::NXDomain, Vec::new())
             }
         }
     }
+
+    /// Build the response to a query message received over plain UDP.
+    ///
+    /// Only the first question is answered. EDNS0 queries get an OPT record
+    /// back advertising [`DnsConfig::max_udp_payload`].
+    pub async fn respond(&self, request: &Message, client_ip: IpAddr) -> Message {
+        let mut response = Message::new();
+        response
+            .set_id(request.id())
+            .set_message_type(MessageType::Response)
+            .set_op_code(request.op_code())
+            .set_recursion_desired(request.recursion_desired())
+            .add_queries(request.queries().to_vec());
+
+        if request.extensions().is_some() {
+            let mut edns = Edns::new();
+            edns.set_max_payload(self.config.max_udp_payload.max(MIN_UDP_PAYLOAD));
+            response.set_edns(edns);
+        }
+
+        let Some(query) = request.queries().first() else {
+            response.set_response_code(ResponseCode::FormErr);
+            return response;
+        };
+
+        let answer = self
+            .answer(&LowerName::from(query.name()), query.query_type(), client_ip)
+            .await;
+        response
+            .set_authoritative(answer.authoritative)
+            .set_response_code(answer.code)
+            .add_answers(answer.records)
+            .add_additionals(answer.additionals);
+        response
+    }
+
+    /// Largest UDP response to send for `request`: the payload size it
+    /// advertises over EDNS0 (512 bytes without EDNS0), capped at
+    /// [`DnsConfig::max_udp_payload`].
+    fn udp_payload_limit(&self, request: &Message) -> usize {
+        request
+            .max_payload()
+            .min(self.config.max_udp_payload.max(MIN_UDP_PAYLOAD)) as usize
+    }
+}
+
+/// Encode a UDP response of at most `limit` bytes.
+///
+/// A response that doesn't fit goes out with its records dropped and the
+/// TC bit set, telling the client the answer was truncated.
+fn encode_udp(mut response: Message, limit: usize) -> ProtoResult<Vec<u8>> {
+    let bytes = response.to_vec()?;
+    if bytes.len() <= limit {
+        return Ok(bytes);
+    }
+
+    response.take_answers();
+    response.take_name_servers();
+    response.take_additionals();
+    response.set_truncated(true);
+    response.to_vec()
 }
 
 #[async_trait::async_trait]
@@ -135,86 +577,89 @@ impl RequestHandler for DnsHandler {
             request.src()
         );
 
+        let answer = self.answer(name, query_type, request.src().ip()).await;
+
         // Build response header
         let mut header = Header::response_from_request(request.header());
-        header.set_authoritative(true);
-
-        // Only handle A record queries
-        if query_type != RecordType::A {
-            header.set_response_code(ResponseCode::NotImp);
-            let response = MessageResponseBuilder::from_message_request(request)
-                .build_no_records(header);
-            return response_handle.send_response(response).await.unwrap_or_else(|e| {
-                tracing::error!("DNS response error: {:?}", e);
-                header.into()
-            });
-        }
-
-        // Resolve the query
-        let client_ip = request.src().ip();
-        let result = self.resolve(name, client_ip).await;
-
-        match result {
-            Some(ip) => {
-                // Build A record response - convert LowerName to Name
-                let mut record = Record::new();
-                record.set_name(Name::from(name.clone()));
-                record.set_ttl(self.config.ttl);
-                record.set_record_type(RecordType::A);
-                record.set_data(Some(RData::A(A(ip))));
-
-                header.set_response_code(ResponseCode::NoError);
-                let response = MessageResponseBuilder::from_message_request(request)
-                    .build(header, std::iter::once(&record), [], [], []);
-
-                tracing::info!("DNS resolved: {} -> {}", name, ip);
-
-                response_handle.send_response(response).await.unwrap_or_else(|e| {
-                    tracing::error!("DNS response error: {:?}", e);
-                    header.into()
-                })
-            }
-            None => {
-                // NXDOMAIN
-                header.set_response_code(ResponseCode::NXDomain);
-                let response = MessageResponseBuilder::from_message_request(request)
-                    .build_no_records(header);
-
-                tracing::debug!("DNS NXDOMAIN: {}", name);
-
-                response_handle.send_response(response).await.unwrap_or_else(|e| {
-                    tracing::error!("DNS response error: {:?}", e);
-                    header.into()
-                })
-            }
-        }
+        header.set_authoritative(answer.authoritative);
+        header.set_response_code(answer.code);
+        let response = MessageResponseBuilder::from_message_request(request)
+            .build(header, answer.records.iter(), [], [], answer.additionals.iter());
+
+        response_handle.send_response(response).await.unwrap_or_else(|e| {
+            tracing::error!("DNS response error: {:?}", e);
+            header.into()
+        })
     }
 }
 
 /// DNS Server for .internal domain resolution.
+///
+/// The [`DnsHandler`] is built from these settings when the server runs.
 pub struct DnsServer {
     listen_addr: String,
-    handler: Arc<DnsHandler>,
+    proxy_service: Arc<ProxyService>,
+    config: DnsConfig,
+    public_ip_geo: Arc<PublicIpGeo>,
+    shutdown: ShutdownController,
+    listen_options: ListenOptions,
 }
 
 impl DnsServer {
     pub fn new(
         listen_addr: String,
         proxy_service: Arc<ProxyService>,
-        geo_resolver: Option<Arc<dyn GeoResolver>>,
         domain: String,
     ) -> Self {
         let config = DnsConfig {
-            domain,
-            ttl: 30,
+            domains: vec![domain],
+            ..Default::default()
         };
 
+        Self::with_config(listen_addr, proxy_service, config)
+    }
+
+    /// Create a DNS server with a full configuration.
+    pub fn with_config(
+        listen_addr: String,
+        proxy_service: Arc<ProxyService>,
+        config: DnsConfig,
+    ) -> Self {
         Self {
             listen_addr,
-            handler: Arc::new(DnsHandler::new(proxy_service, geo_resolver, config)),
+            proxy_service,
+            config,
+            public_ip_geo: Arc::new(PublicIpGeo::default()),
+            shutdown: ShutdownController::new(),
+            listen_options: ListenOptions::default(),
         }
     }
 
+    /// Share a public IP geo lookup (used for loopback clients) with other listeners.
+    pub fn with_public_ip_geo(mut self, public_ip_geo: Arc<PublicIpGeo>) -> Self {
+        self.public_ip_geo = public_ip_geo;
+        self
+    }
+
+    /// Refuse queries while `shutdown` is draining (see [`DnsHandler::with_shutdown`]).
+    pub fn with_shutdown(mut self, shutdown: ShutdownController) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+
+    /// Set the socket options used when binding the UDP socket.
+    pub fn with_listen_options(mut self, listen_options: ListenOptions) -> Self {
+        self.listen_options = listen_options;
+        self
+    }
+
+    /// Build the request handler for this server's settings.
+    fn handler(&self) -> DnsHandler {
+        DnsHandler::new(self.proxy_service.clone(), self.config.clone())
+            .with_public_ip_geo(self.public_ip_geo.clone())
+            .with_shutdown(self.shutdown.clone())
+    }
+
     /// Run the DNS server (simplified UDP implementation).
     ///
     /// The error handlers inside the infinite loop are excluded from coverage
@@ -222,25 +667,34 @@ impl DnsServer {
     #[cfg_attr(coverage_nightly, coverage(off))]
     pub async fn run(&self) -> anyhow::Result<()> {
         let addr: SocketAddr = self.listen_addr.parse()?;
-        let socket = UdpSocket::bind(&addr).await?;
+        let socket = self.listen_options.bind_udp(&addr.to_string()).await?;
 
         tracing::info!("DNS server listening on {}", self.listen_addr);
 
-        let mut buf = [0u8; 512];
+        let socket = Arc::new(socket);
+        let handler = Arc::new(self.handler());
+        // EDNS0 clients may send queries larger than 512 bytes
+        let mut buf = vec![0u8; self.config.max_udp_payload.max(MIN_UDP_PAYLOAD) as usize];
 
         loop {
             match socket.recv_from(&mut buf).await {
                 Ok((len, src)) => {
                     let data = buf[..len].to_vec();
-                    let socket_clone = socket.local_addr().ok();
-                    let handler = self.handler.clone();
+                    let local = socket.local_addr().ok();
+                    let handler = handler.clone();
+                    let socket = socket.clone();
 
                     // Handle in background
                     tokio::spawn(async move {
-                        if let Err(e) =
-                            Self::handle_packet(handler, &data, src, socket_clone).await
-                        {
-                            tracing::error!("DNS packet error from {}: {:?}", src, e);
+                        match Self::handle_packet(handler, &data, src, local).await {
+                            Ok(response) => {
+                                if let Err(e) = socket.send_to(&response, src).await {
+                                    tracing::error!("DNS send error to {}: {:?}", src, e);
+                                }
+                            }
+                            Err(e) => {
+                                tracing::error!("DNS packet error from {}: {:?}", src, e);
+                            }
                         }
                     });
                 }
@@ -251,19 +705,20 @@ impl DnsServer {
         }
     }
 
-    /// Handle a DNS packet.
+    /// Handle a DNS packet, returning the encoded response.
+    ///
+    /// The response is truncated (TC set) when it doesn't fit the payload
+    /// size the client advertised, so it can retry over TCP.
     ///
     /// This function is called from within the run() loop and is excluded from
     /// coverage as it's an async network handler.
     #[cfg_attr(coverage_nightly, coverage(off))]
     async fn handle_packet(
-        _handler: Arc<DnsHandler>,
+        handler: Arc<DnsHandler>,
         data: &[u8],
         src: SocketAddr,
         _local: Option<SocketAddr>,
-    ) -> anyhow::Result<()> {
-        // Parse DNS message
-        use hickory_proto::op::Message;
+    ) -> anyhow::Result<Vec<u8>> {
         use hickory_proto::serialize::binary::BinDecodable;
 
         let message = Message::from_bytes(data)?;
@@ -274,12 +729,8 @@ impl DnsServer {
             message.queries().len()
         );
 
-        // For now, just log - full implementation would process and respond
-        for query in message.queries() {
-            tracing::debug!("  Query: {} {}", query.name(), query.query_type());
-        }
-
-        Ok(())
+        let response = handler.respond(&message, src.ip()).await;
+        Ok(encode_udp(response, handler.udp_payload_limit(&message))?)
     }
 }
 
@@ -292,7 +743,7 @@ pub async fn resolve_internal(
     name: &str,
     server_addr: &str,
 ) -> anyhow::Result<Option<Ipv4Addr>> {
-    use hickory_proto::op::{Message, Query};
+    use hickory_proto::op::Query;
     use hickory_proto::serialize::binary::{BinDecodable, BinEncodable};
     use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -347,7 +798,7 @@ mod tests {
     use super::*;
     use crate::adapters::outbound::{DashMapBindingRepository, DashMapMetricsStore};
     use crate::domain::entities::Backend;
-    use crate::domain::ports::BackendRepository;
+    use crate::domain::ports::{BackendRepository, GeoResolver};
     use crate::domain::value_objects::RegionCode;
     use async_trait::async_trait;
 
@@ -393,6 +844,9 @@ mod tests {
             weight: 2,
             soft_limit: 100,
             hard_limit: 150,
+            draining: false,
+            maintenance: false,
+            priority: 0,
         }
     }
 
@@ -413,25 +867,35 @@ mod tests {
     #[test]
     fn test_dns_config_default() {
         let config = DnsConfig::default();
-        assert_eq!(config.domain, "internal");
+        assert_eq!(config.domains, vec!["internal".to_string()]);
         assert_eq!(config.ttl, 30);
+        assert!(!config.servfail_on_unhealthy);
+        assert_eq!(config.max_udp_payload, DEFAULT_MAX_UDP_PAYLOAD);
+        assert!(!config.nxdomain_when_draining);
     }
 
     #[test]
     fn test_dns_config_custom() {
         let config = DnsConfig {
-            domain: "mycompany.local".to_string(),
+            domains: vec!["mycompany.local".to_string()],
             ttl: 60,
+            servfail_on_unhealthy: true,
+            nat64_prefix: None,
+            weighted_rotation: false,
+            wildcard_apps: Vec::new(),
+            max_udp_payload: 4096,
+            nxdomain_when_draining: false,
         };
-        assert_eq!(config.domain, "mycompany.local");
+        assert_eq!(config.domains, vec!["mycompany.local".to_string()]);
         assert_eq!(config.ttl, 60);
+        assert!(config.servfail_on_unhealthy);
     }
 
     #[test]
     fn test_dns_config_clone() {
         let config = DnsConfig::default();
         let cloned = config.clone();
-        assert_eq!(config.domain, cloned.domain);
+        assert_eq!(config.domains, cloned.domains);
         assert_eq!(config.ttl, cloned.ttl);
     }
 
@@ -471,8 +935,8 @@ mod tests {
             create_test_backend("eu-1", "myapp", "10.0.0.1"),
         ]);
         let config = DnsConfig::default();
-        let handler = DnsHandler::new(proxy_service, None, config);
-        assert_eq!(handler.config.domain, "internal");
+        let handler = DnsHandler::new(proxy_service, config);
+        assert_eq!(handler.config.domains, vec!["internal".to_string()]);
     }
 
     #[test]
@@ -483,7 +947,6 @@ mod tests {
         let server = DnsServer::new(
             "0.0.0.0:5353".to_string(),
             proxy_service,
-            None,
             "internal".to_string(),
         );
         assert_eq!(server.listen_addr, "0.0.0.0:5353");
@@ -497,7 +960,6 @@ mod tests {
         let server = DnsServer::new(
             "127.0.0.1:5354".to_string(),
             proxy_service,
-            None,
             "custom.local".to_string(),
         );
         assert_eq!(server.listen_addr, "127.0.0.1:5354");
@@ -509,7 +971,7 @@ mod tests {
             create_test_backend("eu-1", "myapp", "10.50.1.1"),
         ]);
         let config = DnsConfig::default();
-        let handler = DnsHandler::new(proxy_service, None, config);
+        let handler = DnsHandler::new(proxy_service, config);
 
         let name = LowerName::from_str("myapp.internal.").unwrap();
         let client_ip = "192.168.1.1".parse().unwrap();
@@ -519,11 +981,33 @@ mod tests {
         assert_eq!(result.unwrap(), "10.50.1.1".parse::<Ipv4Addr>().unwrap());
     }
 
+    #[tokio::test]
+    async fn test_dns_handler_resolve_is_independent_of_backend_order() {
+        let backends = vec![
+            create_test_backend("eu-3", "myapp", "10.50.1.3"),
+            create_test_backend("eu-1", "myapp", "10.50.1.1"),
+            create_test_backend("eu-2", "myapp", "10.50.1.2"),
+        ];
+        let mut reversed = backends.clone();
+        reversed.reverse();
+
+        let name = LowerName::from_str("myapp.internal.").unwrap();
+        let client_ip = "192.168.1.1".parse().unwrap();
+
+        for backends in [backends, reversed] {
+            let handler = DnsHandler::new(create_proxy_service(backends), DnsConfig::default());
+            for _ in 0..3 {
+                let result = handler.resolve(&name, client_ip).await;
+                assert_eq!(result, Some("10.50.1.1".parse::<Ipv4Addr>().unwrap()));
+            }
+        }
+    }
+
     #[tokio::test]
     async fn test_dns_handler_resolve_no_backend() {
         let proxy_service = create_proxy_service(vec![]); // No backends
         let config = DnsConfig::default();
-        let handler = DnsHandler::new(proxy_service, None, config);
+        let handler = DnsHandler::new(proxy_service, config);
 
         let name = LowerName::from_str("myapp.internal.").unwrap();
         let client_ip = "192.168.1.1".parse().unwrap();
@@ -538,7 +1022,7 @@ mod tests {
             create_test_backend("eu-1", "myapp", "10.50.1.1"),
         ]);
         let config = DnsConfig::default();
-        let handler = DnsHandler::new(proxy_service, None, config);
+        let handler = DnsHandler::new(proxy_service, config);
 
         let name = LowerName::from_str("myapp.external.").unwrap();
         let client_ip = "192.168.1.1".parse().unwrap();
@@ -553,7 +1037,7 @@ mod tests {
             create_test_backend("eu-1", "myapp", "10.50.1.1"),
         ]);
         let config = DnsConfig::default();
-        let handler = DnsHandler::new(proxy_service, None, config);
+        let handler = DnsHandler::new(proxy_service, config);
 
         let name = LowerName::from_str("internal.").unwrap();
         let client_ip = "192.168.1.1".parse().unwrap();
@@ -569,7 +1053,7 @@ mod tests {
             create_test_backend("eu-1", "myapp", "2001:db8::1"),
         ]);
         let config = DnsConfig::default();
-        let handler = DnsHandler::new(proxy_service, None, config);
+        let handler = DnsHandler::new(proxy_service, config);
 
         let name = LowerName::from_str("myapp.internal.").unwrap();
         let client_ip = "192.168.1.1".parse().unwrap();
@@ -579,13 +1063,38 @@ mod tests {
         assert!(result.is_none());
     }
 
+    #[tokio::test]
+    async fn test_dns_handler_resolve_skips_hostname_backends() {
+        let name = LowerName::from_str("myapp.internal.").unwrap();
+        let client_ip = "192.168.1.1".parse().unwrap();
+
+        let handler = DnsHandler::new(
+            create_proxy_service(vec![
+                create_test_backend("eu-0", "myapp", "app-0.internal"),
+                create_test_backend("eu-1", "myapp", "10.50.1.1"),
+            ]),
+            DnsConfig::default(),
+        );
+        for _ in 0..3 {
+            let result = handler.resolve(&name, client_ip).await;
+            assert_eq!(result, Some("10.50.1.1".parse::<Ipv4Addr>().unwrap()));
+        }
+
+        // Only hostname backends: healthy, but nothing to put in an A record
+        let handler = DnsHandler::new(
+            create_proxy_service(vec![create_test_backend("eu-0", "myapp", "app-0.internal")]),
+            DnsConfig::default(),
+        );
+        assert!(handler.resolve(&name, client_ip).await.is_none());
+    }
+
     #[tokio::test]
     async fn test_dns_handler_resolve_localhost_client() {
         let proxy_service = create_proxy_service(vec![
             create_test_backend("eu-1", "myapp", "10.50.1.1"),
         ]);
         let config = DnsConfig::default();
-        let handler = DnsHandler::new(proxy_service, None, config);
+        let handler = DnsHandler::new(proxy_service, config);
 
         let name = LowerName::from_str("myapp.internal.").unwrap();
         let client_ip = "127.0.0.1".parse().unwrap();
@@ -595,6 +1104,57 @@ mod tests {
         assert!(result.is_some());
     }
 
+    #[tokio::test]
+    async fn test_dns_handler_loopback_clients_located_alike() {
+        use crate::domain::entities::GeoInfo;
+
+        let proxy_service = create_proxy_service(vec![
+            create_test_backend("eu-1", "myapp", "10.50.1.1"),
+            Backend {
+                region: RegionCode::NorthAmerica,
+                country: "US".to_string(),
+                ..create_test_backend("us-1", "myapp", "10.50.2.1")
+            },
+        ]);
+        let us = GeoInfo::new("US".to_string(), RegionCode::NorthAmerica);
+        let public_ip_geo = Arc::new(PublicIpGeo::default().with_geo(us));
+        let handler = DnsHandler::new(proxy_service, DnsConfig::default())
+            .with_public_ip_geo(public_ip_geo);
+
+        // Located at this host's public IP (US), whichever loopback family asks
+        let name = LowerName::from_str("myapp.internal.").unwrap();
+        for client_ip in ["127.0.0.1", "::1", "::ffff:127.0.0.1"] {
+            let result = handler.resolve(&name, client_ip.parse().unwrap()).await;
+            assert_eq!(result, Some(Ipv4Addr::new(10, 50, 2, 1)), "{}", client_ip);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dns_server_with_public_ip_geo() {
+        use crate::domain::entities::GeoInfo;
+
+        let proxy_service = create_proxy_service(vec![
+            create_test_backend("eu-1", "myapp", "10.50.1.1"),
+            Backend {
+                region: RegionCode::NorthAmerica,
+                country: "US".to_string(),
+                ..create_test_backend("us-1", "myapp", "10.50.2.1")
+            },
+        ]);
+        let us = GeoInfo::new("US".to_string(), RegionCode::NorthAmerica);
+        let server = DnsServer::new(
+            "127.0.0.1:5357".to_string(),
+            proxy_service,
+            "internal".to_string(),
+        )
+        .with_public_ip_geo(Arc::new(PublicIpGeo::default().with_geo(us)));
+
+        let handler = server.handler();
+        let name = LowerName::from_str("myapp.internal.").unwrap();
+        let result = handler.resolve(&name, "127.0.0.1".parse().unwrap()).await;
+        assert_eq!(result, Some(Ipv4Addr::new(10, 50, 2, 1)));
+    }
+
     #[test]
     fn test_ipv4_parse_valid() {
         let ip = "10.50.1.1";
@@ -628,7 +1188,6 @@ mod tests {
         let server = DnsServer::new(
             listen_addr.to_string(),
             proxy_service,
-            None,
             "internal".to_string(),
         );
 
@@ -665,7 +1224,7 @@ mod tests {
         ]);
 
         let config = DnsConfig::default();
-        let handler = Arc::new(DnsHandler::new(proxy_service, None, config));
+        let handler = Arc::new(DnsHandler::new(proxy_service, config));
 
         // Build a DNS query
         let query = build_dns_query("myapp.internal", 1);
@@ -680,7 +1239,7 @@ mod tests {
     async fn test_handle_packet_invalid_dns() {
         let proxy_service = create_proxy_service(vec![]);
         let config = DnsConfig::default();
-        let handler = Arc::new(DnsHandler::new(proxy_service, None, config));
+        let handler = Arc::new(DnsHandler::new(proxy_service, config));
 
         // Invalid DNS data
         let invalid_data = vec![0u8; 10];
@@ -697,16 +1256,15 @@ mod tests {
             create_test_backend("eu-1", "api", "10.50.1.1"),
         ]);
         let config = DnsConfig::default();
-        let handler = DnsHandler::new(proxy_service, None, config);
+        let handler = DnsHandler::new(proxy_service, config);
 
         // Query for "webapp" but backend is for "api"
         let name = LowerName::from_str("webapp.internal.").unwrap();
         let client_ip: IpAddr = "192.168.1.1".parse().unwrap();
 
-        // Still resolves (returns best backend even if app doesn't match)
+        // Backends of other apps are never returned
         let result = handler.resolve(&name, client_ip).await;
-        // Current implementation returns backend even if app doesn't match
-        assert!(result.is_some());
+        assert!(result.is_none());
     }
 
     #[tokio::test]
@@ -848,7 +1406,6 @@ mod tests {
         let server = DnsServer::new(
             listen_addr.to_string(),
             proxy_service,
-            None,
             "internal".to_string(),
         );
 
@@ -874,29 +1431,22 @@ mod tests {
 
     #[tokio::test]
     async fn test_dns_handler_resolve_with_geo_resolver() {
+        use crate::adapters::outbound::StaticGeoResolver;
         use crate::domain::entities::GeoInfo;
 
-        // Mock geo resolver
-        struct MockGeoResolver {
-            geo_info: Option<GeoInfo>,
-        }
-
-        impl GeoResolver for MockGeoResolver {
-            fn resolve(&self, _ip: IpAddr) -> Option<GeoInfo> {
-                self.geo_info.clone()
-            }
-        }
-
         let proxy_service = create_proxy_service(vec![
             create_test_backend("eu-1", "myapp", "10.50.1.1"),
         ]);
 
-        let geo_resolver: Arc<dyn GeoResolver> = Arc::new(MockGeoResolver {
-            geo_info: Some(GeoInfo::new("DE".to_string(), RegionCode::Europe)),
-        });
+        let geo_resolver: Arc<dyn GeoResolver> = Arc::new(StaticGeoResolver::new(Some(GeoInfo::new(
+            "DE".to_string(),
+            RegionCode::Europe,
+        ))));
+
+        proxy_service.set_geo_resolver(Some(geo_resolver));
 
         let config = DnsConfig::default();
-        let handler = DnsHandler::new(proxy_service, Some(geo_resolver), config);
+        let handler = DnsHandler::new(proxy_service, config);
 
         let name = LowerName::from_str("myapp.internal.").unwrap();
         let client_ip: IpAddr = "8.8.8.8".parse().unwrap(); // Non-loopback
@@ -912,7 +1462,7 @@ mod tests {
         ]);
 
         let config = DnsConfig::default();
-        let handler = Arc::new(DnsHandler::new(proxy_service, None, config));
+        let handler = Arc::new(DnsHandler::new(proxy_service, config));
 
         let query = build_dns_query("myapp.internal", 1);
         let src: SocketAddr = "127.0.0.1:12345".parse().unwrap();
@@ -939,12 +1489,12 @@ mod tests {
     #[tokio::test]
     async fn test_dns_handler_nested_subdomain() {
         let proxy_service = create_proxy_service(vec![
-            create_test_backend("eu-1", "api.v2", "10.50.1.1"),
+            create_test_backend("eu-1", "api.v2.myapp", "10.50.1.1"),
         ]);
         let config = DnsConfig::default();
-        let handler = DnsHandler::new(proxy_service, None, config);
+        let handler = DnsHandler::new(proxy_service, config);
 
-        // Nested subdomain query
+        // Nested subdomain query (the full prefix is the app name)
         let name = LowerName::from_str("api.v2.myapp.internal.").unwrap();
         let client_ip: IpAddr = "192.168.1.1".parse().unwrap();
 
@@ -1057,6 +1607,40 @@ mod tests {
         }
     }
 
+    /// ResponseHandler that keeps the encoded response for inspection
+    #[derive(Clone, Default)]
+    struct CapturingResponseHandler {
+        bytes: Arc<parking_lot::Mutex<Vec<u8>>>,
+    }
+
+    impl CapturingResponseHandler {
+        fn message(&self) -> hickory_proto::op::Message {
+            use hickory_proto::serialize::binary::BinDecodable;
+            hickory_proto::op::Message::from_bytes(&self.bytes.lock()).unwrap()
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl ResponseHandler for CapturingResponseHandler {
+        async fn send_response<'a>(
+            &mut self,
+            response: hickory_server::authority::MessageResponse<
+                '_,
+                'a,
+                impl Iterator<Item = &'a Record> + Send + 'a,
+                impl Iterator<Item = &'a Record> + Send + 'a,
+                impl Iterator<Item = &'a Record> + Send + 'a,
+                impl Iterator<Item = &'a Record> + Send + 'a,
+            >,
+        ) -> io::Result<ResponseInfo> {
+            let mut bytes = Vec::new();
+            let mut encoder = hickory_proto::serialize::binary::BinEncoder::new(&mut bytes);
+            let info = response.destructive_emit(&mut encoder).map_err(io::Error::other)?;
+            *self.bytes.lock() = bytes;
+            Ok(info)
+        }
+    }
+
     /// Create a mock DNS Request for testing
     fn create_mock_request(name: &str, qtype: RecordType) -> Request {
         use hickory_proto::op::Message;
@@ -1092,7 +1676,7 @@ mod tests {
             create_test_backend("eu-1", "myapp", "10.50.1.1"),
         ]);
         let config = DnsConfig::default();
-        let handler = DnsHandler::new(proxy_service, None, config);
+        let handler = DnsHandler::new(proxy_service, config);
 
         let request = create_mock_request("myapp.internal.", RecordType::A);
         let response_handler = MockResponseHandler::new();
@@ -1105,7 +1689,7 @@ mod tests {
     async fn test_request_handler_a_record_query_nxdomain() {
         let proxy_service = create_proxy_service(vec![]); // No backends
         let config = DnsConfig::default();
-        let handler = DnsHandler::new(proxy_service, None, config);
+        let handler = DnsHandler::new(proxy_service, config);
 
         let request = create_mock_request("myapp.internal.", RecordType::A);
         let response_handler = MockResponseHandler::new();
@@ -1120,10 +1704,10 @@ mod tests {
             create_test_backend("eu-1", "myapp", "10.50.1.1"),
         ]);
         let config = DnsConfig::default();
-        let handler = DnsHandler::new(proxy_service, None, config);
+        let handler = DnsHandler::new(proxy_service, config);
 
-        // Query for AAAA record (IPv6)
-        let request = create_mock_request("myapp.internal.", RecordType::AAAA);
+        // Query for CNAME record (unsupported type)
+        let request = create_mock_request("myapp.internal.", RecordType::CNAME);
         let response_handler = MockResponseHandler::new();
 
         let result = handler.handle_request(&request, response_handler).await;
@@ -1136,7 +1720,7 @@ mod tests {
             create_test_backend("eu-1", "myapp", "10.50.1.1"),
         ]);
         let config = DnsConfig::default();
-        let handler = DnsHandler::new(proxy_service, None, config);
+        let handler = DnsHandler::new(proxy_service, config);
 
         // Query for MX record
         let request = create_mock_request("myapp.internal.", RecordType::MX);
@@ -1152,7 +1736,7 @@ mod tests {
             create_test_backend("eu-1", "myapp", "10.50.1.1"),
         ]);
         let config = DnsConfig::default();
-        let handler = DnsHandler::new(proxy_service, None, config);
+        let handler = DnsHandler::new(proxy_service, config);
 
         let request = create_mock_request("myapp.internal.", RecordType::A);
         let response_handler = MockResponseHandler::failing();
@@ -1167,7 +1751,7 @@ mod tests {
     async fn test_request_handler_send_error_on_nxdomain_path() {
         let proxy_service = create_proxy_service(vec![]); // No backends
         let config = DnsConfig::default();
-        let handler = DnsHandler::new(proxy_service, None, config);
+        let handler = DnsHandler::new(proxy_service, config);
 
         let request = create_mock_request("myapp.internal.", RecordType::A);
         let response_handler = MockResponseHandler::failing();
@@ -1184,7 +1768,7 @@ mod tests {
             create_test_backend("eu-1", "myapp", "10.50.1.1"),
         ]);
         let config = DnsConfig::default();
-        let handler = DnsHandler::new(proxy_service, None, config);
+        let handler = DnsHandler::new(proxy_service, config);
 
         let request = create_mock_request("myapp.internal.", RecordType::TXT);
         let response_handler = MockResponseHandler::failing();
@@ -1195,19 +1779,15 @@ mod tests {
         assert!(result.response_code() == ResponseCode::NotImp || result.response_code() == ResponseCode::ServFail);
     }
 
+    // Shares tracing-test's global subscriber, which other tests rely on
     #[tokio::test]
+    #[tracing_test::traced_test]
     async fn test_request_handler_with_tracing() {
-        // Initialize tracing for this test to cover tracing statements
-        let _ = tracing_subscriber::fmt()
-            .with_test_writer()
-            .with_max_level(tracing::Level::DEBUG)
-            .try_init();
-
         let proxy_service = create_proxy_service(vec![
             create_test_backend("eu-1", "myapp", "10.50.1.1"),
         ]);
         let config = DnsConfig::default();
-        let handler = DnsHandler::new(proxy_service, None, config);
+        let handler = DnsHandler::new(proxy_service, config);
 
         let request = create_mock_request("myapp.internal.", RecordType::A);
         let response_handler = MockResponseHandler::new();
@@ -1217,19 +1797,19 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_request_handler_wrong_domain_nxdomain() {
+    async fn test_request_handler_wrong_domain_refused() {
         let proxy_service = create_proxy_service(vec![
             create_test_backend("eu-1", "myapp", "10.50.1.1"),
         ]);
         let config = DnsConfig::default();
-        let handler = DnsHandler::new(proxy_service, None, config);
+        let handler = DnsHandler::new(proxy_service, config);
 
         // Query for wrong domain
         let request = create_mock_request("myapp.external.", RecordType::A);
         let response_handler = MockResponseHandler::new();
 
         let result = handler.handle_request(&request, response_handler).await;
-        assert_eq!(result.response_code(), ResponseCode::NXDomain);
+        assert_eq!(result.response_code(), ResponseCode::Refused);
     }
 
     #[tokio::test]
@@ -1238,7 +1818,7 @@ mod tests {
             create_test_backend("eu-1", "myapp", "10.50.1.1"),
         ]);
         let config = DnsConfig::default();
-        let handler = DnsHandler::new(proxy_service, None, config);
+        let handler = DnsHandler::new(proxy_service, config);
 
         // Query for root internal domain
         let request = create_mock_request("internal.", RecordType::A);
@@ -1254,7 +1834,7 @@ mod tests {
             create_test_backend("eu-1", "myapp", "2001:db8::1"), // IPv6 backend
         ]);
         let config = DnsConfig::default();
-        let handler = DnsHandler::new(proxy_service, None, config);
+        let handler = DnsHandler::new(proxy_service, config);
 
         let request = create_mock_request("myapp.internal.", RecordType::A);
         let response_handler = MockResponseHandler::new();
@@ -1263,4 +1843,1031 @@ mod tests {
         let result = handler.handle_request(&request, response_handler).await;
         assert_eq!(result.response_code(), ResponseCode::NXDomain);
     }
+
+    // ===== Healthy-only Resolution Tests =====
+
+    fn create_unhealthy_backend(id: &str, app: &str, ip: &str) -> Backend {
+        let mut backend = create_test_backend(id, app, ip);
+        backend.healthy = false;
+        backend
+    }
+
+    fn servfail_config() -> DnsConfig {
+        DnsConfig {
+            servfail_on_unhealthy: true,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dns_handler_resolve_only_healthy_backends() {
+        let proxy_service = create_proxy_service(vec![
+            create_unhealthy_backend("eu-down-1", "myapp", "10.50.1.1"),
+            create_test_backend("eu-up-1", "myapp", "10.50.1.2"),
+            create_unhealthy_backend("eu-down-2", "myapp", "10.50.1.3"),
+            create_test_backend("eu-up-2", "myapp", "10.50.1.4"),
+            create_test_backend("other-1", "other", "10.60.1.1"),
+        ]);
+        let handler = DnsHandler::new(proxy_service, DnsConfig::default());
+        let name = LowerName::from_str("myapp.internal.").unwrap();
+
+        let healthy: Vec<Ipv4Addr> = vec!["10.50.1.2".parse().unwrap(), "10.50.1.4".parse().unwrap()];
+        for i in 1..=20u8 {
+            let client_ip: IpAddr = format!("192.168.1.{}", i).parse().unwrap();
+            let ip = handler.resolve(&name, client_ip).await.unwrap();
+            assert!(healthy.contains(&ip), "unexpected address {}", ip);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dns_handler_resolve_ignores_stale_binding_to_unhealthy() {
+        use crate::domain::entities::{Binding, ClientKey};
+        use crate::domain::ports::BindingRepository;
+
+        let backend_repo = Arc::new(MockBackendRepository::new(vec![
+            create_unhealthy_backend("eu-down", "myapp", "10.50.1.1"),
+            create_test_backend("eu-up", "myapp", "10.50.1.2"),
+        ]));
+        let binding_repo = Arc::new(DashMapBindingRepository::new());
+        let client_ip: IpAddr = "192.168.1.1".parse().unwrap();
+        binding_repo
+            .set(ClientKey::new(client_ip), Binding::new("eu-down".to_string()))
+            .await;
+
+        let proxy_service = Arc::new(ProxyService::new(
+            backend_repo,
+            binding_repo,
+            None,
+            Arc::new(DashMapMetricsStore::new()),
+            RegionCode::Europe,
+        ));
+        let handler = DnsHandler::new(proxy_service, DnsConfig::default());
+
+        let name = LowerName::from_str("myapp.internal.").unwrap();
+        let result = handler.resolve(&name, client_ip).await;
+        assert_eq!(result, Some("10.50.1.2".parse().unwrap()));
+    }
+
+    #[tokio::test]
+    async fn test_dns_handler_resolve_query_no_healthy_backends() {
+        let proxy_service = create_proxy_service(vec![
+            create_unhealthy_backend("eu-1", "myapp", "10.50.1.1"),
+            create_test_backend("eu-2", "other", "10.50.1.2"),
+        ]);
+        let handler = DnsHandler::new(proxy_service, DnsConfig::default());
+        let client_ip: IpAddr = "192.168.1.1".parse().unwrap();
+
+        let name = LowerName::from_str("myapp.internal.").unwrap();
+        assert_eq!(
+            handler.resolve_query(&name, client_ip, RecordType::A).await,
+            DnsResolution::NoHealthyBackends
+        );
+
+        let name = LowerName::from_str("myapp.external.").unwrap();
+        assert_eq!(
+            handler.resolve_query(&name, client_ip, RecordType::A).await,
+            DnsResolution::NotAuthoritative
+        );
+    }
+
+    #[tokio::test]
+    async fn test_request_handler_all_unhealthy_nxdomain_by_default() {
+        let proxy_service = create_proxy_service(vec![
+            create_unhealthy_backend("eu-1", "myapp", "10.50.1.1"),
+            create_unhealthy_backend("eu-2", "myapp", "10.50.1.2"),
+        ]);
+        let handler = DnsHandler::new(proxy_service, DnsConfig::default());
+
+        let request = create_mock_request("myapp.internal.", RecordType::A);
+        let result = handler.handle_request(&request, MockResponseHandler::new()).await;
+        assert_eq!(result.response_code(), ResponseCode::NXDomain);
+    }
+
+    #[tokio::test]
+    async fn test_request_handler_all_unhealthy_servfail_when_configured() {
+        let proxy_service = create_proxy_service(vec![
+            create_unhealthy_backend("eu-1", "myapp", "10.50.1.1"),
+            create_test_backend("eu-2", "other", "10.50.1.2"),
+        ]);
+        let handler = DnsHandler::new(proxy_service, servfail_config());
+
+        let request = create_mock_request("myapp.internal.", RecordType::A);
+        let result = handler.handle_request(&request, MockResponseHandler::new()).await;
+        assert_eq!(result.response_code(), ResponseCode::ServFail);
+    }
+
+    #[tokio::test]
+    async fn test_request_handler_servfail_config_refuses_outside_domain() {
+        let proxy_service = create_proxy_service(vec![
+            create_unhealthy_backend("eu-1", "myapp", "10.50.1.1"),
+        ]);
+        let handler = DnsHandler::new(proxy_service, servfail_config());
+
+        let request = create_mock_request("myapp.external.", RecordType::A);
+        let result = handler.handle_request(&request, MockResponseHandler::new()).await;
+        assert_eq!(result.response_code(), ResponseCode::Refused);
+    }
+
+    #[tokio::test]
+    async fn test_request_handler_servfail_config_healthy_still_resolves() {
+        let proxy_service = create_proxy_service(vec![
+            create_unhealthy_backend("eu-1", "myapp", "10.50.1.1"),
+            create_test_backend("eu-2", "myapp", "10.50.1.2"),
+        ]);
+        let handler = DnsHandler::new(proxy_service, servfail_config());
+
+        let request = create_mock_request("myapp.internal.", RecordType::A);
+        let result = handler.handle_request(&request, MockResponseHandler::new()).await;
+        assert_eq!(result.response_code(), ResponseCode::NoError);
+    }
+
+    #[tokio::test]
+    async fn test_request_handler_servfail_send_error() {
+        let proxy_service = create_proxy_service(vec![
+            create_unhealthy_backend("eu-1", "myapp", "10.50.1.1"),
+        ]);
+        let handler = DnsHandler::new(proxy_service, servfail_config());
+
+        let request = create_mock_request("myapp.internal.", RecordType::A);
+        let result = handler.handle_request(&request, MockResponseHandler::failing()).await;
+        assert_eq!(result.response_code(), ResponseCode::ServFail);
+    }
+
+    #[test]
+    fn test_dns_server_with_config() {
+        let proxy_service = create_proxy_service(vec![]);
+        let server = DnsServer::with_config(
+            "127.0.0.1:5355".to_string(),
+            proxy_service,
+            servfail_config(),
+        );
+        assert_eq!(server.listen_addr, "127.0.0.1:5355");
+        assert!(server.config.servfail_on_unhealthy);
+    }
+
+    // ===== AAAA / NAT64 Tests =====
+
+    fn nat64_config() -> DnsConfig {
+        DnsConfig {
+            nat64_prefix: Some("64:ff9b::".parse().unwrap()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_nat64_prefix() {
+        let expected: Ipv6Addr = "64:ff9b::".parse().unwrap();
+        assert_eq!(DnsConfig::parse_nat64_prefix("64:ff9b::").unwrap(), expected);
+        assert_eq!(DnsConfig::parse_nat64_prefix("64:ff9b::/96").unwrap(), expected);
+        assert!(DnsConfig::parse_nat64_prefix("64:ff9b::/64").is_err());
+        assert!(DnsConfig::parse_nat64_prefix("64:ff9b::a32:101").is_err());
+        assert!(DnsConfig::parse_nat64_prefix("not-an-ip").is_err());
+    }
+
+    #[test]
+    fn test_nat64_embedded_ipv4() {
+        let prefix: Ipv6Addr = "64:ff9b::".parse().unwrap();
+
+        let inside: Ipv6Addr = "64:ff9b::a32:101".parse().unwrap();
+        assert_eq!(nat64_embedded_ipv4(&prefix, &inside), Some(Ipv4Addr::new(10, 50, 1, 1)));
+
+        let outside: Ipv6Addr = "2001:db8::a32:101".parse().unwrap();
+        assert_eq!(nat64_embedded_ipv4(&prefix, &outside), None);
+    }
+
+    #[tokio::test]
+    async fn test_dns_handler_ipv6_only_app_nat64_mapped() {
+        let proxy_service = create_proxy_service(vec![
+            create_test_backend("eu-1", "myapp", "64:ff9b::a32:101"),
+        ]);
+        let handler = DnsHandler::new(proxy_service, nat64_config());
+
+        let name = LowerName::from_str("myapp.internal.").unwrap();
+        let result = handler.resolve(&name, "192.168.1.1".parse().unwrap()).await;
+        assert_eq!(result, Some(Ipv4Addr::new(10, 50, 1, 1)));
+    }
+
+    #[tokio::test]
+    async fn test_dns_handler_ipv6_only_app_without_prefix_nxdomain() {
+        let proxy_service = create_proxy_service(vec![
+            create_test_backend("eu-1", "myapp", "64:ff9b::a32:101"),
+        ]);
+        let handler = DnsHandler::new(proxy_service, DnsConfig::default());
+
+        let request = create_mock_request("myapp.internal.", RecordType::A);
+        let result = handler.handle_request(&request, MockResponseHandler::new()).await;
+        assert_eq!(result.response_code(), ResponseCode::NXDomain);
+    }
+
+    #[tokio::test]
+    async fn test_dns_handler_nat64_prefix_mismatch_nxdomain() {
+        let proxy_service = create_proxy_service(vec![
+            create_test_backend("eu-1", "myapp", "2001:db8::1"),
+        ]);
+        let handler = DnsHandler::new(proxy_service, nat64_config());
+
+        let request = create_mock_request("myapp.internal.", RecordType::A);
+        let result = handler.handle_request(&request, MockResponseHandler::new()).await;
+        assert_eq!(result.response_code(), ResponseCode::NXDomain);
+    }
+
+    #[tokio::test]
+    async fn test_dns_handler_nat64_prefers_native_ipv4() {
+        let proxy_service = create_proxy_service(vec![
+            create_test_backend("eu-v6", "myapp", "64:ff9b::a32:101"),
+            create_test_backend("eu-v4", "myapp", "10.50.2.2"),
+        ]);
+        let handler = DnsHandler::new(proxy_service, nat64_config());
+
+        let name = LowerName::from_str("myapp.internal.").unwrap();
+        let result = handler.resolve(&name, "192.168.1.1".parse().unwrap()).await;
+        assert_eq!(result, Some(Ipv4Addr::new(10, 50, 2, 2)));
+    }
+
+    #[tokio::test]
+    async fn test_dns_handler_unhealthy_ipv4_falls_back_to_nat64() {
+        let mut down = create_test_backend("eu-v4", "myapp", "10.50.2.2");
+        down.healthy = false;
+        let proxy_service = create_proxy_service(vec![
+            down,
+            create_test_backend("eu-v6", "myapp", "64:ff9b::a32:101"),
+        ]);
+        let handler = DnsHandler::new(proxy_service, nat64_config());
+
+        let name = LowerName::from_str("myapp.internal.").unwrap();
+        let result = handler.resolve(&name, "192.168.1.1".parse().unwrap()).await;
+        assert_eq!(result, Some(Ipv4Addr::new(10, 50, 1, 1)));
+    }
+
+    #[tokio::test]
+    async fn test_request_handler_aaaa_query_ipv6_backend() {
+        let proxy_service = create_proxy_service(vec![
+            create_test_backend("eu-v4", "myapp", "10.50.1.1"),
+            create_test_backend("eu-v6", "myapp", "2001:db8::1"),
+        ]);
+        let handler = DnsHandler::new(proxy_service, DnsConfig::default());
+
+        let name = LowerName::from_str("myapp.internal.").unwrap();
+        let result = handler
+            .resolve_query(&name, "192.168.1.1".parse().unwrap(), RecordType::AAAA)
+            .await;
+        assert_eq!(result, DnsResolution::Found(vec!["2001:db8::1".parse().unwrap()]));
+
+        let request = create_mock_request("myapp.internal.", RecordType::AAAA);
+        let result = handler.handle_request(&request, MockResponseHandler::new()).await;
+        assert_eq!(result.response_code(), ResponseCode::NoError);
+    }
+
+    #[tokio::test]
+    async fn test_request_handler_aaaa_query_ipv4_only_backend() {
+        let proxy_service = create_proxy_service(vec![
+            create_test_backend("eu-1", "myapp", "10.50.1.1"),
+        ]);
+        let handler = DnsHandler::new(proxy_service, servfail_config());
+
+        // Healthy app without IPv6 addresses: NODATA, not NXDOMAIN nor SERVFAIL
+        let name = LowerName::from_str("myapp.internal.").unwrap();
+        let result = handler
+            .resolve_query(&name, "192.168.1.1".parse().unwrap(), RecordType::AAAA)
+            .await;
+        assert_eq!(result, DnsResolution::NoData);
+
+        let request = create_mock_request("myapp.internal.", RecordType::AAAA);
+        let response_handler = CapturingResponseHandler::default();
+        let result = handler.handle_request(&request, response_handler.clone()).await;
+        assert_eq!(result.response_code(), ResponseCode::NoError);
+        let response = response_handler.message();
+        assert!(response.answers().is_empty());
+        assert!(response.authoritative());
+    }
+
+    // ===== Weighted Rotation Tests =====
+
+    fn weighted_backend(id: &str, ip: &str, weight: u8, region: RegionCode) -> Backend {
+        Backend {
+            weight,
+            region,
+            ..create_test_backend(id, "myapp", ip)
+        }
+    }
+
+    fn rotation_config() -> DnsConfig {
+        DnsConfig {
+            weighted_rotation: true,
+            ..Default::default()
+        }
+    }
+
+    async fn resolve_all(handler: &DnsHandler) -> Vec<IpAddr> {
+        let name = LowerName::from_str("myapp.internal.").unwrap();
+        match handler
+            .resolve_query(&name, "192.168.1.1".parse().unwrap(), RecordType::A)
+            .await
+        {
+            DnsResolution::Found(ips) => ips,
+            other => panic!("expected Found, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_weighted_rotation_first_record_follows_weights() {
+        let proxy_service = create_proxy_service(vec![
+            weighted_backend("eu-1", "10.50.1.1", 6, RegionCode::Europe),
+            weighted_backend("eu-2", "10.50.1.2", 3, RegionCode::Europe),
+            weighted_backend("eu-3", "10.50.1.3", 1, RegionCode::Europe),
+        ]);
+        let handler = DnsHandler::new(proxy_service, rotation_config());
+
+        let mut firsts: HashMap<IpAddr, usize> = HashMap::new();
+        for _ in 0..1000 {
+            let ips = resolve_all(&handler).await;
+            assert_eq!(ips.len(), 3);
+            *firsts.entry(ips[0]).or_default() += 1;
+        }
+
+        let share = |ip: &str| firsts[&ip.parse::<IpAddr>().unwrap()] as f64 / 1000.0;
+        assert!((share("10.50.1.1") - 0.6).abs() < 0.02);
+        assert!((share("10.50.1.2") - 0.3).abs() < 0.02);
+        assert!((share("10.50.1.3") - 0.1).abs() < 0.02);
+    }
+
+    #[tokio::test]
+    async fn test_weighted_rotation_stays_in_selected_region() {
+        let proxy_service = create_proxy_service(vec![
+            weighted_backend("eu-1", "10.50.1.1", 2, RegionCode::Europe),
+            weighted_backend("eu-2", "10.50.1.2", 2, RegionCode::Europe),
+            weighted_backend("us-1", "10.50.2.1", 9, RegionCode::NorthAmerica),
+            weighted_backend("eu-v6", "2001:db8::1", 2, RegionCode::Europe),
+            Backend {
+                draining: true,
+                maintenance: false,
+                priority: 0,
+                ..weighted_backend("eu-3", "10.50.1.3", 2, RegionCode::Europe)
+            },
+        ]);
+        let handler = DnsHandler::new(proxy_service, rotation_config());
+
+        for _ in 0..4 {
+            let mut ips = resolve_all(&handler).await;
+            ips.sort();
+            let expected: Vec<IpAddr> = vec!["10.50.1.1".parse().unwrap(), "10.50.1.2".parse().unwrap()];
+            assert_eq!(ips, expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_weighted_rotation_leaves_out_zero_weight() {
+        let proxy_service = create_proxy_service(vec![
+            weighted_backend("eu-1", "10.50.1.1", 2, RegionCode::Europe),
+            weighted_backend("eu-2", "10.50.1.2", 0, RegionCode::Europe),
+        ]);
+        let handler = DnsHandler::new(proxy_service, rotation_config());
+
+        for _ in 0..3 {
+            assert_eq!(resolve_all(&handler).await, vec!["10.50.1.1".parse::<IpAddr>().unwrap()]);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_weighted_rotation_disabled_returns_single_record() {
+        let proxy_service = create_proxy_service(vec![
+            weighted_backend("eu-1", "10.50.1.1", 2, RegionCode::Europe),
+            weighted_backend("eu-2", "10.50.1.2", 2, RegionCode::Europe),
+        ]);
+        let handler = DnsHandler::new(proxy_service, DnsConfig::default());
+
+        for _ in 0..3 {
+            assert_eq!(resolve_all(&handler).await, vec!["10.50.1.1".parse::<IpAddr>().unwrap()]);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_weighted_rotation_rotations_are_per_app() {
+        let proxy_service = create_proxy_service(vec![
+            weighted_backend("eu-1", "10.50.1.1", 1, RegionCode::Europe),
+            weighted_backend("eu-2", "10.50.1.2", 1, RegionCode::Europe),
+            create_test_backend("other-1", "other", "10.60.1.1"),
+        ]);
+        let handler = DnsHandler::new(proxy_service, rotation_config());
+        let other = LowerName::from_str("other.internal.").unwrap();
+        let client_ip: IpAddr = "192.168.1.1".parse().unwrap();
+
+        let first = resolve_all(&handler).await[0];
+        // Queries for another app don't advance this app's rotation
+        handler.resolve_query(&other, client_ip, RecordType::A).await;
+        let second = resolve_all(&handler).await[0];
+        assert_ne!(first, second);
+        assert_eq!(handler.rotations.lock().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_weighted_rotation_answers_all_records() {
+        let proxy_service = create_proxy_service(vec![
+            weighted_backend("eu-1", "10.50.1.1", 2, RegionCode::Europe),
+            weighted_backend("eu-2", "10.50.1.2", 2, RegionCode::Europe),
+        ]);
+        let handler = DnsHandler::new(proxy_service, rotation_config());
+        let capture = CapturingResponseHandler::default();
+
+        let request = create_mock_request("myapp.internal.", RecordType::A);
+        handler.handle_request(&request, capture.clone()).await;
+        let response = capture.message();
+
+        assert_eq!(response.response_code(), ResponseCode::NoError);
+        let mut ips: Vec<Ipv4Addr> = response
+            .answers()
+            .iter()
+            .filter_map(|r| match r.data() {
+                Some(RData::A(a)) => Some(a.0),
+                _ => None,
+            })
+            .collect();
+        ips.sort();
+        assert_eq!(ips, ["10.50.1.1".parse::<Ipv4Addr>().unwrap(), "10.50.1.2".parse().unwrap()]);
+    }
+
+    // ===== Wildcard App Tests =====
+
+    fn wildcard_config(apps: &[&str]) -> DnsConfig {
+        DnsConfig {
+            wildcard_apps: apps.iter().map(|a| a.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn parsed_app(handler: &DnsHandler, name: &str) -> Option<Option<String>> {
+        handler.parse_app_name(&LowerName::from_str(name).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_wildcard_subdomains_resolve_to_app() {
+        let proxy_service = create_proxy_service(vec![
+            create_test_backend("eu-1", "myapp", "10.50.1.1"),
+            create_test_backend("eu-2", "other", "10.50.2.1"),
+        ]);
+        let handler = DnsHandler::new(proxy_service, wildcard_config(&["myapp"]));
+        let client_ip = "192.168.1.1".parse().unwrap();
+
+        for name in ["a.myapp.internal.", "b.myapp.internal.", "myapp.internal."] {
+            let name = LowerName::from_str(name).unwrap();
+            assert_eq!(
+                handler.resolve(&name, client_ip).await,
+                Some("10.50.1.1".parse().unwrap()),
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_wildcard_disabled_keeps_full_label() {
+        let proxy_service = create_proxy_service(vec![
+            create_test_backend("eu-1", "myapp", "10.50.1.1"),
+        ]);
+        let handler = DnsHandler::new(proxy_service, DnsConfig::default());
+
+        assert_eq!(parsed_app(&handler, "a.myapp.internal."), Some(Some("a.myapp".to_string())));
+        let name = LowerName::from_str("a.myapp.internal.").unwrap();
+        assert_eq!(handler.resolve(&name, "192.168.1.1".parse().unwrap()).await, None);
+    }
+
+    #[test]
+    fn test_wildcard_app_matching() {
+        let proxy_service = create_proxy_service(vec![]);
+        let handler = DnsHandler::new(proxy_service, wildcard_config(&["myapp", "api.myapp"]));
+
+        // Any depth, longest wildcard wins
+        assert_eq!(parsed_app(&handler, "x.y.myapp.internal."), Some(Some("myapp".to_string())));
+        assert_eq!(parsed_app(&handler, "v1.api.myapp.internal."), Some(Some("api.myapp".to_string())));
+        // Only whole labels match
+        assert_eq!(parsed_app(&handler, "notmyapp.internal."), Some(Some("notmyapp".to_string())));
+        // Other apps and out-of-domain names are untouched
+        assert_eq!(parsed_app(&handler, "a.other.internal."), Some(Some("a.other".to_string())));
+        assert_eq!(parsed_app(&handler, "a.myapp.example."), None);
+    }
+
+    // ===== Multiple Domain Tests =====
+
+    fn multi_domain_handler(domains: &[&str]) -> DnsHandler {
+        let proxy_service = create_proxy_service(vec![
+            create_test_backend("eu-1", "myapp", "10.50.1.1"),
+            create_test_backend("eu-2", "api", "10.50.2.1"),
+        ]);
+        let config = DnsConfig {
+            domains: domains.iter().map(|d| d.to_string()).collect(),
+            ..Default::default()
+        };
+        DnsHandler::new(proxy_service, config)
+    }
+
+    #[test]
+    fn test_multiple_domains_extract_app() {
+        let handler = multi_domain_handler(&["internal", "svc.cluster.local"]);
+
+        assert_eq!(parsed_app(&handler, "myapp.internal."), Some(Some("myapp".to_string())));
+        assert_eq!(parsed_app(&handler, "myapp.svc.cluster.local."), Some(Some("myapp".to_string())));
+        assert_eq!(parsed_app(&handler, "v1.api.svc.cluster.local."), Some(Some("v1.api".to_string())));
+        assert_eq!(parsed_app(&handler, "internal."), Some(None));
+        assert_eq!(parsed_app(&handler, "svc.cluster.local."), Some(None));
+        // Under none of them
+        assert_eq!(parsed_app(&handler, "myapp.cluster.local."), None);
+        assert_eq!(parsed_app(&handler, "myappinternal."), None);
+    }
+
+    #[test]
+    fn test_multiple_domains_longest_suffix_wins() {
+        // Order doesn't matter: the most specific domain is stripped
+        for domains in [["cluster.local", "svc.cluster.local"], ["svc.cluster.local", "cluster.local"]] {
+            let handler = multi_domain_handler(&domains);
+            assert_eq!(parsed_app(&handler, "api.svc.cluster.local."), Some(Some("api".to_string())));
+            assert_eq!(parsed_app(&handler, "api.cluster.local."), Some(Some("api".to_string())));
+            // A bare domain is the domain itself, not an app under a shorter one
+            assert_eq!(parsed_app(&handler, "svc.cluster.local."), Some(None));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_multiple_domains_resolve_and_refuse() {
+        let handler = multi_domain_handler(&["internal", "svc.cluster.local"]);
+        let client_ip = "192.168.1.1".parse().unwrap();
+
+        for name in ["api.internal.", "api.svc.cluster.local."] {
+            let name = LowerName::from_str(name).unwrap();
+            assert_eq!(
+                handler.resolve_query(&name, client_ip, RecordType::A).await,
+                DnsResolution::Found(vec!["10.50.2.1".parse().unwrap()]),
+                "{}",
+                name
+            );
+        }
+        let foreign = LowerName::from_str("api.example.com.").unwrap();
+        assert_eq!(
+            handler.resolve_query(&foreign, client_ip, RecordType::A).await,
+            DnsResolution::NotAuthoritative
+        );
+        let unknown = LowerName::from_str("nope.svc.cluster.local.").unwrap();
+        assert_eq!(
+            handler.resolve_query(&unknown, client_ip, RecordType::A).await,
+            DnsResolution::NoHealthyBackends
+        );
+    }
+
+    // ===== SRV Tests =====
+
+    fn tiered_backend(id: &str, ip: &str, priority: u16, weight: u8) -> Backend {
+        Backend {
+            priority,
+            weight,
+            ..create_test_backend(id, "myapp", ip)
+        }
+    }
+
+    async fn srv_response(handler: &DnsHandler, name: &str) -> Message {
+        let mut request = Message::new();
+        request.set_id(42).set_message_type(MessageType::Query).set_op_code(OpCode::Query);
+        request.add_query(hickory_proto::op::Query::query(Name::from_str(name).unwrap(), RecordType::SRV));
+        handler.respond(&request, "192.168.1.1".parse().unwrap()).await
+    }
+
+    /// (priority, weight, port, target) of each SRV answer, in answer order.
+    fn srv_answers(response: &Message) -> Vec<(u16, u16, u16, String)> {
+        response
+            .answers()
+            .iter()
+            .map(|record| match record.data() {
+                Some(RData::SRV(srv)) => (srv.priority(), srv.weight(), srv.port(), srv.target().to_string()),
+                other => panic!("expected SRV, got {:?}", other),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_srv_owner_strips_service_labels() {
+        let owner = |name: &str| srv_owner(&LowerName::from_str(name).unwrap()).to_string();
+        assert_eq!(owner("_http._tcp.myapp.internal."), "myapp.internal.");
+        assert_eq!(owner("myapp.internal."), "myapp.internal.");
+        assert_eq!(owner("_grpc.internal."), "internal.");
+    }
+
+    #[tokio::test]
+    async fn test_srv_priority_tiers_and_weights() {
+        let handler = DnsHandler::new(
+            create_proxy_service(vec![
+                tiered_backend("eu-2", "10.50.1.2", 0, 1),
+                tiered_backend("eu-1", "10.50.1.1", 0, 3),
+                tiered_backend("us-1", "10.50.2.1", 1, 5),
+                create_test_backend("other-1", "otherapp", "10.50.3.1"),
+            ]),
+            DnsConfig::default(),
+        );
+
+        let response = srv_response(&handler, "_http._tcp.myapp.internal.").await;
+        assert_eq!(response.response_code(), ResponseCode::NoError);
+        assert!(response.authoritative());
+        assert_eq!(
+            srv_answers(&response),
+            vec![
+                (0, 3, 8080, "eu-1.myapp.internal.".to_string()),
+                (0, 1, 8080, "eu-2.myapp.internal.".to_string()),
+                (1, 5, 8080, "us-1.myapp.internal.".to_string()),
+            ]
+        );
+        for answer in response.answers() {
+            assert_eq!(answer.name().to_string(), "_http._tcp.myapp.internal.");
+        }
+
+        // Targets resolve through the additional section
+        let glue: Vec<(String, Option<&RData>)> = response
+            .additionals()
+            .iter()
+            .map(|r| (r.name().to_string(), r.data()))
+            .collect();
+        assert_eq!(glue.len(), 3);
+        assert!(glue.contains(&(
+            "us-1.myapp.internal.".to_string(),
+            Some(&RData::A(A("10.50.2.1".parse().unwrap())))
+        )));
+    }
+
+    #[tokio::test]
+    async fn test_srv_unhealthy_tier_drops_out() {
+        let mut down = tiered_backend("eu-1", "10.50.1.1", 0, 2);
+        down.healthy = false;
+        let mut draining = tiered_backend("eu-2", "10.50.1.2", 0, 2);
+        draining.draining = true;
+        let handler = DnsHandler::new(
+            create_proxy_service(vec![down, draining, tiered_backend("us-1", "10.50.2.1", 1, 4)]),
+            DnsConfig::default(),
+        );
+
+        let response = srv_response(&handler, "myapp.internal.").await;
+        assert_eq!(
+            srv_answers(&response),
+            vec![(1, 4, 8080, "us-1.myapp.internal.".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_srv_hostname_backend_targets_its_hostname() {
+        let handler = DnsHandler::new(
+            create_proxy_service(vec![tiered_backend("eu-1", "node1.example.com", 2, 7)]),
+            DnsConfig::default(),
+        );
+
+        let response = srv_response(&handler, "myapp.internal.").await;
+        assert_eq!(
+            srv_answers(&response),
+            vec![(2, 7, 8080, "node1.example.com.".to_string())]
+        );
+        assert!(response.additionals().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_srv_unknown_app_and_foreign_domain() {
+        let handler = DnsHandler::new(
+            create_proxy_service(vec![tiered_backend("eu-1", "10.50.1.1", 0, 2)]),
+            DnsConfig::default(),
+        );
+
+        let response = srv_response(&handler, "_http._tcp.nope.internal.").await;
+        assert_eq!(response.response_code(), ResponseCode::NXDomain);
+        assert!(response.answers().is_empty());
+
+        let response = srv_response(&handler, "_http._tcp.myapp.example.com.").await;
+        assert_eq!(response.response_code(), ResponseCode::Refused);
+    }
+
+    // ===== DNS Query Metrics Tests =====
+
+    fn create_proxy_service_with_metrics(
+        backends: Vec<Backend>,
+    ) -> (Arc<ProxyService>, Arc<DashMapMetricsStore>) {
+        let metrics = Arc::new(DashMapMetricsStore::new());
+        let proxy_service = Arc::new(ProxyService::new(
+            Arc::new(MockBackendRepository::new(backends)),
+            Arc::new(DashMapBindingRepository::new()),
+            None,
+            metrics.clone(),
+            RegionCode::Europe,
+        ));
+        (proxy_service, metrics)
+    }
+
+    #[tokio::test]
+    async fn test_request_handler_records_query_outcomes() {
+        use crate::domain::ports::MetricsStore;
+
+        let (proxy_service, metrics) = create_proxy_service_with_metrics(vec![
+            create_test_backend("eu-1", "myapp", "10.50.1.1"),
+        ]);
+        let handler = DnsHandler::new(proxy_service, DnsConfig::default());
+
+        for (name, qtype) in [
+            ("myapp.internal.", RecordType::A),
+            ("myapp.internal.", RecordType::A),
+            ("otherapp.internal.", RecordType::A),
+            ("myapp.internal.", RecordType::MX),
+        ] {
+            let request = create_mock_request(name, qtype);
+            handler.handle_request(&request, MockResponseHandler::new()).await;
+        }
+
+        assert_eq!(metrics.get_dns_query_count("myapp", DnsQueryOutcome::NoError), 2);
+        assert_eq!(metrics.get_dns_query_count("myapp", DnsQueryOutcome::NotImp), 1);
+        assert_eq!(metrics.get_dns_query_count("myapp", DnsQueryOutcome::NxDomain), 0);
+        assert_eq!(metrics.get_dns_query_count("otherapp", DnsQueryOutcome::NxDomain), 1);
+    }
+
+    #[tokio::test]
+    async fn test_missing_address_family_selects_once() {
+        use crate::domain::ports::MetricsStore;
+
+        let (proxy_service, metrics) = create_proxy_service_with_metrics(vec![
+            create_test_backend("eu-1", "myapp", "fd00::1"),
+        ]);
+        let handler = DnsHandler::new(proxy_service, servfail_config());
+        let name = LowerName::from_str("myapp.internal.").unwrap();
+        let client_ip: IpAddr = "192.168.1.1".parse().unwrap();
+
+        // Healthy, just no IPv4 address: NXDOMAIN, not SERVFAIL
+        assert_eq!(
+            handler.resolve_query(&name, client_ip, RecordType::A).await,
+            DnsResolution::NotFound
+        );
+        assert_eq!(metrics.get_geo_unavailable(), 1);
+
+        let request = create_mock_request("myapp.internal.", RecordType::A);
+        let result = handler.handle_request(&request, MockResponseHandler::new()).await;
+        assert_eq!(result.response_code(), ResponseCode::NXDomain);
+        assert_eq!(metrics.get_dns_query_count("myapp", DnsQueryOutcome::NxDomain), 1);
+        assert_eq!(metrics.get_geo_unavailable(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_request_handler_records_servfail_outcome() {
+        use crate::domain::ports::MetricsStore;
+
+        let (proxy_service, metrics) = create_proxy_service_with_metrics(vec![
+            create_unhealthy_backend("eu-1", "myapp", "10.50.1.1"),
+        ]);
+        let handler = DnsHandler::new(proxy_service, servfail_config());
+
+        let request = create_mock_request("myapp.internal.", RecordType::A);
+        handler.handle_request(&request, MockResponseHandler::new()).await;
+
+        assert_eq!(metrics.get_dns_query_count("myapp", DnsQueryOutcome::ServFail), 1);
+    }
+
+    #[tokio::test]
+    async fn test_request_handler_metrics_label_root_and_foreign_domain() {
+        use crate::domain::ports::MetricsStore;
+
+        let (proxy_service, metrics) = create_proxy_service_with_metrics(vec![
+            create_test_backend("eu-1", "myapp", "10.50.1.1"),
+        ]);
+        let handler = DnsHandler::new(proxy_service, DnsConfig::default());
+
+        let request = create_mock_request("internal.", RecordType::A);
+        handler.handle_request(&request, MockResponseHandler::new()).await;
+        let request = create_mock_request("example.com.", RecordType::A);
+        handler.handle_request(&request, MockResponseHandler::new()).await;
+
+        assert_eq!(metrics.get_dns_query_count("-", DnsQueryOutcome::NoError), 1);
+        assert_eq!(metrics.get_dns_query_count("-", DnsQueryOutcome::Refused), 1);
+    }
+
+    #[tokio::test]
+    async fn test_request_handler_refused_outside_domain_nxdomain_inside() {
+        let proxy_service = create_proxy_service(vec![
+            create_test_backend("eu-1", "myapp", "10.50.1.1"),
+        ]);
+        let handler = DnsHandler::new(proxy_service, DnsConfig::default());
+
+        // Supported type, but not our domain: not authoritative
+        for query_type in [RecordType::A, RecordType::AAAA] {
+            let request = create_mock_request("myapp.example.com.", query_type);
+            let result = handler.handle_request(&request, MockResponseHandler::new()).await;
+            assert_eq!(result.response_code(), ResponseCode::Refused);
+        }
+
+        // Our domain, but no backend for the app
+        let request = create_mock_request("unknown.internal.", RecordType::A);
+        let result = handler.handle_request(&request, MockResponseHandler::new()).await;
+        assert_eq!(result.response_code(), ResponseCode::NXDomain);
+    }
+
+    #[tokio::test]
+    async fn test_request_handler_refuses_while_draining() {
+        use crate::domain::ports::MetricsStore;
+
+        let (proxy_service, metrics) = create_proxy_service_with_metrics(vec![
+            create_test_backend("eu-1", "myapp", "10.50.1.1"),
+        ]);
+        let shutdown = ShutdownController::new();
+        let handler = DnsHandler::new(proxy_service, DnsConfig::default())
+            .with_shutdown(shutdown.clone());
+        let query = || create_mock_request("myapp.internal.", RecordType::A);
+
+        let result = handler.handle_request(&query(), MockResponseHandler::new()).await;
+        assert_eq!(result.response_code(), ResponseCode::NoError);
+
+        shutdown.drain();
+        let result = handler.handle_request(&query(), MockResponseHandler::new()).await;
+        assert_eq!(result.response_code(), ResponseCode::Refused);
+        let response = srv_response(&handler, "_http._tcp.myapp.internal.").await;
+        assert_eq!(response.response_code(), ResponseCode::Refused);
+        assert!(response.answers().is_empty());
+        assert!(!response.authoritative());
+        assert_eq!(metrics.get_dns_query_count("myapp", DnsQueryOutcome::Refused), 1);
+
+        shutdown.undrain();
+        let result = handler.handle_request(&query(), MockResponseHandler::new()).await;
+        assert_eq!(result.response_code(), ResponseCode::NoError);
+    }
+
+    #[tokio::test]
+    async fn test_request_handler_nxdomain_while_draining() {
+        let shutdown = ShutdownController::new();
+        let config = DnsConfig {
+            nxdomain_when_draining: true,
+            ..Default::default()
+        };
+        let handler = DnsHandler::new(
+            create_proxy_service(vec![create_test_backend("eu-1", "myapp", "10.50.1.1")]),
+            config,
+        )
+        .with_shutdown(shutdown.clone());
+
+        shutdown.drain();
+        let request = create_mock_request("myapp.internal.", RecordType::A);
+        let result = handler.handle_request(&request, MockResponseHandler::new()).await;
+        assert_eq!(result.response_code(), ResponseCode::NXDomain);
+    }
+
+    #[tokio::test]
+    async fn test_dns_server_with_shutdown() {
+        let shutdown = ShutdownController::new();
+        let server = DnsServer::new(
+            "127.0.0.1:5356".to_string(),
+            create_proxy_service(vec![create_test_backend("eu-1", "myapp", "10.50.1.1")]),
+            "internal".to_string(),
+        )
+        .with_shutdown(shutdown.clone());
+
+        // The handler the server runs with follows the shared drain state
+        let handler = Arc::new(server.handler());
+        let query = || create_mock_request("myapp.internal.", RecordType::A);
+        let result = handler.handle_request(&query(), MockResponseHandler::new()).await;
+        assert_eq!(result.response_code(), ResponseCode::NoError);
+
+        shutdown.drain();
+        let result = handler.handle_request(&query(), MockResponseHandler::new()).await;
+        assert_eq!(result.response_code(), ResponseCode::Refused);
+
+        // Setting it again while the handler is shared doesn't panic
+        let server = server.with_shutdown(ShutdownController::new());
+        assert!(!server.handler().shutdown.is_draining());
+    }
+
+    #[test]
+    fn test_parse_app_name() {
+        let handler = DnsHandler::new(create_proxy_service(vec![]), DnsConfig::default());
+
+        let name = |s: &str| LowerName::from_str(s).unwrap();
+        assert_eq!(handler.parse_app_name(&name("myapp.internal.")), Some(Some("myapp".to_string())));
+        assert_eq!(handler.parse_app_name(&name("internal.")), Some(None));
+        assert_eq!(handler.parse_app_name(&name("example.com.")), None);
+    }
+
+    // ===== UDP Response Size Tests =====
+
+    /// An app with more IPv4 backends than fit in a 512-byte answer.
+    fn many_backends_handler(config: DnsConfig) -> Arc<DnsHandler> {
+        let backends = (1..=40)
+            .map(|i| create_test_backend(&format!("eu-{}", i), "myapp", &format!("10.50.1.{}", i)))
+            .collect();
+        Arc::new(DnsHandler::new(create_proxy_service(backends), config))
+    }
+
+    fn build_edns_query(name: &str, id: u16, max_payload: u16, padding: usize) -> Vec<u8> {
+        use hickory_proto::rr::rdata::opt::EdnsOption;
+        use hickory_proto::serialize::binary::{BinDecodable, BinEncodable};
+
+        let mut message = Message::from_bytes(&build_dns_query(name, id)).unwrap();
+        let mut edns = Edns::new();
+        edns.set_max_payload(max_payload);
+        if padding > 0 {
+            edns.options_mut().insert(EdnsOption::Unknown(12, vec![0; padding]));
+        }
+        message.set_edns(edns);
+        message.to_bytes().unwrap()
+    }
+
+    async fn udp_response(handler: Arc<DnsHandler>, query: &[u8]) -> (usize, Message) {
+        use hickory_proto::serialize::binary::BinDecodable;
+
+        let src: SocketAddr = "192.168.1.1:5353".parse().unwrap();
+        let bytes = DnsServer::handle_packet(handler, query, src, None).await.unwrap();
+        (bytes.len(), Message::from_bytes(&bytes).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_udp_response_truncated_without_edns() {
+        let handler = many_backends_handler(rotation_config());
+
+        let (len, response) = udp_response(handler, &build_dns_query("myapp.internal", 7)).await;
+        assert!(len <= 512);
+        assert_eq!(response.id(), 7);
+        assert_eq!(response.response_code(), ResponseCode::NoError);
+        assert!(response.truncated());
+        assert!(response.answers().is_empty());
+        assert_eq!(response.queries().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_udp_response_uses_edns_payload_size() {
+        let handler = many_backends_handler(rotation_config());
+
+        let query = build_edns_query("myapp.internal", 8, 4096, 0);
+        let (len, response) = udp_response(handler, &query).await;
+        assert!(len > 512 && len <= DEFAULT_MAX_UDP_PAYLOAD as usize);
+        assert!(!response.truncated());
+        assert_eq!(response.answers().len(), 40);
+        // We advertise our own cap back
+        assert_eq!(response.max_payload(), DEFAULT_MAX_UDP_PAYLOAD);
+    }
+
+    #[tokio::test]
+    async fn test_udp_response_capped_at_max_udp_payload() {
+        let handler = many_backends_handler(DnsConfig {
+            max_udp_payload: 512,
+            ..rotation_config()
+        });
+
+        // The client could take it, but we won't send more than 512 bytes
+        let query = build_edns_query("myapp.internal", 9, 4096, 0);
+        let (len, response) = udp_response(handler, &query).await;
+        assert!(len <= 512);
+        assert!(response.truncated());
+        assert!(response.answers().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_udp_response_small_answer_not_truncated() {
+        let handler = Arc::new(DnsHandler::new(
+            create_proxy_service(vec![create_test_backend("eu-1", "myapp", "10.50.1.1")]),
+            DnsConfig::default(),
+        ));
+
+        let (_, response) = udp_response(handler.clone(), &build_dns_query("myapp.internal", 10)).await;
+        assert!(!response.truncated());
+        assert!(response.authoritative());
+        assert_eq!(response.answers().len(), 1);
+        // No OPT record for clients that didn't send one
+        assert!(response.extensions().is_none());
+
+        let (_, response) = udp_response(handler, &build_dns_query("unknown.internal", 11)).await;
+        assert_eq!(response.response_code(), ResponseCode::NXDomain);
+    }
+
+    #[tokio::test]
+    async fn test_run_answers_edns_query_larger_than_512_bytes() {
+        use hickory_proto::serialize::binary::BinDecodable;
+        use std::time::Duration;
+
+        let proxy_service = create_proxy_service(vec![
+            create_test_backend("eu-1", "myapp", "10.50.1.1"),
+        ]);
+
+        let temp_socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let listen_addr = temp_socket.local_addr().unwrap();
+        drop(temp_socket);
+
+        let server = DnsServer::with_config(
+            listen_addr.to_string(),
+            proxy_service,
+            DnsConfig::default(),
+        );
+        let server_handle = tokio::spawn(async move {
+            let _ = server.run().await;
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let query = build_edns_query("myapp.internal", 12, 4096, 700);
+        assert!(query.len() > 512);
+
+        let client_socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        client_socket.send_to(&query, listen_addr).await.unwrap();
+
+        let mut buf = [0u8; 4096];
+        let (len, _) = tokio::time::timeout(Duration::from_secs(2), client_socket.recv_from(&mut buf))
+            .await
+            .expect("no response to a query larger than 512 bytes")
+            .unwrap();
+        let response = Message::from_bytes(&buf[..len]).unwrap();
+        assert_eq!(response.id(), 12);
+        assert_eq!(response.answers().len(), 1);
+
+        server_handle.abort();
+    }
 }
@@ -0,0 +1,707 @@
+//! HTTP Server Adapter (L7 Host Routing)
+//!
+//! Terminates plaintext HTTP/1.1 and HTTP/2 (h2c with prior knowledge) and
+//! routes each request by the host it is addressed to: the `Host` header,
+//! or `:authority` in HTTP/2. Hosts map to apps through the same table as
+//! CONNECT mode ([`ConnectConfig::app_for`]).
+//!
+//! Requests are routed one by one, so a keep-alive connection (or an HTTP/2
+//! connection carrying several hosts) can reach several apps. Within a client
+//! connection, requests for the same app reuse one backend connection and
+//! stay pinned to that backend while the connection is up.
+
+use super::connect::{ConnectConfig, BACKEND_HEADER};
+use super::dial::DialOptions;
+use super::listener::ListenOptions;
+use super::public_ip::PublicIpGeo;
+use crate::application::{ProxyService, Unavailable};
+use crate::domain::entities::{Backend, GeoInfo};
+use crate::domain::value_objects::CloseReason;
+use crate::infrastructure::ShutdownController;
+use axum::body::Body;
+use hyper::body::Incoming;
+use hyper::client::conn::http1::SendRequest;
+use hyper::header::{
+    HeaderMap, HeaderName, HeaderValue, CONNECTION, CONTENT_LENGTH, HOST, RETRY_AFTER,
+};
+use hyper::{Request, Response, StatusCode, Uri, Version};
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::net::TcpStream;
+use tokio::sync::Mutex;
+
+/// Headers that only apply to a single hop and aren't forwarded.
+const HOP_BY_HOP: &[&str] = &[
+    "connection",
+    "keep-alive",
+    "proxy-connection",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+/// Header carrying the client address chain to the backend.
+const FORWARDED_FOR: &str = "x-forwarded-for";
+
+/// HTTP Server - inbound adapter routing plaintext HTTP by host.
+///
+/// Unlike the TCP listener, which picks a backend once per connection,
+/// every request is mapped host -> app and gets a backend of that app.
+/// Client bindings aren't used: as with CONNECT and DNS, the app decides.
+pub struct HttpServer {
+    proxy_service: Arc<ProxyService>,
+    listen_addr: String,
+    public_ip_geo: Arc<PublicIpGeo>,
+    routing: Arc<ConnectConfig>,
+    listen_options: ListenOptions,
+    dial_options: DialOptions,
+    shutdown: ShutdownController,
+}
+
+impl HttpServer {
+    /// Create a new HTTP server mapping hosts to apps with `routing`.
+    pub fn new(
+        proxy_service: Arc<ProxyService>,
+        listen_addr: String,
+        routing: ConnectConfig,
+    ) -> Self {
+        Self {
+            proxy_service,
+            listen_addr,
+            public_ip_geo: Arc::new(PublicIpGeo::default()),
+            routing: Arc::new(routing),
+            listen_options: ListenOptions::default(),
+            dial_options: DialOptions::default(),
+            shutdown: ShutdownController::new(),
+        }
+    }
+
+    /// Set the socket options used when binding the listener.
+    pub fn with_listen_options(mut self, listen_options: ListenOptions) -> Self {
+        self.listen_options = listen_options;
+        self
+    }
+
+    /// Set the socket options used when connecting to backends.
+    pub fn with_dial_options(mut self, dial_options: DialOptions) -> Self {
+        self.dial_options = dial_options;
+        self
+    }
+
+    /// Share a public IP geo lookup (used for loopback clients) with other listeners.
+    pub fn with_public_ip_geo(mut self, public_ip_geo: Arc<PublicIpGeo>) -> Self {
+        self.public_ip_geo = public_ip_geo;
+        self
+    }
+
+    /// Refuse new connections while `shutdown` is draining, and count active ones on it.
+    pub fn with_shutdown(mut self, shutdown: ShutdownController) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+
+    /// Run the HTTP server.
+    ///
+    /// Binds the listen address, then serves each connection on its own
+    /// task, speaking HTTP/1.1 or HTTP/2 as the client does.
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    pub async fn run(&self) -> anyhow::Result<()> {
+        let listener = self.listen_options.bind_tcp(&self.listen_addr).await?;
+        tracing::info!("HTTP host routing listening on {}", self.listen_addr);
+
+        loop {
+            let (stream, addr) = listener.accept().await?;
+            if self.shutdown.is_draining() {
+                tracing::debug!("draining, refusing HTTP connection from {}", addr);
+                let _ = stream.set_zero_linger();
+                continue;
+            }
+            let client_ip = addr.ip();
+
+            let service = self.proxy_service.clone();
+            let public_ip_geo = self.public_ip_geo.clone();
+            let routing = self.routing.clone();
+            let dial_options = self.dial_options.clone();
+
+            self.shutdown.spawn_connection(async move {
+                // Loopback clients (IPv4 or IPv6) are located per the loopback policy
+                let client_geo = public_ip_geo.client_geo(&service, client_ip).await;
+                let session = Arc::new(Session {
+                    service,
+                    routing,
+                    dial_options,
+                    client_ip,
+                    client_geo,
+                    upstreams: Mutex::new(HashMap::new()),
+                    client_closed: Arc::new(AtomicBool::new(false)),
+                });
+                session.serve(stream).await;
+            });
+        }
+    }
+}
+
+/// One client connection and the backend connections opened for it.
+struct Session {
+    service: Arc<ProxyService>,
+    routing: Arc<ConnectConfig>,
+    dial_options: DialOptions,
+    client_ip: IpAddr,
+    client_geo: Option<GeoInfo>,
+    /// Idle backend connection per app; taken out while a request uses it
+    upstreams: Mutex<HashMap<String, Upstream>>,
+    /// Set once the client connection is done, telling close reasons apart
+    client_closed: Arc<AtomicBool>,
+}
+
+/// An HTTP/1.1 connection to a backend.
+struct Upstream {
+    backend_id: String,
+    sender: SendRequest<Incoming>,
+}
+
+impl Session {
+    /// Serve the client connection until it closes.
+    async fn serve(self: Arc<Self>, stream: TcpStream) {
+        let session = self.clone();
+        let service = hyper::service::service_fn(move |req| {
+            let session = session.clone();
+            async move { Ok::<_, Infallible>(session.route(req).await) }
+        });
+
+        if let Err(e) = hyper_util::server::conn::auto::Builder::new(TokioExecutor::new())
+            .serve_connection(TokioIo::new(stream), service)
+            .await
+        {
+            tracing::debug!("HTTP connection from {} failed: {:?}", self.client_ip, e);
+        }
+
+        // Dropping the idle senders closes their backend connections
+        self.client_closed.store(true, Ordering::Relaxed);
+        self.upstreams.lock().await.clear();
+    }
+
+    /// Route one request to a backend of its host's app.
+    async fn route(&self, mut req: Request<Incoming>) -> Response<Body> {
+        let Some(host) = request_host(&req) else {
+            tracing::debug!("HTTP request without host from {}", self.client_ip);
+            return status_response(StatusCode::BAD_REQUEST);
+        };
+        let Some(app) = self.routing.app_for(&host) else {
+            tracing::debug!(
+                "HTTP request for unknown host {} from {}",
+                host,
+                self.client_ip
+            );
+            return status_response(StatusCode::NOT_FOUND);
+        };
+
+        let mut upstream = match self.upstream_for(&app).await {
+            Ok(upstream) => upstream,
+            Err(response) => return response,
+        };
+
+        prepare_request(&mut req, self.client_ip);
+        let backend_id = upstream.backend_id.clone();
+        match upstream.sender.send_request(req).await {
+            Ok(response) => {
+                // Ready for the next request to this app once the body is read
+                self.upstreams.lock().await.entry(app).or_insert(upstream);
+                self.backend_response(response, &backend_id)
+            }
+            Err(e) => {
+                tracing::warn!("HTTP request to backend {} failed: {:?}", backend_id, e);
+                status_response(StatusCode::BAD_GATEWAY)
+            }
+        }
+    }
+
+    /// The pinned backend connection for `app`, or a new one to a freshly
+    /// selected backend if there is none or it was closed.
+    async fn upstream_for(&self, app: &str) -> Result<Upstream, Response<Body>> {
+        let idle = self.upstreams.lock().await.remove(app);
+        if let Some(mut upstream) = idle {
+            if upstream.sender.ready().await.is_ok() {
+                return Ok(upstream);
+            }
+        }
+
+        let Some(backend) = self
+            .service
+            .select_connection_backend(Some(app), self.client_geo.as_ref())
+            .await
+        else {
+            return Err(match self.service.unavailable(Some(app)).await {
+                Unavailable::Overloaded => {
+                    tracing::warn!(
+                        "all backends of app {} at hard limit ({})",
+                        app,
+                        self.client_ip
+                    );
+                    let mut response = status_response(StatusCode::SERVICE_UNAVAILABLE);
+                    response
+                        .headers_mut()
+                        .insert(RETRY_AFTER, HeaderValue::from_static("1"));
+                    response
+                }
+                Unavailable::NoBackend => {
+                    tracing::warn!("no backend available for app {} ({})", app, self.client_ip);
+                    status_response(StatusCode::SERVICE_UNAVAILABLE)
+                }
+            });
+        };
+
+        self.connect(&backend)
+            .await
+            .map_err(|_| status_response(StatusCode::BAD_GATEWAY))
+    }
+
+    /// Open an HTTP/1.1 connection to `backend`.
+    ///
+    /// The connection counts as one backend connection in the metrics,
+    /// however many requests it carries.
+    async fn connect(&self, backend: &Backend) -> anyhow::Result<Upstream> {
+        let backend_addr = backend.addr();
+
+        let (stream, timings) = self
+            .dial_options
+            .connect_timed(&backend_addr)
+            .await
+            .map_err(|e| {
+                tracing::error!(
+                    "failed to connect to backend {} at {}: {:?}",
+                    backend.id,
+                    backend_addr,
+                    e
+                );
+                e
+            })?;
+        let (sender, connection) =
+            hyper::client::conn::http1::handshake(TokioIo::new(stream)).await?;
+
+        tracing::debug!(
+            "HTTP {} -> {} ({})",
+            self.client_ip,
+            backend.id,
+            backend_addr
+        );
+        self.service
+            .record_connection_start(&backend.id, &backend.app);
+        timings.record(&self.service, &backend.id);
+
+        let service = self.service.clone();
+        let backend_id = backend.id.clone();
+        let client_closed = self.client_closed.clone();
+        tokio::spawn(async move {
+            let reason = match connection.await {
+                Ok(()) if client_closed.load(Ordering::Relaxed) => CloseReason::ClientClosed,
+                Ok(()) => CloseReason::BackendClosed,
+                Err(e) => {
+                    tracing::trace!("backend {} HTTP connection error: {:?}", backend_id, e);
+                    CloseReason::Error
+                }
+            };
+            service.record_connection_end(&backend_id, reason);
+        });
+
+        Ok(Upstream {
+            backend_id: backend.id.clone(),
+            sender,
+        })
+    }
+
+    /// Pass a backend response on to the client.
+    fn backend_response(
+        &self,
+        mut response: Response<Incoming>,
+        backend_id: &str,
+    ) -> Response<Body> {
+        strip_hop_by_hop(response.headers_mut());
+        if self.routing.expose_backend {
+            let id: String = backend_id.chars().filter(|c| !c.is_control()).collect();
+            if let (Ok(name), Ok(value)) = (
+                HeaderName::from_bytes(BACKEND_HEADER.as_bytes()),
+                HeaderValue::from_str(&id),
+            ) {
+                response.headers_mut().insert(name, value);
+            }
+        }
+        response.map(Body::new)
+    }
+}
+
+/// Host a request is addressed to, without port.
+///
+/// HTTP/2 requests and absolute-form HTTP/1 targets carry it in the URI
+/// authority, which wins over the `Host` header.
+fn request_host<B>(req: &Request<B>) -> Option<String> {
+    let host = match req.uri().authority() {
+        Some(authority) => authority.host().to_string(),
+        None => {
+            let header = req.headers().get(HOST)?.to_str().ok()?;
+            header
+                .parse::<hyper::http::uri::Authority>()
+                .ok()?
+                .host()
+                .to_string()
+        }
+    };
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    (!host.is_empty()).then(|| host.to_string())
+}
+
+/// Rewrite a client request for an HTTP/1.1 backend.
+///
+/// The target becomes origin-form with the host in a `Host` header,
+/// hop-by-hop headers are dropped and the client address is appended to
+/// `X-Forwarded-For`.
+fn prepare_request<B>(req: &mut Request<B>, client_ip: IpAddr) {
+    if let Some(authority) = req.uri().authority().cloned() {
+        if !req.headers().contains_key(HOST) {
+            if let Ok(value) = HeaderValue::from_str(authority.as_str()) {
+                req.headers_mut().insert(HOST, value);
+            }
+        }
+        let path = req.uri().path_and_query().map_or("/", |p| p.as_str());
+        *req.uri_mut() = path.parse().unwrap_or_else(|_| Uri::from_static("/"));
+    }
+    *req.version_mut() = Version::HTTP_11;
+    strip_hop_by_hop(req.headers_mut());
+
+    let forwarded_for = match req
+        .headers()
+        .get(FORWARDED_FOR)
+        .and_then(|v| v.to_str().ok())
+    {
+        Some(chain) => format!("{}, {}", chain, client_ip),
+        None => client_ip.to_string(),
+    };
+    if let Ok(value) = HeaderValue::from_str(&forwarded_for) {
+        req.headers_mut().insert(FORWARDED_FOR, value);
+    }
+}
+
+/// Remove hop-by-hop headers, including any the `Connection` header names.
+fn strip_hop_by_hop(headers: &mut HeaderMap) {
+    let named: Vec<String> = headers
+        .get_all(CONNECTION)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(|name| name.trim().to_lowercase())
+        .filter(|name| !name.is_empty())
+        .collect();
+
+    for name in HOP_BY_HOP
+        .iter()
+        .copied()
+        .chain(named.iter().map(String::as_str))
+    {
+        headers.remove(name);
+    }
+}
+
+/// Empty response with `status`, generated by the proxy itself.
+fn status_response(status: StatusCode) -> Response<Body> {
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = status;
+    response
+        .headers_mut()
+        .insert(CONTENT_LENGTH, HeaderValue::from_static("0"));
+    response
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::adapters::outbound::{DashMapBindingRepository, DashMapMetricsStore};
+    use crate::domain::ports::{BackendRepository, MetricsStore};
+    use crate::domain::value_objects::RegionCode;
+    use async_trait::async_trait;
+    use bytes::Bytes;
+    use http_body_util::{BodyExt, Empty};
+    use std::time::Duration;
+    use tokio::net::TcpListener;
+
+    struct MockBackendRepository {
+        backends: Vec<Backend>,
+    }
+
+    #[async_trait]
+    impl BackendRepository for MockBackendRepository {
+        async fn get_all(&self) -> Vec<Backend> {
+            self.backends.clone()
+        }
+
+        async fn get_by_id(&self, id: &str) -> Option<Backend> {
+            self.backends.iter().find(|b| b.id == id).cloned()
+        }
+
+        async fn get_healthy(&self) -> Vec<Backend> {
+            self.backends
+                .iter()
+                .filter(|b| b.healthy)
+                .cloned()
+                .collect()
+        }
+
+        async fn get_version(&self) -> u64 {
+            1
+        }
+    }
+
+    fn backend(id: &str, app: &str, port: u16) -> Backend {
+        Backend {
+            id: id.to_string(),
+            app: app.to_string(),
+            region: RegionCode::Europe,
+            country: "DE".to_string(),
+            wg_ip: "127.0.0.1".to_string(),
+            port,
+            healthy: true,
+            weight: 2,
+            soft_limit: 100,
+            hard_limit: 150,
+            draining: false,
+            maintenance: false,
+            priority: 0,
+        }
+    }
+
+    /// An HTTP/1.1 backend answering every request with `<id> <Host header>`.
+    async fn start_backend(id: &'static str) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let service =
+                        hyper::service::service_fn(move |req: Request<Incoming>| async move {
+                            let host = req
+                                .headers()
+                                .get(HOST)
+                                .and_then(|h| h.to_str().ok())
+                                .unwrap_or_default()
+                                .to_string();
+                            Ok::<_, Infallible>(Response::new(Body::from(format!(
+                                "{} {}",
+                                id, host
+                            ))))
+                        });
+                    let _ = hyper::server::conn::http1::Builder::new()
+                        .serve_connection(TokioIo::new(stream), service)
+                        .await;
+                });
+            }
+        });
+        port
+    }
+
+    /// Start a server routing `web.internal`/`www.example.com` to a `web`
+    /// backend and `api.internal` to an `api` backend.
+    async fn start_server(
+        expose_backend: bool,
+    ) -> (std::net::SocketAddr, Arc<DashMapMetricsStore>) {
+        let web = backend("web-1", "web", start_backend("web-1").await);
+        let api = backend("api-1", "api", start_backend("api-1").await);
+        let metrics = Arc::new(DashMapMetricsStore::new());
+        let proxy_service = Arc::new(ProxyService::new(
+            Arc::new(MockBackendRepository {
+                backends: vec![web, api],
+            }),
+            Arc::new(DashMapBindingRepository::new()),
+            None,
+            metrics.clone(),
+            RegionCode::Europe,
+        ));
+
+        let routing = ConnectConfig {
+            hosts: HashMap::from([("www.example.com".to_string(), "web".to_string())]),
+            expose_backend,
+            ..ConnectConfig::default()
+        };
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+
+        let server = HttpServer::new(proxy_service, addr.to_string(), routing);
+        tokio::spawn(async move {
+            let _ = server.run().await;
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        (addr, metrics)
+    }
+
+    async fn http1_client(addr: std::net::SocketAddr) -> SendRequest<Empty<Bytes>> {
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let (sender, connection) = hyper::client::conn::http1::handshake(TokioIo::new(stream))
+            .await
+            .unwrap();
+        tokio::spawn(connection);
+        sender
+    }
+
+    async fn get(sender: &mut SendRequest<Empty<Bytes>>, host: &str) -> (StatusCode, String) {
+        sender.ready().await.unwrap();
+        let req = Request::get("/path?q=1")
+            .header(HOST, host)
+            .body(Empty::new())
+            .unwrap();
+        let response = sender.send_request(req).await.unwrap();
+        let status = response.status();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        (status, String::from_utf8_lossy(&body).to_string())
+    }
+
+    // ===== Host Parsing Tests =====
+
+    #[test]
+    fn test_request_host_from_header() {
+        let req = Request::get("/")
+            .header(HOST, "Web.Internal:8080")
+            .body(())
+            .unwrap();
+        assert_eq!(request_host(&req).as_deref(), Some("Web.Internal"));
+    }
+
+    #[test]
+    fn test_request_host_prefers_uri_authority() {
+        let req = Request::get("http://api.internal/x")
+            .header(HOST, "web.internal")
+            .body(())
+            .unwrap();
+        assert_eq!(request_host(&req).as_deref(), Some("api.internal"));
+
+        let req = Request::get("http://[::1]:8080/").body(()).unwrap();
+        assert_eq!(request_host(&req).as_deref(), Some("::1"));
+    }
+
+    #[test]
+    fn test_request_host_missing() {
+        let req = Request::get("/").body(()).unwrap();
+        assert_eq!(request_host(&req), None);
+    }
+
+    #[test]
+    fn test_prepare_request_for_backend() {
+        let mut req = Request::get("http://api.internal/v1?x=2")
+            .version(Version::HTTP_2)
+            .header("connection", "keep-alive, x-hop")
+            .header("x-hop", "1")
+            .header("keep-alive", "timeout=5")
+            .header(FORWARDED_FOR, "192.0.2.1")
+            .header("x-kept", "yes")
+            .body(())
+            .unwrap();
+
+        prepare_request(&mut req, "10.0.0.9".parse().unwrap());
+
+        assert_eq!(req.uri(), "/v1?x=2");
+        assert_eq!(req.version(), Version::HTTP_11);
+        assert_eq!(req.headers()[HOST], "api.internal");
+        assert_eq!(req.headers()[FORWARDED_FOR], "192.0.2.1, 10.0.0.9");
+        assert_eq!(req.headers()["x-kept"], "yes");
+        for name in ["connection", "keep-alive", "x-hop"] {
+            assert!(!req.headers().contains_key(name), "{} was forwarded", name);
+        }
+    }
+
+    // ===== Routing Tests =====
+
+    #[tokio::test]
+    async fn test_routes_each_keep_alive_request_by_host() {
+        let (addr, _) = start_server(false).await;
+        let mut client = http1_client(addr).await;
+
+        assert_eq!(
+            get(&mut client, "web.internal").await,
+            (StatusCode::OK, "web-1 web.internal".to_string())
+        );
+        assert_eq!(
+            get(&mut client, "api.internal:8080").await,
+            (StatusCode::OK, "api-1 api.internal:8080".to_string())
+        );
+        // Explicit host table entry, on the same connection
+        assert_eq!(
+            get(&mut client, "www.example.com").await,
+            (StatusCode::OK, "web-1 www.example.com".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_keep_alive_requests_reuse_backend_connection() {
+        let (addr, metrics) = start_server(false).await;
+        let mut client = http1_client(addr).await;
+
+        for _ in 0..3 {
+            assert_eq!(get(&mut client, "web.internal").await.0, StatusCode::OK);
+        }
+        // One backend connection carried all three requests
+        assert_eq!(metrics.get_backend_selected("web", "web-1"), 1);
+        assert_eq!(metrics.get_connection_count("web-1"), 1);
+
+        drop(client);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(metrics.get_connection_count("web-1"), 0);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_host_and_missing_backend() {
+        let (addr, _) = start_server(false).await;
+        let mut client = http1_client(addr).await;
+
+        assert_eq!(
+            get(&mut client, "example.org").await.0,
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            get(&mut client, "db.internal").await.0,
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+        // The connection is still usable afterwards
+        assert_eq!(get(&mut client, "api.internal").await.0, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_expose_backend_header() {
+        let (addr, _) = start_server(true).await;
+        let mut client = http1_client(addr).await;
+        client.ready().await.unwrap();
+
+        let req = Request::get("/")
+            .header(HOST, "api.internal")
+            .body(Empty::new())
+            .unwrap();
+        let response = client.send_request(req).await.unwrap();
+        assert_eq!(response.headers()[BACKEND_HEADER], "api-1");
+    }
+
+    #[tokio::test]
+    async fn test_routes_http2_requests_by_authority() {
+        let (addr, _) = start_server(false).await;
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let (mut client, connection) =
+            hyper::client::conn::http2::handshake(TokioExecutor::new(), TokioIo::new(stream))
+                .await
+                .unwrap();
+        tokio::spawn(connection);
+
+        for (uri, expected) in [
+            ("http://api.internal/", "api-1 api.internal"),
+            ("http://web.internal/", "web-1 web.internal"),
+        ] {
+            let req = Request::get(uri).body(Empty::<Bytes>::new()).unwrap();
+            let response = client.send_request(req).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            assert_eq!(body, expected);
+        }
+    }
+}
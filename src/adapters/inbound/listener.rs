@@ -0,0 +1,282 @@
+//! Listener Socket Options
+//!
+//! Builds the TCP and UDP sockets inbound adapters listen on, applying
+//! socket options that tokio's plain `bind` doesn't expose.
+
+use socket2::{Domain, Protocol, SockRef, Socket, Type};
+use std::io;
+use std::net::SocketAddr;
+use tokio::net::{TcpListener, UdpSocket};
+
+/// Pending connection queue length (same as tokio's `TcpListener::bind`).
+pub const DEFAULT_LISTEN_BACKLOG: u32 = 1024;
+
+/// `SO_RCVBUF`/`SO_SNDBUF` sizes for TCP sockets, in bytes.
+///
+/// Zero keeps the OS default (and its autotuning). The OS may round or cap
+/// the request: Linux doubles it and caps it at `net.core.rmem_max` /
+/// `net.core.wmem_max`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SocketBuffers {
+    pub recv: usize,
+    pub send: usize,
+}
+
+impl SocketBuffers {
+    /// Set the configured sizes on `socket`.
+    ///
+    /// Call before connecting or listening, so the TCP window scale
+    /// negotiated in the handshake accounts for them.
+    pub fn apply(&self, socket: SockRef<'_>) -> io::Result<()> {
+        if self.recv > 0 {
+            socket.set_recv_buffer_size(self.recv)?;
+        }
+        if self.send > 0 {
+            socket.set_send_buffer_size(self.send)?;
+        }
+        Ok(())
+    }
+
+    /// Whether any size is set.
+    pub fn is_set(&self) -> bool {
+        self.recv > 0 || self.send > 0
+    }
+}
+
+/// Socket options applied when binding a listener.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ListenOptions {
+    /// Set `SO_REUSEPORT` so a new process can bind the same port
+    /// and start accepting before the old one exits (Unix only).
+    pub reuse_port: bool,
+    /// Buffer sizes for TCP listeners, inherited by accepted connections
+    pub buffers: SocketBuffers,
+    /// Pending connection queue length for TCP listeners
+    /// (0 = `DEFAULT_LISTEN_BACKLOG`).
+    ///
+    /// The OS silently caps it: Linux at `net.core.somaxconn` (4096 by
+    /// default since 5.4), macOS and the BSDs at `kern.ipc.somaxconn`
+    /// (128 by default). Raising it past the cap needs that sysctl raised too.
+    pub backlog: u32,
+}
+
+impl ListenOptions {
+    /// Bind a TCP listener on `addr` (host names are resolved).
+    pub async fn bind_tcp(&self, addr: &str) -> io::Result<TcpListener> {
+        let addr = resolve(addr).await?;
+        let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
+
+        // Matches tokio's default so restarts don't hit TIME_WAIT
+        #[cfg(unix)]
+        socket.set_reuse_address(true)?;
+        self.apply(&socket)?;
+        self.buffers.apply(SockRef::from(&socket))?;
+
+        socket.set_nonblocking(true)?;
+        socket.bind(&addr.into())?;
+        socket.listen(self.listen_backlog())?;
+
+        TcpListener::from_std(socket.into())
+    }
+
+    /// Bind a UDP socket on `addr` (host names are resolved).
+    pub async fn bind_udp(&self, addr: &str) -> io::Result<UdpSocket> {
+        let addr = resolve(addr).await?;
+        let socket = Socket::new(Domain::for_address(addr), Type::DGRAM, Some(Protocol::UDP))?;
+
+        self.apply(&socket)?;
+
+        socket.set_nonblocking(true)?;
+        socket.bind(&addr.into())?;
+
+        UdpSocket::from_std(socket.into())
+    }
+
+    /// Backlog passed to `listen(2)`.
+    fn listen_backlog(&self) -> i32 {
+        let backlog = match self.backlog {
+            0 => DEFAULT_LISTEN_BACKLOG,
+            n => n,
+        };
+        i32::try_from(backlog).unwrap_or(i32::MAX)
+    }
+
+    /// Apply the configured options to an unbound socket.
+    fn apply(&self, socket: &Socket) -> io::Result<()> {
+        if self.reuse_port {
+            #[cfg(unix)]
+            socket.set_reuse_port(true)?;
+            #[cfg(not(unix))]
+            tracing::warn!("SO_REUSEPORT is not supported on this platform, ignoring");
+        }
+        Ok(())
+    }
+}
+
+/// Resolve a listen address to the first socket address it maps to.
+async fn resolve(addr: &str) -> io::Result<SocketAddr> {
+    tokio::net::lookup_host(addr).await?.next().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("listen address {} did not resolve", addr),
+        )
+    })
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_options() {
+        let options = ListenOptions::default();
+        assert!(!options.reuse_port);
+        assert!(!options.buffers.is_set());
+        assert_eq!(options.backlog, 0);
+        assert_eq!(options.listen_backlog(), DEFAULT_LISTEN_BACKLOG as i32);
+    }
+
+    #[test]
+    fn test_listen_backlog_clamped_to_i32() {
+        let options = ListenOptions {
+            backlog: u32::MAX,
+            ..Default::default()
+        };
+        assert_eq!(options.listen_backlog(), i32::MAX);
+    }
+
+    /// Accept queue limit of a listening socket, which Linux reports in
+    /// `tcpi_sacked` of `TCP_INFO`.
+    #[cfg(target_os = "linux")]
+    fn max_accept_backlog(listener: &TcpListener) -> u32 {
+        use std::os::fd::AsRawFd;
+
+        // SAFETY: tcp_info is plain integers, so all-zero is a valid value
+        let mut info: libc::tcp_info = unsafe { std::mem::zeroed() };
+        let mut len = std::mem::size_of::<libc::tcp_info>() as libc::socklen_t;
+        // SAFETY: valid fd, and `info`/`len` describe a writable tcp_info
+        let rc = unsafe {
+            libc::getsockopt(
+                listener.as_raw_fd(),
+                libc::IPPROTO_TCP,
+                libc::TCP_INFO,
+                &mut info as *mut _ as *mut libc::c_void,
+                &mut len,
+            )
+        };
+        assert_eq!(rc, 0, "getsockopt: {}", io::Error::last_os_error());
+        info.tcpi_sacked
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_bind_tcp_uses_requested_backlog() {
+        let options = ListenOptions {
+            backlog: 37,
+            ..Default::default()
+        };
+        let listener = options.bind_tcp("127.0.0.1:0").await.unwrap();
+        assert_eq!(max_accept_backlog(&listener), 37);
+
+        // Default stays at tokio's value (below any stock somaxconn)
+        let listener = ListenOptions::default().bind_tcp("127.0.0.1:0").await.unwrap();
+        assert_eq!(max_accept_backlog(&listener), DEFAULT_LISTEN_BACKLOG);
+    }
+
+    #[tokio::test]
+    async fn test_bind_tcp_accepts_connections() {
+        let listener = ListenOptions::default().bind_tcp("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let connect = tokio::spawn(async move { tokio::net::TcpStream::connect(addr).await });
+        assert!(listener.accept().await.is_ok());
+        assert!(connect.await.unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_bind_tcp_resolves_host_names() {
+        let listener = ListenOptions::default().bind_tcp("localhost:0").await.unwrap();
+        assert!(listener.local_addr().unwrap().ip().is_loopback());
+    }
+
+    #[tokio::test]
+    async fn test_bind_tcp_invalid_address() {
+        assert!(ListenOptions::default().bind_tcp("not-an-address").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_bind_tcp_same_port_without_reuse_port_fails() {
+        let options = ListenOptions::default();
+        let first = options.bind_tcp("127.0.0.1:0").await.unwrap();
+        let addr = first.local_addr().unwrap().to_string();
+
+        assert!(options.bind_tcp(&addr).await.is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_bind_tcp_same_port_with_reuse_port() {
+        let options = ListenOptions {
+            reuse_port: true,
+            ..Default::default()
+        };
+        let first = options.bind_tcp("127.0.0.1:0").await.unwrap();
+        let addr = first.local_addr().unwrap();
+
+        let second = options.bind_tcp(&addr.to_string()).await.unwrap();
+        assert_eq!(second.local_addr().unwrap(), addr);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_bind_udp_same_port_with_reuse_port() {
+        let options = ListenOptions {
+            reuse_port: true,
+            ..Default::default()
+        };
+        let first = options.bind_udp("127.0.0.1:0").await.unwrap();
+        let addr = first.local_addr().unwrap();
+
+        let second = options.bind_udp(&addr.to_string()).await.unwrap();
+        assert_eq!(second.local_addr().unwrap(), addr);
+    }
+
+    // Linux doubles the requested size to leave room for bookkeeping
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_accepted_connections_inherit_buffer_sizes() {
+        let options = ListenOptions {
+            buffers: SocketBuffers {
+                recv: 32 * 1024,
+                send: 48 * 1024,
+            },
+            ..Default::default()
+        };
+        let listener = options.bind_tcp("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let _client = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let (accepted, _) = listener.accept().await.unwrap();
+
+        let socket = SockRef::from(&accepted);
+        assert_eq!(socket.recv_buffer_size().unwrap(), 2 * 32 * 1024);
+        assert_eq!(socket.send_buffer_size().unwrap(), 2 * 48 * 1024);
+    }
+
+    #[tokio::test]
+    async fn test_zero_buffer_sizes_keep_os_default() {
+        let plain = tokio::net::TcpSocket::new_v4().unwrap();
+        let configured = tokio::net::TcpSocket::new_v4().unwrap();
+        SocketBuffers::default().apply(SockRef::from(&configured)).unwrap();
+
+        assert_eq!(
+            SockRef::from(&configured).recv_buffer_size().unwrap(),
+            SockRef::from(&plain).recv_buffer_size().unwrap()
+        );
+        assert_eq!(
+            SockRef::from(&configured).send_buffer_size().unwrap(),
+            SockRef::from(&plain).send_buffer_size().unwrap()
+        );
+    }
+}
@@ -1,15 +1,27 @@
 mod api_server;
+mod connect;
+mod dial;
 mod dns_server;
+mod http_server;
+mod listener;
+mod public_ip;
 mod tcp_server;
 mod tls_server;
+mod udp_server;
 
 pub use api_server::ApiServer;
+pub use connect::ConnectConfig;
+pub use dial::{ConnectPressure, DialOptions, PressureAlert, ResolveCache};
 pub use dns_server::DnsServer;
+pub use http_server::HttpServer;
+pub use listener::{ListenOptions, SocketBuffers, DEFAULT_LISTEN_BACKLOG};
+pub use public_ip::{LoopbackGeo, PublicIpGeo};
 pub use tcp_server::TcpServer;
-pub use tls_server::{TlsConfig, TlsServer};
+pub use tls_server::{KeyAlgorithm, SelfSignedParams, TlsConfig, TlsServer};
+pub use udp_server::{UdpProxyServer, DEFAULT_UDP_SESSION_TIMEOUT};
 
 // Re-export for external use (e.g., integration tests)
 #[allow(unused_imports)]
-pub use api_server::{ApiState, RegisterRequest};
+pub use api_server::{ApiState, RegisterOutcome, RegisterRequest};
 #[allow(unused_imports)]
 pub use dns_server::{DnsConfig, DnsHandler};
@@ -0,0 +1,475 @@
+//! Public IP Geo Lookup
+//!
+//! Loopback clients carry no location of their own, so their geo comes
+//! from this host's public IP, as reported by a [`PublicIpProvider`]. The
+//! lookup is shared: concurrent loopback connections wait on one in-flight
+//! request, a success is cached until the service's geo resolver is
+//! swapped and a failure is remembered for a short while, so the connection
+//! path never pays for more than one slow lookup at a time.
+//!
+//! Every listener locates clients through [`PublicIpGeo::client_geo`], so
+//! `127.0.0.1`, `::1` and IPv4-mapped loopback follow the same
+//! [`LoopbackGeo`] policy on TCP, TLS, HTTP and DNS alike.
+
+use crate::adapters::outbound::HttpPublicIpProvider;
+use crate::application::ProxyService;
+use crate::domain::entities::GeoInfo;
+use crate::domain::ports::{GeoResolver, PublicIpProvider};
+use std::net::IpAddr;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// How long a failed lookup is remembered before the next attempt.
+const DEFAULT_NEGATIVE_TTL: Duration = Duration::from_secs(30);
+
+/// Where loopback clients are located.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LoopbackGeo {
+    /// At this host's public IP
+    #[default]
+    PublicIp,
+    /// Nowhere: routed as if their geo were unknown
+    Unknown,
+}
+
+impl FromStr for LoopbackGeo {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "public-ip" | "public_ip" => Ok(Self::PublicIp),
+            "none" | "unknown" => Ok(Self::Unknown),
+            other => anyhow::bail!("unknown loopback geo policy {:?} (expected public-ip or none)", other),
+        }
+    }
+}
+
+/// Whether `ip` is a loopback address, including IPv4-mapped `::ffff:127.0.0.0/104`.
+pub fn is_loopback_client(ip: IpAddr) -> bool {
+    ip.to_canonical().is_loopback()
+}
+
+/// Outcome of the last lookup.
+#[derive(Default)]
+struct LookupState {
+    geo: Option<GeoInfo>,
+    failed_at: Option<Instant>,
+    /// Resolver the lookup went through (None for a preset geo)
+    resolver: Option<Arc<dyn GeoResolver>>,
+}
+
+/// Cached, single-flight geo lookup of this host's public IP.
+pub struct PublicIpGeo {
+    provider: Arc<dyn PublicIpProvider>,
+    negative_ttl: Duration,
+    // Held across the lookup, so concurrent callers queue behind one request
+    state: Mutex<LookupState>,
+    fetches: AtomicU64,
+    loopback: LoopbackGeo,
+}
+
+impl Default for PublicIpGeo {
+    /// Looks the public IP up through AWS checkip.
+    fn default() -> Self {
+        Self::new(Arc::new(HttpPublicIpProvider::default()))
+    }
+}
+
+impl PublicIpGeo {
+    /// Look up the public IP through `provider`.
+    pub fn new(provider: Arc<dyn PublicIpProvider>) -> Self {
+        Self {
+            provider,
+            negative_ttl: DEFAULT_NEGATIVE_TTL,
+            state: Mutex::new(LookupState::default()),
+            fetches: AtomicU64::new(0),
+            loopback: LoopbackGeo::default(),
+        }
+    }
+
+    /// Set how long a failed lookup is remembered.
+    pub fn with_negative_ttl(mut self, negative_ttl: Duration) -> Self {
+        self.negative_ttl = negative_ttl;
+        self
+    }
+
+    /// Start with a known geo, skipping the lookup entirely.
+    pub fn with_geo(mut self, geo: GeoInfo) -> Self {
+        self.state.get_mut().geo = Some(geo);
+        self
+    }
+
+    /// Set where loopback clients are located.
+    pub fn with_loopback_geo(mut self, loopback: LoopbackGeo) -> Self {
+        self.loopback = loopback;
+        self
+    }
+
+    /// Provider lookups made so far.
+    pub fn fetches(&self) -> u64 {
+        self.fetches.load(Ordering::Relaxed)
+    }
+
+    /// Geo for a client at `client_ip`.
+    ///
+    /// Loopback clients, IPv4 or IPv6, are located by the loopback policy;
+    /// anyone else through the service's current geo resolver.
+    pub async fn client_geo(&self, service: &ProxyService, client_ip: IpAddr) -> Option<GeoInfo> {
+        if !is_loopback_client(client_ip) {
+            return service.resolve_geo(client_ip);
+        }
+        match self.loopback {
+            LoopbackGeo::PublicIp => self.resolve(service).await,
+            LoopbackGeo::Unknown => None,
+        }
+    }
+
+    /// Geo for this host's public IP.
+    ///
+    /// Returns the cached geo when there is one. Otherwise asks the
+    /// provider for the public IP (at most one lookup in flight) and
+    /// resolves it through the service's current geo resolver, unless the
+    /// last attempt failed less than the negative TTL ago. Without a
+    /// resolver there is nothing to look up, so the provider isn't called.
+    ///
+    /// The outcome of a lookup is dropped once the service's resolver is
+    /// swapped for another one; a preset geo is kept.
+    pub async fn resolve(&self, service: &ProxyService) -> Option<GeoInfo> {
+        let mut state = self.state.lock().await;
+        let geo_resolver = service.geo_resolver();
+        if let Some(previous) = &state.resolver {
+            if !geo_resolver
+                .as_ref()
+                .is_some_and(|current| Arc::ptr_eq(previous, current))
+            {
+                *state = LookupState::default();
+            }
+        }
+        if state.geo.is_some() {
+            return state.geo.clone();
+        }
+        let geo_resolver = geo_resolver?;
+        if state
+            .failed_at
+            .is_some_and(|failed_at| failed_at.elapsed() < self.negative_ttl)
+        {
+            return None;
+        }
+
+        self.fetches.fetch_add(1, Ordering::Relaxed);
+        let geo = self
+            .provider
+            .public_ip()
+            .await
+            .and_then(|ip| geo_resolver.resolve(ip));
+        match &geo {
+            Some(_) => state.failed_at = None,
+            None => {
+                tracing::debug!("public IP geo lookup failed, retrying later");
+                state.failed_at = Some(Instant::now());
+            }
+        }
+        state.geo = geo.clone();
+        state.resolver = Some(geo_resolver);
+        geo
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::adapters::outbound::StaticPublicIpProvider;
+    use crate::domain::value_objects::RegionCode;
+    use crate::adapters::outbound::{DashMapBindingRepository, DashMapMetricsStore};
+    use crate::domain::entities::Backend;
+    use crate::domain::ports::BackendRepository;
+    use async_trait::async_trait;
+
+    struct MockGeoResolver;
+
+    impl GeoResolver for MockGeoResolver {
+        fn resolve(&self, ip: IpAddr) -> Option<GeoInfo> {
+            (ip.to_string() == "203.0.113.7")
+                .then(|| GeoInfo::new("BR".to_string(), RegionCode::SouthAmerica))
+        }
+    }
+
+    /// Provider returning a fixed answer after `delay`, counting calls.
+    struct MockPublicIpProvider {
+        ip: Option<IpAddr>,
+        delay: Duration,
+        calls: AtomicU64,
+    }
+
+    impl MockPublicIpProvider {
+        fn new(ip: Option<&str>, delay: Duration) -> Arc<Self> {
+            Arc::new(Self {
+                ip: ip.map(|ip| ip.parse().unwrap()),
+                delay,
+                calls: AtomicU64::new(0),
+            })
+        }
+
+        fn calls(&self) -> u64 {
+            self.calls.load(Ordering::SeqCst)
+        }
+    }
+
+    #[async_trait]
+    impl PublicIpProvider for MockPublicIpProvider {
+        async fn public_ip(&self) -> Option<IpAddr> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(self.delay).await;
+            self.ip
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resolves_provider_ip() {
+        let provider = MockPublicIpProvider::new(Some("203.0.113.7"), Duration::ZERO);
+        let public_ip = PublicIpGeo::new(provider.clone());
+        let service = service_with(Some(Arc::new(MockGeoResolver)));
+
+        let geo = public_ip.resolve(&service).await.unwrap();
+        assert_eq!(geo.country, "BR");
+        assert_eq!(provider.calls(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_static_provider_needs_no_network() {
+        let provider = StaticPublicIpProvider::new("203.0.113.7".parse().unwrap());
+        let public_ip = PublicIpGeo::new(Arc::new(provider));
+        let service = service_with(Some(Arc::new(MockGeoResolver)));
+
+        let geo = public_ip.resolve(&service).await.unwrap();
+        assert_eq!(geo.region, RegionCode::SouthAmerica);
+    }
+
+    #[tokio::test]
+    async fn test_no_resolver_makes_no_request() {
+        let provider = MockPublicIpProvider::new(Some("203.0.113.7"), Duration::ZERO);
+        let public_ip = PublicIpGeo::new(provider.clone());
+        let service = service_with(None);
+
+        assert!(public_ip.resolve(&service).await.is_none());
+        assert_eq!(provider.calls(), 0);
+        assert_eq!(public_ip.fetches(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_preset_geo_skips_lookup() {
+        let provider = MockPublicIpProvider::new(Some("203.0.113.7"), Duration::ZERO);
+        let geo = GeoInfo::new("DE".to_string(), RegionCode::Europe);
+        let public_ip = PublicIpGeo::new(provider.clone()).with_geo(geo);
+        let service = service_with(Some(Arc::new(MockGeoResolver)));
+
+        let result = public_ip.resolve(&service).await;
+        assert_eq!(result.unwrap().country, "DE");
+        assert_eq!(provider.calls(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_lookups_share_one_fetch() {
+        let provider = MockPublicIpProvider::new(Some("203.0.113.7"), Duration::from_millis(100));
+        let public_ip = Arc::new(PublicIpGeo::new(provider.clone()));
+        let service = Arc::new(service_with(Some(Arc::new(MockGeoResolver))));
+
+        let lookups: Vec<_> = (0..10)
+            .map(|_| {
+                let public_ip = public_ip.clone();
+                let service = service.clone();
+                tokio::spawn(async move { public_ip.resolve(&service).await })
+            })
+            .collect();
+        for lookup in lookups {
+            assert_eq!(lookup.await.unwrap().unwrap().country, "BR");
+        }
+
+        assert_eq!(provider.calls(), 1);
+        assert_eq!(public_ip.fetches(), 1);
+
+        // Cached from now on
+        assert!(public_ip.resolve(&service).await.is_some());
+        assert_eq!(provider.calls(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_failure_is_cached_for_negative_ttl() {
+        let provider = MockPublicIpProvider::new(None, Duration::ZERO);
+        let public_ip =
+            PublicIpGeo::new(provider.clone()).with_negative_ttl(Duration::from_millis(100));
+        let service = service_with(Some(Arc::new(MockGeoResolver)));
+
+        assert!(public_ip.resolve(&service).await.is_none());
+        assert!(public_ip.resolve(&service).await.is_none());
+        assert_eq!(provider.calls(), 1);
+
+        // Retried once the negative entry expires
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert!(public_ip.resolve(&service).await.is_none());
+        assert_eq!(provider.calls(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_unlocatable_ip_is_cached_as_failure() {
+        let provider = MockPublicIpProvider::new(Some("198.51.100.1"), Duration::ZERO);
+        let public_ip = PublicIpGeo::new(provider.clone());
+        let service = service_with(Some(Arc::new(MockGeoResolver)));
+
+        assert!(public_ip.resolve(&service).await.is_none());
+        assert!(public_ip.resolve(&service).await.is_none());
+        assert_eq!(provider.calls(), 1);
+    }
+
+    // ===== Loopback Policy Tests =====
+
+    struct EmptyBackendRepository;
+
+    #[async_trait]
+    impl BackendRepository for EmptyBackendRepository {
+        async fn get_all(&self) -> Vec<Backend> {
+            Vec::new()
+        }
+
+        async fn get_by_id(&self, _id: &str) -> Option<Backend> {
+            None
+        }
+
+        async fn get_healthy(&self) -> Vec<Backend> {
+            Vec::new()
+        }
+
+        async fn get_version(&self) -> u64 {
+            1
+        }
+    }
+
+    /// Resolver placing every address in France, loopback included.
+    struct EverywhereGeoResolver;
+
+    impl GeoResolver for EverywhereGeoResolver {
+        fn resolve(&self, _ip: IpAddr) -> Option<GeoInfo> {
+            Some(GeoInfo::new("FR".to_string(), RegionCode::Europe))
+        }
+    }
+
+    fn service_with(geo_resolver: Option<Arc<dyn GeoResolver>>) -> ProxyService {
+        ProxyService::new(
+            Arc::new(EmptyBackendRepository),
+            Arc::new(DashMapBindingRepository::new()),
+            geo_resolver,
+            Arc::new(DashMapMetricsStore::new()),
+            RegionCode::Europe,
+        )
+    }
+
+    const LOOPBACKS: [&str; 3] = ["127.0.0.1", "::1", "::ffff:127.0.0.1"];
+
+    #[test]
+    fn test_is_loopback_client() {
+        for ip in LOOPBACKS.iter().chain(&["127.8.9.10"]) {
+            assert!(is_loopback_client(ip.parse().unwrap()), "{}", ip);
+        }
+        for ip in ["10.0.0.1", "::ffff:10.0.0.1", "fe80::1"] {
+            assert!(!is_loopback_client(ip.parse().unwrap()), "{}", ip);
+        }
+    }
+
+    #[test]
+    fn test_loopback_geo_from_str() {
+        assert_eq!("public-ip".parse::<LoopbackGeo>().unwrap(), LoopbackGeo::PublicIp);
+        assert_eq!("Public_IP".parse::<LoopbackGeo>().unwrap(), LoopbackGeo::PublicIp);
+        assert_eq!("none".parse::<LoopbackGeo>().unwrap(), LoopbackGeo::Unknown);
+        assert!("nearest".parse::<LoopbackGeo>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_loopback_clients_use_public_ip_for_both_families() {
+        let provider = MockPublicIpProvider::new(Some("203.0.113.7"), Duration::ZERO);
+        let public_ip = PublicIpGeo::new(provider.clone());
+        let service = service_with(Some(Arc::new(MockGeoResolver)));
+
+        for ip in LOOPBACKS {
+            let geo = public_ip.client_geo(&service, ip.parse().unwrap()).await;
+            assert_eq!(geo.unwrap().country, "BR", "{}", ip);
+        }
+        assert_eq!(provider.calls(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_loopback_clients_unlocated_for_both_families() {
+        let provider = MockPublicIpProvider::new(Some("203.0.113.7"), Duration::ZERO);
+        let public_ip = PublicIpGeo::new(provider.clone()).with_loopback_geo(LoopbackGeo::Unknown);
+        let service = service_with(Some(Arc::new(MockGeoResolver)));
+
+        for ip in LOOPBACKS {
+            let geo = public_ip.client_geo(&service, ip.parse().unwrap()).await;
+            assert!(geo.is_none(), "{}", ip);
+        }
+        assert_eq!(provider.calls(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_other_clients_use_service_resolver() {
+        let provider = MockPublicIpProvider::new(Some("203.0.113.7"), Duration::ZERO);
+        let public_ip = PublicIpGeo::new(provider.clone());
+        let service = service_with(Some(Arc::new(EverywhereGeoResolver)));
+
+        let geo = public_ip
+            .client_geo(&service, "198.51.100.1".parse().unwrap())
+            .await;
+        assert_eq!(geo.unwrap().country, "FR");
+        assert_eq!(provider.calls(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_swapped_resolver_drops_cached_geo() {
+        let provider = MockPublicIpProvider::new(Some("203.0.113.7"), Duration::ZERO);
+        let public_ip = PublicIpGeo::new(provider.clone());
+        let service = service_with(Some(Arc::new(MockGeoResolver)));
+
+        assert_eq!(public_ip.resolve(&service).await.unwrap().country, "BR");
+        assert_eq!(public_ip.resolve(&service).await.unwrap().country, "BR");
+        assert_eq!(provider.calls(), 1);
+
+        // Looked up again through the new resolver
+        service.set_geo_resolver(Some(Arc::new(EverywhereGeoResolver)));
+        assert_eq!(public_ip.resolve(&service).await.unwrap().country, "FR");
+        assert_eq!(provider.calls(), 2);
+
+        service.set_geo_resolver(None);
+        assert!(public_ip.resolve(&service).await.is_none());
+        assert_eq!(provider.calls(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_swapped_resolver_retries_failed_lookup() {
+        let provider = MockPublicIpProvider::new(Some("198.51.100.1"), Duration::ZERO);
+        let public_ip = PublicIpGeo::new(provider.clone());
+        let service = service_with(Some(Arc::new(MockGeoResolver)));
+
+        assert!(public_ip.resolve(&service).await.is_none());
+        assert_eq!(provider.calls(), 1);
+
+        // The negative entry belonged to the old resolver
+        service.set_geo_resolver(Some(Arc::new(EverywhereGeoResolver)));
+        assert_eq!(public_ip.resolve(&service).await.unwrap().country, "FR");
+        assert_eq!(provider.calls(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_preset_geo_survives_resolver_swap() {
+        let provider = MockPublicIpProvider::new(Some("203.0.113.7"), Duration::ZERO);
+        let geo = GeoInfo::new("DE".to_string(), RegionCode::Europe);
+        let public_ip = PublicIpGeo::new(provider.clone()).with_geo(geo);
+        let service = service_with(Some(Arc::new(MockGeoResolver)));
+
+        service.set_geo_resolver(Some(Arc::new(EverywhereGeoResolver)));
+        assert_eq!(public_ip.resolve(&service).await.unwrap().country, "DE");
+        assert_eq!(provider.calls(), 0);
+    }
+}
@@ -3,15 +3,64 @@
 //! Accepts TCP connections and proxies them to backends
 //! using the application service layer.
 
-use crate::application::ProxyService;
-use crate::domain::entities::GeoInfo;
-use crate::domain::ports::GeoResolver;
+use super::connect::{self, ConnectConfig, ConnectRequest};
+use super::dial::DialOptions;
+use super::listener::ListenOptions;
+use super::public_ip::PublicIpGeo;
+use crate::application::{ProxyService, Unavailable};
+use crate::domain::entities::{Backend, GeoInfo};
+use crate::domain::value_objects::{CloseReason, ConnectionId};
+use crate::infrastructure::{AbortOnDrop, ShutdownController};
 use std::net::{IpAddr, SocketAddr};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::task::Poll;
 use std::time::{Duration, Instant};
 use tokio::io::{self, AsyncWriteExt};
 use tokio::net::{TcpListener, TcpStream};
-use tokio::sync::RwLock;
+use tokio::task::JoinError;
+use tracing::Instrument;
+
+/// Close reason of a session given the copy direction that finished first:
+/// its side's EOF, or `Error` if the copy failed.
+pub(super) fn first_finished(
+    side: CloseReason,
+    result: Result<io::Result<u64>, JoinError>,
+) -> CloseReason {
+    match result {
+        Ok(Ok(_)) => side,
+        Ok(Err(e)) => {
+            tracing::trace!("{} copy error: {:?}", side, e);
+            CloseReason::Error
+        }
+        Err(_) => CloseReason::Error,
+    }
+}
+
+/// Span wrapping everything logged for one accepted connection.
+pub(super) fn connection_span(conn_id: ConnectionId, client_addr: SocketAddr) -> tracing::Span {
+    tracing::info_span!("conn", id = %conn_id, client = %client_addr)
+}
+
+/// Emit the access log entry of a finished session.
+pub(super) fn log_access(
+    conn_id: ConnectionId,
+    client_addr: SocketAddr,
+    backend: &Backend,
+    reason: CloseReason,
+    started: Instant,
+) {
+    tracing::info!(
+        target: "edge_proxy::access",
+        conn_id = %conn_id,
+        client = %client_addr,
+        app = %backend.app,
+        backend = %backend.id,
+        reason = %reason,
+        duration_ms = started.elapsed().as_millis() as u64,
+        "access"
+    );
+}
 
 /// TCP Server - inbound adapter for handling client connections.
 ///
@@ -20,88 +69,219 @@ use tokio::sync::RwLock;
 /// 2. Uses ProxyService to resolve the best backend
 /// 3. Establishes connection to backend
 /// 4. Performs bidirectional TCP copy (L4 passthrough)
+///
+/// In CONNECT proxy mode the client first sends an HTTP `CONNECT host:port`
+/// request; the host selects the app and the tunnel is proxied the same way.
+///
+/// A server can listen on several addresses; every listener shares the same
+/// `ProxyService`, so bindings and backend limits apply across all of them.
 pub struct TcpServer {
     proxy_service: Arc<ProxyService>,
-    listen_addr: String,
-    geo_resolver: Option<Arc<dyn GeoResolver>>,
-    public_ip_geo: Arc<RwLock<Option<GeoInfo>>>,
+    listen_addrs: Vec<String>,
+    public_ip_geo: Arc<PublicIpGeo>,
+    max_session: Option<Duration>,
+    listen_options: ListenOptions,
+    dial_options: DialOptions,
+    connect: Option<Arc<ConnectConfig>>,
+    shutdown: ShutdownController,
+    access_log: bool,
 }
 
 impl TcpServer {
     /// Create a new TCP server.
-    pub fn new(
-        proxy_service: Arc<ProxyService>,
-        listen_addr: String,
-        geo_resolver: Option<Arc<dyn GeoResolver>>,
-    ) -> Self {
+    pub fn new(proxy_service: Arc<ProxyService>, listen_addr: String) -> Self {
         Self {
             proxy_service,
-            listen_addr,
-            geo_resolver,
-            public_ip_geo: Arc::new(RwLock::new(None)),
+            listen_addrs: vec![listen_addr],
+            public_ip_geo: Arc::new(PublicIpGeo::default()),
+            max_session: None,
+            listen_options: ListenOptions::default(),
+            dial_options: DialOptions::default(),
+            connect: None,
+            shutdown: ShutdownController::new(),
+            access_log: false,
         }
     }
 
+    /// Also listen on `listen_addr` (e.g. a second port or an IPv6 address).
+    pub fn add_listener(mut self, listen_addr: String) -> Self {
+        self.listen_addrs.push(listen_addr);
+        self
+    }
+
+    /// Addresses the server listens on, in bind order.
+    pub fn listen_addrs(&self) -> &[String] {
+        &self.listen_addrs
+    }
+
+    /// Set the socket options used when binding the listener.
+    pub fn with_listen_options(mut self, listen_options: ListenOptions) -> Self {
+        self.listen_options = listen_options;
+        self
+    }
+
+    /// Set the socket options used when connecting to backends.
+    pub fn with_dial_options(mut self, dial_options: DialOptions) -> Self {
+        self.dial_options = dial_options;
+        self
+    }
+
+    /// Cap the total duration of each proxied session (`None` disables the cap).
+    pub fn with_max_session(mut self, max_session: Option<Duration>) -> Self {
+        self.max_session = max_session;
+        self
+    }
+
+    /// Enable HTTP CONNECT proxy mode (`None` keeps transparent routing).
+    pub fn with_connect_proxy(mut self, connect: Option<ConnectConfig>) -> Self {
+        self.connect = connect.map(Arc::new);
+        self
+    }
+
+    /// Share a public IP geo lookup (used for loopback clients) with other listeners.
+    pub fn with_public_ip_geo(mut self, public_ip_geo: Arc<PublicIpGeo>) -> Self {
+        self.public_ip_geo = public_ip_geo;
+        self
+    }
+
+    /// Refuse new connections while `shutdown` is draining, and count active ones on it.
+    pub fn with_shutdown(mut self, shutdown: ShutdownController) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+
+    /// Log one access line per finished session (target `edge_proxy::access`).
+    pub fn with_access_log(mut self, access_log: bool) -> Self {
+        self.access_log = access_log;
+        self
+    }
+
     /// Run the TCP server.
     ///
-    /// This will listen for incoming connections and spawn
-    /// a new task for each connection. The error handler inside the spawned
-    /// task is excluded from coverage as it's an async error path.
+    /// This binds every listen address (failing if any can't be bound),
+    /// then accepts on all of them concurrently and spawns a new task for
+    /// each connection. The error handler inside the spawned task is
+    /// excluded from coverage as it's an async error path.
     #[cfg_attr(coverage_nightly, coverage(off))]
     pub async fn run(&self) -> anyhow::Result<()> {
-        let listener = TcpListener::bind(&self.listen_addr).await?;
-        tracing::info!("edgeProxy listening on {}", self.listen_addr);
+        let mut listeners = Vec::with_capacity(self.listen_addrs.len());
+        for listen_addr in &self.listen_addrs {
+            listeners.push(self.listen_options.bind_tcp(listen_addr).await?);
+            tracing::info!("edgeProxy listening on {}", listen_addr);
+        }
 
         loop {
-            let (stream, addr) = listener.accept().await?;
+            let (stream, addr) = Self::accept_any(&listeners).await?;
+            if self.shutdown.is_draining() {
+                tracing::debug!("draining, refusing connection from {}", addr);
+                let _ = stream.set_zero_linger();
+                continue;
+            }
             let service = self.proxy_service.clone();
-            let geo_resolver = self.geo_resolver.clone();
             let public_ip_geo = self.public_ip_geo.clone();
-
-            tokio::spawn(async move {
-                if let Err(e) =
-                    Self::handle_connection(service, stream, addr, geo_resolver, public_ip_geo)
-                        .await
-                {
-                    tracing::error!("connection error from {}: {:?}", addr, e);
+            let max_session = self.max_session;
+            let connect = self.connect.clone();
+            let dial_options = self.dial_options.clone();
+            let access_log = self.access_log;
+            let conn_id = ConnectionId::next();
+
+            self.shutdown.spawn_connection(
+                async move {
+                    if let Err(e) = Self::handle_connection(
+                        service,
+                        stream,
+                        addr,
+                        public_ip_geo,
+                        max_session,
+                        connect,
+                        dial_options,
+                        conn_id,
+                        access_log,
+                    )
+                    .await
+                    {
+                        tracing::error!("connection error from {}: {:?}", addr, e);
+                    }
                 }
-            });
+                .instrument(connection_span(conn_id, addr)),
+            );
         }
     }
 
+    /// Accept the next connection from whichever listener has one ready.
+    ///
+    /// Polling starts at a rotating listener so a busy one can't starve
+    /// the others.
+    async fn accept_any(listeners: &[TcpListener]) -> io::Result<(TcpStream, SocketAddr)> {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let start = NEXT.fetch_add(1, Ordering::Relaxed);
+
+        std::future::poll_fn(|cx| {
+            for i in 0..listeners.len() {
+                let listener = &listeners[(start + i) % listeners.len()];
+                if let Poll::Ready(result) = listener.poll_accept(cx) {
+                    return Poll::Ready(result);
+                }
+            }
+            Poll::Pending
+        })
+        .await
+    }
+
     /// Handle a single client connection.
+    ///
+    /// Expected to run inside the connection's [`connection_span`]; when
+    /// `access_log` is set, a proxied session ends with an access log entry.
     #[cfg_attr(coverage_nightly, coverage(off))]
+    #[allow(clippy::too_many_arguments)]
     async fn handle_connection(
         service: Arc<ProxyService>,
-        client_stream: TcpStream,
+        mut client_stream: TcpStream,
         client_addr: SocketAddr,
-        geo_resolver: Option<Arc<dyn GeoResolver>>,
-        public_ip_geo: Arc<RwLock<Option<GeoInfo>>>,
+        public_ip_geo: Arc<PublicIpGeo>,
+        max_session: Option<Duration>,
+        connect: Option<Arc<ConnectConfig>>,
+        dial_options: DialOptions,
+        conn_id: ConnectionId,
+        access_log: bool,
     ) -> anyhow::Result<()> {
+        let started = Instant::now();
         let client_ip = client_addr.ip();
-
-        // For localhost connections, use public IP for geo resolution
-        let client_geo = if client_ip.is_loopback() {
-            Self::resolve_localhost_geo(geo_resolver, public_ip_geo).await
-        } else {
-            service.resolve_geo(client_ip)
-        };
-
-        // Resolve backend
-        let backend = match service.resolve_backend_with_geo(client_ip, client_geo).await {
-            Some(b) => b,
+        tracing::debug!("accepted connection from {}", client_addr);
+
+        // Loopback clients (IPv4 or IPv6) are located per the loopback policy
+        let client_geo = public_ip_geo.client_geo(&service, client_ip).await;
+
+        // Resolve backend (from the CONNECT target's app in proxy mode)
+        let (backend, early_data) = match &connect {
+            Some(connect) => {
+                let Some(resolved) =
+                    Self::accept_connect(&service, &mut client_stream, client_ip, client_geo, connect)
+                        .await?
+                else {
+                    return Ok(());
+                };
+                resolved
+            }
             None => {
-                tracing::warn!("no backend available for {}", client_ip);
-                return Ok(());
+                let dest_port = client_stream.local_addr().ok().map(|addr| addr.port());
+                match service.resolve_flow_backend(client_addr, dest_port, client_geo).await {
+                    Some(b) => (b, Vec::new()),
+                    None => {
+                        if service.unavailable(None).await == Unavailable::Overloaded {
+                            // Reset rather than close so the client fails fast
+                            tracing::warn!("all backends at hard limit, rejecting {}", client_ip);
+                            client_stream.set_zero_linger()?;
+                        } else {
+                            tracing::warn!("no backend available for {}", client_ip);
+                        }
+                        return Ok(());
+                    }
+                }
             }
         };
 
-        // Format backend address
-        let backend_addr = if backend.wg_ip.contains(':') {
-            format!("[{}]:{}", backend.wg_ip, backend.port)
-        } else {
-            format!("{}:{}", backend.wg_ip, backend.port)
-        };
+        let backend_addr = backend.addr();
 
         tracing::debug!(
             "proxying {} -> {} ({})",
@@ -110,9 +290,8 @@ impl TcpServer {
             backend_addr
         );
 
-        // Connect to backend and measure RTT
-        let t0 = Instant::now();
-        let backend_stream = match TcpStream::connect(&backend_addr).await {
+        // Connect to backend, timing each phase of the dial
+        let (mut backend_stream, timings) = match dial_options.connect_timed(&backend_addr).await {
             Ok(s) => s,
             Err(e) => {
                 tracing::error!(
@@ -121,123 +300,155 @@ impl TcpServer {
                     backend_addr,
                     e
                 );
+                if connect.is_some() {
+                    let _ = client_stream.write_all(connect::RESPONSE_BAD_GATEWAY).await;
+                }
                 // Clear binding on connection failure
                 service.clear_binding(client_ip).await;
                 return Ok(());
             }
         };
-        let rtt_ms = t0.elapsed().as_millis() as u64;
+
+        // Tunnel is up: tell the CONNECT client and pass on anything it sent early
+        if let Some(connect) = &connect {
+            client_stream.write_all(&connect.established_response(&backend.id)).await?;
+            if !early_data.is_empty() {
+                backend_stream.write_all(&early_data).await?;
+            }
+        }
 
         // Record metrics
         let backend_id = backend.id.clone();
-        service.record_connection_start(&backend_id);
-        service.record_rtt(&backend_id, rtt_ms);
+        service.record_connection_start(&backend_id, &backend.app);
+        timings.record(&service, &backend_id);
 
         // Perform bidirectional copy
-        let result = Self::proxy_bidirectional(client_stream, backend_stream).await;
+        let result = Self::proxy_bidirectional(client_stream, backend_stream, max_session).await;
+        let reason = *result.as_ref().unwrap_or(&CloseReason::Error);
 
-        // Record connection end
-        service.record_connection_end(&backend_id);
-
-        // Propagate proxy errors
-        result.map_err(|e| anyhow::anyhow!("{} proxy error: {:?}", backend_id, e))
-    }
-
-    /// Resolve geo for localhost connections using public IP.
-    ///
-    /// This function depends on external network calls (fetch_public_ip) and is
-    /// excluded from coverage as it cannot be reliably tested in unit tests.
-    #[cfg_attr(coverage_nightly, coverage(off))]
-    async fn resolve_localhost_geo(
-        geo_resolver: Option<Arc<dyn GeoResolver>>,
-        public_ip_geo: Arc<RwLock<Option<GeoInfo>>>,
-    ) -> Option<GeoInfo> {
-        // Try to get cached geo first
-        {
-            let cached = public_ip_geo.read().await;
-            if cached.is_some() {
-                return cached.clone();
-            }
+        if reason == CloseReason::SessionTimeout {
+            tracing::info!("closing {} -> {}: reason={}", client_ip, backend_id, reason);
+            service.record_session_timeout(&backend_id);
+        } else {
+            tracing::debug!("closed {} -> {}: reason={}", client_ip, backend_id, reason);
         }
 
-        // Fetch public IP and resolve geo
-        if let Some(public_ip) = Self::fetch_public_ip().await {
-            let geo_info = geo_resolver.as_ref().and_then(|g| g.resolve(public_ip));
-
-            if geo_info.is_some() {
-                // Cache the result
-                let mut cached = public_ip_geo.write().await;
-                *cached = geo_info.clone();
-            }
-
-            return geo_info;
+        // Record connection end
+        service.record_connection_end(&backend_id, reason);
+        if access_log {
+            log_access(conn_id, client_addr, &backend, reason, started);
         }
 
-        None
+        // Propagate proxy errors
+        result
+            .map(|_| ())
+            .map_err(|e| anyhow::anyhow!("{} proxy error: {:?}", backend_id, e))
     }
 
-    /// Fetch public IP from AWS checkip service.
+    /// Read the client's CONNECT request and pick a backend for its app.
     ///
-    /// This function makes external network calls and is excluded from coverage.
-    #[cfg_attr(coverage_nightly, coverage(off))]
-    async fn fetch_public_ip() -> Option<IpAddr> {
-        let client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(5))
-            .build()
-            .ok()?;
-
-        let resp = client
-            .get("https://checkip.amazonaws.com/")
-            .send()
-            .await
-            .ok()?;
+    /// Error replies (400 malformed, 404 unknown host, 503 no backend or
+    /// overloaded) are sent here, returning None. On success returns the backend and any
+    /// tunnel bytes the client sent along with the request.
+    async fn accept_connect(
+        service: &ProxyService,
+        client_stream: &mut TcpStream,
+        client_ip: IpAddr,
+        client_geo: Option<GeoInfo>,
+        connect: &ConnectConfig,
+    ) -> io::Result<Option<(Backend, Vec<u8>)>> {
+        let head = connect::read_head(client_stream).await?;
+        let Some((request, early_data)) =
+            head.and_then(|(head, rest)| Some((ConnectRequest::parse(&head)?, rest)))
+        else {
+            tracing::debug!("malformed CONNECT request from {}", client_ip);
+            client_stream.write_all(connect::RESPONSE_BAD_REQUEST).await?;
+            return Ok(None);
+        };
 
-        let text = resp.text().await.ok()?.trim().to_string();
+        let Some(app) = connect.app_for(&request.host) else {
+            tracing::debug!("CONNECT to unknown host {} from {}", request.host, client_ip);
+            client_stream.write_all(connect::RESPONSE_NOT_FOUND).await?;
+            return Ok(None);
+        };
 
-        match text.parse::<IpAddr>() {
-            Ok(ip) => {
-                tracing::debug!("public IP detected: {}", ip);
-                Some(ip)
+        match service.select_connection_backend(Some(&app), client_geo.as_ref()).await {
+            Some(backend) => {
+                tracing::debug!(
+                    "CONNECT {}:{} from {} -> app {}",
+                    request.host,
+                    request.port,
+                    client_ip,
+                    app
+                );
+                Ok(Some((backend, early_data)))
+            }
+            None => {
+                let response = match service.unavailable(Some(&app)).await {
+                    Unavailable::Overloaded => {
+                        tracing::warn!("all backends of app {} at hard limit ({})", app, client_ip);
+                        connect::RESPONSE_OVERLOADED
+                    }
+                    Unavailable::NoBackend => {
+                        tracing::warn!("no backend available for app {} ({})", app, client_ip);
+                        connect::RESPONSE_UNAVAILABLE
+                    }
+                };
+                client_stream.write_all(response).await?;
+                Ok(None)
             }
-            Err(_) => None,
         }
     }
 
     /// Perform bidirectional TCP copy between client and backend.
     ///
+    /// When `max_session` is set, both directions are aborted (closing the
+    /// sockets) once the session has run that long. Otherwise the side that
+    /// finished first decides the close reason.
+    ///
     /// This function handles network I/O and spawned task error paths
     /// that are difficult to test deterministically.
     #[cfg_attr(coverage_nightly, coverage(off))]
     async fn proxy_bidirectional(
         client_stream: TcpStream,
         backend_stream: TcpStream,
-    ) -> io::Result<()> {
+        max_session: Option<Duration>,
+    ) -> io::Result<CloseReason> {
         let (mut client_read, mut client_write) = client_stream.into_split();
         let (mut backend_read, mut backend_write) = backend_stream.into_split();
 
         // Spawn tasks for each direction
-        let client_to_backend = tokio::spawn(async move {
+        let mut client_to_backend = AbortOnDrop(tokio::spawn(async move {
             let result = io::copy(&mut client_read, &mut backend_write).await;
             let _ = backend_write.shutdown().await;
             result
-        });
+        }));
 
-        let backend_to_client = tokio::spawn(async move {
+        let mut backend_to_client = AbortOnDrop(tokio::spawn(async move {
             io::copy(&mut backend_read, &mut client_write).await
-        });
-
-        // Wait for both to complete
-        let (c2b, b2c) = tokio::join!(client_to_backend, backend_to_client);
-
-        // Log errors but don't propagate (connection closing is normal)
-        if let Ok(Err(e)) = c2b {
-            tracing::trace!("client->backend copy error: {:?}", e);
-        }
-        if let Ok(Err(e)) = b2c {
-            tracing::trace!("backend->client copy error: {:?}", e);
+        }));
+
+        // Wait for both to complete, or for the session cap
+        let both = async {
+            tokio::select! {
+                c2b = &mut client_to_backend => {
+                    let _ = (&mut backend_to_client).await;
+                    first_finished(CloseReason::ClientClosed, c2b)
+                }
+                b2c = &mut backend_to_client => {
+                    let _ = (&mut client_to_backend).await;
+                    first_finished(CloseReason::BackendClosed, b2c)
+                }
+            }
+        };
+        match max_session {
+            Some(max) => match tokio::time::timeout(max, both).await {
+                Ok(reason) => Ok(reason),
+                // Dropping the guards aborts both directions, closing the sockets
+                Err(_) => Ok(CloseReason::SessionTimeout),
+            },
+            None => Ok(both.await),
         }
-
-        Ok(())
     }
 }
 
@@ -245,11 +456,15 @@ impl TcpServer {
 #[cfg_attr(coverage_nightly, coverage(off))]
 mod tests {
     use super::*;
-    use crate::adapters::outbound::{DashMapBindingRepository, DashMapMetricsStore};
+    use tokio::io::AsyncReadExt;
+    use crate::adapters::outbound::{
+        DashMapBindingRepository, DashMapMetricsStore, NullGeoResolver, StaticGeoResolver,
+    };
     use crate::domain::entities::Backend;
-    use crate::domain::ports::BackendRepository;
-    use crate::domain::value_objects::RegionCode;
+    use crate::domain::ports::{BackendRepository, GeoResolver, MetricsStore};
+    use crate::domain::value_objects::{ConnectPhase, RegionCode};
     use async_trait::async_trait;
+    use tracing_test::traced_test;
 
     // Mock backend repository for testing
     struct MockBackendRepository {
@@ -293,6 +508,9 @@ mod tests {
             weight: 2,
             soft_limit: 100,
             hard_limit: 150,
+            draining: false,
+            maintenance: false,
+            priority: 0,
         }
     }
 
@@ -313,15 +531,8 @@ mod tests {
     #[test]
     fn test_tcp_server_new() {
         let proxy_service = create_proxy_service(vec![create_test_backend("test-1")]);
-        let server = TcpServer::new(proxy_service, "0.0.0.0:0".to_string(), None);
-        assert_eq!(server.listen_addr, "0.0.0.0:0");
-    }
-
-    #[test]
-    fn test_tcp_server_new_with_geo_resolver() {
-        let proxy_service = create_proxy_service(vec![create_test_backend("test-1")]);
-        let server = TcpServer::new(proxy_service, "127.0.0.1:8080".to_string(), None);
-        assert!(server.geo_resolver.is_none());
+        let server = TcpServer::new(proxy_service, "0.0.0.0:0".to_string());
+        assert_eq!(server.listen_addrs(), ["0.0.0.0:0"]);
     }
 
     #[tokio::test]
@@ -337,13 +548,12 @@ mod tests {
             weight: 2,
             soft_limit: 100,
             hard_limit: 150,
+            draining: false,
+            maintenance: false,
+            priority: 0,
         };
 
-        let backend_addr = if backend.wg_ip.contains(':') {
-            format!("[{}]:{}", backend.wg_ip, backend.port)
-        } else {
-            format!("{}:{}", backend.wg_ip, backend.port)
-        };
+        let backend_addr = backend.addr();
 
         assert_eq!(backend_addr, "10.0.0.1:8080");
     }
@@ -361,34 +571,16 @@ mod tests {
             weight: 2,
             soft_limit: 100,
             hard_limit: 150,
+            draining: false,
+            maintenance: false,
+            priority: 0,
         };
 
-        let backend_addr = if backend.wg_ip.contains(':') {
-            format!("[{}]:{}", backend.wg_ip, backend.port)
-        } else {
-            format!("{}:{}", backend.wg_ip, backend.port)
-        };
+        let backend_addr = backend.addr();
 
         assert_eq!(backend_addr, "[::1]:8080");
     }
 
-    #[tokio::test]
-    async fn test_resolve_localhost_geo_returns_none_without_resolver() {
-        let public_ip_geo = Arc::new(RwLock::new(None));
-        let result = TcpServer::resolve_localhost_geo(None, public_ip_geo).await;
-        assert!(result.is_none());
-    }
-
-    #[tokio::test]
-    async fn test_resolve_localhost_geo_returns_cached() {
-        let cached_geo = GeoInfo::new("DE".to_string(), RegionCode::Europe);
-        let public_ip_geo = Arc::new(RwLock::new(Some(cached_geo.clone())));
-
-        let result = TcpServer::resolve_localhost_geo(None, public_ip_geo).await;
-        assert!(result.is_some());
-        assert_eq!(result.unwrap().country, "DE");
-    }
-
     // ===== Integration Tests with Mock Backend =====
 
     #[tokio::test]
@@ -429,7 +621,7 @@ mod tests {
         // Run proxy with timeout
         let result = tokio::time::timeout(
             Duration::from_millis(100),
-            TcpServer::proxy_bidirectional(client_stream, backend_stream),
+            TcpServer::proxy_bidirectional(client_stream, backend_stream, None),
         )
         .await;
 
@@ -456,15 +648,19 @@ mod tests {
         let (stream, client_addr) = listener.accept().await.unwrap();
         let _ = connect_handle.await;
 
-        let public_ip_geo = Arc::new(RwLock::new(None));
+        let public_ip_geo = Arc::new(PublicIpGeo::default());
 
         // Should return Ok but not connect (no backends)
         let result = TcpServer::handle_connection(
             proxy_service,
             stream,
             client_addr,
-            None,
             public_ip_geo,
+            None,
+            None,
+            DialOptions::default(),
+            ConnectionId::next(),
+            false,
         )
         .await;
 
@@ -501,6 +697,9 @@ mod tests {
             weight: 2,
             soft_limit: 100,
             hard_limit: 150,
+            draining: false,
+            maintenance: false,
+            priority: 0,
         };
 
         let proxy_service = create_proxy_service(vec![backend]);
@@ -518,7 +717,7 @@ mod tests {
         let (client_stream, addr) = client_listener.accept().await.unwrap();
         let _ = connect_handle.await;
 
-        let public_ip_geo = Arc::new(RwLock::new(None));
+        let public_ip_geo = Arc::new(PublicIpGeo::default());
 
         // Run with longer timeout for CI environments
         let result = tokio::time::timeout(
@@ -527,8 +726,12 @@ mod tests {
                 proxy_service,
                 client_stream,
                 addr,
-                None,
                 public_ip_geo,
+                None,
+                None,
+                DialOptions::default(),
+                ConnectionId::next(),
+                false,
             ),
         )
         .await;
@@ -541,6 +744,79 @@ mod tests {
         assert!(result.is_ok() || result.is_err());
     }
 
+    #[tokio::test]
+    #[traced_test]
+    async fn test_connection_id_shared_across_lifecycle() {
+        // Backend that accepts and closes straight away
+        let backend_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let backend_addr = backend_listener.local_addr().unwrap();
+        let backend_handle = tokio::spawn(async move {
+            if let Ok((mut stream, _)) = backend_listener.accept().await {
+                let _ = stream.shutdown().await;
+            }
+        });
+
+        let backend = Backend {
+            id: "traced-backend".to_string(),
+            app: "tracedapp".to_string(),
+            region: RegionCode::Europe,
+            country: "DE".to_string(),
+            wg_ip: "127.0.0.1".to_string(),
+            port: backend_addr.port(),
+            healthy: true,
+            weight: 1,
+            soft_limit: 100,
+            hard_limit: 150,
+            draining: false,
+            maintenance: false,
+            priority: 0,
+        };
+        let proxy_service = create_proxy_service(vec![backend]);
+
+        let client_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let client = TcpStream::connect(client_listener.local_addr().unwrap()).await.unwrap();
+        let (client_stream, addr) = client_listener.accept().await.unwrap();
+        drop(client);
+
+        let conn_id = ConnectionId::next();
+        tokio::time::timeout(
+            Duration::from_secs(2),
+            TcpServer::handle_connection(
+                proxy_service,
+                client_stream,
+                addr,
+                Arc::new(PublicIpGeo::default()),
+                None,
+                None,
+                DialOptions::default(),
+                conn_id,
+                true,
+            )
+            .instrument(connection_span(conn_id, addr)),
+        )
+        .await
+        .expect("session should end")
+        .unwrap();
+        backend_handle.abort();
+
+        // accept, select, connect/close and the access line all carry the id
+        let span = format!("conn{{id={} client={}}}", conn_id, addr);
+        let events = [
+            "accepted connection".to_string(),
+            "proxying".to_string(),
+            "closed".to_string(),
+            format!("access conn_id={} client={} app=tracedapp backend=traced-backend", conn_id, addr),
+        ];
+        logs_assert(|lines: &[&str]| {
+            for event in &events {
+                if !lines.iter().any(|line| line.contains(&span) && line.contains(event.as_str())) {
+                    return Err(format!("no '{}' line in span {}", event, span));
+                }
+            }
+            Ok(())
+        });
+    }
+
     #[tokio::test]
     async fn test_handle_connection_backend_unreachable() {
         // Create backend pointing to unreachable address
@@ -555,6 +831,9 @@ mod tests {
             weight: 2,
             soft_limit: 100,
             hard_limit: 150,
+            draining: false,
+            maintenance: false,
+            priority: 0,
         };
 
         let proxy_service = create_proxy_service(vec![backend]);
@@ -570,15 +849,19 @@ mod tests {
         let (client_stream, addr) = client_listener.accept().await.unwrap();
         let _ = connect_handle.await;
 
-        let public_ip_geo = Arc::new(RwLock::new(None));
+        let public_ip_geo = Arc::new(PublicIpGeo::default());
 
         // Should return Ok even on connection failure
         let result = TcpServer::handle_connection(
             proxy_service,
             client_stream,
             addr,
-            None,
             public_ip_geo,
+            None,
+            None,
+            DialOptions::default(),
+            ConnectionId::next(),
+            false,
         )
         .await;
 
@@ -604,6 +887,9 @@ mod tests {
             weight: 2,
             soft_limit: 100,
             hard_limit: 150,
+            draining: false,
+            maintenance: false,
+            priority: 0,
         };
 
         let proxy_service = create_proxy_service(vec![backend]);
@@ -616,7 +902,6 @@ mod tests {
         let server = TcpServer::new(
             proxy_service,
             server_addr.to_string(),
-            None,
         );
 
         // Use channel to signal backend completion
@@ -687,6 +972,9 @@ mod tests {
             weight: 2,
             soft_limit: 100,
             hard_limit: 150,
+            draining: false,
+            maintenance: false,
+            priority: 0,
         };
 
         let proxy_service = create_proxy_service(vec![backend]);
@@ -705,9 +993,10 @@ mod tests {
         // addr.ip() should be 127.0.0.1 (loopback)
         assert!(addr.ip().is_loopback());
 
-        let public_ip_geo = Arc::new(RwLock::new(Some(
-            GeoInfo::new("BR".to_string(), RegionCode::SouthAmerica),
-        )));
+        let public_ip_geo = Arc::new(
+            PublicIpGeo::default()
+                .with_geo(GeoInfo::new("BR".to_string(), RegionCode::SouthAmerica)),
+        );
 
         let result = tokio::time::timeout(
             Duration::from_millis(500),
@@ -715,8 +1004,12 @@ mod tests {
                 proxy_service,
                 client_stream,
                 addr,
-                None,
                 public_ip_geo,
+                None,
+                None,
+                DialOptions::default(),
+                ConnectionId::next(),
+                false,
             ),
         )
         .await;
@@ -742,6 +1035,9 @@ mod tests {
             weight: 2,
             soft_limit: 100,
             hard_limit: 150,
+            draining: false,
+            maintenance: false,
+            priority: 0,
         };
 
         let proxy_service = create_proxy_service(vec![backend]);
@@ -757,15 +1053,19 @@ mod tests {
         let (client_stream, addr) = client_listener.accept().await.unwrap();
         let _ = connect_handle.await;
 
-        let public_ip_geo = Arc::new(RwLock::new(None));
+        let public_ip_geo = Arc::new(PublicIpGeo::default());
 
         // Should handle IPv6 format and fail to connect (no server on that port)
         let result = TcpServer::handle_connection(
             proxy_service,
             client_stream,
             addr,
-            None,
             public_ip_geo,
+            None,
+            None,
+            DialOptions::default(),
+            ConnectionId::next(),
+            false,
         )
         .await;
 
@@ -810,7 +1110,7 @@ mod tests {
         // This should handle errors gracefully
         let result = tokio::time::timeout(
             Duration::from_millis(200),
-            TcpServer::proxy_bidirectional(client_stream, backend_stream),
+            TcpServer::proxy_bidirectional(client_stream, backend_stream, None),
         )
         .await;
 
@@ -821,42 +1121,6 @@ mod tests {
         assert!(result.is_ok() || result.is_err());
     }
 
-    // Mock geo resolver for testing
-    struct MockGeoResolver {
-        geo_info: Option<GeoInfo>,
-    }
-
-    impl MockGeoResolver {
-        fn new(geo_info: Option<GeoInfo>) -> Self {
-            Self { geo_info }
-        }
-    }
-
-    impl GeoResolver for MockGeoResolver {
-        fn resolve(&self, _ip: IpAddr) -> Option<GeoInfo> {
-            self.geo_info.clone()
-        }
-    }
-
-    #[tokio::test]
-    async fn test_resolve_localhost_geo_with_resolver_no_cached() {
-        // Test with geo resolver but no cached value and no public IP
-        let geo_info = GeoInfo::new("BR".to_string(), RegionCode::SouthAmerica);
-        let resolver = Arc::new(MockGeoResolver::new(Some(geo_info)));
-        let public_ip_geo = Arc::new(RwLock::new(None));
-
-        // This will try to fetch public IP which will fail in test env
-        // So result should be None (fetch_public_ip returns None)
-        let result = tokio::time::timeout(
-            Duration::from_millis(100),
-            TcpServer::resolve_localhost_geo(Some(resolver), public_ip_geo),
-        )
-        .await;
-
-        // Should timeout or return None (can't fetch public IP in test)
-        assert!(result.is_err() || result.unwrap().is_none());
-    }
-
     #[tokio::test]
     async fn test_handle_connection_records_metrics() {
         use tokio::sync::oneshot;
@@ -891,6 +1155,9 @@ mod tests {
             weight: 2,
             soft_limit: 100,
             hard_limit: 150,
+            draining: false,
+            maintenance: false,
+            priority: 0,
         };
 
         let backend_repo = Arc::new(MockBackendRepository::new(vec![backend]));
@@ -918,7 +1185,7 @@ mod tests {
 
         let (client_stream, addr) = client_listener.accept().await.unwrap();
 
-        let public_ip_geo = Arc::new(RwLock::new(None));
+        let public_ip_geo = Arc::new(PublicIpGeo::default());
 
         let result = tokio::time::timeout(
             Duration::from_secs(2),
@@ -926,8 +1193,12 @@ mod tests {
                 proxy_service.clone(),
                 client_stream,
                 addr,
-                None,
                 public_ip_geo,
+                None,
+                None,
+                DialOptions::default(),
+                ConnectionId::next(),
+                false,
             ),
         )
         .await;
@@ -971,6 +1242,9 @@ mod tests {
             weight: 2,
             soft_limit: 100,
             hard_limit: 150,
+            draining: false,
+            maintenance: false,
+            priority: 0,
         };
 
         let proxy_service = create_proxy_service(vec![backend]);
@@ -980,7 +1254,7 @@ mod tests {
         let server_addr = listener.local_addr().unwrap();
         drop(listener);
 
-        let server = TcpServer::new(proxy_service, server_addr.to_string(), None);
+        let server = TcpServer::new(proxy_service, server_addr.to_string());
 
         let server_handle = tokio::spawn(async move {
             let _ = server.run().await;
@@ -1010,6 +1284,67 @@ mod tests {
         backend_handle.abort();
     }
 
+    #[tokio::test]
+    async fn test_concurrent_loopback_connections_share_public_ip_fetch() {
+        use crate::domain::ports::PublicIpProvider;
+        use std::sync::atomic::AtomicU64;
+
+        // Slow provider counting the lookups it serves
+        struct SlowProvider(AtomicU64);
+
+        #[async_trait]
+        impl PublicIpProvider for SlowProvider {
+            async fn public_ip(&self) -> Option<IpAddr> {
+                self.0.fetch_add(1, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                "203.0.113.7".parse().ok()
+            }
+        }
+
+        let provider = Arc::new(SlowProvider(AtomicU64::new(0)));
+
+        let mut backend = create_test_backend("echo");
+        backend.port = start_echo_backend().await;
+        let proxy_service = create_proxy_service(vec![backend]);
+        proxy_service.set_geo_resolver(Some(Arc::new(StaticGeoResolver::new(Some(GeoInfo::new(
+            "DE".to_string(),
+            RegionCode::Europe,
+        ))))));
+        let public_ip_geo = Arc::new(PublicIpGeo::new(provider.clone()));
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let server_addr = listener.local_addr().unwrap();
+        drop(listener);
+        let server = TcpServer::new(proxy_service, server_addr.to_string())
+            .with_public_ip_geo(public_ip_geo.clone());
+        let server_handle = tokio::spawn(async move { server.run().await });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let clients: Vec<_> = (0..5)
+            .map(|_| {
+                tokio::spawn(async move {
+                    let mut stream = TcpStream::connect(server_addr).await.unwrap();
+                    stream.write_all(b"ping").await.unwrap();
+                    let mut buf = [0u8; 4];
+                    stream.read_exact(&mut buf).await.unwrap();
+                    buf
+                })
+            })
+            .collect();
+        for client in clients {
+            let echoed = tokio::time::timeout(Duration::from_secs(2), client)
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(&echoed, b"ping");
+        }
+
+        assert_eq!(provider.0.load(Ordering::SeqCst), 1);
+        assert_eq!(public_ip_geo.fetches(), 1);
+
+        server_handle.abort();
+    }
+
     // ===== Additional tests to increase coverage =====
 
     #[tokio::test]
@@ -1047,20 +1382,6 @@ mod tests {
         assert_eq!(version, 1);
     }
 
-    #[tokio::test]
-    async fn test_fetch_public_ip_timeout() {
-        // This test just verifies that fetch_public_ip handles errors gracefully
-        // In test environment, it will likely fail due to network restrictions
-        let result = tokio::time::timeout(
-            Duration::from_millis(100),
-            TcpServer::fetch_public_ip(),
-        )
-        .await;
-
-        // Should timeout or return a result (either way is fine)
-        assert!(result.is_err() || result.is_ok());
-    }
-
     #[tokio::test]
     async fn test_handle_connection_with_non_loopback_geo() {
         use tokio::sync::oneshot;
@@ -1089,11 +1410,14 @@ mod tests {
             weight: 2,
             soft_limit: 100,
             hard_limit: 150,
+            draining: false,
+            maintenance: false,
+            priority: 0,
         };
 
         // Create service with geo resolver
         let geo_info = GeoInfo::new("BR".to_string(), RegionCode::SouthAmerica);
-        let geo_resolver: Arc<dyn GeoResolver> = Arc::new(MockGeoResolver::new(Some(geo_info)));
+        let geo_resolver: Arc<dyn GeoResolver> = Arc::new(StaticGeoResolver::new(Some(geo_info)));
 
         let backend_repo = Arc::new(MockBackendRepository::new(vec![backend]));
         let binding_repo = Arc::new(DashMapBindingRepository::new());
@@ -1102,7 +1426,7 @@ mod tests {
         let proxy_service = Arc::new(ProxyService::new(
             backend_repo,
             binding_repo,
-            Some(geo_resolver.clone()),
+            Some(geo_resolver),
             metrics,
             RegionCode::SouthAmerica,
         ));
@@ -1118,7 +1442,7 @@ mod tests {
         let (client_stream, _addr) = client_listener.accept().await.unwrap();
         let _ = connect_handle.await;
 
-        let public_ip_geo = Arc::new(RwLock::new(None));
+        let public_ip_geo = Arc::new(PublicIpGeo::default());
 
         // Use a fake non-loopback address to trigger the non-loopback geo resolution path
         let fake_public_addr: SocketAddr = "192.168.1.100:54321".parse().unwrap();
@@ -1129,8 +1453,12 @@ mod tests {
                 proxy_service,
                 client_stream,
                 fake_public_addr, // Use fake public IP instead of actual addr
-                Some(geo_resolver),
                 public_ip_geo,
+                None,
+                None,
+                DialOptions::default(),
+                ConnectionId::next(),
+                false,
             ),
         )
         .await;
@@ -1144,9 +1472,9 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_mock_geo_resolver() {
+    async fn test_static_geo_resolver_default() {
         let geo_info = GeoInfo::new("US".to_string(), RegionCode::NorthAmerica);
-        let resolver = MockGeoResolver::new(Some(geo_info));
+        let resolver = StaticGeoResolver::new(Some(geo_info));
 
         let ip: IpAddr = "8.8.8.8".parse().unwrap();
         let result = resolver.resolve(ip);
@@ -1156,8 +1484,8 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_mock_geo_resolver_none() {
-        let resolver = MockGeoResolver::new(None);
+    async fn test_null_geo_resolver() {
+        let resolver = NullGeoResolver;
 
         let ip: IpAddr = "8.8.8.8".parse().unwrap();
         let result = resolver.resolve(ip);
@@ -1197,7 +1525,7 @@ mod tests {
         // Proxy should handle closed connections gracefully
         let result = tokio::time::timeout(
             Duration::from_millis(200),
-            TcpServer::proxy_bidirectional(client1, client2),
+            TcpServer::proxy_bidirectional(client1, client2, None),
         )
         .await;
 
@@ -1207,21 +1535,663 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    // ===== Session Cap Tests =====
+
     #[tokio::test]
-    async fn test_resolve_localhost_geo_caches_result() {
-        let geo_info = GeoInfo::new("JP".to_string(), RegionCode::AsiaPacific);
-        let resolver = Arc::new(MockGeoResolver::new(Some(geo_info.clone())));
+    async fn test_proxy_bidirectional_closed_under_session_cap() {
+        let listener1 = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr1 = listener1.local_addr().unwrap();
+        let h1 = tokio::spawn(async move {
+            if let Ok((stream, _)) = listener1.accept().await {
+                drop(stream);
+            }
+        });
 
-        // Start with cached value
-        let public_ip_geo = Arc::new(RwLock::new(Some(geo_info.clone())));
+        let listener2 = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr2 = listener2.local_addr().unwrap();
+        let h2 = tokio::spawn(async move {
+            if let Ok((stream, _)) = listener2.accept().await {
+                drop(stream);
+            }
+        });
 
-        let result = TcpServer::resolve_localhost_geo(Some(resolver), public_ip_geo.clone()).await;
+        let client1 = TcpStream::connect(addr1).await.unwrap();
+        let client2 = TcpStream::connect(addr2).await.unwrap();
 
-        assert!(result.is_some());
-        assert_eq!(result.unwrap().country, "JP");
+        let result = tokio::time::timeout(
+            Duration::from_secs(2),
+            TcpServer::proxy_bidirectional(client1, client2, Some(Duration::from_secs(5))),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+        h1.abort();
+        h2.abort();
+
+        assert!(matches!(result, CloseReason::ClientClosed | CloseReason::BackendClosed));
+    }
+
+    #[tokio::test]
+    async fn test_drain_or_abort_closes_stuck_relay() {
+        // Backend that accepts and then never reads nor writes
+        let backend_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let backend_addr = backend_listener.local_addr().unwrap();
+        let backend_handle = tokio::spawn(async move {
+            let (stream, _) = backend_listener.accept().await.unwrap();
+            std::future::pending::<()>().await;
+            drop(stream);
+        });
+
+        let front_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut client = TcpStream::connect(front_listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (server_side, _) = front_listener.accept().await.unwrap();
+        let backend = TcpStream::connect(backend_addr).await.unwrap();
+
+        let shutdown = ShutdownController::new();
+        shutdown.spawn_connection(async move {
+            let _ = TcpServer::proxy_bidirectional(server_side, backend, None).await;
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        shutdown.shutdown();
+        assert_eq!(shutdown.drain_or_abort(Duration::from_millis(50)).await, 1);
+
+        // The relay tasks died with the connection, so the client sees EOF
+        let mut buf = [0u8; 16];
+        let n = tokio::time::timeout(Duration::from_secs(2), client.read(&mut buf))
+            .await
+            .expect("client socket still open after drain_or_abort")
+            .unwrap();
+        assert_eq!(n, 0);
+
+        backend_handle.abort();
+    }
+
+    #[tokio::test]
+    async fn test_handle_connection_session_timeout_terminates_long_session() {
+        use tokio::io::AsyncReadExt;
+
+        // Echo backend that never closes on its own
+        let backend_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let backend_addr = backend_listener.local_addr().unwrap();
+        let backend_handle = tokio::spawn(async move {
+            if let Ok((mut stream, _)) = backend_listener.accept().await {
+                let (mut reader, mut writer) = stream.split();
+                let _ = io::copy(&mut reader, &mut writer).await;
+            }
+        });
+
+        let mut backend = create_test_backend("capped-backend");
+        backend.port = backend_addr.port();
+
+        let backend_repo = Arc::new(MockBackendRepository::new(vec![backend]));
+        let metrics = Arc::new(DashMapMetricsStore::new());
+        let proxy_service = Arc::new(ProxyService::new(
+            backend_repo,
+            Arc::new(DashMapBindingRepository::new()),
+            None,
+            metrics.clone(),
+            RegionCode::Europe,
+        ));
+
+        let client_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let client_addr = client_listener.local_addr().unwrap();
+
+        // Client keeps the session busy until the proxy closes it
+        let client_handle = tokio::spawn(async move {
+            let mut stream = TcpStream::connect(client_addr).await.unwrap();
+            let mut buf = [0u8; 64];
+            loop {
+                if stream.write_all(b"ping").await.is_err() {
+                    break;
+                }
+                match stream.read(&mut buf).await {
+                    Ok(0) | Err(_) => break,
+                    Ok(_) => tokio::time::sleep(Duration::from_millis(10)).await,
+                }
+            }
+        });
+
+        let (client_stream, addr) = client_listener.accept().await.unwrap();
+
+        let started = std::time::Instant::now();
+        let result = tokio::time::timeout(
+            Duration::from_secs(5),
+            TcpServer::handle_connection(
+                proxy_service.clone(),
+                client_stream,
+                addr,
+                Arc::new(PublicIpGeo::default()),
+                Some(Duration::from_millis(200)),
+                None,
+                DialOptions::default(),
+                ConnectionId::next(),
+                false,
+            ),
+        )
+        .await
+        .expect("session should be cut at the cap");
+
+        assert!(result.is_ok());
+        assert!(started.elapsed() >= Duration::from_millis(200));
+        assert_eq!(metrics.get_session_timeouts("capped-backend"), 1);
+        assert_eq!(metrics.get_connection_count("capped-backend"), 0);
+        assert_eq!(metrics.get_connection_closed(CloseReason::SessionTimeout), 1);
+
+        // Client observes the connection being closed
+        tokio::time::timeout(Duration::from_secs(2), client_handle)
+            .await
+            .expect("client should see EOF")
+            .unwrap();
+
+        backend_handle.abort();
+    }
+
+    // ===== Close Reason Tests =====
+
+    /// Proxy one client connection to a backend running `backend`, with
+    /// the client side driven by `client`; returns the metrics store.
+    async fn proxy_session<B, BF, C, CF>(backend: B, client: C) -> Arc<DashMapMetricsStore>
+    where
+        B: FnOnce(TcpStream) -> BF + Send + 'static,
+        BF: std::future::Future<Output = ()> + Send,
+        C: FnOnce(TcpStream) -> CF + Send + 'static,
+        CF: std::future::Future<Output = ()> + Send,
+    {
+        proxy_session_via("127.0.0.1", backend, client).await
+    }
+
+    /// Like [`proxy_session`], with the backend registered under `wg_ip`.
+    async fn proxy_session_via<B, BF, C, CF>(
+        wg_ip: &str,
+        backend: B,
+        client: C,
+    ) -> Arc<DashMapMetricsStore>
+    where
+        B: FnOnce(TcpStream) -> BF + Send + 'static,
+        BF: std::future::Future<Output = ()> + Send,
+        C: FnOnce(TcpStream) -> CF + Send + 'static,
+        CF: std::future::Future<Output = ()> + Send,
+    {
+        let backend_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let backend_addr = backend_listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            if let Ok((stream, _)) = backend_listener.accept().await {
+                backend(stream).await;
+            }
+        });
+
+        let mut target = create_test_backend("close-backend");
+        target.wg_ip = wg_ip.to_string();
+        target.port = backend_addr.port();
+        let metrics = Arc::new(DashMapMetricsStore::new());
+        let proxy_service = Arc::new(ProxyService::new(
+            Arc::new(MockBackendRepository::new(vec![target])),
+            Arc::new(DashMapBindingRepository::new()),
+            None,
+            metrics.clone(),
+            RegionCode::Europe,
+        ));
+
+        let client_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let client_addr = client_listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            client(TcpStream::connect(client_addr).await.unwrap()).await;
+        });
+        let (client_stream, addr) = client_listener.accept().await.unwrap();
+
+        tokio::time::timeout(
+            Duration::from_secs(5),
+            TcpServer::handle_connection(
+                proxy_service,
+                client_stream,
+                addr,
+                Arc::new(PublicIpGeo::default()),
+                None,
+                None,
+                DialOptions::default(),
+                ConnectionId::next(),
+                false,
+            ),
+        )
+        .await
+        .expect("session should end")
+        .unwrap();
+
+        metrics
+    }
+
+    #[tokio::test]
+    async fn test_client_eof_recorded_as_close_reason() {
+        use tokio::io::AsyncReadExt;
+
+        // Echo backend that closes once the client is done sending
+        let metrics = proxy_session(
+            |mut stream| async move {
+                let (mut reader, mut writer) = stream.split();
+                let _ = io::copy(&mut reader, &mut writer).await;
+            },
+            |mut stream| async move {
+                stream.write_all(b"hello").await.unwrap();
+                stream.shutdown().await.unwrap();
+                let mut buf = Vec::new();
+                let _ = stream.read_to_end(&mut buf).await;
+            },
+        )
+        .await;
+
+        assert_eq!(metrics.get_connection_closed(CloseReason::ClientClosed), 1);
+        assert_eq!(metrics.get_connection_closed(CloseReason::BackendClosed), 0);
+        assert_eq!(metrics.get_connection_count("close-backend"), 0);
+    }
+
+    #[tokio::test]
+    async fn test_backend_eof_recorded_as_close_reason() {
+        use tokio::io::AsyncReadExt;
+
+        // Backend answers and hangs up; the client leaves once it sees EOF
+        let metrics = proxy_session(
+            |mut stream| async move {
+                let _ = stream.write_all(b"bye").await;
+            },
+            |mut stream| async move {
+                let mut buf = Vec::new();
+                let _ = stream.read_to_end(&mut buf).await;
+            },
+        )
+        .await;
+
+        assert_eq!(metrics.get_connection_closed(CloseReason::BackendClosed), 1);
+        assert_eq!(metrics.get_connection_closed(CloseReason::ClientClosed), 0);
+    }
+
+    // ===== Connect Phase Tests =====
+
+    async fn hang_up(mut stream: TcpStream) {
+        let _ = stream.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_hostname_backend_records_resolve_and_connect() {
+        let metrics = proxy_session_via("localhost", hang_up, hang_up).await;
+
+        let resolve = metrics
+            .get_connect_phase("close-backend", ConnectPhase::Resolve)
+            .expect("hostname dial records a resolve phase");
+        let connect = metrics
+            .get_connect_phase("close-backend", ConnectPhase::Connect)
+            .expect("every dial records a connect phase");
+        // The overall RTT still covers the whole dial
+        assert!(metrics.get_last_rtt("close-backend").unwrap() >= resolve + connect);
+    }
+
+    #[tokio::test]
+    async fn test_ip_backend_records_connect_only() {
+        let metrics = proxy_session(hang_up, hang_up).await;
+
+        assert!(metrics
+            .get_connect_phase("close-backend", ConnectPhase::Resolve)
+            .is_none());
+        assert!(metrics
+            .get_connect_phase("close-backend", ConnectPhase::Connect)
+            .is_some());
+        assert!(metrics.get_last_rtt("close-backend").is_some());
+    }
+
+    // ===== CONNECT Proxy Tests =====
+
+    /// Start a backend that echoes everything back, returning its port.
+    async fn start_echo_backend() -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let (mut read, mut write) = stream.into_split();
+                    let _ = io::copy(&mut read, &mut write).await;
+                });
+            }
+        });
+        port
+    }
+
+    /// Start a CONNECT-mode session, returning the client end of it.
+    async fn start_connect_session(backends: Vec<Backend>) -> TcpStream {
+        start_connect_session_with(backends, ConnectConfig::default()).await
+    }
+
+    async fn start_connect_session_with(backends: Vec<Backend>, config: ConnectConfig) -> TcpStream {
+        start_connect_session_on(create_proxy_service(backends), config).await
+    }
+
+    async fn start_connect_session_on(proxy_service: Arc<ProxyService>, config: ConnectConfig) -> TcpStream {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap()).await.unwrap();
+        let (stream, client_addr) = listener.accept().await.unwrap();
+
+        tokio::spawn(TcpServer::handle_connection(
+            proxy_service,
+            stream,
+            client_addr,
+            Arc::new(PublicIpGeo::default()),
+            None,
+            Some(Arc::new(config)),
+            DialOptions::default(),
+            ConnectionId::next(),
+            false,
+        ));
+        client
+    }
+
+    async fn read_response(client: &mut TcpStream) -> Vec<u8> {
+        let mut response = Vec::new();
+        tokio::time::timeout(Duration::from_secs(10), client.read_to_end(&mut response))
+            .await
+            .unwrap()
+            .unwrap();
+        response
+    }
+
+    #[test]
+    fn test_with_connect_proxy() {
+        let proxy_service = create_proxy_service(vec![]);
+        let server = TcpServer::new(proxy_service, "127.0.0.1:0".to_string());
+        assert!(server.connect.is_none());
+
+        let server = server.with_connect_proxy(Some(ConnectConfig::default()));
+        assert_eq!(server.connect.unwrap().domain, "internal");
+    }
+
+    #[tokio::test]
+    async fn test_connect_tunnel_proxies_bytes() {
+        let mut backend = create_test_backend("connect-backend");
+        backend.port = start_echo_backend().await;
+        let mut client = start_connect_session(vec![backend]).await;
+
+        // Bytes sent right behind the request head go through the tunnel too
+        client
+            .write_all(b"CONNECT testapp.internal:443 HTTP/1.1\r\nHost: testapp.internal:443\r\n\r\nearly")
+            .await
+            .unwrap();
+
+        let mut established = vec![0u8; connect::RESPONSE_ESTABLISHED.len()];
+        client.read_exact(&mut established).await.unwrap();
+        assert_eq!(established, connect::RESPONSE_ESTABLISHED);
+
+        let mut early = [0u8; 5];
+        client.read_exact(&mut early).await.unwrap();
+        assert_eq!(&early, b"early");
+
+        client.write_all(b"ping").await.unwrap();
+        let mut echoed = [0u8; 4];
+        client.read_exact(&mut echoed).await.unwrap();
+        assert_eq!(&echoed, b"ping");
+    }
+
+    /// Read the reply head of an established tunnel.
+    async fn read_head(client: &mut TcpStream) -> String {
+        let mut head = Vec::new();
+        while !head.ends_with(b"\r\n\r\n") {
+            let mut byte = [0u8; 1];
+            client.read_exact(&mut byte).await.unwrap();
+            head.push(byte[0]);
+        }
+        String::from_utf8(head).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_connect_backend_header_when_enabled() {
+        let mut backend = create_test_backend("connect-backend");
+        backend.port = start_echo_backend().await;
+        let config = ConnectConfig {
+            expose_backend: true,
+            ..Default::default()
+        };
+        let mut client = start_connect_session_with(vec![backend], config).await;
+        client.write_all(b"CONNECT testapp.internal:443 HTTP/1.1\r\n\r\n").await.unwrap();
+
+        let head = read_head(&mut client).await;
+        assert!(head.starts_with("HTTP/1.1 200 Connection Established\r\n"));
+        assert!(head.contains("\r\nX-EdgeProxy-Backend: connect-backend\r\n"));
+
+        // The tunnel still works after the longer reply
+        client.write_all(b"ping").await.unwrap();
+        let mut echoed = [0u8; 4];
+        client.read_exact(&mut echoed).await.unwrap();
+        assert_eq!(&echoed, b"ping");
+    }
+
+    #[tokio::test]
+    async fn test_connect_backend_header_absent_by_default() {
+        let mut backend = create_test_backend("connect-backend");
+        backend.port = start_echo_backend().await;
+        let mut client = start_connect_session(vec![backend]).await;
+        client.write_all(b"CONNECT testapp.internal:443 HTTP/1.1\r\n\r\n").await.unwrap();
+
+        let head = read_head(&mut client).await;
+        assert_eq!(head.as_bytes(), connect::RESPONSE_ESTABLISHED);
+        assert!(!head.contains(connect::BACKEND_HEADER));
+    }
+
+    #[tokio::test]
+    async fn test_connect_malformed_request_gets_400() {
+        let mut client = start_connect_session(vec![create_test_backend("b1")]).await;
+        client.write_all(b"GET / HTTP/1.1\r\nHost: testapp.internal\r\n\r\n").await.unwrap();
+
+        assert_eq!(read_response(&mut client).await, connect::RESPONSE_BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_connect_unknown_host_gets_404() {
+        let mut client = start_connect_session(vec![create_test_backend("b1")]).await;
+        client.write_all(b"CONNECT example.com:443 HTTP/1.1\r\n\r\n").await.unwrap();
+
+        assert_eq!(read_response(&mut client).await, connect::RESPONSE_NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_connect_app_without_backend_gets_503() {
+        let mut client = start_connect_session(vec![create_test_backend("b1")]).await;
+        client.write_all(b"CONNECT otherapp.internal:443 HTTP/1.1\r\n\r\n").await.unwrap();
+
+        assert_eq!(read_response(&mut client).await, connect::RESPONSE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn test_connect_unreachable_backend_gets_502() {
+        let unused = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut backend = create_test_backend("down");
+        backend.port = unused.local_addr().unwrap().port();
+        drop(unused);
+
+        let mut client = start_connect_session(vec![backend]).await;
+        client.write_all(b"CONNECT testapp.internal:443 HTTP/1.1\r\n\r\n").await.unwrap();
+
+        assert_eq!(read_response(&mut client).await, connect::RESPONSE_BAD_GATEWAY);
+    }
+
+    // ===== Overload Tests =====
+
+    /// Proxy service whose backends are all filled to a hard limit of 1.
+    fn create_saturated_proxy_service(
+        backends: Vec<Backend>,
+    ) -> (Arc<ProxyService>, Arc<DashMapMetricsStore>) {
+        let metrics = Arc::new(DashMapMetricsStore::new());
+        let backends = backends
+            .into_iter()
+            .map(|mut b| {
+                b.hard_limit = 1;
+                metrics.increment_connections(&b.id);
+                b
+            })
+            .collect();
+        let service = Arc::new(ProxyService::new(
+            Arc::new(MockBackendRepository::new(backends)),
+            Arc::new(DashMapBindingRepository::new()),
+            None,
+            metrics.clone(),
+            RegionCode::Europe,
+        ));
+        (service, metrics)
+    }
+
+    #[tokio::test]
+    async fn test_connect_overloaded_gets_503_with_retry_after() {
+        let (service, metrics) = create_saturated_proxy_service(vec![create_test_backend("b1")]);
+        let mut client = start_connect_session_on(service, ConnectConfig::default()).await;
+        client.write_all(b"CONNECT testapp.internal:443 HTTP/1.1\r\n\r\n").await.unwrap();
+
+        let response = read_response(&mut client).await;
+        assert_eq!(response, connect::RESPONSE_OVERLOADED);
+        assert!(String::from_utf8(response).unwrap().contains("\r\nRetry-After: 1\r\n"));
+        assert_eq!(metrics.get_overload_rejections(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_connect_other_app_overload_is_not_reported() {
+        let (service, metrics) = create_saturated_proxy_service(vec![create_test_backend("b1")]);
+        let mut client = start_connect_session_on(service, ConnectConfig::default()).await;
+        client.write_all(b"CONNECT otherapp.internal:443 HTTP/1.1\r\n\r\n").await.unwrap();
+
+        assert_eq!(read_response(&mut client).await, connect::RESPONSE_UNAVAILABLE);
+        assert_eq!(metrics.get_overload_rejections(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_transparent_overload_resets_connection() {
+        let (service, metrics) = create_saturated_proxy_service(vec![create_test_backend("b1")]);
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).await.unwrap();
+        let (stream, client_addr) = listener.accept().await.unwrap();
+
+        TcpServer::handle_connection(
+            service,
+            stream,
+            client_addr,
+            Arc::new(PublicIpGeo::default()),
+            None,
+            None,
+            DialOptions::default(),
+            ConnectionId::next(),
+            false,
+        )
+        .await
+        .unwrap();
+
+        let mut buf = [0u8; 1];
+        let err = client.read(&mut buf).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::ConnectionReset);
+        assert_eq!(metrics.get_overload_rejections(), 1);
+    }
+
+    // ===== Drain Tests =====
+
+    #[tokio::test]
+    async fn test_run_refuses_new_connections_while_draining() {
+        let mut backend = create_test_backend("echo");
+        backend.port = start_echo_backend().await;
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let server_addr = listener.local_addr().unwrap();
+        drop(listener);
+
+        let shutdown = ShutdownController::new();
+        let server = TcpServer::new(create_proxy_service(vec![backend]), server_addr.to_string())
+            .with_shutdown(shutdown.clone());
+        let server_handle = tokio::spawn(async move { server.run().await });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        // An established session keeps working through the drain
+        let mut existing = TcpStream::connect(server_addr).await.unwrap();
+        existing.write_all(b"before").await.unwrap();
+        let mut buf = [0u8; 6];
+        existing.read_exact(&mut buf).await.unwrap();
+        assert_eq!(shutdown.active_connections(), 1);
+
+        shutdown.drain();
+        let mut refused = TcpStream::connect(server_addr).await.unwrap();
+        let _ = refused.write_all(b"ping").await;
+        let mut byte = [0u8; 1];
+        let read = tokio::time::timeout(Duration::from_secs(5), refused.read(&mut byte))
+            .await
+            .unwrap();
+        assert!(!matches!(read, Ok(n) if n > 0));
+
+        existing.write_all(b"during").await.unwrap();
+        existing.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"during");
+
+        // Undraining lets new connections through again
+        assert!(shutdown.undrain());
+        let mut fresh = TcpStream::connect(server_addr).await.unwrap();
+        fresh.write_all(b"after!").await.unwrap();
+        fresh.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"after!");
+
+        server_handle.abort();
+    }
+
+    // ===== Multiple Listener Tests =====
+
+    /// Reserve a free local port (released before returning).
+    async fn free_addr() -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        listener.local_addr().unwrap()
+    }
+
+    #[test]
+    fn test_add_listener() {
+        let proxy_service = create_proxy_service(vec![]);
+        let server = TcpServer::new(proxy_service, "0.0.0.0:80".to_string())
+            .add_listener("0.0.0.0:8080".to_string())
+            .add_listener("[::]:8080".to_string());
+
+        assert_eq!(server.listen_addrs(), ["0.0.0.0:80", "0.0.0.0:8080", "[::]:8080"]);
+    }
+
+    #[tokio::test]
+    async fn test_run_multiple_listeners_accept_and_proxy() {
+        let mut backend = create_test_backend("multi-listener");
+        backend.port = start_echo_backend().await;
+        let proxy_service = create_proxy_service(vec![backend]);
+
+        let (first, second) = (free_addr().await, free_addr().await);
+        let server = TcpServer::new(proxy_service.clone(), first.to_string())
+            .add_listener(second.to_string());
+        let server_handle = tokio::spawn(async move { server.run().await });
+
+        // Give server time to bind both listeners
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let mut clients = Vec::new();
+        for addr in [first, second] {
+            let mut client = TcpStream::connect(addr).await.unwrap();
+            client.write_all(b"ping").await.unwrap();
+            let mut echoed = [0u8; 4];
+            tokio::time::timeout(Duration::from_secs(5), client.read_exact(&mut echoed))
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(&echoed, b"ping");
+            clients.push(client);
+        }
+
+        // Both sessions count against the same backend
+        assert_eq!(proxy_service.get_connection_count("multi-listener"), 2);
+
+        server_handle.abort();
+    }
+
+    #[tokio::test]
+    async fn test_run_fails_if_any_listener_cannot_bind() {
+        let taken = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy_service = create_proxy_service(vec![]);
+
+        let server = TcpServer::new(proxy_service, free_addr().await.to_string())
+            .add_listener(taken.local_addr().unwrap().to_string());
 
-        // Verify cache is still there
-        let cached = public_ip_geo.read().await;
-        assert!(cached.is_some());
+        let result = tokio::time::timeout(Duration::from_secs(5), server.run()).await;
+        assert!(result.unwrap().is_err());
     }
 }
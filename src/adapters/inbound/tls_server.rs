@@ -3,20 +3,113 @@
 //! Accepts TLS-encrypted TCP connections and proxies them to backends.
 //! Supports certificate loading from files or self-signed generation for testing.
 
-use crate::application::ProxyService;
-use crate::domain::entities::GeoInfo;
-use crate::domain::ports::GeoResolver;
+use super::tcp_server::{connection_span, first_finished, log_access};
+use super::dial::DialOptions;
+use super::listener::ListenOptions;
+use super::public_ip::PublicIpGeo;
+use crate::application::{ProxyService, Unavailable};
+use crate::domain::value_objects::{CloseReason, ConnectionId};
+use crate::infrastructure::{AbortOnDrop, ShutdownController};
 use rustls::pki_types::{CertificateDer, PrivateKeyDer};
 use std::fs::File;
 use std::io::BufReader;
-use std::net::{IpAddr, SocketAddr};
+use std::net::SocketAddr;
 use std::path::Path;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::io::{self, AsyncWriteExt};
-use tokio::net::{TcpListener, TcpStream};
-use tokio::sync::RwLock;
+use tokio::net::TcpStream;
 use tokio_rustls::TlsAcceptor;
+use tracing::Instrument;
+
+/// Default time a client has to complete the TLS handshake.
+pub const DEFAULT_TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Most 0-RTT early data accepted per connection when early data is enabled.
+pub const MAX_EARLY_DATA_SIZE: u32 = 16 * 1024;
+
+/// Key algorithm of a generated self-signed certificate.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum KeyAlgorithm {
+    /// ECDSA on P-256 with SHA-256
+    #[default]
+    EcdsaP256,
+    /// ECDSA on P-384 with SHA-384
+    EcdsaP384,
+    /// Ed25519
+    Ed25519,
+}
+
+impl KeyAlgorithm {
+    fn signature_algorithm(self) -> &'static rcgen::SignatureAlgorithm {
+        match self {
+            Self::EcdsaP256 => &rcgen::PKCS_ECDSA_P256_SHA256,
+            Self::EcdsaP384 => &rcgen::PKCS_ECDSA_P384_SHA384,
+            Self::Ed25519 => &rcgen::PKCS_ED25519,
+        }
+    }
+}
+
+/// Parameters for a generated self-signed certificate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelfSignedParams {
+    /// Subject alternative names; IP addresses become IP SANs
+    pub subject_alt_names: Vec<String>,
+    /// Validity from the time of generation (None = rcgen's 1975-4096 window)
+    pub validity: Option<Duration>,
+    pub key_algorithm: KeyAlgorithm,
+}
+
+impl SelfSignedParams {
+    /// SANs for `domain`, `localhost` and `127.0.0.1`, with default validity and key.
+    pub fn for_domain(domain: &str) -> Self {
+        Self {
+            subject_alt_names: vec![
+                domain.to_string(),
+                "localhost".to_string(),
+                "127.0.0.1".to_string(),
+            ],
+            validity: None,
+            key_algorithm: KeyAlgorithm::default(),
+        }
+    }
+
+    /// Add a subject alternative name.
+    pub fn with_san(mut self, name: impl Into<String>) -> Self {
+        self.subject_alt_names.push(name.into());
+        self
+    }
+
+    /// Set how long the certificate is valid, starting now.
+    pub fn with_validity(mut self, validity: Duration) -> Self {
+        self.validity = Some(validity);
+        self
+    }
+
+    /// Set the key algorithm.
+    pub fn with_key_algorithm(mut self, key_algorithm: KeyAlgorithm) -> Self {
+        self.key_algorithm = key_algorithm;
+        self
+    }
+
+    /// Generate the certificate and its private key.
+    pub fn generate(&self) -> anyhow::Result<(CertificateDer<'static>, PrivateKeyDer<'static>)> {
+        let mut params = rcgen::CertificateParams::new(self.subject_alt_names.clone())?;
+        if let Some(validity) = self.validity {
+            let now = time::OffsetDateTime::now_utc();
+            params.not_before = now;
+            params.not_after = now + validity;
+        }
+
+        let key_pair = rcgen::KeyPair::generate_for(self.key_algorithm.signature_algorithm())?;
+        let cert = params.self_signed(&key_pair)?;
+
+        let cert_der = CertificateDer::from(cert.der().to_vec());
+        let key_der = PrivateKeyDer::try_from(key_pair.serialize_der())
+            .map_err(|e| anyhow::anyhow!("failed to serialize key: {:?}", e))?;
+        Ok((cert_der, key_der))
+    }
+}
 
 /// TLS Server configuration.
 #[derive(Clone)]
@@ -39,6 +132,44 @@ impl TlsConfig {
         Self::from_certs_and_key(certs, key)
     }
 
+    /// Load TLS config from a single PEM file holding the certificate chain
+    /// and the private key, in any order.
+    ///
+    /// The bundle must contain at least one certificate and exactly one
+    /// private key (PKCS#1, PKCS#8 or SEC1). Other PEM sections are ignored.
+    pub fn from_pem_bundle(path: &Path) -> anyhow::Result<Self> {
+        let file = File::open(path)?;
+
+        let mut certs: Vec<CertificateDer<'static>> = Vec::new();
+        let mut keys: Vec<PrivateKeyDer<'static>> = Vec::new();
+        for item in rustls_pemfile::read_all(&mut BufReader::new(file)) {
+            match item? {
+                rustls_pemfile::Item::X509Certificate(cert) => certs.push(cert),
+                rustls_pemfile::Item::Pkcs1Key(key) => keys.push(key.into()),
+                rustls_pemfile::Item::Pkcs8Key(key) => keys.push(key.into()),
+                rustls_pemfile::Item::Sec1Key(key) => keys.push(key.into()),
+                _ => {}
+            }
+        }
+
+        if certs.is_empty() {
+            anyhow::bail!("no certificate found in {}", path.display());
+        }
+        let key = match <[_; 1]>::try_from(keys) {
+            Ok([key]) => key,
+            Err(keys) if keys.is_empty() => {
+                anyhow::bail!("no private key found in {}", path.display())
+            }
+            Err(keys) => anyhow::bail!(
+                "expected one private key in {}, found {}",
+                path.display(),
+                keys.len()
+            ),
+        };
+
+        Self::from_certs_and_key(certs, key)
+    }
+
     /// Create TLS config from certificates and key.
     pub fn from_certs_and_key(
         certs: Vec<CertificateDer<'static>>,
@@ -53,20 +184,33 @@ impl TlsConfig {
         })
     }
 
+    /// Accept TLS 1.3 0-RTT early data from resuming clients (off by default).
+    ///
+    /// Early data is not protected against replay: an attacker can resend
+    /// it, so only enable this where the first bytes a client sends are safe
+    /// to process twice.
+    pub fn with_early_data(self, enable_early_data: bool) -> Self {
+        let mut config = rustls::ServerConfig::clone(self.acceptor.config());
+        config.max_early_data_size = if enable_early_data { MAX_EARLY_DATA_SIZE } else { 0 };
+        Self {
+            acceptor: TlsAcceptor::from(Arc::new(config)),
+        }
+    }
+
+    /// Whether 0-RTT early data is accepted.
+    pub fn early_data_enabled(&self) -> bool {
+        self.acceptor.config().max_early_data_size > 0
+    }
+
     /// Generate self-signed certificate for testing.
     pub fn self_signed(domain: &str) -> anyhow::Result<Self> {
-        let subject_alt_names = vec![
-            domain.to_string(),
-            "localhost".to_string(),
-            "127.0.0.1".to_string(),
-        ];
-
-        let cert = rcgen::generate_simple_self_signed(subject_alt_names)?;
-        let cert_der = CertificateDer::from(cert.cert.der().to_vec());
-        let key_der = PrivateKeyDer::try_from(cert.key_pair.serialize_der())
-            .map_err(|e| anyhow::anyhow!("failed to serialize key: {:?}", e))?;
+        Self::self_signed_with(SelfSignedParams::for_domain(domain))
+    }
 
-        Self::from_certs_and_key(vec![cert_der], key_der)
+    /// Generate a self-signed certificate with custom SANs, validity and key.
+    pub fn self_signed_with(params: SelfSignedParams) -> anyhow::Result<Self> {
+        let (cert, key) = params.generate()?;
+        Self::from_certs_and_key(vec![cert], key)
     }
 }
 
@@ -74,9 +218,14 @@ impl TlsConfig {
 pub struct TlsServer {
     proxy_service: Arc<ProxyService>,
     listen_addr: String,
-    geo_resolver: Option<Arc<dyn GeoResolver>>,
-    public_ip_geo: Arc<RwLock<Option<GeoInfo>>>,
+    public_ip_geo: Arc<PublicIpGeo>,
     tls_config: TlsConfig,
+    max_session: Option<Duration>,
+    handshake_timeout: Duration,
+    listen_options: ListenOptions,
+    dial_options: DialOptions,
+    shutdown: ShutdownController,
+    access_log: bool,
 }
 
 impl TlsServer {
@@ -84,18 +233,64 @@ impl TlsServer {
     pub fn new(
         proxy_service: Arc<ProxyService>,
         listen_addr: String,
-        geo_resolver: Option<Arc<dyn GeoResolver>>,
         tls_config: TlsConfig,
     ) -> Self {
         Self {
             proxy_service,
             listen_addr,
-            geo_resolver,
-            public_ip_geo: Arc::new(RwLock::new(None)),
+            public_ip_geo: Arc::new(PublicIpGeo::default()),
             tls_config,
+            max_session: None,
+            handshake_timeout: DEFAULT_TLS_HANDSHAKE_TIMEOUT,
+            listen_options: ListenOptions::default(),
+            dial_options: DialOptions::default(),
+            shutdown: ShutdownController::new(),
+            access_log: false,
         }
     }
 
+    /// Share a public IP geo lookup (used for loopback clients) with other listeners.
+    pub fn with_public_ip_geo(mut self, public_ip_geo: Arc<PublicIpGeo>) -> Self {
+        self.public_ip_geo = public_ip_geo;
+        self
+    }
+
+    /// Refuse new connections while `shutdown` is draining, and count active ones on it.
+    pub fn with_shutdown(mut self, shutdown: ShutdownController) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+
+    /// Log one access line per finished session (target `edge_proxy::access`).
+    pub fn with_access_log(mut self, access_log: bool) -> Self {
+        self.access_log = access_log;
+        self
+    }
+
+    /// Set how long a client may take to complete the TLS handshake.
+    pub fn with_handshake_timeout(mut self, handshake_timeout: Duration) -> Self {
+        self.handshake_timeout = handshake_timeout;
+        self
+    }
+
+    /// Set the socket options used when binding the listener.
+    pub fn with_listen_options(mut self, listen_options: ListenOptions) -> Self {
+        self.listen_options = listen_options;
+        self
+    }
+
+    /// Set the socket options used when connecting to backends.
+    pub fn with_dial_options(mut self, dial_options: DialOptions) -> Self {
+        self.dial_options = dial_options;
+        self
+    }
+
+    /// Cap the total duration of each proxied session (`None` disables the cap).
+    pub fn with_max_session(mut self, max_session: Option<Duration>) -> Self {
+        self.max_session = max_session;
+        self
+    }
+
     /// Run the TLS server.
     ///
     /// This function runs an infinite loop accepting connections.
@@ -103,83 +298,142 @@ impl TlsServer {
     /// as they are async error paths that are difficult to test deterministically.
     #[cfg_attr(coverage_nightly, coverage(off))]
     pub async fn run(&self) -> anyhow::Result<()> {
-        let listener = TcpListener::bind(&self.listen_addr).await?;
+        let listener = self.listen_options.bind_tcp(&self.listen_addr).await?;
         tracing::info!("edgeProxy TLS listening on {}", self.listen_addr);
 
         loop {
             let (stream, addr) = listener.accept().await?;
+            if self.shutdown.is_draining() {
+                tracing::debug!("draining, refusing TLS connection from {}", addr);
+                let _ = stream.set_zero_linger();
+                continue;
+            }
             let service = self.proxy_service.clone();
-            let geo_resolver = self.geo_resolver.clone();
             let public_ip_geo = self.public_ip_geo.clone();
             let acceptor = self.tls_config.acceptor.clone();
-
-            tokio::spawn(async move {
-                // Perform TLS handshake
-                match acceptor.accept(stream).await {
-                    Ok(tls_stream) => {
-                        if let Err(e) = Self::handle_connection(
-                            service,
-                            tls_stream,
-                            addr,
-                            geo_resolver,
-                            public_ip_geo,
-                        )
-                        .await
-                        {
-                            tracing::error!("TLS connection error from {}: {:?}", addr, e);
-                        }
-                    }
-                    Err(e) => {
-                        tracing::debug!("TLS handshake failed from {}: {:?}", addr, e);
+            let max_session = self.max_session;
+            let handshake_timeout = self.handshake_timeout;
+            let dial_options = self.dial_options.clone();
+            let access_log = self.access_log;
+            let conn_id = ConnectionId::next();
+
+            self.shutdown.spawn_connection(
+                async move {
+                    tracing::debug!("accepted TLS connection from {}", addr);
+                    let Some(tls_stream) =
+                        Self::handshake(&acceptor, stream, addr, handshake_timeout).await
+                    else {
+                        return;
+                    };
+
+                    if let Err(e) = Self::handle_connection(
+                        service,
+                        tls_stream,
+                        addr,
+                        public_ip_geo,
+                        max_session,
+                        dial_options,
+                        conn_id,
+                        access_log,
+                    )
+                    .await
+                    {
+                        tracing::error!("TLS connection error from {}: {:?}", addr, e);
                     }
                 }
-            });
+                .instrument(connection_span(conn_id, addr)),
+            );
         }
     }
 
-    /// Handle a single TLS client connection.
+    /// Perform the TLS handshake, giving up after `timeout`.
+    ///
+    /// Returns None (dropping the connection) if the handshake fails or
+    /// the client doesn't complete it in time.
+    async fn handshake(
+        acceptor: &TlsAcceptor,
+        stream: TcpStream,
+        client_addr: SocketAddr,
+        timeout: Duration,
+    ) -> Option<tokio_rustls::server::TlsStream<TcpStream>> {
+        match tokio::time::timeout(timeout, acceptor.accept(stream)).await {
+            Ok(Ok(tls_stream)) => Some(tls_stream),
+            Ok(Err(e)) => {
+                tracing::debug!("TLS handshake failed from {}: {:?}", client_addr, e);
+                None
+            }
+            Err(_) => {
+                tracing::debug!(
+                    "TLS handshake from {} timed out after {:?}",
+                    client_addr,
+                    timeout
+                );
+                None
+            }
+        }
+    }
+
+    /// Take the 0-RTT early data a client sent along with its handshake.
+    ///
+    /// Returns `None` when the connection did not use early data. The stream
+    /// never yields early data through `AsyncRead`, so it has to be drained
+    /// here and forwarded to the backend ahead of the rest.
+    fn take_early_data(tls_stream: &mut tokio_rustls::server::TlsStream<TcpStream>) -> Option<Vec<u8>> {
+        let mut reader = tls_stream.get_mut().1.early_data()?;
+        let mut data = Vec::new();
+        std::io::Read::read_to_end(&mut reader, &mut data).ok()?;
+        Some(data)
+    }
+
+    /// Handle a single TLS client connection, after the handshake.
+    ///
+    /// Like the plain TCP path, this runs inside the connection's span and
+    /// optionally ends a proxied session with an access log entry.
+    #[allow(clippy::too_many_arguments)]
     async fn handle_connection(
         service: Arc<ProxyService>,
-        tls_stream: tokio_rustls::server::TlsStream<TcpStream>,
+        mut tls_stream: tokio_rustls::server::TlsStream<TcpStream>,
         client_addr: SocketAddr,
-        geo_resolver: Option<Arc<dyn GeoResolver>>,
-        public_ip_geo: Arc<RwLock<Option<GeoInfo>>>,
+        public_ip_geo: Arc<PublicIpGeo>,
+        max_session: Option<Duration>,
+        dial_options: DialOptions,
+        conn_id: ConnectionId,
+        access_log: bool,
     ) -> anyhow::Result<()> {
+        let started = Instant::now();
         let client_ip = client_addr.ip();
+        let early_data = Self::take_early_data(&mut tls_stream);
 
-        // For localhost connections, use public IP for geo resolution
-        let client_geo = if client_ip.is_loopback() {
-            Self::resolve_localhost_geo(geo_resolver, public_ip_geo).await
-        } else {
-            service.resolve_geo(client_ip)
-        };
+        // Loopback clients (IPv4 or IPv6) are located per the loopback policy
+        let client_geo = public_ip_geo.client_geo(&service, client_ip).await;
 
         // Resolve backend
         let backend = match service.resolve_backend_with_geo(client_ip, client_geo).await {
             Some(b) => b,
             None => {
-                tracing::warn!("no backend available for TLS client {}", client_ip);
+                if service.unavailable(None).await == Unavailable::Overloaded {
+                    // Reset rather than close so the client fails fast
+                    tracing::warn!("all backends at hard limit, rejecting TLS client {}", client_ip);
+                    tls_stream.get_ref().0.set_zero_linger()?;
+                } else {
+                    tracing::warn!("no backend available for TLS client {}", client_ip);
+                }
                 return Ok(());
             }
         };
 
-        // Format backend address
-        let backend_addr = if backend.wg_ip.contains(':') {
-            format!("[{}]:{}", backend.wg_ip, backend.port)
-        } else {
-            format!("{}:{}", backend.wg_ip, backend.port)
-        };
+        let backend_addr = backend.addr();
 
         tracing::debug!(
-            "TLS proxying {} -> {} ({})",
+            "TLS proxying {} -> {} ({}) early_data={}",
             client_ip,
             backend.id,
-            backend_addr
+            backend_addr,
+            early_data.is_some()
         );
 
-        // Connect to backend and measure RTT
-        let t0 = Instant::now();
-        let backend_stream = match TcpStream::connect(&backend_addr).await {
+        // Connect to backend, timing each phase of the dial
+        let (mut backend_stream, timings) = match dial_options.connect_timed(&backend_addr).await {
             Ok(s) => s,
             Err(e) => {
                 tracing::error!(
@@ -192,109 +446,85 @@ impl TlsServer {
                 return Ok(());
             }
         };
-        let rtt_ms = t0.elapsed().as_millis() as u64;
+
+        // Early data precedes everything the client sends after the handshake
+        if let Some(data) = early_data.filter(|data| !data.is_empty()) {
+            backend_stream.write_all(&data).await?;
+        }
 
         // Record metrics
         let backend_id = backend.id.clone();
-        service.record_connection_start(&backend_id);
-        service.record_rtt(&backend_id, rtt_ms);
+        service.record_connection_start(&backend_id, &backend.app);
+        timings.record(&service, &backend_id);
 
         // Perform bidirectional copy (TLS client <-> plain backend)
-        let result = Self::proxy_bidirectional(tls_stream, backend_stream).await;
-
-        // Record connection end
-        service.record_connection_end(&backend_id);
+        let result = Self::proxy_bidirectional(tls_stream, backend_stream, max_session).await;
+        let reason = *result.as_ref().unwrap_or(&CloseReason::Error);
 
-        // Propagate proxy errors
-        result.map_err(|e| anyhow::anyhow!("TLS {} proxy error: {:?}", backend_id, e))
-    }
-
-    /// Resolve geo for localhost connections using public IP.
-    ///
-    /// Excluded from coverage as it depends on external network calls.
-    #[cfg_attr(coverage_nightly, coverage(off))]
-    async fn resolve_localhost_geo(
-        geo_resolver: Option<Arc<dyn GeoResolver>>,
-        public_ip_geo: Arc<RwLock<Option<GeoInfo>>>,
-    ) -> Option<GeoInfo> {
-        {
-            let cached = public_ip_geo.read().await;
-            if cached.is_some() {
-                return cached.clone();
-            }
+        if reason == CloseReason::SessionTimeout {
+            tracing::info!("closing TLS {} -> {}: reason={}", client_ip, backend_id, reason);
+            service.record_session_timeout(&backend_id);
+        } else {
+            tracing::debug!("closed TLS {} -> {}: reason={}", client_ip, backend_id, reason);
         }
 
-        if let Some(public_ip) = Self::fetch_public_ip().await {
-            let geo_info = geo_resolver.as_ref().and_then(|g| g.resolve(public_ip));
-
-            if geo_info.is_some() {
-                let mut cached = public_ip_geo.write().await;
-                *cached = geo_info.clone();
-            }
-
-            return geo_info;
+        // Record connection end
+        service.record_connection_end(&backend_id, reason);
+        if access_log {
+            log_access(conn_id, client_addr, &backend, reason, started);
         }
 
-        None
-    }
-
-    /// Fetch public IP from AWS checkip service.
-    ///
-    /// Excluded from coverage as it makes external network calls.
-    #[cfg_attr(coverage_nightly, coverage(off))]
-    async fn fetch_public_ip() -> Option<IpAddr> {
-        let client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(5))
-            .build()
-            .ok()?;
-
-        let resp = client
-            .get("https://checkip.amazonaws.com/")
-            .send()
-            .await
-            .ok()?;
-
-        let text = resp.text().await.ok()?.trim().to_string();
-
-        match text.parse::<IpAddr>() {
-            Ok(ip) => {
-                tracing::debug!("public IP detected: {}", ip);
-                Some(ip)
-            }
-            Err(_) => None,
-        }
+        // Propagate proxy errors
+        result
+            .map(|_| ())
+            .map_err(|e| anyhow::anyhow!("TLS {} proxy error: {:?}", backend_id, e))
     }
 
     /// Perform bidirectional copy between TLS client and plain backend.
+    ///
+    /// When `max_session` is set, both directions are aborted once the
+    /// session has run that long. Otherwise the side that finished first
+    /// decides the close reason.
     async fn proxy_bidirectional(
         tls_stream: tokio_rustls::server::TlsStream<TcpStream>,
         backend_stream: TcpStream,
-    ) -> io::Result<()> {
+        max_session: Option<Duration>,
+    ) -> io::Result<CloseReason> {
         let (mut tls_read, mut tls_write) = tokio::io::split(tls_stream);
         let (mut backend_read, mut backend_write) = backend_stream.into_split();
 
-        let client_to_backend = tokio::spawn(async move {
+        let mut client_to_backend = AbortOnDrop(tokio::spawn(async move {
             let result = io::copy(&mut tls_read, &mut backend_write).await;
             let _ = backend_write.shutdown().await;
             result
-        });
+        }));
 
-        let backend_to_client = tokio::spawn(async move {
+        let mut backend_to_client = AbortOnDrop(tokio::spawn(async move {
             let result = io::copy(&mut backend_read, &mut tls_write).await;
             let _ = tls_write.shutdown().await;
             result
-        });
+        }));
 
-        let (c2b, b2c) = tokio::join!(client_to_backend, backend_to_client);
-
-        if let Ok(Err(e)) = c2b {
-            tracing::trace!("TLS client->backend copy error: {:?}", e);
-        }
-        if let Ok(Err(e)) = b2c {
-            tracing::trace!("TLS backend->client copy error: {:?}", e);
+        let both = async {
+            tokio::select! {
+                c2b = &mut client_to_backend => {
+                    let _ = (&mut backend_to_client).await;
+                    first_finished(CloseReason::ClientClosed, c2b)
+                }
+                b2c = &mut backend_to_client => {
+                    let _ = (&mut client_to_backend).await;
+                    first_finished(CloseReason::BackendClosed, b2c)
+                }
+            }
+        };
+        match max_session {
+            Some(max) => match tokio::time::timeout(max, both).await {
+                Ok(reason) => Ok(reason),
+                // Dropping the guards aborts both directions, closing the sockets
+                Err(_) => Ok(CloseReason::SessionTimeout),
+            },
+            None => Ok(both.await),
         }
-
-        Ok(())
     }
 }
 
@@ -302,9 +532,12 @@ impl TlsServer {
 #[cfg_attr(coverage_nightly, coverage(off))]
 mod tests {
     use super::*;
-    use crate::adapters::outbound::{DashMapBindingRepository, DashMapMetricsStore};
-    use crate::domain::entities::Backend;
-    use crate::domain::ports::BackendRepository;
+    use tokio::net::TcpListener;
+    use crate::adapters::outbound::{
+        DashMapBindingRepository, DashMapMetricsStore, NullGeoResolver, StaticGeoResolver,
+    };
+    use crate::domain::entities::{Backend, GeoInfo};
+    use crate::domain::ports::{BackendRepository, GeoResolver, MetricsStore};
     use crate::domain::value_objects::RegionCode;
     use async_trait::async_trait;
     use std::sync::Once;
@@ -359,6 +592,9 @@ mod tests {
             weight: 2,
             soft_limit: 100,
             hard_limit: 150,
+            draining: false,
+            maintenance: false,
+            priority: 0,
         }
     }
 
@@ -404,6 +640,90 @@ mod tests {
         assert!(config.is_ok());
     }
 
+    // ===== Self-Signed Params Tests =====
+
+    /// Whether `cert` is valid for `name` (a DNS name or IP address).
+    fn cert_valid_for(cert: &CertificateDer<'_>, name: &str) -> bool {
+        let cert = webpki::EndEntityCert::try_from(cert).unwrap();
+        let name = rustls::pki_types::ServerName::try_from(name).unwrap();
+        cert.verify_is_valid_for_subject_name(&name).is_ok()
+    }
+
+    #[test]
+    fn test_self_signed_params_for_domain() {
+        let params = SelfSignedParams::for_domain("edge.internal");
+        assert_eq!(
+            params.subject_alt_names,
+            vec!["edge.internal", "localhost", "127.0.0.1"]
+        );
+        assert_eq!(params.validity, None);
+        assert_eq!(params.key_algorithm, KeyAlgorithm::EcdsaP256);
+    }
+
+    #[test]
+    fn test_self_signed_params_custom_sans_in_certificate() {
+        let params = SelfSignedParams::for_domain("edge.internal")
+            .with_san("api.example.com")
+            .with_san("10.50.1.1");
+        let (cert, _) = params.generate().unwrap();
+
+        for name in ["edge.internal", "localhost", "127.0.0.1", "api.example.com", "10.50.1.1"] {
+            assert!(cert_valid_for(&cert, name), "{} missing from certificate", name);
+        }
+        assert!(!cert_valid_for(&cert, "other.example.com"));
+        assert!(!cert_valid_for(&cert, "10.50.1.2"));
+    }
+
+    #[test]
+    fn test_self_signed_params_only_listed_sans() {
+        let params = SelfSignedParams {
+            subject_alt_names: vec!["only.example.com".to_string()],
+            ..SelfSignedParams::for_domain("unused")
+        };
+        let (cert, _) = params.generate().unwrap();
+
+        assert!(cert_valid_for(&cert, "only.example.com"));
+        assert!(!cert_valid_for(&cert, "localhost"));
+    }
+
+    #[test]
+    fn test_self_signed_params_validity() {
+        let (cert, _) = SelfSignedParams::for_domain("edge.internal")
+            .with_validity(Duration::from_secs(86400))
+            .generate()
+            .unwrap();
+
+        let anchors = [webpki::anchor_from_trusted_cert(&cert).unwrap()];
+        let end_entity = webpki::EndEntityCert::try_from(&cert).unwrap();
+        let verify_at = |offset: Duration| {
+            let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap();
+            end_entity.verify_for_usage(
+                webpki::ALL_VERIFICATION_ALGS,
+                &anchors,
+                &[],
+                rustls::pki_types::UnixTime::since_unix_epoch(now + offset),
+                webpki::KeyUsage::server_auth(),
+                None,
+                None,
+            )
+        };
+
+        assert!(verify_at(Duration::from_secs(60)).is_ok());
+        assert!(matches!(
+            verify_at(Duration::from_secs(2 * 86400)),
+            Err(webpki::Error::CertExpired { .. })
+        ));
+    }
+
+    #[test]
+    fn test_self_signed_params_key_algorithms() {
+        setup_crypto_provider();
+        for key_algorithm in [KeyAlgorithm::EcdsaP256, KeyAlgorithm::EcdsaP384, KeyAlgorithm::Ed25519] {
+            let params = SelfSignedParams::for_domain("edge.internal").with_key_algorithm(key_algorithm);
+            assert!(TlsConfig::self_signed_with(params).is_ok(), "{:?}", key_algorithm);
+        }
+    }
+
     #[test]
     fn test_from_pem_files_nonexistent() {
         setup_crypto_provider();
@@ -422,42 +742,30 @@ mod tests {
         let server = TlsServer::new(
             proxy_service,
             "0.0.0.0:8443".to_string(),
-            None,
             tls_config,
         );
         assert_eq!(server.listen_addr, "0.0.0.0:8443");
     }
 
     #[test]
-    fn test_tls_server_new_with_custom_config() {
+    fn test_tls_server_with_public_ip_geo() {
         setup_crypto_provider();
         let proxy_service = create_proxy_service(vec![create_test_backend("test-1")]);
-        let tls_config = TlsConfig::self_signed("custom.domain").unwrap();
-        let server = TlsServer::new(
-            proxy_service,
-            "127.0.0.1:9443".to_string(),
-            None,
-            tls_config,
-        );
-        assert_eq!(server.listen_addr, "127.0.0.1:9443");
-        assert!(server.geo_resolver.is_none());
-    }
+        let tls_config = TlsConfig::self_signed("test.internal").unwrap();
+        let public_ip_geo = Arc::new(PublicIpGeo::default());
 
-    #[tokio::test]
-    async fn test_resolve_localhost_geo_returns_none_without_resolver() {
-        let public_ip_geo = Arc::new(RwLock::new(None));
-        let result = TlsServer::resolve_localhost_geo(None, public_ip_geo).await;
-        assert!(result.is_none());
+        let server = TlsServer::new(proxy_service, "0.0.0.0:8443".to_string(), tls_config)
+            .with_public_ip_geo(public_ip_geo.clone());
+        assert!(Arc::ptr_eq(&server.public_ip_geo, &public_ip_geo));
     }
 
-    #[tokio::test]
-    async fn test_resolve_localhost_geo_returns_cached() {
-        let cached_geo = GeoInfo::new("DE".to_string(), RegionCode::Europe);
-        let public_ip_geo = Arc::new(RwLock::new(Some(cached_geo.clone())));
-
-        let result = TlsServer::resolve_localhost_geo(None, public_ip_geo).await;
-        assert!(result.is_some());
-        assert_eq!(result.unwrap().country, "DE");
+    #[test]
+    fn test_tls_server_new_with_custom_config() {
+        setup_crypto_provider();
+        let proxy_service = create_proxy_service(vec![create_test_backend("test-1")]);
+        let tls_config = TlsConfig::self_signed("custom.domain").unwrap();
+        let server = TlsServer::new(proxy_service, "127.0.0.1:9443".to_string(), tls_config);
+        assert_eq!(server.listen_addr, "127.0.0.1:9443");
     }
 
     #[tokio::test]
@@ -473,13 +781,12 @@ mod tests {
             weight: 2,
             soft_limit: 100,
             hard_limit: 150,
+            draining: false,
+            maintenance: false,
+            priority: 0,
         };
 
-        let backend_addr = if backend.wg_ip.contains(':') {
-            format!("[{}]:{}", backend.wg_ip, backend.port)
-        } else {
-            format!("{}:{}", backend.wg_ip, backend.port)
-        };
+        let backend_addr = backend.addr();
 
         assert_eq!(backend_addr, "10.0.0.1:8080");
     }
@@ -497,13 +804,12 @@ mod tests {
             weight: 2,
             soft_limit: 100,
             hard_limit: 150,
+            draining: false,
+            maintenance: false,
+            priority: 0,
         };
 
-        let backend_addr = if backend.wg_ip.contains(':') {
-            format!("[{}]:{}", backend.wg_ip, backend.port)
-        } else {
-            format!("{}:{}", backend.wg_ip, backend.port)
-        };
+        let backend_addr = backend.addr();
 
         assert_eq!(backend_addr, "[2001:db8::1]:8080");
     }
@@ -517,6 +823,98 @@ mod tests {
         drop(cloned);
     }
 
+    // ===== Early Data Tests =====
+
+    #[test]
+    fn test_early_data_off_by_default() {
+        setup_crypto_provider();
+        let config = TlsConfig::self_signed("test.internal").unwrap();
+        assert!(!config.early_data_enabled());
+        assert_eq!(config.acceptor.config().max_early_data_size, 0);
+    }
+
+    #[test]
+    fn test_with_early_data_configures_acceptance() {
+        setup_crypto_provider();
+        let config = TlsConfig::self_signed("test.internal").unwrap().with_early_data(true);
+        assert!(config.early_data_enabled());
+        assert_eq!(config.acceptor.config().max_early_data_size, MAX_EARLY_DATA_SIZE);
+
+        assert!(!config.with_early_data(false).early_data_enabled());
+    }
+
+    /// Connect twice with a blocking client; the second connection resumes
+    /// the first session and sends `payload` as early data if it may.
+    fn resume_with_early_data(addr: SocketAddr, payload: &'static [u8]) -> bool {
+        use rustls::pki_types::ServerName;
+        use std::io::{Read, Write};
+
+        let mut config = rustls::ClientConfig::builder()
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(danger::NoCertificateVerification::new(
+                rustls::crypto::ring::default_provider(),
+            )))
+            .with_no_client_auth();
+        config.enable_early_data = true;
+        let config = Arc::new(config);
+        let name = ServerName::try_from("test.internal").unwrap();
+        let mut byte = [0u8; 1];
+
+        // The first connection only collects a session ticket
+        let mut conn = rustls::ClientConnection::new(config.clone(), name.clone()).unwrap();
+        let mut sock = std::net::TcpStream::connect(addr).unwrap();
+        rustls::Stream::new(&mut conn, &mut sock).read_exact(&mut byte).unwrap();
+
+        let mut conn = rustls::ClientConnection::new(config, name).unwrap();
+        let mut sock = std::net::TcpStream::connect(addr).unwrap();
+        if let Some(mut early) = conn.early_data() {
+            early.write_all(payload).unwrap();
+        }
+        rustls::Stream::new(&mut conn, &mut sock).read_exact(&mut byte).unwrap();
+        conn.is_early_data_accepted()
+    }
+
+    /// Accept two connections and collect the early data each one used.
+    async fn accept_early_data(listener: TcpListener, tls_config: TlsConfig) -> Vec<Option<Vec<u8>>> {
+        let mut seen = Vec::new();
+        for _ in 0..2 {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut tls_stream = tls_config.acceptor.accept(stream).await.unwrap();
+            seen.push(TlsServer::take_early_data(&mut tls_stream));
+            tls_stream.write_all(b"k").await.unwrap();
+            tls_stream.flush().await.unwrap();
+        }
+        seen
+    }
+
+    #[tokio::test]
+    async fn test_take_early_data_from_resumed_connection() {
+        setup_crypto_provider();
+        let tls_config = TlsConfig::self_signed("test.internal").unwrap().with_early_data(true);
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let client = tokio::task::spawn_blocking(move || resume_with_early_data(addr, b"GET / 0rtt"));
+        let seen = accept_early_data(listener, tls_config).await;
+
+        assert!(client.await.unwrap());
+        assert_eq!(seen, vec![None, Some(b"GET / 0rtt".to_vec())]);
+    }
+
+    #[tokio::test]
+    async fn test_take_early_data_none_when_disabled() {
+        setup_crypto_provider();
+        let tls_config = TlsConfig::self_signed("test.internal").unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let client = tokio::task::spawn_blocking(move || resume_with_early_data(addr, b"GET / 0rtt"));
+        let seen = accept_early_data(listener, tls_config).await;
+
+        assert!(!client.await.unwrap());
+        assert_eq!(seen, vec![None, None]);
+    }
+
     // ===== Integration Tests with Mock Backend =====
 
     #[tokio::test]
@@ -558,13 +956,16 @@ mod tests {
 
         let result = tokio::time::timeout(Duration::from_secs(2), async {
             if let Ok(tls_stream) = acceptor.accept(stream).await {
-                let public_ip_geo = Arc::new(RwLock::new(None));
+                let public_ip_geo = Arc::new(PublicIpGeo::default());
                 let _ = TlsServer::handle_connection(
                     proxy_service.clone(),
                     tls_stream,
                     client_addr,
-                    None,
                     public_ip_geo,
+                    None,
+                    DialOptions::default(),
+                    ConnectionId::next(),
+                    false,
                 )
                 .await;
             }
@@ -575,6 +976,68 @@ mod tests {
         client_handle.abort();
     }
 
+    #[tokio::test]
+    async fn test_handle_connection_overloaded_resets_client() {
+        setup_crypto_provider();
+        let metrics = Arc::new(DashMapMetricsStore::new());
+        let mut backend = create_test_backend("full");
+        backend.hard_limit = 1;
+        metrics.increment_connections("full");
+        let proxy_service = Arc::new(ProxyService::new(
+            Arc::new(MockBackendRepository::new(vec![backend])),
+            Arc::new(DashMapBindingRepository::new()),
+            None,
+            metrics.clone(),
+            RegionCode::Europe,
+        ));
+        let client_addr: SocketAddr = "192.168.1.100:12345".parse().unwrap();
+        let tls_config = TlsConfig::self_signed("test.internal").unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let listen_addr = listener.local_addr().unwrap();
+
+        let client_handle = tokio::spawn(async move {
+            use rustls::pki_types::ServerName;
+            use tokio::io::AsyncReadExt;
+            use tokio_rustls::TlsConnector;
+
+            let client_config = rustls::ClientConfig::builder()
+                .dangerous()
+                .with_custom_certificate_verifier(Arc::new(danger::NoCertificateVerification::new(
+                    rustls::crypto::ring::default_provider(),
+                )))
+                .with_no_client_auth();
+            let connector = TlsConnector::from(Arc::new(client_config));
+            let stream = TcpStream::connect(listen_addr).await.unwrap();
+            let server_name = ServerName::try_from("test.internal").unwrap();
+            let mut tls = connector.connect(server_name, stream).await.unwrap();
+            let mut buf = [0u8; 16];
+            tls.read(&mut buf).await
+        });
+
+        let (stream, _) = listener.accept().await.unwrap();
+        let tls_stream = tls_config.acceptor.clone().accept(stream).await.unwrap();
+        TlsServer::handle_connection(
+            proxy_service,
+            tls_stream,
+            client_addr,
+            Arc::new(PublicIpGeo::default()),
+            None,
+            DialOptions::default(),
+            ConnectionId::next(),
+            false,
+        )
+        .await
+        .unwrap();
+
+        // The client gets no data, only the reset
+        let read = tokio::time::timeout(Duration::from_secs(5), client_handle)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(read.is_err());
+        assert_eq!(metrics.get_overload_rejections(), 1);
+    }
+
     #[tokio::test]
     async fn test_handle_connection_with_backend() {
         setup_crypto_provider();
@@ -607,6 +1070,9 @@ mod tests {
             weight: 2,
             soft_limit: 100,
             hard_limit: 150,
+            draining: false,
+            maintenance: false,
+            priority: 0,
         };
 
         let proxy_service = create_proxy_service(vec![backend]);
@@ -644,14 +1110,17 @@ mod tests {
 
         let result = tokio::time::timeout(Duration::from_secs(2), async {
             if let Ok(tls_stream) = acceptor.accept(stream).await {
-                let public_ip_geo = Arc::new(RwLock::new(None));
+                let public_ip_geo = Arc::new(PublicIpGeo::default());
                 let client_addr: SocketAddr = "192.168.1.100:12345".parse().unwrap();
                 let _ = TlsServer::handle_connection(
                     proxy_service.clone(),
                     tls_stream,
                     client_addr,
-                    None,
                     public_ip_geo,
+                    None,
+                    DialOptions::default(),
+                    ConnectionId::next(),
+                    false,
                 )
                 .await;
             }
@@ -680,6 +1149,9 @@ mod tests {
             weight: 2,
             soft_limit: 100,
             hard_limit: 150,
+            draining: false,
+            maintenance: false,
+            priority: 0,
         };
 
         let proxy_service = create_proxy_service(vec![backend]);
@@ -710,15 +1182,18 @@ mod tests {
 
         let result = tokio::time::timeout(Duration::from_secs(2), async {
             if let Ok(tls_stream) = acceptor.accept(stream).await {
-                let public_ip_geo = Arc::new(RwLock::new(None));
+                let public_ip_geo = Arc::new(PublicIpGeo::default());
                 let client_addr: SocketAddr = "192.168.1.100:12345".parse().unwrap();
                 // This should handle the connection error gracefully
                 let _ = TlsServer::handle_connection(
                     proxy_service.clone(),
                     tls_stream,
                     client_addr,
-                    None,
                     public_ip_geo,
+                    None,
+                    DialOptions::default(),
+                    ConnectionId::next(),
+                    false,
                 )
                 .await;
             }
@@ -759,6 +1234,9 @@ mod tests {
             weight: 2,
             soft_limit: 100,
             hard_limit: 150,
+            draining: false,
+            maintenance: false,
+            priority: 0,
         };
 
         let proxy_service = create_proxy_service(vec![backend]);
@@ -794,15 +1272,18 @@ mod tests {
 
         let result = tokio::time::timeout(Duration::from_secs(2), async {
             if let Ok(tls_stream) = acceptor.accept(stream).await {
-                let public_ip_geo = Arc::new(RwLock::new(None));
+                let public_ip_geo = Arc::new(PublicIpGeo::default());
                 // Use loopback address as client
                 let client_addr: SocketAddr = "127.0.0.1:12345".parse().unwrap();
                 let _ = TlsServer::handle_connection(
                     proxy_service.clone(),
                     tls_stream,
                     client_addr,
-                    None,
                     public_ip_geo,
+                    None,
+                    DialOptions::default(),
+                    ConnectionId::next(),
+                    false,
                 )
                 .await;
             }
@@ -845,6 +1326,9 @@ mod tests {
             weight: 2,
             soft_limit: 100,
             hard_limit: 150,
+            draining: false,
+            maintenance: false,
+            priority: 0,
         };
 
         let proxy_service = create_proxy_service(vec![backend]);
@@ -858,7 +1342,6 @@ mod tests {
         let server = TlsServer::new(
             proxy_service,
             listen_addr.to_string(),
-            None,
             tls_config.clone(),
         );
 
@@ -919,6 +1402,9 @@ mod tests {
             weight: 2,
             soft_limit: 100,
             hard_limit: 150,
+            draining: false,
+            maintenance: false,
+            priority: 0,
         };
 
         let proxy_service = create_proxy_service(vec![backend]);
@@ -949,14 +1435,17 @@ mod tests {
 
         let result = tokio::time::timeout(Duration::from_secs(2), async {
             if let Ok(tls_stream) = acceptor.accept(stream).await {
-                let public_ip_geo = Arc::new(RwLock::new(None));
+                let public_ip_geo = Arc::new(PublicIpGeo::default());
                 let client_addr: SocketAddr = "192.168.1.100:12345".parse().unwrap();
                 let _ = TlsServer::handle_connection(
                     proxy_service.clone(),
                     tls_stream,
                     client_addr,
-                    None,
                     public_ip_geo,
+                    None,
+                    DialOptions::default(),
+                    ConnectionId::next(),
+                    false,
                 )
                 .await;
             }
@@ -982,6 +1471,9 @@ mod tests {
             weight: 2,
             soft_limit: 100,
             hard_limit: 150,
+            draining: false,
+            maintenance: false,
+            priority: 0,
         };
 
         let proxy_service = create_proxy_service(vec![backend]);
@@ -994,7 +1486,6 @@ mod tests {
         let server = TlsServer::new(
             proxy_service,
             listen_addr.to_string(),
-            None,
             tls_config,
         );
 
@@ -1020,6 +1511,92 @@ mod tests {
         server_handle.abort();
     }
 
+    // ===== Handshake Timeout Tests =====
+
+    #[test]
+    fn test_tls_server_default_handshake_timeout() {
+        setup_crypto_provider();
+        let tls_config = TlsConfig::self_signed("test.internal").unwrap();
+        let server = TlsServer::new(
+            create_proxy_service(vec![]),
+            "127.0.0.1:0".to_string(),
+            tls_config,
+        );
+        assert_eq!(server.handshake_timeout, DEFAULT_TLS_HANDSHAKE_TIMEOUT);
+
+        let server = server.with_handshake_timeout(Duration::from_millis(250));
+        assert_eq!(server.handshake_timeout, Duration::from_millis(250));
+    }
+
+    #[tokio::test]
+    async fn test_handshake_times_out_without_client_hello() {
+        setup_crypto_provider();
+        let tls_config = TlsConfig::self_signed("test.internal").unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        // Raw TCP client that connects and never sends a ClientHello
+        let client = TcpStream::connect(addr).await.unwrap();
+        let (stream, client_addr) = listener.accept().await.unwrap();
+
+        let started = std::time::Instant::now();
+        let result = tokio::time::timeout(
+            Duration::from_secs(5),
+            TlsServer::handshake(
+                &tls_config.acceptor,
+                stream,
+                client_addr,
+                Duration::from_millis(200),
+            ),
+        )
+        .await
+        .expect("handshake should give up at its timeout");
+
+        assert!(result.is_none());
+        assert!(started.elapsed() >= Duration::from_millis(200));
+
+        drop(client);
+    }
+
+    #[tokio::test]
+    async fn test_handshake_completes_within_timeout() {
+        setup_crypto_provider();
+        let tls_config = TlsConfig::self_signed("test.internal").unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let client_handle = tokio::spawn(async move {
+            use rustls::pki_types::ServerName;
+            use tokio_rustls::TlsConnector;
+
+            let client_config = rustls::ClientConfig::builder()
+                .dangerous()
+                .with_custom_certificate_verifier(Arc::new(danger::NoCertificateVerification::new(
+                    rustls::crypto::ring::default_provider(),
+                )))
+                .with_no_client_auth();
+
+            let connector = TlsConnector::from(Arc::new(client_config));
+            let stream = TcpStream::connect(addr).await.unwrap();
+            let server_name = ServerName::try_from("test.internal").unwrap();
+            connector.connect(server_name, stream).await
+        });
+
+        let (stream, client_addr) = listener.accept().await.unwrap();
+        let result = TlsServer::handshake(
+            &tls_config.acceptor,
+            stream,
+            client_addr,
+            Duration::from_secs(5),
+        )
+        .await;
+
+        assert!(result.is_some());
+        assert!(client_handle.await.unwrap().is_ok());
+    }
+
     #[tokio::test]
     async fn test_proxy_bidirectional_with_errors() {
         setup_crypto_provider();
@@ -1079,7 +1656,7 @@ mod tests {
                 // This should handle errors gracefully
                 let result = tokio::time::timeout(
                     Duration::from_millis(500),
-                    TlsServer::proxy_bidirectional(tls_stream, backend_stream),
+                    TlsServer::proxy_bidirectional(tls_stream, backend_stream, None),
                 )
                 .await;
 
@@ -1092,40 +1669,6 @@ mod tests {
         backend_handle.abort();
     }
 
-    // Mock geo resolver for testing
-    struct MockGeoResolver {
-        geo_info: Option<GeoInfo>,
-    }
-
-    impl MockGeoResolver {
-        fn new(geo_info: Option<GeoInfo>) -> Self {
-            Self { geo_info }
-        }
-    }
-
-    impl GeoResolver for MockGeoResolver {
-        fn resolve(&self, _ip: IpAddr) -> Option<GeoInfo> {
-            self.geo_info.clone()
-        }
-    }
-
-    #[tokio::test]
-    async fn test_resolve_localhost_geo_with_resolver() {
-        let geo_info = GeoInfo::new("BR".to_string(), RegionCode::SouthAmerica);
-        let resolver = Arc::new(MockGeoResolver::new(Some(geo_info)));
-        let public_ip_geo = Arc::new(RwLock::new(None));
-
-        // This will try to fetch public IP which will fail/timeout in test env
-        let result = tokio::time::timeout(
-            Duration::from_millis(100),
-            TlsServer::resolve_localhost_geo(Some(resolver), public_ip_geo),
-        )
-        .await;
-
-        // Should timeout (fetch_public_ip takes time) or return None
-        assert!(result.is_err() || result.unwrap().is_none());
-    }
-
     #[tokio::test]
     async fn test_run_multiple_connections() {
         setup_crypto_provider();
@@ -1152,6 +1695,9 @@ mod tests {
             weight: 2,
             soft_limit: 100,
             hard_limit: 150,
+            draining: false,
+            maintenance: false,
+            priority: 0,
         };
 
         let proxy_service = create_proxy_service(vec![backend]);
@@ -1164,7 +1710,6 @@ mod tests {
         let server = TlsServer::new(
             proxy_service,
             listen_addr.to_string(),
-            None,
             tls_config.clone(),
         );
 
@@ -1207,6 +1752,74 @@ mod tests {
         backend_handle.abort();
     }
 
+    /// PEM section with `der` base64-encoded under `label`.
+    fn pem_section(label: &str, der: &[u8]) -> String {
+        format!("-----BEGIN {label}-----\n{}\n-----END {label}-----\n", base64_encode(der))
+    }
+
+    fn write_bundle(sections: &[String]) -> tempfile::NamedTempFile {
+        use std::io::Write;
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(sections.concat().as_bytes()).unwrap();
+        file
+    }
+
+    #[test]
+    fn test_from_pem_bundle_success() {
+        setup_crypto_provider();
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let ca = rcgen::generate_simple_self_signed(vec!["ca.local".to_string()]).unwrap();
+
+        // Key first, then a leaf and a second chain cert
+        let bundle = write_bundle(&[
+            pem_section("PRIVATE KEY", &cert.key_pair.serialize_der()),
+            pem_section("CERTIFICATE", cert.cert.der()),
+            pem_section("CERTIFICATE", ca.cert.der()),
+        ]);
+
+        assert!(TlsConfig::from_pem_bundle(bundle.path()).is_ok());
+    }
+
+    #[test]
+    fn test_from_pem_bundle_missing_key() {
+        setup_crypto_provider();
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let bundle = write_bundle(&[pem_section("CERTIFICATE", cert.cert.der())]);
+
+        let err = TlsConfig::from_pem_bundle(bundle.path()).err().unwrap();
+        assert!(err.to_string().contains("no private key found"));
+    }
+
+    #[test]
+    fn test_from_pem_bundle_missing_cert() {
+        setup_crypto_provider();
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let bundle = write_bundle(&[pem_section("PRIVATE KEY", &cert.key_pair.serialize_der())]);
+
+        let err = TlsConfig::from_pem_bundle(bundle.path()).err().unwrap();
+        assert!(err.to_string().contains("no certificate found"));
+    }
+
+    #[test]
+    fn test_from_pem_bundle_two_keys() {
+        setup_crypto_provider();
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let other = rcgen::generate_simple_self_signed(vec!["other".to_string()]).unwrap();
+        let bundle = write_bundle(&[
+            pem_section("CERTIFICATE", cert.cert.der()),
+            pem_section("PRIVATE KEY", &cert.key_pair.serialize_der()),
+            pem_section("PRIVATE KEY", &other.key_pair.serialize_der()),
+        ]);
+
+        let err = TlsConfig::from_pem_bundle(bundle.path()).err().unwrap();
+        assert!(err.to_string().contains("expected one private key"));
+    }
+
+    #[test]
+    fn test_from_pem_bundle_nonexistent() {
+        assert!(TlsConfig::from_pem_bundle(Path::new("/nonexistent/bundle.pem")).is_err());
+    }
+
     #[tokio::test]
     async fn test_from_pem_files_success() {
         setup_crypto_provider();
@@ -1335,14 +1948,14 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_mock_geo_resolver() {
+    async fn test_static_geo_resolver_default() {
         let geo = GeoInfo::new("BR".to_string(), RegionCode::SouthAmerica);
-        let resolver = MockGeoResolver::new(Some(geo.clone()));
+        let resolver = StaticGeoResolver::new(Some(geo.clone()));
         let result = resolver.resolve("8.8.8.8".parse().unwrap());
         assert!(result.is_some());
         assert_eq!(result.unwrap().country, "BR");
 
-        let resolver_none = MockGeoResolver::new(None);
+        let resolver_none = NullGeoResolver;
         let result_none = resolver_none.resolve("8.8.8.8".parse().unwrap());
         assert!(result_none.is_none());
     }
@@ -1362,14 +1975,13 @@ mod tests {
             weight: 2,
             soft_limit: 100,
             hard_limit: 150,
+            draining: false,
+            maintenance: false,
+            priority: 0,
         };
 
         // Directly test the formatting logic
-        let backend_addr = if backend.wg_ip.contains(':') {
-            format!("[{}]:{}", backend.wg_ip, backend.port)
-        } else {
-            format!("{}:{}", backend.wg_ip, backend.port)
-        };
+        let backend_addr = backend.addr();
         assert_eq!(backend_addr, "[2001:db8::1]:8080");
     }
 
@@ -0,0 +1,759 @@
+//! UDP Server Adapter
+//!
+//! Relays UDP datagrams between clients and backends. UDP has no
+//! connections, so each client address gets a session of its own that
+//! ends once no datagram has gone either way for the session timeout.
+
+use super::listener::ListenOptions;
+use super::public_ip::PublicIpGeo;
+use super::tcp_server::{connection_span, log_access};
+use crate::application::ProxyService;
+use crate::domain::entities::Backend;
+use crate::domain::value_objects::{CloseReason, ConnectionId};
+use crate::infrastructure::ShutdownController;
+use dashmap::mapref::entry::Entry;
+use dashmap::DashMap;
+use std::io;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::net::UdpSocket;
+use tracing::Instrument;
+
+/// Receive buffer size, enough for any UDP payload.
+const MAX_DATAGRAM: usize = 65_535;
+
+/// Idle time after which a session ends, unless configured otherwise.
+pub const DEFAULT_UDP_SESSION_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Datagrams held per client while its session opens; later ones are dropped.
+const MAX_QUEUED_DATAGRAMS: usize = 64;
+
+/// One client's relay to its backend.
+struct UdpSession {
+    backend: Backend,
+    /// Socket connected to the backend, used by this client only
+    upstream: UdpSocket,
+    started: Instant,
+    /// Time since `started` of the last datagram either way, in milliseconds
+    last_active_ms: AtomicU64,
+}
+
+impl UdpSession {
+    fn new(backend: Backend, upstream: UdpSocket) -> Self {
+        Self {
+            backend,
+            upstream,
+            started: Instant::now(),
+            last_active_ms: AtomicU64::new(0),
+        }
+    }
+
+    /// Record traffic, pushing back the idle timeout.
+    fn touch(&self) {
+        let now_ms = self.started.elapsed().as_millis() as u64;
+        self.last_active_ms.fetch_max(now_ms, Ordering::Relaxed);
+    }
+
+    /// Time since the last datagram either way.
+    fn idle_for(&self) -> Duration {
+        let last = Duration::from_millis(self.last_active_ms.load(Ordering::Relaxed));
+        self.started.elapsed().saturating_sub(last)
+    }
+}
+
+/// A client's entry in the session table.
+enum SessionSlot {
+    /// Backend still being picked; the client's datagrams wait here
+    Opening(Vec<Vec<u8>>),
+    Open(Arc<UdpSession>),
+}
+
+impl SessionSlot {
+    /// Whether this slot holds `session`.
+    fn holds(&self, session: &Arc<UdpSession>) -> bool {
+        matches!(self, Self::Open(open) if Arc::ptr_eq(open, session))
+    }
+}
+
+/// UDP Server - inbound adapter relaying datagrams to backends.
+///
+/// This adapter:
+/// 1. Receives datagrams on one socket
+/// 2. Opens a session per client address, picking its backend with
+///    ProxyService (so bindings and geo routing apply as for TCP)
+/// 3. Forwards the client's datagrams from a socket of the session's own,
+///    connected to the backend
+/// 4. Sends the backend's replies back to that client only
+///
+/// A session ends after `session_timeout` without traffic in either
+/// direction, which is recorded as the client closing it.
+pub struct UdpProxyServer {
+    proxy_service: Arc<ProxyService>,
+    listen_addr: String,
+    public_ip_geo: Arc<PublicIpGeo>,
+    session_timeout: Duration,
+    listen_options: ListenOptions,
+    shutdown: ShutdownController,
+    access_log: bool,
+    sessions: Arc<DashMap<SocketAddr, SessionSlot>>,
+}
+
+impl UdpProxyServer {
+    /// Create a new UDP server.
+    pub fn new(proxy_service: Arc<ProxyService>, listen_addr: String) -> Self {
+        Self {
+            proxy_service,
+            listen_addr,
+            public_ip_geo: Arc::new(PublicIpGeo::default()),
+            session_timeout: DEFAULT_UDP_SESSION_TIMEOUT,
+            listen_options: ListenOptions::default(),
+            shutdown: ShutdownController::new(),
+            access_log: false,
+            sessions: Arc::new(DashMap::new()),
+        }
+    }
+
+    /// End sessions after this long without a datagram either way.
+    pub fn with_session_timeout(mut self, session_timeout: Duration) -> Self {
+        self.session_timeout = session_timeout;
+        self
+    }
+
+    /// Set the socket options used when binding the UDP socket.
+    pub fn with_listen_options(mut self, listen_options: ListenOptions) -> Self {
+        self.listen_options = listen_options;
+        self
+    }
+
+    /// Share a public IP geo lookup (used for loopback clients) with other listeners.
+    pub fn with_public_ip_geo(mut self, public_ip_geo: Arc<PublicIpGeo>) -> Self {
+        self.public_ip_geo = public_ip_geo;
+        self
+    }
+
+    /// Refuse new sessions while `shutdown` is draining, and count active ones on it.
+    pub fn with_shutdown(mut self, shutdown: ShutdownController) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+
+    /// Log one access line per finished session (target `edge_proxy::access`).
+    pub fn with_access_log(mut self, access_log: bool) -> Self {
+        self.access_log = access_log;
+        self
+    }
+
+    /// Number of open sessions.
+    pub fn session_count(&self) -> usize {
+        self.sessions
+            .iter()
+            .filter(|slot| matches!(slot.value(), SessionSlot::Open(_)))
+            .count()
+    }
+
+    /// Share the same state (used to open sessions off the receive loop).
+    fn handle(&self) -> Self {
+        Self {
+            proxy_service: self.proxy_service.clone(),
+            listen_addr: self.listen_addr.clone(),
+            public_ip_geo: self.public_ip_geo.clone(),
+            session_timeout: self.session_timeout,
+            listen_options: self.listen_options,
+            shutdown: self.shutdown.clone(),
+            access_log: self.access_log,
+            sessions: self.sessions.clone(),
+        }
+    }
+
+    /// Run the UDP server.
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    pub async fn run(&self) -> anyhow::Result<()> {
+        let socket = self.listen_options.bind_udp(&self.listen_addr).await?;
+        tracing::info!("UDP proxy listening on {}", self.listen_addr);
+
+        self.serve(Arc::new(socket)).await
+    }
+
+    /// Relay datagrams received on `socket` until it fails.
+    ///
+    /// A new client's session is opened on a task of its own, so a slow
+    /// backend pick never holds up datagrams of other clients. The client's
+    /// datagrams are queued until its session is ready.
+    async fn serve(&self, socket: Arc<UdpSocket>) -> anyhow::Result<()> {
+        let mut buf = vec![0u8; MAX_DATAGRAM];
+
+        loop {
+            let (len, client_addr) = match socket.recv_from(&mut buf).await {
+                Ok(received) => received,
+                // e.g. an ICMP error for an earlier reply (Windows reports these)
+                Err(e) if e.kind() == io::ErrorKind::ConnectionReset => continue,
+                Err(e) => return Err(e.into()),
+            };
+            let datagram = &buf[..len];
+
+            let session = match self.sessions.entry(client_addr) {
+                Entry::Occupied(mut entry) => match entry.get_mut() {
+                    // Touched under the entry lock, so the session can't be
+                    // ended as idle before the datagram is forwarded
+                    SessionSlot::Open(session) => {
+                        session.touch();
+                        session.clone()
+                    }
+                    SessionSlot::Opening(queued) => {
+                        if queued.len() < MAX_QUEUED_DATAGRAMS {
+                            queued.push(datagram.to_vec());
+                        } else {
+                            tracing::debug!(
+                                "session for {} still opening, dropping datagram",
+                                client_addr
+                            );
+                        }
+                        continue;
+                    }
+                },
+                Entry::Vacant(entry) => {
+                    if self.shutdown.is_draining() {
+                        tracing::debug!(
+                            "draining, dropping datagram from new client {}",
+                            client_addr
+                        );
+                        continue;
+                    }
+                    entry.insert(SessionSlot::Opening(vec![datagram.to_vec()]));
+                    let server = self.handle();
+                    let socket = socket.clone();
+                    tokio::spawn(async move { server.open_session(&socket, client_addr).await });
+                    continue;
+                }
+            };
+
+            Self::forward(&session, client_addr, datagram).await;
+        }
+    }
+
+    /// Send a client's datagram on to the session's backend.
+    async fn forward(session: &UdpSession, client_addr: SocketAddr, datagram: &[u8]) {
+        if let Err(e) = session.upstream.send(datagram).await {
+            tracing::debug!(
+                "failed to forward datagram from {} to {}: {}",
+                client_addr,
+                session.backend.id,
+                e
+            );
+        }
+    }
+
+    /// Open the session of a new client, whose slot is `Opening`.
+    ///
+    /// Forwards the datagrams queued meanwhile, marks the slot open and
+    /// starts relaying replies. Without a usable backend the slot and its
+    /// queued datagrams are dropped.
+    async fn open_session(&self, socket: &Arc<UdpSocket>, client_addr: SocketAddr) {
+        let Some(session) = self.pick_session(client_addr).await else {
+            self.sessions
+                .remove_if(&client_addr, |_, slot| matches!(slot, SessionSlot::Opening(_)));
+            return;
+        };
+
+        // Datagrams keep queueing until the slot is open, keeping their order
+        loop {
+            let queued = match self.sessions.get_mut(&client_addr).as_deref_mut() {
+                Some(SessionSlot::Opening(queued)) if !queued.is_empty() => std::mem::take(queued),
+                Some(slot) => {
+                    *slot = SessionSlot::Open(session.clone());
+                    break;
+                }
+                None => return,
+            };
+            for datagram in queued {
+                session.touch();
+                Self::forward(&session, client_addr, &datagram).await;
+            }
+        }
+
+        let service = self.proxy_service.clone();
+        let conn_id = ConnectionId::next();
+        service.record_connection_start(&session.backend.id, &session.backend.app);
+        tracing::debug!(
+            "relaying {} -> {} ({})",
+            client_addr,
+            session.backend.id,
+            session.backend.addr()
+        );
+
+        let socket = socket.clone();
+        let sessions = self.sessions.clone();
+        let session_timeout = self.session_timeout;
+        let access_log = self.access_log;
+
+        self.shutdown.spawn_connection(
+            async move {
+                let reason =
+                    Self::relay_replies(&socket, client_addr, &session, &sessions, session_timeout)
+                        .await;
+                sessions.remove_if(&client_addr, |_, slot| slot.holds(&session));
+
+                tracing::debug!(
+                    "closed {} -> {}: reason={}",
+                    client_addr,
+                    session.backend.id,
+                    reason
+                );
+                service.record_connection_end(&session.backend.id, reason);
+                if access_log {
+                    log_access(conn_id, client_addr, &session.backend, reason, session.started);
+                }
+            }
+            .instrument(connection_span(conn_id, client_addr)),
+        );
+    }
+
+    /// Pick a backend for a new client and connect a socket to it.
+    ///
+    /// Returns None when no backend is available, or when the backend's
+    /// address can't be used.
+    async fn pick_session(&self, client_addr: SocketAddr) -> Option<Arc<UdpSession>> {
+        let service = &self.proxy_service;
+        let client_ip = client_addr.ip();
+
+        // Loopback clients (IPv4 or IPv6) are located per the loopback policy
+        let client_geo = self.public_ip_geo.client_geo(service, client_ip).await;
+
+        let Some(backend) = service.resolve_backend_with_geo(client_ip, client_geo).await else {
+            tracing::warn!("no backend available for {}", client_addr);
+            return None;
+        };
+
+        match Self::connect_upstream(&backend.addr()).await {
+            Ok(upstream) => Some(Arc::new(UdpSession::new(backend, upstream))),
+            Err(e) => {
+                tracing::error!(
+                    "failed to open UDP socket to backend {} at {}: {:?}",
+                    backend.id,
+                    backend.addr(),
+                    e
+                );
+                service.clear_binding(client_ip).await;
+                None
+            }
+        }
+    }
+
+    /// Open a socket connected to a backend, of the backend's address family.
+    async fn connect_upstream(backend_addr: &str) -> io::Result<UdpSocket> {
+        let addr = tokio::net::lookup_host(backend_addr).await?.next().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("backend address {} did not resolve", backend_addr),
+            )
+        })?;
+        let local: SocketAddr = match addr {
+            SocketAddr::V4(_) => (Ipv4Addr::UNSPECIFIED, 0).into(),
+            SocketAddr::V6(_) => (Ipv6Addr::UNSPECIFIED, 0).into(),
+        };
+
+        let socket = UdpSocket::bind(local).await?;
+        socket.connect(addr).await?;
+        Ok(socket)
+    }
+
+    /// Send the backend's datagrams to the client until the session idles out.
+    ///
+    /// Returns `ClientClosed` once idle, `Error` if either socket fails
+    /// (e.g. the backend port answered with ICMP unreachable).
+    async fn relay_replies(
+        socket: &UdpSocket,
+        client_addr: SocketAddr,
+        session: &Arc<UdpSession>,
+        sessions: &DashMap<SocketAddr, SessionSlot>,
+        session_timeout: Duration,
+    ) -> CloseReason {
+        let mut buf = vec![0u8; MAX_DATAGRAM];
+
+        loop {
+            // Client datagrams also count as activity, so recheck after each wait
+            let remaining = session_timeout.saturating_sub(session.idle_for());
+            if remaining.is_zero() {
+                if Self::end_if_idle(sessions, client_addr, session, session_timeout) {
+                    return CloseReason::ClientClosed;
+                }
+                continue;
+            }
+
+            match tokio::time::timeout(remaining, session.upstream.recv(&mut buf)).await {
+                Ok(Ok(len)) => {
+                    session.touch();
+                    if let Err(e) = socket.send_to(&buf[..len], client_addr).await {
+                        tracing::debug!("failed to send datagram to {}: {}", client_addr, e);
+                        return CloseReason::Error;
+                    }
+                }
+                Ok(Err(e)) => {
+                    tracing::debug!("backend {} receive error: {}", session.backend.id, e);
+                    return CloseReason::Error;
+                }
+                Err(_) => continue,
+            }
+        }
+    }
+
+    /// Remove `session` from `sessions` if it is still idle.
+    ///
+    /// Checked under the client's entry lock, which the receive loop holds
+    /// while touching a session it forwards to. So a datagram either keeps
+    /// the session alive or finds it gone and opens a new one. Returns
+    /// false if the session was touched in the meantime.
+    fn end_if_idle(
+        sessions: &DashMap<SocketAddr, SessionSlot>,
+        client_addr: SocketAddr,
+        session: &Arc<UdpSession>,
+        session_timeout: Duration,
+    ) -> bool {
+        match sessions.entry(client_addr) {
+            Entry::Occupied(entry) if entry.get().holds(session) => {
+                if session.idle_for() < session_timeout {
+                    return false;
+                }
+                entry.remove();
+                true
+            }
+            // Already gone from the table
+            _ => true,
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::adapters::outbound::{DashMapBindingRepository, DashMapMetricsStore};
+    use crate::domain::ports::{BackendRepository, MetricsStore};
+    use crate::domain::value_objects::RegionCode;
+    use async_trait::async_trait;
+
+    // Mock backend repository for testing
+    struct MockBackendRepository {
+        backends: Vec<Backend>,
+    }
+
+    #[async_trait]
+    impl BackendRepository for MockBackendRepository {
+        async fn get_all(&self) -> Vec<Backend> {
+            self.backends.clone()
+        }
+
+        async fn get_by_id(&self, id: &str) -> Option<Backend> {
+            self.backends.iter().find(|b| b.id == id).cloned()
+        }
+
+        async fn get_healthy(&self) -> Vec<Backend> {
+            self.backends.iter().filter(|b| b.healthy).cloned().collect()
+        }
+
+        async fn get_version(&self) -> u64 {
+            1
+        }
+    }
+
+    /// Repository whose lookups take `delay_ms` milliseconds.
+    struct SlowBackendRepository {
+        backends: Vec<Backend>,
+        delay_ms: AtomicU64,
+    }
+
+    impl SlowBackendRepository {
+        async fn delay(&self) {
+            let delay = self.delay_ms.load(Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(delay)).await;
+        }
+    }
+
+    #[async_trait]
+    impl BackendRepository for SlowBackendRepository {
+        async fn get_all(&self) -> Vec<Backend> {
+            self.delay().await;
+            self.backends.clone()
+        }
+
+        async fn get_by_id(&self, id: &str) -> Option<Backend> {
+            self.delay().await;
+            self.backends.iter().find(|b| b.id == id).cloned()
+        }
+
+        async fn get_healthy(&self) -> Vec<Backend> {
+            self.delay().await;
+            self.backends.iter().filter(|b| b.healthy).cloned().collect()
+        }
+
+        async fn get_version(&self) -> u64 {
+            1
+        }
+    }
+
+    fn create_test_backend(id: &str, port: u16) -> Backend {
+        Backend {
+            id: id.to_string(),
+            app: "testapp".to_string(),
+            region: RegionCode::Europe,
+            country: "DE".to_string(),
+            wg_ip: "127.0.0.1".to_string(),
+            port,
+            healthy: true,
+            weight: 2,
+            soft_limit: 100,
+            hard_limit: 150,
+            draining: false,
+            maintenance: false,
+            priority: 0,
+        }
+    }
+
+    fn create_proxy_service(
+        backends: Vec<Backend>,
+        metrics: Arc<DashMapMetricsStore>,
+    ) -> Arc<ProxyService> {
+        Arc::new(ProxyService::new(
+            Arc::new(MockBackendRepository { backends }),
+            Arc::new(DashMapBindingRepository::new()),
+            None,
+            metrics,
+            RegionCode::Europe,
+        ))
+    }
+
+    /// UDP backend answering each datagram with `prefix` + the datagram.
+    async fn spawn_echo_backend(prefix: &'static [u8]) -> u16 {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let port = socket.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 2048];
+            while let Ok((len, from)) = socket.recv_from(&mut buf).await {
+                let reply = [prefix, &buf[..len]].concat();
+                let _ = socket.send_to(&reply, from).await;
+            }
+        });
+        port
+    }
+
+    /// Start `server` on a loopback socket, returning its address.
+    async fn start(server: Arc<UdpProxyServer>) -> SocketAddr {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = socket.local_addr().unwrap();
+        tokio::spawn(async move { server.serve(Arc::new(socket)).await });
+        addr
+    }
+
+    async fn client() -> UdpSocket {
+        UdpSocket::bind("127.0.0.1:0").await.unwrap()
+    }
+
+    async fn exchange(client: &UdpSocket, server: SocketAddr, payload: &[u8]) -> Option<Vec<u8>> {
+        client.send_to(payload, server).await.unwrap();
+        let mut buf = [0u8; 2048];
+        let received = tokio::time::timeout(Duration::from_millis(500), client.recv_from(&mut buf));
+        let (len, from) = received.await.ok()?.unwrap();
+        assert_eq!(from, server);
+        Some(buf[..len].to_vec())
+    }
+
+    #[test]
+    fn test_udp_server_defaults() {
+        let metrics = Arc::new(DashMapMetricsStore::new());
+        let service = create_proxy_service(vec![], metrics);
+        let server = UdpProxyServer::new(service, "0.0.0.0:0".to_string());
+        assert_eq!(server.session_timeout, DEFAULT_UDP_SESSION_TIMEOUT);
+        assert!(!server.access_log);
+        assert_eq!(server.session_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_relays_datagrams_through_echo_backend() {
+        let port = spawn_echo_backend(b"echo:").await;
+        let metrics = Arc::new(DashMapMetricsStore::new());
+        let backends = vec![create_test_backend("udp-1", port)];
+        let service = create_proxy_service(backends, metrics.clone());
+        let server = Arc::new(UdpProxyServer::new(service, "127.0.0.1:0".to_string()));
+        let addr = start(server.clone()).await;
+
+        let client = client().await;
+        assert_eq!(exchange(&client, addr, b"ping").await.unwrap(), b"echo:ping");
+        assert_eq!(exchange(&client, addr, b"again").await.unwrap(), b"echo:again");
+
+        // Both datagrams went through one session
+        assert_eq!(server.session_count(), 1);
+        assert_eq!(metrics.get_connection_count("udp-1"), 1);
+    }
+
+    #[tokio::test]
+    async fn test_replies_return_to_the_right_client() {
+        let port = spawn_echo_backend(b"").await;
+        let metrics = Arc::new(DashMapMetricsStore::new());
+        let service = create_proxy_service(vec![create_test_backend("udp-1", port)], metrics);
+        let server = Arc::new(UdpProxyServer::new(service, "127.0.0.1:0".to_string()));
+        let addr = start(server.clone()).await;
+
+        let alice = client().await;
+        let bob = client().await;
+        alice.send_to(b"from alice", addr).await.unwrap();
+        bob.send_to(b"from bob", addr).await.unwrap();
+
+        let mut buf = [0u8; 64];
+        let (len, _) = tokio::time::timeout(Duration::from_millis(500), alice.recv_from(&mut buf))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(&buf[..len], b"from alice");
+        let (len, _) = tokio::time::timeout(Duration::from_millis(500), bob.recv_from(&mut buf))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(&buf[..len], b"from bob");
+
+        // One session per client address, even from the same IP
+        assert_eq!(server.session_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_idle_session_times_out() {
+        let port = spawn_echo_backend(b"").await;
+        let metrics = Arc::new(DashMapMetricsStore::new());
+        let backends = vec![create_test_backend("udp-1", port)];
+        let service = create_proxy_service(backends, metrics.clone());
+        let server = Arc::new(
+            UdpProxyServer::new(service, "127.0.0.1:0".to_string())
+                .with_session_timeout(Duration::from_millis(100)),
+        );
+        let addr = start(server.clone()).await;
+
+        let client = client().await;
+        assert!(exchange(&client, addr, b"ping").await.is_some());
+        assert_eq!(server.session_count(), 1);
+
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert_eq!(server.session_count(), 0);
+        assert_eq!(metrics.get_connection_count("udp-1"), 0);
+        assert_eq!(metrics.get_connection_closed(CloseReason::ClientClosed), 1);
+
+        // The client can start over with a new session
+        assert!(exchange(&client, addr, b"ping").await.is_some());
+        assert_eq!(server.session_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_no_backend_drops_datagrams() {
+        let metrics = Arc::new(DashMapMetricsStore::new());
+        let service = create_proxy_service(vec![], metrics);
+        let server = Arc::new(UdpProxyServer::new(service, "127.0.0.1:0".to_string()));
+        let addr = start(server.clone()).await;
+
+        let client = client().await;
+        assert!(exchange(&client, addr, b"ping").await.is_none());
+        assert_eq!(server.session_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_draining_refuses_new_sessions_only() {
+        let port = spawn_echo_backend(b"").await;
+        let metrics = Arc::new(DashMapMetricsStore::new());
+        let service = create_proxy_service(vec![create_test_backend("udp-1", port)], metrics);
+        let shutdown = ShutdownController::new();
+        let server = Arc::new(
+            UdpProxyServer::new(service, "127.0.0.1:0".to_string())
+                .with_shutdown(shutdown.clone()),
+        );
+        let addr = start(server.clone()).await;
+
+        let existing = client().await;
+        assert!(exchange(&existing, addr, b"before").await.is_some());
+        assert_eq!(shutdown.active_connections(), 1);
+
+        shutdown.drain();
+        assert!(exchange(&existing, addr, b"after").await.is_some());
+        assert!(exchange(&client().await, addr, b"new").await.is_none());
+        assert_eq!(server.session_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_slow_session_open_does_not_hold_up_other_clients() {
+        let port = spawn_echo_backend(b"").await;
+        let repo = Arc::new(SlowBackendRepository {
+            backends: vec![create_test_backend("udp-1", port)],
+            delay_ms: AtomicU64::new(0),
+        });
+        let service = Arc::new(ProxyService::new(
+            repo.clone(),
+            Arc::new(DashMapBindingRepository::new()),
+            None,
+            Arc::new(DashMapMetricsStore::new()),
+            RegionCode::Europe,
+        ));
+        let server = Arc::new(UdpProxyServer::new(service, "127.0.0.1:0".to_string()));
+        let addr = start(server.clone()).await;
+
+        let existing = client().await;
+        assert!(exchange(&existing, addr, b"before").await.is_some());
+
+        // The newcomer's backend pick now takes a second
+        repo.delay_ms.store(1000, Ordering::SeqCst);
+        let newcomer = client().await;
+        for payload in [b"one", b"two", b"six"] {
+            newcomer.send_to(payload, addr).await.unwrap();
+        }
+
+        let started = Instant::now();
+        assert_eq!(exchange(&existing, addr, b"during").await.unwrap(), b"during");
+        assert!(started.elapsed() < Duration::from_millis(500));
+        assert_eq!(server.session_count(), 1);
+
+        // Queued datagrams are forwarded in order once the session opens
+        let mut buf = [0u8; 64];
+        for expected in [b"one", b"two", b"six"] {
+            let received = newcomer.recv_from(&mut buf);
+            let (len, _) = tokio::time::timeout(Duration::from_secs(3), received)
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(&buf[..len], expected);
+        }
+        assert_eq!(server.session_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_end_if_idle_keeps_touched_session() {
+        let upstream = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let session = Arc::new(UdpSession::new(create_test_backend("udp-1", 9), upstream));
+        let client_addr: SocketAddr = "127.0.0.1:40000".parse().unwrap();
+        let sessions = DashMap::new();
+        sessions.insert(client_addr, SessionSlot::Open(session.clone()));
+        let timeout = Duration::from_millis(20);
+
+        // A datagram arrived just after the relay saw the session idle
+        tokio::time::sleep(Duration::from_millis(40)).await;
+        session.touch();
+        assert!(!UdpProxyServer::end_if_idle(&sessions, client_addr, &session, timeout));
+        assert!(sessions.contains_key(&client_addr));
+
+        tokio::time::sleep(Duration::from_millis(40)).await;
+        assert!(UdpProxyServer::end_if_idle(&sessions, client_addr, &session, timeout));
+        assert!(sessions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_end_if_idle_leaves_replacement_session() {
+        let backend = create_test_backend("udp-1", 9);
+        let old = Arc::new(UdpSession::new(
+            backend.clone(),
+            UdpSocket::bind("127.0.0.1:0").await.unwrap(),
+        ));
+        let new = Arc::new(UdpSession::new(backend, UdpSocket::bind("127.0.0.1:0").await.unwrap()));
+        let client_addr: SocketAddr = "127.0.0.1:40000".parse().unwrap();
+        let sessions = DashMap::new();
+        sessions.insert(client_addr, SessionSlot::Open(new.clone()));
+
+        assert!(UdpProxyServer::end_if_idle(&sessions, client_addr, &old, Duration::ZERO));
+        assert!(sessions.get(&client_addr).unwrap().holds(&new));
+    }
+}
@@ -0,0 +1,526 @@
+//! Consul Backend Repository
+//!
+//! Implements BackendRepository from Consul's health API. Instances of one
+//! Consul service become backends, and blocking queries pick up changes
+//! as soon as Consul sees them.
+
+use crate::domain::entities::Backend;
+use crate::domain::ports::BackendRepository;
+use crate::domain::value_objects::RegionCode;
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+
+/// Response header carrying the Consul index of the returned data.
+const CONSUL_INDEX_HEADER: &str = "X-Consul-Index";
+
+/// Consul connection configuration.
+#[derive(Debug, Clone)]
+pub struct ConsulConfig {
+    /// HTTP address of the Consul agent
+    pub address: String,
+    /// Service whose instances are the backends
+    pub service: String,
+    /// ACL token sent as `X-Consul-Token`
+    pub token: Option<String>,
+    /// Datacenter to query (the agent's own when unset)
+    pub datacenter: Option<String>,
+    /// How long a blocking query may wait for a change
+    pub wait: Duration,
+    /// Pause before retrying after a failed query
+    pub retry_interval: Duration,
+}
+
+impl Default for ConsulConfig {
+    fn default() -> Self {
+        Self {
+            address: "http://127.0.0.1:8500".to_string(),
+            service: String::new(),
+            token: None,
+            datacenter: None,
+            wait: Duration::from_secs(60),
+            retry_interval: Duration::from_secs(5),
+        }
+    }
+}
+
+/// One entry of `/v1/health/service/<service>`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct HealthEntry {
+    node: ConsulNode,
+    service: ConsulService,
+    #[serde(default)]
+    checks: Vec<ConsulCheck>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ConsulNode {
+    address: String,
+    #[serde(default)]
+    datacenter: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ConsulService {
+    #[serde(rename = "ID")]
+    id: String,
+    service: String,
+    #[serde(default)]
+    tags: Option<Vec<String>>,
+    #[serde(default)]
+    address: String,
+    port: u16,
+    #[serde(default)]
+    meta: Option<HashMap<String, String>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ConsulCheck {
+    status: String,
+}
+
+impl HealthEntry {
+    /// A `key=value` tag, falling back to the service meta entry `key`.
+    fn attribute(&self, key: &str) -> Option<&str> {
+        self.service
+            .tags
+            .iter()
+            .flatten()
+            .find_map(|tag| tag.strip_prefix(key)?.strip_prefix('='))
+            .or_else(|| self.service.meta.as_ref()?.get(key).map(String::as_str))
+    }
+
+    /// Map the instance to a backend; it is healthy only if every check passes.
+    fn into_backend(self) -> Backend {
+        let region = RegionCode::from_code(
+            self.attribute("region").unwrap_or(&self.node.datacenter),
+        );
+        let country = self
+            .attribute("country")
+            .unwrap_or_else(|| region.default_country())
+            .to_uppercase();
+        let number = |key: &str, default: u32| {
+            self.attribute(key)
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        };
+        let weight = number("weight", 2).min(u8::MAX as u32) as u8;
+        let soft_limit = number("soft_limit", 100);
+        let hard_limit = number("hard_limit", 150);
+        let priority = number("priority", 0).min(u16::MAX as u32) as u16;
+        let app = self.attribute("app").unwrap_or(&self.service.service).to_string();
+        let healthy = self.checks.iter().all(|c| c.status == "passing");
+        let wg_ip = if self.service.address.is_empty() {
+            self.node.address
+        } else {
+            self.service.address
+        };
+
+        Backend {
+            id: self.service.id,
+            app,
+            region,
+            country,
+            wg_ip,
+            port: self.service.port,
+            healthy,
+            weight,
+            soft_limit,
+            hard_limit,
+            draining: false,
+            maintenance: false,
+            priority,
+        }
+    }
+}
+
+/// Consul-backed backend repository.
+///
+/// The version is bumped whenever a query returns a different set of
+/// backends. A failed query leaves the last good set in place.
+pub struct ConsulBackendRepository {
+    config: ConsulConfig,
+    client: reqwest::Client,
+    backends: Arc<RwLock<Vec<Backend>>>,
+    version: Arc<AtomicU64>,
+    /// Consul index of the current set, used for the next blocking query
+    index: Arc<AtomicU64>,
+}
+
+impl ConsulBackendRepository {
+    /// Create a repository for `config.service`; nothing is loaded until
+    /// the first `refresh`.
+    pub fn new(config: ConsulConfig) -> Result<Self> {
+        // Consul adds up to wait/16 of jitter to a blocking query
+        let client = reqwest::Client::builder()
+            .timeout(config.wait + config.wait / 16 + Duration::from_secs(5))
+            .build()?;
+
+        Ok(Self {
+            config,
+            client,
+            backends: Arc::new(RwLock::new(Vec::new())),
+            version: Arc::new(AtomicU64::new(0)),
+            index: Arc::new(AtomicU64::new(0)),
+        })
+    }
+
+    /// Share the same state (used to hand the repository to the watch task).
+    fn handle(&self) -> Self {
+        Self {
+            config: self.config.clone(),
+            client: self.client.clone(),
+            backends: self.backends.clone(),
+            version: self.version.clone(),
+            index: self.index.clone(),
+        }
+    }
+
+    /// Consul index of the loaded backends (0 before the first load).
+    pub fn index(&self) -> u64 {
+        self.index.load(Ordering::SeqCst)
+    }
+
+    /// Query Consul, blocking until the service changes once a set has
+    /// been loaded, and replace the backends if they differ.
+    ///
+    /// Returns true if a new set was loaded.
+    pub async fn refresh(&self) -> Result<bool> {
+        let last_index = self.index();
+        let url = format!(
+            "{}/v1/health/service/{}",
+            self.config.address.trim_end_matches('/'),
+            self.config.service
+        );
+        let mut request = self.client.get(&url);
+        if last_index > 0 {
+            request = request.query(&[
+                ("index", last_index.to_string()),
+                ("wait", format!("{}s", self.config.wait.as_secs().max(1))),
+            ]);
+        }
+        if let Some(dc) = &self.config.datacenter {
+            request = request.query(&[("dc", dc)]);
+        }
+        if let Some(token) = &self.config.token {
+            request = request.header("X-Consul-Token", token);
+        }
+
+        let response = request.send().await?.error_for_status()?;
+        let index = response
+            .headers()
+            .get(CONSUL_INDEX_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(0);
+        let entries: Vec<HealthEntry> = response.json().await?;
+
+        // An index going backwards means Consul's state was reset: start over
+        self.index.store(if index < last_index { 0 } else { index }, Ordering::SeqCst);
+
+        let mut backends: Vec<Backend> = entries.into_iter().map(HealthEntry::into_backend).collect();
+        backends.sort_by(|a, b| a.id.cmp(&b.id));
+
+        let mut current = self.backends.write().await;
+        if *current == backends {
+            tracing::trace!("consul service {} unchanged (index={})", self.config.service, index);
+            return Ok(false);
+        }
+        let count = backends.len();
+        *current = backends;
+        self.version.fetch_add(1, Ordering::SeqCst);
+
+        tracing::info!(
+            "loaded {} backends from consul service {} (index={})",
+            count,
+            self.config.service,
+            index
+        );
+        Ok(true)
+    }
+
+    /// Keep the backends in sync with Consul using blocking queries.
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    pub fn start_watching(&self) {
+        let repo = self.handle();
+
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = repo.refresh().await {
+                    tracing::error!(
+                        "keeping last good backends, failed to query consul service {}: {:#}",
+                        repo.config.service,
+                        e
+                    );
+                    tokio::time::sleep(repo.config.retry_interval).await;
+                }
+            }
+        });
+    }
+}
+
+#[async_trait]
+impl BackendRepository for ConsulBackendRepository {
+    async fn get_all(&self) -> Vec<Backend> {
+        self.backends.read().await.clone()
+    }
+
+    async fn get_by_id(&self, id: &str) -> Option<Backend> {
+        self.backends
+            .read()
+            .await
+            .iter()
+            .find(|b| b.id == id)
+            .cloned()
+    }
+
+    async fn get_healthy(&self) -> Vec<Backend> {
+        self.backends
+            .read()
+            .await
+            .iter()
+            .filter(|b| b.healthy)
+            .cloned()
+            .collect()
+    }
+
+    async fn get_version(&self) -> u64 {
+        self.version.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wiremock::matchers::{header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn entry(id: &str, tags: &[&str], address: &str, status: &str) -> serde_json::Value {
+        json!({
+            "Node": {"Node": "node-1", "Address": "10.0.0.1", "Datacenter": "dc1"},
+            "Service": {
+                "ID": id,
+                "Service": "web",
+                "Tags": tags,
+                "Address": address,
+                "Port": 8080,
+                "Meta": {"country": "fr"}
+            },
+            "Checks": [
+                {"CheckID": "serfHealth", "Status": "passing"},
+                {"CheckID": "service:web", "Status": status}
+            ]
+        })
+    }
+
+    fn health_response(index: u64, entries: Vec<serde_json::Value>) -> ResponseTemplate {
+        ResponseTemplate::new(200)
+            .insert_header(CONSUL_INDEX_HEADER, index.to_string().as_str())
+            .set_body_json(entries)
+    }
+
+    fn repo(server: &MockServer) -> ConsulBackendRepository {
+        ConsulBackendRepository::new(ConsulConfig {
+            address: server.uri(),
+            service: "web".to_string(),
+            wait: Duration::from_secs(1),
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_consul_config_default() {
+        let config = ConsulConfig::default();
+        assert_eq!(config.address, "http://127.0.0.1:8500");
+        assert!(config.token.is_none());
+        assert_eq!(config.wait, Duration::from_secs(60));
+    }
+
+    #[tokio::test]
+    async fn test_catalog_load_maps_instances() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/health/service/web"))
+            .respond_with(health_response(
+                10,
+                vec![
+                    entry("web-2", &["region=us", "weight=5", "priority=1"], "", "critical"),
+                    entry("web-1", &["app=myapp", "region=eu", "v2"], "10.50.1.1", "passing"),
+                ],
+            ))
+            .mount(&server)
+            .await;
+
+        let repo = repo(&server);
+        assert!(repo.refresh().await.unwrap());
+        assert_eq!(repo.index(), 10);
+        assert_eq!(repo.get_version().await, 1);
+
+        let all = repo.get_all().await;
+        assert_eq!(all.len(), 2);
+
+        let web1 = repo.get_by_id("web-1").await.unwrap();
+        assert_eq!(web1.app, "myapp");
+        assert_eq!(web1.region, RegionCode::Europe);
+        assert_eq!(web1.country, "FR");
+        assert_eq!(web1.wg_ip, "10.50.1.1");
+        assert_eq!(web1.port, 8080);
+        assert_eq!(web1.weight, 2);
+        assert_eq!(web1.priority, 0);
+        assert!(web1.healthy);
+
+        // Defaults: app from the service name, address from the node
+        let web2 = repo.get_by_id("web-2").await.unwrap();
+        assert_eq!(web2.app, "web");
+        assert_eq!(web2.region, RegionCode::NorthAmerica);
+        assert_eq!(web2.wg_ip, "10.0.0.1");
+        assert_eq!(web2.weight, 5);
+        assert_eq!(web2.priority, 1);
+        assert!(!web2.healthy);
+
+        let healthy = repo.get_healthy().await;
+        assert_eq!(healthy.len(), 1);
+        assert_eq!(healthy[0].id, "web-1");
+    }
+
+    #[tokio::test]
+    async fn test_blocking_query_detects_change() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/health/service/web"))
+            .and(query_param("index", "10"))
+            .respond_with(health_response(
+                11,
+                vec![entry("web-1", &["region=eu"], "10.50.1.1", "critical")],
+            ))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/health/service/web"))
+            .respond_with(health_response(
+                10,
+                vec![entry("web-1", &["region=eu"], "10.50.1.1", "passing")],
+            ))
+            .mount(&server)
+            .await;
+
+        let repo = repo(&server);
+        assert!(repo.refresh().await.unwrap());
+        assert_eq!(repo.get_healthy().await.len(), 1);
+
+        // The next query blocks on index 10 and sees the failing check
+        assert!(repo.refresh().await.unwrap());
+        assert_eq!(repo.index(), 11);
+        assert_eq!(repo.get_version().await, 2);
+        assert!(repo.get_healthy().await.is_empty());
+        assert_eq!(repo.get_all().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_unchanged_set_keeps_version() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/health/service/web"))
+            .respond_with(health_response(
+                10,
+                vec![entry("web-1", &[], "10.50.1.1", "passing")],
+            ))
+            .mount(&server)
+            .await;
+
+        let repo = repo(&server);
+        assert!(repo.refresh().await.unwrap());
+        // Blocking query timed out with the same data
+        assert!(!repo.refresh().await.unwrap());
+        assert_eq!(repo.get_version().await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_index_reset_starts_over() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/health/service/web"))
+            .and(query_param("index", "10"))
+            .respond_with(health_response(3, vec![]))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/health/service/web"))
+            .respond_with(health_response(
+                10,
+                vec![entry("web-1", &[], "10.50.1.1", "passing")],
+            ))
+            .mount(&server)
+            .await;
+
+        let repo = repo(&server);
+        repo.refresh().await.unwrap();
+        assert!(repo.refresh().await.unwrap());
+        assert_eq!(repo.index(), 0);
+        assert!(repo.get_all().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_token_and_datacenter_sent() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/health/service/web"))
+            .and(header("X-Consul-Token", "secret"))
+            .and(query_param("dc", "eu-west"))
+            .respond_with(health_response(
+                1,
+                vec![entry("web-1", &[], "10.50.1.1", "passing")],
+            ))
+            .mount(&server)
+            .await;
+
+        let repo = ConsulBackendRepository::new(ConsulConfig {
+            address: server.uri(),
+            service: "web".to_string(),
+            token: Some("secret".to_string()),
+            datacenter: Some("eu-west".to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(repo.refresh().await.unwrap());
+        assert_eq!(repo.get_all().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_failed_query_keeps_last_good_set() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/health/service/web"))
+            .and(query_param("index", "10"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/health/service/web"))
+            .respond_with(health_response(
+                10,
+                vec![entry("web-1", &[], "10.50.1.1", "passing")],
+            ))
+            .mount(&server)
+            .await;
+
+        let repo = repo(&server);
+        repo.refresh().await.unwrap();
+        assert!(repo.refresh().await.is_err());
+        assert_eq!(repo.index(), 10);
+        assert_eq!(repo.get_all().await.len(), 1);
+        assert_eq!(repo.get_version().await, 1);
+    }
+}
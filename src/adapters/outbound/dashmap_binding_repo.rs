@@ -9,12 +9,17 @@ use dashmap::DashMap;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
+/// Callback invoked with the client and backend id of an expired binding.
+pub type EvictionCallback = Arc<dyn Fn(&ClientKey, &str) + Send + Sync>;
+
 /// DashMap-backed binding repository.
 ///
 /// Uses DashMap for lock-free concurrent access to bindings.
 /// Supports periodic garbage collection of expired bindings.
 pub struct DashMapBindingRepository {
     bindings: Arc<DashMap<ClientKey, Binding>>,
+    /// Callback when GC evicts a binding
+    on_evict: Option<EvictionCallback>,
 }
 
 impl DashMapBindingRepository {
@@ -22,7 +27,48 @@ impl DashMapBindingRepository {
     pub fn new() -> Self {
         Self {
             bindings: Arc::new(DashMap::new()),
+            on_evict: None,
+        }
+    }
+
+    /// Set callback for bindings evicted by garbage collection.
+    ///
+    /// Explicit `remove` calls do not fire it.
+    pub fn on_evict<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&ClientKey, &str) + Send + Sync + 'static,
+    {
+        self.on_evict = Some(Arc::new(callback));
+        self
+    }
+
+    /// Remove bindings not seen within `ttl`, notifying the eviction callback.
+    ///
+    /// A binding touched between the scan and its removal is kept.
+    fn evict_expired(
+        bindings: &DashMap<ClientKey, Binding>,
+        ttl: Duration,
+        on_evict: &Option<EvictionCallback>,
+    ) -> usize {
+        let now = Instant::now();
+        let expired = |binding: &Binding| now.duration_since(binding.last_seen) > ttl;
+
+        let to_remove: Vec<ClientKey> = bindings
+            .iter()
+            .filter(|entry| expired(entry.value()))
+            .map(|entry| entry.key().clone())
+            .collect();
+
+        let mut removed_count = 0;
+        for key in to_remove {
+            if let Some((key, binding)) = bindings.remove_if(&key, |_, binding| expired(binding)) {
+                removed_count += 1;
+                if let Some(callback) = on_evict {
+                    callback(&key, &binding.backend_id);
+                }
+            }
         }
+        removed_count
     }
 
     /// Start the background garbage collection task.
@@ -30,23 +76,11 @@ impl DashMapBindingRepository {
     /// Removes bindings that have not been seen within the TTL.
     pub fn start_gc(&self, ttl: Duration, interval: Duration) {
         let bindings = self.bindings.clone();
+        let on_evict = self.on_evict.clone();
 
         tokio::spawn(async move {
             loop {
-                let now = Instant::now();
-                let mut to_remove = Vec::new();
-
-                for entry in bindings.iter() {
-                    if now.duration_since(entry.value().last_seen) > ttl {
-                        to_remove.push(entry.key().clone());
-                    }
-                }
-
-                let removed_count = to_remove.len();
-                for key in to_remove {
-                    bindings.remove(&key);
-                }
-
+                let removed_count = Self::evict_expired(&bindings, ttl, &on_evict);
                 if removed_count > 0 {
                     tracing::debug!("binding GC removed {} expired entries", removed_count);
                 }
@@ -90,21 +124,7 @@ impl BindingRepository for DashMapBindingRepository {
     }
 
     async fn cleanup_expired(&self, ttl: Duration) -> usize {
-        let now = Instant::now();
-        let mut to_remove = Vec::new();
-
-        for entry in self.bindings.iter() {
-            if now.duration_since(entry.value().last_seen) > ttl {
-                to_remove.push(entry.key().clone());
-            }
-        }
-
-        let count = to_remove.len();
-        for key in to_remove {
-            self.bindings.remove(&key);
-        }
-
-        count
+        Self::evict_expired(&self.bindings, ttl, &self.on_evict)
     }
 
     async fn count(&self) -> usize {
@@ -462,4 +482,60 @@ mod tests {
         assert!(repo.get(&key1).await.is_none());
         assert!(repo.get(&key2).await.is_some());
     }
+
+    // ===== Eviction Callback Tests =====
+
+    type Evicted = Arc<std::sync::Mutex<Vec<(ClientKey, String)>>>;
+
+    fn recording_repo() -> (DashMapBindingRepository, Evicted) {
+        let evicted = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = evicted.clone();
+        let repo = DashMapBindingRepository::new().on_evict(move |key, backend_id| {
+            sink.lock().unwrap().push((key.clone(), backend_id.to_string()));
+        });
+        (repo, evicted)
+    }
+
+    #[tokio::test]
+    async fn test_start_gc_fires_eviction_callback() {
+        let (repo, evicted) = recording_repo();
+
+        let expired_key = ClientKey::new(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1)));
+        let mut binding = Binding::new("backend-1".to_string());
+        binding.last_seen = Instant::now() - Duration::from_millis(200);
+        repo.set(expired_key.clone(), binding).await;
+
+        let fresh_key = ClientKey::new(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 2)));
+        repo.set(fresh_key, Binding::new("backend-2".to_string())).await;
+
+        repo.start_gc(Duration::from_millis(100), Duration::from_millis(50));
+        tokio::time::sleep(Duration::from_millis(80)).await;
+
+        assert_eq!(*evicted.lock().unwrap(), vec![(expired_key, "backend-1".to_string())]);
+    }
+
+    #[tokio::test]
+    async fn test_cleanup_expired_fires_eviction_callback() {
+        let (repo, evicted) = recording_repo();
+
+        let key = ClientKey::new(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)));
+        let mut binding = Binding::new("backend-9".to_string());
+        binding.last_seen = Instant::now() - Duration::from_secs(100);
+        repo.set(key.clone(), binding).await;
+
+        assert_eq!(repo.cleanup_expired(Duration::from_secs(50)).await, 1);
+        assert_eq!(*evicted.lock().unwrap(), vec![(key, "backend-9".to_string())]);
+    }
+
+    #[tokio::test]
+    async fn test_remove_does_not_fire_eviction_callback() {
+        let (repo, evicted) = recording_repo();
+
+        let key = ClientKey::new(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)));
+        repo.set(key.clone(), Binding::new("backend-1".to_string())).await;
+        repo.remove(&key).await;
+        repo.cleanup_expired(Duration::from_secs(50)).await;
+
+        assert!(evicted.lock().unwrap().is_empty());
+    }
 }
@@ -1,25 +1,116 @@
 //! DashMap Metrics Store
 //!
 //! Implements MetricsStore using DashMap for lock-free concurrent access.
+//!
+//! Cumulative counters can optionally be snapshotted to disk and restored
+//! on start, so they resume roughly where the previous process left off.
+//! Anything counted after the last snapshot is lost, so Prometheus can
+//! still see a small drop (a counter reset) across a restart; `rate()` and
+//! `increase()` handle that as they do any reset.
 
 use crate::domain::ports::MetricsStore;
+use crate::domain::value_objects::{
+    CircuitState, CloseReason, ConnectPhase, DnsQueryOutcome, SelectionOutcome,
+};
 use dashmap::DashMap;
-use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicIsize, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+/// Upper bound on the stripes of a [`ShardedCounter`].
+const MAX_COUNTER_SHARDS: usize = 16;
+
+/// One stripe, on its own cache line so stripes don't false-share.
+#[derive(Debug, Default)]
+#[repr(align(64))]
+struct CounterShard(AtomicIsize);
+
+/// A gauge striped across up to [`MAX_COUNTER_SHARDS`] atomics.
+///
+/// Each thread updates its own stripe, so connection starts and ends on
+/// different worker threads don't contend on one cache line; reads sum
+/// the stripes. A decrement on one stripe may follow the increment on
+/// another, so stripes can go negative; the sum is clamped at zero, and
+/// an unmatched decrement offsets the next increment instead.
+#[derive(Debug)]
+pub struct ShardedCounter {
+    shards: Box<[CounterShard]>,
+}
+
+impl ShardedCounter {
+    /// Create a counter with one stripe per CPU, up to the bound.
+    pub fn new() -> Self {
+        static SHARDS: OnceLock<usize> = OnceLock::new();
+        let shards = *SHARDS.get_or_init(|| {
+            std::thread::available_parallelism()
+                .map_or(1, |n| n.get())
+                .next_power_of_two()
+                .min(MAX_COUNTER_SHARDS)
+        });
+        Self::with_shards(shards)
+    }
+
+    fn with_shards(shards: usize) -> Self {
+        Self {
+            shards: (0..shards.clamp(1, MAX_COUNTER_SHARDS))
+                .map(|_| CounterShard::default())
+                .collect(),
+        }
+    }
+
+    /// The stripe the calling thread updates.
+    fn shard(&self) -> &AtomicIsize {
+        static NEXT_THREAD: AtomicUsize = AtomicUsize::new(0);
+        thread_local! {
+            static THREAD_INDEX: usize = NEXT_THREAD.fetch_add(1, Ordering::Relaxed);
+        }
+        let index = THREAD_INDEX.with(|i| *i) % self.shards.len();
+        &self.shards[index].0
+    }
+
+    /// Add one on the calling thread's stripe.
+    pub fn increment(&self) {
+        self.shard().fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Subtract one on the calling thread's stripe.
+    pub fn decrement(&self) {
+        self.shard().fetch_sub(1, Ordering::Relaxed);
+    }
+
+    /// The current count: the sum of all stripes, never below zero.
+    pub fn load(&self) -> usize {
+        let sum: isize = self.shards.iter().map(|s| s.0.load(Ordering::Relaxed)).sum();
+        sum.max(0) as usize
+    }
+}
+
+impl Default for ShardedCounter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 /// Metrics for a single backend.
 #[derive(Debug)]
 pub struct BackendMetrics {
     /// Current number of active connections
-    pub current_conns: AtomicUsize,
+    pub current_conns: ShardedCounter,
     /// Last recorded round-trip time in milliseconds
     pub last_rtt_ms: AtomicU64,
+    /// Sessions aborted at the maximum session duration
+    pub session_timeouts: AtomicU64,
 }
 
 impl BackendMetrics {
     fn new() -> Self {
         Self {
-            current_conns: AtomicUsize::new(0),
+            current_conns: ShardedCounter::new(),
             last_rtt_ms: AtomicU64::new(0),
+            session_timeouts: AtomicU64::new(0),
         }
     }
 }
@@ -30,12 +121,77 @@ impl Default for BackendMetrics {
     }
 }
 
+/// Cumulative counters of a [`DashMapMetricsStore`], as written to disk.
+///
+/// Gauges (active connections, last RTT) describe the live process and
+/// are not included.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MetricsSnapshot {
+    /// Sessions aborted at the session cap, per backend
+    #[serde(default)]
+    pub session_timeouts: HashMap<String, u64>,
+    /// Routing reload changes: added, removed, updated
+    #[serde(default)]
+    pub routing_changes: [u64; 3],
+    /// DNS queries per app, keyed by outcome label
+    #[serde(default)]
+    pub dns_queries: HashMap<String, HashMap<String, u64>>,
+    /// Backend selections, keyed by outcome label
+    #[serde(default)]
+    pub selections: HashMap<String, u64>,
+    /// Load balancer picks per app, keyed by backend id
+    #[serde(default)]
+    pub backends_selected: HashMap<String, HashMap<String, u64>>,
+    /// Closed connections, keyed by reason label
+    #[serde(default)]
+    pub connections_closed: HashMap<String, u64>,
+    /// Connections rejected because every backend was at its hard limit
+    #[serde(default)]
+    pub overload_rejections: u64,
+    /// Routing decisions made while no geo resolver was loaded
+    #[serde(default)]
+    pub geo_unavailable: u64,
+    /// Replicated changes rejected by conflict resolution, per table
+    #[serde(default)]
+    pub lww_rejected: HashMap<String, u64>,
+    /// Circuit breaker transitions per backend, keyed by state label
+    #[serde(default)]
+    pub circuit_transitions: HashMap<String, HashMap<String, u64>>,
+}
+
 /// DashMap-backed metrics store.
 ///
 /// Uses DashMap for lock-free concurrent access to metrics.
 /// Each backend has its own metrics entry.
 pub struct DashMapMetricsStore {
     metrics: DashMap<String, BackendMetrics>,
+    /// Last duration of each dial phase per backend, in milliseconds
+    connect_phases: DashMap<(String, ConnectPhase), u64>,
+    dns_queries: DashMap<(String, DnsQueryOutcome), AtomicU64>,
+    /// Routing reload changes: added, removed, updated
+    routing_changes: [AtomicU64; 3],
+    /// Loaded backends currently down for maintenance
+    maintenance_backends: AtomicU64,
+    /// Backend dials in progress and addresses queued behind them
+    connect_pressure: [AtomicU64; 2],
+    /// Backend selections, indexed by `SelectionOutcome::index`
+    selections: [AtomicU64; 4],
+    /// Load balancer picks per (app, backend)
+    backends_selected: DashMap<(String, String), AtomicU64>,
+    /// Closed connections, indexed by `CloseReason::index`
+    connections_closed: [AtomicU64; 5],
+    /// Connections rejected because every backend was at its hard limit
+    overload_rejections: AtomicU64,
+    /// Routing decisions made while no geo resolver was loaded
+    geo_unavailable: AtomicU64,
+    /// Last (inbound, outbound) replication lag per peer
+    replication_lag: DashMap<String, (u64, u64)>,
+    /// Replicated changes rejected by conflict resolution, per table
+    lww_rejected: DashMap<String, AtomicU64>,
+    /// Current circuit breaker state per backend
+    circuit_states: DashMap<String, CircuitState>,
+    /// Circuit breaker transitions per (backend, state entered)
+    circuit_transitions: DashMap<(String, CircuitState), AtomicU64>,
 }
 
 impl DashMapMetricsStore {
@@ -43,6 +199,20 @@ impl DashMapMetricsStore {
     pub fn new() -> Self {
         Self {
             metrics: DashMap::new(),
+            connect_phases: DashMap::new(),
+            dns_queries: DashMap::new(),
+            routing_changes: Default::default(),
+            maintenance_backends: AtomicU64::new(0),
+            connect_pressure: Default::default(),
+            selections: Default::default(),
+            backends_selected: DashMap::new(),
+            connections_closed: Default::default(),
+            overload_rejections: AtomicU64::new(0),
+            geo_unavailable: AtomicU64::new(0),
+            replication_lag: DashMap::new(),
+            lww_rejected: DashMap::new(),
+            circuit_states: DashMap::new(),
+            circuit_transitions: DashMap::new(),
         }
     }
 
@@ -57,11 +227,177 @@ impl DashMapMetricsStore {
     pub fn get_metrics(&self, backend_id: &str) -> Option<(usize, u64)> {
         self.metrics.get(backend_id).map(|m| {
             (
-                m.current_conns.load(Ordering::Relaxed),
+                m.current_conns.load(),
                 m.last_rtt_ms.load(Ordering::Relaxed),
             )
         })
     }
+
+    /// Capture the current cumulative counters.
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        let mut dns_queries: HashMap<String, HashMap<String, u64>> = HashMap::new();
+        for entry in self.dns_queries.iter() {
+            let (app, outcome) = entry.key();
+            dns_queries
+                .entry(app.clone())
+                .or_default()
+                .insert(outcome.to_string(), entry.value().load(Ordering::Relaxed));
+        }
+        let mut circuit_transitions: HashMap<String, HashMap<String, u64>> = HashMap::new();
+        for entry in self.circuit_transitions.iter() {
+            let (backend_id, state) = entry.key();
+            circuit_transitions
+                .entry(backend_id.clone())
+                .or_default()
+                .insert(state.to_string(), entry.value().load(Ordering::Relaxed));
+        }
+        let mut backends_selected: HashMap<String, HashMap<String, u64>> = HashMap::new();
+        for entry in self.backends_selected.iter() {
+            let (app, backend_id) = entry.key();
+            backends_selected
+                .entry(app.clone())
+                .or_default()
+                .insert(backend_id.clone(), entry.value().load(Ordering::Relaxed));
+        }
+
+        MetricsSnapshot {
+            session_timeouts: self
+                .metrics
+                .iter()
+                .map(|m| (m.key().clone(), m.session_timeouts.load(Ordering::Relaxed)))
+                .filter(|(_, n)| *n > 0)
+                .collect(),
+            routing_changes: self
+                .routing_changes
+                .each_ref()
+                .map(|c| c.load(Ordering::Relaxed)),
+            dns_queries,
+            selections: SelectionOutcome::ALL
+                .iter()
+                .map(|o| (o.to_string(), self.get_selection_count(*o)))
+                .collect(),
+            backends_selected,
+            connections_closed: CloseReason::ALL
+                .iter()
+                .map(|r| (r.to_string(), self.get_connection_closed(*r)))
+                .collect(),
+            overload_rejections: self.get_overload_rejections(),
+            geo_unavailable: self.get_geo_unavailable(),
+            lww_rejected: self
+                .lww_rejected
+                .iter()
+                .map(|e| (e.key().clone(), e.value().load(Ordering::Relaxed)))
+                .collect(),
+            circuit_transitions,
+        }
+    }
+
+    /// Add the counters of a snapshot to this store.
+    ///
+    /// Meant to be called once on start, before traffic is counted.
+    /// Unknown outcome labels (e.g. from a newer version) are skipped.
+    pub fn restore(&self, snapshot: &MetricsSnapshot) {
+        for (backend_id, n) in &snapshot.session_timeouts {
+            self.metrics
+                .entry(backend_id.clone())
+                .or_default()
+                .session_timeouts
+                .fetch_add(*n, Ordering::Relaxed);
+        }
+        for (counter, n) in self.routing_changes.iter().zip(snapshot.routing_changes) {
+            counter.fetch_add(n, Ordering::Relaxed);
+        }
+        for (app, outcomes) in &snapshot.dns_queries {
+            for (label, n) in outcomes {
+                if let Some(outcome) = DnsQueryOutcome::ALL.iter().find(|o| o.as_str() == label) {
+                    self.dns_queries
+                        .entry((app.clone(), *outcome))
+                        .or_default()
+                        .fetch_add(*n, Ordering::Relaxed);
+                }
+            }
+        }
+        for (label, n) in &snapshot.selections {
+            if let Some(outcome) = SelectionOutcome::ALL.iter().find(|o| o.as_str() == label) {
+                self.selections[outcome.index()].fetch_add(*n, Ordering::Relaxed);
+            }
+        }
+        for (app, backends) in &snapshot.backends_selected {
+            for (backend_id, n) in backends {
+                self.backends_selected
+                    .entry((app.clone(), backend_id.clone()))
+                    .or_default()
+                    .fetch_add(*n, Ordering::Relaxed);
+            }
+        }
+        for (label, n) in &snapshot.connections_closed {
+            if let Some(reason) = CloseReason::ALL.iter().find(|r| r.as_str() == label) {
+                self.connections_closed[reason.index()].fetch_add(*n, Ordering::Relaxed);
+            }
+        }
+        self.overload_rejections
+            .fetch_add(snapshot.overload_rejections, Ordering::Relaxed);
+        self.geo_unavailable
+            .fetch_add(snapshot.geo_unavailable, Ordering::Relaxed);
+        for (table, n) in &snapshot.lww_rejected {
+            self.lww_rejected
+                .entry(table.clone())
+                .or_default()
+                .fetch_add(*n, Ordering::Relaxed);
+        }
+        for (backend_id, states) in &snapshot.circuit_transitions {
+            for (label, n) in states {
+                if let Some(state) = CircuitState::ALL.iter().find(|s| s.as_str() == label) {
+                    self.circuit_transitions
+                        .entry((backend_id.clone(), *state))
+                        .or_default()
+                        .fetch_add(*n, Ordering::Relaxed);
+                }
+            }
+        }
+    }
+
+    /// Write a snapshot of the cumulative counters to `path`.
+    ///
+    /// The snapshot goes to a temporary file that is then renamed over
+    /// `path`, so a crash mid-write leaves the previous snapshot intact.
+    pub fn save_snapshot(&self, path: &Path) -> anyhow::Result<()> {
+        let json = serde_json::to_vec(&self.snapshot())?;
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, json)?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// Restore counters from a snapshot at `path`.
+    ///
+    /// Returns false if there is no snapshot yet.
+    pub fn load_snapshot(&self, path: &Path) -> anyhow::Result<bool> {
+        let json = match std::fs::read(path) {
+            Ok(json) => json,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+            Err(e) => return Err(e.into()),
+        };
+        let snapshot: MetricsSnapshot = serde_json::from_slice(&json)?;
+        self.restore(&snapshot);
+        Ok(true)
+    }
+
+    /// Start a background task that snapshots the counters to `path`
+    /// every `interval`.
+    pub fn start_snapshots(store: Arc<Self>, path: PathBuf, interval: Duration) {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            // The first tick completes immediately; nothing to save yet
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                if let Err(e) = store.save_snapshot(&path) {
+                    tracing::warn!("failed to write metrics snapshot to {:?}: {:?}", path, e);
+                }
+            }
+        });
+    }
 }
 
 impl Default for DashMapMetricsStore {
@@ -74,33 +410,26 @@ impl MetricsStore for DashMapMetricsStore {
     fn get_connection_count(&self, backend_id: &str) -> usize {
         self.metrics
             .get(backend_id)
-            .map(|m| m.current_conns.load(Ordering::Relaxed))
+            .map(|m| m.current_conns.load())
             .unwrap_or(0)
     }
 
     fn increment_connections(&self, backend_id: &str) {
+        // Known backends only take a read lock on their map shard
+        if let Some(m) = self.metrics.get(backend_id) {
+            m.current_conns.increment();
+            return;
+        }
         self.metrics
             .entry(backend_id.to_string())
             .or_default()
             .current_conns
-            .fetch_add(1, Ordering::Relaxed);
+            .increment();
     }
 
     fn decrement_connections(&self, backend_id: &str) {
         if let Some(m) = self.metrics.get(backend_id) {
-            // Use compare_exchange loop to prevent underflow
-            let mut current = m.current_conns.load(Ordering::Relaxed);
-            while current > 0 {
-                match m.current_conns.compare_exchange_weak(
-                    current,
-                    current - 1,
-                    Ordering::Relaxed,
-                    Ordering::Relaxed,
-                ) {
-                    Ok(_) => break,
-                    Err(c) => current = c,
-                }
-            }
+            m.current_conns.decrement();
         }
     }
 
@@ -117,6 +446,163 @@ impl MetricsStore for DashMapMetricsStore {
             .get(backend_id)
             .map(|m| m.last_rtt_ms.load(Ordering::Relaxed))
     }
+
+    fn record_connect_phase(&self, backend_id: &str, phase: ConnectPhase, ms: u64) {
+        self.connect_phases.insert((backend_id.to_string(), phase), ms);
+    }
+
+    fn get_connect_phase(&self, backend_id: &str, phase: ConnectPhase) -> Option<u64> {
+        self.connect_phases
+            .get(&(backend_id.to_string(), phase))
+            .map(|v| *v)
+    }
+
+    fn record_session_timeout(&self, backend_id: &str) {
+        self.metrics
+            .entry(backend_id.to_string())
+            .or_default()
+            .session_timeouts
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn get_session_timeouts(&self, backend_id: &str) -> u64 {
+        self.metrics
+            .get(backend_id)
+            .map(|m| m.session_timeouts.load(Ordering::Relaxed))
+            .unwrap_or(0)
+    }
+
+    fn record_routing_changes(&self, added: usize, removed: usize, updated: usize) {
+        for (counter, n) in self.routing_changes.iter().zip([added, removed, updated]) {
+            counter.fetch_add(n as u64, Ordering::Relaxed);
+        }
+    }
+
+    fn get_routing_changes(&self) -> (u64, u64, u64) {
+        let [added, removed, updated] = &self.routing_changes;
+        (
+            added.load(Ordering::Relaxed),
+            removed.load(Ordering::Relaxed),
+            updated.load(Ordering::Relaxed),
+        )
+    }
+
+    fn set_maintenance_backends(&self, count: usize) {
+        self.maintenance_backends.store(count as u64, Ordering::Relaxed);
+    }
+
+    fn get_maintenance_backends(&self) -> u64 {
+        self.maintenance_backends.load(Ordering::Relaxed)
+    }
+
+    fn set_connect_pressure(&self, in_flight: usize, queued: usize) {
+        let [in_flight_gauge, queued_gauge] = &self.connect_pressure;
+        in_flight_gauge.store(in_flight as u64, Ordering::Relaxed);
+        queued_gauge.store(queued as u64, Ordering::Relaxed);
+    }
+
+    fn get_connect_pressure(&self) -> (u64, u64) {
+        let [in_flight, queued] = &self.connect_pressure;
+        (in_flight.load(Ordering::Relaxed), queued.load(Ordering::Relaxed))
+    }
+
+    fn record_dns_query(&self, app: &str, outcome: DnsQueryOutcome) {
+        self.dns_queries
+            .entry((app.to_string(), outcome))
+            .or_default()
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn get_dns_query_count(&self, app: &str, outcome: DnsQueryOutcome) -> u64 {
+        self.dns_queries
+            .get(&(app.to_string(), outcome))
+            .map(|c| c.load(Ordering::Relaxed))
+            .unwrap_or(0)
+    }
+
+    fn record_selection(&self, outcome: SelectionOutcome) {
+        self.selections[outcome.index()].fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn get_selection_count(&self, outcome: SelectionOutcome) -> u64 {
+        self.selections[outcome.index()].load(Ordering::Relaxed)
+    }
+
+    fn record_backend_selected(&self, app: &str, backend_id: &str) {
+        self.backends_selected
+            .entry((app.to_string(), backend_id.to_string()))
+            .or_default()
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn get_backend_selected(&self, app: &str, backend_id: &str) -> u64 {
+        self.backends_selected
+            .get(&(app.to_string(), backend_id.to_string()))
+            .map_or(0, |n| n.load(Ordering::Relaxed))
+    }
+
+    fn record_connection_closed(&self, reason: CloseReason) {
+        self.connections_closed[reason.index()].fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn get_connection_closed(&self, reason: CloseReason) -> u64 {
+        self.connections_closed[reason.index()].load(Ordering::Relaxed)
+    }
+
+    fn record_overload_rejection(&self) {
+        self.overload_rejections.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn get_overload_rejections(&self) -> u64 {
+        self.overload_rejections.load(Ordering::Relaxed)
+    }
+
+    fn record_geo_unavailable(&self) {
+        self.geo_unavailable.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn get_geo_unavailable(&self) -> u64 {
+        self.geo_unavailable.load(Ordering::Relaxed)
+    }
+
+    fn record_replication_lag(&self, peer: &str, inbound: u64, outbound: u64) {
+        self.replication_lag.insert(peer.to_string(), (inbound, outbound));
+    }
+
+    fn get_replication_lag(&self, peer: &str) -> Option<(u64, u64)> {
+        self.replication_lag.get(peer).map(|lag| *lag)
+    }
+
+    fn record_circuit_transition(&self, backend_id: &str, state: CircuitState) {
+        self.circuit_states.insert(backend_id.to_string(), state);
+        self.circuit_transitions
+            .entry((backend_id.to_string(), state))
+            .or_default()
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn get_circuit_state(&self, backend_id: &str) -> Option<CircuitState> {
+        self.circuit_states.get(backend_id).map(|state| *state)
+    }
+
+    fn get_circuit_transitions(&self, backend_id: &str, state: CircuitState) -> u64 {
+        self.circuit_transitions
+            .get(&(backend_id.to_string(), state))
+            .map_or(0, |n| n.load(Ordering::Relaxed))
+    }
+
+    fn record_lww_rejected(&self, table: &str) {
+        self.lww_rejected
+            .entry(table.to_string())
+            .or_default()
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn get_lww_rejected(&self, table: &str) -> u64 {
+        self.lww_rejected
+            .get(table)
+            .map_or(0, |n| n.load(Ordering::Relaxed))
+    }
 }
 
 #[cfg(test)]
@@ -209,6 +695,30 @@ mod tests {
         assert_eq!(store.get_last_rtt("backend-1"), Some(100));
     }
 
+    #[test]
+    fn test_connect_phases_recorded_separately() {
+        let store = DashMapMetricsStore::new();
+        assert!(store
+            .get_connect_phase("backend-1", ConnectPhase::Resolve)
+            .is_none());
+
+        store.record_connect_phase("backend-1", ConnectPhase::Resolve, 3);
+        store.record_connect_phase("backend-1", ConnectPhase::Connect, 12);
+        store.record_connect_phase("backend-1", ConnectPhase::Connect, 9);
+
+        assert_eq!(
+            store.get_connect_phase("backend-1", ConnectPhase::Resolve),
+            Some(3)
+        );
+        assert_eq!(
+            store.get_connect_phase("backend-1", ConnectPhase::Connect),
+            Some(9)
+        );
+        assert!(store
+            .get_connect_phase("backend-2", ConnectPhase::Connect)
+            .is_none());
+    }
+
     #[test]
     fn test_rtt_zero_value() {
         let store = DashMapMetricsStore::new();
@@ -290,6 +800,301 @@ mod tests {
         assert!(store.get_metrics("nonexistent").is_none());
     }
 
+    // ===== DNS Query Tests =====
+
+    #[test]
+    fn test_session_timeouts() {
+        let store = DashMapMetricsStore::new();
+        assert_eq!(store.get_session_timeouts("b1"), 0);
+
+        store.record_session_timeout("b1");
+        store.record_session_timeout("b1");
+
+        assert_eq!(store.get_session_timeouts("b1"), 2);
+        assert_eq!(store.get_session_timeouts("b2"), 0);
+    }
+
+    #[test]
+    fn test_routing_changes_accumulate() {
+        let store = DashMapMetricsStore::new();
+        assert_eq!(store.get_routing_changes(), (0, 0, 0));
+
+        store.record_routing_changes(2, 0, 1);
+        store.record_routing_changes(1, 3, 0);
+
+        assert_eq!(store.get_routing_changes(), (3, 3, 1));
+    }
+
+    #[test]
+    fn test_maintenance_backends_is_a_gauge() {
+        let store = DashMapMetricsStore::new();
+        assert_eq!(store.get_maintenance_backends(), 0);
+
+        store.set_maintenance_backends(3);
+        store.set_maintenance_backends(1);
+
+        assert_eq!(store.get_maintenance_backends(), 1);
+    }
+
+    #[test]
+    fn test_connect_pressure_is_a_gauge() {
+        let store = DashMapMetricsStore::new();
+        assert_eq!(store.get_connect_pressure(), (0, 0));
+
+        store.set_connect_pressure(12, 3);
+        store.set_connect_pressure(5, 0);
+
+        assert_eq!(store.get_connect_pressure(), (5, 0));
+    }
+
+    // ===== Snapshot Tests =====
+
+    fn populated_store() -> DashMapMetricsStore {
+        let store = DashMapMetricsStore::new();
+        store.increment_connections("b1");
+        store.record_rtt("b1", 12);
+        store.record_session_timeout("b1");
+        store.record_session_timeout("b1");
+        store.record_routing_changes(3, 1, 2);
+        store.record_dns_query("myapp", DnsQueryOutcome::NoError);
+        store.record_dns_query("myapp", DnsQueryOutcome::NxDomain);
+        store.record_selection(SelectionOutcome::RegionFallback);
+        store.record_backend_selected("myapp", "b1");
+        store.record_connection_closed(CloseReason::BackendClosed);
+        store.record_overload_rejection();
+        store.record_geo_unavailable();
+        store.record_lww_rejected("backends");
+        store.record_circuit_transition("b1", CircuitState::Open);
+        store
+    }
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("metrics.json");
+
+        populated_store().save_snapshot(&path).unwrap();
+
+        let restored = DashMapMetricsStore::new();
+        assert!(restored.load_snapshot(&path).unwrap());
+
+        assert_eq!(restored.get_session_timeouts("b1"), 2);
+        assert_eq!(restored.get_routing_changes(), (3, 1, 2));
+        assert_eq!(restored.get_dns_query_count("myapp", DnsQueryOutcome::NoError), 1);
+        assert_eq!(restored.get_dns_query_count("myapp", DnsQueryOutcome::NxDomain), 1);
+        assert_eq!(restored.get_selection_count(SelectionOutcome::RegionFallback), 1);
+        assert_eq!(restored.get_backend_selected("myapp", "b1"), 1);
+        assert_eq!(restored.get_connection_closed(CloseReason::BackendClosed), 1);
+        assert_eq!(restored.get_overload_rejections(), 1);
+        assert_eq!(restored.get_geo_unavailable(), 1);
+        assert_eq!(restored.get_lww_rejected("backends"), 1);
+        assert_eq!(restored.get_circuit_transitions("b1", CircuitState::Open), 1);
+        assert_eq!(restored.snapshot(), populated_store().snapshot());
+
+        // The circuit state is live and starts over with the breaker
+        assert!(restored.get_circuit_state("b1").is_none());
+
+        // Gauges describe the old process and are not restored
+        assert_eq!(restored.get_connection_count("b1"), 0);
+        assert_eq!(restored.get_last_rtt("b1"), Some(0));
+    }
+
+    #[test]
+    fn test_restore_adds_to_existing_counters() {
+        let store = populated_store();
+        store.restore(&populated_store().snapshot());
+
+        assert_eq!(store.get_session_timeouts("b1"), 4);
+        assert_eq!(store.get_routing_changes(), (6, 2, 4));
+        assert_eq!(store.get_selection_count(SelectionOutcome::RegionFallback), 2);
+        assert_eq!(store.get_backend_selected("myapp", "b1"), 2);
+        assert_eq!(store.get_connection_closed(CloseReason::BackendClosed), 2);
+        assert_eq!(store.get_overload_rejections(), 2);
+        assert_eq!(store.get_geo_unavailable(), 2);
+        assert_eq!(store.get_lww_rejected("backends"), 2);
+    }
+
+    #[test]
+    fn test_save_snapshot_replaces_previous() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("metrics.json");
+        let store = DashMapMetricsStore::new();
+
+        store.save_snapshot(&path).unwrap();
+        store.record_routing_changes(1, 0, 0);
+        store.save_snapshot(&path).unwrap();
+
+        let restored = DashMapMetricsStore::new();
+        restored.load_snapshot(&path).unwrap();
+        assert_eq!(restored.get_routing_changes(), (1, 0, 0));
+        assert!(!path.with_extension("tmp").exists());
+    }
+
+    #[test]
+    fn test_load_snapshot_missing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = DashMapMetricsStore::new();
+        assert!(!store.load_snapshot(&dir.path().join("missing.json")).unwrap());
+    }
+
+    #[test]
+    fn test_load_snapshot_corrupt_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("metrics.json");
+        std::fs::write(&path, b"not json").unwrap();
+
+        assert!(DashMapMetricsStore::new().load_snapshot(&path).is_err());
+    }
+
+    #[test]
+    fn test_restore_skips_unknown_labels() {
+        let mut snapshot = MetricsSnapshot::default();
+        snapshot.selections.insert("sideways".to_string(), 5);
+        snapshot
+            .dns_queries
+            .insert("myapp".to_string(), HashMap::from([("weird".to_string(), 5)]));
+
+        let store = DashMapMetricsStore::new();
+        store.restore(&snapshot);
+
+        assert!(SelectionOutcome::ALL
+            .iter()
+            .all(|o| store.get_selection_count(*o) == 0));
+        assert!(DnsQueryOutcome::ALL
+            .iter()
+            .all(|o| store.get_dns_query_count("myapp", *o) == 0));
+    }
+
+    #[tokio::test]
+    async fn test_start_snapshots_writes_periodically() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("metrics.json");
+        let store = Arc::new(populated_store());
+
+        DashMapMetricsStore::start_snapshots(store, path.clone(), Duration::from_millis(10));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let restored = DashMapMetricsStore::new();
+        assert!(restored.load_snapshot(&path).unwrap());
+        assert_eq!(restored.get_routing_changes(), (3, 1, 2));
+    }
+
+    #[test]
+    fn test_selection_counts_per_outcome() {
+        let store = DashMapMetricsStore::new();
+
+        store.record_selection(SelectionOutcome::InRegion);
+        store.record_selection(SelectionOutcome::RegionFallback);
+        store.record_selection(SelectionOutcome::RegionFallback);
+
+        assert_eq!(store.get_selection_count(SelectionOutcome::InRegion), 1);
+        assert_eq!(store.get_selection_count(SelectionOutcome::RegionFallback), 2);
+        assert_eq!(store.get_selection_count(SelectionOutcome::NoBackend), 0);
+    }
+
+    #[test]
+    fn test_backend_selected_per_app_and_backend() {
+        let store = DashMapMetricsStore::new();
+
+        store.record_backend_selected("myapp", "b1");
+        store.record_backend_selected("myapp", "b1");
+        store.record_backend_selected("myapp", "b2");
+        store.record_backend_selected("other", "b1");
+
+        assert_eq!(store.get_backend_selected("myapp", "b1"), 2);
+        assert_eq!(store.get_backend_selected("myapp", "b2"), 1);
+        assert_eq!(store.get_backend_selected("other", "b1"), 1);
+        assert_eq!(store.get_backend_selected("other", "b2"), 0);
+    }
+
+    #[test]
+    fn test_connections_closed_per_reason() {
+        let store = DashMapMetricsStore::new();
+
+        store.record_connection_closed(CloseReason::ClientClosed);
+        store.record_connection_closed(CloseReason::SessionTimeout);
+        store.record_connection_closed(CloseReason::SessionTimeout);
+
+        assert_eq!(store.get_connection_closed(CloseReason::ClientClosed), 1);
+        assert_eq!(store.get_connection_closed(CloseReason::SessionTimeout), 2);
+        assert_eq!(store.get_connection_closed(CloseReason::Error), 0);
+    }
+
+    #[test]
+    fn test_dns_query_count_starts_at_zero() {
+        let store = DashMapMetricsStore::new();
+        assert_eq!(
+            store.get_dns_query_count("myapp", DnsQueryOutcome::NoError),
+            0
+        );
+    }
+
+    #[test]
+    fn test_dns_query_counts_per_app_and_outcome() {
+        let store = DashMapMetricsStore::new();
+
+        store.record_dns_query("myapp", DnsQueryOutcome::NoError);
+        store.record_dns_query("myapp", DnsQueryOutcome::NoError);
+        store.record_dns_query("myapp", DnsQueryOutcome::NxDomain);
+        store.record_dns_query("other", DnsQueryOutcome::NotImp);
+
+        assert_eq!(
+            store.get_dns_query_count("myapp", DnsQueryOutcome::NoError),
+            2
+        );
+        assert_eq!(
+            store.get_dns_query_count("myapp", DnsQueryOutcome::NxDomain),
+            1
+        );
+        assert_eq!(
+            store.get_dns_query_count("myapp", DnsQueryOutcome::NotImp),
+            0
+        );
+        assert_eq!(
+            store.get_dns_query_count("other", DnsQueryOutcome::NotImp),
+            1
+        );
+    }
+
+    #[test]
+    fn test_replication_lag_keeps_latest_per_peer() {
+        let store = DashMapMetricsStore::new();
+        assert_eq!(store.get_replication_lag("node-2"), None);
+
+        store.record_replication_lag("node-2", 4, 1);
+        store.record_replication_lag("node-2", 0, 2);
+
+        assert_eq!(store.get_replication_lag("node-2"), Some((0, 2)));
+    }
+
+    #[test]
+    fn test_circuit_transitions() {
+        let store = DashMapMetricsStore::new();
+        assert!(store.get_circuit_state("b1").is_none());
+
+        store.record_circuit_transition("b1", CircuitState::Open);
+        store.record_circuit_transition("b1", CircuitState::HalfOpen);
+        store.record_circuit_transition("b1", CircuitState::Open);
+
+        assert_eq!(store.get_circuit_state("b1"), Some(CircuitState::Open));
+        assert_eq!(store.get_circuit_transitions("b1", CircuitState::Open), 2);
+        assert_eq!(store.get_circuit_transitions("b1", CircuitState::HalfOpen), 1);
+        assert_eq!(store.get_circuit_transitions("b1", CircuitState::Closed), 0);
+        assert_eq!(store.get_circuit_transitions("b2", CircuitState::Open), 0);
+    }
+
+    #[test]
+    fn test_lww_rejected_counts_per_table() {
+        let store = DashMapMetricsStore::new();
+        store.record_lww_rejected("backends");
+        store.record_lww_rejected("backends");
+        store.record_lww_rejected("routes");
+
+        assert_eq!(store.get_lww_rejected("backends"), 2);
+        assert_eq!(store.get_lww_rejected("routes"), 1);
+        assert_eq!(store.get_lww_rejected("other"), 0);
+    }
+
     // ===== Default Trait Tests =====
 
     #[test]
@@ -355,10 +1160,80 @@ mod tests {
         assert_eq!(store.get_connection_count("backend-1"), 0);
     }
 
+    #[test]
+    fn test_concurrent_connection_churn_on_hot_backend() {
+        use std::sync::Arc;
+        use std::thread;
+
+        const THREADS: usize = 16;
+        const OPS: usize = 50_000;
+
+        let store = Arc::new(DashMapMetricsStore::new());
+        let handles: Vec<_> = (0..THREADS)
+            .map(|t| {
+                let store = store.clone();
+                thread::spawn(move || {
+                    // Open and close connections, leaving `t` open per thread
+                    for _ in 0..OPS {
+                        store.increment_connections("hot");
+                        store.decrement_connections("hot");
+                    }
+                    for _ in 0..t {
+                        store.increment_connections("hot");
+                    }
+                })
+            })
+            .collect();
+        for h in handles {
+            h.join().unwrap();
+        }
+
+        let open: usize = (0..THREADS).sum();
+        assert_eq!(store.get_connection_count("hot"), open);
+
+        // Connections opened on one thread may close on another
+        let closers: Vec<_> = (0..4)
+            .map(|_| {
+                let store = store.clone();
+                thread::spawn(move || {
+                    for _ in 0..open / 4 {
+                        store.decrement_connections("hot");
+                    }
+                })
+            })
+            .collect();
+        for h in closers {
+            h.join().unwrap();
+        }
+        assert_eq!(store.get_connection_count("hot"), open % 4);
+    }
+
+    #[test]
+    fn test_sharded_counter_sums_stripes() {
+        let counter = ShardedCounter::with_shards(4);
+        assert_eq!(counter.shards.len(), 4);
+
+        // Stripes other than the caller's can go negative
+        counter.shards[0].0.store(5, Ordering::Relaxed);
+        counter.shards[1].0.store(-2, Ordering::Relaxed);
+        assert_eq!(counter.load(), 3);
+
+        counter.shards[2].0.store(-10, Ordering::Relaxed);
+        assert_eq!(counter.load(), 0);
+    }
+
+    #[test]
+    fn test_sharded_counter_is_bounded() {
+        assert_eq!(ShardedCounter::with_shards(0).shards.len(), 1);
+        assert_eq!(ShardedCounter::with_shards(1024).shards.len(), MAX_COUNTER_SHARDS);
+        assert!(ShardedCounter::new().shards.len() <= MAX_COUNTER_SHARDS);
+        assert_eq!(std::mem::align_of::<CounterShard>(), 64);
+    }
+
     #[test]
     fn test_backend_metrics_default() {
         let metrics = BackendMetrics::default();
-        assert_eq!(metrics.current_conns.load(Ordering::Relaxed), 0);
+        assert_eq!(metrics.current_conns.load(), 0);
         assert_eq!(metrics.last_rtt_ms.load(Ordering::Relaxed), 0);
     }
 
@@ -0,0 +1,382 @@
+//! File Backend Repository
+//!
+//! Implements BackendRepository from a JSON file, for static deployments
+//! that don't need a database. Reloads when the file changes.
+
+use crate::domain::entities::Backend;
+use crate::domain::ports::BackendRepository;
+use crate::domain::value_objects::RegionCode;
+use crate::infrastructure::{ConfigChange, ConfigWatcher};
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::RwLock;
+
+/// A backend entry in the file; optional fields default like API registrations.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FileBackend {
+    id: String,
+    app: String,
+    region: String,
+    #[serde(default)]
+    country: Option<String>,
+    #[serde(alias = "ip")]
+    wg_ip: String,
+    port: u16,
+    #[serde(default = "default_healthy")]
+    healthy: bool,
+    #[serde(default = "default_weight")]
+    weight: u8,
+    #[serde(default = "default_soft_limit")]
+    soft_limit: u32,
+    #[serde(default = "default_hard_limit")]
+    hard_limit: u32,
+    #[serde(default)]
+    draining: bool,
+    #[serde(default)]
+    maintenance: bool,
+    #[serde(default)]
+    priority: u16,
+}
+
+fn default_healthy() -> bool {
+    true
+}
+fn default_weight() -> u8 {
+    2
+}
+fn default_soft_limit() -> u32 {
+    100
+}
+fn default_hard_limit() -> u32 {
+    150
+}
+
+/// Either a bare list of backends or `{"backends": [...]}`.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum FileContents {
+    List(Vec<FileBackend>),
+    Wrapped { backends: Vec<FileBackend> },
+}
+
+impl From<FileBackend> for Backend {
+    fn from(entry: FileBackend) -> Self {
+        let region = RegionCode::from_code(&entry.region);
+        let country = entry
+            .country
+            .unwrap_or_else(|| region.default_country().to_string());
+        Backend {
+            id: entry.id,
+            app: entry.app,
+            region,
+            country,
+            wg_ip: entry.wg_ip,
+            port: entry.port,
+            healthy: entry.healthy,
+            weight: entry.weight,
+            soft_limit: entry.soft_limit,
+            hard_limit: entry.hard_limit,
+            draining: entry.draining,
+            maintenance: entry.maintenance,
+            priority: entry.priority,
+        }
+    }
+}
+
+/// File-backed backend repository.
+///
+/// The version is a checksum of the file contents, so it changes exactly
+/// when the loaded backends may have. A file that fails to parse leaves
+/// the last good set in place.
+pub struct FileBackendRepository {
+    path: PathBuf,
+    backends: Arc<RwLock<Vec<Backend>>>,
+    version: Arc<AtomicU64>,
+}
+
+impl FileBackendRepository {
+    /// Load backends from a file; fails if the initial file is unreadable or invalid.
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let contents = std::fs::read(&path)?;
+        let backends = Self::parse(&contents)?;
+        tracing::info!("loaded {} backends from {}", backends.len(), path.display());
+
+        Ok(Self {
+            path,
+            backends: Arc::new(RwLock::new(backends)),
+            version: Arc::new(AtomicU64::new(Self::content_version(&contents))),
+        })
+    }
+
+    /// Path of the backing file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Share the same state (used to hand the repository to the watch task).
+    fn handle(&self) -> Self {
+        Self {
+            path: self.path.clone(),
+            backends: self.backends.clone(),
+            version: self.version.clone(),
+        }
+    }
+
+    /// Re-read the file, replacing the backends if its contents changed.
+    ///
+    /// Returns true if a new set was loaded. On a read or parse error the
+    /// current backends and version are kept and the error is returned.
+    pub async fn reload(&self) -> Result<bool> {
+        let contents = tokio::fs::read(&self.path).await?;
+        let version = Self::content_version(&contents);
+        if version == self.version.load(Ordering::SeqCst) {
+            tracing::trace!("{} unchanged, skipping reload", self.path.display());
+            return Ok(false);
+        }
+
+        let backends = Self::parse(&contents)?;
+        let count = backends.len();
+        *self.backends.write().await = backends;
+        self.version.store(version, Ordering::SeqCst);
+
+        tracing::info!(
+            "reloaded {} backends from {} (version={:08x})",
+            count,
+            self.path.display(),
+            version
+        );
+        Ok(true)
+    }
+
+    /// Reload whenever `watcher` reports the file modified.
+    ///
+    /// The watcher's own polling task must be started separately.
+    pub async fn start_watching(&self, watcher: Arc<ConfigWatcher>) -> Result<()> {
+        let mut changes = watcher.subscribe();
+        watcher.watch_file(&self.path).await?;
+        let repo = self.handle();
+
+        tokio::spawn(async move {
+            loop {
+                match changes.recv().await {
+                    Ok(ConfigChange::FileModified(path)) if path == repo.path => {}
+                    Ok(ConfigChange::FullReload) | Err(RecvError::Lagged(_)) => {}
+                    Ok(_) => continue,
+                    Err(RecvError::Closed) => break,
+                }
+
+                if let Err(e) = repo.reload().await {
+                    tracing::error!(
+                        "keeping last good backends, failed to load {}: {:#}",
+                        repo.path.display(),
+                        e
+                    );
+                }
+            }
+        });
+        Ok(())
+    }
+
+    /// Parse file contents, rejecting duplicate ids.
+    fn parse(contents: &[u8]) -> Result<Vec<Backend>> {
+        let entries = match serde_json::from_slice(contents)? {
+            FileContents::List(entries) => entries,
+            FileContents::Wrapped { backends } => backends,
+        };
+
+        let mut seen = HashSet::new();
+        for entry in &entries {
+            if !seen.insert(entry.id.as_str()) {
+                anyhow::bail!("duplicate backend id: {}", entry.id);
+            }
+        }
+
+        Ok(entries.into_iter().map(Backend::from).collect())
+    }
+
+    /// Checksum of the file contents, used as the repository version.
+    fn content_version(contents: &[u8]) -> u64 {
+        crc32fast::hash(contents) as u64
+    }
+}
+
+#[async_trait]
+impl BackendRepository for FileBackendRepository {
+    async fn get_all(&self) -> Vec<Backend> {
+        self.backends.read().await.clone()
+    }
+
+    async fn get_by_id(&self, id: &str) -> Option<Backend> {
+        self.backends
+            .read()
+            .await
+            .iter()
+            .find(|b| b.id == id)
+            .cloned()
+    }
+
+    async fn get_healthy(&self) -> Vec<Backend> {
+        self.backends
+            .read()
+            .await
+            .iter()
+            .filter(|b| b.healthy)
+            .cloned()
+            .collect()
+    }
+
+    async fn get_version(&self) -> u64 {
+        self.version.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::time::Duration;
+    use tempfile::NamedTempFile;
+
+    const TWO_BACKENDS: &str = r#"[
+        {"id": "eu-1", "app": "myapp", "region": "eu", "wg_ip": "10.50.1.1", "port": 8080},
+        {"id": "us-1", "app": "myapp", "region": "us", "country": "CA", "ip": "10.50.2.1", "port": 8080,
+         "healthy": false, "weight": 5, "draining": true}
+    ]"#;
+
+    fn write_file(contents: &str) -> NamedTempFile {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(contents.as_bytes()).unwrap();
+        file
+    }
+
+    fn rewrite(file: &NamedTempFile, contents: &str) {
+        std::fs::write(file.path(), contents).unwrap();
+    }
+
+    // ===== Load Tests =====
+
+    #[tokio::test]
+    async fn test_initial_load() {
+        let file = write_file(TWO_BACKENDS);
+        let repo = FileBackendRepository::load(file.path()).unwrap();
+
+        assert_eq!(repo.get_all().await.len(), 2);
+        assert_ne!(repo.get_version().await, 0);
+
+        let eu = repo.get_by_id("eu-1").await.unwrap();
+        assert_eq!(eu.region, RegionCode::Europe);
+        assert_eq!(eu.country, RegionCode::Europe.default_country());
+        assert!(eu.healthy);
+        assert_eq!((eu.weight, eu.soft_limit, eu.hard_limit), (2, 100, 150));
+
+        let us = repo.get_by_id("us-1").await.unwrap();
+        assert_eq!(us.country, "CA");
+        assert_eq!(us.wg_ip, "10.50.2.1");
+        assert_eq!(us.weight, 5);
+        assert!(us.draining);
+
+        let healthy = repo.get_healthy().await;
+        assert_eq!(healthy.len(), 1);
+        assert_eq!(healthy[0].id, "eu-1");
+    }
+
+    #[tokio::test]
+    async fn test_load_wrapped_object() {
+        let file = write_file(r#"{"backends": [{"id": "b1", "app": "a", "region": "sa", "wg_ip": "10.0.0.1", "port": 80}]}"#);
+        let repo = FileBackendRepository::load(file.path()).unwrap();
+        assert_eq!(repo.get_all().await.len(), 1);
+        assert_eq!(repo.path(), file.path());
+    }
+
+    #[test]
+    fn test_load_rejects_invalid_initial_file() {
+        assert!(FileBackendRepository::load("/nonexistent/backends.json").is_err());
+
+        let file = write_file("not json");
+        assert!(FileBackendRepository::load(file.path()).is_err());
+
+        let file = write_file(r#"[{"id": "b1", "app": "a", "region": "eu", "wg_ip": "10.0.0.1"}]"#);
+        assert!(FileBackendRepository::load(file.path()).is_err());
+    }
+
+    #[test]
+    fn test_load_rejects_duplicate_ids() {
+        let file = write_file(r#"[
+            {"id": "b1", "app": "a", "region": "eu", "wg_ip": "10.0.0.1", "port": 80},
+            {"id": "b1", "app": "a", "region": "eu", "wg_ip": "10.0.0.2", "port": 80}
+        ]"#);
+        let err = FileBackendRepository::load(file.path()).err().unwrap();
+        assert!(err.to_string().contains("duplicate backend id"));
+    }
+
+    // ===== Reload Tests =====
+
+    #[tokio::test]
+    async fn test_reload_on_change() {
+        let file = write_file(TWO_BACKENDS);
+        let repo = FileBackendRepository::load(file.path()).unwrap();
+        let version = repo.get_version().await;
+
+        // Unchanged contents are not a new version
+        assert!(!repo.reload().await.unwrap());
+        assert_eq!(repo.get_version().await, version);
+
+        rewrite(&file, r#"[{"id": "ap-1", "app": "myapp", "region": "ap", "wg_ip": "10.50.3.1", "port": 9000}]"#);
+        assert!(repo.reload().await.unwrap());
+
+        assert_ne!(repo.get_version().await, version);
+        let all = repo.get_all().await;
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].id, "ap-1");
+    }
+
+    #[tokio::test]
+    async fn test_invalid_file_keeps_last_good_set() {
+        let file = write_file(TWO_BACKENDS);
+        let repo = FileBackendRepository::load(file.path()).unwrap();
+        let version = repo.get_version().await;
+
+        rewrite(&file, r#"[{"id": "broken""#);
+        assert!(repo.reload().await.is_err());
+        assert_eq!(repo.get_all().await.len(), 2);
+        assert_eq!(repo.get_version().await, version);
+
+        // A removed file is also survived
+        std::fs::remove_file(file.path()).unwrap();
+        assert!(repo.reload().await.is_err());
+        assert_eq!(repo.get_all().await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_watcher_triggers_reload() {
+        let file = write_file(TWO_BACKENDS);
+        let repo = FileBackendRepository::load(file.path()).unwrap();
+        let version = repo.get_version().await;
+
+        let watcher = Arc::new(ConfigWatcher::new(Duration::from_millis(10)));
+        repo.start_watching(watcher.clone()).await.unwrap();
+        watcher.clone().start();
+
+        // Ensure the new mtime is strictly later
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        rewrite(&file, r#"[{"id": "sa-1", "app": "myapp", "region": "sa", "wg_ip": "10.50.4.1", "port": 8080}]"#);
+
+        let reloaded = tokio::time::timeout(Duration::from_secs(2), async {
+            while repo.get_version().await == version {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await;
+        assert!(reloaded.is_ok());
+        assert!(repo.get_by_id("sa-1").await.is_some());
+    }
+}
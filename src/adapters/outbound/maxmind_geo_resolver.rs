@@ -5,10 +5,14 @@
 use crate::domain::entities::GeoInfo;
 use crate::domain::ports::GeoResolver;
 use crate::domain::value_objects::RegionCode;
+use crate::infrastructure::{ConfigChange, ConfigWatcher};
+use arc_swap::ArcSwap;
 use maxminddb::Reader;
 use serde::Deserialize;
 use std::net::IpAddr;
+use std::path::PathBuf;
 use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
 
 /// Embedded GeoLite2-Country database (compiled into binary).
 const EMBEDDED_GEOIP: &[u8] = include_bytes!("../../../GeoLite2-Country.mmdb");
@@ -16,9 +20,11 @@ const EMBEDDED_GEOIP: &[u8] = include_bytes!("../../../GeoLite2-Country.mmdb");
 /// MaxMind GeoIP resolver.
 ///
 /// Uses the MaxMind GeoLite2 database to resolve IP addresses
-/// to country codes and geographic regions.
+/// to country codes and geographic regions. The database can be
+/// replaced at runtime with `reload_from_file`, or automatically when
+/// its file changes with `start_watching`.
 pub struct MaxMindGeoResolver {
-    reader: Arc<Reader<Vec<u8>>>,
+    reader: ArcSwap<Reader<Vec<u8>>>,
 }
 
 impl MaxMindGeoResolver {
@@ -26,7 +32,7 @@ impl MaxMindGeoResolver {
     pub fn embedded() -> anyhow::Result<Self> {
         let reader = Reader::from_source(EMBEDDED_GEOIP.to_vec())?;
         Ok(Self {
-            reader: Arc::new(reader),
+            reader: ArcSwap::from_pointee(reader),
         })
     }
 
@@ -34,9 +40,57 @@ impl MaxMindGeoResolver {
     pub fn from_file(path: &str) -> anyhow::Result<Self> {
         let reader = Reader::open_readfile(path)?;
         Ok(Self {
-            reader: Arc::new(reader),
+            reader: ArcSwap::from_pointee(reader),
         })
     }
+
+    /// Atomically replace the database with the one at `path`.
+    ///
+    /// The new file is fully loaded before the swap; on error the
+    /// current database stays in use.
+    pub fn reload_from_file(&self, path: &str) -> anyhow::Result<()> {
+        let reader = Reader::open_readfile(path)?;
+        self.reader.store(Arc::new(reader));
+        tracing::info!("GeoIP database reloaded from {}", path);
+        Ok(())
+    }
+
+    /// Reload from `path` whenever `watcher` reports the file modified.
+    ///
+    /// A file that fails to load (e.g. corrupt or still being written) is
+    /// logged and skipped; the current database keeps serving until a
+    /// later change loads cleanly. The watcher's own polling task must be
+    /// started separately.
+    pub async fn start_watching(
+        self: &Arc<Self>,
+        path: impl Into<PathBuf>,
+        watcher: Arc<ConfigWatcher>,
+    ) -> anyhow::Result<()> {
+        let path = path.into();
+        let mut changes = watcher.subscribe();
+        watcher.watch_file(&path).await?;
+        let resolver = self.clone();
+
+        tokio::spawn(async move {
+            loop {
+                match changes.recv().await {
+                    Ok(ConfigChange::FileModified(changed)) if changed == path => {}
+                    Ok(ConfigChange::FullReload) | Err(RecvError::Lagged(_)) => {}
+                    Ok(_) => continue,
+                    Err(RecvError::Closed) => break,
+                }
+
+                if let Err(e) = resolver.reload_from_file(&path.to_string_lossy()) {
+                    tracing::error!(
+                        "keeping current GeoIP database, failed to load {}: {:#}",
+                        path.display(),
+                        e
+                    );
+                }
+            }
+        });
+        Ok(())
+    }
 }
 
 impl GeoResolver for MaxMindGeoResolver {
@@ -51,7 +105,7 @@ impl GeoResolver for MaxMindGeoResolver {
             country: Option<Country>,
         }
 
-        let resp: CountryResp = self.reader.lookup(ip).ok()?;
+        let resp: CountryResp = self.reader.load().lookup(ip).ok()?;
         let iso = resp.country?.iso_code?;
 
         let region = RegionCode::from_country(&iso);
@@ -164,6 +218,102 @@ mod tests {
         assert!(result.is_none());
     }
 
+    #[test]
+    fn test_reload_from_file() {
+        use std::io::Write;
+
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(EMBEDDED_GEOIP).unwrap();
+        let path = file.path().to_str().unwrap();
+
+        let resolver = MaxMindGeoResolver::from_file(path).unwrap();
+        let ip = IpAddr::V4(Ipv4Addr::new(8, 8, 8, 8));
+        let before = resolver.resolve(ip);
+
+        resolver.reload_from_file(path).unwrap();
+        assert_eq!(resolver.resolve(ip).map(|g| g.country), before.map(|g| g.country));
+    }
+
+    #[test]
+    fn test_reload_from_file_invalid_keeps_current() {
+        use std::io::Write;
+
+        let resolver = MaxMindGeoResolver::embedded().unwrap();
+        let ip = IpAddr::V4(Ipv4Addr::new(8, 8, 8, 8));
+        let before = resolver.resolve(ip).map(|g| g.country);
+
+        let mut garbage = tempfile::NamedTempFile::new().unwrap();
+        garbage.write_all(b"not a maxmind database").unwrap();
+
+        assert!(resolver.reload_from_file(garbage.path().to_str().unwrap()).is_err());
+        assert!(resolver.reload_from_file("/nonexistent/path/GeoLite2.mmdb").is_err());
+        assert_eq!(resolver.resolve(ip).map(|g| g.country), before);
+    }
+
+    /// Wait until the resolver's database is no longer `reader`.
+    async fn wait_for_swap(resolver: &MaxMindGeoResolver, reader: &Arc<Reader<Vec<u8>>>) -> bool {
+        tokio::time::timeout(std::time::Duration::from_secs(2), async {
+            while Arc::ptr_eq(&resolver.reader.load_full(), reader) {
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .is_ok()
+    }
+
+    #[tokio::test]
+    async fn test_watcher_reloads_changed_file() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), EMBEDDED_GEOIP).unwrap();
+        let resolver = Arc::new(MaxMindGeoResolver::from_file(file.path().to_str().unwrap()).unwrap());
+        let before = resolver.reader.load_full();
+
+        let watcher = Arc::new(ConfigWatcher::new(std::time::Duration::from_millis(10)));
+        resolver.start_watching(file.path(), watcher.clone()).await.unwrap();
+        watcher.clone().start();
+
+        // Ensure the new mtime is strictly later
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        std::fs::write(file.path(), EMBEDDED_GEOIP).unwrap();
+
+        assert!(wait_for_swap(&resolver, &before).await);
+        let ip = IpAddr::V4(Ipv4Addr::new(8, 8, 8, 8));
+        assert_eq!(resolver.resolve(ip).map(|g| g.country), Some("US".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_watcher_rejects_corrupt_file() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), EMBEDDED_GEOIP).unwrap();
+        let resolver = Arc::new(MaxMindGeoResolver::from_file(file.path().to_str().unwrap()).unwrap());
+        let before = resolver.reader.load_full();
+        let ip = IpAddr::V4(Ipv4Addr::new(8, 8, 8, 8));
+
+        let watcher = Arc::new(ConfigWatcher::new(std::time::Duration::from_millis(10)));
+        resolver.start_watching(file.path(), watcher.clone()).await.unwrap();
+        watcher.clone().start();
+
+        // A truncated download is rejected and lookups keep working
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        std::fs::write(file.path(), &EMBEDDED_GEOIP[..EMBEDDED_GEOIP.len() / 2]).unwrap();
+        assert!(!wait_for_swap(&resolver, &before).await);
+        assert_eq!(resolver.resolve(ip).map(|g| g.country), Some("US".to_string()));
+
+        // The next good file is picked up
+        std::fs::write(file.path(), EMBEDDED_GEOIP).unwrap();
+        assert!(wait_for_swap(&resolver, &before).await);
+    }
+
+    #[tokio::test]
+    async fn test_start_watching_missing_file() {
+        let resolver = Arc::new(MaxMindGeoResolver::embedded().unwrap());
+        let watcher = Arc::new(ConfigWatcher::default());
+        assert!(resolver
+            .start_watching("/nonexistent/path/GeoLite2.mmdb", watcher)
+            .await
+            .is_err());
+    }
+
     #[test]
     fn test_from_file_nonexistent() {
         let result = MaxMindGeoResolver::from_file("/nonexistent/path/GeoLite2.mmdb");
@@ -213,9 +363,9 @@ mod tests {
     fn test_embedded_database_arc_clone() {
         let resolver = MaxMindGeoResolver::embedded().unwrap();
 
-        // Test that the Arc<Reader> can be cloned
-        let reader_clone = resolver.reader.clone();
-        assert!(Arc::strong_count(&resolver.reader) >= 2);
+        // Test that the current Arc<Reader> can be cloned out of the swap
+        let reader_clone = resolver.reader.load_full();
+        assert!(std::sync::Arc::strong_count(&reader_clone) >= 2);
         drop(reader_clone);
     }
 
@@ -1,13 +1,23 @@
+mod consul_backend_repo;
 mod dashmap_binding_repo;
 mod dashmap_metrics_store;
+mod file_backend_repo;
 mod maxmind_geo_resolver;
 mod postgres_backend_repo;
 mod prometheus_metrics_store;
+mod public_ip_provider;
 mod sqlite_backend_repo;
+mod static_geo_resolver;
 
-pub use dashmap_binding_repo::DashMapBindingRepository;
-pub use dashmap_metrics_store::DashMapMetricsStore;
+pub use consul_backend_repo::{ConsulBackendRepository, ConsulConfig};
+pub use dashmap_binding_repo::{DashMapBindingRepository, EvictionCallback};
+pub use dashmap_metrics_store::{DashMapMetricsStore, MetricsSnapshot};
+pub use file_backend_repo::FileBackendRepository;
 pub use maxmind_geo_resolver::MaxMindGeoResolver;
 pub use postgres_backend_repo::{PostgresBackendRepository, PostgresConfig, PostgresError};
 pub use prometheus_metrics_store::{PrometheusMetricsStore, AggregatedMetrics, BackendMetrics as PrometheusBackendMetrics};
+pub use public_ip_provider::{
+    HttpPublicIpProvider, StaticPublicIpProvider, DEFAULT_PUBLIC_IP_URL,
+};
 pub use sqlite_backend_repo::SqliteBackendRepository;
+pub use static_geo_resolver::{NullGeoResolver, StaticGeoResolver};
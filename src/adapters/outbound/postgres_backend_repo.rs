@@ -274,6 +274,9 @@ mod tests {
             weight: 2,
             soft_limit: 100,
             hard_limit: 150,
+            draining: false,
+            maintenance: false,
+            priority: 0,
         }
     }
 
@@ -3,15 +3,20 @@
 //! Implements MetricsStore with Prometheus metrics exposition.
 
 use crate::domain::ports::MetricsStore;
+use crate::domain::value_objects::{
+    CircuitState, CloseReason, ConnectPhase, DnsQueryOutcome, SelectionOutcome,
+};
 use dashmap::DashMap;
 use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
 
-/// Aggregated metrics for Prometheus export.
+/// Aggregated metrics for Prometheus export (globally or per app).
 #[derive(Debug, Default)]
 pub struct AggregatedMetrics {
     /// Total connections established
     pub total_connections: AtomicU64,
+    /// Currently active connections
+    pub active_connections: AtomicUsize,
     /// Total bytes proxied (client to backend)
     pub bytes_sent: AtomicU64,
     /// Total bytes proxied (backend to client)
@@ -33,8 +38,16 @@ pub struct BackendMetrics {
     pub rtt_sum_ms: AtomicU64,
     /// Number of RTT measurements
     pub rtt_count: AtomicU64,
+    /// Last duration of each dial phase, indexed by `ConnectPhase::index`
+    pub connect_phase_last_ms: [AtomicU64; 2],
+    /// Sum of each dial phase's durations
+    pub connect_phase_sum_ms: [AtomicU64; 2],
+    /// Number of measurements of each dial phase
+    pub connect_phase_count: [AtomicU64; 2],
     /// Connection errors to this backend
     pub connection_errors: AtomicU64,
+    /// Sessions aborted at the maximum session duration
+    pub session_timeouts: AtomicU64,
 }
 
 impl BackendMetrics {
@@ -45,7 +58,11 @@ impl BackendMetrics {
             last_rtt_ms: AtomicU64::new(0),
             rtt_sum_ms: AtomicU64::new(0),
             rtt_count: AtomicU64::new(0),
+            connect_phase_last_ms: Default::default(),
+            connect_phase_sum_ms: Default::default(),
+            connect_phase_count: Default::default(),
             connection_errors: AtomicU64::new(0),
+            session_timeouts: AtomicU64::new(0),
         }
     }
 
@@ -58,6 +75,16 @@ impl BackendMetrics {
         let sum = self.rtt_sum_ms.load(Ordering::Relaxed);
         sum as f64 / count as f64
     }
+
+    /// Get the average duration of a dial phase in milliseconds.
+    pub fn avg_connect_phase_ms(&self, phase: ConnectPhase) -> f64 {
+        let count = self.connect_phase_count[phase.index()].load(Ordering::Relaxed);
+        if count == 0 {
+            return 0.0;
+        }
+        let sum = self.connect_phase_sum_ms[phase.index()].load(Ordering::Relaxed);
+        sum as f64 / count as f64
+    }
 }
 
 impl Default for BackendMetrics {
@@ -74,6 +101,36 @@ pub struct PrometheusMetricsStore {
     backends: DashMap<String, Arc<BackendMetrics>>,
     /// Global aggregated metrics
     global: Arc<AggregatedMetrics>,
+    /// Per-app aggregated metrics
+    apps: DashMap<String, Arc<AggregatedMetrics>>,
+    /// App of each backend, as reported on connection start
+    backend_apps: DashMap<String, String>,
+    /// DNS query counts per (app, outcome)
+    dns_queries: DashMap<(String, DnsQueryOutcome), AtomicU64>,
+    /// Routing reload changes: added, removed, updated
+    routing_changes: [AtomicU64; 3],
+    /// Loaded backends currently down for maintenance
+    maintenance_backends: AtomicU64,
+    /// Backend dials in progress and addresses queued behind them
+    connect_pressure: [AtomicU64; 2],
+    /// Backend selections, indexed by `SelectionOutcome::index`
+    selections: [AtomicU64; 4],
+    /// Load balancer picks per (app, backend)
+    backends_selected: DashMap<(String, String), AtomicU64>,
+    /// Closed connections, indexed by `CloseReason::index`
+    connections_closed: [AtomicU64; 5],
+    /// Connections rejected because every backend was at its hard limit
+    overload_rejections: AtomicU64,
+    /// Routing decisions made while no geo resolver was loaded
+    geo_unavailable: AtomicU64,
+    /// Current circuit breaker state per backend
+    circuit_states: DashMap<String, CircuitState>,
+    /// Circuit breaker transitions per (backend, state entered)
+    circuit_transitions: DashMap<(String, CircuitState), AtomicU64>,
+    /// Last (inbound, outbound) replication lag per peer
+    replication_lag: DashMap<String, (u64, u64)>,
+    /// Replicated changes rejected by conflict resolution, per table
+    lww_rejected: DashMap<String, AtomicU64>,
     /// Region label for metrics
     region: String,
 }
@@ -84,6 +141,21 @@ impl PrometheusMetricsStore {
         Self {
             backends: DashMap::new(),
             global: Arc::new(AggregatedMetrics::default()),
+            apps: DashMap::new(),
+            backend_apps: DashMap::new(),
+            dns_queries: DashMap::new(),
+            routing_changes: Default::default(),
+            maintenance_backends: AtomicU64::new(0),
+            connect_pressure: Default::default(),
+            selections: Default::default(),
+            backends_selected: DashMap::new(),
+            connections_closed: Default::default(),
+            overload_rejections: AtomicU64::new(0),
+            geo_unavailable: AtomicU64::new(0),
+            circuit_states: DashMap::new(),
+            circuit_transitions: DashMap::new(),
+            replication_lag: DashMap::new(),
+            lww_rejected: DashMap::new(),
             region,
         }
     }
@@ -96,11 +168,25 @@ impl PrometheusMetricsStore {
             .clone()
     }
 
+    /// Get the aggregated metrics of a backend's app, if its app is known.
+    fn app_of(&self, backend_id: &str) -> Option<Arc<AggregatedMetrics>> {
+        let app = self.backend_apps.get(backend_id)?;
+        Some(
+            self.apps
+                .entry(app.value().clone())
+                .or_insert_with(|| Arc::new(AggregatedMetrics::default()))
+                .clone(),
+        )
+    }
+
     /// Record a connection error.
     pub fn record_error(&self, backend_id: &str) {
         let metrics = self.get_or_create(backend_id);
         metrics.connection_errors.fetch_add(1, Ordering::Relaxed);
         self.global.connection_errors.fetch_add(1, Ordering::Relaxed);
+        if let Some(app) = self.app_of(backend_id) {
+            app.connection_errors.fetch_add(1, Ordering::Relaxed);
+        }
     }
 
     /// Record bytes transferred.
@@ -111,6 +197,25 @@ impl PrometheusMetricsStore {
             .fetch_add(received, Ordering::Relaxed);
     }
 
+    /// Record bytes transferred through a backend (also counted globally).
+    pub fn record_backend_bytes(&self, backend_id: &str, sent: u64, received: u64) {
+        self.record_bytes(sent, received);
+        if let Some(app) = self.app_of(backend_id) {
+            app.bytes_sent.fetch_add(sent, Ordering::Relaxed);
+            app.bytes_received.fetch_add(received, Ordering::Relaxed);
+        }
+    }
+
+    /// Get all app names with aggregated metrics.
+    pub fn app_names(&self) -> Vec<String> {
+        self.apps.iter().map(|e| e.key().clone()).collect()
+    }
+
+    /// Get aggregated metrics for an app.
+    pub fn get_app_metrics(&self, app: &str) -> Option<Arc<AggregatedMetrics>> {
+        self.apps.get(app).map(|e| e.clone())
+    }
+
     /// Get all backend IDs.
     pub fn backend_ids(&self) -> Vec<String> {
         self.backends.iter().map(|e| e.key().clone()).collect()
@@ -176,9 +281,18 @@ impl PrometheusMetricsStore {
         output.push_str("# HELP edgeproxy_backend_rtt_avg_ms Average RTT to backend in milliseconds\n");
         output.push_str("# TYPE edgeproxy_backend_rtt_avg_ms gauge\n");
 
+        output.push_str("# HELP edgeproxy_backend_connect_phase_ms Last duration of each phase of dialing a backend in milliseconds\n");
+        output.push_str("# TYPE edgeproxy_backend_connect_phase_ms gauge\n");
+
+        output.push_str("# HELP edgeproxy_backend_connect_phase_avg_ms Average duration of each phase of dialing a backend in milliseconds\n");
+        output.push_str("# TYPE edgeproxy_backend_connect_phase_avg_ms gauge\n");
+
         output.push_str("# HELP edgeproxy_backend_errors_total Total errors per backend\n");
         output.push_str("# TYPE edgeproxy_backend_errors_total counter\n");
 
+        output.push_str("# HELP edgeproxy_backend_session_timeouts_total Sessions aborted at the maximum session duration per backend\n");
+        output.push_str("# TYPE edgeproxy_backend_session_timeouts_total counter\n");
+
         for entry in self.backends.iter() {
             let backend_id = entry.key();
             let metrics = entry.value();
@@ -211,18 +325,290 @@ impl PrometheusMetricsStore {
                 metrics.avg_rtt_ms()
             ));
 
+            for phase in ConnectPhase::ALL {
+                if metrics.connect_phase_count[phase.index()].load(Ordering::Relaxed) == 0 {
+                    continue;
+                }
+                output.push_str(&format!(
+                    "edgeproxy_backend_connect_phase_ms{{region=\"{}\",backend=\"{}\",phase=\"{}\"}} {}\n",
+                    self.region,
+                    backend_id,
+                    phase,
+                    metrics.connect_phase_last_ms[phase.index()].load(Ordering::Relaxed)
+                ));
+                output.push_str(&format!(
+                    "edgeproxy_backend_connect_phase_avg_ms{{region=\"{}\",backend=\"{}\",phase=\"{}\"}} {:.2}\n",
+                    self.region,
+                    backend_id,
+                    phase,
+                    metrics.avg_connect_phase_ms(phase)
+                ));
+            }
+
             output.push_str(&format!(
                 "edgeproxy_backend_errors_total{{region=\"{}\",backend=\"{}\"}} {}\n",
                 self.region,
                 backend_id,
                 metrics.connection_errors.load(Ordering::Relaxed)
             ));
+
+            output.push_str(&format!(
+                "edgeproxy_backend_session_timeouts_total{{region=\"{}\",backend=\"{}\"}} {}\n",
+                self.region,
+                backend_id,
+                metrics.session_timeouts.load(Ordering::Relaxed)
+            ));
+        }
+
+        // Per-app metrics
+        output.push_str("# HELP edgeproxy_app_connections_active Current active connections per app\n");
+        output.push_str("# TYPE edgeproxy_app_connections_active gauge\n");
+
+        output.push_str("# HELP edgeproxy_app_connections_total Total connections per app\n");
+        output.push_str("# TYPE edgeproxy_app_connections_total counter\n");
+
+        output.push_str("# HELP edgeproxy_app_errors_total Total errors per app\n");
+        output.push_str("# TYPE edgeproxy_app_errors_total counter\n");
+
+        output.push_str("# HELP edgeproxy_app_bytes_sent_total Total bytes sent to an app's backends\n");
+        output.push_str("# TYPE edgeproxy_app_bytes_sent_total counter\n");
+
+        output.push_str("# HELP edgeproxy_app_bytes_received_total Total bytes received from an app's backends\n");
+        output.push_str("# TYPE edgeproxy_app_bytes_received_total counter\n");
+
+        for entry in self.apps.iter() {
+            let app = entry.key();
+            let metrics = entry.value();
+
+            output.push_str(&format!(
+                "edgeproxy_app_connections_active{{region=\"{}\",app=\"{}\"}} {}\n",
+                self.region,
+                app,
+                metrics.active_connections.load(Ordering::Relaxed)
+            ));
+
+            output.push_str(&format!(
+                "edgeproxy_app_connections_total{{region=\"{}\",app=\"{}\"}} {}\n",
+                self.region,
+                app,
+                metrics.total_connections.load(Ordering::Relaxed)
+            ));
+
+            output.push_str(&format!(
+                "edgeproxy_app_errors_total{{region=\"{}\",app=\"{}\"}} {}\n",
+                self.region,
+                app,
+                metrics.connection_errors.load(Ordering::Relaxed)
+            ));
+
+            output.push_str(&format!(
+                "edgeproxy_app_bytes_sent_total{{region=\"{}\",app=\"{}\"}} {}\n",
+                self.region,
+                app,
+                metrics.bytes_sent.load(Ordering::Relaxed)
+            ));
+
+            output.push_str(&format!(
+                "edgeproxy_app_bytes_received_total{{region=\"{}\",app=\"{}\"}} {}\n",
+                self.region,
+                app,
+                metrics.bytes_received.load(Ordering::Relaxed)
+            ));
+        }
+
+        // Routing reload metrics
+        output.push_str("# HELP edgeproxy_routing_backend_changes_total Backends changed by routing reloads\n");
+        output.push_str("# TYPE edgeproxy_routing_backend_changes_total counter\n");
+
+        for (change, counter) in ["added", "removed", "updated"]
+            .iter()
+            .zip(self.routing_changes.iter())
+        {
+            output.push_str(&format!(
+                "edgeproxy_routing_backend_changes_total{{region=\"{}\",change=\"{}\"}} {}\n",
+                self.region,
+                change,
+                counter.load(Ordering::Relaxed)
+            ));
+        }
+
+        output.push_str("# HELP edgeproxy_backends_maintenance Loaded backends down for maintenance\n");
+        output.push_str("# TYPE edgeproxy_backends_maintenance gauge\n");
+        output.push_str(&format!(
+            "edgeproxy_backends_maintenance{{region=\"{}\"}} {}\n",
+            self.region,
+            self.maintenance_backends.load(Ordering::Relaxed)
+        ));
+
+        let (in_flight, queued) = self.get_connect_pressure();
+        output.push_str("# HELP edgeproxy_backend_connects_in_flight Backend dials in progress\n");
+        output.push_str("# TYPE edgeproxy_backend_connects_in_flight gauge\n");
+        output.push_str(&format!(
+            "edgeproxy_backend_connects_in_flight{{region=\"{}\"}} {}\n",
+            self.region, in_flight
+        ));
+        output.push_str("# HELP edgeproxy_backend_connect_queue_depth Backend addresses waiting for their turn to be tried\n");
+        output.push_str("# TYPE edgeproxy_backend_connect_queue_depth gauge\n");
+        output.push_str(&format!(
+            "edgeproxy_backend_connect_queue_depth{{region=\"{}\"}} {}\n",
+            self.region, queued
+        ));
+
+        // Backend selection metrics
+        output.push_str("# HELP edgeproxy_backend_selections_total Backend selections by where they landed relative to the client's region\n");
+        output.push_str("# TYPE edgeproxy_backend_selections_total counter\n");
+
+        for outcome in SelectionOutcome::ALL {
+            output.push_str(&format!(
+                "edgeproxy_backend_selections_total{{region=\"{}\",outcome=\"{}\"}} {}\n",
+                self.region,
+                outcome,
+                self.selections[outcome.index()].load(Ordering::Relaxed)
+            ));
+        }
+
+        output.push_str("# HELP edgeproxy_backend_selected_total Times the load balancer picked each backend of an app\n");
+        output.push_str("# TYPE edgeproxy_backend_selected_total counter\n");
+
+        for entry in self.backends_selected.iter() {
+            let (app, backend_id) = entry.key();
+            output.push_str(&format!(
+                "edgeproxy_backend_selected_total{{region=\"{}\",app=\"{}\",backend_id=\"{}\"}} {}\n",
+                self.region,
+                app,
+                backend_id,
+                entry.value().load(Ordering::Relaxed)
+            ));
+        }
+
+        // Connection close metrics
+        output.push_str("# HELP edgeproxy_connection_closed_total Client connections closed, by reason\n");
+        output.push_str("# TYPE edgeproxy_connection_closed_total counter\n");
+
+        for reason in CloseReason::ALL {
+            output.push_str(&format!(
+                "edgeproxy_connection_closed_total{{region=\"{}\",reason=\"{}\"}} {}\n",
+                self.region,
+                reason,
+                self.connections_closed[reason.index()].load(Ordering::Relaxed)
+            ));
+        }
+
+        // Overload metrics
+        output.push_str("# HELP edgeproxy_overload_rejections_total Connections rejected because every backend was at its hard limit\n");
+        output.push_str("# TYPE edgeproxy_overload_rejections_total counter\n");
+        output.push_str(&format!(
+            "edgeproxy_overload_rejections_total{{region=\"{}\"}} {}\n",
+            self.region,
+            self.overload_rejections.load(Ordering::Relaxed)
+        ));
+
+        // Geo metrics
+        output.push_str("# HELP edgeproxy_geo_unavailable_total Routing decisions made while no GeoIP database was loaded\n");
+        output.push_str("# TYPE edgeproxy_geo_unavailable_total counter\n");
+        output.push_str(&format!(
+            "edgeproxy_geo_unavailable_total{{region=\"{}\"}} {}\n",
+            self.region,
+            self.geo_unavailable.load(Ordering::Relaxed)
+        ));
+
+        // Circuit breaker metrics
+        output.push_str("# HELP edgeproxy_backend_circuit_state Circuit breaker state per backend (1 for the current state)\n");
+        output.push_str("# TYPE edgeproxy_backend_circuit_state gauge\n");
+
+        for entry in self.circuit_states.iter() {
+            for state in CircuitState::ALL {
+                output.push_str(&format!(
+                    "edgeproxy_backend_circuit_state{{region=\"{}\",backend=\"{}\",state=\"{}\"}} {}\n",
+                    self.region,
+                    entry.key(),
+                    state,
+                    u8::from(*entry.value() == state)
+                ));
+            }
+        }
+
+        output.push_str("# HELP edgeproxy_backend_circuit_transitions_total Circuit breaker transitions per backend, by state entered\n");
+        output.push_str("# TYPE edgeproxy_backend_circuit_transitions_total counter\n");
+
+        for entry in self.circuit_transitions.iter() {
+            let (backend_id, state) = entry.key();
+            output.push_str(&format!(
+                "edgeproxy_backend_circuit_transitions_total{{region=\"{}\",backend=\"{}\",state=\"{}\"}} {}\n",
+                self.region,
+                backend_id,
+                state,
+                entry.value().load(Ordering::Relaxed)
+            ));
+        }
+
+        // DNS metrics
+        output.push_str("# HELP edgeproxy_dns_queries_total Total DNS queries per app and outcome\n");
+        output.push_str("# TYPE edgeproxy_dns_queries_total counter\n");
+
+        for entry in self.dns_queries.iter() {
+            let (app, outcome) = entry.key();
+            output.push_str(&format!(
+                "edgeproxy_dns_queries_total{{region=\"{}\",app=\"{}\",outcome=\"{}\"}} {}\n",
+                self.region,
+                app,
+                outcome,
+                entry.value().load(Ordering::Relaxed)
+            ));
+        }
+
+        // Replication metrics
+        output.push_str("# HELP edgeproxy_replication_lag Changesets replication with a peer is behind, per direction\n");
+        output.push_str("# TYPE edgeproxy_replication_lag gauge\n");
+
+        for entry in self.replication_lag.iter() {
+            let (inbound, outbound) = *entry.value();
+            for (direction, lag) in [("inbound", inbound), ("outbound", outbound)] {
+                output.push_str(&format!(
+                    "edgeproxy_replication_lag{{region=\"{}\",peer=\"{}\",direction=\"{}\"}} {}\n",
+                    self.region,
+                    entry.key(),
+                    direction,
+                    lag
+                ));
+            }
+        }
+
+        output.push_str("# HELP edgeproxy_replication_lww_rejected_total Replicated changes rejected by conflict resolution (older than the applied version)\n");
+        output.push_str("# TYPE edgeproxy_replication_lww_rejected_total counter\n");
+
+        for entry in self.lww_rejected.iter() {
+            output.push_str(&format!(
+                "edgeproxy_replication_lww_rejected_total{{region=\"{}\",table=\"{}\"}} {}\n",
+                self.region,
+                entry.key(),
+                entry.value().load(Ordering::Relaxed)
+            ));
         }
 
         output
     }
 }
 
+/// Decrement a counter without going below zero.
+///
+/// Returns true if the counter was decremented.
+fn decrement_saturating(counter: &AtomicUsize) -> bool {
+    let mut current = counter.load(Ordering::Relaxed);
+    while current > 0 {
+        match counter.compare_exchange_weak(
+            current,
+            current - 1,
+            Ordering::Relaxed,
+            Ordering::Relaxed,
+        ) {
+            Ok(_) => return true,
+            Err(c) => current = c,
+        }
+    }
+    false
+}
+
 impl Default for PrometheusMetricsStore {
     fn default() -> Self {
         Self::new("unknown".to_string())
@@ -242,25 +628,36 @@ impl MetricsStore for PrometheusMetricsStore {
         metrics.active_connections.fetch_add(1, Ordering::Relaxed);
         metrics.total_connections.fetch_add(1, Ordering::Relaxed);
         self.global.total_connections.fetch_add(1, Ordering::Relaxed);
+        self.global.active_connections.fetch_add(1, Ordering::Relaxed);
+        if let Some(app) = self.app_of(backend_id) {
+            app.total_connections.fetch_add(1, Ordering::Relaxed);
+            app.active_connections.fetch_add(1, Ordering::Relaxed);
+        }
     }
 
     fn decrement_connections(&self, backend_id: &str) {
-        if let Some(m) = self.backends.get(backend_id) {
-            let mut current = m.active_connections.load(Ordering::Relaxed);
-            while current > 0 {
-                match m.active_connections.compare_exchange_weak(
-                    current,
-                    current - 1,
-                    Ordering::Relaxed,
-                    Ordering::Relaxed,
-                ) {
-                    Ok(_) => break,
-                    Err(c) => current = c,
-                }
+        let decremented = self
+            .backends
+            .get(backend_id)
+            .is_some_and(|m| decrement_saturating(&m.active_connections));
+
+        // Aggregates only drop for connections the backend actually had
+        if decremented {
+            decrement_saturating(&self.global.active_connections);
+            if let Some(app) = self.app_of(backend_id) {
+                decrement_saturating(&app.active_connections);
             }
         }
     }
 
+    fn set_backend_app(&self, backend_id: &str, app: &str) {
+        if self.backend_apps.get(backend_id).is_some_and(|a| a.value() == app) {
+            return;
+        }
+        self.backend_apps
+            .insert(backend_id.to_string(), app.to_string());
+    }
+
     fn record_rtt(&self, backend_id: &str, rtt_ms: u64) {
         let metrics = self.get_or_create(backend_id);
         metrics.last_rtt_ms.store(rtt_ms, Ordering::Relaxed);
@@ -273,6 +670,166 @@ impl MetricsStore for PrometheusMetricsStore {
             .get(backend_id)
             .map(|m| m.last_rtt_ms.load(Ordering::Relaxed))
     }
+
+    fn record_connect_phase(&self, backend_id: &str, phase: ConnectPhase, ms: u64) {
+        let metrics = self.get_or_create(backend_id);
+        let i = phase.index();
+        metrics.connect_phase_last_ms[i].store(ms, Ordering::Relaxed);
+        metrics.connect_phase_sum_ms[i].fetch_add(ms, Ordering::Relaxed);
+        metrics.connect_phase_count[i].fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn get_connect_phase(&self, backend_id: &str, phase: ConnectPhase) -> Option<u64> {
+        let metrics = self.backends.get(backend_id)?;
+        let i = phase.index();
+        (metrics.connect_phase_count[i].load(Ordering::Relaxed) > 0)
+            .then(|| metrics.connect_phase_last_ms[i].load(Ordering::Relaxed))
+    }
+
+    fn record_session_timeout(&self, backend_id: &str) {
+        self.get_or_create(backend_id)
+            .session_timeouts
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn get_session_timeouts(&self, backend_id: &str) -> u64 {
+        self.backends
+            .get(backend_id)
+            .map(|m| m.session_timeouts.load(Ordering::Relaxed))
+            .unwrap_or(0)
+    }
+
+    fn record_routing_changes(&self, added: usize, removed: usize, updated: usize) {
+        for (counter, n) in self.routing_changes.iter().zip([added, removed, updated]) {
+            counter.fetch_add(n as u64, Ordering::Relaxed);
+        }
+    }
+
+    fn get_routing_changes(&self) -> (u64, u64, u64) {
+        let [added, removed, updated] = &self.routing_changes;
+        (
+            added.load(Ordering::Relaxed),
+            removed.load(Ordering::Relaxed),
+            updated.load(Ordering::Relaxed),
+        )
+    }
+
+    fn set_maintenance_backends(&self, count: usize) {
+        self.maintenance_backends.store(count as u64, Ordering::Relaxed);
+    }
+
+    fn get_maintenance_backends(&self) -> u64 {
+        self.maintenance_backends.load(Ordering::Relaxed)
+    }
+
+    fn set_connect_pressure(&self, in_flight: usize, queued: usize) {
+        let [in_flight_gauge, queued_gauge] = &self.connect_pressure;
+        in_flight_gauge.store(in_flight as u64, Ordering::Relaxed);
+        queued_gauge.store(queued as u64, Ordering::Relaxed);
+    }
+
+    fn get_connect_pressure(&self) -> (u64, u64) {
+        let [in_flight, queued] = &self.connect_pressure;
+        (in_flight.load(Ordering::Relaxed), queued.load(Ordering::Relaxed))
+    }
+
+    fn record_dns_query(&self, app: &str, outcome: DnsQueryOutcome) {
+        self.dns_queries
+            .entry((app.to_string(), outcome))
+            .or_default()
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn get_dns_query_count(&self, app: &str, outcome: DnsQueryOutcome) -> u64 {
+        self.dns_queries
+            .get(&(app.to_string(), outcome))
+            .map(|c| c.load(Ordering::Relaxed))
+            .unwrap_or(0)
+    }
+
+    fn record_selection(&self, outcome: SelectionOutcome) {
+        self.selections[outcome.index()].fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn get_selection_count(&self, outcome: SelectionOutcome) -> u64 {
+        self.selections[outcome.index()].load(Ordering::Relaxed)
+    }
+
+    fn record_backend_selected(&self, app: &str, backend_id: &str) {
+        self.backends_selected
+            .entry((app.to_string(), backend_id.to_string()))
+            .or_default()
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn get_backend_selected(&self, app: &str, backend_id: &str) -> u64 {
+        self.backends_selected
+            .get(&(app.to_string(), backend_id.to_string()))
+            .map_or(0, |n| n.load(Ordering::Relaxed))
+    }
+
+    fn record_connection_closed(&self, reason: CloseReason) {
+        self.connections_closed[reason.index()].fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn get_connection_closed(&self, reason: CloseReason) -> u64 {
+        self.connections_closed[reason.index()].load(Ordering::Relaxed)
+    }
+
+    fn record_overload_rejection(&self) {
+        self.overload_rejections.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn get_overload_rejections(&self) -> u64 {
+        self.overload_rejections.load(Ordering::Relaxed)
+    }
+
+    fn record_geo_unavailable(&self) {
+        self.geo_unavailable.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn get_geo_unavailable(&self) -> u64 {
+        self.geo_unavailable.load(Ordering::Relaxed)
+    }
+
+    fn record_circuit_transition(&self, backend_id: &str, state: CircuitState) {
+        self.circuit_states.insert(backend_id.to_string(), state);
+        self.circuit_transitions
+            .entry((backend_id.to_string(), state))
+            .or_default()
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn get_circuit_state(&self, backend_id: &str) -> Option<CircuitState> {
+        self.circuit_states.get(backend_id).map(|state| *state)
+    }
+
+    fn get_circuit_transitions(&self, backend_id: &str, state: CircuitState) -> u64 {
+        self.circuit_transitions
+            .get(&(backend_id.to_string(), state))
+            .map_or(0, |n| n.load(Ordering::Relaxed))
+    }
+
+    fn record_replication_lag(&self, peer: &str, inbound: u64, outbound: u64) {
+        self.replication_lag.insert(peer.to_string(), (inbound, outbound));
+    }
+
+    fn get_replication_lag(&self, peer: &str) -> Option<(u64, u64)> {
+        self.replication_lag.get(peer).map(|lag| *lag)
+    }
+
+    fn record_lww_rejected(&self, table: &str) {
+        self.lww_rejected
+            .entry(table.to_string())
+            .or_default()
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn get_lww_rejected(&self, table: &str) -> u64 {
+        self.lww_rejected
+            .get(table)
+            .map_or(0, |n| n.load(Ordering::Relaxed))
+    }
 }
 
 #[cfg(test)]
@@ -435,6 +992,378 @@ mod tests {
         assert!(output.contains("region=\"eu\""));
     }
 
+    #[test]
+    fn test_connect_phases_exported() {
+        let store = PrometheusMetricsStore::new("eu".to_string());
+        store.record_connect_phase("b1", ConnectPhase::Resolve, 4);
+        store.record_connect_phase("b1", ConnectPhase::Connect, 10);
+        store.record_connect_phase("b1", ConnectPhase::Connect, 20);
+        store.record_connect_phase("b2", ConnectPhase::Connect, 7);
+
+        assert_eq!(store.get_connect_phase("b1", ConnectPhase::Resolve), Some(4));
+        assert_eq!(store.get_connect_phase("b1", ConnectPhase::Connect), Some(20));
+        assert_eq!(store.get_connect_phase("b2", ConnectPhase::Resolve), None);
+        assert_eq!(store.get_connect_phase("b3", ConnectPhase::Connect), None);
+
+        let output = store.export_prometheus();
+        assert!(output.contains("# TYPE edgeproxy_backend_connect_phase_ms gauge"));
+        assert!(output.contains(
+            "edgeproxy_backend_connect_phase_ms{region=\"eu\",backend=\"b1\",phase=\"resolve\"} 4"
+        ));
+        assert!(output.contains(
+            "edgeproxy_backend_connect_phase_avg_ms{region=\"eu\",backend=\"b1\",phase=\"connect\"} 15.00"
+        ));
+        // A backend dialed by IP has no resolve phase to report
+        assert!(!output.contains("backend=\"b2\",phase=\"resolve\""));
+    }
+
+    #[test]
+    fn test_session_timeouts_exported() {
+        let store = PrometheusMetricsStore::new("eu".to_string());
+        store.record_session_timeout("b1");
+
+        assert_eq!(store.get_session_timeouts("b1"), 1);
+        assert_eq!(store.get_session_timeouts("b2"), 0);
+
+        let output = store.export_prometheus();
+        assert!(output.contains("# TYPE edgeproxy_backend_session_timeouts_total counter"));
+        assert!(output.contains(
+            "edgeproxy_backend_session_timeouts_total{region=\"eu\",backend=\"b1\"} 1"
+        ));
+    }
+
+    // ===== Per-App Aggregation Tests =====
+
+    #[test]
+    fn test_app_metrics_sum_backends_of_same_app() {
+        let store = PrometheusMetricsStore::new("eu".to_string());
+        store.set_backend_app("web-1", "web");
+        store.set_backend_app("web-2", "web");
+        store.set_backend_app("api-1", "api");
+
+        store.increment_connections("web-1");
+        store.increment_connections("web-1");
+        store.increment_connections("web-2");
+        store.increment_connections("api-1");
+        store.decrement_connections("web-1");
+        store.record_error("web-2");
+        store.record_error("web-1");
+        store.record_backend_bytes("web-1", 100, 10);
+        store.record_backend_bytes("web-2", 200, 20);
+
+        let web = store.get_app_metrics("web").unwrap();
+        assert_eq!(web.total_connections.load(Ordering::Relaxed), 3);
+        assert_eq!(web.active_connections.load(Ordering::Relaxed), 2);
+        assert_eq!(web.connection_errors.load(Ordering::Relaxed), 2);
+        assert_eq!(web.bytes_sent.load(Ordering::Relaxed), 300);
+        assert_eq!(web.bytes_received.load(Ordering::Relaxed), 30);
+
+        let api = store.get_app_metrics("api").unwrap();
+        assert_eq!(api.total_connections.load(Ordering::Relaxed), 1);
+        assert_eq!(api.active_connections.load(Ordering::Relaxed), 1);
+
+        // Backend bytes also count globally
+        assert_eq!(store.global.bytes_sent.load(Ordering::Relaxed), 300);
+        assert_eq!(store.global.active_connections.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn test_app_metrics_active_does_not_underflow() {
+        let store = PrometheusMetricsStore::new("eu".to_string());
+        store.set_backend_app("web-1", "web");
+
+        store.increment_connections("web-1");
+        store.decrement_connections("web-1");
+        store.decrement_connections("web-1");
+
+        let web = store.get_app_metrics("web").unwrap();
+        assert_eq!(web.active_connections.load(Ordering::Relaxed), 0);
+        assert_eq!(store.global.active_connections.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn test_backend_without_app_not_aggregated() {
+        let store = PrometheusMetricsStore::new("eu".to_string());
+        store.increment_connections("orphan");
+
+        assert!(store.app_names().is_empty());
+        assert_eq!(store.global.total_connections.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_export_prometheus_app_metrics() {
+        let store = PrometheusMetricsStore::new("eu".to_string());
+        store.set_backend_app("web-1", "web");
+        store.set_backend_app("web-2", "web");
+
+        store.increment_connections("web-1");
+        store.increment_connections("web-2");
+        store.record_error("web-2");
+        store.record_backend_bytes("web-1", 64, 32);
+
+        let output = store.export_prometheus();
+        assert!(output.contains("# TYPE edgeproxy_app_connections_total counter"));
+        assert!(output.contains("edgeproxy_app_connections_total{region=\"eu\",app=\"web\"} 2"));
+        assert!(output.contains("edgeproxy_app_connections_active{region=\"eu\",app=\"web\"} 2"));
+        assert!(output.contains("edgeproxy_app_errors_total{region=\"eu\",app=\"web\"} 1"));
+        assert!(output.contains("edgeproxy_app_bytes_sent_total{region=\"eu\",app=\"web\"} 64"));
+        assert!(output.contains("edgeproxy_app_bytes_received_total{region=\"eu\",app=\"web\"} 32"));
+    }
+
+    #[test]
+    fn test_export_prometheus_routing_changes() {
+        let store = PrometheusMetricsStore::new("eu".to_string());
+        store.record_routing_changes(2, 1, 0);
+
+        assert_eq!(store.get_routing_changes(), (2, 1, 0));
+
+        let output = store.export_prometheus();
+        assert!(output.contains("# TYPE edgeproxy_routing_backend_changes_total counter"));
+        assert!(output.contains(
+            "edgeproxy_routing_backend_changes_total{region=\"eu\",change=\"added\"} 2"
+        ));
+        assert!(output.contains(
+            "edgeproxy_routing_backend_changes_total{region=\"eu\",change=\"removed\"} 1"
+        ));
+        assert!(output.contains(
+            "edgeproxy_routing_backend_changes_total{region=\"eu\",change=\"updated\"} 0"
+        ));
+    }
+
+    #[test]
+    fn test_export_prometheus_maintenance_backends() {
+        let store = PrometheusMetricsStore::new("eu".to_string());
+        store.set_maintenance_backends(2);
+
+        assert_eq!(store.get_maintenance_backends(), 2);
+
+        let output = store.export_prometheus();
+        assert!(output.contains("# TYPE edgeproxy_backends_maintenance gauge"));
+        assert!(output.contains("edgeproxy_backends_maintenance{region=\"eu\"} 2"));
+    }
+
+    #[test]
+    fn test_export_prometheus_connect_pressure() {
+        let store = PrometheusMetricsStore::new("eu".to_string());
+        store.set_connect_pressure(7, 2);
+
+        assert_eq!(store.get_connect_pressure(), (7, 2));
+
+        let output = store.export_prometheus();
+        assert!(output.contains("# TYPE edgeproxy_backend_connects_in_flight gauge"));
+        assert!(output.contains("edgeproxy_backend_connects_in_flight{region=\"eu\"} 7"));
+        assert!(output.contains("edgeproxy_backend_connect_queue_depth{region=\"eu\"} 2"));
+    }
+
+    #[test]
+    fn test_export_prometheus_circuit_breaker() {
+        use crate::infrastructure::{CircuitBreaker, CircuitBreakerConfig};
+        use std::time::Duration;
+
+        let store = Arc::new(PrometheusMetricsStore::new("eu".to_string()));
+        let cb = CircuitBreaker::new(CircuitBreakerConfig {
+            failure_threshold: 2,
+            reset_timeout: Duration::from_millis(1),
+            success_threshold: 1,
+            ..Default::default()
+        })
+        .with_metrics(store.clone());
+
+        cb.record_failure("b1");
+        cb.record_failure("b1");
+        assert_eq!(store.get_circuit_state("b1"), Some(CircuitState::Open));
+
+        let output = store.export_prometheus();
+        assert!(output.contains("# TYPE edgeproxy_backend_circuit_state gauge"));
+        assert!(output.contains(
+            "edgeproxy_backend_circuit_state{region=\"eu\",backend=\"b1\",state=\"open\"} 1"
+        ));
+        assert!(output.contains(
+            "edgeproxy_backend_circuit_state{region=\"eu\",backend=\"b1\",state=\"closed\"} 0"
+        ));
+
+        // Open -> half-open -> closed
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(cb.allow_request("b1"));
+        cb.record_success("b1");
+        assert_eq!(store.get_circuit_state("b1"), Some(CircuitState::Closed));
+        assert_eq!(store.get_circuit_transitions("b1", CircuitState::Open), 1);
+        assert_eq!(store.get_circuit_transitions("b1", CircuitState::HalfOpen), 1);
+        assert_eq!(store.get_circuit_transitions("b1", CircuitState::Closed), 1);
+
+        let output = store.export_prometheus();
+        assert!(output.contains("# TYPE edgeproxy_backend_circuit_transitions_total counter"));
+        assert!(output.contains(
+            "edgeproxy_backend_circuit_state{region=\"eu\",backend=\"b1\",state=\"closed\"} 1"
+        ));
+        assert!(output.contains(
+            "edgeproxy_backend_circuit_transitions_total{region=\"eu\",backend=\"b1\",state=\"half-open\"} 1"
+        ));
+    }
+
+    #[test]
+    fn test_dns_query_counts() {
+        let store = PrometheusMetricsStore::new("eu".to_string());
+
+        store.record_dns_query("myapp", DnsQueryOutcome::NoError);
+        store.record_dns_query("myapp", DnsQueryOutcome::NoError);
+        store.record_dns_query("myapp", DnsQueryOutcome::NxDomain);
+
+        assert_eq!(store.get_dns_query_count("myapp", DnsQueryOutcome::NoError), 2);
+        assert_eq!(store.get_dns_query_count("myapp", DnsQueryOutcome::NxDomain), 1);
+        assert_eq!(store.get_dns_query_count("myapp", DnsQueryOutcome::NotImp), 0);
+        assert_eq!(store.get_dns_query_count("other", DnsQueryOutcome::NoError), 0);
+    }
+
+    #[test]
+    fn test_export_prometheus_dns_queries() {
+        let store = PrometheusMetricsStore::new("eu".to_string());
+
+        store.record_dns_query("myapp", DnsQueryOutcome::NoError);
+        store.record_dns_query("myapp", DnsQueryOutcome::NotImp);
+        store.record_dns_query("myapp", DnsQueryOutcome::NotImp);
+
+        let output = store.export_prometheus();
+
+        assert!(output.contains("# TYPE edgeproxy_dns_queries_total counter"));
+        assert!(output.contains(
+            "edgeproxy_dns_queries_total{region=\"eu\",app=\"myapp\",outcome=\"noerror\"} 1"
+        ));
+        assert!(output.contains(
+            "edgeproxy_dns_queries_total{region=\"eu\",app=\"myapp\",outcome=\"notimp\"} 2"
+        ));
+    }
+
+    #[test]
+    fn test_export_prometheus_replication_lag() {
+        let store = PrometheusMetricsStore::new("eu".to_string());
+
+        store.record_replication_lag("node-2", 3, 0);
+
+        let output = store.export_prometheus();
+
+        assert!(output.contains("# TYPE edgeproxy_replication_lag gauge"));
+        assert!(output.contains(
+            "edgeproxy_replication_lag{region=\"eu\",peer=\"node-2\",direction=\"inbound\"} 3"
+        ));
+        assert!(output.contains(
+            "edgeproxy_replication_lag{region=\"eu\",peer=\"node-2\",direction=\"outbound\"} 0"
+        ));
+    }
+
+    #[test]
+    fn test_export_prometheus_lww_rejected() {
+        let store = PrometheusMetricsStore::new("eu".to_string());
+        assert_eq!(store.get_lww_rejected("backends"), 0);
+
+        store.record_lww_rejected("backends");
+        store.record_lww_rejected("backends");
+
+        assert_eq!(store.get_lww_rejected("backends"), 2);
+        let output = store.export_prometheus();
+        assert!(output.contains("# TYPE edgeproxy_replication_lww_rejected_total counter"));
+        assert!(output.contains(
+            "edgeproxy_replication_lww_rejected_total{region=\"eu\",table=\"backends\"} 2"
+        ));
+    }
+
+    #[test]
+    fn test_export_prometheus_selections() {
+        let store = PrometheusMetricsStore::new("eu".to_string());
+
+        store.record_selection(SelectionOutcome::RegionFallback);
+        store.record_selection(SelectionOutcome::NoBackend);
+        store.record_selection(SelectionOutcome::NoBackend);
+
+        assert_eq!(store.get_selection_count(SelectionOutcome::NoBackend), 2);
+
+        let output = store.export_prometheus();
+        assert!(output.contains("# TYPE edgeproxy_backend_selections_total counter"));
+        assert!(output.contains(
+            "edgeproxy_backend_selections_total{region=\"eu\",outcome=\"in_region\"} 0"
+        ));
+        assert!(output.contains(
+            "edgeproxy_backend_selections_total{region=\"eu\",outcome=\"region_fallback\"} 1"
+        ));
+        assert!(output.contains(
+            "edgeproxy_backend_selections_total{region=\"eu\",outcome=\"no_backend\"} 2"
+        ));
+    }
+
+    #[test]
+    fn test_export_prometheus_backend_selected() {
+        let store = PrometheusMetricsStore::new("eu".to_string());
+        assert_eq!(store.get_backend_selected("myapp", "b1"), 0);
+
+        store.record_backend_selected("myapp", "b1");
+        store.record_backend_selected("myapp", "b1");
+        store.record_backend_selected("myapp", "b2");
+
+        assert_eq!(store.get_backend_selected("myapp", "b1"), 2);
+        let output = store.export_prometheus();
+        assert!(output.contains("# TYPE edgeproxy_backend_selected_total counter"));
+        assert!(output.contains(
+            "edgeproxy_backend_selected_total{region=\"eu\",app=\"myapp\",backend_id=\"b1\"} 2"
+        ));
+        assert!(output.contains(
+            "edgeproxy_backend_selected_total{region=\"eu\",app=\"myapp\",backend_id=\"b2\"} 1"
+        ));
+    }
+
+    #[test]
+    fn test_export_prometheus_connections_closed() {
+        let store = PrometheusMetricsStore::new("eu".to_string());
+
+        store.record_connection_closed(CloseReason::ClientClosed);
+        store.record_connection_closed(CloseReason::Overload);
+        store.record_connection_closed(CloseReason::Overload);
+
+        assert_eq!(store.get_connection_closed(CloseReason::Overload), 2);
+
+        let output = store.export_prometheus();
+        assert!(output.contains("# TYPE edgeproxy_connection_closed_total counter"));
+        assert!(output.contains(
+            "edgeproxy_connection_closed_total{region=\"eu\",reason=\"client_eof\"} 1"
+        ));
+        assert!(output.contains(
+            "edgeproxy_connection_closed_total{region=\"eu\",reason=\"backend_eof\"} 0"
+        ));
+        assert!(output.contains(
+            "edgeproxy_connection_closed_total{region=\"eu\",reason=\"overload\"} 2"
+        ));
+    }
+
+    #[test]
+    fn test_export_prometheus_overload_rejections() {
+        let store = PrometheusMetricsStore::new("eu".to_string());
+        assert!(store
+            .export_prometheus()
+            .contains("edgeproxy_overload_rejections_total{region=\"eu\"} 0"));
+
+        store.record_overload_rejection();
+        store.record_overload_rejection();
+
+        assert_eq!(store.get_overload_rejections(), 2);
+        let output = store.export_prometheus();
+        assert!(output.contains("# TYPE edgeproxy_overload_rejections_total counter"));
+        assert!(output.contains("edgeproxy_overload_rejections_total{region=\"eu\"} 2"));
+    }
+
+    #[test]
+    fn test_export_prometheus_geo_unavailable() {
+        let store = PrometheusMetricsStore::new("eu".to_string());
+        assert!(store
+            .export_prometheus()
+            .contains("edgeproxy_geo_unavailable_total{region=\"eu\"} 0"));
+
+        store.record_geo_unavailable();
+
+        assert_eq!(store.get_geo_unavailable(), 1);
+        let output = store.export_prometheus();
+        assert!(output.contains("# TYPE edgeproxy_geo_unavailable_total counter"));
+        assert!(output.contains("edgeproxy_geo_unavailable_total{region=\"eu\"} 1"));
+    }
+
     #[test]
     fn test_backend_metrics_default() {
         let metrics = BackendMetrics::default();
@@ -0,0 +1,137 @@
+//! Public IP Providers
+//!
+//! Implements PublicIpProvider with an HTTP "what is my IP" service
+//! (AWS checkip by default) or a fixed, configured address.
+
+use crate::domain::ports::PublicIpProvider;
+use async_trait::async_trait;
+use std::net::IpAddr;
+use std::time::Duration;
+
+/// Endpoint used when none is configured (returns the caller's IP as text).
+pub const DEFAULT_PUBLIC_IP_URL: &str = "https://checkip.amazonaws.com/";
+
+/// Per-request timeout for the public IP endpoint.
+const FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Public IP from an HTTP endpoint that answers with the caller's IP as text.
+#[derive(Debug, Clone)]
+pub struct HttpPublicIpProvider {
+    url: String,
+}
+
+impl Default for HttpPublicIpProvider {
+    fn default() -> Self {
+        Self::new(DEFAULT_PUBLIC_IP_URL)
+    }
+}
+
+impl HttpPublicIpProvider {
+    /// Query `url` for the public IP.
+    pub fn new(url: impl Into<String>) -> Self {
+        Self { url: url.into() }
+    }
+
+    /// Endpoint queried for the public IP.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+}
+
+#[async_trait]
+impl PublicIpProvider for HttpPublicIpProvider {
+    async fn public_ip(&self) -> Option<IpAddr> {
+        let client = reqwest::Client::builder()
+            .timeout(FETCH_TIMEOUT)
+            .build()
+            .ok()?;
+
+        let resp = client.get(&self.url).send().await.ok()?;
+        let text = resp.text().await.ok()?.trim().to_string();
+
+        match text.parse::<IpAddr>() {
+            Ok(ip) => {
+                tracing::debug!("public IP detected via {}: {}", self.url, ip);
+                Some(ip)
+            }
+            Err(_) => None,
+        }
+    }
+}
+
+/// Fixed public IP, for hosts where outbound lookups are blocked.
+#[derive(Debug, Clone, Copy)]
+pub struct StaticPublicIpProvider {
+    ip: IpAddr,
+}
+
+impl StaticPublicIpProvider {
+    pub fn new(ip: IpAddr) -> Self {
+        Self { ip }
+    }
+}
+
+#[async_trait]
+impl PublicIpProvider for StaticPublicIpProvider {
+    async fn public_ip(&self) -> Option<IpAddr> {
+        Some(self.ip)
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Plain HTTP endpoint answering a single request with `body`.
+    async fn spawn_endpoint(body: &'static str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+
+        tokio::spawn(async move {
+            if let Ok((mut stream, _)) = listener.accept().await {
+                let mut buf = [0u8; 1024];
+                let _ = stream.read(&mut buf).await;
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+
+        url
+    }
+
+    #[test]
+    fn test_http_provider_default_url() {
+        assert_eq!(HttpPublicIpProvider::default().url(), DEFAULT_PUBLIC_IP_URL);
+    }
+
+    #[tokio::test]
+    async fn test_http_provider_parses_body() {
+        let provider = HttpPublicIpProvider::new(spawn_endpoint("203.0.113.7\n").await);
+        assert_eq!(provider.public_ip().await, Some("203.0.113.7".parse().unwrap()));
+    }
+
+    #[tokio::test]
+    async fn test_http_provider_invalid_body() {
+        let provider = HttpPublicIpProvider::new(spawn_endpoint("<html>oops</html>").await);
+        assert!(provider.public_ip().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_http_provider_unreachable() {
+        let provider = HttpPublicIpProvider::new("http://127.0.0.1:1/");
+        assert!(provider.public_ip().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_static_provider() {
+        let ip: IpAddr = "2001:db8::1".parse().unwrap();
+        assert_eq!(StaticPublicIpProvider::new(ip).public_ip().await, Some(ip));
+    }
+}
@@ -4,23 +4,147 @@
 //! Supports periodic reloading for dynamic backend updates.
 
 use crate::domain::entities::Backend;
-use crate::domain::ports::BackendRepository;
+use crate::domain::ports::{BackendRepository, MetricsStore};
 use crate::domain::value_objects::RegionCode;
+use crate::replication::schema;
 use anyhow::Result;
 use async_trait::async_trait;
-use rusqlite::{Connection, Row};
+use rusqlite::{Connection, OpenFlags, Row};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::time::SystemTime;
+use tokio::sync::{watch, RwLock};
 use tokio::time::{sleep, Duration};
 
+/// On-disk version of a SQLite database.
+///
+/// Taken from file metadata, covering the `-wal` file too since writes in
+/// WAL mode don't touch the main file until a checkpoint. Metadata alone
+/// can miss an in-place write that keeps the size within the mtime
+/// granularity, so the repository adds SQLite's own change counter
+/// (`PRAGMA data_version`) on top.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiskVersion {
+    modified: SystemTime,
+    len: u64,
+    wal: Option<(SystemTime, u64)>,
+    /// Identity of the main file (its inode), to spot it being replaced
+    file_id: u64,
+    /// `PRAGMA data_version` as seen by a kept-open connection
+    data_version: Option<i64>,
+}
+
+impl DiskVersion {
+    /// Read the current on-disk version, or None if the file can't be stat'ed.
+    ///
+    /// Only file metadata is read; the data version is left unset.
+    pub fn read(db_path: &str) -> Option<Self> {
+        let stat = |path: &str| {
+            std::fs::metadata(path)
+                .and_then(|m| Ok((m.modified()?, m.len())))
+                .ok()
+        };
+
+        let metadata = std::fs::metadata(db_path).ok()?;
+        Some(Self {
+            modified: metadata.modified().ok()?,
+            len: metadata.len(),
+            wal: stat(&format!("{}-wal", db_path)),
+            file_id: file_id(&metadata),
+            data_version: None,
+        })
+    }
+}
+
+#[cfg(unix)]
+fn file_id(metadata: &std::fs::Metadata) -> u64 {
+    std::os::unix::fs::MetadataExt::ino(metadata)
+}
+
+#[cfg(not(unix))]
+fn file_id(_metadata: &std::fs::Metadata) -> u64 {
+    0
+}
+
+/// Connection kept open on the database to read `PRAGMA data_version`.
+///
+/// The pragma only compares between reads on the same connection, so it
+/// is reopened only when the file it was opened on gets replaced.
+struct DataVersionWatch {
+    file_id: u64,
+    conn: Connection,
+}
+
+/// Per-backend changes between two loaded backend sets, keyed by id.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BackendDiff {
+    pub added: Vec<Backend>,
+    pub removed: Vec<String>,
+    pub updated: Vec<Backend>,
+}
+
+impl BackendDiff {
+    /// Compute the changes that turn `current` into `new`.
+    pub fn between(current: &[Backend], new: &[Backend]) -> Self {
+        let mut diff = Self::default();
+
+        for backend in new {
+            match current.iter().find(|b| b.id == backend.id) {
+                None => diff.added.push(backend.clone()),
+                Some(existing) if existing != backend => diff.updated.push(backend.clone()),
+                Some(_) => {}
+            }
+        }
+
+        diff.removed = current
+            .iter()
+            .filter(|b| !new.iter().any(|n| n.id == b.id))
+            .map(|b| b.id.clone())
+            .collect();
+
+        diff
+    }
+
+    /// True if the two sets were identical.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.updated.is_empty()
+    }
+
+    /// Apply the changes in place, leaving unchanged backends untouched.
+    pub fn apply(self, backends: &mut Vec<Backend>) {
+        backends.retain(|b| !self.removed.contains(&b.id));
+        for backend in self.updated {
+            if let Some(slot) = backends.iter_mut().find(|b| b.id == backend.id) {
+                *slot = backend;
+            }
+        }
+        backends.extend(self.added);
+    }
+}
+
 /// SQLite-backed backend repository.
 ///
 /// Periodically reloads backends from the database file.
 /// The database is expected to be replicated via Corrosion.
+///
+/// Every change to the in-memory set bumps `version` by exactly one, while
+/// the write lock on the set is still held. So:
+/// - the version strictly increases with each mutation and never moves
+///   on reads or on reloads that find nothing to change
+/// - a caller that sees version N from `get_version` and then reads the
+///   set gets a set at least as new as mutation N (it may be newer, which
+///   the next version check picks up)
 pub struct SqliteBackendRepository {
     backends: Arc<RwLock<Vec<Backend>>>,
+    /// Number of mutations applied to `backends`
     version: Arc<AtomicU64>,
+    /// On-disk version of the database as of the last applied reload
+    last_applied: Arc<parking_lot::Mutex<Option<DiskVersion>>>,
+    /// On-disk version at which an empty read was seen and held back
+    suspect_empty: Arc<parking_lot::Mutex<Option<DiskVersion>>>,
+    /// Kept-open connection feeding the data version of `DiskVersion`
+    watch: Arc<parking_lot::Mutex<Option<DataVersionWatch>>>,
+    metrics: Option<Arc<dyn MetricsStore>>,
 }
 
 impl SqliteBackendRepository {
@@ -29,43 +153,117 @@ impl SqliteBackendRepository {
         Self {
             backends: Arc::new(RwLock::new(Vec::new())),
             version: Arc::new(AtomicU64::new(0)),
+            last_applied: Arc::new(parking_lot::Mutex::new(None)),
+            suspect_empty: Arc::new(parking_lot::Mutex::new(None)),
+            watch: Arc::new(parking_lot::Mutex::new(None)),
+            metrics: None,
+        }
+    }
+
+    /// Report added/removed/updated counts of each reload to a metrics store.
+    pub fn with_metrics(mut self, metrics: Arc<dyn MetricsStore>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Share the same state (used to hand the repository to the sync task).
+    fn handle(&self) -> Self {
+        Self {
+            backends: self.backends.clone(),
+            version: self.version.clone(),
+            last_applied: self.last_applied.clone(),
+            suspect_empty: self.suspect_empty.clone(),
+            watch: self.watch.clone(),
+            metrics: self.metrics.clone(),
+        }
+    }
+
+    /// Reload backends from the database if its on-disk version advanced.
+    ///
+    /// Only backends that genuinely changed are touched, and the version
+    /// is bumped only when something changed. Returns true in that case.
+    ///
+    /// Reads that can't be trusted are held back without being recorded,
+    /// so the next call retries them:
+    /// - the file changed while it was being read (partial read)
+    /// - the read came back empty while backends are loaded; it is only
+    ///   applied once a second read at the same on-disk version agrees
+    pub async fn sync_once(&self, db_path: &str) -> Result<bool> {
+        let disk_version = self.read_version(db_path);
+        if disk_version.is_some() && *self.last_applied.lock() == disk_version {
+            tracing::trace!("routing unchanged on disk, skipping reload");
+            return Ok(false);
+        }
+
+        let path = db_path.to_string();
+        let new_backends =
+            tokio::task::spawn_blocking(move || Self::load_from_sqlite(&path)).await??;
+
+        if self.read_version(db_path) != disk_version {
+            tracing::debug!("routing changed during reload, retrying on next sync");
+            return Ok(false);
+        }
+
+        let mut guard = self.backends.write().await;
+
+        if new_backends.is_empty() && !guard.is_empty() {
+            let mut suspect = self.suspect_empty.lock();
+            if disk_version.is_none() || *suspect != disk_version {
+                tracing::warn!(
+                    "routing reload returned no backends (had {}), holding current set",
+                    guard.len()
+                );
+                *suspect = disk_version;
+                return Ok(false);
+            }
+        }
+        *self.suspect_empty.lock() = None;
+        *self.last_applied.lock() = disk_version;
+
+        let diff = BackendDiff::between(&guard, &new_backends);
+        if diff.is_empty() {
+            tracing::trace!("routing reload found no backend changes");
+            return Ok(false);
         }
+
+        let (added, removed, updated) = (diff.added.len(), diff.removed.len(), diff.updated.len());
+        let new_version = self.apply_locked(&mut guard, diff);
+        let count = guard.len();
+        let maintenance = guard.iter().filter(|b| b.maintenance).count();
+        drop(guard);
+
+        if let Some(metrics) = &self.metrics {
+            metrics.record_routing_changes(added, removed, updated);
+            metrics.set_maintenance_backends(maintenance);
+        }
+
+        tracing::info!(
+            "routing reload ok, version={} backends={} added={} removed={} updated={}",
+            new_version,
+            count,
+            added,
+            removed,
+            updated
+        );
+        Ok(true)
     }
 
     /// Start the background sync task.
     ///
     /// This spawns a Tokio task that periodically reloads backends
-    /// from the SQLite database file. The error handling paths inside
-    /// the spawned task are excluded from coverage as they require
-    /// specific runtime failures (spawn_blocking, IO errors) to trigger.
+    /// from the SQLite database file, skipping the reload when the
+    /// on-disk version hasn't advanced since the last applied one.
+    /// The error handling paths inside the spawned task are excluded
+    /// from coverage as they require specific runtime failures
+    /// (spawn_blocking, IO errors) to trigger.
     #[cfg_attr(coverage_nightly, coverage(off))]
     pub fn start_sync(&self, db_path: String, interval_secs: u64) {
-        let backends = self.backends.clone();
-        let version = self.version.clone();
+        let repo = self.handle();
 
         tokio::spawn(async move {
             loop {
-                let db_path_clone = db_path.clone();
-                match tokio::task::spawn_blocking(move || {
-                    Self::load_from_sqlite(&db_path_clone)
-                })
-                .await
-                {
-                    Ok(Ok(new_backends)) => {
-                        let count = new_backends.len();
-                        {
-                            let mut guard = backends.write().await;
-                            *guard = new_backends;
-                        }
-                        let new_version = version.fetch_add(1, Ordering::SeqCst) + 1;
-                        tracing::info!(
-                            "routing reload ok, version={} backends={}",
-                            new_version,
-                            count
-                        );
-                    }
-                    Ok(Err(e)) => tracing::error!("error reading routing: {:?}", e),
-                    Err(e) => tracing::error!("spawn_blocking error: {:?}", e),
+                if let Err(e) = repo.sync_once(&db_path).await {
+                    tracing::error!("error reading routing: {:?}", e);
                 }
 
                 sleep(Duration::from_secs(interval_secs)).await;
@@ -73,6 +271,62 @@ impl SqliteBackendRepository {
         });
     }
 
+    /// Reload as soon as `changes` is signalled, on top of the periodic sync.
+    ///
+    /// Fed by the replication sync service so replicated backend changes
+    /// reach routing without waiting for the reload interval. The on-disk
+    /// version check is bypassed, so the notified change is picked up even
+    /// if the data version couldn't be read.
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    pub fn start_notified_sync(&self, db_path: String, mut changes: watch::Receiver<u64>) {
+        let repo = self.handle();
+
+        tokio::spawn(async move {
+            while changes.changed().await.is_ok() {
+                repo.invalidate();
+                if let Err(e) = repo.sync_once(&db_path).await {
+                    tracing::error!("error reading routing after change notification: {:?}", e);
+                }
+            }
+        });
+    }
+
+    /// Apply `diff` to the set behind `guard` and bump the version.
+    ///
+    /// The only way the in-memory set changes; taking the write guard makes
+    /// the bump land before any reader can see the new set. Returns the
+    /// new version.
+    fn apply_locked(&self, guard: &mut Vec<Backend>, diff: BackendDiff) -> u64 {
+        diff.apply(guard);
+        self.version.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    /// Read the on-disk version, including the data version of the watch
+    /// connection (opened, or reopened on a replaced file, as needed).
+    ///
+    /// A failed pragma leaves the data version unset, which only costs a
+    /// reload when it differs from the last applied one.
+    fn read_version(&self, db_path: &str) -> Option<DiskVersion> {
+        let mut version = DiskVersion::read(db_path)?;
+        let mut watch = self.watch.lock();
+        if watch.as_ref().is_none_or(|w| w.file_id != version.file_id) {
+            *watch = Connection::open_with_flags(db_path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+                .ok()
+                .map(|conn| DataVersionWatch { file_id: version.file_id, conn });
+        }
+        version.data_version = watch.as_ref().and_then(|w| {
+            w.conn
+                .query_row("PRAGMA data_version", [], |row| row.get(0))
+                .ok()
+        });
+        Some(version)
+    }
+
+    /// Forget the last applied on-disk version so the next sync reloads.
+    pub fn invalidate(&self) {
+        *self.last_applied.lock() = None;
+    }
+
     /// Load backends from SQLite database file.
     ///
     /// This function is only called from start_sync and error paths
@@ -81,12 +335,20 @@ impl SqliteBackendRepository {
     fn load_from_sqlite(db_path: &str) -> Result<Vec<Backend>> {
         let conn = Connection::open(db_path)?;
 
-        let mut stmt = conn.prepare(
-            "SELECT id, app, region, country, wg_ip, port, healthy, weight, soft_limit, hard_limit
-             FROM backends
-             WHERE deleted IS NULL OR deleted = 0",
+        // Older schemas lack some optional columns; read their defaults instead
+        let columns = schema::backends_projection(
+            &conn,
+            &[
+                "id", "app", "region", "country", "wg_ip", "port", "healthy", "weight",
+                "soft_limit", "hard_limit", "draining", "deleted", "maintenance", "priority",
+            ],
         )?;
 
+        let mut stmt = conn.prepare(&format!(
+            "SELECT * FROM (SELECT {} FROM backends) WHERE deleted IS NULL OR deleted = 0",
+            columns.join(", ")
+        ))?;
+
         let backends = stmt
             .query_map([], Self::row_to_backend)?
             .collect::<Result<Vec<_>, _>>()?;
@@ -99,7 +361,7 @@ impl SqliteBackendRepository {
         Ok(Backend {
             id: row.get(0)?,
             app: row.get(1)?,
-            region: RegionCode::from_str(&row.get::<_, String>(2)?),
+            region: RegionCode::from_code(&row.get::<_, String>(2)?),
             country: row.get(3)?,
             wg_ip: row.get(4)?,
             port: row.get::<_, i64>(5)? as u16,
@@ -107,6 +369,10 @@ impl SqliteBackendRepository {
             weight: row.get::<_, i64>(7)? as u8,
             soft_limit: row.get::<_, i64>(8)? as u32,
             hard_limit: row.get::<_, i64>(9)? as u32,
+            // Optional column: absent from older queries and schemas
+            draining: row.get::<_, Option<i64>>(10).ok().flatten().unwrap_or(0) != 0,
+            maintenance: row.get::<_, Option<i64>>(12).ok().flatten().unwrap_or(0) != 0,
+            priority: row.get::<_, Option<i64>>(13).ok().flatten().unwrap_or(0) as u16,
         })
     }
 }
@@ -124,6 +390,10 @@ impl SqliteBackendRepository {
         Self {
             backends: Arc::new(RwLock::new(backends)),
             version: Arc::new(AtomicU64::new(1)),
+            last_applied: Arc::new(parking_lot::Mutex::new(None)),
+            suspect_empty: Arc::new(parking_lot::Mutex::new(None)),
+            watch: Arc::new(parking_lot::Mutex::new(None)),
+            metrics: None,
         }
     }
 }
@@ -175,6 +445,9 @@ mod tests {
             weight: 2,
             soft_limit: 100,
             hard_limit: 150,
+            draining: false,
+            maintenance: false,
+            priority: 0,
         }
     }
 
@@ -382,6 +655,50 @@ mod tests {
         assert_eq!(backends[0].id, "active");
     }
 
+    #[test]
+    fn test_load_from_sqlite_old_schema() {
+        use tempfile::NamedTempFile;
+
+        let temp_file = NamedTempFile::new().unwrap();
+        let db_path = temp_file.path().to_str().unwrap();
+
+        // No weight/limit/deleted/draining columns yet
+        let conn = Connection::open(db_path).unwrap();
+        conn.execute(
+            "CREATE TABLE backends (id TEXT PRIMARY KEY, app TEXT, region TEXT, country TEXT, wg_ip TEXT, port INTEGER, healthy INTEGER)",
+            [],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO backends VALUES ('old', 'myapp', 'eu', 'DE', '10.50.1.1', 8080, 1)",
+            [],
+        )
+        .unwrap();
+
+        let backends = SqliteBackendRepository::load_from_sqlite(db_path).unwrap();
+        assert_eq!(backends.len(), 1);
+        assert_eq!((backends[0].weight, backends[0].soft_limit, backends[0].hard_limit), (2, 100, 150));
+        assert!(!backends[0].draining);
+
+        // Reading doesn't alter the schema
+        assert_eq!(crate::replication::schema::table_columns(&conn, "backends").unwrap().len(), 7);
+    }
+
+    #[test]
+    fn test_load_from_sqlite_missing_required_column() {
+        use tempfile::NamedTempFile;
+
+        let temp_file = NamedTempFile::new().unwrap();
+        let db_path = temp_file.path().to_str().unwrap();
+
+        let conn = Connection::open(db_path).unwrap();
+        conn.execute("CREATE TABLE backends (id TEXT PRIMARY KEY, app TEXT, region TEXT)", [])
+            .unwrap();
+
+        let err = SqliteBackendRepository::load_from_sqlite(db_path).unwrap_err();
+        assert!(err.to_string().contains("missing required column `wg_ip`"));
+    }
+
     #[test]
     fn test_row_to_backend_mapping() {
         use tempfile::NamedTempFile;
@@ -608,4 +925,549 @@ mod tests {
         assert_eq!(backends.len(), 1);
         assert_eq!(backends[0].id, "null-deleted");
     }
+
+    // ===== On-disk Version Tests =====
+
+    fn create_routing_db(db_path: &str) {
+        let conn = Connection::open(db_path).unwrap();
+        conn.execute(
+            "CREATE TABLE backends (
+                id TEXT PRIMARY KEY, app TEXT, region TEXT, country TEXT, wg_ip TEXT,
+                port INTEGER, healthy INTEGER, weight INTEGER, soft_limit INTEGER, hard_limit INTEGER, deleted INTEGER DEFAULT 0
+            )",
+            [],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO backends VALUES ('dv-1', 'app', 'eu', 'DE', '10.0.0.1', 80, 1, 1, 10, 20, 0)",
+            [],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_disk_version_missing_file() {
+        assert!(DiskVersion::read("/nonexistent/path/db.sqlite").is_none());
+    }
+
+    #[test]
+    fn test_disk_version_changes_on_write() {
+        use tempfile::NamedTempFile;
+
+        let temp_file = NamedTempFile::new().unwrap();
+        let db_path = temp_file.path().to_str().unwrap();
+        create_routing_db(db_path);
+
+        let v1 = DiskVersion::read(db_path).unwrap();
+        assert_eq!(DiskVersion::read(db_path).unwrap(), v1);
+
+        let conn = Connection::open(db_path).unwrap();
+        conn.execute(
+            "INSERT INTO backends VALUES ('dv-2', 'app', 'eu', 'DE', '10.0.0.2', 80, 1, 1, 10, 20, 0)",
+            [],
+        )
+        .unwrap();
+        drop(conn);
+
+        assert_ne!(DiskVersion::read(db_path).unwrap(), v1);
+    }
+
+    #[tokio::test]
+    async fn test_sync_once_skips_when_version_held() {
+        use tempfile::NamedTempFile;
+
+        let temp_file = NamedTempFile::new().unwrap();
+        let db_path = temp_file.path().to_str().unwrap();
+        create_routing_db(db_path);
+
+        let repo = SqliteBackendRepository::new();
+
+        assert!(repo.sync_once(db_path).await.unwrap());
+        assert_eq!(repo.get_version().await, 1);
+
+        // Nothing changed on disk: no reload, version held
+        assert!(!repo.sync_once(db_path).await.unwrap());
+        assert!(!repo.sync_once(db_path).await.unwrap());
+        assert_eq!(repo.get_version().await, 1);
+        assert_eq!(repo.get_all().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_sync_once_reloads_write_hidden_from_metadata() {
+        use tempfile::NamedTempFile;
+
+        let temp_file = NamedTempFile::new().unwrap();
+        let db_path = temp_file.path().to_str().unwrap();
+        create_routing_db(db_path);
+
+        let repo = SqliteBackendRepository::new();
+        assert!(repo.sync_once(db_path).await.unwrap());
+        let before = DiskVersion::read(db_path).unwrap();
+
+        // Same-size in-place write, with the mtime put back afterwards
+        let conn = Connection::open(db_path).unwrap();
+        conn.execute("UPDATE backends SET healthy = 0 WHERE id = 'dv-1'", [])
+            .unwrap();
+        drop(conn);
+        std::fs::File::options()
+            .write(true)
+            .open(db_path)
+            .unwrap()
+            .set_modified(before.modified)
+            .unwrap();
+        assert_eq!(DiskVersion::read(db_path).unwrap(), before);
+
+        assert!(repo.sync_once(db_path).await.unwrap());
+        assert_eq!(repo.get_version().await, 2);
+        assert!(repo.get_healthy().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_sync_once_reloads_when_version_advances() {
+        use tempfile::NamedTempFile;
+
+        let temp_file = NamedTempFile::new().unwrap();
+        let db_path = temp_file.path().to_str().unwrap();
+        create_routing_db(db_path);
+
+        let repo = SqliteBackendRepository::new();
+        assert!(repo.sync_once(db_path).await.unwrap());
+
+        let conn = Connection::open(db_path).unwrap();
+        conn.execute(
+            "INSERT INTO backends VALUES ('dv-2', 'app', 'eu', 'DE', '10.0.0.2', 80, 1, 1, 10, 20, 0)",
+            [],
+        )
+        .unwrap();
+        drop(conn);
+
+        assert!(repo.sync_once(db_path).await.unwrap());
+        assert_eq!(repo.get_version().await, 2);
+        assert_eq!(repo.get_all().await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_version_strictly_increases_across_mutations() {
+        use tempfile::NamedTempFile;
+
+        let temp_file = NamedTempFile::new().unwrap();
+        let db_path = temp_file.path().to_str().unwrap();
+        create_routing_db(db_path);
+
+        let repo = SqliteBackendRepository::new();
+        assert!(repo.sync_once(db_path).await.unwrap());
+        let mut last = repo.get_version().await;
+
+        for i in 2..=5 {
+            let conn = Connection::open(db_path).unwrap();
+            conn.execute(
+                &format!(
+                    "INSERT INTO backends VALUES ('dv-{i}', 'app', 'eu', 'DE', '10.0.0.{i}', 80, 1, 1, 10, 20, 0)"
+                ),
+                [],
+            )
+            .unwrap();
+            drop(conn);
+
+            repo.invalidate();
+            assert!(repo.sync_once(db_path).await.unwrap());
+            let version = repo.get_version().await;
+            assert_eq!(version, last + 1);
+
+            // Reads, and reloads that change nothing, leave it alone
+            repo.get_all().await;
+            repo.get_healthy().await;
+            repo.invalidate();
+            assert!(!repo.sync_once(db_path).await.unwrap());
+            assert_eq!(repo.get_version().await, version);
+            last = version;
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_version_never_lags_visible_set() {
+        let repo = Arc::new(SqliteBackendRepository::new());
+
+        // Each mutation adds one backend, so the set size equals its version
+        let writer = {
+            let repo = repo.clone();
+            tokio::spawn(async move {
+                for i in 0..200 {
+                    let mut guard = repo.backends.write().await;
+                    let diff = BackendDiff {
+                        added: vec![create_test_backend(&format!("b{i}"), true)],
+                        ..Default::default()
+                    };
+                    repo.apply_locked(&mut guard, diff);
+                    drop(guard);
+                    tokio::task::yield_now().await;
+                }
+            })
+        };
+
+        let readers: Vec<_> = (0..3)
+            .map(|_| {
+                let repo = repo.clone();
+                tokio::spawn(async move {
+                    let mut last = 0;
+                    while last < 200 {
+                        let seen = repo.get_all().await.len() as u64;
+                        let version = repo.get_version().await;
+                        assert!(version >= seen, "set of {seen} visible at version {version}");
+                        assert!(version >= last, "version went back from {last} to {version}");
+                        last = version;
+                    }
+                })
+            })
+            .collect();
+
+        writer.await.unwrap();
+        for reader in readers {
+            reader.await.unwrap();
+        }
+        assert_eq!(repo.get_version().await, 200);
+    }
+
+    #[tokio::test]
+    async fn test_invalidate_forgets_applied_version() {
+        use tempfile::NamedTempFile;
+
+        let temp_file = NamedTempFile::new().unwrap();
+        let db_path = temp_file.path().to_str().unwrap();
+        create_routing_db(db_path);
+
+        let repo = SqliteBackendRepository::new();
+        assert!(repo.sync_once(db_path).await.unwrap());
+        assert!(repo.last_applied.lock().is_some());
+
+        repo.invalidate();
+        assert!(repo.last_applied.lock().is_none());
+
+        // Re-reads the unchanged file, finding nothing to apply
+        assert!(!repo.sync_once(db_path).await.unwrap());
+        assert!(repo.last_applied.lock().is_some());
+        assert_eq!(repo.get_version().await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_notified_sync_reloads_without_waiting_for_interval() {
+        use tempfile::NamedTempFile;
+
+        let temp_file = NamedTempFile::new().unwrap();
+        let db_path = temp_file.path().to_str().unwrap();
+        create_routing_db(db_path);
+
+        let repo = SqliteBackendRepository::new();
+        assert!(repo.sync_once(db_path).await.unwrap());
+
+        let (tx, rx) = watch::channel(0u64);
+        repo.start_notified_sync(db_path.to_string(), rx);
+
+        let conn = Connection::open(db_path).unwrap();
+        conn.execute(
+            "INSERT INTO backends VALUES ('dv-2', 'app', 'eu', 'DE', '10.0.0.2', 80, 1, 1, 10, 20, 0)",
+            [],
+        )
+        .unwrap();
+        drop(conn);
+        tx.send_modify(|generation| *generation += 1);
+
+        for _ in 0..100 {
+            if repo.get_all().await.len() == 2 {
+                break;
+            }
+            sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(repo.get_all().await.len(), 2);
+        assert_eq!(repo.get_version().await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_sync_once_error_keeps_state() {
+        let repo = SqliteBackendRepository::with_backends(vec![create_test_backend("b1", true)]);
+
+        assert!(repo.sync_once("/nonexistent/path/db.sqlite").await.is_err());
+        assert_eq!(repo.get_version().await, 1);
+        assert_eq!(repo.get_all().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_start_sync_holds_version_without_changes() {
+        use tempfile::NamedTempFile;
+
+        let temp_file = NamedTempFile::new().unwrap();
+        let db_path = temp_file.path().to_str().unwrap().to_string();
+        create_routing_db(&db_path);
+
+        let repo = SqliteBackendRepository::new();
+        repo.start_sync(db_path, 1);
+
+        // Two sync cycles with no writes in between
+        tokio::time::sleep(Duration::from_secs(1) + Duration::from_millis(200)).await;
+        assert_eq!(repo.get_version().await, 1);
+    }
+
+    #[test]
+    fn test_load_from_sqlite_reads_draining_column() {
+        use tempfile::NamedTempFile;
+
+        let temp_file = NamedTempFile::new().unwrap();
+        let db_path = temp_file.path().to_str().unwrap();
+        create_routing_db(db_path);
+
+        // Older schema: no draining column, backends aren't draining
+        let backends = SqliteBackendRepository::load_from_sqlite(db_path).unwrap();
+        assert!(!backends[0].draining);
+
+        let conn = Connection::open(db_path).unwrap();
+        conn.execute("ALTER TABLE backends ADD COLUMN draining INTEGER DEFAULT 0", [])
+            .unwrap();
+        conn.execute("UPDATE backends SET draining = 1 WHERE id = 'dv-1'", [])
+            .unwrap();
+        drop(conn);
+
+        let backends = SqliteBackendRepository::load_from_sqlite(db_path).unwrap();
+        assert!(backends[0].draining);
+        assert!(!backends[0].accepts_new_connections());
+    }
+
+    #[test]
+    fn test_load_from_sqlite_reads_maintenance_column() {
+        use tempfile::NamedTempFile;
+
+        let temp_file = NamedTempFile::new().unwrap();
+        let db_path = temp_file.path().to_str().unwrap();
+        create_routing_db(db_path);
+
+        let backends = SqliteBackendRepository::load_from_sqlite(db_path).unwrap();
+        assert!(!backends[0].maintenance);
+
+        let conn = Connection::open(db_path).unwrap();
+        conn.execute("ALTER TABLE backends ADD COLUMN maintenance INTEGER DEFAULT 0", [])
+            .unwrap();
+        conn.execute("UPDATE backends SET maintenance = 1 WHERE id = 'dv-1'", [])
+            .unwrap();
+        drop(conn);
+
+        let backends = SqliteBackendRepository::load_from_sqlite(db_path).unwrap();
+        assert!(backends[0].maintenance);
+        assert!(backends[0].healthy);
+        assert!(!backends[0].accepts_new_connections());
+    }
+
+    #[test]
+    fn test_load_from_sqlite_region_codes_round_trip() {
+        use tempfile::NamedTempFile;
+
+        let temp_file = NamedTempFile::new().unwrap();
+        let db_path = temp_file.path().to_str().unwrap();
+        create_routing_db(db_path);
+
+        let conn = Connection::open(db_path).unwrap();
+        conn.execute("DELETE FROM backends", []).unwrap();
+        for code in ["sa", "us", "eu", "ap", "mars"] {
+            conn.execute(
+                "INSERT INTO backends VALUES (?1, 'app', ?1, 'XX', '10.0.0.1', 80, 1, 1, 10, 20, 0)",
+                [code],
+            )
+            .unwrap();
+        }
+        drop(conn);
+
+        let backends = SqliteBackendRepository::load_from_sqlite(db_path).unwrap();
+        for backend in &backends {
+            if backend.id == "mars" {
+                assert_eq!(backend.region, RegionCode::Unknown);
+            } else {
+                // Written back as stored
+                assert_eq!(backend.region.as_str(), backend.id);
+            }
+        }
+        assert_eq!(backends.len(), 5);
+    }
+
+    // ===== Diff Reload Tests =====
+
+    #[test]
+    fn test_backend_diff_between() {
+        let mut changed = create_test_backend("b2", true);
+        changed.weight = 9;
+
+        let current = vec![
+            create_test_backend("b1", true),
+            create_test_backend("b2", true),
+            create_test_backend("b3", true),
+        ];
+        let new = vec![
+            create_test_backend("b1", true),
+            changed.clone(),
+            create_test_backend("b4", true),
+        ];
+
+        let diff = BackendDiff::between(&current, &new);
+        assert_eq!(diff.added, vec![create_test_backend("b4", true)]);
+        assert_eq!(diff.removed, vec!["b3".to_string()]);
+        assert_eq!(diff.updated, vec![changed]);
+        assert!(!diff.is_empty());
+
+        assert!(BackendDiff::between(&current, &current).is_empty());
+    }
+
+    #[test]
+    fn test_backend_diff_apply_keeps_unchanged_in_place() {
+        let mut current = vec![
+            create_test_backend("b1", true),
+            create_test_backend("b2", true),
+            create_test_backend("b3", true),
+        ];
+        let new = vec![
+            create_test_backend("b3", true),
+            create_test_backend("b2", false),
+            create_test_backend("b4", true),
+        ];
+
+        BackendDiff::between(&current, &new).apply(&mut current);
+
+        let ids: Vec<_> = current.iter().map(|b| b.id.as_str()).collect();
+        assert_eq!(ids, vec!["b2", "b3", "b4"]);
+        assert!(!current[0].healthy);
+    }
+
+    #[tokio::test]
+    async fn test_sync_once_identical_rewrite_holds_version() {
+        use tempfile::NamedTempFile;
+
+        let temp_file = NamedTempFile::new().unwrap();
+        let db_path = temp_file.path().to_str().unwrap();
+        create_routing_db(db_path);
+
+        let repo = SqliteBackendRepository::new();
+        assert!(repo.sync_once(db_path).await.unwrap());
+
+        // Write that leaves the routing content as it was
+        let conn = Connection::open(db_path).unwrap();
+        conn.execute("UPDATE backends SET weight = 1 WHERE id = 'dv-1'", [])
+            .unwrap();
+        drop(conn);
+
+        assert!(!repo.sync_once(db_path).await.unwrap());
+        assert_eq!(repo.get_version().await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_sync_once_records_change_metrics() {
+        use crate::adapters::outbound::DashMapMetricsStore;
+        use tempfile::NamedTempFile;
+
+        let temp_file = NamedTempFile::new().unwrap();
+        let db_path = temp_file.path().to_str().unwrap();
+        create_routing_db(db_path);
+
+        let metrics = Arc::new(DashMapMetricsStore::new());
+        let repo = SqliteBackendRepository::new().with_metrics(metrics.clone());
+        assert!(repo.sync_once(db_path).await.unwrap());
+        assert_eq!(metrics.get_routing_changes(), (1, 0, 0));
+
+        let conn = Connection::open(db_path).unwrap();
+        conn.execute("UPDATE backends SET healthy = 0 WHERE id = 'dv-1'", [])
+            .unwrap();
+        conn.execute(
+            "INSERT INTO backends VALUES ('dv-2', 'app', 'eu', 'DE', '10.0.0.2', 80, 1, 1, 10, 20, 0)",
+            [],
+        )
+        .unwrap();
+        drop(conn);
+
+        assert!(repo.sync_once(db_path).await.unwrap());
+        assert_eq!(metrics.get_routing_changes(), (2, 0, 1));
+        assert_eq!(metrics.get_maintenance_backends(), 0);
+
+        // Maintenance is reported on its own, not as an unhealthy backend
+        let conn = Connection::open(db_path).unwrap();
+        conn.execute("ALTER TABLE backends ADD COLUMN maintenance INTEGER DEFAULT 0", [])
+            .unwrap();
+        conn.execute("UPDATE backends SET maintenance = 1 WHERE id = 'dv-2'", [])
+            .unwrap();
+        drop(conn);
+
+        assert!(repo.sync_once(db_path).await.unwrap());
+        assert_eq!(metrics.get_maintenance_backends(), 1);
+        assert_eq!(repo.get_healthy().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_transient_empty_read_does_not_churn_bindings() {
+        use crate::adapters::outbound::{DashMapBindingRepository, DashMapMetricsStore};
+        use crate::application::ProxyService;
+        use crate::domain::ports::BindingRepository;
+        use tempfile::NamedTempFile;
+
+        let temp_file = NamedTempFile::new().unwrap();
+        let db_path = temp_file.path().to_str().unwrap();
+        create_routing_db(db_path);
+
+        let repo = Arc::new(SqliteBackendRepository::new());
+        assert!(repo.sync_once(db_path).await.unwrap());
+
+        let binding_repo = Arc::new(DashMapBindingRepository::new());
+        let service = ProxyService::new(
+            repo.clone(),
+            binding_repo.clone(),
+            None,
+            Arc::new(DashMapMetricsStore::new()),
+            RegionCode::Europe,
+        );
+        let client: std::net::IpAddr = "10.10.10.10".parse().unwrap();
+        assert_eq!(service.resolve_backend(client).await.unwrap().id, "dv-1");
+
+        // Reload observes an empty table (e.g. mid-rewrite)
+        let conn = Connection::open(db_path).unwrap();
+        conn.execute("DELETE FROM backends", []).unwrap();
+        drop(conn);
+
+        assert!(!repo.sync_once(db_path).await.unwrap());
+        assert_eq!(repo.get_all().await.len(), 1);
+        assert_eq!(repo.get_version().await, 1);
+
+        // Client keeps its binding and backend
+        assert_eq!(service.resolve_backend(client).await.unwrap().id, "dv-1");
+        assert_eq!(binding_repo.count().await, 1);
+
+        // The table is refilled before the next reload: nothing churns
+        let conn = Connection::open(db_path).unwrap();
+        conn.execute(
+            "INSERT INTO backends VALUES ('dv-1', 'app', 'eu', 'DE', '10.0.0.1', 80, 1, 1, 10, 20, 0)",
+            [],
+        )
+        .unwrap();
+        drop(conn);
+
+        assert!(!repo.sync_once(db_path).await.unwrap());
+        assert_eq!(repo.get_version().await, 1);
+        assert_eq!(service.resolve_backend(client).await.unwrap().id, "dv-1");
+    }
+
+    #[tokio::test]
+    async fn test_confirmed_empty_read_is_applied() {
+        use tempfile::NamedTempFile;
+
+        let temp_file = NamedTempFile::new().unwrap();
+        let db_path = temp_file.path().to_str().unwrap();
+        create_routing_db(db_path);
+
+        let repo = SqliteBackendRepository::new();
+        assert!(repo.sync_once(db_path).await.unwrap());
+
+        let conn = Connection::open(db_path).unwrap();
+        conn.execute("DELETE FROM backends", []).unwrap();
+        drop(conn);
+
+        // First empty read is held back, the second at the same version applies
+        assert!(!repo.sync_once(db_path).await.unwrap());
+        assert_eq!(repo.get_all().await.len(), 1);
+
+        assert!(repo.sync_once(db_path).await.unwrap());
+        assert!(repo.get_all().await.is_empty());
+        assert_eq!(repo.get_version().await, 2);
+    }
 }
@@ -0,0 +1,128 @@
+//! Static GeoIP Resolvers
+//!
+//! Implements GeoResolver from a fixed table of networks, or not at all,
+//! for tests and embedders that don't ship a GeoIP database.
+
+use crate::domain::entities::GeoInfo;
+use crate::domain::ports::GeoResolver;
+use crate::infrastructure::CidrBlock;
+use std::net::IpAddr;
+
+/// Resolves addresses from a fixed table of networks.
+///
+/// The most specific matching network wins; unmatched addresses get the
+/// default (None unless set).
+#[derive(Debug, Clone, Default)]
+pub struct StaticGeoResolver {
+    rules: Vec<(CidrBlock, GeoInfo)>,
+    default: Option<GeoInfo>,
+}
+
+impl StaticGeoResolver {
+    /// Create a resolver answering `default` for every unmatched address.
+    pub fn new(default: Option<GeoInfo>) -> Self {
+        Self {
+            rules: Vec::new(),
+            default,
+        }
+    }
+
+    /// Resolve every address inside `block` to `geo`.
+    pub fn with_network(mut self, block: CidrBlock, geo: GeoInfo) -> Self {
+        self.rules.push((block, geo));
+        self
+    }
+
+    /// Resolve a single address to `geo`.
+    pub fn with_ip(self, ip: IpAddr, geo: GeoInfo) -> Self {
+        self.with_network(CidrBlock::from(ip), geo)
+    }
+}
+
+impl GeoResolver for StaticGeoResolver {
+    fn resolve(&self, ip: IpAddr) -> Option<GeoInfo> {
+        self.rules
+            .iter()
+            .filter(|(block, _)| block.contains(ip))
+            .max_by_key(|(block, _)| block.prefix_len())
+            .map(|(_, geo)| geo.clone())
+            .or_else(|| self.default.clone())
+    }
+}
+
+/// Resolves nothing, as if every address were missing from the database.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NullGeoResolver;
+
+impl GeoResolver for NullGeoResolver {
+    fn resolve(&self, _ip: IpAddr) -> Option<GeoInfo> {
+        None
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::domain::value_objects::RegionCode;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    fn geo(country: &str, region: RegionCode) -> GeoInfo {
+        GeoInfo::new(country.to_string(), region)
+    }
+
+    #[test]
+    fn test_static_resolver_matches_cidr() {
+        let resolver = StaticGeoResolver::new(None)
+            .with_network("10.0.0.0/8".parse().unwrap(), geo("DE", RegionCode::Europe))
+            .with_network("2001:db8::/32".parse().unwrap(), geo("BR", RegionCode::SouthAmerica));
+
+        assert_eq!(resolver.resolve(ip("10.1.2.3")), Some(geo("DE", RegionCode::Europe)));
+        assert_eq!(
+            resolver.resolve(ip("2001:db8::1")),
+            Some(geo("BR", RegionCode::SouthAmerica))
+        );
+        assert_eq!(resolver.resolve(ip("11.0.0.1")), None);
+    }
+
+    #[test]
+    fn test_static_resolver_most_specific_network_wins() {
+        let resolver = StaticGeoResolver::new(None)
+            .with_network("10.0.0.0/8".parse().unwrap(), geo("DE", RegionCode::Europe))
+            .with_ip(ip("10.9.9.9"), geo("JP", RegionCode::AsiaPacific))
+            .with_network("10.1.0.0/16".parse().unwrap(), geo("US", RegionCode::NorthAmerica));
+
+        assert_eq!(resolver.resolve(ip("10.9.9.9")), Some(geo("JP", RegionCode::AsiaPacific)));
+        assert_eq!(resolver.resolve(ip("10.1.0.5")), Some(geo("US", RegionCode::NorthAmerica)));
+        assert_eq!(resolver.resolve(ip("10.2.0.5")), Some(geo("DE", RegionCode::Europe)));
+    }
+
+    #[test]
+    fn test_static_resolver_default_fallback() {
+        let resolver = StaticGeoResolver::new(Some(geo("US", RegionCode::NorthAmerica)))
+            .with_network("10.0.0.0/8".parse().unwrap(), geo("DE", RegionCode::Europe));
+
+        assert_eq!(resolver.resolve(ip("10.0.0.1")), Some(geo("DE", RegionCode::Europe)));
+        assert_eq!(resolver.resolve(ip("8.8.8.8")), Some(geo("US", RegionCode::NorthAmerica)));
+        assert_eq!(resolver.resolve(ip("::1")), Some(geo("US", RegionCode::NorthAmerica)));
+    }
+
+    #[test]
+    fn test_static_resolver_ipv4_rule_ignores_ipv6() {
+        let resolver = StaticGeoResolver::new(None)
+            .with_network("0.0.0.0/0".parse().unwrap(), geo("DE", RegionCode::Europe));
+
+        assert!(resolver.resolve(ip("1.2.3.4")).is_some());
+        assert!(resolver.resolve(ip("::ffff:1.2.3.4")).is_none());
+    }
+
+    #[test]
+    fn test_null_resolver_resolves_nothing() {
+        let resolver = NullGeoResolver;
+        assert!(resolver.resolve(ip("8.8.8.8")).is_none());
+        assert!(resolver.resolve(ip("::1")).is_none());
+    }
+}
@@ -1,3 +1,6 @@
 mod proxy_service;
 
-pub use proxy_service::ProxyService;
+pub use proxy_service::{
+    AppSummary, GeoUnavailablePolicy, LoadBalancingStrategy, ProxyService, ProxyServiceBuildError, ProxyServiceBuilder, RouteCandidate, RouteExplanation,
+    RegionSummary, RouteStrategy, Unavailable,
+};
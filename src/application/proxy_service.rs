@@ -5,12 +5,152 @@
 
 use crate::domain::entities::{Backend, Binding, ClientKey, GeoInfo};
 use crate::domain::ports::{BackendRepository, BindingRepository, GeoResolver, MetricsStore};
-use crate::domain::services::LoadBalancer;
-use crate::domain::value_objects::RegionCode;
-use std::net::IpAddr;
+use crate::domain::services::{FlowKey, LoadBalancer, SelectionContext};
+use crate::domain::value_objects::{
+    CloseReason, ConnectPhase, DnsQueryOutcome, RegionCode, SelectionOutcome,
+};
+use crate::infrastructure::{RateLimitResult, RateLimiter};
+use arc_swap::ArcSwap;
+use rand::RngCore;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
 
+/// How [`ProxyService::explain_route`] arrived at its answer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RouteStrategy {
+    /// The client is already bound to a backend that still takes connections
+    ExistingBinding,
+    /// Geo tier + load / weight scoring by the load balancer
+    GeoLoadScore,
+}
+
+/// One backend as seen by a dry-run routing decision.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RouteCandidate {
+    pub id: String,
+    pub app: String,
+    pub region: String,
+    pub country: String,
+    pub active_connections: usize,
+    /// Load balancer score (lower is better), absent when excluded
+    pub score: Option<f64>,
+    /// Why the backend was left out (e.g. "hard_limit")
+    pub excluded: Option<&'static str>,
+}
+
+/// Dry-run routing decision for a client, without side effects.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RouteExplanation {
+    pub client_ip: IpAddr,
+    pub app: Option<String>,
+    /// Resolved client country, if the IP could be located
+    pub country: Option<String>,
+    /// Resolved client region, if the IP could be located
+    pub region: Option<String>,
+    pub local_region: String,
+    pub strategy: RouteStrategy,
+    /// Every backend considered, in id order
+    pub candidates: Vec<RouteCandidate>,
+    /// Id of the backend a real connection would be routed to
+    pub chosen: Option<String>,
+    /// Where the choice sits relative to the client's region
+    /// (a selection outcome label, e.g. "region_fallback")
+    pub outcome: &'static str,
+    /// Human-readable summary of the decision
+    pub reason: String,
+}
+
+/// Backend counts and load of one region.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RegionSummary {
+    pub region: String,
+    /// Backends registered in the region
+    pub total: usize,
+    /// Backends the repository reports as healthy
+    pub healthy: usize,
+    /// Backends down for maintenance (counted apart from unhealthy ones)
+    pub maintenance: usize,
+    /// Active connections summed over the region's backends
+    pub active_connections: usize,
+}
+
+/// Backend counts and load of one app.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AppSummary {
+    pub app: String,
+    /// Backends registered for the app
+    pub total: usize,
+    /// Backends the repository reports as healthy
+    pub healthy: usize,
+    /// Active connections summed over the app's backends
+    pub active_connections: usize,
+}
+
+/// Why no backend could be picked for a connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unavailable {
+    /// No healthy, non-draining backend serves the request
+    NoBackend,
+    /// Backends serve the request, but every one is at its hard limit
+    Overloaded,
+}
+
+/// How clients are routed while no geo resolver is loaded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GeoUnavailablePolicy {
+    /// Route without geo, preferring backends in the local region
+    #[default]
+    FailOpen,
+    /// Refuse to route, as if no backend were available
+    FailClosed,
+}
+
+impl FromStr for GeoUnavailablePolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "open" | "fail-open" => Ok(Self::FailOpen),
+            "closed" | "fail-closed" => Ok(Self::FailClosed),
+            other => anyhow::bail!("unknown geo unavailable policy {:?} (expected open or closed)", other),
+        }
+    }
+}
+
+/// How new TCP connections are assigned to backends.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LoadBalancingStrategy {
+    /// Geo tier + load / weight scoring, with the client bound to the
+    /// chosen backend for session affinity
+    #[default]
+    GeoLoadScore,
+    /// Geo tier first, then a weighted hash of the connection's client
+    /// address, client port and destination port. The same flow always
+    /// maps to the same backend without storing a binding, which is
+    /// cheaper for short-lived connections; load is not considered.
+    FlowHash,
+}
+
+impl FromStr for LoadBalancingStrategy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "geo" | "geo-load-score" => Ok(Self::GeoLoadScore),
+            "flow" | "flow-hash" => Ok(Self::FlowHash),
+            other => anyhow::bail!(
+                "unknown load balancing strategy {:?} (expected geo or flow-hash)",
+                other
+            ),
+        }
+    }
+}
+
 /// Proxy service - main application use case.
 ///
 /// This service orchestrates the proxy logic:
@@ -18,11 +158,16 @@ use std::time::Duration;
 /// 2. Manages client-to-backend bindings (session affinity)
 /// 3. Records connection metrics
 pub struct ProxyService {
-    backend_repo: Arc<dyn BackendRepository>,
+    backend_repo: ArcSwap<Arc<dyn BackendRepository>>,
     binding_repo: Arc<dyn BindingRepository>,
-    geo_resolver: Option<Arc<dyn GeoResolver>>,
+    geo_resolver: ArcSwap<Option<Arc<dyn GeoResolver>>>,
     metrics: Arc<dyn MetricsStore>,
     local_region: RegionCode,
+    strict_country: bool,
+    zero_weight_fallback: bool,
+    geo_unavailable: GeoUnavailablePolicy,
+    strategy: LoadBalancingStrategy,
+    rate_limiter: Option<Arc<RateLimiter>>,
 }
 
 impl ProxyService {
@@ -35,14 +180,58 @@ impl ProxyService {
         local_region: RegionCode,
     ) -> Self {
         Self {
-            backend_repo,
+            backend_repo: ArcSwap::from_pointee(backend_repo),
             binding_repo,
-            geo_resolver,
+            geo_resolver: ArcSwap::from_pointee(geo_resolver),
             metrics,
             local_region,
+            strict_country: false,
+            zero_weight_fallback: false,
+            geo_unavailable: GeoUnavailablePolicy::default(),
+            strategy: LoadBalancingStrategy::default(),
+            rate_limiter: None,
         }
     }
 
+    /// When every eligible backend has weight 0, route new clients to them
+    /// as if equally weighted instead of rejecting them. Bound clients keep
+    /// their backend either way.
+    pub fn with_zero_weight_fallback(mut self, zero_weight_fallback: bool) -> Self {
+        self.zero_weight_fallback = zero_weight_fallback;
+        self
+    }
+
+    /// Set how clients are routed while no geo resolver is loaded.
+    pub fn with_geo_unavailable(mut self, policy: GeoUnavailablePolicy) -> Self {
+        self.geo_unavailable = policy;
+        self
+    }
+
+    /// Set how [`ProxyService::resolve_flow_backend`] assigns connections.
+    pub fn with_strategy(mut self, strategy: LoadBalancingStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Only route to backends outside the client's country when none inside
+    /// it is available, regardless of load.
+    pub fn with_strict_country(mut self, strict_country: bool) -> Self {
+        self.strict_country = strict_country;
+        self
+    }
+
+    /// Check the limiter's per-app and per-region limits before handing out
+    /// a backend for a new connection. Per-client limits are not consulted.
+    pub fn with_rate_limiter(mut self, limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = Some(limiter);
+        self
+    }
+
+    /// Start building a proxy service with named setters.
+    pub fn builder() -> ProxyServiceBuilder {
+        ProxyServiceBuilder::new()
+    }
+
     /// Resolve the best backend for a client IP.
     ///
     /// This is the main entry point for routing decisions. It:
@@ -64,9 +253,12 @@ impl ProxyService {
             // Update last_seen
             self.binding_repo.touch(&client_key).await;
 
-            // Verify backend is still healthy
-            if let Some(backend) = self.backend_repo.get_by_id(&binding.backend_id).await {
-                if backend.healthy {
+            // Verify backend is still healthy, not draining and below its hard limit
+            if let Some(backend) = self.backend_repo().get_by_id(&binding.backend_id).await {
+                if self.admits(&backend) {
+                    if !self.within_rate_limits(&backend) {
+                        return None;
+                    }
                     tracing::debug!(
                         "using existing binding for {} -> {}",
                         client_ip,
@@ -76,7 +268,7 @@ impl ProxyService {
                 }
             }
 
-            // Backend unhealthy or gone - remove stale binding
+            // Backend unhealthy, draining, full or gone - remove stale binding
             self.binding_repo.remove(&client_key).await;
             tracing::debug!("removed stale binding for {}", client_ip);
         }
@@ -85,20 +277,15 @@ impl ProxyService {
         let client_geo = self.resolve_geo(client_ip);
 
         // 3. Get healthy backends
-        let backends = self.backend_repo.get_healthy().await;
-        if backends.is_empty() {
-            tracing::warn!("no healthy backends available");
-            return None;
-        }
+        let backends = self.backend_repo().get_healthy().await;
 
         // 4. Use load balancer to pick best backend
-        let metrics = self.metrics.clone();
-        let backend = LoadBalancer::pick_backend(
-            &backends,
-            &self.local_region,
-            client_geo.as_ref(),
-            |id| metrics.get_connection_count(id),
-        )?;
+        let Some(backend) =
+            self.select_connection(&backends, Some(client_ip), client_geo.as_ref(), None)
+        else {
+            tracing::warn!("no backend available for {}", client_ip);
+            return None;
+        };
 
         // 5. Create binding for session affinity
         self.binding_repo
@@ -123,34 +310,76 @@ impl ProxyService {
         &self,
         client_ip: IpAddr,
         client_geo: Option<GeoInfo>,
+    ) -> Option<Backend> {
+        self.resolve_backend_excluding(client_ip, client_geo, &HashSet::new())
+            .await
+    }
+
+    /// Resolve a backend for one connection according to the configured
+    /// [`LoadBalancingStrategy`].
+    ///
+    /// With [`LoadBalancingStrategy::GeoLoadScore`] this is
+    /// [`ProxyService::resolve_backend_with_geo`]. With
+    /// [`LoadBalancingStrategy::FlowHash`] the backend is picked by hashing
+    /// `client` and `dest_port`, and client bindings are neither read nor
+    /// written.
+    pub async fn resolve_flow_backend(
+        &self,
+        client: SocketAddr,
+        dest_port: Option<u16>,
+        client_geo: Option<GeoInfo>,
+    ) -> Option<Backend> {
+        if self.strategy == LoadBalancingStrategy::GeoLoadScore {
+            return self.resolve_backend_with_geo(client.ip(), client_geo).await;
+        }
+
+        let flow = FlowKey {
+            client,
+            dest_port,
+        };
+        let backends = self.backend_repo().get_healthy().await;
+        self.select_connection(&backends, Some(client.ip()), client_geo.as_ref(), Some(&flow))
+    }
+
+    /// Resolve the best backend, skipping the given backend ids.
+    ///
+    /// Intended for connect retries: pass the ids already tried and the
+    /// next-best backend is returned (and bound to the client). An existing
+    /// binding to an excluded backend is ignored. Returns `None` when every
+    /// candidate is excluded.
+    pub async fn resolve_backend_excluding(
+        &self,
+        client_ip: IpAddr,
+        client_geo: Option<GeoInfo>,
+        exclude: &HashSet<String>,
     ) -> Option<Backend> {
         let client_key = ClientKey::new(client_ip);
 
         // Check for existing binding first
         if let Some(binding) = self.binding_repo.get(&client_key).await {
-            self.binding_repo.touch(&client_key).await;
-            if let Some(backend) = self.backend_repo.get_by_id(&binding.backend_id).await {
-                if backend.healthy {
-                    return Some(backend);
+            if !exclude.contains(&binding.backend_id) {
+                self.binding_repo.touch(&client_key).await;
+                if let Some(backend) = self.backend_repo().get_by_id(&binding.backend_id).await {
+                    if self.admits(&backend) {
+                        return self.within_rate_limits(&backend).then_some(backend);
+                    }
                 }
             }
             self.binding_repo.remove(&client_key).await;
         }
 
-        // Get healthy backends
-        let backends = self.backend_repo.get_healthy().await;
-        if backends.is_empty() {
-            return None;
-        }
+        // Get healthy backends that haven't been excluded
+        let backends: Vec<Backend> = self
+            .backend_repo()
+            .get_healthy()
+            .await
+            .into_iter()
+            .filter(|b| !exclude.contains(&b.id))
+            .collect();
 
         // Use load balancer with provided geo
-        let metrics = self.metrics.clone();
-        let backend = LoadBalancer::pick_backend(
-            &backends,
-            &self.local_region,
-            client_geo.as_ref(),
-            |id| metrics.get_connection_count(id),
-        )?;
+        let backend =
+            self.select_connection(&backends, Some(client_ip), client_geo.as_ref(), None)?;
 
         // Create binding
         self.binding_repo
@@ -160,6 +389,347 @@ impl ProxyService {
         Some(backend)
     }
 
+    /// Select the best healthy backend without touching client bindings.
+    ///
+    /// Only backends returned by `get_healthy` are considered, optionally
+    /// restricted to a single app. Used by the DNS path, where the querying
+    /// resolver is not the client that will eventually connect, so the pick
+    /// isn't recorded in the selection metrics.
+    pub async fn select_healthy_backend(
+        &self,
+        app: Option<&str>,
+        client_geo: Option<&GeoInfo>,
+    ) -> Option<Backend> {
+        self.select_healthy_backend_where(app, client_geo, |_| true)
+            .await
+    }
+
+    /// Select a backend for a new connection without touching client bindings.
+    ///
+    /// Like [`ProxyService::select_healthy_backend`], but the connection also
+    /// counts against the per-app and per-region rate limits, and `None` is
+    /// returned when the selected backend's app or region is over its limit.
+    /// Picks that pass the limits are recorded in the selection metrics.
+    pub async fn select_connection_backend(
+        &self,
+        app: Option<&str>,
+        client_geo: Option<&GeoInfo>,
+    ) -> Option<Backend> {
+        let backends = self.healthy_backends_where(app, |_| true).await;
+        self.select_connection(&backends, None, client_geo, None)
+    }
+
+    /// Like [`ProxyService::select_healthy_backend`], but only considers
+    /// backends accepted by `predicate` (e.g. a given address family).
+    pub async fn select_healthy_backend_where<P>(
+        &self,
+        app: Option<&str>,
+        client_geo: Option<&GeoInfo>,
+        predicate: P,
+    ) -> Option<Backend>
+    where
+        P: Fn(&Backend) -> bool,
+    {
+        let backends = self.healthy_backends_where(app, predicate).await;
+        if !self.geo_routable() {
+            return None;
+        }
+        self.balance(&backends, None, client_geo, None, None)
+    }
+
+    /// All healthy backends, optionally restricted to one app, that are
+    /// accepted by `predicate`. No selection or metrics are involved.
+    pub async fn healthy_backends_where<P>(&self, app: Option<&str>, predicate: P) -> Vec<Backend>
+    where
+        P: Fn(&Backend) -> bool,
+    {
+        self.backend_repo()
+            .get_healthy()
+            .await
+            .into_iter()
+            .filter(|b| app.is_none_or(|app| b.app == app) && predicate(b))
+            .collect()
+    }
+
+    /// Explain where a client would be routed, without routing it.
+    ///
+    /// Runs the same geo lookup, binding check and load balancer scoring
+    /// as a real connection, but is read-only: bindings are neither created,
+    /// touched nor removed, and no metrics are recorded. With `app` set, only
+    /// that app's backends are considered and bindings are ignored, as on the
+    /// DNS path.
+    pub async fn explain_route(&self, client_ip: IpAddr, app: Option<&str>) -> RouteExplanation {
+        let client_geo = self.resolve_geo(client_ip);
+        let backends: Vec<Backend> = self
+            .backend_repo()
+            .get_all()
+            .await
+            .into_iter()
+            .filter(|b| app.is_none_or(|app| b.app == app))
+            .collect();
+        let binding = match app {
+            Some(_) => None,
+            None => self.binding_repo.get(&ClientKey::new(client_ip)).await,
+        };
+
+        let active =
+            LoadBalancer::active_counts(&backends, |id| self.metrics.get_connection_count(id));
+        let ctx = SelectionContext::new(&self.local_region)
+            .with_client_ip(Some(client_ip))
+            .with_client_geo(client_geo.as_ref())
+            .with_active(&active)
+            .with_strict_country(self.strict_country)
+            .with_zero_weight_fallback(self.zero_weight_fallback);
+
+        let candidates: Vec<RouteCandidate> = LoadBalancer::evaluate(&backends, &ctx)
+            .into_iter()
+            .map(|eval| RouteCandidate {
+                id: eval.backend.id.clone(),
+                app: eval.backend.app.clone(),
+                region: eval.backend.region.as_str().to_string(),
+                country: eval.backend.country.clone(),
+                active_connections: eval.active,
+                score: eval.verdict.ok(),
+                excluded: eval.verdict.err().map(|e| e.as_str()),
+            })
+            .collect();
+
+        let bound = binding
+            .and_then(|binding| backends.iter().find(|b| b.id == binding.backend_id))
+            .filter(|backend| self.admits(backend));
+        let (strategy, chosen, reason) = match bound {
+            Some(backend) => (
+                RouteStrategy::ExistingBinding,
+                Some(backend),
+                format!("client is bound to {}", backend.id),
+            ),
+            None => {
+                let chosen = LoadBalancer::select(&backends, ctx);
+                let eligible = candidates.iter().filter(|c| c.score.is_some()).count();
+                let reason = match chosen {
+                    Some(backend) => format!(
+                        "lowest score among {} eligible of {} backends: {}",
+                        eligible,
+                        candidates.len(),
+                        backend.id
+                    ),
+                    None if candidates.is_empty() => "no backends registered".to_string(),
+                    None => format!("all {} backends excluded", candidates.len()),
+                };
+                (RouteStrategy::GeoLoadScore, chosen, reason)
+            }
+        };
+
+        RouteExplanation {
+            client_ip,
+            app: app.map(str::to_string),
+            country: client_geo.as_ref().map(|geo| geo.country.clone()),
+            region: client_geo
+                .as_ref()
+                .map(|geo| geo.region.as_str().to_string()),
+            local_region: self.local_region.as_str().to_string(),
+            strategy,
+            candidates,
+            chosen: chosen.map(|b| b.id.clone()),
+            outcome: LoadBalancer::outcome(chosen, &self.local_region, client_geo.as_ref())
+                .as_str(),
+            reason,
+        }
+    }
+
+    /// Explain why resolution for `app` (or any app) found no backend.
+    ///
+    /// Call after a resolve or select came back empty. When backends exist
+    /// but every one is at its hard limit the connection is an overload
+    /// rejection: it is counted here, so adapters only have to pick the
+    /// matching signal for their protocol.
+    pub async fn unavailable(&self, app: Option<&str>) -> Unavailable {
+        let backends = self.healthy_backends_where(app, Backend::accepts_new_connections).await;
+        if backends.is_empty() || !backends.iter().all(|b| self.at_hard_limit(b)) {
+            return Unavailable::NoBackend;
+        }
+        self.metrics.record_overload_rejection();
+        self.metrics.record_connection_closed(CloseReason::Overload);
+        Unavailable::Overloaded
+    }
+
+    /// Whether the app and region of `backend` may take one more connection.
+    ///
+    /// Always true without a rate limiter.
+    fn within_rate_limits(&self, backend: &Backend) -> bool {
+        let Some(limiter) = &self.rate_limiter else {
+            return true;
+        };
+        match limiter.check_target(&backend.app, &backend.region) {
+            RateLimitResult::Allowed { .. } => true,
+            RateLimitResult::Limited {
+                dimension,
+                retry_after_ms,
+            } => {
+                tracing::warn!(
+                    "{} rate limit reached for backend {} (app {}, region {}), retry in {}ms",
+                    dimension,
+                    backend.id,
+                    backend.app,
+                    backend.region,
+                    retry_after_ms
+                );
+                false
+            }
+        }
+    }
+
+    /// Whether a new connection may go to `backend`.
+    fn admits(&self, backend: &Backend) -> bool {
+        backend.accepts_new_connections() && !self.at_hard_limit(backend)
+    }
+
+    /// Whether `backend` has reached its hard connection limit (0 = unlimited).
+    fn at_hard_limit(&self, backend: &Backend) -> bool {
+        backend.hard_limit != 0
+            && self.metrics.get_connection_count(&backend.id) as u64 >= backend.hard_limit as u64
+    }
+
+    /// Whether selection may go ahead, counting a missing geo resolver.
+    ///
+    /// False when no geo resolver is loaded and the policy fails closed.
+    fn geo_routable(&self) -> bool {
+        if self.geo_resolver.load().is_none() {
+            self.metrics.record_geo_unavailable();
+            if self.geo_unavailable == GeoUnavailablePolicy::FailClosed {
+                tracing::debug!("no geo resolver loaded, refusing to route");
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Select a backend for a new connection, subject to the rate limits.
+    ///
+    /// Records whether the pick came from the client's region, a fallback
+    /// region, or nowhere (no backend at all), and which backend was picked.
+    /// A pick rejected by the rate limits is not recorded. Backends tied on
+    /// score are drawn at random, by weight.
+    fn select_connection(
+        &self,
+        backends: &[Backend],
+        client_ip: Option<IpAddr>,
+        client_geo: Option<&GeoInfo>,
+        flow: Option<&FlowKey>,
+    ) -> Option<Backend> {
+        if !self.geo_routable() {
+            return None;
+        }
+
+        let mut rng = rand::thread_rng();
+        let selected = self.balance(backends, client_ip, client_geo, flow, Some(&mut rng));
+        if selected.as_ref().is_some_and(|backend| !self.within_rate_limits(backend)) {
+            return None;
+        }
+
+        let outcome = LoadBalancer::outcome(selected.as_ref(), &self.local_region, client_geo);
+        self.metrics.record_selection(outcome);
+        if let Some(backend) = &selected {
+            self.metrics.record_backend_selected(&backend.app, &backend.id);
+        }
+        if matches!(
+            outcome,
+            SelectionOutcome::RegionFallback | SelectionOutcome::AnyRegion
+        ) {
+            tracing::debug!(
+                "no backend in client region (geo: {:?}), served by {:?} ({})",
+                client_geo,
+                selected.as_ref().map(|b| &b.id),
+                outcome
+            );
+        }
+
+        selected
+    }
+
+    /// Gather selection inputs (connection counts) and run the load balancer.
+    ///
+    /// Hashes `flow` instead of scoring load when one is given. Ties are
+    /// broken with `rng` if given, else they go to the first backend by id.
+    /// Nothing is recorded.
+    fn balance(
+        &self,
+        backends: &[Backend],
+        client_ip: Option<IpAddr>,
+        client_geo: Option<&GeoInfo>,
+        flow: Option<&FlowKey>,
+        rng: Option<&mut dyn RngCore>,
+    ) -> Option<Backend> {
+        let active =
+            LoadBalancer::active_counts(backends, |id| self.metrics.get_connection_count(id));
+        let mut ctx = SelectionContext::new(&self.local_region)
+            .with_client_ip(client_ip)
+            .with_client_geo(client_geo)
+            .with_active(&active)
+            .with_strict_country(self.strict_country)
+            .with_zero_weight_fallback(self.zero_weight_fallback);
+        if let Some(rng) = rng {
+            ctx = ctx.with_rng(rng);
+        }
+        let selected = match flow {
+            Some(flow) => LoadBalancer::select_flow(backends, &ctx, flow),
+            None => LoadBalancer::select(backends, ctx),
+        };
+        selected.cloned()
+    }
+
+    /// Per-region backend counts and active connections, for every region.
+    ///
+    /// Backends with an unknown region code get an `unknown` row of their own.
+    pub async fn region_summary(&self) -> Vec<RegionSummary> {
+        let backends = self.backend_repo().get_all().await;
+        let healthy = self.backend_repo().get_healthy().await;
+        let unknown = backends
+            .iter()
+            .any(|b| b.region == RegionCode::Unknown)
+            .then_some(&RegionCode::Unknown);
+
+        RegionCode::ALL
+            .iter()
+            .chain(unknown)
+            .map(|region| {
+                let in_region: Vec<&Backend> = backends.iter().filter(|b| b.region == *region).collect();
+                RegionSummary {
+                    region: region.as_str().to_string(),
+                    total: in_region.len(),
+                    healthy: healthy.iter().filter(|b| b.region == *region).count(),
+                    maintenance: in_region.iter().filter(|b| b.maintenance).count(),
+                    active_connections: in_region
+                        .iter()
+                        .map(|b| self.metrics.get_connection_count(&b.id))
+                        .sum(),
+                }
+            })
+            .collect()
+    }
+
+    /// Per-app backend counts and active connections, for every known app.
+    pub async fn app_summary(&self) -> Vec<AppSummary> {
+        let apps = self.backend_repo().list_apps().await;
+        let backends = self.backend_repo().get_all().await;
+        let healthy = self.backend_repo().get_healthy().await;
+
+        apps.into_iter()
+            .map(|app| {
+                let of_app: Vec<&Backend> = backends.iter().filter(|b| b.app == app).collect();
+                AppSummary {
+                    total: of_app.len(),
+                    healthy: healthy.iter().filter(|b| b.app == app).count(),
+                    active_connections: of_app
+                        .iter()
+                        .map(|b| self.metrics.get_connection_count(&b.id))
+                        .sum(),
+                    app,
+                }
+            })
+            .collect()
+    }
+
     /// Clear the binding for a client.
     ///
     /// Useful when detecting VPN changes or other scenarios
@@ -170,18 +740,62 @@ impl ProxyService {
     }
 
     /// Resolve geographic information for an IP address.
+    ///
+    /// IPv4-mapped IPv6 addresses (as seen on dual-stack listeners) are
+    /// looked up as IPv4. Loopback addresses, `127.0.0.0/8` and `::1` alike,
+    /// carry no location and resolve to `None`; listeners locate loopback
+    /// clients through their loopback policy instead.
     pub fn resolve_geo(&self, ip: IpAddr) -> Option<GeoInfo> {
-        self.geo_resolver.as_ref().and_then(|g| g.resolve(ip))
+        let ip = ip.to_canonical();
+        if ip.is_loopback() {
+            return None;
+        }
+        self.geo_resolver
+            .load()
+            .as_ref()
+            .as_ref()
+            .and_then(|g| g.resolve(ip))
+    }
+
+    /// The geo resolver currently in use, if any.
+    pub fn geo_resolver(&self) -> Option<Arc<dyn GeoResolver>> {
+        Option::clone(&self.geo_resolver.load())
+    }
+
+    /// The backend repository currently in use.
+    fn backend_repo(&self) -> Arc<dyn BackendRepository> {
+        Arc::clone(&self.backend_repo.load())
+    }
+
+    /// Atomically replace the backend repository, e.g. to move from SQLite
+    /// to another store without rebuilding the service.
+    ///
+    /// Resolutions already in progress finish against the previous
+    /// repository. Bindings are kept: a bound backend missing from the new
+    /// repository is dropped on the client's next connection.
+    pub fn set_backend_repository(&self, repo: Arc<dyn BackendRepository>) {
+        self.backend_repo.store(Arc::new(repo));
+    }
+
+    /// Atomically replace the geo resolver used for new lookups.
+    ///
+    /// Lookups already in progress finish against the previous resolver.
+    /// Passing `None` disables geo resolution; clients are then routed
+    /// according to the [`GeoUnavailablePolicy`].
+    pub fn set_geo_resolver(&self, resolver: Option<Arc<dyn GeoResolver>>) {
+        self.geo_resolver.store(Arc::new(resolver));
     }
 
-    /// Record the start of a connection to a backend.
-    pub fn record_connection_start(&self, backend_id: &str) {
+    /// Record the start of a connection to a backend of `app`.
+    pub fn record_connection_start(&self, backend_id: &str, app: &str) {
+        self.metrics.set_backend_app(backend_id, app);
         self.metrics.increment_connections(backend_id);
     }
 
-    /// Record the end of a connection to a backend.
-    pub fn record_connection_end(&self, backend_id: &str) {
+    /// Record the end of a connection to a backend and why it closed.
+    pub fn record_connection_end(&self, backend_id: &str, reason: CloseReason) {
         self.metrics.decrement_connections(backend_id);
+        self.metrics.record_connection_closed(reason);
     }
 
     /// Record the round-trip time for connecting to a backend.
@@ -189,6 +803,26 @@ impl ProxyService {
         self.metrics.record_rtt(backend_id, rtt_ms);
     }
 
+    /// Record how long one phase of dialing a backend took.
+    pub fn record_connect_phase(&self, backend_id: &str, phase: ConnectPhase, ms: u64) {
+        self.metrics.record_connect_phase(backend_id, phase, ms);
+    }
+
+    /// Report the backend dials in progress and the addresses queued behind them.
+    pub fn set_connect_pressure(&self, in_flight: usize, queued: usize) {
+        self.metrics.set_connect_pressure(in_flight, queued);
+    }
+
+    /// Record a session aborted at the maximum session duration.
+    pub fn record_session_timeout(&self, backend_id: &str) {
+        self.metrics.record_session_timeout(backend_id);
+    }
+
+    /// Record the outcome of a DNS query for an app.
+    pub fn record_dns_query(&self, app: &str, outcome: DnsQueryOutcome) {
+        self.metrics.record_dns_query(app, outcome);
+    }
+
     /// Get the current connection count for a backend.
     #[allow(dead_code)]
     pub fn get_connection_count(&self, backend_id: &str) -> usize {
@@ -215,11 +849,152 @@ impl ProxyService {
     }
 }
 
+/// Errors returned by [`ProxyServiceBuilder::build`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ProxyServiceBuildError {
+    #[error("backend repository is required")]
+    MissingBackendRepository,
+    #[error("binding repository is required")]
+    MissingBindingRepository,
+    #[error("metrics store is required")]
+    MissingMetricsStore,
+}
+
+/// Builder for [`ProxyService`].
+///
+/// The backend repository, binding repository and metrics store are required.
+/// The geo resolver is optional and the local region defaults to
+/// `RegionCode::default()`.
+///
+/// # Example
+/// ```ignore
+/// let service = ProxyService::builder()
+///     .backend_repo(backend_repo)
+///     .binding_repo(binding_repo)
+///     .metrics(metrics)
+///     .local_region(RegionCode::SouthAmerica)
+///     .build()?;
+/// ```
+#[derive(Default)]
+pub struct ProxyServiceBuilder {
+    backend_repo: Option<Arc<dyn BackendRepository>>,
+    binding_repo: Option<Arc<dyn BindingRepository>>,
+    geo_resolver: Option<Arc<dyn GeoResolver>>,
+    metrics: Option<Arc<dyn MetricsStore>>,
+    local_region: RegionCode,
+    strict_country: bool,
+    zero_weight_fallback: bool,
+    geo_unavailable: GeoUnavailablePolicy,
+    strategy: LoadBalancingStrategy,
+    rate_limiter: Option<Arc<RateLimiter>>,
+}
+
+impl ProxyServiceBuilder {
+    /// Create an empty builder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the backend repository.
+    pub fn backend_repo(mut self, repo: Arc<dyn BackendRepository>) -> Self {
+        self.backend_repo = Some(repo);
+        self
+    }
+
+    /// Set the binding repository.
+    pub fn binding_repo(mut self, repo: Arc<dyn BindingRepository>) -> Self {
+        self.binding_repo = Some(repo);
+        self
+    }
+
+    /// Set the geo resolver.
+    pub fn geo_resolver(mut self, resolver: Arc<dyn GeoResolver>) -> Self {
+        self.geo_resolver = Some(resolver);
+        self
+    }
+
+    /// Set or clear the geo resolver.
+    pub fn maybe_geo_resolver(mut self, resolver: Option<Arc<dyn GeoResolver>>) -> Self {
+        self.geo_resolver = resolver;
+        self
+    }
+
+    /// Set the metrics store.
+    pub fn metrics(mut self, metrics: Arc<dyn MetricsStore>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Set the local region of this POP.
+    pub fn local_region(mut self, region: RegionCode) -> Self {
+        self.local_region = region;
+        self
+    }
+
+    /// Prefer same-country backends over everything else (off by default).
+    pub fn strict_country(mut self, strict_country: bool) -> Self {
+        self.strict_country = strict_country;
+        self
+    }
+
+    /// Route to zero-weight backends as a last resort (off by default).
+    pub fn zero_weight_fallback(mut self, zero_weight_fallback: bool) -> Self {
+        self.zero_weight_fallback = zero_weight_fallback;
+        self
+    }
+
+    /// Set how clients are routed while no geo resolver is loaded.
+    pub fn geo_unavailable(mut self, policy: GeoUnavailablePolicy) -> Self {
+        self.geo_unavailable = policy;
+        self
+    }
+
+    /// Set how new TCP connections are assigned (geo / load scoring by default).
+    pub fn strategy(mut self, strategy: LoadBalancingStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Enforce the per-app and per-region limits of `limiter` (off by default).
+    pub fn rate_limiter(mut self, limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = Some(limiter);
+        self
+    }
+
+    /// Build the proxy service, checking that all required ports are set.
+    pub fn build(self) -> Result<ProxyService, ProxyServiceBuildError> {
+        let backend_repo = self
+            .backend_repo
+            .ok_or(ProxyServiceBuildError::MissingBackendRepository)?;
+        let binding_repo = self
+            .binding_repo
+            .ok_or(ProxyServiceBuildError::MissingBindingRepository)?;
+        let metrics = self
+            .metrics
+            .ok_or(ProxyServiceBuildError::MissingMetricsStore)?;
+
+        let mut service = ProxyService::new(
+            backend_repo,
+            binding_repo,
+            self.geo_resolver,
+            metrics,
+            self.local_region,
+        )
+        .with_strict_country(self.strict_country)
+        .with_zero_weight_fallback(self.zero_weight_fallback)
+        .with_geo_unavailable(self.geo_unavailable)
+        .with_strategy(self.strategy);
+        service.rate_limiter = self.rate_limiter;
+        Ok(service)
+    }
+}
+
 #[cfg(test)]
 #[cfg_attr(coverage_nightly, coverage(off))]
 mod tests {
     use super::*;
     use crate::domain::entities::Backend;
+    use crate::infrastructure::{RateLimitConfig, TargetLimit};
     use async_trait::async_trait;
     use std::collections::HashMap;
     use std::sync::Mutex;
@@ -298,6 +1073,11 @@ mod tests {
     struct MockMetrics {
         counts: Mutex<HashMap<String, usize>>,
         rtts: Mutex<HashMap<String, u64>>,
+        selections: Mutex<HashMap<SelectionOutcome, u64>>,
+        selected: Mutex<HashMap<(String, String), u64>>,
+        closed: Mutex<HashMap<CloseReason, u64>>,
+        overload_rejections: Mutex<u64>,
+        geo_unavailable: Mutex<u64>,
     }
 
     impl MockMetrics {
@@ -305,6 +1085,11 @@ mod tests {
             Self {
                 counts: Mutex::new(HashMap::new()),
                 rtts: Mutex::new(HashMap::new()),
+                selections: Mutex::new(HashMap::new()),
+                selected: Mutex::new(HashMap::new()),
+                closed: Mutex::new(HashMap::new()),
+                overload_rejections: Mutex::new(0),
+                geo_unavailable: Mutex::new(0),
             }
         }
     }
@@ -339,6 +1124,56 @@ mod tests {
         fn get_last_rtt(&self, backend_id: &str) -> Option<u64> {
             self.rtts.lock().unwrap().get(backend_id).copied()
         }
+
+        fn record_selection(&self, outcome: SelectionOutcome) {
+            *self.selections.lock().unwrap().entry(outcome).or_insert(0) += 1;
+        }
+
+        fn get_selection_count(&self, outcome: SelectionOutcome) -> u64 {
+            *self.selections.lock().unwrap().get(&outcome).unwrap_or(&0)
+        }
+
+        fn record_backend_selected(&self, app: &str, backend_id: &str) {
+            *self
+                .selected
+                .lock()
+                .unwrap()
+                .entry((app.to_string(), backend_id.to_string()))
+                .or_insert(0) += 1;
+        }
+
+        fn get_backend_selected(&self, app: &str, backend_id: &str) -> u64 {
+            *self
+                .selected
+                .lock()
+                .unwrap()
+                .get(&(app.to_string(), backend_id.to_string()))
+                .unwrap_or(&0)
+        }
+
+        fn record_connection_closed(&self, reason: CloseReason) {
+            *self.closed.lock().unwrap().entry(reason).or_insert(0) += 1;
+        }
+
+        fn get_connection_closed(&self, reason: CloseReason) -> u64 {
+            *self.closed.lock().unwrap().get(&reason).unwrap_or(&0)
+        }
+
+        fn record_overload_rejection(&self) {
+            *self.overload_rejections.lock().unwrap() += 1;
+        }
+
+        fn get_overload_rejections(&self) -> u64 {
+            *self.overload_rejections.lock().unwrap()
+        }
+
+        fn record_geo_unavailable(&self) {
+            *self.geo_unavailable.lock().unwrap() += 1;
+        }
+
+        fn get_geo_unavailable(&self) -> u64 {
+            *self.geo_unavailable.lock().unwrap()
+        }
     }
 
     struct MockGeoResolver {
@@ -371,7 +1206,7 @@ mod tests {
         Backend {
             id: id.to_string(),
             app: "test".to_string(),
-            region: RegionCode::from_str(region),
+            region: RegionCode::from_code(region),
             country: country.to_string(),
             wg_ip: "10.0.0.1".to_string(),
             port: 8080,
@@ -379,6 +1214,9 @@ mod tests {
             weight: 1,
             soft_limit: 100,
             hard_limit: 200,
+            draining: false,
+            maintenance: false,
+            priority: 0,
         }
     }
 
@@ -583,79 +1421,945 @@ mod tests {
         assert_eq!(result.unwrap().id, "us-1");
     }
 
-    // ===== clear_binding Tests =====
-
-    #[tokio::test]
-    async fn test_clear_binding() {
-        let backends = vec![create_test_backend("br-1", "sa", "BR")];
-        let binding_repo = Arc::new(MockBindingRepo::new());
-        let client_ip: IpAddr = "192.168.1.1".parse().unwrap();
+    // ===== resolve_backend_excluding Tests =====
 
-        // Create binding
-        binding_repo
-            .set(
-                ClientKey::new(client_ip),
-                Binding::new("br-1".to_string()),
-            )
-            .await;
+    fn create_excluding_service(binding_repo: Arc<MockBindingRepo>) -> ProxyService {
+        let backends = vec![
+            create_test_backend("br-1", "sa", "BR"),
+            create_test_backend("ar-1", "sa", "AR"),
+            create_test_backend("us-1", "us", "US"),
+        ];
 
-        let service = ProxyService::new(
+        ProxyService::new(
             Arc::new(MockBackendRepo { backends }),
-            binding_repo.clone(),
+            binding_repo,
             None,
             Arc::new(MockMetrics::new()),
-            RegionCode::SouthAmerica,
-        );
-
-        // Clear binding
-        service.clear_binding(client_ip).await;
-
-        // Verify binding is gone
-        let binding = binding_repo.get(&ClientKey::new(client_ip)).await;
-        assert!(binding.is_none());
+            RegionCode::NorthAmerica,
+        )
     }
 
-    // ===== resolve_geo Tests =====
+    fn br_geo() -> Option<GeoInfo> {
+        Some(GeoInfo::new("BR".to_string(), RegionCode::SouthAmerica))
+    }
 
     #[tokio::test]
-    async fn test_resolve_geo_with_resolver() {
-        let backends = vec![create_test_backend("br-1", "sa", "BR")];
+    async fn test_resolve_backend_excluding_top_choice_returns_second_best() {
+        let service = create_excluding_service(Arc::new(MockBindingRepo::new()));
         let client_ip: IpAddr = "192.168.1.1".parse().unwrap();
 
-        let geo_resolver = MockGeoResolver::new().with_geo(
-            client_ip,
-            "FR",
+        let exclude: HashSet<String> = ["br-1".to_string()].into_iter().collect();
+        let result = service.resolve_backend_excluding(client_ip, br_geo(), &exclude).await;
+
+        // Same country excluded -> same region is next best
+        assert_eq!(result.unwrap().id, "ar-1");
+    }
+
+    #[tokio::test]
+    async fn test_resolve_backend_excluding_walks_down_the_ranking() {
+        let service = create_excluding_service(Arc::new(MockBindingRepo::new()));
+        let client_ip: IpAddr = "192.168.1.1".parse().unwrap();
+
+        let exclude: HashSet<String> =
+            ["br-1".to_string(), "ar-1".to_string()].into_iter().collect();
+        let result = service.resolve_backend_excluding(client_ip, br_geo(), &exclude).await;
+
+        assert_eq!(result.unwrap().id, "us-1");
+    }
+
+    #[tokio::test]
+    async fn test_resolve_backend_excluding_all_returns_none() {
+        let service = create_excluding_service(Arc::new(MockBindingRepo::new()));
+        let client_ip: IpAddr = "192.168.1.1".parse().unwrap();
+
+        let exclude: HashSet<String> = ["br-1", "ar-1", "us-1"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        let result = service.resolve_backend_excluding(client_ip, br_geo(), &exclude).await;
+
+        assert!(result.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_resolve_backend_excluding_ignores_excluded_binding() {
+        let binding_repo = Arc::new(MockBindingRepo::new());
+        let client_ip: IpAddr = "192.168.1.1".parse().unwrap();
+        binding_repo
+            .set(ClientKey::new(client_ip), Binding::new("br-1".to_string()))
+            .await;
+
+        let service = create_excluding_service(binding_repo.clone());
+
+        let exclude: HashSet<String> = ["br-1".to_string()].into_iter().collect();
+        let result = service.resolve_backend_excluding(client_ip, br_geo(), &exclude).await;
+
+        assert_eq!(result.unwrap().id, "ar-1");
+        // Binding moves to the retry target
+        let binding = binding_repo.get(&ClientKey::new(client_ip)).await.unwrap();
+        assert_eq!(binding.backend_id, "ar-1");
+    }
+
+    #[tokio::test]
+    async fn test_resolve_backend_excluding_keeps_non_excluded_binding() {
+        let binding_repo = Arc::new(MockBindingRepo::new());
+        let client_ip: IpAddr = "192.168.1.1".parse().unwrap();
+        binding_repo
+            .set(ClientKey::new(client_ip), Binding::new("us-1".to_string()))
+            .await;
+
+        let service = create_excluding_service(binding_repo);
+
+        let exclude: HashSet<String> = ["br-1".to_string()].into_iter().collect();
+        let result = service.resolve_backend_excluding(client_ip, br_geo(), &exclude).await;
+
+        assert_eq!(result.unwrap().id, "us-1");
+    }
+
+    #[tokio::test]
+    async fn test_resolve_backend_excluding_empty_set_matches_normal_selection() {
+        let service = create_excluding_service(Arc::new(MockBindingRepo::new()));
+        let client_ip: IpAddr = "192.168.1.1".parse().unwrap();
+
+        let result = service
+            .resolve_backend_excluding(client_ip, br_geo(), &HashSet::new())
+            .await;
+
+        assert_eq!(result.unwrap().id, "br-1");
+    }
+
+    // ===== select_healthy_backend Tests =====
+
+    #[tokio::test]
+    async fn test_select_healthy_backend_filters_by_app() {
+        let mut other = create_test_backend("br-other", "sa", "BR");
+        other.app = "other".to_string();
+        let backends = vec![other, create_test_backend("us-1", "us", "US")];
+
+        let service = ProxyService::new(
+            Arc::new(MockBackendRepo { backends }),
+            Arc::new(MockBindingRepo::new()),
+            None,
+            Arc::new(MockMetrics::new()),
+            RegionCode::SouthAmerica,
+        );
+
+        let result = service.select_healthy_backend(Some("test"), None).await;
+        assert_eq!(result.unwrap().id, "us-1");
+
+        let result = service.select_healthy_backend(Some("missing"), None).await;
+        assert!(result.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_select_healthy_backend_skips_unhealthy() {
+        let backends = vec![
+            create_unhealthy_backend("br-1", "sa", "BR"),
+            create_test_backend("us-1", "us", "US"),
+        ];
+
+        let service = ProxyService::new(
+            Arc::new(MockBackendRepo { backends }),
+            Arc::new(MockBindingRepo::new()),
+            None,
+            Arc::new(MockMetrics::new()),
+            RegionCode::SouthAmerica,
+        );
+
+        let geo = GeoInfo::new("BR".to_string(), RegionCode::SouthAmerica);
+        let result = service.select_healthy_backend(Some("test"), Some(&geo)).await;
+        assert_eq!(result.unwrap().id, "us-1");
+    }
+
+    #[tokio::test]
+    async fn test_select_healthy_backend_all_unhealthy() {
+        let backends = vec![create_unhealthy_backend("br-1", "sa", "BR")];
+
+        let service = ProxyService::new(
+            Arc::new(MockBackendRepo { backends }),
+            Arc::new(MockBindingRepo::new()),
+            None,
+            Arc::new(MockMetrics::new()),
+            RegionCode::SouthAmerica,
+        );
+
+        assert!(service.select_healthy_backend(Some("test"), None).await.is_none());
+        assert!(service.select_healthy_backend(None, None).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_select_healthy_backend_does_not_create_binding() {
+        let backends = vec![create_test_backend("br-1", "sa", "BR")];
+        let binding_repo = Arc::new(MockBindingRepo::new());
+
+        let service = ProxyService::new(
+            Arc::new(MockBackendRepo { backends }),
+            binding_repo.clone(),
+            None,
+            Arc::new(MockMetrics::new()),
+            RegionCode::SouthAmerica,
+        );
+
+        assert!(service.select_healthy_backend(None, None).await.is_some());
+        assert_eq!(binding_repo.count().await, 0);
+    }
+
+    // ===== Empty Region Fallback Tests =====
+
+    fn create_fallback_service(backends: Vec<Backend>, metrics: Arc<MockMetrics>) -> ProxyService {
+        ProxyService::new(
+            Arc::new(MockBackendRepo { backends }),
+            Arc::new(MockBindingRepo::new()),
+            None,
+            metrics,
+            RegionCode::SouthAmerica,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_empty_region_with_global_backends() {
+        // Client is in Europe, but Europe has no backends
+        let metrics = Arc::new(MockMetrics::new());
+        let service = create_fallback_service(
+            vec![
+                create_test_backend("ap-1", "ap", "JP"),
+                create_test_backend("us-1", "us", "US"),
+            ],
+            metrics.clone(),
+        );
+        let client_ip: IpAddr = "192.168.1.1".parse().unwrap();
+        let client_geo = Some(GeoInfo::new("DE".to_string(), RegionCode::Europe));
+
+        let result = service.resolve_backend_with_geo(client_ip, client_geo).await;
+
+        // us is first in Europe's fallback chain
+        assert_eq!(result.unwrap().id, "us-1");
+        assert_eq!(metrics.get_selection_count(SelectionOutcome::RegionFallback), 1);
+        assert_eq!(metrics.get_selection_count(SelectionOutcome::NoBackend), 0);
+    }
+
+    #[tokio::test]
+    async fn test_empty_region_and_chain_uses_any_region() {
+        let metrics = Arc::new(MockMetrics::new());
+        let service = create_fallback_service(
+            vec![create_test_backend("ap-1", "ap", "JP")],
+            metrics.clone(),
+        );
+        let client_geo = GeoInfo::new("DE".to_string(), RegionCode::Europe);
+
+        let result = service
+            .select_connection_backend(Some("test"), Some(&client_geo))
+            .await;
+
+        assert_eq!(result.unwrap().id, "ap-1");
+        assert_eq!(metrics.get_selection_count(SelectionOutcome::AnyRegion), 1);
+    }
+
+    #[tokio::test]
+    async fn test_dns_selection_is_not_recorded() {
+        let metrics = Arc::new(MockMetrics::new());
+        let service = create_fallback_service(
+            vec![create_test_backend("br-1", "sa", "BR")],
+            metrics.clone(),
+        );
+
+        assert!(service.select_healthy_backend(Some("test"), None).await.is_some());
+        assert!(service.select_healthy_backend(Some("missing"), None).await.is_none());
+
+        assert_eq!(metrics.get_backend_selected("test", "br-1"), 0);
+        for outcome in SelectionOutcome::ALL {
+            assert_eq!(metrics.get_selection_count(outcome), 0);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_pick_is_not_recorded() {
+        let metrics = Arc::new(MockMetrics::new());
+        let limiter = RateLimiter::new(RateLimitConfig {
+            per_app: app_limit(1),
+            ..Default::default()
+        });
+        let service = create_fallback_service(
+            vec![create_test_backend("br-1", "sa", "BR")],
+            metrics.clone(),
+        )
+        .with_rate_limiter(Arc::new(limiter));
+
+        assert!(service.select_connection_backend(Some("test"), None).await.is_some());
+        assert!(service.select_connection_backend(Some("test"), None).await.is_none());
+        let client_ip: IpAddr = "192.168.1.1".parse().unwrap();
+        assert!(service.resolve_backend(client_ip).await.is_none());
+
+        assert_eq!(metrics.get_backend_selected("test", "br-1"), 1);
+        assert_eq!(metrics.get_selection_count(SelectionOutcome::InRegion), 1);
+    }
+
+    #[tokio::test]
+    async fn test_no_backends_at_all_is_recorded_separately() {
+        let metrics = Arc::new(MockMetrics::new());
+        let service = create_fallback_service(
+            vec![create_unhealthy_backend("de-1", "eu", "DE")],
+            metrics.clone(),
+        );
+        let client_ip: IpAddr = "192.168.1.1".parse().unwrap();
+        let client_geo = Some(GeoInfo::new("DE".to_string(), RegionCode::Europe));
+
+        let result = service.resolve_backend_with_geo(client_ip, client_geo).await;
+
+        assert!(result.is_none());
+        assert_eq!(metrics.get_selection_count(SelectionOutcome::NoBackend), 1);
+        assert_eq!(metrics.get_selection_count(SelectionOutcome::RegionFallback), 0);
+    }
+
+    #[tokio::test]
+    async fn test_in_region_selection_is_recorded() {
+        let metrics = Arc::new(MockMetrics::new());
+        let service = create_fallback_service(
+            vec![create_test_backend("br-1", "sa", "BR")],
+            metrics.clone(),
+        );
+
+        let result = service.resolve_backend("192.168.1.1".parse().unwrap()).await;
+
+        assert_eq!(result.unwrap().id, "br-1");
+        assert_eq!(metrics.get_selection_count(SelectionOutcome::InRegion), 1);
+    }
+
+    #[tokio::test]
+    async fn test_selected_backend_is_counted() {
+        let metrics = Arc::new(MockMetrics::new());
+        let service = create_fallback_service(
+            vec![create_test_backend("br-1", "sa", "BR")],
+            metrics.clone(),
+        );
+
+        service.resolve_backend("192.168.1.1".parse().unwrap()).await.unwrap();
+        service.resolve_backend("192.168.1.2".parse().unwrap()).await.unwrap();
+        // A client reusing its binding is not a new pick
+        service.resolve_backend("192.168.1.1".parse().unwrap()).await.unwrap();
+
+        assert_eq!(metrics.get_backend_selected("test", "br-1"), 2);
+        assert_eq!(metrics.get_backend_selected("test", "other"), 0);
+    }
+
+    #[tokio::test]
+    async fn test_no_backend_is_not_counted_as_selected() {
+        let metrics = Arc::new(MockMetrics::new());
+        let service = create_fallback_service(
+            vec![create_unhealthy_backend("de-1", "eu", "DE")],
+            metrics.clone(),
+        );
+
+        assert!(service.resolve_backend("192.168.1.1".parse().unwrap()).await.is_none());
+        assert_eq!(metrics.get_backend_selected("test", "de-1"), 0);
+    }
+
+    // ===== Strict Country Tests =====
+
+    #[tokio::test]
+    async fn test_strict_country_prefers_same_country_over_same_region() {
+        // The client's country is served by one backend far past its soft
+        // limit, its region by an idle backend in a neighbouring country
+        let mut ar_1 = create_test_backend("ar-1", "sa", "AR");
+        ar_1.soft_limit = 1;
+        ar_1.hard_limit = 0;
+        let backends = vec![ar_1, create_test_backend("br-1", "sa", "BR")];
+        let client_geo = || Some(GeoInfo::new("AR".to_string(), RegionCode::SouthAmerica));
+        let client_ip: IpAddr = "192.168.1.1".parse().unwrap();
+
+        for strict_country in [false, true] {
+            let metrics = Arc::new(MockMetrics::new());
+            for _ in 0..150 {
+                metrics.increment_connections("ar-1");
+            }
+            let service = ProxyService::builder()
+                .backend_repo(Arc::new(MockBackendRepo { backends: backends.clone() }))
+                .binding_repo(Arc::new(MockBindingRepo::new()))
+                .metrics(metrics)
+                .local_region(RegionCode::SouthAmerica)
+                .strict_country(strict_country)
+                .build()
+                .unwrap();
+
+            let chosen = service.resolve_backend_with_geo(client_ip, client_geo()).await;
+            let expected = if strict_country { "ar-1" } else { "br-1" };
+            assert_eq!(chosen.unwrap().id, expected, "strict_country={}", strict_country);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_strict_country_explains_other_country_exclusion() {
+        let service = create_explain_service(
+            vec![
+                create_test_backend("ca-1", "us", "CA"),
+                create_test_backend("us-1", "us", "US"),
+            ],
+            Arc::new(MockBindingRepo::new()),
+            Arc::new(MockMetrics::new()),
+        )
+        .with_strict_country(true);
+
+        let explanation = service
+            .explain_route("203.0.113.7".parse().unwrap(), None)
+            .await;
+
+        assert_eq!(explanation.candidates[0].excluded, Some("other_country"));
+        assert_eq!(explanation.chosen.as_deref(), Some("us-1"));
+    }
+
+    // ===== explain_route Tests =====
+
+    fn create_explain_service(
+        backends: Vec<Backend>,
+        binding_repo: Arc<MockBindingRepo>,
+        metrics: Arc<MockMetrics>,
+    ) -> ProxyService {
+        let client_ip: IpAddr = "203.0.113.7".parse().unwrap();
+        let geo = MockGeoResolver::new().with_geo(client_ip, "US", RegionCode::NorthAmerica);
+        ProxyService::new(
+            Arc::new(MockBackendRepo { backends }),
+            binding_repo,
+            Some(Arc::new(geo)),
+            metrics,
+            RegionCode::SouthAmerica,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_explain_route_reports_geo_and_scores() {
+        let metrics = Arc::new(MockMetrics::new());
+        let binding_repo = Arc::new(MockBindingRepo::new());
+        let service = create_explain_service(
+            vec![
+                create_test_backend("br-1", "sa", "BR"),
+                create_test_backend("us-1", "us", "US"),
+            ],
+            binding_repo.clone(),
+            metrics.clone(),
+        );
+        let client_ip: IpAddr = "203.0.113.7".parse().unwrap();
+
+        let explanation = service.explain_route(client_ip, None).await;
+
+        assert_eq!(explanation.country.as_deref(), Some("US"));
+        assert_eq!(explanation.region.as_deref(), Some("us"));
+        assert_eq!(explanation.local_region, "sa");
+        assert_eq!(explanation.strategy, RouteStrategy::GeoLoadScore);
+        assert_eq!(explanation.chosen.as_deref(), Some("us-1"));
+        assert_eq!(explanation.outcome, "in_region");
+        assert_eq!(explanation.candidates.len(), 2);
+        assert!(explanation.candidates.iter().all(|c| c.excluded.is_none()));
+        assert!(explanation.candidates[1].score < explanation.candidates[0].score);
+
+        // Dry run: no binding created, no selection recorded
+        assert!(binding_repo.get(&ClientKey::new(client_ip)).await.is_none());
+        assert_eq!(metrics.get_selection_count(SelectionOutcome::InRegion), 0);
+    }
+
+    #[tokio::test]
+    async fn test_explain_route_hard_limit_exclusion() {
+        let metrics = Arc::new(MockMetrics::new());
+        for _ in 0..200 {
+            metrics.increment_connections("us-1");
+        }
+        let service = create_explain_service(
+            vec![
+                create_test_backend("br-1", "sa", "BR"),
+                create_test_backend("us-1", "us", "US"),
+                create_unhealthy_backend("us-2", "us", "US"),
+            ],
+            Arc::new(MockBindingRepo::new()),
+            metrics,
+        );
+
+        let explanation = service
+            .explain_route("203.0.113.7".parse().unwrap(), None)
+            .await;
+
+        let us_1 = &explanation.candidates[1];
+        assert_eq!(us_1.id, "us-1");
+        assert_eq!(us_1.excluded, Some("hard_limit"));
+        assert_eq!(us_1.active_connections, 200);
+        assert!(us_1.score.is_none());
+        assert_eq!(explanation.candidates[2].excluded, Some("unhealthy"));
+
+        // Client's country is full, so the local region takes it
+        assert_eq!(explanation.chosen.as_deref(), Some("br-1"));
+        assert_eq!(explanation.outcome, "region_fallback");
+        assert!(explanation.reason.contains("1 eligible of 3"));
+    }
+
+    #[tokio::test]
+    async fn test_explain_route_existing_binding() {
+        let binding_repo = Arc::new(MockBindingRepo::new());
+        let client_ip: IpAddr = "203.0.113.7".parse().unwrap();
+        binding_repo
+            .set(ClientKey::new(client_ip), Binding::new("br-1".to_string()))
+            .await;
+        let service = create_explain_service(
+            vec![
+                create_test_backend("br-1", "sa", "BR"),
+                create_test_backend("us-1", "us", "US"),
+            ],
+            binding_repo,
+            Arc::new(MockMetrics::new()),
+        );
+
+        let explanation = service.explain_route(client_ip, None).await;
+        assert_eq!(explanation.strategy, RouteStrategy::ExistingBinding);
+        assert_eq!(explanation.chosen.as_deref(), Some("br-1"));
+        assert_eq!(explanation.reason, "client is bound to br-1");
+
+        // Per-app lookups ignore bindings, as on the DNS path
+        let explanation = service.explain_route(client_ip, Some("test")).await;
+        assert_eq!(explanation.strategy, RouteStrategy::GeoLoadScore);
+        assert_eq!(explanation.chosen.as_deref(), Some("us-1"));
+    }
+
+    #[tokio::test]
+    async fn test_explain_route_no_backends() {
+        let service = create_explain_service(
+            vec![create_unhealthy_backend("us-1", "us", "US")],
+            Arc::new(MockBindingRepo::new()),
+            Arc::new(MockMetrics::new()),
+        );
+        let client_ip: IpAddr = "198.51.100.1".parse().unwrap();
+
+        let explanation = service.explain_route(client_ip, None).await;
+        assert!(explanation.country.is_none());
+        assert!(explanation.chosen.is_none());
+        assert_eq!(explanation.outcome, "no_backend");
+        assert_eq!(explanation.reason, "all 1 backends excluded");
+
+        let explanation = service.explain_route(client_ip, Some("missing")).await;
+        assert!(explanation.candidates.is_empty());
+        assert_eq!(explanation.reason, "no backends registered");
+    }
+
+    // ===== Rate Limit Tests =====
+
+    fn create_rate_limited_service(config: RateLimitConfig) -> ProxyService {
+        let mut shop = create_test_backend("shop-1", "eu", "DE");
+        shop.app = "shop".to_string();
+        let mut blog = create_test_backend("blog-1", "eu", "DE");
+        blog.app = "blog".to_string();
+        let mut docs = create_test_backend("docs-1", "us", "US");
+        docs.app = "docs".to_string();
+
+        ProxyService::builder()
+            .backend_repo(Arc::new(MockBackendRepo {
+                backends: vec![shop, blog, docs],
+            }))
+            .binding_repo(Arc::new(MockBindingRepo::new()))
+            .metrics(Arc::new(MockMetrics::new()))
+            .local_region(RegionCode::Europe)
+            .rate_limiter(Arc::new(RateLimiter::new(config)))
+            .build()
+            .unwrap()
+    }
+
+    fn app_limit(burst_size: u64) -> Option<TargetLimit> {
+        Some(TargetLimit {
+            max_requests: 1,
+            window: Duration::from_secs(60),
+            burst_size,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_app_rate_limit_rejects_saturated_app_only() {
+        let service = create_rate_limited_service(RateLimitConfig {
+            per_app: app_limit(3),
+            ..Default::default()
+        });
+
+        for _ in 0..3 {
+            let backend = service.select_connection_backend(Some("shop"), None).await;
+            assert_eq!(backend.unwrap().id, "shop-1");
+        }
+
+        // The app is saturated: new connections to it are rejected...
+        assert!(service.select_connection_backend(Some("shop"), None).await.is_none());
+        assert!(service.select_connection_backend(Some("shop"), None).await.is_none());
+
+        // ...while other apps proceed
+        for _ in 0..3 {
+            let backend = service.select_connection_backend(Some("blog"), None).await;
+            assert_eq!(backend.unwrap().id, "blog-1");
+        }
+
+        // Selection that is not a connection (e.g. DNS) is never limited
+        assert!(service.select_healthy_backend(Some("shop"), None).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_region_rate_limit_rejects_saturated_region() {
+        let service = create_rate_limited_service(RateLimitConfig {
+            per_region: app_limit(2),
+            ..Default::default()
+        });
+
+        assert!(service.select_connection_backend(Some("shop"), None).await.is_some());
+        assert!(service.select_connection_backend(Some("blog"), None).await.is_some());
+        // Both apps live in eu, which is now saturated
+        assert!(service.select_connection_backend(Some("shop"), None).await.is_none());
+        assert!(service.select_connection_backend(Some("blog"), None).await.is_none());
+        // us still has room
+        assert!(service.select_connection_backend(Some("docs"), None).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_applies_to_bound_clients() {
+        let mut backend = create_test_backend("shop-1", "eu", "DE");
+        backend.app = "shop".to_string();
+        let binding_repo = Arc::new(MockBindingRepo::new());
+        let limiter = RateLimiter::new(RateLimitConfig {
+            per_app: app_limit(2),
+            ..Default::default()
+        });
+        let service = ProxyService::new(
+            Arc::new(MockBackendRepo {
+                backends: vec![backend],
+            }),
+            binding_repo.clone(),
+            None,
+            Arc::new(MockMetrics::new()),
+            RegionCode::Europe,
+        )
+        .with_rate_limiter(Arc::new(limiter));
+
+        let client_ip: IpAddr = "192.168.1.1".parse().unwrap();
+        let other_ip: IpAddr = "192.168.1.2".parse().unwrap();
+
+        // New client, then the same client through its binding
+        assert!(service.resolve_backend(client_ip).await.is_some());
+        assert!(service.resolve_backend_with_geo(client_ip, None).await.is_some());
+
+        assert!(service.resolve_backend(client_ip).await.is_none());
+        assert!(service.resolve_backend_with_geo(other_ip, None).await.is_none());
+
+        // A limited connection keeps the existing binding and creates none
+        let client_key = ClientKey::new(client_ip);
+        assert!(binding_repo.get(&client_key).await.is_some());
+        assert!(binding_repo.get(&ClientKey::new(other_ip)).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_no_rate_limiter_never_limits() {
+        let mut backend = create_test_backend("shop-1", "eu", "DE");
+        backend.app = "shop".to_string();
+        let service = ProxyService::new(
+            Arc::new(MockBackendRepo {
+                backends: vec![backend],
+            }),
+            Arc::new(MockBindingRepo::new()),
+            None,
+            Arc::new(MockMetrics::new()),
+            RegionCode::Europe,
+        );
+
+        for _ in 0..50 {
+            assert!(service.select_connection_backend(Some("shop"), None).await.is_some());
+        }
+    }
+
+    // ===== ProxyServiceBuilder Tests =====
+
+    #[tokio::test]
+    async fn test_builder_with_required_ports() {
+        let backends = vec![create_test_backend("us-1", "us", "US")];
+
+        let service = ProxyService::builder()
+            .backend_repo(Arc::new(MockBackendRepo { backends }))
+            .binding_repo(Arc::new(MockBindingRepo::new()))
+            .metrics(Arc::new(MockMetrics::new()))
+            .build()
+            .unwrap();
+
+        assert_eq!(service.local_region(), &RegionCode::default());
+        assert!(service.resolve_geo("8.8.8.8".parse().unwrap()).is_none());
+
+        let result = service.resolve_backend("192.168.1.1".parse().unwrap()).await;
+        assert_eq!(result.unwrap().id, "us-1");
+    }
+
+    #[tokio::test]
+    async fn test_builder_with_optional_components() {
+        let backends = vec![
+            create_test_backend("br-1", "sa", "BR"),
+            create_test_backend("us-1", "us", "US"),
+        ];
+        let client_ip: IpAddr = "189.1.1.1".parse().unwrap();
+        let geo = MockGeoResolver::new().with_geo(client_ip, "BR", RegionCode::SouthAmerica);
+
+        let service = ProxyService::builder()
+            .backend_repo(Arc::new(MockBackendRepo { backends }))
+            .binding_repo(Arc::new(MockBindingRepo::new()))
+            .metrics(Arc::new(MockMetrics::new()))
+            .geo_resolver(Arc::new(geo))
+            .local_region(RegionCode::Europe)
+            .build()
+            .unwrap();
+
+        assert_eq!(service.local_region(), &RegionCode::Europe);
+        assert!(service.resolve_geo(client_ip).is_some());
+
+        let result = service.resolve_backend(client_ip).await;
+        assert_eq!(result.unwrap().id, "br-1");
+    }
+
+    #[test]
+    fn test_builder_maybe_geo_resolver_none() {
+        let service = ProxyServiceBuilder::new()
+            .backend_repo(Arc::new(MockBackendRepo { backends: vec![] }))
+            .binding_repo(Arc::new(MockBindingRepo::new()))
+            .metrics(Arc::new(MockMetrics::new()))
+            .geo_resolver(Arc::new(MockGeoResolver::new().with_geo(
+                "1.1.1.1".parse().unwrap(),
+                "AU",
+                RegionCode::AsiaPacific,
+            )))
+            .maybe_geo_resolver(None)
+            .build()
+            .unwrap();
+
+        assert!(service.resolve_geo("1.1.1.1".parse().unwrap()).is_none());
+    }
+
+    #[test]
+    fn test_builder_missing_backend_repo() {
+        let result = ProxyService::builder()
+            .binding_repo(Arc::new(MockBindingRepo::new()))
+            .metrics(Arc::new(MockMetrics::new()))
+            .build();
+
+        assert_eq!(result.err(), Some(ProxyServiceBuildError::MissingBackendRepository));
+    }
+
+    #[test]
+    fn test_builder_missing_binding_repo() {
+        let result = ProxyService::builder()
+            .backend_repo(Arc::new(MockBackendRepo { backends: vec![] }))
+            .metrics(Arc::new(MockMetrics::new()))
+            .build();
+
+        assert_eq!(result.err(), Some(ProxyServiceBuildError::MissingBindingRepository));
+    }
+
+    #[test]
+    fn test_builder_missing_metrics() {
+        let result = ProxyService::builder()
+            .backend_repo(Arc::new(MockBackendRepo { backends: vec![] }))
+            .binding_repo(Arc::new(MockBindingRepo::new()))
+            .build();
+
+        assert_eq!(result.err(), Some(ProxyServiceBuildError::MissingMetricsStore));
+    }
+
+    #[test]
+    fn test_builder_error_display() {
+        assert_eq!(
+            ProxyServiceBuildError::MissingBackendRepository.to_string(),
+            "backend repository is required"
+        );
+        assert_eq!(
+            ProxyServiceBuildError::MissingMetricsStore.to_string(),
+            "metrics store is required"
+        );
+    }
+
+    // ===== clear_binding Tests =====
+
+    #[tokio::test]
+    async fn test_clear_binding() {
+        let backends = vec![create_test_backend("br-1", "sa", "BR")];
+        let binding_repo = Arc::new(MockBindingRepo::new());
+        let client_ip: IpAddr = "192.168.1.1".parse().unwrap();
+
+        // Create binding
+        binding_repo
+            .set(
+                ClientKey::new(client_ip),
+                Binding::new("br-1".to_string()),
+            )
+            .await;
+
+        let service = ProxyService::new(
+            Arc::new(MockBackendRepo { backends }),
+            binding_repo.clone(),
+            None,
+            Arc::new(MockMetrics::new()),
+            RegionCode::SouthAmerica,
+        );
+
+        // Clear binding
+        service.clear_binding(client_ip).await;
+
+        // Verify binding is gone
+        let binding = binding_repo.get(&ClientKey::new(client_ip)).await;
+        assert!(binding.is_none());
+    }
+
+    // ===== resolve_geo Tests =====
+
+    #[tokio::test]
+    async fn test_resolve_geo_with_resolver() {
+        let backends = vec![create_test_backend("br-1", "sa", "BR")];
+        let client_ip: IpAddr = "192.168.1.1".parse().unwrap();
+
+        let geo_resolver = MockGeoResolver::new().with_geo(
+            client_ip,
+            "FR",
             RegionCode::Europe,
         );
 
         let service = ProxyService::new(
-            Arc::new(MockBackendRepo { backends }),
+            Arc::new(MockBackendRepo { backends }),
+            Arc::new(MockBindingRepo::new()),
+            Some(Arc::new(geo_resolver)),
+            Arc::new(MockMetrics::new()),
+            RegionCode::SouthAmerica,
+        );
+
+        let geo = service.resolve_geo(client_ip);
+        assert!(geo.is_some());
+        assert_eq!(geo.unwrap().country, "FR");
+    }
+
+    #[tokio::test]
+    async fn test_resolve_geo_without_resolver() {
+        let backends = vec![create_test_backend("br-1", "sa", "BR")];
+
+        let service = ProxyService::new(
+            Arc::new(MockBackendRepo { backends }),
+            Arc::new(MockBindingRepo::new()),
+            None, // No geo resolver
+            Arc::new(MockMetrics::new()),
+            RegionCode::SouthAmerica,
+        );
+
+        let client_ip: IpAddr = "192.168.1.1".parse().unwrap();
+        let geo = service.resolve_geo(client_ip);
+        assert!(geo.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_resolve_geo_loopback_is_unlocated_for_both_families() {
+        let mut geo_resolver = MockGeoResolver::new();
+        for ip in ["127.0.0.1", "::1", "::ffff:127.0.0.1"] {
+            geo_resolver = geo_resolver.with_geo(ip.parse().unwrap(), "FR", RegionCode::Europe);
+        }
+        let service = ProxyService::new(
+            Arc::new(MockBackendRepo { backends: vec![] }),
             Arc::new(MockBindingRepo::new()),
             Some(Arc::new(geo_resolver)),
             Arc::new(MockMetrics::new()),
             RegionCode::SouthAmerica,
         );
 
-        let geo = service.resolve_geo(client_ip);
-        assert!(geo.is_some());
-        assert_eq!(geo.unwrap().country, "FR");
+        assert!(service.resolve_geo("127.0.0.1".parse().unwrap()).is_none());
+        assert!(service.resolve_geo("::1".parse().unwrap()).is_none());
+        assert!(service.resolve_geo("::ffff:127.0.0.1".parse().unwrap()).is_none());
     }
 
     #[tokio::test]
-    async fn test_resolve_geo_without_resolver() {
-        let backends = vec![create_test_backend("br-1", "sa", "BR")];
+    async fn test_resolve_geo_looks_up_ipv4_mapped_as_ipv4() {
+        let client_ip: IpAddr = "192.168.1.1".parse().unwrap();
+        let service = ProxyService::new(
+            Arc::new(MockBackendRepo { backends: vec![] }),
+            Arc::new(MockBindingRepo::new()),
+            Some(Arc::new(MockGeoResolver::new().with_geo(client_ip, "FR", RegionCode::Europe))),
+            Arc::new(MockMetrics::new()),
+            RegionCode::SouthAmerica,
+        );
 
+        let mapped: IpAddr = "::ffff:192.168.1.1".parse().unwrap();
+        assert_eq!(service.resolve_geo(mapped).unwrap().country, "FR");
+    }
+
+    #[tokio::test]
+    async fn test_set_geo_resolver_swaps_lookups() {
+        let client_ip: IpAddr = "192.168.1.1".parse().unwrap();
         let service = ProxyService::new(
-            Arc::new(MockBackendRepo { backends }),
+            Arc::new(MockBackendRepo { backends: vec![] }),
             Arc::new(MockBindingRepo::new()),
-            None, // No geo resolver
+            Some(Arc::new(MockGeoResolver::new().with_geo(client_ip, "FR", RegionCode::Europe))),
             Arc::new(MockMetrics::new()),
             RegionCode::SouthAmerica,
         );
+        assert_eq!(service.resolve_geo(client_ip).unwrap().country, "FR");
+
+        service.set_geo_resolver(Some(Arc::new(
+            MockGeoResolver::new().with_geo(client_ip, "BR", RegionCode::SouthAmerica),
+        )));
+        assert_eq!(service.resolve_geo(client_ip).unwrap().country, "BR");
+
+        service.set_geo_resolver(None);
+        assert!(service.resolve_geo(client_ip).is_none());
+    }
 
+    #[tokio::test]
+    async fn test_set_geo_resolver_affects_routing() {
         let client_ip: IpAddr = "192.168.1.1".parse().unwrap();
-        let geo = service.resolve_geo(client_ip);
-        assert!(geo.is_none());
+        let backends = vec![
+            create_test_backend("eu-1", "eu", "DE"),
+            create_test_backend("br-1", "sa", "BR"),
+        ];
+        let service = ProxyService::new(
+            Arc::new(MockBackendRepo { backends }),
+            Arc::new(MockBindingRepo::new()),
+            Some(Arc::new(MockGeoResolver::new().with_geo(client_ip, "DE", RegionCode::Europe))),
+            Arc::new(MockMetrics::new()),
+            RegionCode::NorthAmerica,
+        );
+        assert_eq!(service.resolve_backend(client_ip).await.unwrap().id, "eu-1");
+
+        // New database places the client in Brazil; drop the old binding
+        service.set_geo_resolver(Some(Arc::new(
+            MockGeoResolver::new().with_geo(client_ip, "BR", RegionCode::SouthAmerica),
+        )));
+        service.clear_binding(client_ip).await;
+        assert_eq!(service.resolve_backend(client_ip).await.unwrap().id, "br-1");
+    }
+
+    #[test]
+    fn test_set_geo_resolver_concurrent_lookups() {
+        let client_ip: IpAddr = "192.168.1.1".parse().unwrap();
+        let service = Arc::new(ProxyService::new(
+            Arc::new(MockBackendRepo { backends: vec![] }),
+            Arc::new(MockBindingRepo::new()),
+            Some(Arc::new(MockGeoResolver::new().with_geo(client_ip, "FR", RegionCode::Europe))),
+            Arc::new(MockMetrics::new()),
+            RegionCode::SouthAmerica,
+        ));
+
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let service = service.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..1000 {
+                        // Every lookup sees one whole resolver, never a torn state
+                        let country = service.resolve_geo(client_ip).unwrap().country;
+                        assert!(country == "FR" || country == "BR");
+                    }
+                })
+            })
+            .collect();
+
+        for i in 0..100 {
+            let (country, region) = if i % 2 == 0 {
+                ("BR", RegionCode::SouthAmerica)
+            } else {
+                ("FR", RegionCode::Europe)
+            };
+            service.set_geo_resolver(Some(Arc::new(
+                MockGeoResolver::new().with_geo(client_ip, country, region),
+            )));
+        }
+
+        for r in readers {
+            r.join().unwrap();
+        }
     }
 
     // ===== Metrics Recording Tests =====
@@ -673,12 +2377,39 @@ mod tests {
             RegionCode::SouthAmerica,
         );
 
-        service.record_connection_start("br-1");
-        service.record_connection_start("br-1");
+        service.record_connection_start("br-1", "myapp");
+        service.record_connection_start("br-1", "myapp");
 
         assert_eq!(metrics.get_connection_count("br-1"), 2);
     }
 
+    #[tokio::test]
+    async fn test_record_connection_start_aggregates_by_app() {
+        use crate::adapters::outbound::PrometheusMetricsStore;
+
+        let backends = vec![
+            create_test_backend("br-1", "sa", "BR"),
+            create_test_backend("br-2", "sa", "BR"),
+        ];
+        let metrics = Arc::new(PrometheusMetricsStore::new("sa".to_string()));
+
+        let service = ProxyService::new(
+            Arc::new(MockBackendRepo { backends }),
+            Arc::new(MockBindingRepo::new()),
+            None,
+            metrics.clone(),
+            RegionCode::SouthAmerica,
+        );
+
+        service.record_connection_start("br-1", "myapp");
+        service.record_connection_start("br-2", "myapp");
+        service.record_connection_end("br-1", CloseReason::ClientClosed);
+
+        let app = metrics.get_app_metrics("myapp").unwrap();
+        assert_eq!(app.total_connections.load(std::sync::atomic::Ordering::Relaxed), 2);
+        assert_eq!(app.active_connections.load(std::sync::atomic::Ordering::Relaxed), 1);
+    }
+
     #[tokio::test]
     async fn test_record_connection_end() {
         let backends = vec![create_test_backend("br-1", "sa", "BR")];
@@ -692,11 +2423,13 @@ mod tests {
             RegionCode::SouthAmerica,
         );
 
-        service.record_connection_start("br-1");
-        service.record_connection_start("br-1");
-        service.record_connection_end("br-1");
+        service.record_connection_start("br-1", "myapp");
+        service.record_connection_start("br-1", "myapp");
+        service.record_connection_end("br-1", CloseReason::BackendClosed);
 
         assert_eq!(metrics.get_connection_count("br-1"), 1);
+        assert_eq!(metrics.get_connection_closed(CloseReason::BackendClosed), 1);
+        assert_eq!(metrics.get_connection_closed(CloseReason::ClientClosed), 0);
     }
 
     #[tokio::test]
@@ -756,6 +2489,230 @@ mod tests {
         assert_eq!(*service.local_region(), RegionCode::Europe);
     }
 
+    // ===== region_summary Tests =====
+
+    #[tokio::test]
+    async fn test_region_summary_counts_per_region() {
+        let backends = vec![
+            create_test_backend("br-1", "sa", "BR"),
+            create_unhealthy_backend("br-2", "sa", "BR"),
+            create_test_backend("de-1", "eu", "DE"),
+            create_test_backend("de-2", "eu", "DE"),
+            create_test_backend("de-3", "eu", "DE"),
+        ];
+        let metrics = Arc::new(MockMetrics::new());
+        metrics.increment_connections("br-1");
+        metrics.increment_connections("br-2");
+        metrics.increment_connections("de-1");
+        metrics.increment_connections("de-3");
+        metrics.increment_connections("de-3");
+        metrics.increment_connections("elsewhere");
+
+        let service = ProxyService::new(
+            Arc::new(MockBackendRepo { backends }),
+            Arc::new(MockBindingRepo::new()),
+            None,
+            metrics,
+            RegionCode::Europe,
+        );
+
+        let summary = service.region_summary().await;
+        let row = |region: &str| summary.iter().find(|s| s.region == region).unwrap().clone();
+        assert_eq!(summary.len(), 4);
+        assert_eq!(
+            row("sa"),
+            RegionSummary {
+                region: "sa".to_string(),
+                total: 2,
+                healthy: 1,
+                maintenance: 0,
+                active_connections: 2
+            }
+        );
+        assert_eq!(
+            row("eu"),
+            RegionSummary {
+                region: "eu".to_string(),
+                total: 3,
+                healthy: 3,
+                maintenance: 0,
+                active_connections: 3
+            }
+        );
+        // Regions without backends are still listed
+        assert_eq!((row("us").total, row("ap").active_connections), (0, 0));
+    }
+
+    fn zero_weight_service(binding_repo: Arc<MockBindingRepo>, fallback: bool) -> ProxyService {
+        let backends = ["br-1", "br-2"]
+            .iter()
+            .map(|id| {
+                let mut backend = create_test_backend(id, "sa", "BR");
+                backend.weight = 0;
+                backend
+            })
+            .collect();
+        ProxyService::new(
+            Arc::new(MockBackendRepo { backends }),
+            binding_repo,
+            None,
+            Arc::new(MockMetrics::new()),
+            RegionCode::SouthAmerica,
+        )
+        .with_zero_weight_fallback(fallback)
+    }
+
+    #[tokio::test]
+    async fn test_zero_weight_only_serves_existing_bindings() {
+        let binding_repo = Arc::new(MockBindingRepo::new());
+        let bound: IpAddr = "192.168.1.1".parse().unwrap();
+        binding_repo.set(ClientKey::new(bound), Binding::new("br-2".to_string())).await;
+        let service = zero_weight_service(binding_repo, false);
+
+        assert_eq!(service.resolve_backend(bound).await.unwrap().id, "br-2");
+        assert_eq!(
+            service.resolve_backend_with_geo(bound, None).await.unwrap().id,
+            "br-2"
+        );
+
+        let new_client: IpAddr = "192.168.1.2".parse().unwrap();
+        assert!(service.resolve_backend(new_client).await.is_none());
+        assert!(service.select_healthy_backend(None, None).await.is_none());
+
+        let explanation = service.explain_route(new_client, None).await;
+        assert_eq!(explanation.chosen, None);
+        assert!(explanation.candidates.iter().all(|c| c.excluded == Some("zero_weight")));
+    }
+
+    #[tokio::test]
+    async fn test_zero_weight_fallback_routes_new_clients() {
+        let service = zero_weight_service(Arc::new(MockBindingRepo::new()), true);
+
+        // Both backends tie, so a new client may land on either
+        let client_ip: IpAddr = "192.168.1.2".parse().unwrap();
+        let backend = service.resolve_backend(client_ip).await.unwrap();
+        assert!(["br-1", "br-2"].contains(&backend.id.as_str()));
+        assert_eq!(service.select_healthy_backend(None, None).await.unwrap().id, "br-1");
+    }
+
+    #[tokio::test]
+    async fn test_connection_ties_are_drawn_at_random() {
+        let service = create_fallback_service(
+            vec![
+                create_test_backend("br-1", "sa", "BR"),
+                create_test_backend("br-2", "sa", "BR"),
+            ],
+            Arc::new(MockMetrics::new()),
+        );
+
+        let mut picked = HashSet::new();
+        for i in 1..=64u8 {
+            let client_ip: IpAddr = format!("192.168.1.{}", i).parse().unwrap();
+            picked.insert(service.resolve_backend(client_ip).await.unwrap().id);
+        }
+        assert_eq!(picked.len(), 2);
+
+        // DNS answers stay stable: ties go to the first backend by id
+        for _ in 0..8 {
+            assert_eq!(service.select_healthy_backend(None, None).await.unwrap().id, "br-1");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_app_summary_counts_per_app() {
+        let app_backend = |id: &str, app: &str, healthy: bool| {
+            let mut backend = create_test_backend(id, "sa", "BR");
+            backend.app = app.to_string();
+            backend.healthy = healthy;
+            backend
+        };
+        let repo = Arc::new(MockBackendRepo {
+            backends: vec![
+                app_backend("web-1", "web", true),
+                app_backend("api-1", "api", true),
+                app_backend("web-2", "web", false),
+                app_backend("db-1", "db", false),
+                app_backend("api-2", "api", true),
+            ],
+        });
+        assert_eq!(repo.list_apps().await, vec!["api", "db", "web"]);
+
+        let metrics = Arc::new(MockMetrics::new());
+        metrics.increment_connections("web-1");
+        metrics.increment_connections("web-2");
+        metrics.increment_connections("api-2");
+        let service = ProxyService::new(
+            repo,
+            Arc::new(MockBindingRepo::new()),
+            None,
+            metrics,
+            RegionCode::SouthAmerica,
+        );
+
+        let summary = service.app_summary().await;
+        let row = |app: &str, total, healthy, active_connections| AppSummary {
+            app: app.to_string(),
+            total,
+            healthy,
+            active_connections,
+        };
+        assert_eq!(
+            summary,
+            vec![row("api", 2, 2, 1), row("db", 1, 0, 0), row("web", 2, 1, 2)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_apps_empty() {
+        let repo = MockBackendRepo { backends: vec![] };
+        assert!(repo.list_apps().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_region_summary_counts_maintenance_apart_from_unhealthy() {
+        let mut maintenance = create_test_backend("br-2", "sa", "BR");
+        maintenance.maintenance = true;
+        let backends = vec![create_test_backend("br-1", "sa", "BR"), maintenance];
+
+        let service = ProxyService::new(
+            Arc::new(MockBackendRepo { backends }),
+            Arc::new(MockBindingRepo::new()),
+            None,
+            Arc::new(MockMetrics::new()),
+            RegionCode::Europe,
+        );
+
+        let summary = service.region_summary().await;
+        let sa = summary.iter().find(|s| s.region == "sa").unwrap();
+        assert_eq!((sa.total, sa.healthy, sa.maintenance), (2, 2, 1));
+
+        // Never selected, even though it isn't unhealthy
+        for i in 1..=10 {
+            let client = format!("10.1.0.{}", i).parse().unwrap();
+            assert_eq!(service.resolve_backend(client).await.unwrap().id, "br-1");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_region_summary_reports_unknown_region() {
+        let backends = vec![
+            create_test_backend("br-1", "sa", "BR"),
+            create_test_backend("x-1", "mars", "XX"),
+        ];
+        let service = ProxyService::new(
+            Arc::new(MockBackendRepo { backends }),
+            Arc::new(MockBindingRepo::new()),
+            None,
+            Arc::new(MockMetrics::new()),
+            RegionCode::Europe,
+        );
+
+        let summary = service.region_summary().await;
+        assert_eq!(summary.len(), 5);
+        let unknown = summary.iter().find(|s| s.region == "unknown").unwrap();
+        assert_eq!(unknown.total, 1);
+    }
+
     // ===== Binding with non-existent backend =====
 
     #[tokio::test]
@@ -1081,4 +3038,244 @@ mod tests {
         // Should return None because backend is at hard_limit
         assert!(result.is_none());
     }
+
+    // ===== Overload Tests =====
+
+    /// Backend with a hard limit of 1 and the one connection it allows.
+    fn full_backend(id: &str, metrics: &MockMetrics) -> Backend {
+        let mut backend = create_test_backend(id, "sa", "BR");
+        backend.hard_limit = 1;
+        metrics.increment_connections(id);
+        backend
+    }
+
+    fn overload_service(
+        backends: Vec<Backend>,
+        binding_repo: Arc<MockBindingRepo>,
+        metrics: Arc<MockMetrics>,
+    ) -> ProxyService {
+        ProxyService::new(
+            Arc::new(MockBackendRepo { backends }),
+            binding_repo,
+            None,
+            metrics,
+            RegionCode::SouthAmerica,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_unavailable_overloaded_when_all_at_hard_limit() {
+        let metrics = Arc::new(MockMetrics::new());
+        let backends = vec![full_backend("br-1", &metrics), full_backend("br-2", &metrics)];
+        let service = overload_service(backends, Arc::new(MockBindingRepo::new()), metrics.clone());
+
+        let client_ip: IpAddr = "192.168.1.1".parse().unwrap();
+        assert!(service.resolve_backend(client_ip).await.is_none());
+        assert_eq!(service.unavailable(None).await, Unavailable::Overloaded);
+        assert_eq!(service.unavailable(Some("test")).await, Unavailable::Overloaded);
+        assert_eq!(metrics.get_overload_rejections(), 2);
+        assert_eq!(metrics.get_connection_closed(CloseReason::Overload), 2);
+    }
+
+    #[tokio::test]
+    async fn test_no_geo_resolver_fails_open_to_local_region() {
+        let metrics = Arc::new(MockMetrics::new());
+        let backends = vec![
+            create_test_backend("us-1", "us", "US"),
+            create_test_backend("br-1", "sa", "BR"),
+        ];
+        let service = overload_service(backends, Arc::new(MockBindingRepo::new()), metrics.clone());
+
+        let client_ip: IpAddr = "203.0.113.7".parse().unwrap();
+        let backend = service.resolve_backend(client_ip).await.unwrap();
+        assert_eq!(backend.region, RegionCode::SouthAmerica);
+        assert!(service.select_healthy_backend(None, None).await.is_some());
+        assert_eq!(metrics.get_geo_unavailable(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_no_geo_resolver_fails_closed() {
+        let metrics = Arc::new(MockMetrics::new());
+        let backends = vec![create_test_backend("br-1", "sa", "BR")];
+        let service = overload_service(backends, Arc::new(MockBindingRepo::new()), metrics.clone())
+            .with_geo_unavailable(GeoUnavailablePolicy::FailClosed);
+
+        let client_ip: IpAddr = "203.0.113.7".parse().unwrap();
+        assert!(service.resolve_backend(client_ip).await.is_none());
+        assert!(service.resolve_backend_with_geo(client_ip, None).await.is_none());
+        assert!(service.select_healthy_backend(None, None).await.is_none());
+        assert_eq!(metrics.get_geo_unavailable(), 3);
+        assert_eq!(metrics.get_backend_selected("test", "br-1"), 0);
+    }
+
+    #[tokio::test]
+    async fn test_geo_resolver_present_ignores_fail_closed() {
+        let metrics = Arc::new(MockMetrics::new());
+        let service = ProxyService::new(
+            Arc::new(MockBackendRepo { backends: vec![create_test_backend("br-1", "sa", "BR")] }),
+            Arc::new(MockBindingRepo::new()),
+            Some(Arc::new(MockGeoResolver::new())),
+            metrics.clone(),
+            RegionCode::SouthAmerica,
+        )
+        .with_geo_unavailable(GeoUnavailablePolicy::FailClosed);
+
+        let client_ip: IpAddr = "203.0.113.7".parse().unwrap();
+        assert!(service.resolve_backend(client_ip).await.is_some());
+        assert_eq!(metrics.get_geo_unavailable(), 0);
+
+        // Dropping the resolver at runtime starts refusing
+        service.set_geo_resolver(None);
+        assert!(service.select_healthy_backend(None, None).await.is_none());
+        assert_eq!(metrics.get_geo_unavailable(), 1);
+    }
+
+    #[test]
+    fn test_geo_unavailable_policy_from_str() {
+        assert_eq!("open".parse::<GeoUnavailablePolicy>().unwrap(), GeoUnavailablePolicy::FailOpen);
+        assert_eq!(" Fail-Closed ".parse::<GeoUnavailablePolicy>().unwrap(), GeoUnavailablePolicy::FailClosed);
+        assert_eq!("closed".parse::<GeoUnavailablePolicy>().unwrap(), GeoUnavailablePolicy::FailClosed);
+        assert!("maybe".parse::<GeoUnavailablePolicy>().is_err());
+        assert_eq!(GeoUnavailablePolicy::default(), GeoUnavailablePolicy::FailOpen);
+    }
+
+    #[tokio::test]
+    async fn test_set_backend_repository_swaps_selection_source() {
+        let binding_repo = Arc::new(MockBindingRepo::new());
+        let service = ProxyService::new(
+            Arc::new(MockBackendRepo {
+                backends: vec![create_test_backend("old-1", "sa", "BR")],
+            }),
+            binding_repo.clone(),
+            None,
+            Arc::new(MockMetrics::new()),
+            RegionCode::SouthAmerica,
+        );
+        let client_ip: IpAddr = "192.168.1.1".parse().unwrap();
+
+        assert_eq!(service.resolve_backend(client_ip).await.unwrap().id, "old-1");
+        assert_eq!(service.select_healthy_backend(None, None).await.unwrap().id, "old-1");
+
+        service.set_backend_repository(Arc::new(MockBackendRepo {
+            backends: vec![create_test_backend("new-1", "sa", "BR")],
+        }));
+
+        // The binding to a backend the new repository doesn't know is dropped
+        assert_eq!(service.resolve_backend(client_ip).await.unwrap().id, "new-1");
+        let binding = binding_repo.get(&ClientKey::new(client_ip)).await.unwrap();
+        assert_eq!(binding.backend_id, "new-1");
+        assert_eq!(service.select_healthy_backend(None, None).await.unwrap().id, "new-1");
+    }
+
+    #[test]
+    fn test_load_balancing_strategy_from_str() {
+        assert_eq!("geo".parse::<LoadBalancingStrategy>().unwrap(), LoadBalancingStrategy::GeoLoadScore);
+        assert_eq!(" Flow-Hash ".parse::<LoadBalancingStrategy>().unwrap(), LoadBalancingStrategy::FlowHash);
+        assert_eq!("flow".parse::<LoadBalancingStrategy>().unwrap(), LoadBalancingStrategy::FlowHash);
+        assert!("random".parse::<LoadBalancingStrategy>().is_err());
+        assert_eq!(LoadBalancingStrategy::default(), LoadBalancingStrategy::GeoLoadScore);
+    }
+
+    // ===== resolve_flow_backend Tests =====
+
+    fn create_flow_service(
+        binding_repo: Arc<MockBindingRepo>,
+        strategy: LoadBalancingStrategy,
+    ) -> ProxyService {
+        let backends = (0..4)
+            .map(|i| create_test_backend(&format!("br-{}", i), "sa", "BR"))
+            .chain([create_test_backend("us-1", "us", "US")])
+            .collect();
+        ProxyService::builder()
+            .backend_repo(Arc::new(MockBackendRepo { backends }))
+            .binding_repo(binding_repo)
+            .metrics(Arc::new(MockMetrics::new()))
+            .local_region(RegionCode::SouthAmerica)
+            .strategy(strategy)
+            .build()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_resolve_flow_backend_hashes_without_bindings() {
+        let binding_repo = Arc::new(MockBindingRepo::new());
+        let service = create_flow_service(binding_repo.clone(), LoadBalancingStrategy::FlowHash);
+        let client: SocketAddr = "203.0.113.7:40000".parse().unwrap();
+
+        let first = service.resolve_flow_backend(client, Some(8080), br_geo()).await.unwrap();
+        assert!(first.id.starts_with("br-"));
+        for _ in 0..5 {
+            let again = service.resolve_flow_backend(client, Some(8080), br_geo()).await;
+            assert_eq!(again.unwrap().id, first.id);
+        }
+        assert!(binding_repo.bindings.lock().unwrap().is_empty());
+
+        // Other ports of the same client spread over the same-country backends
+        let mut seen = HashSet::new();
+        for port in 40001..40100 {
+            let client = SocketAddr::new(client.ip(), port);
+            let backend = service.resolve_flow_backend(client, Some(8080), br_geo()).await;
+            seen.insert(backend.unwrap().id);
+        }
+        assert_eq!(seen.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_flow_backend_geo_strategy_binds_client() {
+        let binding_repo = Arc::new(MockBindingRepo::new());
+        let service = create_flow_service(binding_repo.clone(), LoadBalancingStrategy::GeoLoadScore);
+        let client: SocketAddr = "203.0.113.7:40000".parse().unwrap();
+
+        let backend = service.resolve_flow_backend(client, None, br_geo()).await.unwrap();
+        let binding = binding_repo.get(&ClientKey::new(client.ip())).await.unwrap();
+        assert_eq!(binding.backend_id, backend.id);
+    }
+
+    #[tokio::test]
+    async fn test_unavailable_no_backend() {
+        let metrics = Arc::new(MockMetrics::new());
+        let backends = vec![create_unhealthy_backend("br-1", "sa", "BR")];
+        let service = overload_service(backends, Arc::new(MockBindingRepo::new()), metrics.clone());
+
+        assert_eq!(service.unavailable(None).await, Unavailable::NoBackend);
+        assert_eq!(service.unavailable(Some("other")).await, Unavailable::NoBackend);
+        assert_eq!(metrics.get_overload_rejections(), 0);
+        assert_eq!(metrics.get_connection_closed(CloseReason::Overload), 0);
+    }
+
+    #[tokio::test]
+    async fn test_unavailable_only_counts_accepting_backends_of_the_app() {
+        let metrics = Arc::new(MockMetrics::new());
+        let mut other = create_test_backend("other-1", "sa", "BR");
+        other.app = "other".to_string();
+        let mut draining = create_test_backend("br-2", "sa", "BR");
+        draining.draining = true;
+        let backends = vec![full_backend("br-1", &metrics), other, draining];
+        let service = overload_service(backends, Arc::new(MockBindingRepo::new()), metrics.clone());
+
+        // The draining backend's spare capacity doesn't count
+        assert_eq!(service.unavailable(Some("test")).await, Unavailable::Overloaded);
+        // Across all apps there is room on "other-1"
+        assert_eq!(service.unavailable(None).await, Unavailable::NoBackend);
+        assert_eq!(metrics.get_overload_rejections(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_binding_to_backend_at_hard_limit_is_rebalanced() {
+        let metrics = Arc::new(MockMetrics::new());
+        let binding_repo = Arc::new(MockBindingRepo::new());
+        let backends = vec![full_backend("br-1", &metrics), create_test_backend("br-2", "sa", "BR")];
+        let service = overload_service(backends, binding_repo.clone(), metrics);
+
+        let client_ip: IpAddr = "192.168.1.1".parse().unwrap();
+        let client_key = ClientKey::new(client_ip);
+        binding_repo.set(client_key.clone(), Binding::new("br-1".to_string())).await;
+
+        assert_eq!(service.resolve_backend(client_ip).await.unwrap().id, "br-2");
+        assert_eq!(binding_repo.get(&client_key).await.unwrap().backend_id, "br-2");
+
+        binding_repo.set(client_key.clone(), Binding::new("br-1".to_string())).await;
+        let backend = service.resolve_backend_with_geo(client_ip, None).await.unwrap();
+        assert_eq!(backend.id, "br-2");
+    }
 }
@@ -1,15 +1,55 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path};
 
-#[derive(Debug, Deserialize, Clone)]
+/// Fields holding credentials, hidden by [`Config::redacted`].
+const SECRET_FIELDS: &[&str] = &["consul_token", "replication_cluster_secret"];
+
+/// Placeholder shown instead of a secret value.
+pub const REDACTED: &str = "[redacted]";
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Config {
     // Core proxy settings
     pub listen_addr: String,
+    pub extra_listen_addrs: Vec<String>,
     pub db_path: String,
     pub region: String,
+    pub strict_country: bool,
+    pub zero_weight_fallback: bool,
+    pub reuse_port: bool,
+    pub listen_backlog: u32,
+    pub tcp_fast_open: bool,
+    pub socket_recv_buffer: usize,
+    pub socket_send_buffer: usize,
+    pub backend_resolve_ttl_secs: u64,
+    pub connect_pressure_threshold: usize,
+    pub connect_pressure_duration_secs: u64,
     pub db_reload_secs: u64,
+    pub backends_file: Option<String>,
+    pub consul_addr: String,
+    pub consul_service: Option<String>,
+    pub consul_token: Option<String>,
+    pub consul_datacenter: Option<String>,
     pub geoip_path: Option<String>,
+    pub geoip_reload_secs: u64,
+    pub public_ip_url: String,
+    pub public_ip: Option<String>,
+    pub loopback_geo: String,
+    pub geo_unavailable: String,
+    pub lb_strategy: String,
     pub binding_ttl_secs: u64,
     pub binding_gc_interval_secs: u64,
+    pub max_session_secs: u64,
+    pub drain_timeout_secs: u64,
+    pub connect_proxy: bool,
+    pub connect_hosts: Vec<String>,
+    pub connect_expose_backend: bool,
+    pub http_listen_addr: Option<String>,
+    pub udp_listen_addr: Option<String>,
+    pub udp_session_timeout_secs: u64,
+    pub metrics_snapshot_path: Option<String>,
+    pub metrics_snapshot_secs: u64,
+    pub access_log: bool,
     pub debug: bool,
 
     // TLS settings
@@ -17,16 +57,25 @@ pub struct Config {
     pub tls_cert_path: Option<String>,
     pub tls_key_path: Option<String>,
     pub tls_listen_addr: Option<String>,
+    pub tls_handshake_timeout_ms: u64,
+    pub tls_enable_early_data: bool,
 
     // Auto-Discovery API settings
     pub api_enabled: bool,
     pub api_listen_addr: String,
+    pub api_socket_mode: u32,
     pub heartbeat_ttl_secs: u64,
 
     // DNS server settings
     pub dns_enabled: bool,
     pub dns_listen_addr: String,
-    pub dns_domain: String,
+    pub dns_domains: Vec<String>,
+    pub dns_servfail_on_unhealthy: bool,
+    pub dns_nat64_prefix: Option<String>,
+    pub dns_weighted_rotation: bool,
+    pub dns_wildcard_apps: Vec<String>,
+    pub dns_max_udp_payload: u16,
+    pub dns_nxdomain_when_draining: bool,
 
     // Built-in replication settings
     pub replication_enabled: bool,
@@ -34,59 +83,272 @@ pub struct Config {
     pub replication_gossip_addr: String,
     pub replication_transport_addr: String,
     pub replication_bootstrap_peers: Vec<String>,
+    /// Database replication writes to; defaults to `db_path`, the one the
+    /// SQLite backend repository routes from
     pub replication_db_path: String,
     pub replication_cluster_name: String,
+    /// Shared secret peers authenticate each other's transport certificates with
+    pub replication_cluster_secret: Option<String>,
+    /// Accept any peer certificate on the replication transport (development only)
+    pub replication_insecure_transport: bool,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             listen_addr: "0.0.0.0:8080".to_string(),
+            extra_listen_addrs: Vec::new(),
             db_path: "routing.db".to_string(),
             region: "sa".to_string(),
+            strict_country: false,
+            zero_weight_fallback: false,
+            reuse_port: false,
+            listen_backlog: 0,
+            tcp_fast_open: false,
+            socket_recv_buffer: 0,
+            socket_send_buffer: 0,
+            backend_resolve_ttl_secs: 5,
+            connect_pressure_threshold: 0,
+            connect_pressure_duration_secs: 10,
             db_reload_secs: 5,
+            backends_file: None,
+            consul_addr: "http://127.0.0.1:8500".to_string(),
+            consul_service: None,
+            consul_token: None,
+            consul_datacenter: None,
             geoip_path: None,
+            geoip_reload_secs: 60,
+            public_ip_url: "https://checkip.amazonaws.com/".to_string(),
+            public_ip: None,
+            loopback_geo: "public-ip".to_string(),
+            geo_unavailable: "open".to_string(),
+            lb_strategy: "geo".to_string(),
             binding_ttl_secs: 600,
             binding_gc_interval_secs: 60,
+            max_session_secs: 0,
+            drain_timeout_secs: 30,
+            connect_proxy: false,
+            connect_hosts: Vec::new(),
+            connect_expose_backend: false,
+            http_listen_addr: None,
+            udp_listen_addr: None,
+            udp_session_timeout_secs: 60,
+            metrics_snapshot_path: None,
+            metrics_snapshot_secs: 60,
+            access_log: false,
             debug: false,
             tls_enabled: false,
             tls_cert_path: None,
             tls_key_path: None,
             tls_listen_addr: None,
+            tls_handshake_timeout_ms: 10000,
+            tls_enable_early_data: false,
             api_enabled: false,
             api_listen_addr: "0.0.0.0:8081".to_string(),
+            api_socket_mode: 0o660,
             heartbeat_ttl_secs: 60,
             dns_enabled: false,
             dns_listen_addr: "0.0.0.0:5353".to_string(),
-            dns_domain: "internal".to_string(),
+            dns_domains: vec!["internal".to_string()],
+            dns_servfail_on_unhealthy: false,
+            dns_nat64_prefix: None,
+            dns_weighted_rotation: false,
+            dns_wildcard_apps: Vec::new(),
+            dns_max_udp_payload: 1232,
+            dns_nxdomain_when_draining: false,
             replication_enabled: false,
             replication_node_id: None,
             replication_gossip_addr: "0.0.0.0:4001".to_string(),
             replication_transport_addr: "0.0.0.0:4002".to_string(),
             replication_bootstrap_peers: Vec::new(),
-            replication_db_path: "state.db".to_string(),
+            replication_db_path: "routing.db".to_string(),
             replication_cluster_name: "edgeproxy".to_string(),
+            replication_cluster_secret: None,
+            replication_insecure_transport: false,
+        }
+    }
+}
+
+impl Config {
+    /// The configuration as JSON, with secrets replaced by [`REDACTED`].
+    ///
+    /// Unset secrets stay `null`, so it's still visible whether one was
+    /// configured. TLS key and certificate are only referenced by path;
+    /// their contents are never part of the config.
+    pub fn redacted(&self) -> serde_json::Value {
+        let mut value = serde_json::to_value(self).expect("config serializes to JSON");
+        for field in SECRET_FIELDS {
+            if let Some(secret) = value.get_mut(*field).filter(|v| !v.is_null()) {
+                *secret = serde_json::Value::from(REDACTED);
+            }
+        }
+        value
+    }
+
+    /// Why replicated backend changes would not reach routing, if they wouldn't.
+    ///
+    /// Replication applies backend changes to `replication_db_path`, and the
+    /// SQLite backend repository reloads `db_path` when told about them. Both
+    /// must be the same database, and the repository must be the one routing
+    /// reads, or the proxy keeps routing on backends replication never
+    /// updates. Always `None` with replication disabled.
+    pub fn replication_routing_issue(&self) -> Option<String> {
+        if !self.replication_enabled {
+            return None;
         }
+        let source = match (&self.backends_file, &self.consul_service) {
+            (Some(path), _) => Some(format!("the backends file {}", path)),
+            (None, Some(service)) => Some(format!("Consul service {}", service)),
+            (None, None) => None,
+        };
+        if let Some(source) = source {
+            return Some(format!(
+                "backends are read from {}, so replicated backend changes are not routed",
+                source
+            ));
+        }
+        if !same_database(&self.db_path, &self.replication_db_path) {
+            return Some(format!(
+                "replication writes {} but routing reads {}, so replicated backend changes \
+                 are not routed (unset EDGEPROXY_REPLICATION_DB_PATH to share {})",
+                self.replication_db_path, self.db_path, self.db_path
+            ));
+        }
+        None
+    }
+}
+
+/// Whether two database paths name the same file.
+///
+/// Existing files are compared by canonical path, so symlinks and relative
+/// paths resolve; otherwise the paths are compared ignoring `.` components.
+fn same_database(a: &str, b: &str) -> bool {
+    if let (Ok(a), Ok(b)) = (std::fs::canonicalize(a), std::fs::canonicalize(b)) {
+        return a == b;
     }
+    fn components(path: &str) -> Vec<Component<'_>> {
+        Path::new(path)
+            .components()
+            .filter(|c| *c != Component::CurDir)
+            .collect()
+    }
+    components(a) == components(b)
 }
 
 pub fn load_config() -> anyhow::Result<Config> {
     let listen_addr = std::env::var("EDGEPROXY_LISTEN_ADDR")
         .unwrap_or_else(|_| "0.0.0.0:8080".to_string());
 
+    // Additional TCP listen addresses (e.g. a second port or IPv6)
+    let extra_listen_addrs = std::env::var("EDGEPROXY_EXTRA_LISTEN_ADDRS")
+        .map(|v| {
+            v.split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+
     let db_path = std::env::var("EDGEPROXY_DB_PATH")
         .unwrap_or_else(|_| "routing.db".to_string());
 
     let region = std::env::var("EDGEPROXY_REGION")
         .unwrap_or_else(|_| "sa".to_string());
 
+    // Route to the client's country whenever a backend there is available
+    let strict_country = std::env::var("EDGEPROXY_STRICT_COUNTRY")
+        .map(|v| v == "1" || v.to_lowercase() == "true")
+        .unwrap_or(false);
+
+    // Route to zero-weight (drained) backends when no other backend is eligible
+    let zero_weight_fallback = std::env::var("EDGEPROXY_ZERO_WEIGHT_FALLBACK")
+        .map(|v| v == "1" || v.to_lowercase() == "true")
+        .unwrap_or(false);
+
+    let reuse_port = std::env::var("EDGEPROXY_REUSE_PORT")
+        .map(|v| v == "1" || v.to_lowercase() == "true")
+        .unwrap_or(false);
+
+    // Pending connection queue for TCP listeners (0 = default of 1024)
+    let listen_backlog = std::env::var("EDGEPROXY_LISTEN_BACKLOG")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+
+    // TCP Fast Open on backend connections (Linux only)
+    let tcp_fast_open = std::env::var("EDGEPROXY_TCP_FAST_OPEN")
+        .map(|v| v == "1" || v.to_lowercase() == "true")
+        .unwrap_or(false);
+
+    // SO_RCVBUF/SO_SNDBUF for client and backend TCP sockets (0 = OS default)
+    let socket_recv_buffer = std::env::var("EDGEPROXY_SOCKET_RECV_BUFFER")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+
+    let socket_send_buffer = std::env::var("EDGEPROXY_SOCKET_SEND_BUFFER")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+
+    // How long resolved hostname backends are cached (0 = resolve every connect)
+    let backend_resolve_ttl_secs = std::env::var("EDGEPROXY_BACKEND_RESOLVE_TTL_SECS")
+        .unwrap_or_else(|_| "5".to_string())
+        .parse()
+        .unwrap_or(5);
+
+    // Warn when more backend dials than this stay in progress (0 = never)
+    let connect_pressure_threshold = std::env::var("EDGEPROXY_CONNECT_PRESSURE_THRESHOLD")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+
+    let connect_pressure_duration_secs = std::env::var("EDGEPROXY_CONNECT_PRESSURE_DURATION_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(10);
+
     let db_reload_secs = std::env::var("EDGEPROXY_DB_RELOAD_SECS")
         .unwrap_or_else(|_| "5".to_string())
         .parse()
         .unwrap_or(5);
 
+    // Static JSON backend list used instead of the SQLite database (optional)
+    let backends_file = std::env::var("EDGEPROXY_BACKENDS_FILE").ok();
+
+    // Consul service whose instances are the backends (optional)
+    let consul_addr = std::env::var("EDGEPROXY_CONSUL_ADDR")
+        .unwrap_or_else(|_| "http://127.0.0.1:8500".to_string());
+    let consul_service = std::env::var("EDGEPROXY_CONSUL_SERVICE").ok();
+    let consul_token = std::env::var("EDGEPROXY_CONSUL_TOKEN").ok();
+    let consul_datacenter = std::env::var("EDGEPROXY_CONSUL_DATACENTER").ok();
+
     let geoip_path = std::env::var("EDGEPROXY_GEOIP_PATH").ok();
 
+    // How often the GeoIP file is checked for changes (0 disables reloading)
+    let geoip_reload_secs = std::env::var("EDGEPROXY_GEOIP_RELOAD_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(60);
+
+    // "What is my IP" endpoint used to locate loopback clients
+    let public_ip_url = std::env::var("EDGEPROXY_PUBLIC_IP_URL")
+        .unwrap_or_else(|_| "https://checkip.amazonaws.com/".to_string());
+
+    // Fixed public IP, skipping the lookup (for hosts without outbound access)
+    let public_ip = std::env::var("EDGEPROXY_PUBLIC_IP").ok();
+
+    // Where loopback clients (127.0.0.1, ::1) are located: public-ip or none
+    let loopback_geo =
+        std::env::var("EDGEPROXY_LOOPBACK_GEO").unwrap_or_else(|_| "public-ip".to_string());
+
+    // Routing while no GeoIP database is loaded: open (route anyway) or closed (refuse)
+    let geo_unavailable =
+        std::env::var("EDGEPROXY_GEO_UNAVAILABLE").unwrap_or_else(|_| "open".to_string());
+
+    // How TCP connections pick a backend: geo (scored, bound) or flow-hash
+    let lb_strategy = std::env::var("EDGEPROXY_LB_STRATEGY").unwrap_or_else(|_| "geo".to_string());
+
     let binding_ttl_secs = std::env::var("EDGEPROXY_BINDING_TTL_SECS")
         .unwrap_or_else(|_| "600".to_string())
         .parse()
@@ -97,6 +359,59 @@ pub fn load_config() -> anyhow::Result<Config> {
         .parse()
         .unwrap_or(60);
 
+    // 0 disables the session duration cap
+    let max_session_secs = std::env::var("EDGEPROXY_MAX_SESSION_SECS")
+        .unwrap_or_else(|_| "0".to_string())
+        .parse()
+        .unwrap_or(0);
+
+    // How long shutdown waits for connections before force-closing them
+    let drain_timeout_secs = std::env::var("EDGEPROXY_DRAIN_TIMEOUT_SECS")
+        .unwrap_or_else(|_| "30".to_string())
+        .parse()
+        .unwrap_or(30);
+
+    // HTTP CONNECT proxy mode on the TCP listener
+    let connect_proxy = std::env::var("EDGEPROXY_CONNECT_PROXY")
+        .map(|v| v == "1" || v.to_lowercase() == "true")
+        .unwrap_or(false);
+
+    // host=app pairs, checked before the app.<dns domain> convention
+    let connect_hosts = std::env::var("EDGEPROXY_CONNECT_HOSTS")
+        .map(|v| v.split(',').map(|s| s.trim().to_string()).collect())
+        .unwrap_or_default();
+
+    // Debug: name the chosen backend in the CONNECT 200 reply
+    let connect_expose_backend = std::env::var("EDGEPROXY_CONNECT_EXPOSE_BACKEND")
+        .map(|v| v == "1" || v.to_lowercase() == "true")
+        .unwrap_or(false);
+
+    // Plaintext HTTP listener routing each request by Host (disabled when unset)
+    let http_listen_addr = std::env::var("EDGEPROXY_HTTP_LISTEN_ADDR").ok();
+
+    // UDP listener relaying datagrams to backends (disabled when unset)
+    let udp_listen_addr = std::env::var("EDGEPROXY_UDP_LISTEN_ADDR").ok();
+
+    // How long a UDP session may go without a datagram before it ends
+    let udp_session_timeout_secs = std::env::var("EDGEPROXY_UDP_SESSION_TIMEOUT_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|&secs| secs > 0)
+        .unwrap_or(60);
+
+    // Periodic on-disk snapshot of cumulative metrics counters (disabled when unset)
+    let metrics_snapshot_path = std::env::var("EDGEPROXY_METRICS_SNAPSHOT_PATH").ok();
+
+    let metrics_snapshot_secs = std::env::var("EDGEPROXY_METRICS_SNAPSHOT_SECS")
+        .unwrap_or_else(|_| "60".to_string())
+        .parse()
+        .unwrap_or(60);
+
+    // One info line per finished session, tagged with its connection id
+    let access_log = std::env::var("EDGEPROXY_ACCESS_LOG")
+        .map(|v| v == "1" || v.to_lowercase() == "true")
+        .unwrap_or(false);
+
     let debug = std::env::var("DEBUG").is_ok();
 
     // TLS settings
@@ -108,6 +423,15 @@ pub fn load_config() -> anyhow::Result<Config> {
     let tls_key_path = std::env::var("EDGEPROXY_TLS_KEY").ok();
     let tls_listen_addr = std::env::var("EDGEPROXY_TLS_LISTEN_ADDR").ok();
 
+    let tls_handshake_timeout_ms = std::env::var("EDGEPROXY_TLS_HANDSHAKE_TIMEOUT_MS")
+        .unwrap_or_else(|_| "10000".to_string())
+        .parse()
+        .unwrap_or(10000);
+
+    let tls_enable_early_data = std::env::var("EDGEPROXY_TLS_ENABLE_EARLY_DATA")
+        .map(|v| v == "1" || v.to_lowercase() == "true")
+        .unwrap_or(false);
+
     // Auto-Discovery API settings
     let api_enabled = std::env::var("EDGEPROXY_API_ENABLED")
         .map(|v| v == "1" || v.to_lowercase() == "true")
@@ -116,6 +440,12 @@ pub fn load_config() -> anyhow::Result<Config> {
     let api_listen_addr = std::env::var("EDGEPROXY_API_LISTEN_ADDR")
         .unwrap_or_else(|_| "0.0.0.0:8081".to_string());
 
+    // Permissions of the API socket when listening on unix:/path (octal)
+    let api_socket_mode = std::env::var("EDGEPROXY_API_SOCKET_MODE")
+        .ok()
+        .and_then(|v| u32::from_str_radix(v.trim_start_matches("0o"), 8).ok())
+        .unwrap_or(0o660);
+
     let heartbeat_ttl_secs = std::env::var("EDGEPROXY_HEARTBEAT_TTL_SECS")
         .unwrap_or_else(|_| "60".to_string())
         .parse()
@@ -129,8 +459,51 @@ pub fn load_config() -> anyhow::Result<Config> {
     let dns_listen_addr = std::env::var("EDGEPROXY_DNS_LISTEN_ADDR")
         .unwrap_or_else(|_| "0.0.0.0:5353".to_string());
 
-    let dns_domain = std::env::var("EDGEPROXY_DNS_DOMAIN")
-        .unwrap_or_else(|_| "internal".to_string());
+    // Domain suffixes the DNS server answers for (comma-separated)
+    let dns_domains: Vec<String> = std::env::var("EDGEPROXY_DNS_DOMAIN")
+        .map(|v| {
+            v.split(',')
+                .map(|s| s.trim().trim_matches('.').to_lowercase())
+                .filter(|s| !s.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+    let dns_domains = if dns_domains.is_empty() {
+        vec!["internal".to_string()]
+    } else {
+        dns_domains
+    };
+
+    let dns_servfail_on_unhealthy = std::env::var("EDGEPROXY_DNS_SERVFAIL_ON_UNHEALTHY")
+        .map(|v| v == "1" || v.to_lowercase() == "true")
+        .unwrap_or(false);
+
+    let dns_nat64_prefix = std::env::var("EDGEPROXY_DNS_NAT64_PREFIX").ok();
+
+    let dns_weighted_rotation = std::env::var("EDGEPROXY_DNS_WEIGHTED_ROTATION")
+        .map(|v| v == "1" || v.to_lowercase() == "true")
+        .unwrap_or(false);
+
+    // Apps that also answer for any subdomain (*.app.<dns domain>)
+    let dns_wildcard_apps = std::env::var("EDGEPROXY_DNS_WILDCARD_APPS")
+        .map(|v| {
+            v.split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    // Cap on UDP responses to EDNS0 clients (larger answers are truncated)
+    let dns_max_udp_payload = std::env::var("EDGEPROXY_DNS_MAX_UDP_PAYLOAD")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(1232);
+
+    // Answer NXDOMAIN rather than REFUSED while the node is draining
+    let dns_nxdomain_when_draining = std::env::var("EDGEPROXY_DNS_NXDOMAIN_WHEN_DRAINING")
+        .map(|v| v == "1" || v.to_lowercase() == "true")
+        .unwrap_or(false);
 
     // Built-in replication settings
     let replication_enabled = std::env::var("EDGEPROXY_REPLICATION_ENABLED")
@@ -149,31 +522,83 @@ pub fn load_config() -> anyhow::Result<Config> {
         .map(|v| v.split(',').map(|s| s.trim().to_string()).collect())
         .unwrap_or_default();
 
+    // Shares the routing database unless set, so replicated backend changes
+    // land where the SQLite repository reads them
     let replication_db_path = std::env::var("EDGEPROXY_REPLICATION_DB_PATH")
-        .unwrap_or_else(|_| "state.db".to_string());
+        .unwrap_or_else(|_| db_path.clone());
 
     let replication_cluster_name = std::env::var("EDGEPROXY_REPLICATION_CLUSTER_NAME")
         .unwrap_or_else(|_| "edgeproxy".to_string());
 
+    let replication_cluster_secret = std::env::var("EDGEPROXY_REPLICATION_CLUSTER_SECRET")
+        .ok()
+        .filter(|s| !s.is_empty());
+
+    let replication_insecure_transport = std::env::var("EDGEPROXY_REPLICATION_INSECURE_TRANSPORT")
+        .map(|v| v == "1" || v.to_lowercase() == "true")
+        .unwrap_or(false);
+
     Ok(Config {
         listen_addr,
+        extra_listen_addrs,
         db_path,
         region,
+        strict_country,
+        zero_weight_fallback,
+        reuse_port,
+        listen_backlog,
+        tcp_fast_open,
+        socket_recv_buffer,
+        socket_send_buffer,
+        backend_resolve_ttl_secs,
+        connect_pressure_threshold,
+        connect_pressure_duration_secs,
         db_reload_secs,
+        backends_file,
+        consul_addr,
+        consul_service,
+        consul_token,
+        consul_datacenter,
         geoip_path,
+        geoip_reload_secs,
+        public_ip_url,
+        public_ip,
+        loopback_geo,
+        geo_unavailable,
+        lb_strategy,
         binding_ttl_secs,
         binding_gc_interval_secs,
+        max_session_secs,
+        drain_timeout_secs,
+        connect_proxy,
+        connect_hosts,
+        connect_expose_backend,
+        http_listen_addr,
+        udp_listen_addr,
+        udp_session_timeout_secs,
+        metrics_snapshot_path,
+        metrics_snapshot_secs,
+        access_log,
         debug,
         tls_enabled,
         tls_cert_path,
         tls_key_path,
         tls_listen_addr,
+        tls_handshake_timeout_ms,
+        tls_enable_early_data,
         api_enabled,
         api_listen_addr,
+        api_socket_mode,
         heartbeat_ttl_secs,
         dns_enabled,
         dns_listen_addr,
-        dns_domain,
+        dns_domains,
+        dns_servfail_on_unhealthy,
+        dns_nat64_prefix,
+        dns_weighted_rotation,
+        dns_wildcard_apps,
+        dns_max_udp_payload,
+        dns_nxdomain_when_draining,
         replication_enabled,
         replication_node_id,
         replication_gossip_addr,
@@ -181,6 +606,8 @@ pub fn load_config() -> anyhow::Result<Config> {
         replication_bootstrap_peers,
         replication_db_path,
         replication_cluster_name,
+        replication_cluster_secret,
+        replication_insecure_transport,
     })
 }
 
@@ -200,6 +627,26 @@ mod tests {
         assert!(!cfg.replication_enabled);
     }
 
+    #[test]
+    fn test_redacted_hides_secrets() {
+        let cfg = Config {
+            consul_token: Some("consul-token".to_string()),
+            replication_cluster_secret: Some("s3cret".to_string()),
+            tls_key_path: Some("/etc/edgeproxy/key.pem".to_string()),
+            ..Config::default()
+        };
+
+        let value = cfg.redacted();
+        assert_eq!(value["consul_token"], REDACTED);
+        assert_eq!(value["replication_cluster_secret"], REDACTED);
+        assert_eq!(value["tls_key_path"], "/etc/edgeproxy/key.pem");
+        assert_eq!(value["region"], "sa");
+        assert!(!value.to_string().contains("s3cret"));
+
+        // Unset secrets stay visible as unset
+        assert!(Config::default().redacted()["consul_token"].is_null());
+    }
+
     #[test]
     fn test_load_config_defaults() {
         std::env::remove_var("EDGEPROXY_LISTEN_ADDR");
@@ -301,6 +748,97 @@ mod tests {
         std::env::remove_var("EDGEPROXY_REPLICATION_BOOTSTRAP_PEERS");
     }
 
+    #[test]
+    fn test_load_config_replication_db_path_defaults_to_db_path() {
+        let cfg = load_config().unwrap();
+        assert_eq!(cfg.replication_db_path, cfg.db_path);
+    }
+
+    fn replicated_config() -> Config {
+        Config {
+            replication_enabled: true,
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn test_replication_routing_coherent() {
+        let cfg = replicated_config();
+        assert!(cfg.replication_routing_issue().is_none());
+
+        let cfg = Config {
+            db_path: "./data/routing.db".to_string(),
+            replication_db_path: "data/routing.db".to_string(),
+            ..replicated_config()
+        };
+        assert!(cfg.replication_routing_issue().is_none());
+
+        // Different paths don't matter while replication is off
+        let cfg = Config {
+            replication_db_path: "state.db".to_string(),
+            ..Config::default()
+        };
+        assert!(cfg.replication_routing_issue().is_none());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_replication_routing_coherent_through_symlink() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = dir.path().join("routing.db");
+        let link = dir.path().join("state.db");
+        std::fs::write(&db, b"").unwrap();
+        std::os::unix::fs::symlink(&db, &link).unwrap();
+
+        let cfg = Config {
+            db_path: db.to_string_lossy().into_owned(),
+            replication_db_path: link.to_string_lossy().into_owned(),
+            ..replicated_config()
+        };
+        assert!(cfg.replication_routing_issue().is_none());
+    }
+
+    #[test]
+    fn test_replication_routing_diverging_paths() {
+        let cfg = Config {
+            replication_db_path: "state.db".to_string(),
+            ..replicated_config()
+        };
+        let issue = cfg.replication_routing_issue().unwrap();
+        assert!(issue.contains("replication writes state.db but routing reads routing.db"));
+    }
+
+    #[test]
+    fn test_replication_routing_other_backend_source() {
+        let cfg = Config {
+            backends_file: Some("backends.yaml".to_string()),
+            ..replicated_config()
+        };
+        assert!(cfg.replication_routing_issue().unwrap().contains("backends file backends.yaml"));
+
+        let cfg = Config {
+            consul_service: Some("web".to_string()),
+            ..replicated_config()
+        };
+        assert!(cfg.replication_routing_issue().unwrap().contains("Consul service web"));
+    }
+
+    #[test]
+    fn test_load_config_replication_transport_trust() {
+        std::env::set_var("EDGEPROXY_REPLICATION_CLUSTER_SECRET", "s3cret");
+        std::env::set_var("EDGEPROXY_REPLICATION_INSECURE_TRANSPORT", "true");
+        let cfg = load_config().unwrap();
+        assert_eq!(cfg.replication_cluster_secret.as_deref(), Some("s3cret"));
+        assert!(cfg.replication_insecure_transport);
+
+        std::env::set_var("EDGEPROXY_REPLICATION_CLUSTER_SECRET", "");
+        std::env::remove_var("EDGEPROXY_REPLICATION_INSECURE_TRANSPORT");
+        let cfg = load_config().unwrap();
+        assert!(cfg.replication_cluster_secret.is_none());
+        assert!(!cfg.replication_insecure_transport);
+        std::env::remove_var("EDGEPROXY_REPLICATION_CLUSTER_SECRET");
+    }
+
     #[test]
     fn test_load_config_with_custom_listen_addr() {
         std::env::set_var("EDGEPROXY_LISTEN_ADDR", "127.0.0.1:9000");
@@ -325,6 +863,257 @@ mod tests {
         std::env::remove_var("EDGEPROXY_REGION");
     }
 
+    #[test]
+    fn test_load_config_with_zero_weight_fallback() {
+        std::env::set_var("EDGEPROXY_ZERO_WEIGHT_FALLBACK", "true");
+        let cfg = load_config().unwrap();
+        assert!(cfg.zero_weight_fallback);
+        std::env::remove_var("EDGEPROXY_ZERO_WEIGHT_FALLBACK");
+        assert!(!load_config().unwrap().zero_weight_fallback);
+    }
+
+    #[test]
+    fn test_load_config_with_strict_country() {
+        std::env::set_var("EDGEPROXY_STRICT_COUNTRY", "true");
+        let cfg = load_config().unwrap();
+        assert!(cfg.strict_country);
+        std::env::remove_var("EDGEPROXY_STRICT_COUNTRY");
+        assert!(!load_config().unwrap().strict_country);
+    }
+
+    #[test]
+    fn test_load_config_with_reuse_port() {
+        std::env::set_var("EDGEPROXY_REUSE_PORT", "true");
+        let cfg = load_config().unwrap();
+        assert!(cfg.reuse_port);
+        std::env::remove_var("EDGEPROXY_REUSE_PORT");
+    }
+
+    #[test]
+    fn test_load_config_with_listen_backlog() {
+        std::env::set_var("EDGEPROXY_LISTEN_BACKLOG", "8192");
+        let cfg = load_config().unwrap();
+        assert_eq!(cfg.listen_backlog, 8192);
+        std::env::remove_var("EDGEPROXY_LISTEN_BACKLOG");
+        assert_eq!(load_config().unwrap().listen_backlog, 0);
+    }
+
+    #[test]
+    fn test_load_config_with_tcp_fast_open() {
+        std::env::set_var("EDGEPROXY_TCP_FAST_OPEN", "1");
+        let cfg = load_config().unwrap();
+        assert!(cfg.tcp_fast_open);
+        std::env::remove_var("EDGEPROXY_TCP_FAST_OPEN");
+    }
+
+    #[test]
+    fn test_load_config_with_socket_buffers() {
+        std::env::set_var("EDGEPROXY_SOCKET_RECV_BUFFER", "4194304");
+        std::env::set_var("EDGEPROXY_SOCKET_SEND_BUFFER", "2097152");
+        let cfg = load_config().unwrap();
+        assert_eq!(cfg.socket_recv_buffer, 4194304);
+        assert_eq!(cfg.socket_send_buffer, 2097152);
+        std::env::remove_var("EDGEPROXY_SOCKET_RECV_BUFFER");
+        std::env::remove_var("EDGEPROXY_SOCKET_SEND_BUFFER");
+
+        let cfg = load_config().unwrap();
+        assert_eq!(cfg.socket_recv_buffer, 0);
+        assert_eq!(cfg.socket_send_buffer, 0);
+    }
+
+    #[test]
+    fn test_load_config_with_backend_resolve_ttl() {
+        std::env::set_var("EDGEPROXY_BACKEND_RESOLVE_TTL_SECS", "30");
+        let cfg = load_config().unwrap();
+        assert_eq!(cfg.backend_resolve_ttl_secs, 30);
+        std::env::remove_var("EDGEPROXY_BACKEND_RESOLVE_TTL_SECS");
+    }
+
+    #[test]
+    fn test_load_config_with_connect_pressure() {
+        std::env::set_var("EDGEPROXY_CONNECT_PRESSURE_THRESHOLD", "200");
+        std::env::set_var("EDGEPROXY_CONNECT_PRESSURE_DURATION_SECS", "30");
+        let cfg = load_config().unwrap();
+        assert_eq!(cfg.connect_pressure_threshold, 200);
+        assert_eq!(cfg.connect_pressure_duration_secs, 30);
+        std::env::remove_var("EDGEPROXY_CONNECT_PRESSURE_THRESHOLD");
+        std::env::remove_var("EDGEPROXY_CONNECT_PRESSURE_DURATION_SECS");
+
+        let cfg = load_config().unwrap();
+        assert_eq!(cfg.connect_pressure_threshold, 0);
+        assert_eq!(cfg.connect_pressure_duration_secs, 10);
+    }
+
+    #[test]
+    fn test_load_config_with_tls_handshake_timeout() {
+        std::env::set_var("EDGEPROXY_TLS_HANDSHAKE_TIMEOUT_MS", "2500");
+        let cfg = load_config().unwrap();
+        assert_eq!(cfg.tls_handshake_timeout_ms, 2500);
+        std::env::remove_var("EDGEPROXY_TLS_HANDSHAKE_TIMEOUT_MS");
+    }
+
+    #[test]
+    fn test_load_config_with_tls_enable_early_data() {
+        assert!(!Config::default().tls_enable_early_data);
+        std::env::set_var("EDGEPROXY_TLS_ENABLE_EARLY_DATA", "true");
+        let cfg = load_config().unwrap();
+        assert!(cfg.tls_enable_early_data);
+        std::env::remove_var("EDGEPROXY_TLS_ENABLE_EARLY_DATA");
+    }
+
+    #[test]
+    fn test_load_config_with_public_ip_url() {
+        std::env::set_var("EDGEPROXY_PUBLIC_IP_URL", "http://ip.example.internal/");
+        let cfg = load_config().unwrap();
+        assert_eq!(cfg.public_ip_url, "http://ip.example.internal/");
+        std::env::remove_var("EDGEPROXY_PUBLIC_IP_URL");
+    }
+
+    #[test]
+    fn test_load_config_with_loopback_geo() {
+        std::env::set_var("EDGEPROXY_LOOPBACK_GEO", "none");
+        let cfg = load_config().unwrap();
+        assert_eq!(cfg.loopback_geo, "none");
+        std::env::remove_var("EDGEPROXY_LOOPBACK_GEO");
+
+        let cfg = load_config().unwrap();
+        assert_eq!(cfg.loopback_geo, "public-ip");
+    }
+
+    #[test]
+    fn test_load_config_with_geo_unavailable() {
+        std::env::set_var("EDGEPROXY_GEO_UNAVAILABLE", "closed");
+        let cfg = load_config().unwrap();
+        assert_eq!(cfg.geo_unavailable, "closed");
+        std::env::remove_var("EDGEPROXY_GEO_UNAVAILABLE");
+
+        let cfg = load_config().unwrap();
+        assert_eq!(cfg.geo_unavailable, "open");
+    }
+
+    #[test]
+    fn test_load_config_with_static_public_ip() {
+        std::env::set_var("EDGEPROXY_PUBLIC_IP", "203.0.113.7");
+        let cfg = load_config().unwrap();
+        assert_eq!(cfg.public_ip.as_deref(), Some("203.0.113.7"));
+        std::env::remove_var("EDGEPROXY_PUBLIC_IP");
+    }
+
+    #[test]
+    fn test_load_config_with_max_session_secs() {
+        std::env::set_var("EDGEPROXY_MAX_SESSION_SECS", "3600");
+        let cfg = load_config().unwrap();
+        assert_eq!(cfg.max_session_secs, 3600);
+        std::env::remove_var("EDGEPROXY_MAX_SESSION_SECS");
+    }
+
+    #[test]
+    fn test_load_config_with_drain_timeout_secs() {
+        std::env::set_var("EDGEPROXY_DRAIN_TIMEOUT_SECS", "5");
+        let cfg = load_config().unwrap();
+        assert_eq!(cfg.drain_timeout_secs, 5);
+        std::env::remove_var("EDGEPROXY_DRAIN_TIMEOUT_SECS");
+
+        let cfg = load_config().unwrap();
+        assert_eq!(cfg.drain_timeout_secs, 30);
+    }
+
+    #[test]
+    fn test_load_config_with_connect_proxy() {
+        std::env::set_var("EDGEPROXY_CONNECT_PROXY", "true");
+        std::env::set_var("EDGEPROXY_CONNECT_HOSTS", "api.example.com=api, www.example.com=web");
+        let cfg = load_config().unwrap();
+        assert!(cfg.connect_proxy);
+        assert_eq!(cfg.connect_hosts, vec!["api.example.com=api", "www.example.com=web"]);
+        std::env::remove_var("EDGEPROXY_CONNECT_PROXY");
+        std::env::remove_var("EDGEPROXY_CONNECT_HOSTS");
+    }
+
+    #[test]
+    fn test_load_config_with_connect_expose_backend() {
+        std::env::set_var("EDGEPROXY_CONNECT_EXPOSE_BACKEND", "true");
+        let cfg = load_config().unwrap();
+        assert!(cfg.connect_expose_backend);
+        std::env::remove_var("EDGEPROXY_CONNECT_EXPOSE_BACKEND");
+
+        let cfg = load_config().unwrap();
+        assert!(!cfg.connect_expose_backend);
+    }
+
+    #[test]
+    fn test_load_config_with_lb_strategy() {
+        std::env::set_var("EDGEPROXY_LB_STRATEGY", "flow-hash");
+        let cfg = load_config().unwrap();
+        assert_eq!(cfg.lb_strategy, "flow-hash");
+        std::env::remove_var("EDGEPROXY_LB_STRATEGY");
+
+        let cfg = load_config().unwrap();
+        assert_eq!(cfg.lb_strategy, "geo");
+    }
+
+    #[test]
+    fn test_load_config_with_http_listen_addr() {
+        std::env::set_var("EDGEPROXY_HTTP_LISTEN_ADDR", "0.0.0.0:8081");
+        let cfg = load_config().unwrap();
+        assert_eq!(cfg.http_listen_addr.as_deref(), Some("0.0.0.0:8081"));
+        std::env::remove_var("EDGEPROXY_HTTP_LISTEN_ADDR");
+
+        let cfg = load_config().unwrap();
+        assert!(cfg.http_listen_addr.is_none());
+    }
+
+    #[test]
+    fn test_load_config_with_udp_listener() {
+        std::env::set_var("EDGEPROXY_UDP_LISTEN_ADDR", "0.0.0.0:4433");
+        std::env::set_var("EDGEPROXY_UDP_SESSION_TIMEOUT_SECS", "15");
+        let cfg = load_config().unwrap();
+        assert_eq!(cfg.udp_listen_addr.as_deref(), Some("0.0.0.0:4433"));
+        assert_eq!(cfg.udp_session_timeout_secs, 15);
+
+        // A zero timeout would end sessions at once
+        std::env::set_var("EDGEPROXY_UDP_SESSION_TIMEOUT_SECS", "0");
+        assert_eq!(load_config().unwrap().udp_session_timeout_secs, 60);
+        std::env::remove_var("EDGEPROXY_UDP_LISTEN_ADDR");
+        std::env::remove_var("EDGEPROXY_UDP_SESSION_TIMEOUT_SECS");
+
+        let cfg = load_config().unwrap();
+        assert!(cfg.udp_listen_addr.is_none());
+        assert_eq!(cfg.udp_session_timeout_secs, 60);
+    }
+
+    #[test]
+    fn test_load_config_with_extra_listen_addrs() {
+        std::env::set_var("EDGEPROXY_EXTRA_LISTEN_ADDRS", "0.0.0.0:80, [::]:8080,");
+        let cfg = load_config().unwrap();
+        assert_eq!(cfg.extra_listen_addrs, vec!["0.0.0.0:80", "[::]:8080"]);
+        std::env::remove_var("EDGEPROXY_EXTRA_LISTEN_ADDRS");
+    }
+
+    #[test]
+    fn test_load_config_with_metrics_snapshot() {
+        std::env::set_var("EDGEPROXY_METRICS_SNAPSHOT_PATH", "/var/lib/edgeproxy/metrics.json");
+        std::env::set_var("EDGEPROXY_METRICS_SNAPSHOT_SECS", "15");
+        let cfg = load_config().unwrap();
+        assert_eq!(
+            cfg.metrics_snapshot_path,
+            Some("/var/lib/edgeproxy/metrics.json".to_string())
+        );
+        assert_eq!(cfg.metrics_snapshot_secs, 15);
+        std::env::remove_var("EDGEPROXY_METRICS_SNAPSHOT_PATH");
+        std::env::remove_var("EDGEPROXY_METRICS_SNAPSHOT_SECS");
+    }
+
+    #[test]
+    fn test_load_config_with_geoip_reload_secs() {
+        std::env::set_var("EDGEPROXY_GEOIP_RELOAD_SECS", "0");
+        let cfg = load_config().unwrap();
+        assert_eq!(cfg.geoip_reload_secs, 0);
+        std::env::remove_var("EDGEPROXY_GEOIP_RELOAD_SECS");
+
+        let cfg = load_config().unwrap();
+        assert_eq!(cfg.geoip_reload_secs, 60);
+    }
+
     #[test]
     fn test_load_config_with_geoip_path() {
         std::env::set_var("EDGEPROXY_GEOIP_PATH", "/path/to/GeoLite2.mmdb");
@@ -333,6 +1122,17 @@ mod tests {
         std::env::remove_var("EDGEPROXY_GEOIP_PATH");
     }
 
+    #[test]
+    fn test_load_config_with_access_log() {
+        std::env::set_var("EDGEPROXY_ACCESS_LOG", "1");
+        let cfg = load_config().unwrap();
+        assert!(cfg.access_log);
+        std::env::remove_var("EDGEPROXY_ACCESS_LOG");
+
+        let cfg = load_config().unwrap();
+        assert!(!cfg.access_log);
+    }
+
     #[test]
     fn test_load_config_with_debug() {
         std::env::set_var("DEBUG", "1");
@@ -366,17 +1166,103 @@ mod tests {
         std::env::remove_var("EDGEPROXY_HEARTBEAT_TTL_SECS");
     }
 
+    #[test]
+    fn test_load_config_with_api_socket() {
+        std::env::set_var("EDGEPROXY_API_LISTEN_ADDR", "unix:/run/edgeproxy/api.sock");
+        std::env::set_var("EDGEPROXY_API_SOCKET_MODE", "600");
+        let cfg = load_config().unwrap();
+        assert_eq!(cfg.api_listen_addr, "unix:/run/edgeproxy/api.sock");
+        assert_eq!(cfg.api_socket_mode, 0o600);
+
+        std::env::set_var("EDGEPROXY_API_SOCKET_MODE", "rw-------");
+        assert_eq!(load_config().unwrap().api_socket_mode, 0o660);
+        std::env::remove_var("EDGEPROXY_API_LISTEN_ADDR");
+        std::env::remove_var("EDGEPROXY_API_SOCKET_MODE");
+    }
+
     #[test]
     fn test_load_config_with_dns_settings() {
         std::env::set_var("EDGEPROXY_DNS_LISTEN_ADDR", "0.0.0.0:5354");
         std::env::set_var("EDGEPROXY_DNS_DOMAIN", "edge.local");
         let cfg = load_config().unwrap();
         assert_eq!(cfg.dns_listen_addr, "0.0.0.0:5354");
-        assert_eq!(cfg.dns_domain, "edge.local");
+        assert_eq!(cfg.dns_domains, vec!["edge.local".to_string()]);
         std::env::remove_var("EDGEPROXY_DNS_LISTEN_ADDR");
         std::env::remove_var("EDGEPROXY_DNS_DOMAIN");
     }
 
+    #[test]
+    fn test_load_config_with_multiple_dns_domains() {
+        std::env::set_var("EDGEPROXY_DNS_DOMAIN", "internal, svc.cluster.local.,,Edge.Local");
+        let cfg = load_config().unwrap();
+        assert_eq!(cfg.dns_domains, vec!["internal", "svc.cluster.local", "edge.local"]);
+        std::env::remove_var("EDGEPROXY_DNS_DOMAIN");
+        assert_eq!(load_config().unwrap().dns_domains, vec!["internal"]);
+    }
+
+    #[test]
+    fn test_load_config_with_dns_servfail_on_unhealthy() {
+        std::env::set_var("EDGEPROXY_DNS_SERVFAIL_ON_UNHEALTHY", "true");
+        let cfg = load_config().unwrap();
+        assert!(cfg.dns_servfail_on_unhealthy);
+        std::env::remove_var("EDGEPROXY_DNS_SERVFAIL_ON_UNHEALTHY");
+
+        let cfg = load_config().unwrap();
+        assert!(!cfg.dns_servfail_on_unhealthy);
+    }
+
+    #[test]
+    fn test_load_config_with_dns_nat64_prefix() {
+        std::env::set_var("EDGEPROXY_DNS_NAT64_PREFIX", "64:ff9b::/96");
+        let cfg = load_config().unwrap();
+        assert_eq!(cfg.dns_nat64_prefix, Some("64:ff9b::/96".to_string()));
+        std::env::remove_var("EDGEPROXY_DNS_NAT64_PREFIX");
+    }
+
+    #[test]
+    fn test_load_config_with_dns_weighted_rotation() {
+        std::env::set_var("EDGEPROXY_DNS_WEIGHTED_ROTATION", "1");
+        let cfg = load_config().unwrap();
+        assert!(cfg.dns_weighted_rotation);
+        std::env::remove_var("EDGEPROXY_DNS_WEIGHTED_ROTATION");
+
+        let cfg = load_config().unwrap();
+        assert!(!cfg.dns_weighted_rotation);
+    }
+
+    #[test]
+    fn test_load_config_with_dns_wildcard_apps() {
+        std::env::set_var("EDGEPROXY_DNS_WILDCARD_APPS", "myapp, api.shop,");
+        let cfg = load_config().unwrap();
+        assert_eq!(cfg.dns_wildcard_apps, vec!["myapp", "api.shop"]);
+        std::env::remove_var("EDGEPROXY_DNS_WILDCARD_APPS");
+
+        let cfg = load_config().unwrap();
+        assert!(cfg.dns_wildcard_apps.is_empty());
+    }
+
+    #[test]
+    fn test_load_config_with_dns_max_udp_payload() {
+        std::env::set_var("EDGEPROXY_DNS_MAX_UDP_PAYLOAD", "4096");
+        let cfg = load_config().unwrap();
+        assert_eq!(cfg.dns_max_udp_payload, 4096);
+        std::env::remove_var("EDGEPROXY_DNS_MAX_UDP_PAYLOAD");
+
+        let cfg = load_config().unwrap();
+        assert_eq!(cfg.dns_max_udp_payload, 1232);
+    }
+
+    #[test]
+    fn test_load_config_with_dns_nxdomain_when_draining() {
+        std::env::set_var("EDGEPROXY_DNS_NXDOMAIN_WHEN_DRAINING", "true");
+        let cfg = load_config().unwrap();
+        assert!(cfg.dns_nxdomain_when_draining);
+        std::env::remove_var("EDGEPROXY_DNS_NXDOMAIN_WHEN_DRAINING");
+
+        let cfg = load_config().unwrap();
+        assert!(!cfg.dns_nxdomain_when_draining);
+    }
+
     #[test]
     fn test_load_config_with_binding_settings() {
         std::env::set_var("EDGEPROXY_BINDING_TTL_SECS", "1200");
@@ -396,6 +1282,35 @@ mod tests {
         std::env::remove_var("EDGEPROXY_DB_RELOAD_SECS");
     }
 
+    #[test]
+    fn test_load_config_with_backends_file() {
+        std::env::set_var("EDGEPROXY_BACKENDS_FILE", "/etc/edgeproxy/backends.json");
+        let cfg = load_config().unwrap();
+        assert_eq!(cfg.backends_file, Some("/etc/edgeproxy/backends.json".to_string()));
+        std::env::remove_var("EDGEPROXY_BACKENDS_FILE");
+    }
+
+    #[test]
+    fn test_load_config_with_consul() {
+        std::env::set_var("EDGEPROXY_CONSUL_ADDR", "http://consul:8500");
+        std::env::set_var("EDGEPROXY_CONSUL_SERVICE", "web");
+        std::env::set_var("EDGEPROXY_CONSUL_TOKEN", "secret");
+        std::env::set_var("EDGEPROXY_CONSUL_DATACENTER", "eu-west");
+        let cfg = load_config().unwrap();
+        assert_eq!(cfg.consul_addr, "http://consul:8500");
+        assert_eq!(cfg.consul_service, Some("web".to_string()));
+        assert_eq!(cfg.consul_token, Some("secret".to_string()));
+        assert_eq!(cfg.consul_datacenter, Some("eu-west".to_string()));
+        std::env::remove_var("EDGEPROXY_CONSUL_ADDR");
+        std::env::remove_var("EDGEPROXY_CONSUL_SERVICE");
+        std::env::remove_var("EDGEPROXY_CONSUL_TOKEN");
+        std::env::remove_var("EDGEPROXY_CONSUL_DATACENTER");
+
+        let cfg = load_config().unwrap();
+        assert_eq!(cfg.consul_addr, "http://127.0.0.1:8500");
+        assert!(cfg.consul_service.is_none());
+    }
+
     #[test]
     fn test_load_config_parse_error_uses_default() {
         std::env::set_var("EDGEPROXY_DB_RELOAD_SECS", "not_a_number");
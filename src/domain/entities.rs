@@ -23,7 +23,7 @@ pub struct Backend {
     pub region: RegionCode,
     /// Country code (ISO 3166-1 alpha-2: BR, US, FR, etc)
     pub country: String,
-    /// WireGuard overlay IP address
+    /// WireGuard overlay IP address, or a hostname resolved on connect
     pub wg_ip: String,
     /// Port number for the backend service
     pub port: u16,
@@ -35,6 +35,37 @@ pub struct Backend {
     pub soft_limit: u32,
     /// Maximum number of connections (hard cap)
     pub hard_limit: u32,
+    /// Whether this backend is draining (kept up, but gets no new clients)
+    #[serde(default)]
+    pub draining: bool,
+    /// Whether this backend is down for maintenance (out of rotation, but
+    /// not a failure: it isn't health checked or alerted on)
+    #[serde(default)]
+    pub maintenance: bool,
+    /// SRV priority tier (lower is preferred): DNS clients only fall back
+    /// to a higher tier when every backend of the lower ones is down
+    #[serde(default)]
+    pub priority: u16,
+}
+
+impl Backend {
+    /// Whether new clients may be routed to this backend.
+    pub fn accepts_new_connections(&self) -> bool {
+        self.healthy && !self.draining && !self.maintenance
+    }
+
+    /// The backend's IP, or `None` when it is addressed by hostname.
+    pub fn ip(&self) -> Option<IpAddr> {
+        self.wg_ip.parse().ok()
+    }
+
+    /// The `host:port` to connect to, bracketing IPv6 literals.
+    pub fn addr(&self) -> String {
+        match self.ip() {
+            Some(IpAddr::V6(ip)) => format!("[{}]:{}", ip, self.port),
+            _ => format!("{}:{}", self.wg_ip, self.port),
+        }
+    }
 }
 
 /// Client-to-backend binding for session affinity.
@@ -99,7 +130,7 @@ impl Hash for ClientKey {
 }
 
 /// Geographic information resolved from an IP address.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct GeoInfo {
     /// Country code (ISO 3166-1 alpha-2)
     pub country: String,
@@ -243,6 +274,9 @@ mod tests {
             weight: 5,
             soft_limit: 100,
             hard_limit: 200,
+            draining: false,
+            maintenance: false,
+            priority: 0,
         };
 
         assert_eq!(backend.id, "fly-gru-1");
@@ -270,6 +304,9 @@ mod tests {
             weight: 1,
             soft_limit: 50,
             hard_limit: 100,
+            draining: false,
+            maintenance: false,
+            priority: 0,
         };
 
         let cloned = backend.clone();
@@ -277,4 +314,73 @@ mod tests {
         assert_eq!(cloned.id, backend.id);
         assert_eq!(cloned.healthy, backend.healthy);
     }
+
+    #[test]
+    fn test_backend_accepts_new_connections() {
+        let mut backend = Backend {
+            id: "test-1".to_string(),
+            app: "app".to_string(),
+            region: RegionCode::Europe,
+            country: "DE".to_string(),
+            wg_ip: "10.0.0.1".to_string(),
+            port: 9000,
+            healthy: true,
+            weight: 1,
+            soft_limit: 50,
+            hard_limit: 100,
+            draining: false,
+            maintenance: false,
+            priority: 0,
+        };
+        assert!(backend.accepts_new_connections());
+
+        backend.draining = true;
+        assert!(!backend.accepts_new_connections());
+
+        backend.draining = false;
+        backend.maintenance = true;
+        assert!(!backend.accepts_new_connections());
+
+        backend.maintenance = false;
+        backend.healthy = false;
+        assert!(!backend.accepts_new_connections());
+    }
+
+    #[test]
+    fn test_backend_addr() {
+        let mut backend = Backend {
+            id: "test-1".to_string(),
+            app: "app".to_string(),
+            region: RegionCode::Europe,
+            country: "DE".to_string(),
+            wg_ip: "10.0.0.1".to_string(),
+            port: 9000,
+            healthy: true,
+            weight: 1,
+            soft_limit: 50,
+            hard_limit: 100,
+            draining: false,
+            maintenance: false,
+            priority: 0,
+        };
+        assert_eq!(backend.ip(), Some("10.0.0.1".parse().unwrap()));
+        assert_eq!(backend.addr(), "10.0.0.1:9000");
+
+        backend.wg_ip = "fd00::1".to_string();
+        assert_eq!(backend.addr(), "[fd00::1]:9000");
+
+        backend.wg_ip = "app-1.internal".to_string();
+        assert_eq!(backend.ip(), None);
+        assert_eq!(backend.addr(), "app-1.internal:9000");
+    }
+
+    #[test]
+    fn test_backend_deserialize_without_draining() {
+        let json = r#"{"id":"b1","app":"app","region":"Europe","country":"DE","wg_ip":"10.0.0.1",
+            "port":80,"healthy":true,"weight":1,"soft_limit":10,"hard_limit":20}"#;
+        let backend: Backend = serde_json::from_str(json).unwrap();
+        assert!(!backend.draining);
+        assert!(!backend.maintenance);
+        assert_eq!(backend.priority, 0);
+    }
 }
@@ -27,4 +27,12 @@ pub trait BackendRepository: Send + Sync {
     /// Used to detect when backends have been updated.
     #[allow(dead_code)]
     async fn get_version(&self) -> u64;
+
+    /// Get the distinct apps of all configured backends, sorted.
+    async fn list_apps(&self) -> Vec<String> {
+        let mut apps: Vec<String> = self.get_all().await.into_iter().map(|b| b.app).collect();
+        apps.sort_unstable();
+        apps.dedup();
+        apps
+    }
 }
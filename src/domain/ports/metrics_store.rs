@@ -2,6 +2,10 @@
 //!
 //! Defines the interface for storing and retrieving runtime metrics.
 
+use crate::domain::value_objects::{
+    CircuitState, CloseReason, ConnectPhase, DnsQueryOutcome, SelectionOutcome,
+};
+
 /// Store for runtime metrics per backend.
 ///
 /// This is an outbound port for tracking connection counts and latency.
@@ -16,10 +20,139 @@ pub trait MetricsStore: Send + Sync {
     /// Decrement the connection count when a connection is closed.
     fn decrement_connections(&self, backend_id: &str);
 
+    /// Associate a backend with its app so stores can aggregate per app.
+    fn set_backend_app(&self, _backend_id: &str, _app: &str) {}
+
     /// Record the round-trip time to establish a connection to a backend.
     fn record_rtt(&self, backend_id: &str, rtt_ms: u64);
 
     /// Get the last recorded RTT for a backend.
     #[allow(dead_code)]
     fn get_last_rtt(&self, backend_id: &str) -> Option<u64>;
+
+    /// Record how long one phase of dialing a backend took.
+    ///
+    /// Complements [`MetricsStore::record_rtt`], which covers the whole dial.
+    fn record_connect_phase(&self, _backend_id: &str, _phase: ConnectPhase, _ms: u64) {}
+
+    /// Get the last recorded duration of a dial phase for a backend.
+    fn get_connect_phase(&self, _backend_id: &str, _phase: ConnectPhase) -> Option<u64> {
+        None
+    }
+
+    /// Record a session aborted for exceeding the maximum session duration.
+    fn record_session_timeout(&self, _backend_id: &str) {}
+
+    /// Get the number of sessions to a backend aborted at the session cap.
+    fn get_session_timeouts(&self, _backend_id: &str) -> u64 {
+        0
+    }
+
+    /// Record the backends added, removed and updated by a routing reload.
+    fn record_routing_changes(&self, _added: usize, _removed: usize, _updated: usize) {}
+
+    /// Get the cumulative (added, removed, updated) routing change counts.
+    fn get_routing_changes(&self) -> (u64, u64, u64) {
+        (0, 0, 0)
+    }
+
+    /// Set how many loaded backends are down for maintenance.
+    ///
+    /// Kept apart from health so planned maintenance doesn't show up as
+    /// backend failures.
+    fn set_maintenance_backends(&self, _count: usize) {}
+
+    /// Get the last reported number of backends down for maintenance.
+    fn get_maintenance_backends(&self) -> u64 {
+        0
+    }
+
+    /// Set how many backend dials are in progress and how many addresses
+    /// are queued behind them, waiting for their turn to be tried.
+    fn set_connect_pressure(&self, _in_flight: usize, _queued: usize) {}
+
+    /// Get the last reported (in-flight, queued) backend connect counts.
+    fn get_connect_pressure(&self) -> (u64, u64) {
+        (0, 0)
+    }
+
+    /// Record an answered DNS query for an app.
+    ///
+    /// Stores that don't track DNS traffic can rely on the no-op default.
+    fn record_dns_query(&self, _app: &str, _outcome: DnsQueryOutcome) {}
+
+    /// Get the number of DNS queries recorded for an app and outcome.
+    fn get_dns_query_count(&self, _app: &str, _outcome: DnsQueryOutcome) -> u64 {
+        0
+    }
+
+    /// Record where a backend selection landed (or that nothing was available).
+    fn record_selection(&self, _outcome: SelectionOutcome) {}
+
+    /// Get the number of backend selections recorded with an outcome.
+    fn get_selection_count(&self, _outcome: SelectionOutcome) -> u64 {
+        0
+    }
+
+    /// Record that the load balancer picked `backend_id` for a connection to `app`.
+    fn record_backend_selected(&self, _app: &str, _backend_id: &str) {}
+
+    /// Get how often the load balancer picked `backend_id` for `app`.
+    fn get_backend_selected(&self, _app: &str, _backend_id: &str) -> u64 {
+        0
+    }
+
+    /// Record why a client connection was closed.
+    fn record_connection_closed(&self, _reason: CloseReason) {}
+
+    /// Get the number of connections closed for a reason.
+    fn get_connection_closed(&self, _reason: CloseReason) -> u64 {
+        0
+    }
+
+    /// Record a connection turned away because every backend was at its hard limit.
+    fn record_overload_rejection(&self) {}
+
+    /// Get the number of connections rejected for overload.
+    fn get_overload_rejections(&self) -> u64 {
+        0
+    }
+
+    /// Record a backend's circuit breaker moving into `state`.
+    fn record_circuit_transition(&self, _backend_id: &str, _state: CircuitState) {}
+
+    /// Get the current circuit breaker state of a backend, if one was recorded.
+    fn get_circuit_state(&self, _backend_id: &str) -> Option<CircuitState> {
+        None
+    }
+
+    /// Get how often a backend's circuit breaker moved into `state`.
+    fn get_circuit_transitions(&self, _backend_id: &str, _state: CircuitState) -> u64 {
+        0
+    }
+
+    /// Record a routing decision made while no geo resolver was loaded.
+    fn record_geo_unavailable(&self) {}
+
+    /// Get the number of routing decisions made without a geo resolver.
+    fn get_geo_unavailable(&self) -> u64 {
+        0
+    }
+
+    /// Record how many changesets replication with a peer is behind, in
+    /// each direction.
+    fn record_replication_lag(&self, _peer: &str, _inbound: u64, _outbound: u64) {}
+
+    /// Get the last recorded (inbound, outbound) replication lag of a peer.
+    fn get_replication_lag(&self, _peer: &str) -> Option<(u64, u64)> {
+        None
+    }
+
+    /// Record a replicated change to `table` rejected by conflict resolution.
+    fn record_lww_rejected(&self, _table: &str) {}
+
+    /// Get the number of replicated changes to `table` rejected by conflict resolution.
+    fn get_lww_rejected(&self, _table: &str) -> u64 {
+        0
+    }
 }
@@ -2,8 +2,10 @@ mod backend_repository;
 mod binding_repository;
 mod geo_resolver;
 mod metrics_store;
+mod public_ip_provider;
 
 pub use backend_repository::BackendRepository;
 pub use binding_repository::BindingRepository;
 pub use geo_resolver::GeoResolver;
 pub use metrics_store::MetricsStore;
+pub use public_ip_provider::PublicIpProvider;
@@ -0,0 +1,17 @@
+//! Public IP Provider Port
+//!
+//! Defines the interface for discovering this host's public IP address.
+
+use async_trait::async_trait;
+use std::net::IpAddr;
+
+/// Source of this host's public IP address.
+///
+/// This is an outbound port used to locate loopback clients, which carry
+/// no location of their own. Implementations may ask an HTTP "what is my
+/// IP" service, use a configured address, or query a STUN server.
+#[async_trait]
+pub trait PublicIpProvider: Send + Sync {
+    /// Look up the public IP, or None if it can't be determined.
+    async fn public_ip(&self) -> Option<IpAddr>;
+}
@@ -4,21 +4,362 @@
 //! This service has NO external dependencies - it's pure Rust.
 
 use crate::domain::entities::{Backend, GeoInfo};
-use crate::domain::value_objects::RegionCode;
+use crate::domain::value_objects::{RegionCode, SelectionOutcome};
+use rand::{Rng, RngCore};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+
+/// FNV-1a over `parts`, finished with a splitmix64 mix.
+///
+/// Unlike `DefaultHasher`, the output is fixed across Rust versions and
+/// processes, which keeps rendezvous hashing consistent between POPs.
+fn stable_hash(parts: &[&[u8]]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in parts.iter().flat_map(|part| part.iter()) {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    hash ^ (hash >> 31)
+}
+
+/// Everything [`LoadBalancer::select`] needs to know about a request.
+///
+/// Callers gather these inputs (geo lookup, connection counts, RNG) and
+/// selection itself stays free of I/O.
+pub struct SelectionContext<'a> {
+    /// Region of the local POP
+    pub local_region: &'a RegionCode,
+    /// Client address, if known
+    pub client_ip: Option<IpAddr>,
+    /// Geographic info for the client, if resolved
+    pub client_geo: Option<&'a GeoInfo>,
+    /// Active connections per backend id (missing ids count as 0)
+    pub active: Option<&'a HashMap<String, usize>>,
+    /// Source of randomness for tie-breaks; ties go to the first
+    /// backend by id when absent
+    pub rng: Option<&'a mut dyn RngCore>,
+    /// Only consider backends in the client's country while any of them
+    /// can take the connection
+    pub strict_country: bool,
+    /// When every eligible backend has weight 0, pick among them as if
+    /// they were equally weighted instead of picking none
+    pub zero_weight_fallback: bool,
+}
+
+impl<'a> SelectionContext<'a> {
+    /// Context with only the local region set.
+    pub fn new(local_region: &'a RegionCode) -> Self {
+        Self {
+            local_region,
+            client_ip: None,
+            client_geo: None,
+            active: None,
+            rng: None,
+            strict_country: false,
+            zero_weight_fallback: false,
+        }
+    }
+
+    /// Set the client address.
+    pub fn with_client_ip(mut self, client_ip: Option<IpAddr>) -> Self {
+        self.client_ip = client_ip;
+        self
+    }
+
+    /// Set the client's resolved geo info.
+    pub fn with_client_geo(mut self, client_geo: Option<&'a GeoInfo>) -> Self {
+        self.client_geo = client_geo;
+        self
+    }
+
+    /// Set active connection counts per backend id.
+    pub fn with_active(mut self, active: &'a HashMap<String, usize>) -> Self {
+        self.active = Some(active);
+        self
+    }
+
+    /// Require a same-country backend whenever one is eligible.
+    pub fn with_strict_country(mut self, strict_country: bool) -> Self {
+        self.strict_country = strict_country;
+        self
+    }
+
+    /// Fall back to zero-weight backends, as equals, when no other is eligible.
+    pub fn with_zero_weight_fallback(mut self, zero_weight_fallback: bool) -> Self {
+        self.zero_weight_fallback = zero_weight_fallback;
+        self
+    }
+
+    /// Break ties with a weighted random draw from `rng`.
+    pub fn with_rng(mut self, rng: &'a mut dyn RngCore) -> Self {
+        self.rng = Some(rng);
+        self
+    }
+
+    /// Active connections for a backend id.
+    fn active_connections(&self, id: &str) -> usize {
+        self.active
+            .and_then(|active| active.get(id).copied())
+            .unwrap_or(0)
+    }
+}
+
+/// The parts of a connection that [`LoadBalancer::select_flow`] hashes.
+///
+/// The client address and port identify the flow; the destination port is
+/// optional, so listeners on several ports can keep their flows apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FlowKey {
+    pub client: SocketAddr,
+    pub dest_port: Option<u16>,
+}
+
+impl FlowKey {
+    /// Key for a flow from `client`, without a destination port.
+    pub fn new(client: SocketAddr) -> Self {
+        Self {
+            client,
+            dest_port: None,
+        }
+    }
+
+    /// Also hash the port the client connected to.
+    pub fn with_dest_port(mut self, dest_port: u16) -> Self {
+        self.dest_port = Some(dest_port);
+        self
+    }
+
+    /// Stable byte encoding of the key, the input to the flow hash.
+    fn to_bytes(self) -> Vec<u8> {
+        let mut bytes = match self.client.ip().to_canonical() {
+            IpAddr::V4(ip) => ip.octets().to_vec(),
+            IpAddr::V6(ip) => ip.octets().to_vec(),
+        };
+        bytes.extend_from_slice(&self.client.port().to_be_bytes());
+        if let Some(port) = self.dest_port {
+            bytes.extend_from_slice(&port.to_be_bytes());
+        }
+        bytes
+    }
+}
+
+/// Why a backend was left out of a selection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exclusion {
+    /// Backend is down for maintenance (taken out on purpose, not a failure)
+    Maintenance,
+    /// Backend failed its health checks
+    Unhealthy,
+    /// Backend is draining and takes no new clients
+    Draining,
+    /// Backend is at its hard connection limit
+    HardLimit,
+    /// Backend has weight 0 and takes no new clients
+    ZeroWeight,
+    /// Backend is outside the client's country and a same-country backend
+    /// is eligible (strict country matching)
+    OtherCountry,
+}
+
+impl Exclusion {
+    /// Label used in logs and API responses.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Exclusion::Maintenance => "maintenance",
+            Exclusion::Unhealthy => "unhealthy",
+            Exclusion::Draining => "draining",
+            Exclusion::HardLimit => "hard_limit",
+            Exclusion::ZeroWeight => "zero_weight",
+            Exclusion::OtherCountry => "other_country",
+        }
+    }
+}
+
+impl std::fmt::Display for Exclusion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// How one backend fared in [`LoadBalancer::evaluate`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Evaluation<'b> {
+    pub backend: &'b Backend,
+    /// Active connections seen for the backend
+    pub active: usize,
+    /// Score (lower is better), or why the backend was excluded
+    pub verdict: Result<f64, Exclusion>,
+}
 
 /// Load balancer service for selecting optimal backends.
 ///
 /// The load balancer uses a scoring algorithm that considers:
-/// 1. Geographic proximity (country > region > local > fallback)
+/// 1. Geographic proximity (country > region > local > fallback chain > any)
 /// 2. Current load (connections / soft_limit)
 /// 3. Backend weight (higher weight = preferred)
 ///
-/// Lower scores are better.
+/// A weight of 0 drains a backend: it keeps its bound clients but gets no
+/// new ones. If every eligible backend has weight 0, nothing is selected,
+/// unless the context enables the zero-weight fallback, in which case they
+/// are picked from as if equally weighted.
+///
+/// Lower scores are better. Candidates are considered in `id` order, so
+/// ties resolve the same way whatever order the repository returned.
 pub struct LoadBalancer;
 
 impl LoadBalancer {
+    /// Select a backend from `candidates` using only the inputs in `ctx`.
+    ///
+    /// This is the whole selection strategy as a pure function: no
+    /// repository, metrics store or clock is touched, so it can be unit
+    /// tested against crafted candidate slices. Unhealthy, draining and
+    /// hard-limited backends are skipped, the rest are scored (geo tier
+    /// first, then load / weight), and ties on the best score go to the
+    /// first backend by id, or to a weighted random draw when `ctx`
+    /// carries an RNG.
+    pub fn select<'b>(candidates: &'b [Backend], ctx: SelectionContext<'_>) -> Option<&'b Backend> {
+        let scored: Vec<(&Backend, f64)> = Self::evaluate(candidates, &ctx)
+            .into_iter()
+            .filter_map(|eval| eval.verdict.ok().map(|score| (eval.backend, score)))
+            .collect();
+
+        let best_score = scored
+            .iter()
+            .map(|(_, score)| *score)
+            .fold(f64::INFINITY, f64::min);
+
+        let tied: Vec<&Backend> = scored
+            .into_iter()
+            .filter(|(_, score)| *score == best_score)
+            .map(|(backend, _)| backend)
+            .collect();
+
+        let rng = match ctx.rng {
+            Some(rng) if tied.len() > 1 => rng,
+            _ => return tied.first().copied(),
+        };
+
+        let total_weight: u32 = tied.iter().map(|b| b.weight.max(1) as u32).sum();
+        let mut roll = rng.gen_range(0..total_weight);
+        for backend in &tied {
+            let weight = backend.weight.max(1) as u32;
+            if roll < weight {
+                return Some(backend);
+            }
+            roll -= weight;
+        }
+
+        tied.last().copied()
+    }
+
+    /// Select a backend for one flow by hashing its [`FlowKey`].
+    ///
+    /// Backends are filtered exactly as in [`LoadBalancer::select`] and only
+    /// the best geo tier among the eligible ones is kept. Within that tier
+    /// the flow is mapped with weighted rendezvous hashing, so the same key
+    /// always lands on the same backend while the set is unchanged, and
+    /// heavier backends take a proportional share of flows. Load is not
+    /// considered and nothing needs to be stored to keep a flow in place.
+    pub fn select_flow<'b>(
+        candidates: &'b [Backend],
+        ctx: &SelectionContext<'_>,
+        flow: &FlowKey,
+    ) -> Option<&'b Backend> {
+        let eligible: Vec<(&Backend, f64)> = Self::evaluate(candidates, ctx)
+            .into_iter()
+            .filter(|eval| eval.verdict.is_ok())
+            .map(|eval| {
+                let tier =
+                    Self::calculate_geo_score(eval.backend, ctx.local_region, ctx.client_geo);
+                (eval.backend, tier)
+            })
+            .collect();
+        let best_tier = eligible
+            .iter()
+            .map(|(_, tier)| *tier)
+            .fold(f64::INFINITY, f64::min);
+
+        let key = flow.to_bytes();
+        Self::highest_score(
+            eligible
+                .into_iter()
+                .filter(|(_, tier)| *tier == best_tier)
+                .map(|(backend, _)| backend),
+            &key,
+        )
+    }
+
+    /// Score or exclude every backend in `candidates`, in id order.
+    ///
+    /// This is the filtering and scoring half of [`LoadBalancer::select`],
+    /// exposed so a routing decision can be explained: each backend comes
+    /// back with its score, or with the reason it was left out. Zero-weight
+    /// backends are excluded unless the fallback applies. With
+    /// `strict_country`, eligible backends outside the client's country are
+    /// excluded as long as one inside it is eligible, whatever the load.
+    pub fn evaluate<'b>(candidates: &'b [Backend], ctx: &SelectionContext<'_>) -> Vec<Evaluation<'b>> {
+        let mut evaluations: Vec<Evaluation<'b>> = candidates
+            .iter()
+            .map(|backend| {
+                let active = ctx.active_connections(&backend.id);
+                // Maintenance wins over unhealthy: a backend under maintenance
+                // is expected to be down and shouldn't read as a failure
+                let verdict = if backend.maintenance {
+                    Err(Exclusion::Maintenance)
+                } else if !backend.healthy {
+                    Err(Exclusion::Unhealthy)
+                } else if backend.draining {
+                    Err(Exclusion::Draining)
+                } else if backend.hard_limit != 0 && active as u64 >= backend.hard_limit as u64 {
+                    Err(Exclusion::HardLimit)
+                } else {
+                    Ok(Self::score(backend, ctx.local_region, ctx.client_geo, |id| {
+                        ctx.active_connections(id)
+                    }))
+                };
+                Evaluation {
+                    backend,
+                    active,
+                    verdict,
+                }
+            })
+            .collect();
+        evaluations.sort_by(|a, b| a.backend.id.cmp(&b.backend.id));
+
+        let weighted = evaluations
+            .iter()
+            .any(|e| e.verdict.is_ok() && e.backend.weight > 0);
+        if weighted || !ctx.zero_weight_fallback {
+            for eval in evaluations.iter_mut() {
+                if eval.verdict.is_ok() && eval.backend.weight == 0 {
+                    eval.verdict = Err(Exclusion::ZeroWeight);
+                }
+            }
+        }
+
+        if let Some(geo) = ctx.client_geo.filter(|_| ctx.strict_country) {
+            let in_country = |eval: &Evaluation<'_>| eval.backend.country == geo.country;
+            if evaluations.iter().any(|e| e.verdict.is_ok() && in_country(e)) {
+                for eval in evaluations.iter_mut() {
+                    if eval.verdict.is_ok() && !in_country(eval) {
+                        eval.verdict = Err(Exclusion::OtherCountry);
+                    }
+                }
+            }
+        }
+
+        evaluations
+    }
+
     /// Select the best backend for a client.
     ///
+    /// Convenience wrapper around [`LoadBalancer::select`] that reads
+    /// connection counts through a closure. Ties go to the first backend
+    /// by id.
+    ///
     /// # Arguments
     /// * `backends` - List of available backends
     /// * `local_region` - Region of the local POP (Point of Presence)
@@ -46,59 +387,97 @@ impl LoadBalancer {
     where
         F: Fn(&str) -> usize,
     {
-        let mut best: Option<(Backend, f64)> = None;
-
-        for backend in backends.iter().filter(|b| b.healthy) {
-            let current = get_conn_count(&backend.id) as f64;
-
-            // Calculate limits
-            let soft = if backend.soft_limit == 0 {
-                1.0
-            } else {
-                backend.soft_limit as f64
-            };
-
-            let hard = if backend.hard_limit == 0 {
-                f64::MAX
-            } else {
-                backend.hard_limit as f64
-            };
-
-            // Skip if at hard limit
-            if current >= hard {
-                continue;
-            }
+        let active = Self::active_counts(backends, get_conn_count);
+        let ctx = SelectionContext::new(local_region)
+            .with_client_geo(client_geo)
+            .with_active(&active);
+        Self::select(backends, ctx).cloned()
+    }
 
-            // Calculate geo score (0-3 scale, lower is better)
-            let geo_score = Self::calculate_geo_score(backend, local_region, client_geo);
+    /// Select the best backend, breaking ties with a weighted random choice.
+    ///
+    /// Scoring is identical to [`LoadBalancer::pick_backend`], but when several
+    /// backends share the best score (e.g. idle backends in the same country)
+    /// one of them is drawn at random, proportionally to its weight, instead of
+    /// always returning the first. The RNG is injected so tests can seed it;
+    /// connection selection in `ProxyService` draws from `rand::thread_rng()`.
+    pub fn pick_backend_with_rng<F, R>(
+        backends: &[Backend],
+        local_region: &RegionCode,
+        client_geo: Option<&GeoInfo>,
+        get_conn_count: F,
+        rng: &mut R,
+    ) -> Option<Backend>
+    where
+        F: Fn(&str) -> usize,
+        R: Rng + ?Sized,
+    {
+        let mut rng = rng;
+        let active = Self::active_counts(backends, get_conn_count);
+        let ctx = SelectionContext::new(local_region)
+            .with_client_geo(client_geo)
+            .with_active(&active)
+            .with_rng(&mut rng);
+        Self::select(backends, ctx).cloned()
+    }
 
-            // Calculate load factor (0.0 = empty, 1.0 = at soft limit, >1.0 = overloaded)
-            let load_factor = current / soft;
+    /// Snapshot connection counts for `backends` into a map for [`SelectionContext`].
+    pub fn active_counts<F>(backends: &[Backend], get_conn_count: F) -> HashMap<String, usize>
+    where
+        F: Fn(&str) -> usize,
+    {
+        backends
+            .iter()
+            .map(|b| (b.id.clone(), get_conn_count(&b.id)))
+            .collect()
+    }
 
-            // Weight factor (higher weight = lower score contribution)
-            let weight = if backend.weight == 0 {
-                1.0
-            } else {
-                backend.weight as f64
-            };
+    /// Map a key (e.g. a client IP) to a backend with weighted rendezvous hashing.
+    ///
+    /// Every backend gets a pseudo-random score for the key, scaled by its
+    /// weight (`weight / -ln(u)` with `u` uniform in (0, 1)), and the highest
+    /// score wins. Heavier backends therefore attract a proportional share
+    /// of keys, and when a backend is added or removed only the keys it
+    /// gains or loses move. The hash is fixed, so every node maps a key the
+    /// same way. Unhealthy, draining and zero-weight backends are skipped.
+    pub fn rendezvous<'b>(candidates: &'b [Backend], key: &str) -> Option<&'b Backend> {
+        Self::highest_score(Self::candidates(candidates), key.as_bytes())
+    }
 
-            // Final score: geo_score * 100 + (load_factor / weight)
-            // - Geo has much higher priority (100x multiplier)
-            // - Load and weight fine-tune within the same geo tier
-            let score = geo_score * 100.0 + (load_factor / weight);
+    /// Backend with the highest rendezvous score for `key`, from backends
+    /// given in id order.
+    fn highest_score<'b, I>(backends: I, key: &[u8]) -> Option<&'b Backend>
+    where
+        I: IntoIterator<Item = &'b Backend>,
+    {
+        backends
+            .into_iter()
+            .map(|backend| (backend, Self::rendezvous_score(backend, key)))
+            // Strictly greater, so exact ties go to the first backend by id
+            .fold(None, |best: Option<(&Backend, f64)>, (backend, score)| match best {
+                Some((_, best_score)) if score <= best_score => best,
+                _ => Some((backend, score)),
+            })
+            .map(|(backend, _)| backend)
+    }
 
-            match &best {
-                Some((_, best_score)) if score < *best_score => {
-                    best = Some((backend.clone(), score));
-                }
-                None => {
-                    best = Some((backend.clone(), score));
-                }
-                _ => {}
-            }
-        }
+    /// Weighted rendezvous score of a backend for a key (higher wins).
+    fn rendezvous_score(backend: &Backend, key: &[u8]) -> f64 {
+        let hash = stable_hash(&[key, &[0xff], backend.id.as_bytes()]);
+        // Top 53 bits as a float strictly inside (0, 1)
+        let unit = ((hash >> 11) as f64 + 0.5) / (1u64 << 53) as f64;
+        let weight = backend.weight.max(1) as f64;
+        weight / -unit.ln()
+    }
 
-        best.map(|(backend, _)| backend)
+    /// Backends that may take new connections, sorted by id.
+    fn candidates(backends: &[Backend]) -> Vec<&Backend> {
+        let mut candidates: Vec<&Backend> = backends
+            .iter()
+            .filter(|b| b.accepts_new_connections() && b.weight > 0)
+            .collect();
+        candidates.sort_by(|a, b| a.id.cmp(&b.id));
+        candidates
     }
 
     /// Calculate geographic score for a backend.
@@ -107,7 +486,11 @@ impl LoadBalancer {
     /// - 0.0: Same country as client (best)
     /// - 1.0: Same region as client
     /// - 2.0: Same region as local POP
-    /// - 3.0: Fallback (different region)
+    /// - 3.0 + n: n-th region in the client region's fallback chain
+    /// - after the chain: any other region (best effort)
+    ///
+    /// The client region is the resolved geo region, or the local POP's
+    /// region when the client couldn't be located.
     fn calculate_geo_score(
         backend: &Backend,
         local_region: &RegionCode,
@@ -120,12 +503,48 @@ impl LoadBalancer {
             Some(geo) if backend.region == geo.region => 1.0,
             // OK: backend is in the same region as the local POP
             _ if backend.region == *local_region => 2.0,
-            // Fallback: different region entirely
-            _ => 3.0,
+            // Fallback: nearest regions first, then anything left
+            _ => {
+                let chain = client_geo
+                    .map_or(local_region, |geo| &geo.region)
+                    .fallback_chain();
+                let tier = chain
+                    .iter()
+                    .position(|region| *region == backend.region)
+                    .unwrap_or(chain.len());
+                3.0 + tier as f64
+            }
+        }
+    }
+
+    /// Classify a selection relative to the client's region.
+    ///
+    /// Lets callers tell "the client's region is empty" (served from a
+    /// fallback) apart from "there are no backends at all" (`None`).
+    pub fn outcome(
+        selected: Option<&Backend>,
+        local_region: &RegionCode,
+        client_geo: Option<&GeoInfo>,
+    ) -> SelectionOutcome {
+        let Some(backend) = selected else {
+            return SelectionOutcome::NoBackend;
+        };
+        let client_region = client_geo.map_or(local_region, |geo| &geo.region);
+
+        if backend.region == *client_region
+            || client_geo.is_some_and(|geo| geo.country == backend.country)
+        {
+            SelectionOutcome::InRegion
+        } else if backend.region == *local_region
+            || client_region.fallback_chain().contains(&backend.region)
+        {
+            SelectionOutcome::RegionFallback
+        } else {
+            SelectionOutcome::AnyRegion
         }
     }
 
-    /// Calculate scores for all backends (useful for debugging/metrics).
+    /// Calculate scores for all backends, in id order (useful for debugging/metrics).
     #[allow(dead_code)]
     pub fn calculate_all_scores<F>(
         backends: &[Backend],
@@ -136,35 +555,48 @@ impl LoadBalancer {
     where
         F: Fn(&str) -> usize,
     {
-        backends
-            .iter()
-            .filter(|b| b.healthy)
+        Self::candidates(backends)
+            .into_iter()
             .map(|backend| {
-                let current = get_conn_count(&backend.id) as f64;
-                let soft = if backend.soft_limit == 0 {
-                    1.0
-                } else {
-                    backend.soft_limit as f64
-                };
-                let weight = if backend.weight == 0 {
-                    1.0
-                } else {
-                    backend.weight as f64
-                };
-
-                let geo_score = Self::calculate_geo_score(backend, local_region, client_geo);
-                let load_factor = current / soft;
-                let score = geo_score * 100.0 + (load_factor / weight);
-
+                let score = Self::score(backend, local_region, client_geo, &get_conn_count);
                 (backend.id.clone(), score)
             })
             .collect()
     }
+
+    /// Score a single backend (geo tier * 100 + load / weight).
+    fn score<F>(
+        backend: &Backend,
+        local_region: &RegionCode,
+        client_geo: Option<&GeoInfo>,
+        get_conn_count: F,
+    ) -> f64
+    where
+        F: Fn(&str) -> usize,
+    {
+        let current = get_conn_count(&backend.id) as f64;
+        let soft = if backend.soft_limit == 0 {
+            1.0
+        } else {
+            backend.soft_limit as f64
+        };
+        let weight = if backend.weight == 0 {
+            1.0
+        } else {
+            backend.weight as f64
+        };
+
+        let geo_score = Self::calculate_geo_score(backend, local_region, client_geo);
+        let load_factor = current / soft;
+        geo_score * 100.0 + (load_factor / weight)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
 
     // ===== Test Helpers =====
 
@@ -172,7 +604,7 @@ mod tests {
         Backend {
             id: id.to_string(),
             app: "test".to_string(),
-            region: RegionCode::from_str(region),
+            region: RegionCode::from_code(region),
             country: country.to_string(),
             wg_ip: "10.0.0.1".to_string(),
             port: 8080,
@@ -180,6 +612,9 @@ mod tests {
             weight: 1,
             soft_limit: 100,
             hard_limit: 200,
+            draining: false,
+            maintenance: false,
+            priority: 0,
         }
     }
 
@@ -194,7 +629,7 @@ mod tests {
         Backend {
             id: id.to_string(),
             app: "test".to_string(),
-            region: RegionCode::from_str(region),
+            region: RegionCode::from_code(region),
             country: country.to_string(),
             wg_ip: "10.0.0.1".to_string(),
             port: 8080,
@@ -202,6 +637,9 @@ mod tests {
             weight,
             soft_limit,
             hard_limit,
+            draining: false,
+            maintenance: false,
+            priority: 0,
         }
     }
 
@@ -373,6 +811,42 @@ mod tests {
         assert_eq!(result.unwrap().id, "us-1");
     }
 
+    #[test]
+    fn test_pick_backend_skips_draining() {
+        let mut draining = create_backend("br-1", "sa", "BR", true);
+        draining.draining = true;
+        let backends = vec![draining, create_backend("us-1", "us", "US", true)];
+
+        let client_geo = GeoInfo::new("BR".to_string(), RegionCode::SouthAmerica);
+
+        let result = LoadBalancer::pick_backend(
+            &backends,
+            &RegionCode::SouthAmerica,
+            Some(&client_geo),
+            |_| 0,
+        );
+
+        assert_eq!(result.unwrap().id, "us-1");
+    }
+
+    #[test]
+    fn test_pick_backend_skips_maintenance() {
+        let mut maintenance = create_backend("br-1", "sa", "BR", true);
+        maintenance.maintenance = true;
+        let backends = vec![maintenance, create_backend("us-1", "us", "US", true)];
+
+        let client_geo = GeoInfo::new("BR".to_string(), RegionCode::SouthAmerica);
+
+        let result = LoadBalancer::pick_backend(
+            &backends,
+            &RegionCode::SouthAmerica,
+            Some(&client_geo),
+            |_| 0,
+        );
+
+        assert_eq!(result.unwrap().id, "us-1");
+    }
+
     #[test]
     fn test_pick_backend_all_unhealthy() {
         let backends = vec![
@@ -436,7 +910,7 @@ mod tests {
     }
 
     #[test]
-    fn test_pick_backend_zero_weight_treated_as_one() {
+    fn test_pick_backend_zero_weight_only_picks_none() {
         let mut backend = create_backend("br-1", "sa", "BR", true);
         backend.weight = 0;
 
@@ -449,7 +923,75 @@ mod tests {
             |_| 50,
         );
 
-        assert!(result.is_some());
+        assert!(result.is_none());
+    }
+
+    #[test]
+    fn test_select_skips_zero_weight_while_weighted_eligible() {
+        let backends = vec![
+            create_backend_with_limits("br-1", "sa", "BR", 0, 100, 200),
+            create_backend_with_limits("us-1", "us", "US", 1, 100, 200),
+        ];
+        let geo = GeoInfo::new("BR".to_string(), RegionCode::SouthAmerica);
+        let ctx = SelectionContext::new(&RegionCode::SouthAmerica)
+            .with_client_geo(Some(&geo))
+            .with_zero_weight_fallback(true);
+
+        // The fallback only applies when no weighted backend is eligible
+        assert_eq!(LoadBalancer::select(&backends, ctx).unwrap().id, "us-1");
+
+        let ctx = SelectionContext::new(&RegionCode::SouthAmerica);
+        let evaluations = LoadBalancer::evaluate(&backends, &ctx);
+        assert_eq!(evaluations[0].verdict, Err(Exclusion::ZeroWeight));
+        assert!(evaluations[1].verdict.is_ok());
+    }
+
+    #[test]
+    fn test_select_all_zero_weight() {
+        let backends: Vec<Backend> = ["b1", "b2", "b3"]
+            .iter()
+            .map(|id| create_backend_with_limits(id, "sa", "BR", 0, 100, 200))
+            .collect();
+
+        // Rejected by default
+        let ctx = SelectionContext::new(&RegionCode::SouthAmerica);
+        assert!(LoadBalancer::select(&backends, ctx).is_none());
+        let mut rng = StdRng::seed_from_u64(7);
+        let ctx = SelectionContext::new(&RegionCode::SouthAmerica).with_rng(&mut rng);
+        assert!(LoadBalancer::select(&backends, ctx).is_none());
+
+        // With the fallback, scored and drawn as equal weights
+        let ctx = SelectionContext::new(&RegionCode::SouthAmerica).with_zero_weight_fallback(true);
+        assert_eq!(LoadBalancer::select(&backends, ctx).unwrap().id, "b1");
+
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for _ in 0..3000 {
+            let ctx = SelectionContext::new(&RegionCode::SouthAmerica)
+                .with_zero_weight_fallback(true)
+                .with_rng(&mut rng);
+            let id = LoadBalancer::select(&backends, ctx).unwrap().id.clone();
+            *counts.entry(id).or_default() += 1;
+        }
+        assert_eq!(counts.len(), 3);
+        assert!(counts.values().all(|&n| (800..1200).contains(&n)), "{:?}", counts);
+    }
+
+    #[test]
+    fn test_select_zero_weight_fallback_skips_ineligible() {
+        let mut unhealthy = create_backend_with_limits("b1", "sa", "BR", 0, 100, 200);
+        unhealthy.healthy = false;
+        let backends = vec![
+            unhealthy,
+            create_backend_with_limits("b2", "sa", "BR", 0, 100, 1),
+            create_backend_with_limits("b3", "sa", "BR", 0, 100, 200),
+        ];
+        let active: HashMap<String, usize> = [("b2".to_string(), 1)].into();
+
+        let ctx = SelectionContext::new(&RegionCode::SouthAmerica)
+            .with_active(&active)
+            .with_zero_weight_fallback(true);
+        assert_eq!(LoadBalancer::select(&backends, ctx).unwrap().id, "b3");
     }
 
     #[test]
@@ -591,7 +1133,7 @@ mod tests {
     #[test]
     fn test_calculate_all_scores_with_zero_weight() {
         let mut backend = create_backend("br-1", "sa", "BR", true);
-        backend.weight = 0; // should be treated as 1
+        backend.weight = 0; // takes no new clients
 
         let backends = vec![backend];
 
@@ -602,8 +1144,7 @@ mod tests {
             |_| 50,
         );
 
-        assert_eq!(scores.len(), 1);
-        assert_eq!(scores[0].0, "br-1");
+        assert!(scores.is_empty());
     }
 
     #[test]
@@ -726,4 +1267,804 @@ mod tests {
         // All have same score, first one wins
         assert_eq!(result.unwrap().id, "br-1");
     }
+
+    // ===== Deterministic Ordering Tests =====
+
+    #[test]
+    fn test_pick_backend_tie_is_independent_of_input_order() {
+        let forward = vec![
+            create_backend("br-a", "sa", "BR", true),
+            create_backend("br-b", "sa", "BR", true),
+            create_backend("br-c", "sa", "BR", true),
+        ];
+        let mut reversed = forward.clone();
+        reversed.reverse();
+        let client_geo = GeoInfo::new("BR".to_string(), RegionCode::SouthAmerica);
+
+        for backends in [&forward, &reversed] {
+            let result = LoadBalancer::pick_backend(
+                backends,
+                &RegionCode::SouthAmerica,
+                Some(&client_geo),
+                |_| 0,
+            );
+            assert_eq!(result.unwrap().id, "br-a");
+        }
+    }
+
+    #[test]
+    fn test_pick_backend_with_rng_sequence_is_independent_of_input_order() {
+        let forward = vec![
+            create_backend_with_limits("br-1", "sa", "BR", 1, 100, 200),
+            create_backend_with_limits("br-2", "sa", "BR", 3, 100, 200),
+            create_backend_with_limits("br-3", "sa", "BR", 2, 100, 200),
+        ];
+        let shuffled = vec![forward[2].clone(), forward[0].clone(), forward[1].clone()];
+
+        assert_eq!(
+            seeded_sequence(&forward, 11, 32),
+            seeded_sequence(&shuffled, 11, 32)
+        );
+    }
+
+    #[test]
+    fn test_calculate_all_scores_sorted_by_id() {
+        let backends = vec![
+            create_backend("us-1", "us", "US", true),
+            create_backend("br-1", "sa", "BR", true),
+            create_backend("jp-1", "ap", "JP", true),
+        ];
+
+        let scores = LoadBalancer::calculate_all_scores(
+            &backends,
+            &RegionCode::SouthAmerica,
+            None,
+            |_| 0,
+        );
+
+        let ids: Vec<&str> = scores.iter().map(|(id, _)| id.as_str()).collect();
+        assert_eq!(ids, vec!["br-1", "jp-1", "us-1"]);
+    }
+
+    // ===== Seeded RNG Tie-Break Tests =====
+
+    fn seeded_sequence(backends: &[Backend], seed: u64, picks: usize) -> Vec<String> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let client_geo = GeoInfo::new("BR".to_string(), RegionCode::SouthAmerica);
+        (0..picks)
+            .map(|_| {
+                LoadBalancer::pick_backend_with_rng(
+                    backends,
+                    &RegionCode::SouthAmerica,
+                    Some(&client_geo),
+                    |_| 0,
+                    &mut rng,
+                )
+                .unwrap()
+                .id
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_pick_backend_with_rng_seeded_sequence() {
+        let backends = vec![
+            create_backend("br-1", "sa", "BR", true),
+            create_backend("br-2", "sa", "BR", true),
+            create_backend("br-3", "sa", "BR", true),
+        ];
+
+        let sequence = seeded_sequence(&backends, 42, 8);
+
+        assert_eq!(
+            sequence,
+            vec!["br-1", "br-2", "br-1", "br-2", "br-3", "br-2", "br-1", "br-2"]
+        );
+    }
+
+    #[test]
+    fn test_pick_backend_with_rng_same_seed_is_reproducible() {
+        let backends = vec![
+            create_backend("br-1", "sa", "BR", true),
+            create_backend("br-2", "sa", "BR", true),
+            create_backend("br-3", "sa", "BR", true),
+        ];
+
+        assert_eq!(
+            seeded_sequence(&backends, 7, 32),
+            seeded_sequence(&backends, 7, 32)
+        );
+    }
+
+    #[test]
+    fn test_pick_backend_with_rng_weighted_tie_break() {
+        let backends = vec![
+            create_backend_with_limits("br-light", "sa", "BR", 1, 100, 200),
+            create_backend_with_limits("br-heavy", "sa", "BR", 9, 100, 200),
+        ];
+
+        let sequence = seeded_sequence(&backends, 1, 1000);
+        let heavy = sequence.iter().filter(|id| *id == "br-heavy").count();
+
+        // 9:1 weight ratio -> roughly 90% of ties go to the heavier backend
+        assert_eq!(heavy, 899);
+    }
+
+    #[test]
+    fn test_pick_backend_with_rng_best_score_wins_without_tie() {
+        let backends = vec![
+            create_backend("us-1", "us", "US", true),
+            create_backend("br-1", "sa", "BR", true),
+        ];
+
+        // Only one backend in the best tier: the RNG must not matter
+        let sequence = seeded_sequence(&backends, 99, 16);
+        assert!(sequence.iter().all(|id| id == "br-1"));
+    }
+
+    #[test]
+    fn test_pick_backend_with_rng_skips_unhealthy_and_hard_limit() {
+        let backends = vec![
+            create_backend("br-down", "sa", "BR", false),
+            create_backend("br-full", "sa", "BR", true),
+            create_backend("br-ok", "sa", "BR", true),
+        ];
+        let mut rng = StdRng::seed_from_u64(3);
+
+        for _ in 0..16 {
+            let result = LoadBalancer::pick_backend_with_rng(
+                &backends,
+                &RegionCode::SouthAmerica,
+                None,
+                |id| if id == "br-full" { 200 } else { 0 },
+                &mut rng,
+            );
+            assert_eq!(result.unwrap().id, "br-ok");
+        }
+    }
+
+    #[test]
+    fn test_pick_backend_with_rng_skips_draining() {
+        let mut draining = create_backend("br-drain", "sa", "BR", true);
+        draining.draining = true;
+        let backends = vec![draining, create_backend("br-ok", "sa", "BR", true)];
+        let mut rng = StdRng::seed_from_u64(5);
+
+        for _ in 0..16 {
+            let result = LoadBalancer::pick_backend_with_rng(
+                &backends,
+                &RegionCode::SouthAmerica,
+                None,
+                |_| 0,
+                &mut rng,
+            );
+            assert_eq!(result.unwrap().id, "br-ok");
+        }
+    }
+
+    #[test]
+    fn test_pick_backend_with_rng_no_candidates() {
+        let backends = vec![create_backend("br-1", "sa", "BR", false)];
+        let mut rng = StdRng::seed_from_u64(0);
+
+        let result = LoadBalancer::pick_backend_with_rng(
+            &backends,
+            &RegionCode::SouthAmerica,
+            None,
+            |_| 0,
+            &mut rng,
+        );
+
+        assert!(result.is_none());
+    }
+
+    // ===== Pure Selection Tests =====
+
+    fn active(counts: &[(&str, usize)]) -> HashMap<String, usize> {
+        counts.iter().map(|(id, n)| (id.to_string(), *n)).collect()
+    }
+
+    #[test]
+    fn test_select_returns_reference_into_candidates() {
+        let candidates = vec![
+            create_backend("us-1", "us", "US", true),
+            create_backend("br-1", "sa", "BR", true),
+        ];
+        let geo = GeoInfo::new("BR".to_string(), RegionCode::SouthAmerica);
+        let ctx = SelectionContext::new(&RegionCode::NorthAmerica).with_client_geo(Some(&geo));
+
+        let selected = LoadBalancer::select(&candidates, ctx).unwrap();
+        assert!(std::ptr::eq(selected, &candidates[1]));
+    }
+
+    #[test]
+    fn test_select_geo_tiers_without_client_geo() {
+        let candidates = vec![
+            create_backend("eu-1", "eu", "DE", true),
+            create_backend("us-1", "us", "US", true),
+        ];
+
+        let ctx = SelectionContext::new(&RegionCode::NorthAmerica);
+        assert_eq!(LoadBalancer::select(&candidates, ctx).unwrap().id, "us-1");
+
+        let ctx = SelectionContext::new(&RegionCode::Europe);
+        assert_eq!(LoadBalancer::select(&candidates, ctx).unwrap().id, "eu-1");
+    }
+
+    #[test]
+    fn test_select_uses_active_counts() {
+        let candidates = vec![
+            create_backend("br-1", "sa", "BR", true),
+            create_backend("br-2", "sa", "BR", true),
+        ];
+        let counts = active(&[("br-1", 50), ("br-2", 10)]);
+        let ctx = SelectionContext::new(&RegionCode::SouthAmerica).with_active(&counts);
+
+        assert_eq!(LoadBalancer::select(&candidates, ctx).unwrap().id, "br-2");
+    }
+
+    #[test]
+    fn test_select_missing_active_count_is_zero() {
+        let candidates = vec![
+            create_backend("br-1", "sa", "BR", true),
+            create_backend("br-2", "sa", "BR", true),
+        ];
+        let counts = active(&[("br-1", 1)]);
+        let ctx = SelectionContext::new(&RegionCode::SouthAmerica).with_active(&counts);
+
+        assert_eq!(LoadBalancer::select(&candidates, ctx).unwrap().id, "br-2");
+    }
+
+    #[test]
+    fn test_select_skips_hard_limited_unhealthy_and_draining() {
+        let mut draining = create_backend("br-drain", "sa", "BR", true);
+        draining.draining = true;
+        let candidates = vec![
+            draining,
+            create_backend("br-down", "sa", "BR", false),
+            create_backend("br-full", "sa", "BR", true),
+            create_backend("us-1", "us", "US", true),
+        ];
+        let counts = active(&[("br-full", 200)]);
+        let ctx = SelectionContext::new(&RegionCode::SouthAmerica).with_active(&counts);
+
+        assert_eq!(LoadBalancer::select(&candidates, ctx).unwrap().id, "us-1");
+    }
+
+    #[test]
+    fn test_evaluate_reports_exclusions_and_scores() {
+        let mut draining = create_backend("br-drain", "sa", "BR", true);
+        draining.draining = true;
+        let candidates = vec![
+            create_backend("us-1", "us", "US", true),
+            draining,
+            create_backend("br-down", "sa", "BR", false),
+            create_backend("br-full", "sa", "BR", true),
+        ];
+        let counts = active(&[("br-full", 200), ("us-1", 5)]);
+        let ctx = SelectionContext::new(&RegionCode::SouthAmerica).with_active(&counts);
+
+        let evals = LoadBalancer::evaluate(&candidates, &ctx);
+        let ids: Vec<&str> = evals.iter().map(|e| e.backend.id.as_str()).collect();
+        assert_eq!(ids, vec!["br-down", "br-drain", "br-full", "us-1"]);
+
+        assert_eq!(evals[0].verdict, Err(Exclusion::Unhealthy));
+        assert_eq!(evals[1].verdict, Err(Exclusion::Draining));
+        assert_eq!(evals[2].verdict, Err(Exclusion::HardLimit));
+        assert_eq!(evals[2].active, 200);
+        assert_eq!(evals[3].active, 5);
+        assert!(evals[3].verdict.is_ok());
+    }
+
+    #[test]
+    fn test_evaluate_reports_maintenance_not_unhealthy() {
+        // A backend under maintenance is usually down too; it's still reported
+        // as maintenance so it doesn't count as a failure
+        let mut maintenance = create_backend("br-maint", "sa", "BR", false);
+        maintenance.maintenance = true;
+        let candidates = vec![maintenance, create_backend("us-1", "us", "US", true)];
+        let ctx = SelectionContext::new(&RegionCode::SouthAmerica);
+
+        let evals = LoadBalancer::evaluate(&candidates, &ctx);
+        assert_eq!(evals[0].verdict, Err(Exclusion::Maintenance));
+        assert!(evals[1].verdict.is_ok());
+        assert_eq!(LoadBalancer::select(&candidates, ctx).unwrap().id, "us-1");
+    }
+
+    #[test]
+    fn test_evaluate_agrees_with_select() {
+        let candidates = vec![
+            create_backend("br-1", "sa", "BR", true),
+            create_backend("us-1", "us", "US", true),
+            create_backend("eu-1", "eu", "DE", true),
+        ];
+        let geo = GeoInfo::new("US".to_string(), RegionCode::NorthAmerica);
+        let ctx = SelectionContext::new(&RegionCode::SouthAmerica).with_client_geo(Some(&geo));
+
+        let best = LoadBalancer::evaluate(&candidates, &ctx)
+            .into_iter()
+            .filter_map(|e| e.verdict.ok().map(|score| (e.backend, score)))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .unwrap();
+        assert_eq!(LoadBalancer::select(&candidates, ctx).unwrap().id, best.0.id);
+    }
+
+    #[test]
+    fn test_exclusion_labels() {
+        assert_eq!(Exclusion::Maintenance.as_str(), "maintenance");
+        assert_eq!(Exclusion::Unhealthy.as_str(), "unhealthy");
+        assert_eq!(Exclusion::Draining.to_string(), "draining");
+        assert_eq!(Exclusion::HardLimit.as_str(), "hard_limit");
+        assert_eq!(Exclusion::OtherCountry.as_str(), "other_country");
+    }
+
+    // ===== Strict Country Tests =====
+
+    /// An overloaded backend in the client's country next to an idle one
+    /// elsewhere in the client's region.
+    fn country_vs_region() -> (Vec<Backend>, HashMap<String, usize>) {
+        let candidates = vec![
+            create_backend_with_limits("br-1", "sa", "BR", 1, 1, 0),
+            create_backend_with_limits("ar-1", "sa", "AR", 1, 100, 0),
+        ];
+        // 150x the soft limit outweighs the country tier's head start
+        (candidates, active(&[("br-1", 150)]))
+    }
+
+    #[test]
+    fn test_country_tier_is_soft_by_default() {
+        let (candidates, counts) = country_vs_region();
+        let geo = GeoInfo::new("BR".to_string(), RegionCode::SouthAmerica);
+        let ctx = SelectionContext::new(&RegionCode::SouthAmerica)
+            .with_client_geo(Some(&geo))
+            .with_active(&counts);
+
+        assert_eq!(LoadBalancer::select(&candidates, ctx).unwrap().id, "ar-1");
+    }
+
+    #[test]
+    fn test_strict_country_prefers_same_country_over_load() {
+        let (candidates, counts) = country_vs_region();
+        let geo = GeoInfo::new("BR".to_string(), RegionCode::SouthAmerica);
+        let ctx = SelectionContext::new(&RegionCode::SouthAmerica)
+            .with_client_geo(Some(&geo))
+            .with_active(&counts)
+            .with_strict_country(true);
+
+        let evals = LoadBalancer::evaluate(&candidates, &ctx);
+        assert_eq!(evals[0].backend.id, "ar-1");
+        assert_eq!(evals[0].verdict, Err(Exclusion::OtherCountry));
+        assert!(evals[1].verdict.is_ok());
+
+        assert_eq!(LoadBalancer::select(&candidates, ctx).unwrap().id, "br-1");
+    }
+
+    #[test]
+    fn test_strict_country_falls_back_to_same_region() {
+        let candidates = vec![
+            create_backend("br-down", "sa", "BR", false),
+            create_backend("ar-1", "sa", "AR", true),
+            create_backend("us-1", "us", "US", true),
+        ];
+        let geo = GeoInfo::new("BR".to_string(), RegionCode::SouthAmerica);
+        let ctx = SelectionContext::new(&RegionCode::NorthAmerica)
+            .with_client_geo(Some(&geo))
+            .with_strict_country(true);
+
+        // No eligible backend in BR, so nothing is excluded for its country
+        let evals = LoadBalancer::evaluate(&candidates, &ctx);
+        assert!(evals.iter().all(|e| e.verdict != Err(Exclusion::OtherCountry)));
+        assert_eq!(LoadBalancer::select(&candidates, ctx).unwrap().id, "ar-1");
+    }
+
+    #[test]
+    fn test_strict_country_needs_client_geo() {
+        let (candidates, counts) = country_vs_region();
+        let ctx = SelectionContext::new(&RegionCode::SouthAmerica)
+            .with_active(&counts)
+            .with_strict_country(true);
+
+        assert_eq!(LoadBalancer::select(&candidates, ctx).unwrap().id, "ar-1");
+    }
+
+    #[test]
+    fn test_select_no_candidates() {
+        let ctx = SelectionContext::new(&RegionCode::SouthAmerica);
+        assert!(LoadBalancer::select(&[], ctx).is_none());
+
+        let candidates = vec![create_backend("br-1", "sa", "BR", false)];
+        let ctx = SelectionContext::new(&RegionCode::SouthAmerica);
+        assert!(LoadBalancer::select(&candidates, ctx).is_none());
+    }
+
+    #[test]
+    fn test_select_tie_without_rng_goes_to_first_id() {
+        let candidates = vec![
+            create_backend("br-2", "sa", "BR", true),
+            create_backend("br-1", "sa", "BR", true),
+        ];
+
+        for _ in 0..8 {
+            let ctx = SelectionContext::new(&RegionCode::SouthAmerica);
+            assert_eq!(LoadBalancer::select(&candidates, ctx).unwrap().id, "br-1");
+        }
+    }
+
+    #[test]
+    fn test_select_tie_with_rng_is_weighted() {
+        let candidates = vec![
+            create_backend_with_limits("br-light", "sa", "BR", 1, 100, 200),
+            create_backend_with_limits("br-heavy", "sa", "BR", 3, 100, 200),
+        ];
+        let mut rng = StdRng::seed_from_u64(11);
+
+        let heavy = (0..400)
+            .filter(|_| {
+                let ctx = SelectionContext::new(&RegionCode::SouthAmerica).with_rng(&mut rng);
+                LoadBalancer::select(&candidates, ctx).unwrap().id == "br-heavy"
+            })
+            .count();
+
+        // 3:1 weight ratio -> roughly 75% of ties
+        assert!((260..340).contains(&heavy), "heavy picked {} times", heavy);
+    }
+
+    #[test]
+    fn test_select_client_ip_does_not_change_scoring() {
+        let candidates = vec![
+            create_backend("br-1", "sa", "BR", true),
+            create_backend("us-1", "us", "US", true),
+        ];
+
+        for ip in ["10.0.0.1", "203.0.113.7", "::1"] {
+            let ctx = SelectionContext::new(&RegionCode::NorthAmerica)
+                .with_client_ip(Some(ip.parse().unwrap()));
+            assert_eq!(LoadBalancer::select(&candidates, ctx).unwrap().id, "us-1");
+        }
+    }
+
+    // ===== Empty Region Fallback Tests =====
+
+    #[test]
+    fn test_empty_region_follows_fallback_chain() {
+        // No backends in sa: us comes before eu in sa's chain, ap is last
+        let backends = vec![
+            create_backend("ap-1", "ap", "JP", true),
+            create_backend("eu-1", "eu", "DE", true),
+            create_backend("us-1", "us", "US", true),
+        ];
+        let client_geo = GeoInfo::new("BR".to_string(), RegionCode::SouthAmerica);
+
+        let scores = LoadBalancer::calculate_all_scores(
+            &backends,
+            &RegionCode::AsiaPacific,
+            Some(&client_geo),
+            |_| 0,
+        );
+        let score = |id: &str| scores.iter().find(|(b, _)| b == id).unwrap().1;
+
+        // The local POP region still beats the chain
+        assert!(score("ap-1") < score("us-1"));
+        assert!(score("us-1") < score("eu-1"));
+
+        let result =
+            LoadBalancer::pick_backend(&backends, &RegionCode::Europe, Some(&client_geo), |_| 0);
+        assert_eq!(result.unwrap().id, "eu-1");
+
+        let without_local: Vec<Backend> =
+            backends.iter().filter(|b| b.id != "ap-1").cloned().collect();
+        let result = LoadBalancer::pick_backend(
+            &without_local,
+            &RegionCode::AsiaPacific,
+            Some(&client_geo),
+            |_| 0,
+        );
+        assert_eq!(result.unwrap().id, "us-1");
+    }
+
+    #[test]
+    fn test_empty_region_falls_back_to_any_region() {
+        // ap is not in eu's chain, but it's still better than nothing
+        let backends = vec![create_backend("ap-1", "ap", "JP", true)];
+        let client_geo = GeoInfo::new("DE".to_string(), RegionCode::Europe);
+
+        let result =
+            LoadBalancer::pick_backend(&backends, &RegionCode::Europe, Some(&client_geo), |_| 0);
+        assert_eq!(result.unwrap().id, "ap-1");
+    }
+
+    #[test]
+    fn test_fallback_chain_beats_load() {
+        let backends = vec![
+            create_backend("ap-1", "ap", "JP", true),
+            create_backend("us-1", "us", "US", true),
+        ];
+        let client_geo = GeoInfo::new("DE".to_string(), RegionCode::Europe);
+
+        // us is in eu's chain, so even a loaded us backend beats idle ap
+        let result = LoadBalancer::pick_backend(
+            &backends,
+            &RegionCode::Europe,
+            Some(&client_geo),
+            |id| if id == "us-1" { 150 } else { 0 },
+        );
+        assert_eq!(result.unwrap().id, "us-1");
+    }
+
+    #[test]
+    fn test_outcome_classification() {
+        let br = create_backend("br-1", "sa", "BR", true);
+        let us = create_backend("us-1", "us", "US", true);
+        let eu = create_backend("eu-1", "eu", "DE", true);
+        let ap = create_backend("ap-1", "ap", "JP", true);
+        let client_geo = GeoInfo::new("BR".to_string(), RegionCode::SouthAmerica);
+        let local = RegionCode::AsiaPacific;
+
+        let outcome = |b: Option<&Backend>| LoadBalancer::outcome(b, &local, Some(&client_geo));
+        assert_eq!(outcome(Some(&br)), SelectionOutcome::InRegion);
+        assert_eq!(outcome(Some(&us)), SelectionOutcome::RegionFallback);
+        assert_eq!(outcome(Some(&eu)), SelectionOutcome::RegionFallback);
+        // Local POP region counts as a fallback for a remote client
+        assert_eq!(outcome(Some(&ap)), SelectionOutcome::RegionFallback);
+        assert_eq!(outcome(None), SelectionOutcome::NoBackend);
+
+        // Without geo, the local region is the client's region
+        assert_eq!(
+            LoadBalancer::outcome(Some(&ap), &RegionCode::Europe, None),
+            SelectionOutcome::AnyRegion
+        );
+        assert_eq!(
+            LoadBalancer::outcome(Some(&eu), &RegionCode::Europe, None),
+            SelectionOutcome::InRegion
+        );
+    }
+
+    // ===== Weighted Rendezvous Tests =====
+
+    fn rendezvous_map(backends: &[Backend], keys: usize) -> Vec<String> {
+        (0..keys)
+            .map(|i| {
+                let key = format!("10.{}.{}.{}", i >> 16, (i >> 8) & 0xff, i & 0xff);
+                LoadBalancer::rendezvous(backends, &key).unwrap().id.clone()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_stable_hash_is_fixed() {
+        // Pinned so a change to the hash (which would remap every key) is noticed
+        assert_eq!(stable_hash(&[b""]), 0xf52a_15e9_a9b5_e89b);
+        assert_eq!(stable_hash(&[b"a", b"b"]), stable_hash(&[b"ab"]));
+        assert_ne!(stable_hash(&[b"ab"]), stable_hash(&[b"ba"]));
+    }
+
+    #[test]
+    fn test_rendezvous_is_deterministic_and_order_independent() {
+        let backends = vec![
+            create_backend("b1", "sa", "BR", true),
+            create_backend("b2", "sa", "BR", true),
+            create_backend("b3", "sa", "BR", true),
+        ];
+        let mut reversed = backends.clone();
+        reversed.reverse();
+
+        assert_eq!(rendezvous_map(&backends, 500), rendezvous_map(&reversed, 500));
+    }
+
+    #[test]
+    fn test_rendezvous_distribution_follows_weight() {
+        let backends = vec![
+            create_backend_with_limits("w1", "sa", "BR", 1, 100, 200),
+            create_backend_with_limits("w2", "sa", "BR", 2, 100, 200),
+            create_backend_with_limits("w3", "sa", "BR", 3, 100, 200),
+        ];
+        let keys = 60_000;
+        let mapping = rendezvous_map(&backends, keys);
+
+        for (id, weight) in [("w1", 1.0), ("w2", 2.0), ("w3", 3.0)] {
+            let share = mapping.iter().filter(|m| *m == id).count() as f64 / keys as f64;
+            let expected = weight / 6.0;
+            assert!(
+                (share - expected).abs() < 0.02,
+                "{} got {:.3} of keys, expected {:.3}",
+                id,
+                share,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_rendezvous_equal_weights_spread_evenly() {
+        let backends: Vec<Backend> = (0..4)
+            .map(|i| create_backend(&format!("b{}", i), "sa", "BR", true))
+            .collect();
+        let keys = 40_000;
+        let mapping = rendezvous_map(&backends, keys);
+
+        for backend in &backends {
+            let share = mapping.iter().filter(|m| **m == backend.id).count() as f64 / keys as f64;
+            assert!((share - 0.25).abs() < 0.02, "{} got {:.3}", backend.id, share);
+        }
+    }
+
+    #[test]
+    fn test_rendezvous_minimal_remap_on_removal() {
+        let backends = vec![
+            create_backend_with_limits("w1", "sa", "BR", 1, 100, 200),
+            create_backend_with_limits("w2", "sa", "BR", 2, 100, 200),
+            create_backend_with_limits("w3", "sa", "BR", 3, 100, 200),
+            create_backend_with_limits("w4", "sa", "BR", 4, 100, 200),
+        ];
+        let before = rendezvous_map(&backends, 10_000);
+
+        let remaining: Vec<Backend> = backends.iter().filter(|b| b.id != "w3").cloned().collect();
+        let after = rendezvous_map(&remaining, 10_000);
+
+        for (old, new) in before.iter().zip(&after) {
+            if old == "w3" {
+                assert_ne!(new, "w3");
+            } else {
+                // Keys that weren't on the removed backend never move
+                assert_eq!(old, new);
+            }
+        }
+    }
+
+    #[test]
+    fn test_rendezvous_minimal_remap_on_addition() {
+        let backends = vec![
+            create_backend("b1", "sa", "BR", true),
+            create_backend("b2", "sa", "BR", true),
+        ];
+        let before = rendezvous_map(&backends, 10_000);
+
+        let mut grown = backends.clone();
+        grown.push(create_backend("b3", "sa", "BR", true));
+        let after = rendezvous_map(&grown, 10_000);
+
+        let moved = before.iter().zip(&after).filter(|(old, new)| old != new).count();
+        // Only keys claimed by the new backend move, roughly a third of them
+        assert!(before
+            .iter()
+            .zip(&after)
+            .all(|(old, new)| old == new || new == "b3"));
+        assert!((2_900..3_800).contains(&moved), "{} keys moved", moved);
+    }
+
+    #[test]
+    fn test_rendezvous_skips_unavailable_backends() {
+        let mut draining = create_backend("b-drain", "sa", "BR", true);
+        draining.draining = true;
+        let backends = vec![
+            draining,
+            create_backend("b-down", "sa", "BR", false),
+            create_backend("b-ok", "sa", "BR", true),
+        ];
+
+        assert!(rendezvous_map(&backends, 200).iter().all(|id| id == "b-ok"));
+        assert!(LoadBalancer::rendezvous(&backends[..2], "10.0.0.1").is_none());
+        assert!(LoadBalancer::rendezvous(&[], "10.0.0.1").is_none());
+    }
+
+    #[test]
+    fn test_rendezvous_skips_zero_weight() {
+        let backends = vec![
+            create_backend_with_limits("zero", "sa", "BR", 0, 100, 200),
+            create_backend_with_limits("one", "sa", "BR", 1, 100, 200),
+        ];
+        assert!(rendezvous_map(&backends, 1000).iter().all(|id| id == "one"));
+        assert!(LoadBalancer::rendezvous(&backends[..1], "10.0.0.1").is_none());
+    }
+
+    // ===== Flow Hash Tests =====
+
+    fn flow(client: &str) -> FlowKey {
+        FlowKey::new(client.parse().unwrap())
+    }
+
+    fn flow_counts(backends: &[Backend], flows: u16) -> HashMap<String, usize> {
+        let local = RegionCode::SouthAmerica;
+        let ctx = SelectionContext::new(&local);
+        let mut counts = HashMap::new();
+        for port in 0..flows {
+            let key = FlowKey::new(SocketAddr::from(([203, 0, 113, 7], 10_000 + port)));
+            let backend = LoadBalancer::select_flow(backends, &ctx, &key).unwrap();
+            *counts.entry(backend.id.clone()).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    #[test]
+    fn test_select_flow_same_tuple_same_backend() {
+        let backends: Vec<Backend> = (0..5)
+            .map(|i| create_backend(&format!("b{}", i), "sa", "BR", true))
+            .collect();
+        let local = RegionCode::SouthAmerica;
+        let key = flow("203.0.113.7:40000").with_dest_port(443);
+
+        let first = LoadBalancer::select_flow(&backends, &SelectionContext::new(&local), &key)
+            .unwrap()
+            .id
+            .clone();
+
+        // Load and candidate order don't move a flow
+        let active: HashMap<String, usize> = [(first.clone(), 90)].into();
+        let mut reversed = backends.clone();
+        reversed.reverse();
+        let ctx = SelectionContext::new(&local).with_active(&active);
+        for _ in 0..10 {
+            assert_eq!(LoadBalancer::select_flow(&reversed, &ctx, &key).unwrap().id, first);
+        }
+    }
+
+    #[test]
+    fn test_select_flow_spreads_tuples() {
+        let backends: Vec<Backend> = ["a", "b", "c"]
+            .iter()
+            .map(|id| create_backend(id, "sa", "BR", true))
+            .collect();
+        let counts = flow_counts(&backends, 3000);
+        for id in ["a", "b", "c"] {
+            let share = counts[id];
+            assert!((800..=1200).contains(&share), "{} got {} of 3000 flows", id, share);
+        }
+    }
+
+    #[test]
+    fn test_select_flow_follows_weight() {
+        let backends = vec![
+            create_backend_with_limits("light", "sa", "BR", 1, 100, 200),
+            create_backend_with_limits("heavy", "sa", "BR", 3, 100, 200),
+        ];
+        let counts = flow_counts(&backends, 4000);
+        let heavy = counts["heavy"] as f64 / 4000.0;
+        assert!((0.70..=0.80).contains(&heavy), "heavy share {}", heavy);
+    }
+
+    #[test]
+    fn test_select_flow_key_includes_ports() {
+        use std::collections::HashSet;
+
+        let backends: Vec<Backend> = (0..8)
+            .map(|i| create_backend(&format!("b{}", i), "sa", "BR", true))
+            .collect();
+        let local = RegionCode::SouthAmerica;
+        let ctx = SelectionContext::new(&local);
+        let pick =
+            |key: FlowKey| LoadBalancer::select_flow(&backends, &ctx, &key).unwrap().id.clone();
+
+        let base = flow("203.0.113.7:40000");
+        let by_client_port: HashSet<String> =
+            (0..50).map(|p| pick(flow(&format!("203.0.113.7:{}", 40000 + p)))).collect();
+        let by_dest_port: HashSet<String> =
+            (0..50).map(|p| pick(base.with_dest_port(8000 + p))).collect();
+        assert!(by_client_port.len() > 1);
+        assert!(by_dest_port.len() > 1);
+
+        // An IPv4-mapped client hashes like the plain IPv4 one
+        assert_eq!(pick(flow("[::ffff:203.0.113.7]:40000")), pick(base));
+    }
+
+    #[test]
+    fn test_select_flow_keeps_best_geo_tier_and_skips_excluded() {
+        let backends = vec![
+            create_backend("br-1", "sa", "BR", true),
+            create_backend("br-down", "sa", "BR", false),
+            create_backend_with_limits("br-full", "sa", "BR", 9, 100, 10),
+            create_backend("us-1", "us", "US", true),
+        ];
+        let local = RegionCode::SouthAmerica;
+        let geo = GeoInfo::new("BR".to_string(), RegionCode::SouthAmerica);
+        let active: HashMap<String, usize> = [("br-full".to_string(), 10)].into();
+        let ctx = SelectionContext::new(&local)
+            .with_client_geo(Some(&geo))
+            .with_active(&active);
+
+        for port in 0..200 {
+            let key = FlowKey::new(SocketAddr::from(([203, 0, 113, 7], 20_000 + port)));
+            assert_eq!(LoadBalancer::select_flow(&backends, &ctx, &key).unwrap().id, "br-1");
+        }
+        assert!(LoadBalancer::select_flow(&backends[1..2], &ctx, &flow("1.2.3.4:5")).is_none());
+    }
 }
@@ -1,3 +1,5 @@
 mod load_balancer;
+mod weighted_round_robin;
 
-pub use load_balancer::LoadBalancer;
+pub use load_balancer::{Evaluation, Exclusion, FlowKey, LoadBalancer, SelectionContext};
+pub use weighted_round_robin::WeightedRoundRobin;
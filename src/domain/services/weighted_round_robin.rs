@@ -0,0 +1,139 @@
+//! Smooth Weighted Round Robin
+//!
+//! Pure domain logic for rotating a set of backends by weight across
+//! successive picks (the nginx "smooth" variant: heavy backends are
+//! spread out instead of picked in bursts).
+
+use crate::domain::entities::Backend;
+use std::collections::HashMap;
+
+/// Smooth weighted round robin state for one set of backends.
+///
+/// Each call to [`WeightedRoundRobin::order`] advances the rotation by one
+/// step. Over `sum(weights)` calls every backend comes first exactly
+/// `weight` times. State for backends that leave the set is dropped.
+#[derive(Debug, Default, Clone)]
+pub struct WeightedRoundRobin {
+    current: HashMap<String, i64>,
+}
+
+impl WeightedRoundRobin {
+    /// Create an empty rotation.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Order `candidates` for the next step of the rotation.
+    ///
+    /// The first backend is the SWRR pick; the rest follow by their
+    /// current weight (highest first), ties broken by id. When every
+    /// weight is zero the backends are returned in id order.
+    pub fn order<'b>(&mut self, candidates: &'b [Backend]) -> Vec<&'b Backend> {
+        self.current
+            .retain(|id, _| candidates.iter().any(|b| &b.id == id));
+
+        let total: i64 = candidates.iter().map(|b| b.weight as i64).sum();
+        for backend in candidates {
+            *self.current.entry(backend.id.clone()).or_insert(0) += backend.weight as i64;
+        }
+
+        let mut ordered: Vec<&Backend> = candidates.iter().collect();
+        if total == 0 {
+            ordered.sort_by(|a, b| a.id.cmp(&b.id));
+            return ordered;
+        }
+
+        ordered.sort_by(|a, b| {
+            self.current[&b.id]
+                .cmp(&self.current[&a.id])
+                .then_with(|| a.id.cmp(&b.id))
+        });
+        if let Some(first) = ordered.first() {
+            *self.current.get_mut(&first.id).unwrap() -= total;
+        }
+        ordered
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::domain::value_objects::RegionCode;
+
+    fn backend(id: &str, weight: u8) -> Backend {
+        Backend {
+            id: id.to_string(),
+            app: "myapp".to_string(),
+            region: RegionCode::Europe,
+            country: "DE".to_string(),
+            wg_ip: "10.50.1.1".to_string(),
+            port: 8080,
+            healthy: true,
+            weight,
+            soft_limit: 100,
+            hard_limit: 150,
+            draining: false,
+            maintenance: false,
+            priority: 0,
+        }
+    }
+
+    fn first_ids(rr: &mut WeightedRoundRobin, backends: &[Backend], steps: usize) -> Vec<String> {
+        (0..steps).map(|_| rr.order(backends)[0].id.clone()).collect()
+    }
+
+    #[test]
+    fn test_smooth_sequence() {
+        // Classic nginx example: weights 5, 1, 1 give a a b a c a a
+        let backends = vec![backend("a", 5), backend("b", 1), backend("c", 1)];
+        let mut rr = WeightedRoundRobin::new();
+        assert_eq!(first_ids(&mut rr, &backends, 7), ["a", "a", "b", "a", "c", "a", "a"]);
+    }
+
+    #[test]
+    fn test_first_pick_distribution_matches_weights() {
+        let backends = vec![backend("a", 3), backend("b", 2), backend("c", 1)];
+        let mut rr = WeightedRoundRobin::new();
+
+        let picks = first_ids(&mut rr, &backends, 600);
+        let count = |id: &str| picks.iter().filter(|p| *p == id).count();
+        assert_eq!((count("a"), count("b"), count("c")), (300, 200, 100));
+    }
+
+    #[test]
+    fn test_order_contains_every_backend() {
+        let backends = vec![backend("a", 1), backend("b", 4), backend("c", 2)];
+        let mut rr = WeightedRoundRobin::new();
+
+        let ordered: Vec<&str> = rr.order(&backends).iter().map(|b| b.id.as_str()).collect();
+        assert_eq!(ordered, ["b", "c", "a"]);
+    }
+
+    #[test]
+    fn test_equal_weights_rotate() {
+        let backends = vec![backend("a", 2), backend("b", 2), backend("c", 2)];
+        let mut rr = WeightedRoundRobin::new();
+        assert_eq!(first_ids(&mut rr, &backends, 6), ["a", "b", "c", "a", "b", "c"]);
+    }
+
+    #[test]
+    fn test_zero_weights() {
+        let backends = vec![backend("b", 0), backend("a", 0)];
+        let mut rr = WeightedRoundRobin::new();
+        assert_eq!(first_ids(&mut rr, &backends, 3), ["a", "a", "a"]);
+
+        // A zero-weight backend never comes first next to a weighted one
+        let backends = vec![backend("a", 0), backend("b", 1)];
+        assert_eq!(first_ids(&mut rr, &backends, 3), ["b", "b", "b"]);
+    }
+
+    #[test]
+    fn test_removed_backend_state_dropped() {
+        let mut rr = WeightedRoundRobin::new();
+        rr.order(&[backend("a", 1), backend("b", 1)]);
+        rr.order(&[backend("b", 1)]);
+        assert!(!rr.current.contains_key("a"));
+        assert_eq!(rr.order(&[]).len(), 0);
+    }
+}
@@ -3,13 +3,20 @@
 //! Value objects are identified by their value rather than identity.
 //! They are immutable and can be freely shared.
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 /// Geographic region code for routing decisions.
 ///
 /// Regions are used to group backends and clients for geo-aware routing.
 /// Clients are preferentially routed to backends in the same region.
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+///
+/// The canonical codes are `sa`, `us`, `eu` and `ap` (plus `unknown`), as
+/// stored in the `backends.region` column and replicated between nodes.
+/// [`FromStr`] accepts exactly these, in any case; [`Display`](std::fmt::Display)
+/// and serde write them back, so every representation round-trips.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
 pub enum RegionCode {
     /// South America (sa) - Brazil, Argentina, Chile, etc.
     SouthAmerica,
@@ -20,26 +27,40 @@ pub enum RegionCode {
     Europe,
     /// Asia Pacific (ap) - Japan, Korea, Southeast Asia, Australia
     AsiaPacific,
+    /// A region code edgeProxy doesn't know (unknown). Never preferred by
+    /// geo routing: such backends are only used as a last resort.
+    Unknown,
 }
 
+/// Error parsing a string that isn't a canonical region code.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("unknown region code {0:?} (expected sa, us, eu or ap)")]
+pub struct ParseRegionError(pub String);
+
 impl RegionCode {
-    /// Parse a region code from a string.
+    /// All known regions, in reporting order.
+    pub const ALL: [RegionCode; 4] = [
+        Self::SouthAmerica,
+        Self::NorthAmerica,
+        Self::Europe,
+        Self::AsiaPacific,
+    ];
+
+    /// Parse a region code, mapping anything unrecognised to [`Self::Unknown`].
+    ///
+    /// For stored data, where a bad value shouldn't drop the row; use
+    /// [`str::parse`] to reject unknown codes instead.
     ///
     /// # Examples
     /// ```
     /// use edge_proxy::domain::value_objects::RegionCode;
     ///
-    /// assert_eq!(RegionCode::from_str("sa"), RegionCode::SouthAmerica);
-    /// assert_eq!(RegionCode::from_str("unknown"), RegionCode::NorthAmerica); // fallback
+    /// assert_eq!(RegionCode::from_code("sa"), RegionCode::SouthAmerica);
+    /// assert_eq!(RegionCode::from_code("mars"), RegionCode::Unknown);
+    /// assert!("mars".parse::<RegionCode>().is_err());
     /// ```
-    pub fn from_str(s: &str) -> Self {
-        match s.to_lowercase().as_str() {
-            "sa" => Self::SouthAmerica,
-            "us" => Self::NorthAmerica,
-            "eu" => Self::Europe,
-            "ap" => Self::AsiaPacific,
-            _ => Self::NorthAmerica, // fallback
-        }
+    pub fn from_code(s: &str) -> Self {
+        s.parse().unwrap_or(Self::Unknown)
     }
 
     /// Convert to string representation.
@@ -49,16 +70,20 @@ impl RegionCode {
             Self::NorthAmerica => "us",
             Self::Europe => "eu",
             Self::AsiaPacific => "ap",
+            Self::Unknown => "unknown",
         }
     }
 
     /// Get a default representative country for this region.
+    ///
+    /// Empty for [`Self::Unknown`], which has no representative country.
     pub fn default_country(&self) -> &'static str {
         match self {
             Self::SouthAmerica => "BR",
             Self::NorthAmerica => "US",
             Self::Europe => "DE",
             Self::AsiaPacific => "JP",
+            Self::Unknown => "",
         }
     }
 
@@ -81,6 +106,35 @@ impl RegionCode {
             _ => Self::NorthAmerica,
         }
     }
+
+    /// Regions to try, nearest first, when this region has no backends.
+    ///
+    /// Regions not listed are still used as a last resort.
+    pub fn fallback_chain(&self) -> &'static [RegionCode] {
+        match self {
+            Self::SouthAmerica => &[Self::NorthAmerica, Self::Europe],
+            Self::NorthAmerica => &[Self::Europe, Self::SouthAmerica],
+            Self::Europe => &[Self::NorthAmerica],
+            Self::AsiaPacific => &[Self::NorthAmerica],
+            Self::Unknown => &[],
+        }
+    }
+}
+
+impl FromStr for RegionCode {
+    type Err = ParseRegionError;
+
+    /// Parse a canonical region code, ignoring case and surrounding whitespace.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "sa" => Ok(Self::SouthAmerica),
+            "us" => Ok(Self::NorthAmerica),
+            "eu" => Ok(Self::Europe),
+            "ap" => Ok(Self::AsiaPacific),
+            "unknown" => Ok(Self::Unknown),
+            _ => Err(ParseRegionError(s.to_string())),
+        }
+    }
 }
 
 impl std::fmt::Display for RegionCode {
@@ -89,6 +143,262 @@ impl std::fmt::Display for RegionCode {
     }
 }
 
+impl Serialize for RegionCode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for RegionCode {
+    /// Accepts canonical codes and, for data written by older versions,
+    /// variant names (`"Europe"`); anything else becomes [`Self::Unknown`].
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Ok(match s.as_str() {
+            "SouthAmerica" => Self::SouthAmerica,
+            "NorthAmerica" => Self::NorthAmerica,
+            "Europe" => Self::Europe,
+            "AsiaPacific" => Self::AsiaPacific,
+            "Unknown" => Self::Unknown,
+            _ => Self::from_code(&s),
+        })
+    }
+}
+
+/// Outcome of an internal DNS query, used as a metrics label.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DnsQueryOutcome {
+    /// Answered with at least one record
+    NoError,
+    /// Name not found (or no backend to answer with)
+    NxDomain,
+    /// Record type not supported
+    NotImp,
+    /// Server failure (e.g. no healthy backends, when configured)
+    ServFail,
+    /// Name outside our domain, which we aren't authoritative for
+    Refused,
+}
+
+impl DnsQueryOutcome {
+    /// All outcomes, in export order.
+    pub const ALL: [DnsQueryOutcome; 5] = [
+        Self::NoError,
+        Self::NxDomain,
+        Self::NotImp,
+        Self::ServFail,
+        Self::Refused,
+    ];
+
+    /// Convert to the label used in metrics output.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::NoError => "noerror",
+            Self::NxDomain => "nxdomain",
+            Self::NotImp => "notimp",
+            Self::ServFail => "servfail",
+            Self::Refused => "refused",
+        }
+    }
+}
+
+impl std::fmt::Display for DnsQueryOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Where a backend selection landed relative to the client's region,
+/// used as a metrics label.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SelectionOutcome {
+    /// Served from the client's own region (or country)
+    InRegion,
+    /// Client's region had no backend; served from the local POP's region
+    /// or the region's fallback chain
+    RegionFallback,
+    /// Client's region and its fallback chain had no backend; served from
+    /// whatever region was left
+    AnyRegion,
+    /// No backend available at all
+    NoBackend,
+}
+
+impl SelectionOutcome {
+    /// All outcomes, in export order.
+    pub const ALL: [SelectionOutcome; 4] = [
+        Self::InRegion,
+        Self::RegionFallback,
+        Self::AnyRegion,
+        Self::NoBackend,
+    ];
+
+    /// Convert to the label used in metrics output.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::InRegion => "in_region",
+            Self::RegionFallback => "region_fallback",
+            Self::AnyRegion => "any_region",
+            Self::NoBackend => "no_backend",
+        }
+    }
+
+    /// Position in [`SelectionOutcome::ALL`], for array-backed counters.
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+impl std::fmt::Display for SelectionOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Why a client connection was closed, used as a metrics label.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CloseReason {
+    /// The client finished sending first (EOF)
+    ClientClosed,
+    /// The backend finished sending first (EOF)
+    BackendClosed,
+    /// Aborted after running for the maximum session duration
+    SessionTimeout,
+    /// A copy in either direction failed (e.g. connection reset)
+    Error,
+    /// Turned away because every backend was at its hard limit
+    Overload,
+}
+
+impl CloseReason {
+    /// All reasons, in export order.
+    pub const ALL: [CloseReason; 5] = [
+        Self::ClientClosed,
+        Self::BackendClosed,
+        Self::SessionTimeout,
+        Self::Error,
+        Self::Overload,
+    ];
+
+    /// Convert to the label used in metrics output and logs.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ClientClosed => "client_eof",
+            Self::BackendClosed => "backend_eof",
+            Self::SessionTimeout => "session_timeout",
+            Self::Error => "error",
+            Self::Overload => "overload",
+        }
+    }
+
+    /// Position in [`CloseReason::ALL`], for array-backed counters.
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+impl std::fmt::Display for CloseReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// A step of dialing a backend, used as a metrics label.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConnectPhase {
+    /// Resolving the backend's hostname to addresses
+    Resolve,
+    /// Establishing the TCP connection
+    Connect,
+}
+
+impl ConnectPhase {
+    /// All phases, in export order.
+    pub const ALL: [ConnectPhase; 2] = [Self::Resolve, Self::Connect];
+
+    /// Convert to the label used in metrics output.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Resolve => "resolve",
+            Self::Connect => "connect",
+        }
+    }
+
+    /// Position in [`ConnectPhase::ALL`], for array-backed counters.
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+impl std::fmt::Display for ConnectPhase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// State of a backend's circuit breaker, used as a metrics label.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum CircuitState {
+    /// Normal operation - requests allowed
+    #[default]
+    Closed,
+    /// Circuit tripped - requests blocked
+    Open,
+    /// Testing recovery - limited requests allowed
+    HalfOpen,
+}
+
+impl CircuitState {
+    /// All states, in export order.
+    pub const ALL: [CircuitState; 3] = [Self::Closed, Self::Open, Self::HalfOpen];
+
+    /// Convert to the label used in metrics output and logs.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Closed => "closed",
+            Self::Open => "open",
+            Self::HalfOpen => "half-open",
+        }
+    }
+
+    /// Position in [`CircuitState::ALL`], for array-backed counters.
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+impl std::fmt::Display for CircuitState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Process-unique id of an accepted client connection.
+///
+/// Attached to the connection's tracing span so every log line it emits
+/// (and its access log entry) can be correlated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ConnectionId(u64);
+
+impl ConnectionId {
+    /// Allocate the next id (ids start at 1 and never repeat within a process).
+    pub fn next() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(1);
+        Self(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+
+    /// The numeric value of the id.
+    pub fn get(&self) -> u64 {
+        self.0
+    }
+}
+
+impl std::fmt::Display for ConnectionId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 /// Score calculated for a backend during load balancing.
 ///
 /// Lower scores are better. The score combines:
@@ -129,7 +439,7 @@ mod tests {
 
         for (input, expected) in tests {
             assert_eq!(
-                RegionCode::from_str(input),
+                RegionCode::from_str(input).unwrap(),
                 expected,
                 "Failed for input: {}",
                 input
@@ -148,7 +458,7 @@ mod tests {
 
         for (input, expected) in tests {
             assert_eq!(
-                RegionCode::from_str(input),
+                RegionCode::from_str(input).unwrap(),
                 expected,
                 "Failed for input: {}",
                 input
@@ -158,10 +468,10 @@ mod tests {
 
     #[test]
     fn test_region_from_str_mixed_case() {
-        assert_eq!(RegionCode::from_str("Sa"), RegionCode::SouthAmerica);
-        assert_eq!(RegionCode::from_str("Us"), RegionCode::NorthAmerica);
-        assert_eq!(RegionCode::from_str("Eu"), RegionCode::Europe);
-        assert_eq!(RegionCode::from_str("Ap"), RegionCode::AsiaPacific);
+        assert_eq!(RegionCode::from_str("Sa").unwrap(), RegionCode::SouthAmerica);
+        assert_eq!(RegionCode::from_str("Us").unwrap(), RegionCode::NorthAmerica);
+        assert_eq!(RegionCode::from_str("Eu").unwrap(), RegionCode::Europe);
+        assert_eq!(RegionCode::from_str("Ap").unwrap(), RegionCode::AsiaPacific);
     }
 
     #[test]
@@ -171,13 +481,31 @@ mod tests {
         for input in invalid_inputs {
             assert_eq!(
                 RegionCode::from_str(input),
-                RegionCode::NorthAmerica,
+                Err(ParseRegionError(input.to_string())),
+                "Parse should fail for input: {}",
+                input
+            );
+            assert_eq!(
+                RegionCode::from_code(input),
+                RegionCode::Unknown,
                 "Fallback failed for input: {}",
                 input
             );
         }
     }
 
+    #[test]
+    fn test_region_from_str_trims_whitespace() {
+        assert_eq!(" eu ".parse::<RegionCode>().unwrap(), RegionCode::Europe);
+    }
+
+    #[test]
+    fn test_unknown_region_has_no_preferences() {
+        assert!(RegionCode::Unknown.fallback_chain().is_empty());
+        assert_eq!(RegionCode::Unknown.default_country(), "");
+        assert!(!RegionCode::ALL.contains(&RegionCode::Unknown));
+    }
+
     // ===== RegionCode::from_country Tests =====
 
     #[test]
@@ -385,15 +713,24 @@ mod tests {
             RegionCode::NorthAmerica,
             RegionCode::Europe,
             RegionCode::AsiaPacific,
+            RegionCode::Unknown,
         ];
 
         for region in regions {
             let str_repr = region.as_str();
-            let parsed = RegionCode::from_str(str_repr);
-            assert_eq!(region, parsed);
+            assert_eq!(region.to_string(), str_repr);
+            assert_eq!(RegionCode::from_str(str_repr).unwrap(), region);
+            assert_eq!(RegionCode::from_code(str_repr), region);
         }
     }
 
+    #[test]
+    fn test_region_all_is_complete() {
+        let set: std::collections::HashSet<_> = RegionCode::ALL.iter().map(|r| r.as_str()).collect();
+        assert_eq!(set.len(), 4);
+        assert_eq!(RegionCode::ALL[0], RegionCode::SouthAmerica);
+    }
+
     // ===== Serde Serialization Tests =====
 
     #[test]
@@ -406,6 +743,22 @@ mod tests {
         assert_eq!(region, deserialized);
     }
 
+    #[test]
+    fn test_region_serializes_as_canonical_code() {
+        assert_eq!(serde_json::to_string(&RegionCode::Europe).unwrap(), r#""eu""#);
+        assert_eq!(serde_json::to_string(&RegionCode::Unknown).unwrap(), r#""unknown""#);
+    }
+
+    #[test]
+    fn test_region_deserialize_legacy_and_unknown() {
+        let legacy: RegionCode = serde_json::from_str(r#""Europe""#).unwrap();
+        assert_eq!(legacy, RegionCode::Europe);
+        let code: RegionCode = serde_json::from_str(r#""AP""#).unwrap();
+        assert_eq!(code, RegionCode::AsiaPacific);
+        let unknown: RegionCode = serde_json::from_str(r#""mars""#).unwrap();
+        assert_eq!(unknown, RegionCode::Unknown);
+    }
+
     #[test]
     fn test_region_serialize_all_variants() {
         use serde_json;
@@ -415,6 +768,7 @@ mod tests {
             RegionCode::NorthAmerica,
             RegionCode::Europe,
             RegionCode::AsiaPacific,
+            RegionCode::Unknown,
         ];
 
         for region in regions {
@@ -440,4 +794,111 @@ mod tests {
         assert_eq!(format!("{:?}", RegionCode::Europe), "Europe");
         assert_eq!(format!("{:?}", RegionCode::AsiaPacific), "AsiaPacific");
     }
+
+    // ===== DnsQueryOutcome Tests =====
+
+    #[test]
+    fn test_dns_query_outcome_as_str() {
+        assert_eq!(DnsQueryOutcome::NoError.as_str(), "noerror");
+        assert_eq!(DnsQueryOutcome::NxDomain.as_str(), "nxdomain");
+        assert_eq!(DnsQueryOutcome::NotImp.as_str(), "notimp");
+        assert_eq!(DnsQueryOutcome::ServFail.as_str(), "servfail");
+        assert_eq!(DnsQueryOutcome::Refused.as_str(), "refused");
+    }
+
+    #[test]
+    fn test_dns_query_outcome_display() {
+        assert_eq!(format!("{}", DnsQueryOutcome::NxDomain), "nxdomain");
+    }
+
+    #[test]
+    fn test_dns_query_outcome_all_unique() {
+        use std::collections::HashSet;
+        let set: HashSet<_> = DnsQueryOutcome::ALL.iter().collect();
+        assert_eq!(set.len(), DnsQueryOutcome::ALL.len());
+    }
+
+    // ===== Region Fallback Tests =====
+
+    #[test]
+    fn test_fallback_chain_excludes_self() {
+        for region in [
+            RegionCode::SouthAmerica,
+            RegionCode::NorthAmerica,
+            RegionCode::Europe,
+            RegionCode::AsiaPacific,
+        ] {
+            assert!(!region.fallback_chain().contains(&region));
+            assert!(!region.fallback_chain().is_empty());
+        }
+    }
+
+    #[test]
+    fn test_fallback_chain_order() {
+        assert_eq!(
+            RegionCode::SouthAmerica.fallback_chain(),
+            &[RegionCode::NorthAmerica, RegionCode::Europe]
+        );
+        assert_eq!(RegionCode::AsiaPacific.fallback_chain(), &[RegionCode::NorthAmerica]);
+    }
+
+    // ===== SelectionOutcome Tests =====
+
+    #[test]
+    fn test_selection_outcome_as_str() {
+        assert_eq!(SelectionOutcome::InRegion.as_str(), "in_region");
+        assert_eq!(SelectionOutcome::RegionFallback.as_str(), "region_fallback");
+        assert_eq!(SelectionOutcome::AnyRegion.as_str(), "any_region");
+        assert_eq!(SelectionOutcome::NoBackend.as_str(), "no_backend");
+        assert_eq!(format!("{}", SelectionOutcome::NoBackend), "no_backend");
+    }
+
+    #[test]
+    fn test_selection_outcome_index_matches_all() {
+        for (i, outcome) in SelectionOutcome::ALL.iter().enumerate() {
+            assert_eq!(outcome.index(), i);
+        }
+    }
+
+    // ===== CloseReason Tests =====
+
+    #[test]
+    fn test_close_reason_as_str() {
+        assert_eq!(CloseReason::ClientClosed.as_str(), "client_eof");
+        assert_eq!(CloseReason::BackendClosed.as_str(), "backend_eof");
+        assert_eq!(CloseReason::SessionTimeout.as_str(), "session_timeout");
+        assert_eq!(CloseReason::Error.as_str(), "error");
+        assert_eq!(format!("{}", CloseReason::Overload), "overload");
+    }
+
+    #[test]
+    fn test_close_reason_index_matches_all() {
+        for (i, reason) in CloseReason::ALL.iter().enumerate() {
+            assert_eq!(reason.index(), i);
+        }
+    }
+
+    // ===== ConnectPhase Tests =====
+
+    #[test]
+    fn test_connect_phase_as_str() {
+        assert_eq!(ConnectPhase::Resolve.as_str(), "resolve");
+        assert_eq!(format!("{}", ConnectPhase::Connect), "connect");
+    }
+
+    #[test]
+    fn test_connect_phase_index_matches_all() {
+        for (i, phase) in ConnectPhase::ALL.iter().enumerate() {
+            assert_eq!(phase.index(), i);
+        }
+    }
+
+    #[test]
+    fn test_connection_id_is_unique_and_increasing() {
+        let first = ConnectionId::next();
+        let second = ConnectionId::next();
+        assert_ne!(first, second);
+        assert!(second.get() > first.get());
+        assert_eq!(first.to_string(), first.get().to_string());
+    }
 }
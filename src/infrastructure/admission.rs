@@ -0,0 +1,447 @@
+//! Priority Admission
+//!
+//! Bounds concurrently admitted connections and, once the bound is reached,
+//! hands freed permits to waiting clients by source-network priority.
+
+use parking_lot::Mutex;
+use std::collections::{BTreeMap, VecDeque};
+use std::net::IpAddr;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::oneshot;
+
+/// Default number of grants a waiting priority level may be passed over.
+pub const DEFAULT_MAX_SKIPS: u32 = 8;
+
+/// An IPv4 or IPv6 network in CIDR notation (e.g. `10.0.0.0/8`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CidrBlock {
+    network: IpAddr,
+    prefix_len: u8,
+}
+
+/// Error parsing a CIDR block.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("invalid CIDR block: {0}")]
+pub struct CidrParseError(String);
+
+impl CidrBlock {
+    /// Prefix length in bits.
+    pub fn prefix_len(&self) -> u8 {
+        self.prefix_len
+    }
+
+    /// Check whether an address falls inside this network.
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.network, ip) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix_len as u32).unwrap_or(0);
+                u32::from(net) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix_len as u32).unwrap_or(0);
+                u128::from(net) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl From<IpAddr> for CidrBlock {
+    /// The single-address network holding `ip` (`/32` or `/128`).
+    fn from(ip: IpAddr) -> Self {
+        let prefix_len = if ip.is_ipv4() { 32 } else { 128 };
+        Self { network: ip, prefix_len }
+    }
+}
+
+impl FromStr for CidrBlock {
+    type Err = CidrParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || CidrParseError(s.to_string());
+        let (addr, len) = match s.split_once('/') {
+            Some((addr, len)) => (addr, Some(len)),
+            None => (s, None),
+        };
+        let network: IpAddr = addr.trim().parse().map_err(|_| err())?;
+        let max_len = if network.is_ipv4() { 32 } else { 128 };
+        let prefix_len = match len {
+            Some(len) => len.trim().parse::<u8>().map_err(|_| err())?,
+            None => max_len,
+        };
+        if prefix_len > max_len {
+            return Err(err());
+        }
+        Ok(Self { network, prefix_len })
+    }
+}
+
+/// Maps client addresses to a priority (higher is served first).
+///
+/// The most specific matching network wins; unmatched clients get the default.
+#[derive(Debug, Clone, Default)]
+pub struct PriorityClassifier {
+    rules: Vec<(CidrBlock, u8)>,
+    default_priority: u8,
+}
+
+impl PriorityClassifier {
+    /// Create a classifier assigning `default_priority` to unmatched clients.
+    pub fn new(default_priority: u8) -> Self {
+        Self {
+            rules: Vec::new(),
+            default_priority,
+        }
+    }
+
+    /// Assign a priority to a source network.
+    pub fn with_rule(mut self, block: CidrBlock, priority: u8) -> Self {
+        self.rules.push((block, priority));
+        self
+    }
+
+    /// Priority of a client address.
+    pub fn classify(&self, ip: IpAddr) -> u8 {
+        self.rules
+            .iter()
+            .filter(|(block, _)| block.contains(ip))
+            .max_by_key(|(block, _)| block.prefix_len)
+            .map(|(_, priority)| *priority)
+            .unwrap_or(self.default_priority)
+    }
+}
+
+/// Clients waiting at one priority level.
+#[derive(Default)]
+struct Level {
+    waiters: VecDeque<oneshot::Sender<AdmissionPermit>>,
+    /// Grants handed to higher levels since this level was last served
+    skipped: u32,
+}
+
+struct State {
+    available: usize,
+    levels: BTreeMap<u8, Level>,
+}
+
+struct Inner {
+    state: Mutex<State>,
+    max_skips: u32,
+}
+
+impl Inner {
+    /// Pick the next waiter: highest priority first, unless a lower level has
+    /// been passed over `max_skips` times, in which case the lowest such level.
+    fn next_waiter(&self, state: &mut State) -> Option<oneshot::Sender<AdmissionPermit>> {
+        state.levels.retain(|_, level| !level.waiters.is_empty());
+
+        let starved = state
+            .levels
+            .iter()
+            .find(|(_, level)| level.skipped >= self.max_skips)
+            .map(|(priority, _)| *priority);
+        let chosen = starved.or_else(|| state.levels.keys().next_back().copied())?;
+
+        for (priority, level) in state.levels.iter_mut() {
+            if *priority < chosen {
+                level.skipped += 1;
+            }
+        }
+        let level = state.levels.get_mut(&chosen)?;
+        level.skipped = 0;
+        level.waiters.pop_front()
+    }
+
+    /// Hand a freed permit to the next live waiter, or return it to the pool.
+    fn release(self: &Arc<Self>) {
+        let mut permit = AdmissionPermit {
+            inner: Some(self.clone()),
+        };
+        loop {
+            let waiter = {
+                let mut state = self.state.lock();
+                match self.next_waiter(&mut state) {
+                    Some(waiter) => waiter,
+                    None => {
+                        state.available += 1;
+                        permit.inner = None;
+                        return;
+                    }
+                }
+            };
+            // A cancelled waiter hands the permit straight back
+            match waiter.send(permit) {
+                Ok(()) => return,
+                Err(returned) => permit = returned,
+            }
+        }
+    }
+}
+
+/// Connection permits granted by source-network priority under contention.
+///
+/// While permits are free, every client is admitted immediately. Once they run
+/// out, each freed permit goes to the highest-priority waiter (FIFO within a
+/// priority). A waiting level that has been passed over `max_skips` times is
+/// served next, so low-priority clients are delayed but never starved.
+#[derive(Clone)]
+pub struct PriorityAdmission {
+    inner: Arc<Inner>,
+    classifier: Arc<PriorityClassifier>,
+}
+
+impl PriorityAdmission {
+    /// Create an admission controller with `max_permits` concurrent permits.
+    pub fn new(max_permits: usize, classifier: PriorityClassifier) -> Self {
+        Self::with_max_skips(max_permits, classifier, DEFAULT_MAX_SKIPS)
+    }
+
+    /// Create an admission controller with a custom starvation bound.
+    pub fn with_max_skips(max_permits: usize, classifier: PriorityClassifier, max_skips: u32) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                state: Mutex::new(State {
+                    available: max_permits,
+                    levels: BTreeMap::new(),
+                }),
+                max_skips,
+            }),
+            classifier: Arc::new(classifier),
+        }
+    }
+
+    /// Wait for a permit for a client; it is released when dropped.
+    pub async fn acquire(&self, ip: IpAddr) -> AdmissionPermit {
+        let priority = self.classifier.classify(ip);
+        let rx = {
+            let mut state = self.inner.state.lock();
+            if state.available > 0 {
+                state.available -= 1;
+                return AdmissionPermit {
+                    inner: Some(self.inner.clone()),
+                };
+            }
+            let (tx, rx) = oneshot::channel();
+            state.levels.entry(priority).or_default().waiters.push_back(tx);
+            rx
+        };
+        // The sender is only dropped after handing over a permit
+        rx.await.expect("admission waiter dropped without a permit")
+    }
+
+    /// Take a permit only if one is free right now.
+    pub fn try_acquire(&self) -> Option<AdmissionPermit> {
+        let mut state = self.inner.state.lock();
+        if state.available == 0 {
+            return None;
+        }
+        state.available -= 1;
+        Some(AdmissionPermit {
+            inner: Some(self.inner.clone()),
+        })
+    }
+
+    /// Number of free permits.
+    pub fn available(&self) -> usize {
+        self.inner.state.lock().available
+    }
+
+    /// Number of clients waiting for a permit (including cancelled ones not yet skipped).
+    pub fn waiting(&self) -> usize {
+        self.inner
+            .state
+            .lock()
+            .levels
+            .values()
+            .map(|level| level.waiters.len())
+            .sum()
+    }
+}
+
+/// A granted connection slot; dropping it admits the next waiter.
+pub struct AdmissionPermit {
+    inner: Option<Arc<Inner>>,
+}
+
+impl std::fmt::Debug for AdmissionPermit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AdmissionPermit").finish_non_exhaustive()
+    }
+}
+
+impl Drop for AdmissionPermit {
+    fn drop(&mut self) {
+        if let Some(inner) = self.inner.take() {
+            inner.release();
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    fn classifier() -> PriorityClassifier {
+        PriorityClassifier::new(0).with_rule("10.0.0.0/8".parse().unwrap(), 10)
+    }
+
+    /// Queue one waiter per label and record the order permits are granted in.
+    async fn grant_order(admission: &PriorityAdmission, clients: &[(&'static str, &str)]) -> Vec<&'static str> {
+        let held = admission.acquire(ip("192.0.2.1")).await;
+        assert_eq!(admission.available(), 0);
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        for (i, (label, addr)) in clients.iter().enumerate() {
+            let waiter = admission.clone();
+            let tx = tx.clone();
+            let (label, addr) = (*label, ip(addr));
+            tokio::spawn(async move {
+                let permit = waiter.acquire(addr).await;
+                tx.send(label).unwrap();
+                drop(permit);
+            });
+            // Enqueue in a known order
+            while admission.waiting() < i + 1 {
+                tokio::task::yield_now().await;
+            }
+        }
+        drop(tx);
+        drop(held);
+
+        let mut order = Vec::new();
+        while let Some(label) = rx.recv().await {
+            order.push(label);
+        }
+        order
+    }
+
+    // ===== CIDR Tests =====
+
+    #[test]
+    fn test_cidr_parse_and_contains_v4() {
+        let block: CidrBlock = "10.1.0.0/16".parse().unwrap();
+        assert_eq!(block.prefix_len(), 16);
+        assert!(block.contains(ip("10.1.200.3")));
+        assert!(!block.contains(ip("10.2.0.1")));
+        assert!(!block.contains(ip("::1")));
+    }
+
+    #[test]
+    fn test_cidr_parse_and_contains_v6() {
+        let block: CidrBlock = "2001:db8::/32".parse().unwrap();
+        assert!(block.contains(ip("2001:db8:1::1")));
+        assert!(!block.contains(ip("2001:db9::1")));
+        assert!(!block.contains(ip("10.0.0.1")));
+    }
+
+    #[test]
+    fn test_cidr_bare_address_and_zero_prefix() {
+        let host: CidrBlock = "192.0.2.7".parse().unwrap();
+        assert_eq!(host.prefix_len(), 32);
+        assert!(host.contains(ip("192.0.2.7")));
+        assert!(!host.contains(ip("192.0.2.8")));
+
+        let any: CidrBlock = "0.0.0.0/0".parse().unwrap();
+        assert!(any.contains(ip("203.0.113.9")));
+    }
+
+    #[test]
+    fn test_cidr_parse_errors() {
+        assert!("10.0.0.0/33".parse::<CidrBlock>().is_err());
+        assert!("::/129".parse::<CidrBlock>().is_err());
+        assert!("not-an-ip/8".parse::<CidrBlock>().is_err());
+        assert!("10.0.0.0/x".parse::<CidrBlock>().is_err());
+    }
+
+    // ===== Classifier Tests =====
+
+    #[test]
+    fn test_classifier_most_specific_rule_wins() {
+        let classifier = PriorityClassifier::new(1)
+            .with_rule("10.0.0.0/8".parse().unwrap(), 5)
+            .with_rule("10.9.0.0/16".parse().unwrap(), 9);
+
+        assert_eq!(classifier.classify(ip("10.9.1.1")), 9);
+        assert_eq!(classifier.classify(ip("10.1.1.1")), 5);
+        assert_eq!(classifier.classify(ip("192.0.2.1")), 1);
+    }
+
+    // ===== Admission Tests =====
+
+    #[tokio::test]
+    async fn test_acquire_immediate_when_permits_free() {
+        let admission = PriorityAdmission::new(2, classifier());
+        let a = admission.acquire(ip("192.0.2.1")).await;
+        let _b = admission.acquire(ip("192.0.2.2")).await;
+        assert_eq!(admission.available(), 0);
+        assert!(admission.try_acquire().is_none());
+
+        drop(a);
+        assert_eq!(admission.available(), 1);
+        assert!(admission.try_acquire().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_high_priority_served_first_under_saturation() {
+        let admission = PriorityAdmission::new(1, classifier());
+        let order = grant_order(
+            &admission,
+            &[
+                ("low-1", "192.0.2.10"),
+                ("low-2", "192.0.2.11"),
+                ("high-1", "10.0.0.1"),
+                ("high-2", "10.0.0.2"),
+            ],
+        )
+        .await;
+
+        assert_eq!(order, vec!["high-1", "high-2", "low-1", "low-2"]);
+        assert_eq!(admission.available(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_low_priority_not_starved() {
+        let admission = PriorityAdmission::with_max_skips(1, classifier(), 2);
+        let order = grant_order(
+            &admission,
+            &[
+                ("low", "192.0.2.10"),
+                ("high-1", "10.0.0.1"),
+                ("high-2", "10.0.0.2"),
+                ("high-3", "10.0.0.3"),
+                ("high-4", "10.0.0.4"),
+            ],
+        )
+        .await;
+
+        assert_eq!(order, vec!["high-1", "high-2", "low", "high-3", "high-4"]);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_waiter_does_not_leak_permit() {
+        let admission = PriorityAdmission::new(1, classifier());
+        let held = admission.acquire(ip("192.0.2.1")).await;
+
+        let waiting = admission.clone();
+        let task = tokio::spawn(async move { waiting.acquire(ip("10.0.0.1")).await });
+        while admission.waiting() == 0 {
+            tokio::task::yield_now().await;
+        }
+        task.abort();
+        let _ = task.await;
+
+        drop(held);
+        assert_eq!(admission.available(), 1);
+        assert_eq!(admission.waiting(), 0);
+
+        let permit = tokio::time::timeout(Duration::from_secs(1), admission.acquire(ip("192.0.2.2"))).await;
+        assert!(permit.is_ok());
+    }
+}
@@ -2,9 +2,14 @@
 //!
 //! Prevents cascading failures by temporarily blocking requests to failing backends.
 
+use crate::domain::ports::MetricsStore;
 use dashmap::DashMap;
 use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+
+pub use crate::domain::value_objects::CircuitState;
 
 /// Circuit breaker configuration.
 #[derive(Debug, Clone)]
@@ -30,25 +35,12 @@ impl Default for CircuitBreakerConfig {
     }
 }
 
-/// Circuit breaker state.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum CircuitState {
-    /// Normal operation - requests allowed
-    Closed,
-    /// Circuit tripped - requests blocked
-    Open,
-    /// Testing recovery - limited requests allowed
-    HalfOpen,
-}
-
-impl std::fmt::Display for CircuitState {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            CircuitState::Closed => write!(f, "closed"),
-            CircuitState::Open => write!(f, "open"),
-            CircuitState::HalfOpen => write!(f, "half-open"),
-        }
-    }
+/// A backend's circuit moving from one state to another.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CircuitEvent {
+    pub backend_id: String,
+    pub from: CircuitState,
+    pub to: CircuitState,
 }
 
 /// Per-backend circuit breaker state.
@@ -85,32 +77,78 @@ impl BackendCircuit {
         }
     }
 
-    fn set_state(&self, state: CircuitState) {
+    /// Store `state`, returning the state it replaced.
+    fn swap_state(&self, state: CircuitState) -> CircuitState {
         let val = match state {
             CircuitState::Closed => 0,
             CircuitState::Open => 1,
             CircuitState::HalfOpen => 2,
         };
-        self.state.store(val, Ordering::SeqCst);
+        match self.state.swap(val, Ordering::SeqCst) {
+            0 => CircuitState::Closed,
+            1 => CircuitState::Open,
+            _ => CircuitState::HalfOpen,
+        }
     }
 }
 
 /// Circuit breaker for backends.
 ///
 /// Tracks failure rates and prevents requests to failing backends.
+/// Every state transition is recorded in the metrics store, when one is
+/// set, and sent to subscribers as a [`CircuitEvent`].
 pub struct CircuitBreaker {
     config: CircuitBreakerConfig,
     /// Per-backend circuit state
     circuits: DashMap<String, BackendCircuit>,
+    metrics: Option<Arc<dyn MetricsStore>>,
+    events: broadcast::Sender<CircuitEvent>,
 }
 
 impl CircuitBreaker {
     /// Create a new circuit breaker.
     pub fn new(config: CircuitBreakerConfig) -> Self {
+        let (events, _) = broadcast::channel(64);
         Self {
             config,
             circuits: DashMap::new(),
+            metrics: None,
+            events,
+        }
+    }
+
+    /// Record circuit states and transitions in `metrics`.
+    pub fn with_metrics(mut self, metrics: Arc<dyn MetricsStore>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Subscribe to circuit state transitions.
+    ///
+    /// Events are only delivered while a receiver is alive; a receiver
+    /// that falls more than 64 events behind skips the oldest.
+    pub fn subscribe(&self) -> broadcast::Receiver<CircuitEvent> {
+        self.events.subscribe()
+    }
+
+    /// Move a circuit to `to`, recording and announcing the change.
+    ///
+    /// Does nothing when the circuit is already in `to`, so a transition
+    /// raced by two callers is only reported once.
+    fn transition(&self, backend_id: &str, circuit: &BackendCircuit, to: CircuitState) -> bool {
+        let from = circuit.swap_state(to);
+        if from == to {
+            return false;
+        }
+        if let Some(metrics) = &self.metrics {
+            metrics.record_circuit_transition(backend_id, to);
         }
+        let _ = self.events.send(CircuitEvent {
+            backend_id: backend_id.to_string(),
+            from,
+            to,
+        });
+        true
     }
 
     /// Get the current timestamp in milliseconds.
@@ -145,9 +183,10 @@ impl CircuitBreaker {
 
                 if now.saturating_sub(opened_at) >= reset_timeout_ms {
                     // Transition to half-open
-                    circuit.set_state(CircuitState::HalfOpen);
                     circuit.successes.store(0, Ordering::Relaxed);
-                    tracing::info!("circuit breaker for {} transitioning to half-open", backend_id);
+                    if self.transition(backend_id, &circuit, CircuitState::HalfOpen) {
+                        tracing::info!("circuit breaker for {} transitioning to half-open", backend_id);
+                    }
                     true
                 } else {
                     false
@@ -166,10 +205,11 @@ impl CircuitBreaker {
             CircuitState::HalfOpen => {
                 let successes = circuit.successes.fetch_add(1, Ordering::Relaxed) + 1;
                 if successes >= self.config.success_threshold {
-                    circuit.set_state(CircuitState::Closed);
                     circuit.failures.store(0, Ordering::Relaxed);
                     circuit.successes.store(0, Ordering::Relaxed);
-                    tracing::info!("circuit breaker for {} closed (recovered)", backend_id);
+                    if self.transition(backend_id, &circuit, CircuitState::Closed) {
+                        tracing::info!("circuit breaker for {} closed (recovered)", backend_id);
+                    }
                 }
             }
             CircuitState::Closed => {
@@ -200,26 +240,28 @@ impl CircuitBreaker {
                 } else {
                     let failures = circuit.failures.fetch_add(1, Ordering::Relaxed) + 1;
                     if failures >= self.config.failure_threshold {
-                        circuit.set_state(CircuitState::Open);
                         circuit.opened_at_ms.store(now, Ordering::Relaxed);
-                        tracing::warn!(
-                            "circuit breaker for {} opened after {} failures",
-                            backend_id,
-                            failures
-                        );
+                        if self.transition(backend_id, &circuit, CircuitState::Open) {
+                            tracing::warn!(
+                                "circuit breaker for {} opened after {} failures",
+                                backend_id,
+                                failures
+                            );
+                        }
                     }
                 }
                 circuit.last_failure_ms.store(now, Ordering::Relaxed);
             }
             CircuitState::HalfOpen => {
                 // Any failure in half-open immediately re-opens
-                circuit.set_state(CircuitState::Open);
                 circuit.opened_at_ms.store(now, Ordering::Relaxed);
                 circuit.successes.store(0, Ordering::Relaxed);
-                tracing::warn!(
-                    "circuit breaker for {} re-opened (failed in half-open)",
-                    backend_id
-                );
+                if self.transition(backend_id, &circuit, CircuitState::Open) {
+                    tracing::warn!(
+                        "circuit breaker for {} re-opened (failed in half-open)",
+                        backend_id
+                    );
+                }
             }
             CircuitState::Open => {
                 // Already open, update opened_at to extend timeout
@@ -259,7 +301,7 @@ impl CircuitBreaker {
     /// Manually reset a circuit to closed.
     pub fn reset(&self, backend_id: &str) {
         if let Some(circuit) = self.circuits.get(backend_id) {
-            circuit.set_state(CircuitState::Closed);
+            self.transition(backend_id, &circuit, CircuitState::Closed);
             circuit.failures.store(0, Ordering::Relaxed);
             circuit.successes.store(0, Ordering::Relaxed);
             tracing::info!("circuit breaker for {} manually reset", backend_id);
@@ -286,12 +328,6 @@ pub struct CircuitMetrics {
     pub successes: u32,
 }
 
-impl Default for CircuitState {
-    fn default() -> Self {
-        CircuitState::Closed
-    }
-}
-
 #[cfg(test)]
 #[cfg_attr(coverage_nightly, coverage(off))]
 mod tests {
@@ -611,4 +647,57 @@ mod tests {
         cb.record_success("b1");
         assert_eq!(cb.get_state("b1"), CircuitState::Open);
     }
+
+    #[test]
+    fn test_transitions_emit_events() {
+        let cb = CircuitBreaker::new(CircuitBreakerConfig {
+            failure_threshold: 1,
+            reset_timeout: Duration::from_millis(1),
+            success_threshold: 1,
+            ..Default::default()
+        });
+        let mut events = cb.subscribe();
+        let event = |from, to| CircuitEvent {
+            backend_id: "b1".to_string(),
+            from,
+            to,
+        };
+
+        cb.record_failure("b1");
+        // Already open: no new event
+        cb.record_failure("b1");
+        std::thread::sleep(Duration::from_millis(5));
+        cb.allow_request("b1");
+        cb.record_failure("b1");
+        std::thread::sleep(Duration::from_millis(5));
+        cb.allow_request("b1");
+        cb.record_success("b1");
+
+        use CircuitState::*;
+        assert_eq!(events.try_recv().unwrap(), event(Closed, Open));
+        assert_eq!(events.try_recv().unwrap(), event(Open, HalfOpen));
+        assert_eq!(events.try_recv().unwrap(), event(HalfOpen, Open));
+        assert_eq!(events.try_recv().unwrap(), event(Open, HalfOpen));
+        assert_eq!(events.try_recv().unwrap(), event(HalfOpen, Closed));
+        assert!(events.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_reset_emits_event_only_when_not_closed() {
+        let cb = CircuitBreaker::new(CircuitBreakerConfig {
+            failure_threshold: 1,
+            ..Default::default()
+        });
+        let mut events = cb.subscribe();
+
+        cb.allow_request("b1");
+        cb.reset("b1");
+        assert!(events.try_recv().is_err());
+
+        cb.record_failure("b1");
+        cb.reset("b1");
+        assert_eq!(events.try_recv().unwrap().to, CircuitState::Open);
+        let reset = events.try_recv().unwrap();
+        assert_eq!((reset.from, reset.to), (CircuitState::Open, CircuitState::Closed));
+    }
 }
@@ -9,7 +9,7 @@ use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::io::AsyncWriteExt;
 use tokio::net::TcpStream;
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, RwLock};
 
 /// Health check configuration.
 #[derive(Debug, Clone)]
@@ -77,13 +77,55 @@ impl Default for HealthStatus {
     }
 }
 
+/// Callback invoked with a backend id and its new health.
+pub type HealthChangeCallback = Arc<dyn Fn(&str, bool) + Send + Sync>;
+
+/// A backend flipped between healthy and unhealthy.
+#[derive(Debug, Clone)]
+pub struct HealthChangeEvent {
+    /// Backend whose health changed
+    pub backend_id: String,
+    /// Status before the check that caused the change
+    pub old: HealthStatus,
+    /// Status after that check
+    pub new: HealthStatus,
+}
+
+/// Where health transitions are reported.
+#[derive(Clone)]
+struct HealthNotifier {
+    /// Callback when health changes
+    callback: Option<HealthChangeCallback>,
+    /// Broadcast channel for health change events
+    events: broadcast::Sender<HealthChangeEvent>,
+}
+
+impl Default for HealthNotifier {
+    fn default() -> Self {
+        Self {
+            callback: None,
+            events: broadcast::channel(64).0,
+        }
+    }
+}
+
+impl HealthNotifier {
+    fn notify(&self, event: HealthChangeEvent) {
+        if let Some(callback) = &self.callback {
+            callback(&event.backend_id, event.new.healthy);
+        }
+        // Nobody subscribed is fine
+        let _ = self.events.send(event);
+    }
+}
+
 /// Active health checker for backends.
 pub struct HealthChecker {
     config: HealthCheckConfig,
     /// Health status per backend ID
     status: Arc<RwLock<HashMap<String, HealthStatus>>>,
-    /// Callback when health changes
-    on_health_change: Option<Arc<dyn Fn(&str, bool) + Send + Sync>>,
+    /// Receivers of health transitions
+    notifier: HealthNotifier,
 }
 
 impl HealthChecker {
@@ -92,7 +134,7 @@ impl HealthChecker {
         Self {
             config,
             status: Arc::new(RwLock::new(HashMap::new())),
-            on_health_change: None,
+            notifier: HealthNotifier::default(),
         }
     }
 
@@ -101,10 +143,18 @@ impl HealthChecker {
     where
         F: Fn(&str, bool) + Send + Sync + 'static,
     {
-        self.on_health_change = Some(Arc::new(callback));
+        self.notifier.callback = Some(Arc::new(callback));
         self
     }
 
+    /// Subscribe to health transitions.
+    ///
+    /// Only flips between healthy and unhealthy are sent, not every check.
+    /// A receiver that falls more than 64 events behind misses the oldest.
+    pub fn subscribe(&self) -> broadcast::Receiver<HealthChangeEvent> {
+        self.notifier.events.subscribe()
+    }
+
     /// Get health status for a backend.
     pub async fn get_status(&self, backend_id: &str) -> Option<HealthStatus> {
         self.status.read().await.get(backend_id).cloned()
@@ -130,7 +180,7 @@ impl HealthChecker {
     pub fn start<R: BackendRepository + 'static>(&self, backend_repo: Arc<R>) {
         let config = self.config.clone();
         let status = self.status.clone();
-        let on_change = self.on_health_change.clone();
+        let notifier = self.notifier.clone();
 
         tokio::spawn(async move {
             let mut interval = tokio::time::interval(config.interval);
@@ -139,19 +189,32 @@ impl HealthChecker {
                 interval.tick().await;
 
                 let backends = backend_repo.get_all().await;
-
-                for backend in backends {
-                    let result = Self::check_backend(&backend, &config).await;
-                    Self::update_status(&status, &backend.id, result, &config, &on_change).await;
-                }
+                Self::check_all(&backends, &status, &config, &notifier).await;
             }
         });
     }
 
+    /// Run one round of checks over `backends`.
+    ///
+    /// Backends under maintenance are skipped: they're expected to be down,
+    /// so probing them would only count failures against them and fire
+    /// unhealthy transitions.
+    async fn check_all(
+        backends: &[Backend],
+        status: &Arc<RwLock<HashMap<String, HealthStatus>>>,
+        config: &HealthCheckConfig,
+        notifier: &HealthNotifier,
+    ) {
+        for backend in backends.iter().filter(|b| should_check(b)) {
+            let result = Self::check_backend(backend, config).await;
+            Self::update_status(status, &backend.id, result, config, notifier).await;
+        }
+    }
+
     /// Perform a single health check on a backend.
     #[cfg_attr(coverage_nightly, coverage(off))]
     async fn check_backend(backend: &Backend, config: &HealthCheckConfig) -> HealthCheckResult {
-        let addr = format!("{}:{}", backend.wg_ip, backend.port);
+        let addr = backend.addr();
         let start = Instant::now();
 
         let result = match &config.check_type {
@@ -208,14 +271,14 @@ impl HealthChecker {
         backend_id: &str,
         result: HealthCheckResult,
         config: &HealthCheckConfig,
-        on_change: &Option<Arc<dyn Fn(&str, bool) + Send + Sync>>,
+        notifier: &HealthNotifier,
     ) {
         let mut statuses = status.write().await;
         let entry = statuses
             .entry(backend_id.to_string())
             .or_insert_with(HealthStatus::default);
 
-        let was_healthy = entry.healthy;
+        let old = entry.clone();
 
         match result {
             HealthCheckResult::Success { latency_ms } => {
@@ -244,11 +307,13 @@ impl HealthChecker {
 
         entry.last_check = Instant::now();
 
-        // Notify callback if health changed
-        if was_healthy != entry.healthy {
-            if let Some(callback) = on_change {
-                callback(backend_id, entry.healthy);
-            }
+        // Notify callback and subscribers if health changed
+        if old.healthy != entry.healthy {
+            notifier.notify(HealthChangeEvent {
+                backend_id: backend_id.to_string(),
+                old,
+                new: entry.clone(),
+            });
         }
     }
 
@@ -343,7 +408,12 @@ pub fn calculate_health_transition(
 
 /// Build address string for backend (Sans-IO pattern).
 pub fn build_backend_addr(backend: &Backend) -> String {
-    format!("{}:{}", backend.wg_ip, backend.port)
+    backend.addr()
+}
+
+/// Whether a backend should be probed (Sans-IO pattern).
+pub fn should_check(backend: &Backend) -> bool {
+    !backend.maintenance
 }
 
 /// Determine if a status indicates the backend should be marked unhealthy.
@@ -375,6 +445,9 @@ mod tests {
             weight: 2,
             soft_limit: 100,
             hard_limit: 150,
+            draining: false,
+            maintenance: false,
+            priority: 0,
         }
     }
 
@@ -503,7 +576,7 @@ mod tests {
         let config = HealthCheckConfig::default();
 
         let result = HealthCheckResult::Success { latency_ms: 10 };
-        HealthChecker::update_status(&status, "b1", result, &config, &None).await;
+        HealthChecker::update_status(&status, "b1", result, &config, &Default::default()).await;
 
         let statuses = status.read().await;
         let s = statuses.get("b1").unwrap();
@@ -522,7 +595,7 @@ mod tests {
             error: "conn refused".to_string(),
             latency_ms: 5,
         };
-        HealthChecker::update_status(&status, "b1", result, &config, &None).await;
+        HealthChecker::update_status(&status, "b1", result, &config, &Default::default()).await;
 
         let statuses = status.read().await;
         let s = statuses.get("b1").unwrap();
@@ -545,11 +618,12 @@ mod tests {
         };
 
         // First failure
-        HealthChecker::update_status(&status, "b1", failure.clone(), &config, &None).await;
+        HealthChecker::update_status(&status, "b1", failure.clone(), &config, &Default::default())
+            .await;
         assert!(status.read().await.get("b1").unwrap().healthy);
 
         // Second failure - should become unhealthy
-        HealthChecker::update_status(&status, "b1", failure, &config, &None).await;
+        HealthChecker::update_status(&status, "b1", failure, &config, &Default::default()).await;
         assert!(!status.read().await.get("b1").unwrap().healthy);
     }
 
@@ -567,17 +641,18 @@ mod tests {
             error: "error".to_string(),
             latency_ms: 5,
         };
-        HealthChecker::update_status(&status, "b1", failure, &config, &None).await;
+        HealthChecker::update_status(&status, "b1", failure, &config, &Default::default()).await;
         assert!(!status.read().await.get("b1").unwrap().healthy);
 
         let success = HealthCheckResult::Success { latency_ms: 10 };
 
         // First success
-        HealthChecker::update_status(&status, "b1", success.clone(), &config, &None).await;
+        HealthChecker::update_status(&status, "b1", success.clone(), &config, &Default::default())
+            .await;
         assert!(!status.read().await.get("b1").unwrap().healthy);
 
         // Second success - should become healthy
-        HealthChecker::update_status(&status, "b1", success, &config, &None).await;
+        HealthChecker::update_status(&status, "b1", success, &config, &Default::default()).await;
         assert!(status.read().await.get("b1").unwrap().healthy);
     }
 
@@ -606,7 +681,7 @@ mod tests {
             "b1",
             failure,
             &checker.config,
-            &checker.on_health_change,
+            &checker.notifier,
         )
         .await;
 
@@ -629,6 +704,173 @@ mod tests {
         assert!(result.is_success());
     }
 
+    #[tokio::test]
+    async fn test_check_all_skips_maintenance_backends() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        // Nothing listens on this port, so a probe would fail
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        drop(listener);
+
+        let called = Arc::new(AtomicBool::new(false));
+        let called_clone = called.clone();
+        let checker = HealthChecker::new(HealthCheckConfig {
+            unhealthy_threshold: 1,
+            ..Default::default()
+        })
+        .on_health_change(move |_, _| {
+            called_clone.store(true, Ordering::SeqCst);
+        });
+
+        let mut backend = create_test_backend(port);
+        backend.maintenance = true;
+        HealthChecker::check_all(
+            std::slice::from_ref(&backend),
+            &checker.status,
+            &checker.config,
+            &checker.notifier,
+        )
+        .await;
+
+        // No failure was recorded and no transition fired
+        assert!(checker.get_status(&backend.id).await.is_none());
+        assert!(checker.is_healthy(&backend.id).await);
+        assert!(!called.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_receives_transitions_only() {
+        let checker = HealthChecker::new(HealthCheckConfig {
+            unhealthy_threshold: 2,
+            healthy_threshold: 1,
+            ..Default::default()
+        });
+        let mut events = checker.subscribe();
+        let failure = HealthCheckResult::Failure {
+            error: "conn refused".to_string(),
+            latency_ms: 5,
+        };
+        let success = HealthCheckResult::Success { latency_ms: 10 };
+
+        for result in [success.clone(), failure.clone(), failure.clone(), failure, success] {
+            HealthChecker::update_status(
+                &checker.status,
+                "b1",
+                result,
+                &checker.config,
+                &checker.notifier,
+            )
+            .await;
+        }
+
+        let down = events.try_recv().unwrap();
+        assert_eq!(down.backend_id, "b1");
+        assert!(down.old.healthy);
+        assert_eq!(down.old.consecutive_failures, 1);
+        assert!(!down.new.healthy);
+        assert_eq!(down.new.consecutive_failures, 2);
+        assert_eq!(down.new.last_error.as_deref(), Some("conn refused"));
+
+        let up = events.try_recv().unwrap();
+        assert_eq!(up.backend_id, "b1");
+        assert!(!up.old.healthy);
+        assert!(up.new.healthy);
+        assert_eq!(up.new.consecutive_successes, 1);
+
+        // Checks that didn't flip health sent nothing
+        assert!(events.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_check_all_emits_events_as_backend_goes_down_and_up() {
+        let config = HealthCheckConfig {
+            timeout: Duration::from_secs(1),
+            unhealthy_threshold: 1,
+            healthy_threshold: 1,
+            ..Default::default()
+        };
+        let accept_forever = |listener: TcpListener| {
+            tokio::spawn(async move {
+                while let Ok((stream, _)) = listener.accept().await {
+                    drop(stream);
+                }
+            })
+        };
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = accept_forever(listener);
+
+        let checker = HealthChecker::new(config);
+        let mut events = checker.subscribe();
+        let backends = vec![create_test_backend(addr.port())];
+        let round = || {
+            HealthChecker::check_all(&backends, &checker.status, &checker.config, &checker.notifier)
+        };
+
+        // Up and staying up: no event
+        round().await;
+        assert!(events.try_recv().is_err());
+
+        // Backend goes away
+        server.abort();
+        let _ = server.await;
+        round().await;
+        let event = events.try_recv().unwrap();
+        assert_eq!(event.backend_id, format!("test-{}", addr.port()));
+        assert!(event.old.healthy);
+        assert!(!event.new.healthy);
+
+        // Still down: no new event
+        round().await;
+        assert!(events.try_recv().is_err());
+
+        // Backend comes back on the same port
+        let server = accept_forever(TcpListener::bind(addr).await.unwrap());
+        round().await;
+        let event = events.try_recv().unwrap();
+        assert!(!event.old.healthy);
+        assert!(event.new.healthy);
+        assert!(events.try_recv().is_err());
+
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_callback_and_subscribers_both_notified() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        let called = Arc::new(AtomicBool::new(false));
+        let called_clone = called.clone();
+        let checker = HealthChecker::new(HealthCheckConfig {
+            unhealthy_threshold: 1,
+            ..Default::default()
+        })
+        .on_health_change(move |_, healthy| {
+            called_clone.store(!healthy, Ordering::SeqCst);
+        });
+        let mut first = checker.subscribe();
+        let mut second = checker.subscribe();
+
+        let failure = HealthCheckResult::Failure {
+            error: "error".to_string(),
+            latency_ms: 5,
+        };
+        HealthChecker::update_status(
+            &checker.status,
+            "b1",
+            failure,
+            &checker.config,
+            &checker.notifier,
+        )
+        .await;
+
+        assert!(called.load(Ordering::SeqCst));
+        assert!(!first.try_recv().unwrap().new.healthy);
+        assert!(!second.try_recv().unwrap().new.healthy);
+    }
+
     // Sans-IO Tests
 
     #[test]
@@ -793,6 +1035,19 @@ mod tests {
         let backend = create_test_backend(8080);
         let addr = build_backend_addr(&backend);
         assert_eq!(addr, "127.0.0.1:8080");
+
+        let mut ipv6 = create_test_backend(8080);
+        ipv6.wg_ip = "fd00::1".to_string();
+        assert_eq!(build_backend_addr(&ipv6), "[fd00::1]:8080");
+    }
+
+    #[test]
+    fn test_should_check_skips_maintenance() {
+        let mut backend = create_test_backend(8080);
+        assert!(should_check(&backend));
+
+        backend.maintenance = true;
+        assert!(!should_check(&backend));
     }
 
     #[test]
@@ -2,6 +2,7 @@
 //!
 //! Cross-cutting concerns and infrastructure components.
 
+pub mod admission;
 pub mod circuit_breaker;
 pub mod config_watcher;
 pub mod connection_pool;
@@ -9,9 +10,14 @@ pub mod health_checker;
 pub mod rate_limiter;
 pub mod shutdown;
 
-pub use circuit_breaker::{CircuitBreaker, CircuitBreakerConfig, CircuitMetrics, CircuitState};
+pub use admission::{AdmissionPermit, CidrBlock, CidrParseError, PriorityAdmission, PriorityClassifier};
+pub use circuit_breaker::{
+    CircuitBreaker, CircuitBreakerConfig, CircuitEvent, CircuitMetrics, CircuitState,
+};
 pub use config_watcher::{ConfigChange, ConfigWatchError, ConfigWatcher, HotValue};
 pub use connection_pool::{ConnectionPool, PoolConfig, PoolError, PoolStats, PooledConnection};
-pub use health_checker::{HealthCheckConfig, HealthCheckResult, HealthCheckType, HealthChecker, HealthStatus};
-pub use rate_limiter::{RateLimitConfig, RateLimitResult, RateLimiter};
-pub use shutdown::{shutdown_signal, ConnectionGuard, ShutdownController};
+pub use health_checker::{
+    HealthChangeEvent, HealthCheckConfig, HealthCheckResult, HealthCheckType, HealthChecker, HealthStatus,
+};
+pub use rate_limiter::{RateLimitConfig, RateLimitDimension, RateLimitResult, RateLimiter, TargetLimit};
+pub use shutdown::{shutdown_signal, AbortOnDrop, ConnectionGuard, ShutdownController};
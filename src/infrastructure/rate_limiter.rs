@@ -1,7 +1,9 @@
 //! Rate Limiter
 //!
-//! Token bucket rate limiting per client IP.
+//! Token bucket rate limiting per client IP, with optional buckets shared
+//! by every connection to one app or region.
 
+use crate::domain::value_objects::RegionCode;
 use dashmap::DashMap;
 use std::net::IpAddr;
 use std::sync::atomic::{AtomicU64, Ordering};
@@ -16,6 +18,10 @@ pub struct RateLimitConfig {
     pub window: Duration,
     /// Maximum burst size (token bucket capacity)
     pub burst_size: u64,
+    /// Limit on connections to each app, whatever the client (None = unlimited)
+    pub per_app: Option<TargetLimit>,
+    /// Limit on connections to each region, whatever the client (None = unlimited)
+    pub per_region: Option<TargetLimit>,
 }
 
 impl Default for RateLimitConfig {
@@ -24,11 +30,43 @@ impl Default for RateLimitConfig {
             max_requests: 100,
             window: Duration::from_secs(1),
             burst_size: 10,
+            per_app: None,
+            per_region: None,
         }
     }
 }
 
-/// Per-client rate limit state.
+/// Token bucket limit for one app or one region.
+///
+/// Every app (or region) gets a bucket of its own with these settings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TargetLimit {
+    /// Maximum requests per window
+    pub max_requests: u64,
+    /// Time window for rate limiting
+    pub window: Duration,
+    /// Maximum burst size (token bucket capacity)
+    pub burst_size: u64,
+}
+
+/// Bucket capacity and refill rate derived from a limit.
+#[derive(Debug, Clone, Copy)]
+struct Rate {
+    burst_size: u64,
+    /// Tokens added per millisecond
+    refill_rate_per_ms: f64,
+}
+
+impl Rate {
+    fn new(max_requests: u64, window: Duration, burst_size: u64) -> Self {
+        Self {
+            burst_size,
+            refill_rate_per_ms: max_requests as f64 / window.as_millis() as f64,
+        }
+    }
+}
+
+/// Rate limit state of one bucket (a client, an app or a region).
 struct ClientState {
     /// Available tokens
     tokens: AtomicU64,
@@ -50,27 +88,93 @@ impl ClientState {
         let start = START.get_or_init(Instant::now);
         start.elapsed().as_millis() as u64
     }
+
+    /// Refill by the time elapsed, then take `cost` tokens.
+    ///
+    /// Returns the tokens left, or how long (ms) until `cost` tokens are
+    /// available when there are too few.
+    fn take(&self, cost: u64, rate: Rate) -> Result<u64, u64> {
+        let now_ms = Self::now_ms();
+        let last_refill = self.last_refill_ms.load(Ordering::Relaxed);
+        let elapsed_ms = now_ms.saturating_sub(last_refill);
+
+        // Calculate tokens to add
+        let tokens_to_add = (elapsed_ms as f64 * rate.refill_rate_per_ms) as u64;
+
+        if tokens_to_add > 0 {
+            // Refill tokens (capped at burst_size)
+            let current = self.tokens.load(Ordering::Relaxed);
+            let new_tokens = (current + tokens_to_add).min(rate.burst_size);
+            self.tokens.store(new_tokens, Ordering::Relaxed);
+            self.last_refill_ms.store(now_ms, Ordering::Relaxed);
+        }
+
+        // Try to consume tokens
+        let mut current = self.tokens.load(Ordering::Relaxed);
+        loop {
+            if current < cost {
+                let missing = (cost - current) as f64;
+                return Err((missing / rate.refill_rate_per_ms).ceil() as u64);
+            }
+
+            match self.tokens.compare_exchange_weak(
+                current,
+                current - cost,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return Ok(current - cost),
+                Err(c) => current = c,
+            }
+        }
+    }
+
+    /// Give back tokens taken for a request that was refused elsewhere.
+    fn refund(&self, cost: u64, rate: Rate) {
+        let _ = self
+            .tokens
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |t| {
+                Some((t + cost).min(rate.burst_size))
+            });
+    }
+
+    fn is_stale(&self, now_ms: u64, max_age_ms: u64) -> bool {
+        now_ms.saturating_sub(self.last_refill_ms.load(Ordering::Relaxed)) >= max_age_ms
+    }
 }
 
 /// Token bucket rate limiter.
 ///
-/// Tracks request rates per client IP using the token bucket algorithm.
+/// Tracks request rates per client IP using the token bucket algorithm,
+/// and, when configured, total rates per app and per region.
 pub struct RateLimiter {
     config: RateLimitConfig,
     /// Per-client state
     clients: DashMap<IpAddr, ClientState>,
-    /// Tokens added per millisecond
-    refill_rate_per_ms: f64,
+    /// Per-app state (only used with `per_app`)
+    apps: DashMap<String, ClientState>,
+    /// Per-region state (only used with `per_region`)
+    regions: DashMap<RegionCode, ClientState>,
+    client_rate: Rate,
+    app_rate: Option<Rate>,
+    region_rate: Option<Rate>,
 }
 
 impl RateLimiter {
     /// Create a new rate limiter.
     pub fn new(config: RateLimitConfig) -> Self {
-        let refill_rate_per_ms = config.max_requests as f64 / config.window.as_millis() as f64;
+        let client_rate = Rate::new(config.max_requests, config.window, config.burst_size);
+        let target_rate = |l: &TargetLimit| Rate::new(l.max_requests, l.window, l.burst_size);
+        let app_rate = config.per_app.as_ref().map(target_rate);
+        let region_rate = config.per_region.as_ref().map(target_rate);
         Self {
             config,
             clients: DashMap::new(),
-            refill_rate_per_ms,
+            apps: DashMap::new(),
+            regions: DashMap::new(),
+            client_rate,
+            app_rate,
+            region_rate,
         }
     }
 
@@ -83,44 +187,101 @@ impl RateLimiter {
 
     /// Check if a request is allowed with a specific cost.
     pub fn check_with_cost(&self, ip: IpAddr, cost: u64) -> bool {
-        let state = self.clients
+        matches!(self.check_client(ip, cost), RateLimitResult::Allowed { .. })
+    }
+
+    /// Like [`RateLimiter::check_with_cost`], but reports the tokens left or
+    /// how long to wait.
+    pub fn check_client(&self, ip: IpAddr, cost: u64) -> RateLimitResult {
+        let state = self
+            .clients
             .entry(ip)
             .or_insert_with(|| ClientState::new(self.config.burst_size));
-
-        let now_ms = ClientState::now_ms();
-        let last_refill = state.last_refill_ms.load(Ordering::Relaxed);
-        let elapsed_ms = now_ms.saturating_sub(last_refill);
-
-        // Calculate tokens to add
-        let tokens_to_add = (elapsed_ms as f64 * self.refill_rate_per_ms) as u64;
-
-        if tokens_to_add > 0 {
-            // Refill tokens (capped at burst_size)
-            let current = state.tokens.load(Ordering::Relaxed);
-            let new_tokens = (current + tokens_to_add).min(self.config.burst_size);
-            state.tokens.store(new_tokens, Ordering::Relaxed);
-            state.last_refill_ms.store(now_ms, Ordering::Relaxed);
+        match state.take(cost, self.client_rate) {
+            Ok(remaining) => RateLimitResult::Allowed { remaining },
+            Err(retry_after_ms) => RateLimitResult::Limited {
+                dimension: RateLimitDimension::Client,
+                retry_after_ms,
+            },
         }
+    }
 
-        // Try to consume tokens
-        let mut current = state.tokens.load(Ordering::Relaxed);
-        loop {
-            if current < cost {
-                return false; // Rate limited
+    /// Check whether one more connection may go to `app` in `region`.
+    ///
+    /// Takes a token from the app bucket and from the region bucket, each
+    /// only when that limit is configured. Nothing is taken when either
+    /// bucket is empty; the result names the one that was. `remaining` is
+    /// the lower of the two buckets (`u64::MAX` when neither is limited).
+    pub fn check_target(&self, app: &str, region: &RegionCode) -> RateLimitResult {
+        let app_remaining = match self.app_rate {
+            Some(rate) => {
+                let state = self
+                    .apps
+                    .entry(app.to_string())
+                    .or_insert_with(|| ClientState::new(rate.burst_size));
+                match state.take(1, rate) {
+                    Ok(remaining) => remaining,
+                    Err(retry_after_ms) => {
+                        return RateLimitResult::Limited {
+                            dimension: RateLimitDimension::App,
+                            retry_after_ms,
+                        }
+                    }
+                }
             }
+            None => u64::MAX,
+        };
 
-            match state.tokens.compare_exchange_weak(
-                current,
-                current - cost,
-                Ordering::Relaxed,
-                Ordering::Relaxed,
-            ) {
-                Ok(_) => return true, // Allowed
-                Err(c) => current = c,
+        let region_remaining = match self.region_rate {
+            Some(rate) => {
+                let state = self
+                    .regions
+                    .entry(region.clone())
+                    .or_insert_with(|| ClientState::new(rate.burst_size));
+                match state.take(1, rate) {
+                    Ok(remaining) => remaining,
+                    Err(retry_after_ms) => {
+                        drop(state);
+                        if let (Some(app_rate), Some(app_state)) = (self.app_rate, self.apps.get(app)) {
+                            app_state.refund(1, app_rate);
+                        }
+                        return RateLimitResult::Limited {
+                            dimension: RateLimitDimension::Region,
+                            retry_after_ms,
+                        };
+                    }
+                }
             }
+            None => u64::MAX,
+        };
+
+        RateLimitResult::Allowed {
+            remaining: app_remaining.min(region_remaining),
         }
     }
 
+    /// Get remaining tokens for an app (`u64::MAX` without a per-app limit).
+    pub fn app_remaining(&self, app: &str) -> u64 {
+        let Some(rate) = self.app_rate else {
+            return u64::MAX;
+        };
+        self.apps
+            .get(app)
+            .map(|s| s.tokens.load(Ordering::Relaxed))
+            .unwrap_or(rate.burst_size)
+    }
+
+    /// Get remaining tokens for a region (`u64::MAX` without a per-region limit).
+    pub fn region_remaining(&self, region: &RegionCode) -> u64 {
+        let Some(rate) = self.region_rate else {
+            return u64::MAX;
+        };
+        self.regions
+            .get(region)
+            .map(|s| s.tokens.load(Ordering::Relaxed))
+            .unwrap_or(rate.burst_size)
+    }
+
     /// Get remaining tokens for a client.
     pub fn remaining(&self, ip: IpAddr) -> u64 {
         self.clients
@@ -137,6 +298,8 @@ impl RateLimiter {
     /// Clear all rate limit state.
     pub fn clear_all(&self) {
         self.clients.clear();
+        self.apps.clear();
+        self.regions.clear();
     }
 
     /// Get the number of tracked clients.
@@ -149,10 +312,9 @@ impl RateLimiter {
         let now_ms = ClientState::now_ms();
         let max_age_ms = max_age.as_millis() as u64;
 
-        self.clients.retain(|_, state| {
-            let last_refill = state.last_refill_ms.load(Ordering::Relaxed);
-            now_ms.saturating_sub(last_refill) < max_age_ms
-        });
+        self.clients.retain(|_, state| !state.is_stale(now_ms, max_age_ms));
+        self.apps.retain(|_, state| !state.is_stale(now_ms, max_age_ms));
+        self.regions.retain(|_, state| !state.is_stale(now_ms, max_age_ms));
     }
 
     /// Start periodic cleanup task.
@@ -171,11 +333,10 @@ impl RateLimiter {
                 let max_age_ms = max_age.as_millis() as u64;
 
                 let before = limiter.clients.len();
-                limiter.clients.retain(|_, state| {
-                    let last_refill = state.last_refill_ms.load(Ordering::Relaxed);
-                    now_ms.saturating_sub(last_refill) < max_age_ms
-                });
+                limiter.clients.retain(|_, state| !state.is_stale(now_ms, max_age_ms));
                 let after = limiter.clients.len();
+                limiter.apps.retain(|_, state| !state.is_stale(now_ms, max_age_ms));
+                limiter.regions.retain(|_, state| !state.is_stale(now_ms, max_age_ms));
 
                 if before != after {
                     tracing::debug!(
@@ -194,13 +355,37 @@ impl Default for RateLimiter {
     }
 }
 
+/// Which limit a request ran into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimitDimension {
+    /// The client IP's own limit
+    Client,
+    /// The limit shared by every connection to the app
+    App,
+    /// The limit shared by every connection to the region
+    Region,
+}
+
+impl std::fmt::Display for RateLimitDimension {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Client => "client",
+            Self::App => "app",
+            Self::Region => "region",
+        })
+    }
+}
+
 /// Result of a rate limit check.
 #[derive(Debug, Clone, PartialEq)]
 pub enum RateLimitResult {
     /// Request is allowed
     Allowed { remaining: u64 },
     /// Request is rate limited
-    Limited { retry_after_ms: u64 },
+    Limited {
+        dimension: RateLimitDimension,
+        retry_after_ms: u64,
+    },
 }
 
 #[cfg(test)]
@@ -239,6 +424,7 @@ mod tests {
             burst_size: 5,
             max_requests: 10,
             window: Duration::from_secs(1),
+            ..Default::default()
         });
 
         let ip = test_ip(1);
@@ -258,6 +444,7 @@ mod tests {
             burst_size: 2,
             max_requests: 10,
             window: Duration::from_secs(1),
+            ..Default::default()
         });
 
         let ip1 = test_ip(1);
@@ -400,6 +587,7 @@ mod tests {
             burst_size: 2,
             max_requests: 1000, // High rate = fast refill
             window: Duration::from_millis(100),
+            ..Default::default()
         });
 
         let ip = test_ip(1);
@@ -425,6 +613,7 @@ mod tests {
             burst_size: 100,
             max_requests: 1000,
             window: Duration::from_secs(1),
+            ..Default::default()
         }));
 
         let mut handles = vec![];
@@ -455,7 +644,10 @@ mod tests {
         let allowed1 = RateLimitResult::Allowed { remaining: 5 };
         let allowed2 = RateLimitResult::Allowed { remaining: 5 };
         let allowed3 = RateLimitResult::Allowed { remaining: 10 };
-        let limited = RateLimitResult::Limited { retry_after_ms: 100 };
+        let limited = RateLimitResult::Limited {
+            dimension: RateLimitDimension::Client,
+            retry_after_ms: 100,
+        };
 
         assert_eq!(allowed1, allowed2);
         assert_ne!(allowed1, allowed3);
@@ -469,7 +661,10 @@ mod tests {
         assert!(debug.contains("Allowed"));
         assert!(debug.contains("42"));
 
-        let limited = RateLimitResult::Limited { retry_after_ms: 100 };
+        let limited = RateLimitResult::Limited {
+            dimension: RateLimitDimension::Client,
+            retry_after_ms: 100,
+        };
         let debug = format!("{:?}", limited);
         assert!(debug.contains("Limited"));
         assert!(debug.contains("100"));
@@ -477,7 +672,10 @@ mod tests {
 
     #[test]
     fn test_rate_limit_result_clone() {
-        let result = RateLimitResult::Limited { retry_after_ms: 500 };
+        let result = RateLimitResult::Limited {
+            dimension: RateLimitDimension::App,
+            retry_after_ms: 500,
+        };
         let cloned = result.clone();
         assert_eq!(result, cloned);
     }
@@ -497,6 +695,7 @@ mod tests {
             max_requests: 200,
             window: Duration::from_secs(2),
             burst_size: 20,
+            ..Default::default()
         };
         let cloned = config.clone();
         assert_eq!(cloned.max_requests, 200);
@@ -563,6 +762,7 @@ mod tests {
             burst_size: 1,
             max_requests: 1_000_000, // Very high
             window: Duration::from_secs(1),
+            ..Default::default()
         });
 
         let ip = test_ip(1);
@@ -575,4 +775,129 @@ mod tests {
         std::thread::sleep(Duration::from_millis(10));
         assert!(limiter.check(ip));
     }
+
+    fn target_limit(burst_size: u64) -> TargetLimit {
+        TargetLimit {
+            max_requests: 1,
+            window: Duration::from_secs(60),
+            burst_size,
+        }
+    }
+
+    #[test]
+    fn test_check_target_unlimited_by_default() {
+        let limiter = RateLimiter::default();
+        for _ in 0..1000 {
+            assert_eq!(
+                limiter.check_target("app", &RegionCode::Europe),
+                RateLimitResult::Allowed { remaining: u64::MAX }
+            );
+        }
+        assert_eq!(limiter.app_remaining("app"), u64::MAX);
+        assert_eq!(limiter.region_remaining(&RegionCode::Europe), u64::MAX);
+    }
+
+    #[test]
+    fn test_check_target_app_limit_is_per_app() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            per_app: Some(target_limit(2)),
+            ..Default::default()
+        });
+
+        assert_eq!(
+            limiter.check_target("busy", &RegionCode::Europe),
+            RateLimitResult::Allowed { remaining: 1 }
+        );
+        assert!(matches!(
+            limiter.check_target("busy", &RegionCode::Europe),
+            RateLimitResult::Allowed { remaining: 0 }
+        ));
+        match limiter.check_target("busy", &RegionCode::Europe) {
+            RateLimitResult::Limited { dimension, retry_after_ms } => {
+                assert_eq!(dimension, RateLimitDimension::App);
+                assert!(retry_after_ms > 0);
+            }
+            other => panic!("expected app limit, got {:?}", other),
+        }
+
+        // Other apps have buckets of their own
+        assert!(matches!(
+            limiter.check_target("quiet", &RegionCode::Europe),
+            RateLimitResult::Allowed { .. }
+        ));
+        assert_eq!(limiter.app_remaining("busy"), 0);
+        assert_eq!(limiter.app_remaining("unseen"), 2);
+    }
+
+    #[test]
+    fn test_check_target_region_limit_refunds_app() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            per_app: Some(target_limit(5)),
+            per_region: Some(target_limit(1)),
+            ..Default::default()
+        });
+
+        assert!(matches!(
+            limiter.check_target("a", &RegionCode::AsiaPacific),
+            RateLimitResult::Allowed { remaining: 0 }
+        ));
+        assert!(matches!(
+            limiter.check_target("a", &RegionCode::AsiaPacific),
+            RateLimitResult::Limited { dimension: RateLimitDimension::Region, .. }
+        ));
+        // The refused connection does not count against the app
+        assert_eq!(limiter.app_remaining("a"), 4);
+        assert_eq!(limiter.region_remaining(&RegionCode::AsiaPacific), 0);
+
+        // Another region still has room
+        assert!(matches!(
+            limiter.check_target("a", &RegionCode::Europe),
+            RateLimitResult::Allowed { .. }
+        ));
+    }
+
+    #[test]
+    fn test_check_client_reports_dimension() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            burst_size: 1,
+            ..Default::default()
+        });
+
+        let ip = test_ip(1);
+        assert_eq!(
+            limiter.check_client(ip, 1),
+            RateLimitResult::Allowed { remaining: 0 }
+        );
+        assert!(matches!(
+            limiter.check_client(ip, 1),
+            RateLimitResult::Limited { dimension: RateLimitDimension::Client, .. }
+        ));
+    }
+
+    #[test]
+    fn test_clear_all_and_cleanup_cover_targets() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            per_app: Some(target_limit(1)),
+            per_region: Some(target_limit(1)),
+            ..Default::default()
+        });
+
+        limiter.check_target("a", &RegionCode::Europe);
+        limiter.clear_all();
+        assert_eq!(limiter.app_remaining("a"), 1);
+        assert_eq!(limiter.region_remaining(&RegionCode::Europe), 1);
+
+        limiter.check_target("a", &RegionCode::Europe);
+        std::thread::sleep(Duration::from_millis(10));
+        limiter.cleanup(Duration::from_millis(1));
+        assert_eq!(limiter.app_remaining("a"), 1);
+        assert_eq!(limiter.region_remaining(&RegionCode::Europe), 1);
+    }
+
+    #[test]
+    fn test_rate_limit_dimension_display() {
+        assert_eq!(RateLimitDimension::Client.to_string(), "client");
+        assert_eq!(RateLimitDimension::App.to_string(), "app");
+        assert_eq!(RateLimitDimension::Region.to_string(), "region");
+    }
 }
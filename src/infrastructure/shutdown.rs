@@ -2,25 +2,40 @@
 //!
 //! Provides coordinated shutdown for all server components.
 
-use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::broadcast;
 use tokio::sync::Notify;
+use std::task::{Context, Poll};
+use tokio::task::{AbortHandle, JoinError, JoinHandle};
 
 /// Shutdown coordinator for graceful termination.
 ///
 /// Tracks active connections and signals shutdown to all components.
+/// Can also be put in draining mode, where listeners refuse new
+/// connections but existing ones keep being served.
 #[derive(Clone)]
 pub struct ShutdownController {
     /// Whether shutdown has been initiated
     shutdown_initiated: Arc<AtomicBool>,
+    /// Whether draining was requested (e.g. through the API)
+    draining: Arc<AtomicBool>,
     /// Number of active connections
     active_connections: Arc<AtomicUsize>,
     /// Broadcast channel for shutdown signal
     shutdown_tx: broadcast::Sender<()>,
     /// Notify when all connections are drained
     drain_complete: Arc<Notify>,
+    /// Running connection tasks, aborted if the drain times out
+    connections: Arc<Mutex<HashMap<u64, AbortHandle>>>,
+    next_connection_id: Arc<AtomicU64>,
+    /// Connections aborted because the drain timed out
+    force_closed: Arc<AtomicUsize>,
 }
 
 impl ShutdownController {
@@ -29,9 +44,13 @@ impl ShutdownController {
         let (shutdown_tx, _) = broadcast::channel(1);
         Self {
             shutdown_initiated: Arc::new(AtomicBool::new(false)),
+            draining: Arc::new(AtomicBool::new(false)),
             active_connections: Arc::new(AtomicUsize::new(0)),
             shutdown_tx,
             drain_complete: Arc::new(Notify::new()),
+            connections: Arc::new(Mutex::new(HashMap::new())),
+            next_connection_id: Arc::new(AtomicU64::new(0)),
+            force_closed: Arc::new(AtomicUsize::new(0)),
         }
     }
 
@@ -53,6 +72,32 @@ impl ShutdownController {
         self.shutdown_initiated.load(Ordering::SeqCst)
     }
 
+    /// Start draining: refuse new connections, keep serving existing ones.
+    pub fn drain(&self) {
+        if !self.draining.swap(true, Ordering::SeqCst) {
+            tracing::info!("draining: refusing new connections");
+        }
+    }
+
+    /// Leave draining mode.
+    ///
+    /// Returns false, leaving the node drained, if shutdown has already
+    /// been initiated.
+    pub fn undrain(&self) -> bool {
+        if self.is_shutdown() {
+            return false;
+        }
+        if self.draining.swap(false, Ordering::SeqCst) {
+            tracing::info!("drain cancelled: accepting new connections");
+        }
+        true
+    }
+
+    /// Check if new connections should be refused (draining or shutting down).
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::SeqCst) || self.is_shutdown()
+    }
+
     /// Get the number of active connections.
     pub fn active_connections(&self) -> usize {
         self.active_connections.load(Ordering::SeqCst)
@@ -74,12 +119,16 @@ impl ShutdownController {
     /// Wait for all connections to drain (with timeout).
     #[cfg_attr(coverage_nightly, coverage(off))]
     pub async fn wait_for_drain(&self, timeout: Duration) -> bool {
+        // Registered before the check, so a connection ending in between isn't missed
+        let drained = self.drain_complete.notified();
+        tokio::pin!(drained);
+        drained.as_mut().enable();
         if self.active_connections() == 0 {
             return true;
         }
 
         tokio::select! {
-            _ = self.drain_complete.notified() => true,
+            _ = drained => true,
             _ = tokio::time::sleep(timeout) => {
                 tracing::warn!(
                     "drain timeout: {} connections still active",
@@ -90,13 +139,64 @@ impl ShutdownController {
         }
     }
 
+    /// Wait up to `timeout` for connections to drain, then force-close the rest.
+    ///
+    /// Returns the number of connections force-closed (0 if all drained in
+    /// time). Only connections started with [`Self::spawn_connection`] can
+    /// be force-closed, and tasks they spawn must be held in an
+    /// [`AbortOnDrop`] to go down with them.
+    pub async fn drain_or_abort(&self, timeout: Duration) -> usize {
+        if self.wait_for_drain(timeout).await {
+            return 0;
+        }
+        let aborted = self.abort_connections();
+        tracing::warn!("drain timeout: force-closed {} connections", aborted);
+        aborted
+    }
+
+    /// Abort every running connection task, returning how many were aborted.
+    pub fn abort_connections(&self) -> usize {
+        let connections = std::mem::take(&mut *self.connections.lock());
+        for handle in connections.values() {
+            handle.abort();
+        }
+        self.force_closed.fetch_add(connections.len(), Ordering::SeqCst);
+        connections.len()
+    }
+
+    /// Get the number of connections force-closed after a drain timeout.
+    pub fn force_closed(&self) -> usize {
+        self.force_closed.load(Ordering::SeqCst)
+    }
+
     /// Create a connection guard that auto-decrements on drop.
     pub fn connection_guard(&self) -> ConnectionGuard {
         self.connection_started();
         ConnectionGuard {
             controller: self.clone(),
+            id: None,
         }
     }
+
+    /// Run a connection on its own task, counted as active until it ends
+    /// and aborted by [`Self::abort_connections`] if still running.
+    pub fn spawn_connection<F>(&self, connection: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let id = self.next_connection_id.fetch_add(1, Ordering::Relaxed);
+        let mut guard = self.connection_guard();
+        guard.id = Some(id);
+
+        // Held across the spawn: a task ending right away waits for its
+        // registration before removing it
+        let mut connections = self.connections.lock();
+        let task = tokio::spawn(async move {
+            let _guard = guard;
+            connection.await;
+        });
+        connections.insert(id, task.abort_handle());
+    }
 }
 
 impl Default for ShutdownController {
@@ -110,14 +210,40 @@ impl Default for ShutdownController {
 /// Automatically decrements the connection count when dropped.
 pub struct ConnectionGuard {
     controller: ShutdownController,
+    /// Registered task to forget, for guards made by `spawn_connection`
+    id: Option<u64>,
 }
 
 impl Drop for ConnectionGuard {
     fn drop(&mut self) {
+        if let Some(id) = self.id {
+            self.controller.connections.lock().remove(&id);
+        }
         self.controller.connection_ended();
     }
 }
 
+/// Spawned task that is aborted when its handle is dropped.
+///
+/// A plain `JoinHandle` detaches the task on drop, so helper tasks spawned
+/// by a connection (the two copy directions of a relay) would outlive an
+/// aborted connection task. Wrapping them ties their lifetime to it.
+pub struct AbortOnDrop<T>(pub JoinHandle<T>);
+
+impl<T> Drop for AbortOnDrop<T> {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+impl<T> Future for AbortOnDrop<T> {
+    type Output = Result<T, JoinError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.0).poll(cx)
+    }
+}
+
 /// Install signal handlers for graceful shutdown.
 ///
 /// Returns a future that completes when a shutdown signal is received.
@@ -429,4 +555,103 @@ mod tests {
         assert!(result.is_ok());
         assert!(result.unwrap()); // Should have drained successfully
     }
+
+    #[test]
+    fn test_drain_and_undrain() {
+        let controller = ShutdownController::new();
+        assert!(!controller.is_draining());
+
+        controller.drain();
+        controller.drain();
+        assert!(controller.is_draining());
+        assert!(!controller.is_shutdown());
+
+        assert!(controller.undrain());
+        assert!(!controller.is_draining());
+        // Undraining a node that isn't draining is a no-op
+        assert!(controller.undrain());
+    }
+
+    #[test]
+    fn test_drain_shared_between_clones() {
+        let controller = ShutdownController::new();
+        let clone = controller.clone();
+        clone.drain();
+        assert!(controller.is_draining());
+    }
+
+    #[test]
+    fn test_undrain_refused_after_shutdown() {
+        let controller = ShutdownController::new();
+        controller.drain();
+        controller.shutdown();
+
+        assert!(!controller.undrain());
+        assert!(controller.is_draining());
+    }
+
+    #[test]
+    fn test_shutdown_implies_draining() {
+        let controller = ShutdownController::new();
+        controller.shutdown();
+        assert!(controller.is_draining());
+    }
+
+    #[tokio::test]
+    async fn test_spawn_connection_tracks_and_forgets_task() {
+        let controller = ShutdownController::new();
+        let (tx, rx) = tokio::sync::oneshot::channel::<()>();
+        controller.spawn_connection(async move {
+            let _ = rx.await;
+        });
+        assert_eq!(controller.active_connections(), 1);
+        assert_eq!(controller.connections.lock().len(), 1);
+
+        tx.send(()).unwrap();
+        controller.shutdown();
+        assert_eq!(controller.drain_or_abort(Duration::from_millis(500)).await, 0);
+        assert_eq!(controller.active_connections(), 0);
+        assert!(controller.connections.lock().is_empty());
+        assert_eq!(controller.force_closed(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_drain_or_abort_force_closes_stuck_connections() {
+        let controller = ShutdownController::new();
+        for _ in 0..2 {
+            controller.spawn_connection(std::future::pending());
+        }
+        controller.spawn_connection(async {});
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        controller.shutdown();
+
+        let started = std::time::Instant::now();
+        let aborted = controller.drain_or_abort(Duration::from_millis(50)).await;
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert_eq!(aborted, 2);
+        assert_eq!(controller.force_closed(), 2);
+
+        // Aborted tasks release their connection count
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(controller.active_connections(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_abort_on_drop_aborts_task() {
+        let (tx, rx) = tokio::sync::oneshot::channel::<()>();
+        let task = AbortOnDrop(tokio::spawn(async move {
+            let _tx = tx;
+            std::future::pending::<()>().await;
+        }));
+        drop(task);
+        // The sender is dropped together with the aborted task
+        let result = tokio::time::timeout(Duration::from_secs(1), rx).await;
+        assert!(result.expect("task was not aborted").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_abort_on_drop_yields_output() {
+        let task = AbortOnDrop(tokio::spawn(async { 7 }));
+        assert_eq!(task.await.unwrap(), 7);
+    }
 }
@@ -4,16 +4,21 @@
 
 #![cfg_attr(coverage_nightly, feature(coverage_attribute))]
 
-use edge_proxy::adapters::inbound::{ApiServer, DnsServer, TcpServer, TlsConfig, TlsServer};
+use edge_proxy::adapters::inbound::{
+    ApiServer, ConnectConfig, DialOptions, DnsConfig, DnsServer, HttpServer, ListenOptions, LoopbackGeo, PressureAlert, PublicIpGeo, ResolveCache, SocketBuffers, TcpServer,
+    TlsConfig, TlsServer, UdpProxyServer,
+};
 use edge_proxy::adapters::outbound::{
-    DashMapBindingRepository, DashMapMetricsStore,
-    MaxMindGeoResolver, SqliteBackendRepository,
+    ConsulBackendRepository, ConsulConfig, DashMapBindingRepository, DashMapMetricsStore,
+    FileBackendRepository, HttpPublicIpProvider,
+    MaxMindGeoResolver, SqliteBackendRepository, StaticPublicIpProvider,
 };
-use edge_proxy::domain::ports::BackendRepository;
-use edge_proxy::application::ProxyService;
+use edge_proxy::domain::ports::{BackendRepository, PublicIpProvider};
+use edge_proxy::application::{GeoUnavailablePolicy, LoadBalancingStrategy, ProxyService};
 use edge_proxy::config::load_config;
 use edge_proxy::domain::ports::GeoResolver;
 use edge_proxy::domain::value_objects::RegionCode;
+use edge_proxy::infrastructure::{shutdown_signal, ConfigWatcher, ShutdownController};
 use edge_proxy::replication::{ReplicationAgent, ReplicationConfig};
 use std::path::Path;
 use std::sync::Arc;
@@ -41,7 +46,11 @@ async fn main() -> anyhow::Result<()> {
     tracing::info!(
         "starting edgeProxy region={} listen={} (hexagonal architecture)",
         cfg.region,
-        cfg.listen_addr
+        std::iter::once(&cfg.listen_addr)
+            .chain(&cfg.extra_listen_addrs)
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(",")
     );
 
     // ===== COMPOSITION ROOT =====
@@ -49,23 +58,66 @@ async fn main() -> anyhow::Result<()> {
 
     // 1. Create outbound adapters
 
-    // Backend repository - uses SQLite for local storage
-    // When replication is enabled, the replication module syncs the state.db across nodes
-    let backend_repo: Arc<dyn BackendRepository> = {
+    // Metrics store (DashMap)
+    let metrics = Arc::new(DashMapMetricsStore::new());
+
+    // Resume cumulative counters from the last snapshot (optional)
+    if let Some(path) = &cfg.metrics_snapshot_path {
+        match metrics.load_snapshot(Path::new(path)) {
+            Ok(true) => tracing::info!("metrics counters restored from {}", path),
+            Ok(false) => tracing::info!("no metrics snapshot at {} yet", path),
+            Err(e) => tracing::warn!("ignoring unreadable metrics snapshot {}: {:?}", path, e),
+        }
+        DashMapMetricsStore::start_snapshots(
+            metrics.clone(),
+            path.into(),
+            Duration::from_secs(cfg.metrics_snapshot_secs.max(1)),
+        );
+    }
+
+    // Backend repository - uses SQLite for local storage, or a static file or Consul if configured
+    // When replication is enabled, the replication module syncs the routing DB across nodes
+    let sqlite_repo = Arc::new(SqliteBackendRepository::new().with_metrics(metrics.clone()));
+    let backend_repo: Arc<dyn BackendRepository> = if let Some(path) = &cfg.backends_file {
+        tracing::info!("using file backend repository (path={})", path);
+        let file_repo = Arc::new(FileBackendRepository::load(path)?);
+        let watcher = Arc::new(ConfigWatcher::new(Duration::from_secs(cfg.db_reload_secs.max(1))));
+        file_repo.start_watching(watcher.clone()).await?;
+        watcher.start();
+        file_repo
+    } else if let Some(service) = &cfg.consul_service {
+        tracing::info!("using Consul backend repository (addr={}, service={})", cfg.consul_addr, service);
+        let consul_repo = Arc::new(ConsulBackendRepository::new(ConsulConfig {
+            address: cfg.consul_addr.clone(),
+            service: service.clone(),
+            token: cfg.consul_token.clone(),
+            datacenter: cfg.consul_datacenter.clone(),
+            ..Default::default()
+        })?);
+        if let Err(e) = consul_repo.refresh().await {
+            tracing::error!("initial Consul query failed, retrying in the background: {:#}", e);
+        }
+        consul_repo.start_watching();
+        consul_repo
+    } else {
         tracing::info!("using SQLite backend repository (path={})", cfg.db_path);
-        let repo = Arc::new(SqliteBackendRepository::new());
-        repo.start_sync(cfg.db_path.clone(), cfg.db_reload_secs);
-        repo
+        sqlite_repo.start_sync(cfg.db_path.clone(), cfg.db_reload_secs);
+        sqlite_repo.clone()
     };
 
     // Binding repository (DashMap)
-    let binding_repo = Arc::new(DashMapBindingRepository::new());
+    let binding_repo = Arc::new(DashMapBindingRepository::new().on_evict(|key, backend_id| {
+        tracing::debug!("binding expired: {} -> {}", key.client_ip, backend_id);
+    }));
     binding_repo.start_gc(
         Duration::from_secs(cfg.binding_ttl_secs),
         Duration::from_secs(cfg.binding_gc_interval_secs),
     );
 
     // Start built-in replication (if enabled)
+    if let Some(issue) = cfg.replication_routing_issue() {
+        tracing::warn!("{}", issue);
+    }
     if cfg.replication_enabled {
         let node_id = cfg.replication_node_id.clone()
             .unwrap_or_else(|| format!("{}-{}", cfg.region, uuid::Uuid::new_v4().to_string()[..8].to_string()));
@@ -75,9 +127,17 @@ async fn main() -> anyhow::Result<()> {
             .transport_addr(cfg.replication_transport_addr.parse()?)
             .bootstrap_peers(cfg.replication_bootstrap_peers.clone())
             .db_path(&cfg.replication_db_path)
-            .cluster_name(&cfg.replication_cluster_name);
+            .cluster_name(&cfg.replication_cluster_name)
+            .insecure_transport(cfg.replication_insecure_transport);
+        let replication_config = match &cfg.replication_cluster_secret {
+            Some(secret) => replication_config.cluster_secret(secret),
+            None => replication_config,
+        };
 
-        let mut agent = ReplicationAgent::new(replication_config)?;
+        let mut agent = ReplicationAgent::new(replication_config)?.with_metrics(metrics.clone());
+
+        // Reload routing as soon as replicated backend changes are applied
+        sqlite_repo.start_notified_sync(cfg.db_path.clone(), agent.subscribe_backend_changes());
 
         tracing::info!(
             "starting built-in replication node_id={} gossip={} transport={}",
@@ -98,7 +158,15 @@ async fn main() -> anyhow::Result<()> {
         Some(path) => match MaxMindGeoResolver::from_file(path) {
             Ok(g) => {
                 tracing::info!("GeoIP DB loaded from {}", path);
-                Some(Arc::new(g) as Arc<dyn GeoResolver>)
+                let g = Arc::new(g);
+                if cfg.geoip_reload_secs > 0 {
+                    let watcher = Arc::new(ConfigWatcher::new(Duration::from_secs(cfg.geoip_reload_secs)));
+                    match g.start_watching(path, watcher.clone()).await {
+                        Ok(()) => watcher.start(),
+                        Err(e) => tracing::warn!("not watching GeoIP DB {} for changes: {:#}", path, e),
+                    }
+                }
+                Some(g as Arc<dyn GeoResolver>)
             }
             Err(e) => {
                 tracing::error!("failed to load GeoIP DB from {}: {:?}", path, e);
@@ -117,23 +185,95 @@ async fn main() -> anyhow::Result<()> {
         },
     };
 
-    // Metrics store (DashMap)
-    let metrics = Arc::new(DashMapMetricsStore::new());
+    // Routing policy while no GeoIP database is loaded
+    let geo_unavailable = cfg
+        .geo_unavailable
+        .parse::<GeoUnavailablePolicy>()
+        .unwrap_or_else(|e| {
+            tracing::warn!("{}, routing without geo", e);
+            GeoUnavailablePolicy::FailOpen
+        });
+    if geo_resolver.is_none() {
+        match geo_unavailable {
+            GeoUnavailablePolicy::FailOpen => {
+                tracing::warn!("no GeoIP DB loaded, routing clients without geo")
+            }
+            GeoUnavailablePolicy::FailClosed => {
+                tracing::error!("no GeoIP DB loaded, refusing to route clients")
+            }
+        }
+    }
 
     // 2. Create application service
-    let proxy_service = Arc::new(ProxyService::new(
-        backend_repo,
-        binding_repo,
-        geo_resolver.clone(),
-        metrics,
-        RegionCode::from_str(&cfg.region),
-    ));
+    let lb_strategy = cfg
+        .lb_strategy
+        .parse::<LoadBalancingStrategy>()
+        .unwrap_or_else(|e| {
+            tracing::warn!("{}, using geo load balancing", e);
+            LoadBalancingStrategy::GeoLoadScore
+        });
+    let local_region = cfg.region.parse::<RegionCode>().unwrap_or_else(|e| {
+        tracing::warn!("{}, no region is local to this POP", e);
+        RegionCode::Unknown
+    });
+    let proxy_service = Arc::new(
+        ProxyService::builder()
+            .backend_repo(backend_repo)
+            .binding_repo(binding_repo)
+            .maybe_geo_resolver(geo_resolver)
+            .metrics(metrics)
+            .local_region(local_region)
+            .strict_country(cfg.strict_country)
+            .zero_weight_fallback(cfg.zero_weight_fallback)
+            .geo_unavailable(geo_unavailable)
+            .strategy(lb_strategy)
+            .build()?,
+    );
 
     // 3. Create inbound adapters and run
 
+    // Socket buffer sizes for client and backend connections
+    let socket_buffers = SocketBuffers {
+        recv: cfg.socket_recv_buffer,
+        send: cfg.socket_send_buffer,
+    };
+
+    // Socket options shared by the TCP, TLS and DNS listeners
+    let listen_options = ListenOptions {
+        reuse_port: cfg.reuse_port,
+        buffers: socket_buffers,
+        backlog: cfg.listen_backlog,
+    };
+
+    // Socket options and hostname cache for connections to backends
+    let dial_options = DialOptions {
+        tcp_fast_open: cfg.tcp_fast_open,
+        resolver: ResolveCache::new(Duration::from_secs(cfg.backend_resolve_ttl_secs)),
+        buffers: socket_buffers,
+        ..Default::default()
+    };
+    dial_options.pressure.start_monitor(
+        proxy_service.clone(),
+        PressureAlert {
+            threshold: cfg.connect_pressure_threshold,
+            sustain: Duration::from_secs(cfg.connect_pressure_duration_secs),
+        },
+    );
+
+    // Drain state shared by the listeners and the API's /admin/drain
+    let shutdown = ShutdownController::new();
+
     // Start Auto-Discovery API server (optional)
     if cfg.api_enabled {
-        let api_server = ApiServer::new(cfg.api_listen_addr.clone(), cfg.heartbeat_ttl_secs);
+        let api_server = ApiServer::new(cfg.api_listen_addr.clone(), cfg.heartbeat_ttl_secs)
+            .with_socket_mode(cfg.api_socket_mode)
+            .with_listen_options(ListenOptions {
+                backlog: cfg.listen_backlog,
+                ..Default::default()
+            })
+            .with_proxy_service(proxy_service.clone())
+            .with_shutdown(shutdown.clone())
+            .with_config(cfg.redacted());
         api_server.start_cleanup_task(30); // Cleanup every 30 seconds
 
         tokio::spawn(async move {
@@ -144,14 +284,45 @@ async fn main() -> anyhow::Result<()> {
         tracing::info!("Auto-Discovery API enabled on {}", cfg.api_listen_addr);
     }
 
+    // Geo for loopback clients, looked up once and shared by every listener
+    let static_public_ip = cfg.public_ip.as_deref().and_then(|ip| {
+        ip.parse()
+            .map_err(|e| tracing::error!("ignoring invalid public IP {}: {:?}", ip, e))
+            .ok()
+    });
+    let public_ip_provider: Arc<dyn PublicIpProvider> = match static_public_ip {
+        Some(ip) => Arc::new(StaticPublicIpProvider::new(ip)),
+        None => Arc::new(HttpPublicIpProvider::new(cfg.public_ip_url.clone())),
+    };
+    let loopback_geo = cfg.loopback_geo.parse::<LoopbackGeo>().unwrap_or_else(|e| {
+        tracing::error!("{:?}, locating loopback clients at the public IP", e);
+        LoopbackGeo::default()
+    });
+    let public_ip_geo =
+        Arc::new(PublicIpGeo::new(public_ip_provider).with_loopback_geo(loopback_geo));
+
     // Start DNS server (optional)
     if cfg.dns_enabled {
-        let dns_server = DnsServer::new(
-            cfg.dns_listen_addr.clone(),
-            proxy_service.clone(),
-            geo_resolver.clone(),
-            cfg.dns_domain.clone(),
-        );
+        let nat64_prefix = cfg.dns_nat64_prefix.as_deref().and_then(|p| {
+            DnsConfig::parse_nat64_prefix(p)
+                .map_err(|e| tracing::error!("ignoring invalid DNS NAT64 prefix {}: {:?}", p, e))
+                .ok()
+        });
+        let dns_config = DnsConfig {
+            domains: cfg.dns_domains.clone(),
+            servfail_on_unhealthy: cfg.dns_servfail_on_unhealthy,
+            nat64_prefix,
+            weighted_rotation: cfg.dns_weighted_rotation,
+            wildcard_apps: cfg.dns_wildcard_apps.clone(),
+            max_udp_payload: cfg.dns_max_udp_payload,
+            nxdomain_when_draining: cfg.dns_nxdomain_when_draining,
+            ..Default::default()
+        };
+        let dns_server =
+            DnsServer::with_config(cfg.dns_listen_addr.clone(), proxy_service.clone(), dns_config)
+                .with_listen_options(listen_options)
+                .with_public_ip_geo(public_ip_geo.clone())
+                .with_shutdown(shutdown.clone());
 
         tokio::spawn(async move {
             if let Err(e) = dns_server.run().await {
@@ -159,12 +330,16 @@ async fn main() -> anyhow::Result<()> {
             }
         });
         tracing::info!(
-            "DNS server enabled on {} for .{} domain",
+            "DNS server enabled on {} for .{}",
             cfg.dns_listen_addr,
-            cfg.dns_domain
+            cfg.dns_domains.join(", .")
         );
     }
 
+    // Hard cap on proxied session duration (0 = unlimited)
+    let max_session =
+        (cfg.max_session_secs > 0).then(|| Duration::from_secs(cfg.max_session_secs));
+
     // Start TLS server (optional)
     if cfg.tls_enabled {
         let tls_listen_addr = cfg
@@ -177,18 +352,23 @@ async fn main() -> anyhow::Result<()> {
             (Some(cert), Some(key)) => {
                 TlsConfig::from_pem_files(Path::new(cert), Path::new(key))?
             }
+            // A certificate path without a key is a combined cert + key bundle
+            (Some(bundle), None) => TlsConfig::from_pem_bundle(Path::new(bundle))?,
             _ => {
                 tracing::warn!("No TLS cert/key provided, generating self-signed certificate");
                 TlsConfig::self_signed("edgeproxy.internal")?
             }
         };
+        let tls_config = tls_config.with_early_data(cfg.tls_enable_early_data);
 
-        let tls_server = TlsServer::new(
-            proxy_service.clone(),
-            tls_listen_addr.clone(),
-            geo_resolver.clone(),
-            tls_config,
-        );
+        let tls_server = TlsServer::new(proxy_service.clone(), tls_listen_addr.clone(), tls_config)
+        .with_listen_options(listen_options)
+        .with_dial_options(dial_options.clone())
+        .with_max_session(max_session)
+        .with_public_ip_geo(public_ip_geo.clone())
+        .with_handshake_timeout(Duration::from_millis(cfg.tls_handshake_timeout_ms))
+        .with_shutdown(shutdown.clone())
+        .with_access_log(cfg.access_log);
 
         tokio::spawn(async move {
             if let Err(e) = tls_server.run().await {
@@ -198,7 +378,86 @@ async fn main() -> anyhow::Result<()> {
         tracing::info!("TLS server enabled on {}", tls_listen_addr);
     }
 
+    // Host -> app table, shared by CONNECT mode and HTTP host routing
+    let host_routing = (cfg.connect_proxy || cfg.http_listen_addr.is_some()).then(|| {
+        let hosts = ConnectConfig::parse_hosts(&cfg.connect_hosts).unwrap_or_else(|e| {
+            tracing::error!("ignoring invalid CONNECT host table: {:?}", e);
+            Default::default()
+        });
+        ConnectConfig {
+            // Host routing resolves names under the first DNS domain only
+            domain: cfg.dns_domains[0].clone(),
+            hosts,
+            expose_backend: cfg.connect_expose_backend,
+        }
+    });
+
+    // Start HTTP host routing server (optional)
+    if let (Some(http_listen_addr), Some(routing)) =
+        (cfg.http_listen_addr.clone(), host_routing.clone())
+    {
+        let http_server = HttpServer::new(proxy_service.clone(), http_listen_addr.clone(), routing)
+        .with_listen_options(listen_options)
+        .with_dial_options(dial_options.clone())
+        .with_public_ip_geo(public_ip_geo.clone())
+        .with_shutdown(shutdown.clone());
+
+        tokio::spawn(async move {
+            if let Err(e) = http_server.run().await {
+                tracing::error!("HTTP server error: {:?}", e);
+            }
+        });
+        tracing::info!("HTTP host routing enabled on {}", http_listen_addr);
+    }
+
+    // Start UDP relay server (optional)
+    if let Some(udp_listen_addr) = cfg.udp_listen_addr.clone() {
+        let udp_server = UdpProxyServer::new(proxy_service.clone(), udp_listen_addr.clone())
+        .with_listen_options(listen_options)
+        .with_session_timeout(Duration::from_secs(cfg.udp_session_timeout_secs))
+        .with_public_ip_geo(public_ip_geo.clone())
+        .with_shutdown(shutdown.clone())
+        .with_access_log(cfg.access_log);
+
+        tokio::spawn(async move {
+            if let Err(e) = udp_server.run().await {
+                tracing::error!("UDP server error: {:?}", e);
+            }
+        });
+        tracing::info!("UDP relay enabled on {}", udp_listen_addr);
+    }
+
+    // HTTP CONNECT proxy mode (optional)
+    let connect = host_routing.filter(|_| cfg.connect_proxy);
+    if let Some(connect) = &connect {
+        tracing::info!("HTTP CONNECT proxy mode enabled ({} host mappings)", connect.hosts.len());
+    }
+
     // Start main TCP server
-    let server = TcpServer::new(proxy_service, cfg.listen_addr, geo_resolver);
-    server.run().await
+    let drain_timeout = Duration::from_secs(cfg.drain_timeout_secs);
+    let server = cfg.extra_listen_addrs.into_iter().fold(
+        TcpServer::new(proxy_service, cfg.listen_addr),
+        TcpServer::add_listener,
+    );
+    let server = server
+        .with_listen_options(listen_options)
+        .with_dial_options(dial_options)
+        .with_max_session(max_session)
+        .with_public_ip_geo(public_ip_geo)
+        .with_connect_proxy(connect)
+        .with_shutdown(shutdown.clone())
+        .with_access_log(cfg.access_log);
+
+    tokio::select! {
+        result = server.run() => result,
+        _ = shutdown_signal(shutdown.clone()) => {
+            // Listeners refuse new connections from here on
+            let force_closed = shutdown.drain_or_abort(drain_timeout).await;
+            tracing::info!(
+                "shutdown complete ({} connections force-closed)",
+                force_closed
+            );
+            Ok(())
+        }
+    }
 }
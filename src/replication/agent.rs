@@ -3,15 +3,19 @@
 //! Orchestrates all replication components (gossip, sync, transport) to provide
 //! a unified interface for distributed state management.
 
+use crate::domain::ports::MetricsStore;
 use crate::replication::config::ReplicationConfig;
-use crate::replication::gossip::{GossipService, Member};
-use crate::replication::sync::SyncService;
-use crate::replication::transport::TransportService;
-use crate::replication::types::{Change, ChangeKind, ChangeSet, Message, NodeId};
+use crate::replication::events::{event_channel, EventSender};
+use crate::replication::gossip::{GossipEvent, GossipService, Incarnation, Member};
+use crate::replication::sync::{ReplicationLag, SyncService};
+use crate::replication::transport::{self, PeerConnection, TransportEvent, TransportService};
+use crate::replication::types::{Change, ChangeKind, ChangeSet, Message, NodeId, Snapshot};
+use serde::Serialize;
+use std::collections::HashSet;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
-use tokio::sync::{mpsc, RwLock};
+use tokio::sync::{mpsc, watch, RwLock};
 use tokio::time::interval;
 
 /// Events emitted by the replication agent.
@@ -29,6 +33,37 @@ pub enum ReplicationEvent {
     Error(String),
 }
 
+/// Point-in-time view of replication health.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ReplicationStats {
+    /// Members currently alive
+    pub alive_members: usize,
+    /// Members suspected of failure
+    pub suspect_members: usize,
+    /// Members declared dead
+    pub dead_members: usize,
+    /// Sequence of the last changeset produced locally
+    pub local_sequence: u64,
+    /// Nodes tracked in the version vector
+    pub version_vector_size: usize,
+    /// Open QUIC peer connections
+    pub connected_peers: usize,
+    /// Recorded changes not yet flushed
+    pub pending_changes: usize,
+}
+
+/// Exclusive right to dial a peer; released when the attempt is over.
+struct DialClaim {
+    peer: String,
+    dialing: Arc<parking_lot::Mutex<HashSet<String>>>,
+}
+
+impl Drop for DialClaim {
+    fn drop(&mut self) {
+        self.dialing.lock().remove(&self.peer);
+    }
+}
+
 /// Replication agent that orchestrates all components.
 pub struct ReplicationAgent {
     config: ReplicationConfig,
@@ -36,9 +71,15 @@ pub struct ReplicationAgent {
     gossip: Arc<GossipService>,
     sync: Arc<SyncService>,
     transport: Arc<RwLock<TransportService>>,
-    event_tx: mpsc::Sender<ReplicationEvent>,
+    event_tx: EventSender<ReplicationEvent>,
     event_rx: Option<mpsc::Receiver<ReplicationEvent>>,
+    metrics: Option<Arc<dyn MetricsStore>>,
     shutdown: Arc<AtomicBool>,
+    gossip_rx: Option<mpsc::Receiver<GossipEvent>>,
+    /// Peers with a QUIC connect in flight
+    dialing: Arc<parking_lot::Mutex<HashSet<String>>>,
+    /// Set while a freshly joined node still has to fetch a snapshot
+    needs_snapshot: Arc<AtomicBool>,
 }
 
 impl ReplicationAgent {
@@ -47,10 +88,18 @@ impl ReplicationAgent {
         config.validate()?;
 
         let node_id = NodeId::new(&config.node_id);
-        let (event_tx, event_rx) = mpsc::channel(1024);
-
-        let gossip = Arc::new(GossipService::new(config.clone()));
-        let sync = Arc::new(SyncService::new(node_id.clone(), config.db_path.clone()));
+        let (event_tx, event_rx) =
+            event_channel(config.event_channel_capacity, config.event_overflow);
+
+        let incarnation = Incarnation::load(&config.db_path, config.incarnation_seed)?;
+        let mut gossip = GossipService::new(config.clone()).with_incarnation(incarnation);
+        let gossip_rx = gossip.take_event_rx();
+        let gossip = Arc::new(gossip);
+        let sync = Arc::new(
+            SyncService::new(node_id.clone(), config.db_path.clone())
+                .with_max_clock_skew(config.max_clock_skew)
+                .with_event_channel(config.event_channel_capacity, config.event_overflow),
+        );
         let transport = Arc::new(RwLock::new(TransportService::new(config.clone())));
 
         Ok(Self {
@@ -61,10 +110,21 @@ impl ReplicationAgent {
             transport,
             event_tx,
             event_rx: Some(event_rx),
+            metrics: None,
             shutdown: Arc::new(AtomicBool::new(false)),
+            gossip_rx,
+            dialing: Arc::new(parking_lot::Mutex::new(HashSet::new())),
+            needs_snapshot: Arc::new(AtomicBool::new(false)),
         })
     }
 
+    /// Publish replication metrics (per-peer lag, rejected changes) to a metrics store.
+    pub fn with_metrics(mut self, metrics: Arc<dyn MetricsStore>) -> Self {
+        self.sync.set_metrics(metrics.clone());
+        self.metrics = Some(metrics);
+        self
+    }
+
     /// Get the event receiver.
     pub fn take_event_rx(&mut self) -> Option<mpsc::Receiver<ReplicationEvent>> {
         self.event_rx.take()
@@ -85,6 +145,25 @@ impl ReplicationAgent {
         self.gossip.alive_members()
     }
 
+    /// Replication lag of every peer heard from, sorted by peer.
+    pub fn replication_lag(&self) -> Vec<(String, ReplicationLag)> {
+        self.sync.replication_lag()
+    }
+
+    /// Snapshot of membership, sync and transport state.
+    pub async fn stats(&self) -> ReplicationStats {
+        let (alive_members, suspect_members, dead_members) = self.gossip.member_counts();
+        ReplicationStats {
+            alive_members,
+            suspect_members,
+            dead_members,
+            local_sequence: self.sync.sequence(),
+            version_vector_size: self.sync.version_vector().len(),
+            connected_peers: self.transport.read().await.peer_count().await,
+            pending_changes: self.sync.pending_count(),
+        }
+    }
+
     /// Check if replication is enabled/running.
     pub fn is_running(&self) -> bool {
         !self.shutdown.load(Ordering::SeqCst)
@@ -103,6 +182,12 @@ impl ReplicationAgent {
         // Initialize sync database
         self.sync.init_db()?;
 
+        // A node with no replicated state bootstraps from the first peer it
+        // connects to instead of waiting on the replication log
+        if self.sync.version_vector().is_empty() && !self.config.bootstrap_peers.is_empty() {
+            self.needs_snapshot.store(true, Ordering::SeqCst);
+        }
+
         // Start transport
         {
             let mut transport = self.transport.write().await;
@@ -111,16 +196,25 @@ impl ReplicationAgent {
 
         // Start gossip
         self.gossip.clone().start().await?;
+        let gossip_rx = self.gossip_rx.take();
+        self.start_gossip_loop(gossip_rx);
 
         // Start event processing
-        self.start_event_loop();
+        let transport_rx = self.transport.write().await.take_event_rx();
+        self.start_event_loop(transport_rx);
 
         // Start periodic flush
         self.start_flush_loop();
 
+        // Start QUIC keepalive pings
+        self.start_keepalive_loop();
+
+        // Start replication log compaction
+        self.start_compaction_loop();
+
         // Notify joined
         let members = self.gossip.alive_members().len();
-        let _ = self.event_tx.send(ReplicationEvent::ClusterJoined { members }).await;
+        self.event_tx.send(ReplicationEvent::ClusterJoined { members }).await;
 
         tracing::info!("replication agent started");
         Ok(())
@@ -134,6 +228,11 @@ impl ReplicationAgent {
         self.transport.read().await.shutdown();
     }
 
+    /// Subscribe to replicated changes of the backends table.
+    pub fn subscribe_backend_changes(&self) -> watch::Receiver<u64> {
+        self.sync.subscribe_backend_changes()
+    }
+
     /// Record a backend change for replication.
     pub fn record_backend_change(&self, id: &str, kind: ChangeKind, data: &str) {
         self.sync.record_change("backends", id, kind, data);
@@ -164,10 +263,208 @@ impl ReplicationAgent {
         self.sync.apply_changeset(changeset).await
     }
 
+    /// Capture this node's replicated state for a joining peer.
+    pub fn snapshot(&self) -> anyhow::Result<Snapshot> {
+        self.sync.snapshot()
+    }
+
+    /// Fetch a snapshot from `peer` and apply it.
+    ///
+    /// Returns the number of backend rows written.
+    pub async fn request_snapshot(&self, peer: &PeerConnection) -> anyhow::Result<usize> {
+        Self::fetch_snapshot(&self.sync, peer).await
+    }
+
     #[cfg_attr(coverage_nightly, coverage(off))]
-    fn start_event_loop(&self) {
+    async fn fetch_snapshot(sync: &SyncService, peer: &PeerConnection) -> anyhow::Result<usize> {
+        match peer.request(&Message::SnapshotRequest).await? {
+            Message::SnapshotResponse(snapshot) => sync.apply_snapshot(&snapshot),
+            other => anyhow::bail!(
+                "expected SnapshotResponse, got {}",
+                transport::message_type_name(&other)
+            ),
+        }
+    }
+
+    /// Bootstrap from `peer` if this node still needs a snapshot.
+    ///
+    /// Only one peer is asked at a time; if it fails, the next peer that
+    /// connects is asked instead.
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    async fn bootstrap_from(sync: &SyncService, needs_snapshot: &AtomicBool, peer: &PeerConnection) {
+        if needs_snapshot
+            .compare_exchange(true, false, Ordering::SeqCst, Ordering::SeqCst)
+            .is_err()
+        {
+            return;
+        }
+
+        match Self::fetch_snapshot(sync, peer).await {
+            Ok(rows) => tracing::info!("bootstrapped {} backends from {}'s snapshot", rows, peer.node_id),
+            Err(e) => {
+                tracing::warn!("snapshot from {} failed, will ask the next peer: {:?}", peer.node_id, e);
+                needs_snapshot.store(true, Ordering::SeqCst);
+            }
+        }
+    }
+
+    /// Handle a message received from a peer.
+    ///
+    /// Returns the reply to send back to the peer, if any.
+    pub async fn handle_message(&self, from: &NodeId, message: Message) -> Option<Message> {
+        Self::process_message(&self.sync, &self.transport, from, message).await
+    }
+
+    /// Apply broadcasts (replying with an ack), record acks from peers and
+    /// answer snapshot requests.
+    ///
+    /// Broadcasts and acks are also recorded as peer progress for the
+    /// replication lag.
+    ///
+    /// An already-seen changeset is acked again, since the earlier ack may
+    /// have been lost. Changesets that fail to apply are not acked so the
+    /// sender re-sends them.
+    async fn process_message(
+        sync: &SyncService,
+        transport: &RwLock<TransportService>,
+        from: &NodeId,
+        message: Message,
+    ) -> Option<Message> {
+        match message {
+            Message::Broadcast(changeset) => {
+                sync.observe_broadcast(&changeset.source, changeset.seq);
+                match sync.apply_changeset(&changeset).await {
+                    Ok(_) => Some(transport::create_ack(&changeset)),
+                    Err(e) => {
+                        tracing::warn!(
+                            "failed to apply changeset seq={} from {}: {:?}",
+                            changeset.seq,
+                            from,
+                            e
+                        );
+                        None
+                    }
+                }
+            }
+            Message::Ack { source, seq } => {
+                sync.observe_ack(from, &source, seq);
+                if !transport.read().await.handle_ack(from, &source, seq) {
+                    tracing::debug!("unexpected ack seq={} source={} from {}", seq, source, from);
+                }
+                None
+            }
+            Message::SnapshotRequest => match sync.snapshot() {
+                Ok(snapshot) => {
+                    tracing::info!(
+                        "sending snapshot of {} backends to {}",
+                        snapshot.backends.len(),
+                        from
+                    );
+                    Some(Message::SnapshotResponse(snapshot))
+                }
+                Err(e) => {
+                    tracing::warn!("failed to take snapshot for {}: {:?}", from, e);
+                    None
+                }
+            },
+            other => {
+                tracing::debug!(
+                    "ignoring {} message from {}",
+                    transport::message_type_name(&other),
+                    from
+                );
+                None
+            }
+        }
+    }
+
+    /// Decide whether a gossip event calls for dialing a peer.
+    ///
+    /// `MemberJoined` is idempotent: a flapping peer may be reported again
+    /// while it is still connected or being dialed, and neither case dials.
+    async fn on_gossip_event(
+        transport: &RwLock<TransportService>,
+        dialing: &Arc<parking_lot::Mutex<HashSet<String>>>,
+        local: &NodeId,
+        event: GossipEvent,
+    ) -> Option<(Member, DialClaim)> {
+        let GossipEvent::MemberJoined(member) = event else {
+            return None;
+        };
+        if member.node_id == *local {
+            return None;
+        }
+
+        if let Some(peer) = transport.read().await.get_peer(&member.node_id.0).await {
+            if peer.is_alive() {
+                tracing::debug!("member {} rejoined, already connected", member.node_id);
+                return None;
+            }
+        }
+
+        if !dialing.lock().insert(member.node_id.0.clone()) {
+            tracing::debug!("member {} rejoined, connect already in progress", member.node_id);
+            return None;
+        }
+
+        let claim = DialClaim {
+            peer: member.node_id.0.clone(),
+            dialing: dialing.clone(),
+        };
+        Some((member, claim))
+    }
+
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    fn start_gossip_loop(&self, gossip_rx: Option<mpsc::Receiver<GossipEvent>>) {
+        let Some(mut gossip_rx) = gossip_rx else {
+            return;
+        };
+        let transport = self.transport.clone();
+        let sync = self.sync.clone();
+        let dialing = self.dialing.clone();
+        let needs_snapshot = self.needs_snapshot.clone();
+        let node_id = self.node_id.clone();
+        let shutdown = self.shutdown.clone();
+
+        tokio::spawn(async move {
+            while let Some(event) = gossip_rx.recv().await {
+                if shutdown.load(Ordering::SeqCst) {
+                    break;
+                }
+
+                let Some((member, claim)) =
+                    Self::on_gossip_event(&transport, &dialing, &node_id, event).await
+                else {
+                    continue;
+                };
+
+                let transport = transport.clone();
+                let sync = sync.clone();
+                let needs_snapshot = needs_snapshot.clone();
+                tokio::spawn(async move {
+                    let _claim = claim;
+                    let result = transport
+                        .read()
+                        .await
+                        .connect(member.transport_addr, &member.node_id.0)
+                        .await;
+                    match result {
+                        Ok(peer) => Self::bootstrap_from(&sync, &needs_snapshot, &peer).await,
+                        Err(e) => {
+                            tracing::debug!("failed to connect to {}: {:?}", member.node_id, e)
+                        }
+                    }
+                });
+            }
+        });
+    }
+
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    fn start_event_loop(&self, mut transport_rx: Option<mpsc::Receiver<TransportEvent>>) {
         let gossip = self.gossip.clone();
+        let sync = self.sync.clone();
         let transport = self.transport.clone();
+        let metrics = self.metrics.clone();
         let shutdown = self.shutdown.clone();
 
         tokio::spawn(async move {
@@ -178,15 +475,50 @@ impl ReplicationAgent {
                     break;
                 }
 
-                check_interval.tick().await;
+                let event = async {
+                    match transport_rx.as_mut() {
+                        Some(rx) => rx.recv().await,
+                        None => std::future::pending().await,
+                    }
+                };
+
+                tokio::select! {
+                    Some(event) = event => {
+                        match event {
+                            TransportEvent::MessageReceived { from, message } => {
+                                let reply = Self::process_message(&sync, &transport, &from, message).await;
+                                if let Some(reply) = reply {
+                                    if let Err(e) = transport.read().await.send_to(&from.0, &reply).await {
+                                        tracing::debug!("failed to reply to {}: {:?}", from, e);
+                                    }
+                                }
+                            }
+                            TransportEvent::RequestReceived { from, message, reply } => {
+                                // Dropping `reply` unanswered closes the peer's stream
+                                if let Some(response) = Self::process_message(&sync, &transport, &from, message).await {
+                                    let _ = reply.send(response);
+                                }
+                            }
+                            _ => {}
+                        }
+                        continue;
+                    }
+                    _ = check_interval.tick() => {}
+                }
+
+                // Re-send changesets peers haven't acked in time
+                let resent = transport.read().await.resend_unacked().await;
+                if resent > 0 {
+                    tracing::debug!("re-sent {} un-acked changesets", resent);
+                }
 
-                // In a full implementation, we would:
-                // 1. Process gossip events (member joins/leaves)
-                // 2. Process transport events (received messages)
-                // 3. Process sync events (applied changes)
-                // 4. Connect to newly discovered peers
+                if let Some(metrics) = &metrics {
+                    for (peer, lag) in sync.replication_lag() {
+                        metrics.record_replication_lag(&peer, lag.inbound, lag.outbound);
+                    }
+                }
 
-                // For now, just check gossip members periodically
+                // Joined members are dialed by the gossip loop; report any still unconnected
                 let members = gossip.alive_members();
                 for member in members {
                     // Ensure we have transport connections to alive members
@@ -199,6 +531,54 @@ impl ReplicationAgent {
         });
     }
 
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    fn start_keepalive_loop(&self) {
+        let transport = self.transport.clone();
+        let shutdown = self.shutdown.clone();
+        let ping_interval = self.config.ping_interval;
+
+        tokio::spawn(async move {
+            let mut timer = interval(ping_interval);
+
+            loop {
+                if shutdown.load(Ordering::SeqCst) {
+                    break;
+                }
+
+                timer.tick().await;
+
+                // Disconnects (and reports) peers that stopped answering
+                transport.read().await.ping_peers().await;
+            }
+        });
+    }
+
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    fn start_compaction_loop(&self) {
+        let sync = self.sync.clone();
+        let shutdown = self.shutdown.clone();
+        let compaction_interval = self.config.log_compaction_interval;
+        let retention = self.config.log_retention;
+
+        tokio::spawn(async move {
+            let mut timer = interval(compaction_interval);
+
+            loop {
+                if shutdown.load(Ordering::SeqCst) {
+                    break;
+                }
+
+                timer.tick().await;
+
+                match sync.compact_log(retention) {
+                    Ok(0) => {}
+                    Ok(deleted) => tracing::debug!("compacted replication log: removed {} rows", deleted),
+                    Err(e) => tracing::warn!("replication log compaction failed: {}", e),
+                }
+            }
+        });
+    }
+
     #[cfg_attr(coverage_nightly, coverage(off))]
     fn start_flush_loop(&self) {
         let sync = self.sync.clone();
@@ -299,6 +679,140 @@ mod tests {
         assert!(agent.is_running());
     }
 
+    #[tokio::test]
+    async fn test_agent_stats_empty() {
+        let temp = NamedTempFile::new().unwrap();
+        let config = ReplicationConfig::new("test-node")
+            .db_path(temp.path().to_str().unwrap());
+
+        let agent = ReplicationAgent::new(config).unwrap();
+        assert_eq!(agent.stats().await, ReplicationStats::default());
+    }
+
+    #[test]
+    fn test_restarted_agent_uses_higher_incarnation() {
+        let temp = NamedTempFile::new().unwrap();
+        let config = ReplicationConfig::new("test-node")
+            .db_path(temp.path().to_str().unwrap());
+
+        let before = ReplicationAgent::new(config.clone()).unwrap().gossip.incarnation();
+        let after = ReplicationAgent::new(config.clone()).unwrap().gossip.incarnation();
+        assert!(after > before);
+
+        // A seed above the stored value takes over
+        let seeded = ReplicationAgent::new(config.incarnation_seed(100)).unwrap().gossip.incarnation();
+        assert_eq!(seeded, 101);
+    }
+
+    #[tokio::test]
+    async fn test_agent_stats_reflect_seeded_state() {
+        use crate::replication::gossip::MemberState;
+
+        let temp = NamedTempFile::new().unwrap();
+        let config = ReplicationConfig::new("test-node")
+            .db_path(temp.path().to_str().unwrap());
+        let agent = ReplicationAgent::new(config).unwrap();
+        agent.sync.init_db().unwrap();
+
+        for (id, state) in [
+            ("alive-1", MemberState::Alive),
+            ("alive-2", MemberState::Alive),
+            ("suspect-1", MemberState::Suspect),
+            ("dead-1", MemberState::Dead),
+        ] {
+            agent.gossip.insert_member(Member {
+                node_id: NodeId::new(id),
+                gossip_addr: "127.0.0.1:4001".parse().unwrap(),
+                transport_addr: "127.0.0.1:4002".parse().unwrap(),
+                state,
+                last_seen: std::time::Instant::now(),
+                incarnation: 1,
+            });
+        }
+
+        // Two local changesets, then one change left unflushed
+        agent.record_backend_change("b1", ChangeKind::Insert, "{}");
+        agent.flush().await.unwrap();
+        agent.record_backend_change("b2", ChangeKind::Insert, "{}");
+        agent.flush().await.unwrap();
+        agent.record_backend_change("b3", ChangeKind::Insert, "{}");
+
+        // One changeset from each of two peers
+        for peer in ["peer-a", "peer-b"] {
+            let source = NodeId::new(peer);
+            let change = Change::new("backends", peer, ChangeKind::Insert, r#"{"app":"a","region":"eu","wg_ip":"10.0.0.1","port":80}"#, &source);
+            agent.apply_changeset(&ChangeSet::new(source, 1, vec![change])).await.unwrap();
+        }
+
+        let stats = agent.stats().await;
+        assert_eq!(stats.alive_members, 2);
+        assert_eq!(stats.suspect_members, 1);
+        assert_eq!(stats.dead_members, 1);
+        assert_eq!(stats.local_sequence, 2);
+        // Both peers plus the local node
+        assert_eq!(stats.version_vector_size, 3);
+        assert_eq!(stats.connected_peers, 0);
+        assert_eq!(stats.pending_changes, 1);
+    }
+
+    fn joined(id: &str) -> GossipEvent {
+        GossipEvent::MemberJoined(Member {
+            node_id: NodeId::new(id),
+            gossip_addr: "127.0.0.1:4001".parse().unwrap(),
+            transport_addr: "127.0.0.1:4002".parse().unwrap(),
+            state: crate::replication::gossip::MemberState::Alive,
+            last_seen: std::time::Instant::now(),
+            incarnation: 1,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_member_joined_dials_once() {
+        let temp = NamedTempFile::new().unwrap();
+        let config = ReplicationConfig::new("test-node")
+            .db_path(temp.path().to_str().unwrap());
+        let agent = ReplicationAgent::new(config).unwrap();
+
+        let mut attempts = Vec::new();
+        for _ in 0..2 {
+            if let Some(dial) = ReplicationAgent::on_gossip_event(
+                &agent.transport,
+                &agent.dialing,
+                &agent.node_id,
+                joined("peer-1"),
+            )
+            .await
+            {
+                attempts.push(dial);
+            }
+        }
+        assert_eq!(attempts.len(), 1);
+        assert_eq!(attempts[0].0.node_id.as_str(), "peer-1");
+
+        // Once the attempt is over, a later join may dial again
+        attempts.clear();
+        assert!(agent.dialing.lock().is_empty());
+        assert!(ReplicationAgent::on_gossip_event(&agent.transport, &agent.dialing, &agent.node_id, joined("peer-1"))
+            .await
+            .is_some());
+    }
+
+    #[tokio::test]
+    async fn test_gossip_event_ignores_self_and_other_events() {
+        let temp = NamedTempFile::new().unwrap();
+        let config = ReplicationConfig::new("test-node")
+            .db_path(temp.path().to_str().unwrap());
+        let agent = ReplicationAgent::new(config).unwrap();
+
+        let own = ReplicationAgent::on_gossip_event(&agent.transport, &agent.dialing, &agent.node_id, joined("test-node")).await;
+        assert!(own.is_none());
+
+        let left = GossipEvent::MemberLeft(NodeId::new("peer-1"));
+        let left = ReplicationAgent::on_gossip_event(&agent.transport, &agent.dialing, &agent.node_id, left).await;
+        assert!(left.is_none());
+        assert!(agent.dialing.lock().is_empty());
+    }
+
     #[tokio::test]
     async fn test_agent_stop() {
         let temp = NamedTempFile::new().unwrap();
@@ -474,7 +988,8 @@ mod tests {
         let config = ReplicationConfig::new("test-node")
             .db_path(temp.path().to_str().unwrap())
             .gossip_addr("127.0.0.1:0".parse().unwrap())
-            .transport_addr("127.0.0.1:0".parse().unwrap());
+            .transport_addr("127.0.0.1:0".parse().unwrap())
+            .cluster_secret("test-cluster-secret");
 
         let mut agent = ReplicationAgent::new(config).unwrap();
 
@@ -559,6 +1074,7 @@ mod tests {
             .db_path(temp.path().to_str().unwrap())
             .gossip_addr("127.0.0.1:0".parse().unwrap())
             .transport_addr("127.0.0.1:0".parse().unwrap())
+            .cluster_secret("test-cluster-secret")
             .bootstrap_peers(vec!["127.0.0.1:9999".to_string()]);
 
         let mut agent = ReplicationAgent::new(config).unwrap();
@@ -591,4 +1107,93 @@ mod tests {
         assert_eq!(cs.changes[1].kind, ChangeKind::Update);
         assert_eq!(cs.changes[2].kind, ChangeKind::Delete);
     }
+
+    #[tokio::test]
+    async fn test_handle_broadcast_applies_and_acks() {
+        let temp = NamedTempFile::new().unwrap();
+        let config = ReplicationConfig::new("test-node")
+            .db_path(temp.path().to_str().unwrap());
+
+        let agent = ReplicationAgent::new(config).unwrap();
+        agent.sync.init_db().unwrap();
+
+        let source = NodeId::new("other-node");
+        let changes = vec![
+            Change::new("backends", "remote-backend", ChangeKind::Insert, r#"{"app":"a"}"#, &source),
+        ];
+        let cs = ChangeSet::new(source, 4, changes);
+        let from = NodeId::new("peer-1");
+
+        let reply = agent.handle_message(&from, Message::Broadcast(cs.clone())).await;
+        match reply {
+            Some(Message::Ack { source, seq }) => {
+                assert_eq!(source.as_str(), "other-node");
+                assert_eq!(seq, 4);
+            }
+            other => panic!("expected ack, got {:?}", other),
+        }
+
+        // Re-delivery is acked again, since the first ack may have been lost
+        let reply = agent.handle_message(&from, Message::Broadcast(cs)).await;
+        assert!(matches!(reply, Some(Message::Ack { seq: 4, .. })));
+    }
+
+    #[tokio::test]
+    async fn test_handle_invalid_broadcast_not_acked() {
+        let temp = NamedTempFile::new().unwrap();
+        let config = ReplicationConfig::new("test-node")
+            .db_path(temp.path().to_str().unwrap());
+
+        let agent = ReplicationAgent::new(config).unwrap();
+        agent.sync.init_db().unwrap();
+
+        let mut cs = ChangeSet::new(NodeId::new("other-node"), 1, vec![]);
+        cs.checksum = 12345;
+
+        let reply = agent.handle_message(&NodeId::new("peer-1"), Message::Broadcast(cs)).await;
+        assert!(reply.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_handle_ack_and_other_messages_have_no_reply() {
+        let temp = NamedTempFile::new().unwrap();
+        let config = ReplicationConfig::new("test-node")
+            .db_path(temp.path().to_str().unwrap());
+
+        let agent = ReplicationAgent::new(config).unwrap();
+        let from = NodeId::new("peer-1");
+
+        let ack = Message::Ack { source: NodeId::new("test-node"), seq: 1 };
+        assert!(agent.handle_message(&from, ack).await.is_none());
+        assert!(agent.handle_message(&from, Message::Ping).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_handle_message_tracks_replication_lag() {
+        let temp = NamedTempFile::new().unwrap();
+        let config = ReplicationConfig::new("test-node")
+            .db_path(temp.path().to_str().unwrap());
+
+        let agent = ReplicationAgent::new(config).unwrap();
+        agent.sync.init_db().unwrap();
+        let from = NodeId::new("peer-1");
+
+        // Three local changesets, only the first acked by the peer
+        for i in 0..3 {
+            agent.record_backend_change(&format!("b{}", i), ChangeKind::Insert, "{}");
+            agent.flush().await.unwrap();
+        }
+        let ack = Message::Ack { source: NodeId::new("test-node"), seq: 1 };
+        agent.handle_message(&from, ack).await;
+
+        // Peer announces seq 5 but it fails to apply
+        let mut cs = ChangeSet::new(NodeId::new("peer-1"), 5, vec![]);
+        cs.checksum = 12345;
+        agent.handle_message(&from, Message::Broadcast(cs)).await;
+
+        assert_eq!(
+            agent.replication_lag(),
+            vec![("peer-1".to_string(), ReplicationLag { inbound: 5, outbound: 2 })]
+        );
+    }
 }
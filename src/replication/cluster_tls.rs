@@ -0,0 +1,148 @@
+//! Cluster Transport Trust
+//!
+//! Certificates for the QUIC transport. Every node derives the same cluster
+//! CA from the cluster name and shared secret, issues itself a certificate
+//! signed by that CA, and only talks to peers presenting such a certificate,
+//! in both directions.
+
+use quinn::rustls::{self, RootCertStore};
+use rcgen::{
+    BasicConstraints, CertificateParams, DnType, ExtendedKeyUsagePurpose, IsCa, KeyPair,
+    KeyUsagePurpose,
+};
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer};
+use rustls::server::WebPkiClientVerifier;
+use std::sync::Arc;
+
+/// Server name every node certificate carries and every peer connects with.
+pub const PEER_SERVER_NAME: &str = "replication.edgeproxy";
+
+/// PKCS#8 v1 header of an Ed25519 private key, followed by the 32-byte seed.
+const ED25519_PKCS8_PREFIX: [u8; 16] = [
+    0x30, 0x2e, 0x02, 0x01, 0x00, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x04, 0x22, 0x04, 0x20,
+];
+
+/// Cluster CA plus this node's certificate and key.
+pub struct ClusterTls {
+    ca_cert: CertificateDer<'static>,
+    cert_chain: Vec<CertificateDer<'static>>,
+    key: PrivatePkcs8KeyDer<'static>,
+}
+
+impl ClusterTls {
+    /// Derive the cluster CA and issue a fresh certificate for `node_id`.
+    ///
+    /// Nodes sharing `cluster_name` and `secret` derive the same CA key, so
+    /// they trust each other's certificates without exchanging any files.
+    pub fn new(cluster_name: &str, secret: &str, node_id: &str) -> anyhow::Result<Self> {
+        let ca_key = derive_ca_key(cluster_name, secret)?;
+        let mut ca_params = CertificateParams::default();
+        ca_params
+            .distinguished_name
+            .push(DnType::CommonName, format!("edgeproxy cluster CA ({})", cluster_name));
+        ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        ca_params.key_usages = vec![KeyUsagePurpose::KeyCertSign, KeyUsagePurpose::CrlSign];
+        let ca_cert = ca_params.self_signed(&ca_key)?;
+
+        let node_key = KeyPair::generate()?;
+        let mut node_params = CertificateParams::new(vec![PEER_SERVER_NAME.to_string()])?;
+        node_params.distinguished_name.push(DnType::CommonName, node_id);
+        node_params.extended_key_usages = vec![
+            ExtendedKeyUsagePurpose::ServerAuth,
+            ExtendedKeyUsagePurpose::ClientAuth,
+        ];
+        let node_cert = node_params.signed_by(&node_key, &ca_cert, &ca_key)?;
+
+        Ok(Self {
+            ca_cert: ca_cert.der().clone(),
+            cert_chain: vec![node_cert.der().clone()],
+            key: PrivatePkcs8KeyDer::from(node_key.serialize_der()),
+        })
+    }
+
+    /// DER of the derived cluster CA certificate.
+    pub fn ca_cert(&self) -> &CertificateDer<'static> {
+        &self.ca_cert
+    }
+
+    /// Server side: present the node certificate and require a client
+    /// certificate issued by the cluster CA.
+    pub fn server_config(&self) -> anyhow::Result<rustls::ServerConfig> {
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let verifier =
+            WebPkiClientVerifier::builder_with_provider(self.roots()?, provider.clone()).build()?;
+        Ok(rustls::ServerConfig::builder_with_provider(provider)
+            .with_protocol_versions(&[&rustls::version::TLS13])?
+            .with_client_cert_verifier(verifier)
+            .with_single_cert(self.cert_chain.clone(), self.private_key())?)
+    }
+
+    /// Client side: only accept servers with a certificate issued by the
+    /// cluster CA, and present the node certificate in turn.
+    pub fn client_config(&self) -> anyhow::Result<rustls::ClientConfig> {
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        Ok(rustls::ClientConfig::builder_with_provider(provider)
+            .with_protocol_versions(&[&rustls::version::TLS13])?
+            .with_root_certificates(self.roots()?)
+            .with_client_auth_cert(self.cert_chain.clone(), self.private_key())?)
+    }
+
+    fn roots(&self) -> anyhow::Result<Arc<RootCertStore>> {
+        let mut roots = RootCertStore::empty();
+        roots.add(self.ca_cert.clone())?;
+        Ok(Arc::new(roots))
+    }
+
+    fn private_key(&self) -> PrivateKeyDer<'static> {
+        PrivateKeyDer::Pkcs8(self.key.clone_key())
+    }
+}
+
+/// Ed25519 CA key whose seed is a hash of the cluster name and secret.
+fn derive_ca_key(cluster_name: &str, secret: &str) -> anyhow::Result<KeyPair> {
+    let mut ctx = ring::digest::Context::new(&ring::digest::SHA256);
+    ctx.update(b"edgeproxy-cluster-ca\0");
+    ctx.update(cluster_name.as_bytes());
+    ctx.update(b"\0");
+    ctx.update(secret.as_bytes());
+    let seed = ctx.finish();
+
+    let mut pkcs8 = ED25519_PKCS8_PREFIX.to_vec();
+    pkcs8.extend_from_slice(seed.as_ref());
+    Ok(KeyPair::try_from(pkcs8.as_slice())?)
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ca_key_is_derived_from_name_and_secret() {
+        let key = |name, secret| derive_ca_key(name, secret).unwrap().public_key_raw().to_vec();
+
+        assert_eq!(key("edgeproxy", "s3cret"), key("edgeproxy", "s3cret"));
+        assert_ne!(key("edgeproxy", "s3cret"), key("edgeproxy", "other"));
+        assert_ne!(key("edgeproxy", "s3cret"), key("staging", "s3cret"));
+    }
+
+    #[test]
+    fn test_node_certificates_chain_to_shared_ca() {
+        let a = ClusterTls::new("edgeproxy", "s3cret", "node-a").unwrap();
+        let b = ClusterTls::new("edgeproxy", "s3cret", "node-b").unwrap();
+
+        // Each node issues its own certificate under the same CA key
+        assert_ne!(a.cert_chain[0], b.cert_chain[0]);
+        let spki = |tls: &ClusterTls| {
+            webpki::anchor_from_trusted_cert(&tls.ca_cert).unwrap().subject_public_key_info.to_vec()
+        };
+        assert_eq!(spki(&a), spki(&b));
+    }
+
+    #[test]
+    fn test_configs_build() {
+        let tls = ClusterTls::new("edgeproxy", "s3cret", "node-a").unwrap();
+        assert!(tls.server_config().is_ok());
+        assert!(tls.client_config().is_ok());
+    }
+}
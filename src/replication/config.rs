@@ -2,6 +2,7 @@
 //!
 //! Configuration for the built-in replication system.
 
+use crate::replication::events::OverflowPolicy;
 use std::net::SocketAddr;
 use std::time::Duration;
 
@@ -40,6 +41,70 @@ pub struct ReplicationConfig {
 
     /// Enable TLS for transport (default: true)
     pub tls_enabled: bool,
+
+    /// Shared secret the cluster CA is derived from; peers must present a
+    /// certificate issued by it (required unless `insecure_transport` is set)
+    pub cluster_secret: Option<String>,
+
+    /// Accept any peer certificate on the transport (development only, default: false)
+    pub insecure_transport: bool,
+
+    /// Time to wait for a peer to ack a changeset before re-sending it (default: 2s)
+    pub ack_timeout: Duration,
+
+    /// Sends of a changeset to a peer before it is given up on if the peer
+    /// never acks it (default: 10)
+    pub max_send_attempts: u32,
+
+    /// Maximum amount a remote change's HLC wall time may be ahead of the
+    /// local clock before the change is rejected (default: 60s)
+    pub max_clock_skew: Duration,
+
+    /// Interval between QUIC-level pings to each connected peer (default: 1s)
+    pub ping_interval: Duration,
+
+    /// Time to wait for a peer's pong before counting the ping as missed (default: 1s)
+    pub ping_timeout: Duration,
+
+    /// Time to wait for the response to a request sent to a peer (default: 5s)
+    pub request_timeout: Duration,
+
+    /// Consecutive missed pings before a peer is disconnected (default: 3)
+    pub max_missed_pings: u32,
+
+    /// Capacity of each service's event channel (default: 1024)
+    pub event_channel_capacity: usize,
+
+    /// What to do with events when a consumer falls behind (default: drop newest)
+    pub event_overflow: OverflowPolicy,
+
+    /// Repeated Joins from the same node within this window are ignored (default: 5s)
+    pub join_dedup_window: Duration,
+
+    /// Minimum time between MemberList replies to the same peer (default: 1s)
+    pub member_list_interval: Duration,
+
+    /// Time without hearing from an alive member before it is declared
+    /// dead (default: 30s)
+    pub failure_timeout: Duration,
+
+    /// How often members are checked against `failure_timeout` (default: 10s)
+    pub failure_check_interval: Duration,
+
+    /// Fraction of the gossip and failure-check intervals randomly added to
+    /// or taken from each tick, so nodes started together drift apart
+    /// (default: 0.1, i.e. ±10%)
+    pub timer_jitter: f64,
+
+    /// Replication log rows younger than this are never compacted (default: 24h)
+    pub log_retention: Duration,
+
+    /// How often the replication log is compacted (default: 5min)
+    pub log_compaction_interval: Duration,
+
+    /// Lowest gossip incarnation to start from; the persisted value wins
+    /// when it is higher (default: 0)
+    pub incarnation_seed: u64,
 }
 
 impl Default for ReplicationConfig {
@@ -56,6 +121,25 @@ impl Default for ReplicationConfig {
             max_pending_changes: 1000,
             broadcast_rate_limit: 10 * 1024 * 1024, // 10 MB/s
             tls_enabled: true,
+            cluster_secret: None,
+            insecure_transport: false,
+            ack_timeout: Duration::from_secs(2),
+            max_send_attempts: 10,
+            max_clock_skew: Duration::from_secs(60),
+            ping_interval: Duration::from_secs(1),
+            ping_timeout: Duration::from_secs(1),
+            request_timeout: Duration::from_secs(5),
+            max_missed_pings: 3,
+            event_channel_capacity: 1024,
+            event_overflow: OverflowPolicy::DropNewest,
+            join_dedup_window: Duration::from_secs(5),
+            member_list_interval: Duration::from_secs(1),
+            failure_timeout: Duration::from_secs(30),
+            failure_check_interval: Duration::from_secs(10),
+            timer_jitter: 0.1,
+            log_retention: Duration::from_secs(24 * 60 * 60),
+            log_compaction_interval: Duration::from_secs(5 * 60),
+            incarnation_seed: 0,
         }
     }
 }
@@ -99,6 +183,120 @@ impl ReplicationConfig {
         self
     }
 
+    /// Set the shared secret the transport's cluster CA is derived from.
+    pub fn cluster_secret(mut self, secret: impl Into<String>) -> Self {
+        self.cluster_secret = Some(secret.into());
+        self
+    }
+
+    /// Accept any peer certificate on the transport (development only).
+    pub fn insecure_transport(mut self, insecure: bool) -> Self {
+        self.insecure_transport = insecure;
+        self
+    }
+
+    /// Set the changeset ack timeout.
+    pub fn ack_timeout(mut self, timeout: Duration) -> Self {
+        self.ack_timeout = timeout;
+        self
+    }
+
+    /// Set how many times a changeset is sent to a peer before giving up.
+    pub fn max_send_attempts(mut self, attempts: u32) -> Self {
+        self.max_send_attempts = attempts;
+        self
+    }
+
+    /// Set the maximum tolerated clock skew for remote changes.
+    pub fn max_clock_skew(mut self, skew: Duration) -> Self {
+        self.max_clock_skew = skew;
+        self
+    }
+
+    /// Set the interval between keepalive pings to peers.
+    pub fn ping_interval(mut self, interval: Duration) -> Self {
+        self.ping_interval = interval;
+        self
+    }
+
+    /// Set how long to wait for a pong before a ping counts as missed.
+    pub fn ping_timeout(mut self, timeout: Duration) -> Self {
+        self.ping_timeout = timeout;
+        self
+    }
+
+    /// Set how long to wait for a peer's response to a request.
+    pub fn request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = timeout;
+        self
+    }
+
+    /// Set how many consecutive missed pings disconnect a peer.
+    pub fn max_missed_pings(mut self, max: u32) -> Self {
+        self.max_missed_pings = max;
+        self
+    }
+
+    /// Set the capacity of the services' event channels.
+    pub fn event_channel_capacity(mut self, capacity: usize) -> Self {
+        self.event_channel_capacity = capacity;
+        self
+    }
+
+    /// Set the policy for events a slow consumer can't take.
+    pub fn event_overflow(mut self, policy: OverflowPolicy) -> Self {
+        self.event_overflow = policy;
+        self
+    }
+
+    /// Set the window in which repeated Joins from one node are ignored.
+    pub fn join_dedup_window(mut self, window: Duration) -> Self {
+        self.join_dedup_window = window;
+        self
+    }
+
+    /// Set the minimum time between MemberList replies to one peer.
+    pub fn member_list_interval(mut self, interval: Duration) -> Self {
+        self.member_list_interval = interval;
+        self
+    }
+
+    /// Set how long an alive member may stay silent before it is declared dead.
+    pub fn failure_timeout(mut self, timeout: Duration) -> Self {
+        self.failure_timeout = timeout;
+        self
+    }
+
+    /// Set how often members are checked for failure.
+    pub fn failure_check_interval(mut self, interval: Duration) -> Self {
+        self.failure_check_interval = interval;
+        self
+    }
+
+    /// Set the timer jitter fraction (clamped to 0.0..=1.0; 0 disables jitter).
+    pub fn timer_jitter(mut self, fraction: f64) -> Self {
+        self.timer_jitter = if fraction.is_nan() { 0.0 } else { fraction.clamp(0.0, 1.0) };
+        self
+    }
+
+    /// Set the minimum age of replication log rows before they can be compacted.
+    pub fn log_retention(mut self, retention: Duration) -> Self {
+        self.log_retention = retention;
+        self
+    }
+
+    /// Set how often the replication log is compacted.
+    pub fn log_compaction_interval(mut self, interval: Duration) -> Self {
+        self.log_compaction_interval = interval;
+        self
+    }
+
+    /// Set the lowest gossip incarnation to start from.
+    pub fn incarnation_seed(mut self, seed: u64) -> Self {
+        self.incarnation_seed = seed;
+        self
+    }
+
     /// Validate the configuration.
     pub fn validate(&self) -> Result<(), ConfigError> {
         if self.node_id.is_empty() {
@@ -131,6 +329,117 @@ mod tests {
         assert_eq!(config.gossip_addr.port(), 4001);
         assert_eq!(config.transport_addr.port(), 4002);
         assert_eq!(config.cluster_name, "edgeproxy");
+        assert_eq!(config.ack_timeout, Duration::from_secs(2));
+        assert_eq!(config.max_send_attempts, 10);
+        assert_eq!(config.max_clock_skew, Duration::from_secs(60));
+        assert_eq!(config.ping_interval, Duration::from_secs(1));
+        assert_eq!(config.ping_timeout, Duration::from_secs(1));
+        assert_eq!(config.request_timeout, Duration::from_secs(5));
+        assert_eq!(config.max_missed_pings, 3);
+        assert_eq!(config.event_channel_capacity, 1024);
+        assert_eq!(config.event_overflow, OverflowPolicy::DropNewest);
+        assert_eq!(config.join_dedup_window, Duration::from_secs(5));
+        assert_eq!(config.member_list_interval, Duration::from_secs(1));
+        assert_eq!(config.failure_timeout, Duration::from_secs(30));
+        assert_eq!(config.failure_check_interval, Duration::from_secs(10));
+        assert_eq!(config.timer_jitter, 0.1);
+        assert_eq!(config.log_retention, Duration::from_secs(86400));
+        assert_eq!(config.incarnation_seed, 0);
+        assert_eq!(config.log_compaction_interval, Duration::from_secs(300));
+        assert!(config.cluster_secret.is_none());
+        assert!(!config.insecure_transport);
+    }
+
+    #[test]
+    fn test_transport_trust_builders() {
+        let config = ReplicationConfig::new("node-1").cluster_secret("s3cret");
+        assert_eq!(config.cluster_secret.as_deref(), Some("s3cret"));
+        assert!(ReplicationConfig::new("node-1").insecure_transport(true).insecure_transport);
+    }
+
+    #[test]
+    fn test_log_compaction_builders() {
+        let config = ReplicationConfig::new("node-1")
+            .log_retention(Duration::from_secs(3600))
+            .log_compaction_interval(Duration::from_secs(30));
+        assert_eq!(config.log_retention, Duration::from_secs(3600));
+        assert_eq!(config.log_compaction_interval, Duration::from_secs(30));
+    }
+
+    #[test]
+    fn test_timer_jitter_builder_clamps() {
+        assert_eq!(ReplicationConfig::new("n").timer_jitter(0.25).timer_jitter, 0.25);
+        assert_eq!(ReplicationConfig::new("n").timer_jitter(3.0).timer_jitter, 1.0);
+        assert_eq!(ReplicationConfig::new("n").timer_jitter(-0.5).timer_jitter, 0.0);
+        assert_eq!(ReplicationConfig::new("n").timer_jitter(f64::NAN).timer_jitter, 0.0);
+    }
+
+    #[test]
+    fn test_join_throttle_builders() {
+        let config = ReplicationConfig::new("node-1")
+            .join_dedup_window(Duration::from_secs(10))
+            .member_list_interval(Duration::from_millis(250));
+        assert_eq!(config.join_dedup_window, Duration::from_secs(10));
+        assert_eq!(config.member_list_interval, Duration::from_millis(250));
+    }
+
+    #[test]
+    fn test_failure_detection_builders() {
+        let config = ReplicationConfig::new("node-1")
+            .failure_timeout(Duration::from_secs(5))
+            .failure_check_interval(Duration::from_secs(1));
+        assert_eq!(config.failure_timeout, Duration::from_secs(5));
+        assert_eq!(config.failure_check_interval, Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_event_channel_builders() {
+        let config = ReplicationConfig::new("node-1")
+            .event_channel_capacity(16)
+            .event_overflow(OverflowPolicy::Block);
+        assert_eq!(config.event_channel_capacity, 16);
+        assert_eq!(config.event_overflow, OverflowPolicy::Block);
+    }
+
+    #[test]
+    fn test_ping_builders() {
+        let config = ReplicationConfig::new("node-1")
+            .ping_interval(Duration::from_millis(200))
+            .ping_timeout(Duration::from_millis(100))
+            .max_missed_pings(5);
+        assert_eq!(config.ping_interval, Duration::from_millis(200));
+        assert_eq!(config.ping_timeout, Duration::from_millis(100));
+        assert_eq!(config.max_missed_pings, 5);
+    }
+
+    #[test]
+    fn test_max_clock_skew_builder() {
+        let config = ReplicationConfig::new("node-1").max_clock_skew(Duration::from_secs(5));
+        assert_eq!(config.max_clock_skew, Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_request_timeout_builder() {
+        let config = ReplicationConfig::new("node-1").request_timeout(Duration::from_millis(300));
+        assert_eq!(config.request_timeout, Duration::from_millis(300));
+    }
+
+    #[test]
+    fn test_incarnation_seed_builder() {
+        let config = ReplicationConfig::new("node-1").incarnation_seed(7);
+        assert_eq!(config.incarnation_seed, 7);
+    }
+
+    #[test]
+    fn test_ack_timeout_builder() {
+        let config = ReplicationConfig::new("node-1").ack_timeout(Duration::from_millis(250));
+        assert_eq!(config.ack_timeout, Duration::from_millis(250));
+    }
+
+    #[test]
+    fn test_max_send_attempts_builder() {
+        let config = ReplicationConfig::new("node-1").max_send_attempts(3);
+        assert_eq!(config.max_send_attempts, 3);
     }
 
     #[test]
@@ -0,0 +1,78 @@
+//! Conflict Resolution
+//!
+//! Decides whether a change received from a peer should be applied over
+//! the local state. Resolvers are chosen per table; tables without one use
+//! Last-Write-Wins on the HLC timestamp.
+
+use crate::replication::types::{Change, HLCTimestamp};
+use rusqlite::Connection;
+
+/// Strategy for resolving a remote change against the local state.
+///
+/// `last_applied` is the timestamp of the newest change applied to the
+/// same row so far, if any. Resolvers that need the current row (e.g.
+/// max-value) can read it through `conn`, which is the connection the
+/// change will be applied on.
+pub trait ConflictResolver: Send + Sync {
+    /// Return true if `change` should be applied.
+    fn should_apply(
+        &self,
+        conn: &Connection,
+        change: &Change,
+        last_applied: Option<&HLCTimestamp>,
+    ) -> anyhow::Result<bool>;
+}
+
+/// Last-Write-Wins: apply a change only if it is newer than the last one applied.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LastWriteWins;
+
+impl ConflictResolver for LastWriteWins {
+    fn should_apply(
+        &self,
+        _conn: &Connection,
+        change: &Change,
+        last_applied: Option<&HLCTimestamp>,
+    ) -> anyhow::Result<bool> {
+        Ok(last_applied.is_none_or(|last| change.timestamp > *last))
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::replication::types::{ChangeKind, NodeId};
+
+    fn change_at(wall_time: u64) -> Change {
+        Change {
+            id: 1,
+            table: "backends".to_string(),
+            pk: "b1".to_string(),
+            kind: ChangeKind::Update,
+            data: "{}".to_string(),
+            timestamp: HLCTimestamp {
+                wall_time,
+                counter: 0,
+                node_hash: 0,
+            },
+            origin: NodeId::new("node-a"),
+        }
+    }
+
+    #[test]
+    fn test_lww_applies_first_change() {
+        let conn = Connection::open_in_memory().unwrap();
+        assert!(LastWriteWins.should_apply(&conn, &change_at(10), None).unwrap());
+    }
+
+    #[test]
+    fn test_lww_only_applies_newer_changes() {
+        let conn = Connection::open_in_memory().unwrap();
+        let last = change_at(10).timestamp;
+
+        assert!(LastWriteWins.should_apply(&conn, &change_at(11), Some(&last)).unwrap());
+        assert!(!LastWriteWins.should_apply(&conn, &change_at(10), Some(&last)).unwrap());
+        assert!(!LastWriteWins.should_apply(&conn, &change_at(9), Some(&last)).unwrap());
+    }
+}
@@ -0,0 +1,535 @@
+//! Corrosion Compatibility
+//!
+//! Maps replication changesets to and from the change format used by
+//! [Corrosion](https://github.com/superfly/corrosion), so a cluster can mix
+//! nodes running Corrosion with nodes using built-in replication through a
+//! bridge that forwards changes between the two.
+//!
+//! Corrosion replicates cr-sqlite changes: one row per changed column,
+//! each with the column's version and the row's causal length. The types
+//! here mirror its `ChangeV1` / `Changeset::Full` JSON encoding.
+//!
+//! ## Mapping
+//!
+//! | [`Change`] / [`ChangeSet`] | Corrosion |
+//! |----------------------------|-----------|
+//! | `ChangeSet::source` | `actor_id` (the node id if it is a UUID, otherwise derived from it) |
+//! | `ChangeSet::seq` | `version` and each row's `db_version` |
+//! | `table` | `table` |
+//! | `pk` | `pk`, packed as a single TEXT column |
+//! | one key of `data` | one row: `cid` = key, `val` = value |
+//! | `Insert` | an extra `-1` sentinel row with `cl` = 1 (row created) |
+//! | `Update` | column rows only, `cl` = 1 |
+//! | `Delete` | a `-1` sentinel row with `cl` = 2 (row deleted) |
+//! | `timestamp` | `col_version` = wall time (µs) << 12 \| counter |
+//!
+//! JSON booleans become integers, and arrays or objects become JSON text.
+//! Change ids don't exist in Corrosion; imported changes get one derived
+//! from their actor, version and sequence, so importing the same changes
+//! twice yields the same ids.
+
+use crate::replication::types::{Change, ChangeKind, ChangeSet, HLCTimestamp, NodeId};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+use uuid::Uuid;
+
+/// Column id of cr-sqlite's row sentinel, marking row creation or deletion.
+pub const SENTINEL_CID: &str = "-1";
+
+/// Bits of `col_version` holding the HLC counter.
+const COUNTER_BITS: u32 = 12;
+
+/// cr-sqlite column type tags used when packing primary keys.
+const PACKED_INTEGER: u8 = 1;
+const PACKED_TEXT: u8 = 3;
+
+/// Error converting Corrosion changes back into replication changes.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum CorrosionError {
+    #[error("invalid packed primary key")]
+    InvalidPk,
+}
+
+/// A Corrosion node id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ActorId(pub Uuid);
+
+impl ActorId {
+    /// The actor id for a node: its id if that is a UUID, otherwise one
+    /// derived from a hash of the id (stable across restarts and nodes).
+    pub fn for_node(node_id: &NodeId) -> Self {
+        if let Ok(uuid) = Uuid::parse_str(node_id.as_str()) {
+            return Self(uuid);
+        }
+        let digest = ring::digest::digest(&ring::digest::SHA256, node_id.as_str().as_bytes());
+        let mut bytes = [0u8; 16];
+        bytes.copy_from_slice(&digest.as_ref()[..16]);
+        Self(uuid::Builder::from_custom_bytes(bytes).into_uuid())
+    }
+
+    /// The node id imported changes are attributed to.
+    pub fn node_id(&self) -> NodeId {
+        NodeId::new(self.0.to_string())
+    }
+}
+
+impl Serialize for ActorId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&self.0.hyphenated())
+    }
+}
+
+impl<'de> Deserialize<'de> for ActorId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Uuid::parse_str(&s).map(Self).map_err(serde::de::Error::custom)
+    }
+}
+
+/// A SQLite value as carried in a Corrosion change.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SqliteValue {
+    Null,
+    Integer(i64),
+    Real(f64),
+    Text(String),
+    Blob(Vec<u8>),
+}
+
+impl From<&serde_json::Value> for SqliteValue {
+    fn from(value: &serde_json::Value) -> Self {
+        use serde_json::Value;
+
+        match value {
+            Value::Null => Self::Null,
+            Value::Bool(b) => Self::Integer(i64::from(*b)),
+            Value::Number(n) => match n.as_i64() {
+                Some(i) => Self::Integer(i),
+                None => Self::Real(n.as_f64().unwrap_or_default()),
+            },
+            Value::String(s) => Self::Text(s.clone()),
+            Value::Array(_) | Value::Object(_) => Self::Text(value.to_string()),
+        }
+    }
+}
+
+impl From<&SqliteValue> for serde_json::Value {
+    fn from(value: &SqliteValue) -> Self {
+        match value {
+            SqliteValue::Null => Self::Null,
+            SqliteValue::Integer(i) => Self::from(*i),
+            SqliteValue::Real(f) => Self::from(*f),
+            SqliteValue::Text(s) => Self::from(s.as_str()),
+            SqliteValue::Blob(b) => Self::from(b.as_slice()),
+        }
+    }
+}
+
+/// One changed column of one row, as cr-sqlite reports it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CrsqlChange {
+    pub table: String,
+    /// Primary key columns, packed in cr-sqlite's format
+    pub pk: Vec<u8>,
+    /// Column name, or [`SENTINEL_CID`] for the row sentinel
+    pub cid: String,
+    pub val: SqliteValue,
+    pub col_version: i64,
+    pub db_version: u64,
+    /// Position of this row within the changeset
+    pub seq: u64,
+    pub site_id: [u8; 16],
+    /// Causal length: odd while the row exists, even once deleted
+    pub cl: i64,
+}
+
+/// A batch of changes from one actor version.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Changeset {
+    Full {
+        version: u64,
+        changes: Vec<CrsqlChange>,
+        seqs: RangeInclusive<u64>,
+        last_seq: u64,
+        /// NTP64 timestamp of the newest change
+        ts: u64,
+    },
+}
+
+/// A changeset tagged with the actor that produced it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChangeV1 {
+    pub actor_id: ActorId,
+    pub changeset: Changeset,
+}
+
+impl ChangeV1 {
+    /// Express a replication changeset as Corrosion changes.
+    pub fn from_changeset(changeset: &ChangeSet) -> Self {
+        let actor_id = ActorId::for_node(&changeset.source);
+        let site_id = *actor_id.0.as_bytes();
+        let mut rows = Vec::new();
+        let mut newest = 0;
+
+        for change in &changeset.changes {
+            newest = newest.max(change.timestamp.wall_time);
+            let pk = pack_text_pk(&change.pk);
+            let col_version = encode_col_version(&change.timestamp);
+            let mut push = |cid: &str, val: SqliteValue, cl: i64| {
+                rows.push(CrsqlChange {
+                    table: change.table.clone(),
+                    pk: pk.clone(),
+                    cid: cid.to_string(),
+                    val,
+                    col_version,
+                    db_version: changeset.seq,
+                    seq: rows.len() as u64,
+                    site_id,
+                    cl,
+                });
+            };
+
+            match change.kind {
+                ChangeKind::Delete => push(SENTINEL_CID, SqliteValue::Null, 2),
+                ChangeKind::Insert | ChangeKind::Update => {
+                    if change.kind == ChangeKind::Insert {
+                        push(SENTINEL_CID, SqliteValue::Null, 1);
+                    }
+                    let columns: BTreeMap<String, serde_json::Value> =
+                        serde_json::from_str(&change.data).unwrap_or_default();
+                    for (column, value) in &columns {
+                        push(column, SqliteValue::from(value), 1);
+                    }
+                }
+            }
+        }
+
+        let last_seq = (rows.len() as u64).saturating_sub(1);
+        Self {
+            actor_id,
+            changeset: Changeset::Full {
+                version: changeset.seq,
+                changes: rows,
+                seqs: 0..=last_seq,
+                last_seq,
+                ts: micros_to_ntp64(newest),
+            },
+        }
+    }
+
+    /// Turn Corrosion changes back into a replication changeset.
+    ///
+    /// Rows are grouped per table and primary key, in the order each row
+    /// first appears. A group is a delete if any row has an even causal
+    /// length, an insert if it has a live sentinel row, and otherwise an
+    /// update of the columns it carries.
+    pub fn to_changeset(&self) -> Result<ChangeSet, CorrosionError> {
+        let Changeset::Full { version, changes, .. } = &self.changeset;
+        let origin = self.actor_id.node_id();
+        let node_hash = crc32fast::hash(origin.as_str().as_bytes());
+
+        let mut groups: Vec<Vec<&CrsqlChange>> = Vec::new();
+        for row in changes {
+            match groups.iter_mut().find(|g| g[0].table == row.table && g[0].pk == row.pk) {
+                Some(rows) => rows.push(row),
+                None => groups.push(vec![row]),
+            }
+        }
+
+        groups
+            .into_iter()
+            .map(|rows| {
+                let first = rows[0];
+                let table = first.table.clone();
+                let pk = unpack_pk(&first.pk)?;
+                let deleted = rows.iter().any(|r| r.cl % 2 == 0);
+                let created = rows.iter().any(|r| r.cid == SENTINEL_CID && r.cl % 2 == 1);
+                let columns: serde_json::Map<String, serde_json::Value> = rows
+                    .iter()
+                    .filter(|r| r.cid != SENTINEL_CID)
+                    .map(|r| (r.cid.clone(), serde_json::Value::from(&r.val)))
+                    .collect();
+
+                let kind = if deleted {
+                    ChangeKind::Delete
+                } else if created {
+                    ChangeKind::Insert
+                } else {
+                    ChangeKind::Update
+                };
+                let data = match kind {
+                    ChangeKind::Delete => String::new(),
+                    _ => serde_json::Value::Object(columns).to_string(),
+                };
+                let col_version = rows.iter().map(|r| r.col_version).max().unwrap_or_default();
+
+                Ok(Change {
+                    id: import_change_id(&self.actor_id, first.db_version, first.seq),
+                    table,
+                    pk,
+                    kind,
+                    data,
+                    timestamp: decode_col_version(col_version, node_hash),
+                    origin: origin.clone(),
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(|changes| ChangeSet::new(origin.clone(), *version, changes))
+    }
+}
+
+/// Pack an HLC timestamp into a column version, keeping its order.
+///
+/// Counters past 12 bits saturate; wall times fit until the year 2248.
+fn encode_col_version(timestamp: &HLCTimestamp) -> i64 {
+    let counter = timestamp.counter.min((1 << COUNTER_BITS) - 1) as i64;
+    ((timestamp.wall_time as i64) << COUNTER_BITS) | counter
+}
+
+fn decode_col_version(col_version: i64, node_hash: u32) -> HLCTimestamp {
+    HLCTimestamp {
+        wall_time: (col_version >> COUNTER_BITS) as u64,
+        counter: (col_version & ((1 << COUNTER_BITS) - 1)) as u32,
+        node_hash,
+    }
+}
+
+/// Convert microseconds since the UNIX epoch to an NTP64 timestamp
+/// (seconds in the high 32 bits, fraction in the low 32).
+fn micros_to_ntp64(micros: u64) -> u64 {
+    let secs = micros / 1_000_000;
+    let frac = ((micros % 1_000_000) << 32) / 1_000_000;
+    (secs << 32) | frac
+}
+
+/// Stable id for an imported change.
+fn import_change_id(actor: &ActorId, db_version: u64, seq: u64) -> u64 {
+    let actor_hash = crc32fast::hash(actor.0.as_bytes()) as u64;
+    let position = crc32fast::hash(format!("{}:{}", db_version, seq).as_bytes()) as u64;
+    (actor_hash << 32) | position
+}
+
+/// Pack a single TEXT primary key the way cr-sqlite does: a column count,
+/// then a type byte holding the size of the length field, the big-endian
+/// length, and the bytes.
+pub fn pack_text_pk(pk: &str) -> Vec<u8> {
+    let len = pk.len() as u64;
+    let len_bytes = len_bytes_needed(len);
+    let mut packed = Vec::with_capacity(2 + len_bytes + pk.len());
+    packed.push(1);
+    packed.push(((len_bytes as u8) << 3) | PACKED_TEXT);
+    packed.extend_from_slice(&len.to_be_bytes()[8 - len_bytes..]);
+    packed.extend_from_slice(pk.as_bytes());
+    packed
+}
+
+/// Unpack a single-column TEXT or INTEGER primary key as a string.
+pub fn unpack_pk(packed: &[u8]) -> Result<String, CorrosionError> {
+    let [1, type_byte, rest @ ..] = packed else {
+        return Err(CorrosionError::InvalidPk);
+    };
+    let size = (type_byte >> 3) as usize;
+    if size > 8 || rest.len() < size {
+        return Err(CorrosionError::InvalidPk);
+    }
+    let (field, rest) = rest.split_at(size);
+    let mut buf = [0u8; 8];
+    buf[8 - size..].copy_from_slice(field);
+
+    match type_byte & 0x07 {
+        PACKED_TEXT => {
+            let len = u64::from_be_bytes(buf) as usize;
+            if rest.len() != len {
+                return Err(CorrosionError::InvalidPk);
+            }
+            String::from_utf8(rest.to_vec()).map_err(|_| CorrosionError::InvalidPk)
+        }
+        PACKED_INTEGER if rest.is_empty() => {
+            // Sign-extend from the bytes used
+            let shift = 64 - 8 * size as u32;
+            let value = (u64::from_be_bytes(buf) << (shift % 64)) as i64 >> (shift % 64);
+            Ok(value.to_string())
+        }
+        _ => Err(CorrosionError::InvalidPk),
+    }
+}
+
+/// Smallest number of bytes holding `len` (at least one).
+fn len_bytes_needed(len: u64) -> usize {
+    (8 - len.leading_zeros() as usize / 8).max(1)
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+
+    fn node() -> NodeId {
+        NodeId::new("pop-eu-1")
+    }
+
+    fn change(pk: &str, kind: ChangeKind, data: &str, wall_time: u64, counter: u32) -> Change {
+        Change::new("backends", pk, kind, data, &node()).with_timestamp(HLCTimestamp {
+            wall_time,
+            counter,
+            node_hash: crc32fast::hash(node().as_str().as_bytes()),
+        })
+    }
+
+    fn rows(v1: &ChangeV1) -> &[CrsqlChange] {
+        let Changeset::Full { changes, .. } = &v1.changeset;
+        changes
+    }
+
+    // ===== Export Tests =====
+
+    #[test]
+    fn test_insert_exports_sentinel_and_columns() {
+        let changeset = ChangeSet::new(
+            node(),
+            7,
+            vec![change("b1", ChangeKind::Insert, r#"{"app":"myapp","port":8080}"#, 1_700_000_000_000_000, 3)],
+        );
+        let v1 = ChangeV1::from_changeset(&changeset);
+
+        assert_eq!(v1.actor_id, ActorId::for_node(&node()));
+        let rows = rows(&v1);
+        let cids: Vec<&str> = rows.iter().map(|r| r.cid.as_str()).collect();
+        assert_eq!(cids, vec![SENTINEL_CID, "app", "port"]);
+        assert_eq!(rows[1].val, SqliteValue::Text("myapp".to_string()));
+        assert_eq!(rows[2].val, SqliteValue::Integer(8080));
+        assert!(rows.iter().all(|r| r.cl == 1 && r.db_version == 7));
+        assert_eq!(rows.iter().map(|r| r.seq).collect::<Vec<_>>(), vec![0, 1, 2]);
+        assert_eq!(unpack_pk(&rows[0].pk).unwrap(), "b1");
+
+        let Changeset::Full { version, seqs, last_seq, .. } = &v1.changeset;
+        assert_eq!((*version, seqs.clone(), *last_seq), (7, 0..=2, 2));
+    }
+
+    #[test]
+    fn test_delete_exports_even_causal_length() {
+        let changeset = ChangeSet::new(node(), 1, vec![change("b1", ChangeKind::Delete, "", 10, 0)]);
+        let v1 = ChangeV1::from_changeset(&changeset);
+
+        let rows = rows(&v1);
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].cid, SENTINEL_CID);
+        assert_eq!(rows[0].cl, 2);
+    }
+
+    #[test]
+    fn test_col_version_keeps_hlc_order() {
+        let older = HLCTimestamp { wall_time: 1_700_000_000_000_000, counter: 9, node_hash: 0 };
+        let newer = HLCTimestamp { wall_time: 1_700_000_000_000_001, counter: 0, node_hash: 0 };
+        assert!(encode_col_version(&older) < encode_col_version(&newer));
+        assert_eq!(decode_col_version(encode_col_version(&older), 0), older);
+    }
+
+    #[test]
+    fn test_actor_id_for_node() {
+        let uuid = "5f0c3f6e-1b6d-4a52-9a3e-2d8f1e7c9b10";
+        assert_eq!(ActorId::for_node(&NodeId::new(uuid)).0.to_string(), uuid);
+
+        // Derived ids are stable and distinct per node
+        assert_eq!(ActorId::for_node(&node()), ActorId::for_node(&node()));
+        assert_ne!(ActorId::for_node(&node()), ActorId::for_node(&NodeId::new("pop-us-1")));
+    }
+
+    #[test]
+    fn test_json_encoding() {
+        let changeset = ChangeSet::new(node(), 2, vec![change("b1", ChangeKind::Update, r#"{"weight":3}"#, 5, 0)]);
+        let json = serde_json::to_value(ChangeV1::from_changeset(&changeset)).unwrap();
+
+        assert_eq!(json["actor_id"], ActorId::for_node(&node()).0.to_string());
+        let row = &json["changeset"]["Full"]["changes"][0];
+        assert_eq!(row["cid"], "weight");
+        assert_eq!(row["val"], 3);
+        assert_eq!(row["cl"], 1);
+    }
+
+    // ===== Packed Primary Key Tests =====
+
+    #[test]
+    fn test_pack_text_pk() {
+        assert_eq!(pack_text_pk("ab"), vec![1, 0x0b, 2, b'a', b'b']);
+
+        let long = "x".repeat(300);
+        let packed = pack_text_pk(&long);
+        assert_eq!(&packed[..4], &[1, 0x13, 0x01, 0x2c]);
+        assert_eq!(unpack_pk(&packed).unwrap(), long);
+    }
+
+    #[test]
+    fn test_unpack_integer_pk() {
+        assert_eq!(unpack_pk(&[1, 0x09, 42]).unwrap(), "42");
+        assert_eq!(unpack_pk(&[1, 0x11, 0xff, 0xfe]).unwrap(), "-2");
+    }
+
+    #[test]
+    fn test_unpack_invalid_pk() {
+        assert_eq!(unpack_pk(&[]), Err(CorrosionError::InvalidPk));
+        assert_eq!(unpack_pk(&[2, 0x0b, 1, b'a']), Err(CorrosionError::InvalidPk));
+        assert_eq!(unpack_pk(&[1, 0x0b, 5, b'a']), Err(CorrosionError::InvalidPk));
+        assert_eq!(unpack_pk(&[1, 0x0c]), Err(CorrosionError::InvalidPk));
+    }
+
+    // ===== Round Trip Tests =====
+
+    #[test]
+    fn test_round_trip_through_json() {
+        let original = ChangeSet::new(
+            node(),
+            42,
+            vec![
+                change(
+                    "b1",
+                    ChangeKind::Insert,
+                    r#"{"app":"myapp","region":"eu","wg_ip":"10.0.0.1","port":8080,"weight":2}"#,
+                    1_700_000_000_000_000,
+                    1,
+                ),
+                change("b2", ChangeKind::Update, r#"{"draining":1}"#, 1_700_000_000_000_500, 0),
+                change("b3", ChangeKind::Delete, "", 1_700_000_000_001_000, 2),
+            ],
+        );
+
+        let json = serde_json::to_string(&ChangeV1::from_changeset(&original)).unwrap();
+        let decoded: ChangeV1 = serde_json::from_str(&json).unwrap();
+        let restored = decoded.to_changeset().unwrap();
+
+        assert!(restored.verify());
+        assert_eq!(restored.seq, 42);
+        assert_eq!(restored.source, ActorId::for_node(&node()).node_id());
+        assert_eq!(restored.changes.len(), 3);
+        for (before, after) in original.changes.iter().zip(&restored.changes) {
+            assert_eq!(after.table, before.table);
+            assert_eq!(after.pk, before.pk);
+            assert_eq!(after.kind, before.kind);
+            assert_eq!(after.timestamp.wall_time, before.timestamp.wall_time);
+            assert_eq!(after.timestamp.counter, before.timestamp.counter);
+            let data = |c: &Change| serde_json::from_str::<serde_json::Value>(&c.data).ok();
+            assert_eq!(data(after), data(before));
+        }
+    }
+
+    #[test]
+    fn test_import_ids_are_stable() {
+        let changeset = ChangeSet::new(node(), 3, vec![change("b1", ChangeKind::Update, r#"{"weight":1}"#, 5, 0)]);
+        let v1 = ChangeV1::from_changeset(&changeset);
+
+        let first = v1.to_changeset().unwrap();
+        let second = v1.to_changeset().unwrap();
+        assert_eq!(first.changes[0].id, second.changes[0].id);
+    }
+
+    #[test]
+    fn test_empty_update_exports_nothing() {
+        let changeset = ChangeSet::new(node(), 3, vec![change("b1", ChangeKind::Update, "{}", 5, 0)]);
+        let v1 = ChangeV1::from_changeset(&changeset);
+
+        assert!(rows(&v1).is_empty());
+        assert!(v1.to_changeset().unwrap().changes.is_empty());
+    }
+}
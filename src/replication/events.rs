@@ -0,0 +1,183 @@
+//! Event Channels
+//!
+//! Bounded channels that carry events out of the replication services,
+//! with an explicit policy for when the consumer can't keep up.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::mpsc::{self, error::TrySendError};
+
+/// What a producer does with an event when the channel is full.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Discard the new event and count it as dropped (default).
+    ///
+    /// Gossip, sync and transport loops never stall on a slow or absent
+    /// consumer. Lost changesets are re-sent until acked and membership
+    /// is re-learned through gossip, so a dropped event is recoverable.
+    #[default]
+    DropNewest,
+    /// Wait for room, counting each wait as lagged.
+    ///
+    /// No event is lost, but the producing loop stalls until the
+    /// consumer catches up.
+    Block,
+}
+
+/// Counters for one event channel.
+#[derive(Debug, Default)]
+pub struct EventChannelStats {
+    sent: AtomicU64,
+    dropped: AtomicU64,
+    lagged: AtomicU64,
+}
+
+impl EventChannelStats {
+    /// Events queued for the consumer.
+    pub fn sent(&self) -> u64 {
+        self.sent.load(Ordering::Relaxed)
+    }
+
+    /// Events discarded because the channel was full or the consumer gone.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Sends that found the channel full and had to wait (`Block` only).
+    pub fn lagged(&self) -> u64 {
+        self.lagged.load(Ordering::Relaxed)
+    }
+}
+
+/// Sending half of an event channel.
+#[derive(Debug)]
+pub struct EventSender<T> {
+    tx: mpsc::Sender<T>,
+    policy: OverflowPolicy,
+    stats: Arc<EventChannelStats>,
+}
+
+impl<T> Clone for EventSender<T> {
+    fn clone(&self) -> Self {
+        Self {
+            tx: self.tx.clone(),
+            policy: self.policy,
+            stats: self.stats.clone(),
+        }
+    }
+}
+
+/// Create a bounded event channel (a capacity of 0 is treated as 1).
+pub fn event_channel<T>(
+    capacity: usize,
+    policy: OverflowPolicy,
+) -> (EventSender<T>, mpsc::Receiver<T>) {
+    let (tx, rx) = mpsc::channel(capacity.max(1));
+    let sender = EventSender {
+        tx,
+        policy,
+        stats: Arc::new(EventChannelStats::default()),
+    };
+    (sender, rx)
+}
+
+impl<T> EventSender<T> {
+    /// Send an event, applying the overflow policy if the channel is full.
+    ///
+    /// Returns true if the event was queued.
+    pub async fn send(&self, event: T) -> bool {
+        let queued = match self.tx.try_send(event) {
+            Ok(()) => true,
+            Err(TrySendError::Full(event)) => match self.policy {
+                OverflowPolicy::DropNewest => false,
+                OverflowPolicy::Block => {
+                    self.stats.lagged.fetch_add(1, Ordering::Relaxed);
+                    self.tx.send(event).await.is_ok()
+                }
+            },
+            Err(TrySendError::Closed(_)) => false,
+        };
+
+        if queued {
+            self.stats.sent.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.stats.dropped.fetch_add(1, Ordering::Relaxed);
+            tracing::trace!("event channel full or closed, dropping event");
+        }
+        queued
+    }
+
+    /// Counters for this channel (shared by all clones of the sender).
+    pub fn stats(&self) -> &EventChannelStats {
+        &self.stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_default_policy_drops_newest() {
+        assert_eq!(OverflowPolicy::default(), OverflowPolicy::DropNewest);
+    }
+
+    #[tokio::test]
+    async fn test_drop_newest_when_full() {
+        let (tx, mut rx) = event_channel(2, OverflowPolicy::DropNewest);
+
+        assert!(tx.send(1).await);
+        assert!(tx.send(2).await);
+        assert!(!tx.send(3).await);
+
+        assert_eq!(tx.stats().sent(), 2);
+        assert_eq!(tx.stats().dropped(), 1);
+        assert_eq!(tx.stats().lagged(), 0);
+
+        // The queued events survive, the overflowing one is gone
+        assert_eq!(rx.recv().await, Some(1));
+        assert_eq!(rx.recv().await, Some(2));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_block_waits_for_consumer() {
+        let (tx, mut rx) = event_channel(1, OverflowPolicy::Block);
+        assert!(tx.send(1).await);
+
+        let sender = tx.clone();
+        let blocked = tokio::spawn(async move { sender.send(2).await });
+
+        // The second send can't complete until the consumer makes room
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!blocked.is_finished());
+
+        assert_eq!(rx.recv().await, Some(1));
+        assert!(blocked.await.unwrap());
+        assert_eq!(rx.recv().await, Some(2));
+
+        assert_eq!(tx.stats().sent(), 2);
+        assert_eq!(tx.stats().lagged(), 1);
+        assert_eq!(tx.stats().dropped(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_closed_receiver_counts_as_dropped() {
+        for policy in [OverflowPolicy::DropNewest, OverflowPolicy::Block] {
+            let (tx, rx) = event_channel(4, policy);
+            drop(rx);
+
+            assert!(!tx.send(1).await);
+            assert_eq!(tx.stats().dropped(), 1);
+            assert_eq!(tx.stats().sent(), 0);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_zero_capacity_is_one() {
+        let (tx, _rx) = event_channel(0, OverflowPolicy::DropNewest);
+        assert!(tx.send(()).await);
+        assert!(!tx.send(()).await);
+    }
+}
@@ -7,10 +7,14 @@
 
 use crate::replication::types::NodeId;
 use crate::replication::config::ReplicationConfig;
+use crate::replication::events::{event_channel, EventChannelStats, EventSender};
 use parking_lot::RwLock;
+use rand::Rng;
+use rusqlite::{params, Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::net::UdpSocket;
@@ -77,6 +81,7 @@ pub enum GossipMessage {
         node_id: String,
         gossip_addr: SocketAddr,
         transport_addr: SocketAddr,
+        incarnation: u64,
     },
     /// Announce member list
     MemberList {
@@ -84,6 +89,153 @@ pub enum GossipMessage {
     },
 }
 
+/// First byte of every enveloped gossip datagram.
+///
+/// Legacy nodes send bare bincode, which starts with the low byte of a
+/// variant tag (0-3), so a datagram starting with this byte is never a
+/// legacy message.
+pub const GOSSIP_WIRE_MAGIC: u8 = 0xE5;
+
+/// Wire format version sent after [`GOSSIP_WIRE_MAGIC`].
+///
+/// Bump this only for incompatible envelope changes; new message variants can
+/// be added without a bump because unknown variant tags are skipped on decode.
+pub const GOSSIP_WIRE_VERSION: u8 = 1;
+
+/// Number of `GossipMessage` variants this node understands.
+/// Must be kept in sync with the enum; bincode encodes the variant index as a u32 tag.
+const KNOWN_MESSAGE_VARIANTS: u32 = 4;
+
+/// Gossip messages as sent by nodes predating the wire envelope.
+///
+/// Same variants and tags as [`GossipMessage`], but `Join` carries no
+/// incarnation.
+#[derive(Debug, Deserialize)]
+enum LegacyGossipMessage {
+    Ping {
+        sender_id: String,
+        sender_gossip_addr: SocketAddr,
+        sender_transport_addr: SocketAddr,
+        incarnation: u64,
+    },
+    Ack {
+        sender_id: String,
+        sender_gossip_addr: SocketAddr,
+        sender_transport_addr: SocketAddr,
+        incarnation: u64,
+    },
+    Join {
+        node_id: String,
+        gossip_addr: SocketAddr,
+        transport_addr: SocketAddr,
+    },
+    MemberList {
+        members: Vec<(String, SocketAddr, SocketAddr, u64)>,
+    },
+}
+
+impl From<LegacyGossipMessage> for GossipMessage {
+    fn from(msg: LegacyGossipMessage) -> Self {
+        match msg {
+            LegacyGossipMessage::Ping {
+                sender_id,
+                sender_gossip_addr,
+                sender_transport_addr,
+                incarnation,
+            } => GossipMessage::Ping {
+                sender_id,
+                sender_gossip_addr,
+                sender_transport_addr,
+                incarnation,
+            },
+            LegacyGossipMessage::Ack {
+                sender_id,
+                sender_gossip_addr,
+                sender_transport_addr,
+                incarnation,
+            } => GossipMessage::Ack {
+                sender_id,
+                sender_gossip_addr,
+                sender_transport_addr,
+                incarnation,
+            },
+            LegacyGossipMessage::Join {
+                node_id,
+                gossip_addr,
+                transport_addr,
+            } => GossipMessage::Join {
+                node_id,
+                gossip_addr,
+                transport_addr,
+                incarnation: 0,
+            },
+            LegacyGossipMessage::MemberList { members } => GossipMessage::MemberList { members },
+        }
+    }
+}
+
+/// Errors decoding a gossip datagram.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum GossipDecodeError {
+    #[error("empty datagram")]
+    Empty,
+    #[error("unsupported wire version {0}")]
+    InvalidVersion(u8),
+    #[error("malformed gossip message: {0}")]
+    Malformed(String),
+}
+
+/// Encode a gossip message with the magic and version envelope (Sans-IO pattern).
+pub fn encode_message(msg: &GossipMessage) -> Result<Vec<u8>, bincode::Error> {
+    let payload = bincode::serialize(msg)?;
+    let mut data = Vec::with_capacity(payload.len() + 2);
+    data.push(GOSSIP_WIRE_MAGIC);
+    data.push(GOSSIP_WIRE_VERSION);
+    data.extend_from_slice(&payload);
+    Ok(data)
+}
+
+/// Decode a gossip datagram (Sans-IO pattern).
+///
+/// Datagrams without [`GOSSIP_WIRE_MAGIC`] come from nodes predating the
+/// envelope and are decoded as bare bincode. Returns `Ok(None)` for
+/// datagrams from newer peers that this node cannot interpret (a newer wire
+/// version or an unknown message variant), so rolling upgrades don't look
+/// like corruption. Genuinely broken input is an error.
+pub fn decode_message(data: &[u8]) -> Result<Option<GossipMessage>, GossipDecodeError> {
+    let (&first, rest) = data.split_first().ok_or(GossipDecodeError::Empty)?;
+    if first != GOSSIP_WIRE_MAGIC {
+        return bincode::deserialize::<LegacyGossipMessage>(data)
+            .map(|msg| Some(msg.into()))
+            .map_err(|e| GossipDecodeError::Malformed(e.to_string()));
+    }
+
+    let (&version, payload) = rest
+        .split_first()
+        .ok_or_else(|| GossipDecodeError::Malformed("truncated envelope".to_string()))?;
+    if version == 0 {
+        return Err(GossipDecodeError::InvalidVersion(version));
+    }
+    if version > GOSSIP_WIRE_VERSION {
+        tracing::debug!("skipping gossip datagram with newer wire version {}", version);
+        return Ok(None);
+    }
+
+    let tag = payload
+        .get(..4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| GossipDecodeError::Malformed("truncated variant tag".to_string()))?;
+
+    if tag >= KNOWN_MESSAGE_VARIANTS {
+        tracing::debug!("skipping unknown gossip message variant {}", tag);
+        return Ok(None);
+    }
+
+    bincode::deserialize(payload)
+        .map(Some)
+        .map_err(|e| GossipDecodeError::Malformed(e.to_string()))
+}
+
 /// Events emitted by the gossip service.
 #[derive(Debug, Clone, PartialEq)]
 pub enum GossipEvent {
@@ -107,6 +259,9 @@ pub enum GossipAction {
     Send { to: SocketAddr, message: GossipMessage },
     /// Emit an event
     Emit(GossipEvent),
+    /// A peer gossiped this node at the given incarnation, newer than the
+    /// local one; move past it so the stale entry is overridden
+    Refute(u64),
     /// No action needed
     None,
 }
@@ -221,14 +376,14 @@ pub fn process_message(
             }
         }
 
-        GossipMessage::Join { node_id, gossip_addr, transport_addr } => {
+        GossipMessage::Join { node_id, gossip_addr, transport_addr, incarnation } => {
             let member = Member {
                 node_id: NodeId::new(node_id),
                 gossip_addr: *gossip_addr,
                 transport_addr: *transport_addr,
                 state: MemberState::Alive,
                 last_seen: Instant::now(),
-                incarnation: 0,
+                incarnation: *incarnation,
             };
 
             let is_new = {
@@ -260,6 +415,9 @@ pub fn process_message(
 
             for (id, gossip_addr, transport_addr, incarnation) in member_list {
                 if id == local_node_id {
+                    if *incarnation > local_incarnation {
+                        result.actions.push(GossipAction::Refute(*incarnation));
+                    }
                     continue;
                 }
 
@@ -274,6 +432,10 @@ pub fn process_message(
 
                 let is_new = {
                     let mut guard = members.write();
+                    // Hearsay never overrides what we know of a newer incarnation
+                    if guard.get(id).is_some_and(|known| known.incarnation > *incarnation) {
+                        continue;
+                    }
                     let is_new = !guard.contains_key(id);
                     guard.insert(id.clone(), member.clone());
                     is_new
@@ -324,9 +486,114 @@ pub fn check_member_failures(
     actions
 }
 
+/// Join storm protection (Sans-IO pattern).
+///
+/// When many nodes bootstrap against one seed at once, every Join would
+/// trigger a full MemberList reply. The throttle ignores repeated Joins
+/// from the same node within `join_window` and sends at most one
+/// MemberList to a peer per `reply_interval`.
+#[derive(Debug)]
+pub struct JoinThrottle {
+    join_window: Duration,
+    reply_interval: Duration,
+    last_join: HashMap<String, Instant>,
+    last_reply: HashMap<SocketAddr, Instant>,
+}
+
+impl JoinThrottle {
+    /// Create a throttle with the given dedup window and reply interval.
+    pub fn new(join_window: Duration, reply_interval: Duration) -> Self {
+        Self {
+            join_window,
+            reply_interval,
+            last_join: HashMap::new(),
+            last_reply: HashMap::new(),
+        }
+    }
+
+    /// Create a throttle from the replication config.
+    pub fn from_config(config: &ReplicationConfig) -> Self {
+        Self::new(config.join_dedup_window, config.member_list_interval)
+    }
+
+    /// Record a Join from `node_id`; false if it repeats one within the window.
+    pub fn accept_join(&mut self, node_id: &str, now: Instant) -> bool {
+        let window = self.join_window;
+        self.last_join
+            .retain(|_, seen| now.saturating_duration_since(*seen) < window);
+        if self.last_join.contains_key(node_id) {
+            return false;
+        }
+        self.last_join.insert(node_id.to_string(), now);
+        true
+    }
+
+    /// Record a MemberList reply to `to`; false if one was sent too recently.
+    pub fn allow_member_list(&mut self, to: SocketAddr, now: Instant) -> bool {
+        let interval = self.reply_interval;
+        self.last_reply
+            .retain(|_, sent| now.saturating_duration_since(*sent) < interval);
+        if self.last_reply.contains_key(&to) {
+            return false;
+        }
+        self.last_reply.insert(to, now);
+        true
+    }
+
+    /// Apply the throttle around `process_message`.
+    ///
+    /// Duplicate Joins are dropped without touching membership, and
+    /// MemberList sends to a peer that was answered recently are removed.
+    #[allow(clippy::too_many_arguments)]
+    pub fn process(
+        &mut self,
+        msg: &GossipMessage,
+        src: SocketAddr,
+        members: &RwLock<HashMap<String, Member>>,
+        local_node_id: &str,
+        local_gossip_addr: SocketAddr,
+        local_transport_addr: SocketAddr,
+        local_incarnation: u64,
+        now: Instant,
+    ) -> ProcessResult {
+        if let GossipMessage::Join { node_id, .. } = msg {
+            if !self.accept_join(node_id, now) {
+                tracing::debug!("ignoring repeated join from {} ({})", node_id, src);
+                return ProcessResult::empty();
+            }
+        }
+
+        let mut result = process_message(
+            msg,
+            src,
+            members,
+            local_node_id,
+            local_gossip_addr,
+            local_transport_addr,
+            local_incarnation,
+        );
+        result.actions.retain(|action| match action {
+            GossipAction::Send { to, message: GossipMessage::MemberList { .. } } => {
+                self.allow_member_list(*to, now)
+            }
+            _ => true,
+        });
+        result
+    }
+}
+
 /// Select random member for ping (Sans-IO pattern).
 pub fn select_ping_target(members: &RwLock<HashMap<String, Member>>) -> Option<SocketAddr> {
-    let member_addrs: Vec<SocketAddr> = members
+    select_ping_target_with_rng(members, &mut rand::thread_rng())
+}
+
+/// Select random member for ping using the given RNG (Sans-IO pattern).
+/// Candidates are sorted by address so a seeded RNG yields a reproducible sequence.
+pub fn select_ping_target_with_rng<R: Rng + ?Sized>(
+    members: &RwLock<HashMap<String, Member>>,
+    rng: &mut R,
+) -> Option<SocketAddr> {
+    let mut member_addrs: Vec<SocketAddr> = members
         .read()
         .values()
         .filter(|m| m.state == MemberState::Alive)
@@ -337,10 +604,26 @@ pub fn select_ping_target(members: &RwLock<HashMap<String, Member>>) -> Option<S
         return None;
     }
 
-    let idx = rand::random::<usize>() % member_addrs.len();
+    member_addrs.sort();
+    let idx = rng.gen_range(0..member_addrs.len());
     Some(member_addrs[idx])
 }
 
+/// Interval until the next tick of a jittered timer.
+pub fn jittered(base: Duration, jitter: f64) -> Duration {
+    jittered_with_rng(base, jitter, &mut rand::thread_rng())
+}
+
+/// `base` scaled by a random factor in `1 ± jitter`, using the given RNG.
+///
+/// A jitter of 0 returns `base` unchanged.
+pub fn jittered_with_rng<R: Rng + ?Sized>(base: Duration, jitter: f64, rng: &mut R) -> Duration {
+    if jitter <= 0.0 {
+        return base;
+    }
+    base.mul_f64(1.0 + rng.gen_range(-jitter..=jitter))
+}
+
 /// Create a ping message (Sans-IO pattern).
 pub fn create_ping(
     node_id: &str,
@@ -361,11 +644,103 @@ pub fn create_join(
     node_id: &str,
     gossip_addr: SocketAddr,
     transport_addr: SocketAddr,
+    incarnation: u64,
 ) -> GossipMessage {
     GossipMessage::Join {
         node_id: node_id.to_string(),
         gossip_addr,
         transport_addr,
+        incarnation,
+    }
+}
+
+/// The local node's gossip incarnation.
+///
+/// Peers trust the highest incarnation they have heard for a node, so it
+/// must only ever grow. When backed by the replication database it is
+/// stored in `__gossip_incarnation`, bumped on every load (i.e. every
+/// restart) and on every refutation.
+#[derive(Debug)]
+pub struct Incarnation {
+    value: AtomicU64,
+    db_path: Option<String>,
+}
+
+impl Incarnation {
+    /// An incarnation that lives only as long as the process.
+    pub fn in_memory(value: u64) -> Self {
+        Self {
+            value: AtomicU64::new(value),
+            db_path: None,
+        }
+    }
+
+    /// Load the stored incarnation from `db_path` and persist the next one.
+    ///
+    /// The new incarnation is one past the larger of the stored value and
+    /// `seed`, so a restarted node always outranks gossip from its past life.
+    pub fn load(db_path: &str, seed: u64) -> anyhow::Result<Self> {
+        let conn = Self::open(db_path)?;
+        let stored: Option<u64> = conn
+            .query_row("SELECT incarnation FROM __gossip_incarnation WHERE id = 0", [], |row| {
+                row.get(0)
+            })
+            .optional()?;
+
+        let value = stored.unwrap_or(0).max(seed) + 1;
+        Self::store(&conn, value)?;
+        tracing::info!("gossip incarnation {} (stored {:?})", value, stored);
+
+        Ok(Self {
+            value: AtomicU64::new(value),
+            db_path: Some(db_path.to_string()),
+        })
+    }
+
+    /// The current incarnation.
+    pub fn current(&self) -> u64 {
+        self.value.load(Ordering::SeqCst)
+    }
+
+    /// Move past an incarnation a peer claims for this node.
+    ///
+    /// Returns the incarnation in effect afterwards; unchanged when
+    /// `observed` is not newer than the current one.
+    pub fn refute(&self, observed: u64) -> u64 {
+        let next = observed.saturating_add(1);
+        let previous = self.value.fetch_max(next, Ordering::SeqCst);
+        if previous >= next {
+            return previous;
+        }
+
+        tracing::info!("refuting gossip at incarnation {}, now {}", observed, next);
+        if let Some(db_path) = &self.db_path {
+            if let Err(e) = Self::open(db_path).and_then(|conn| Self::store(&conn, next)) {
+                tracing::warn!("failed to persist gossip incarnation {}: {}", next, e);
+            }
+        }
+        next
+    }
+
+    fn open(db_path: &str) -> anyhow::Result<Connection> {
+        let conn = Connection::open(db_path)?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS __gossip_incarnation (
+                id INTEGER PRIMARY KEY CHECK (id = 0),
+                incarnation INTEGER NOT NULL
+            )",
+            [],
+        )?;
+        Ok(conn)
+    }
+
+    fn store(conn: &Connection, value: u64) -> anyhow::Result<()> {
+        conn.execute(
+            "INSERT INTO __gossip_incarnation (id, incarnation) VALUES (0, ?1)
+             ON CONFLICT(id) DO UPDATE SET incarnation = MAX(incarnation, excluded.incarnation)",
+            params![value],
+        )?;
+        Ok(())
     }
 }
 
@@ -373,15 +748,19 @@ pub fn create_join(
 pub struct GossipService {
     config: ReplicationConfig,
     members: Arc<RwLock<HashMap<String, Member>>>,
-    event_tx: mpsc::Sender<GossipEvent>,
+    event_tx: EventSender<GossipEvent>,
     event_rx: Option<mpsc::Receiver<GossipEvent>>,
     shutdown: Arc<RwLock<bool>>,
+    incarnation: Arc<Incarnation>,
 }
 
 impl GossipService {
     /// Create a new gossip service.
     pub fn new(config: ReplicationConfig) -> Self {
-        let (event_tx, event_rx) = mpsc::channel(1024);
+        let (event_tx, event_rx) =
+            event_channel(config.event_channel_capacity, config.event_overflow);
+
+        let incarnation = Arc::new(Incarnation::in_memory(config.incarnation_seed));
 
         Self {
             config,
@@ -389,14 +768,31 @@ impl GossipService {
             event_tx,
             event_rx: Some(event_rx),
             shutdown: Arc::new(RwLock::new(false)),
+            incarnation,
         }
     }
 
+    /// Use the given (usually persisted) incarnation instead of the seed.
+    pub fn with_incarnation(mut self, incarnation: Incarnation) -> Self {
+        self.incarnation = Arc::new(incarnation);
+        self
+    }
+
+    /// The local node's current incarnation.
+    pub fn incarnation(&self) -> u64 {
+        self.incarnation.current()
+    }
+
     /// Get the event receiver (can only be called once).
     pub fn take_event_rx(&mut self) -> Option<mpsc::Receiver<GossipEvent>> {
         self.event_rx.take()
     }
 
+    /// Counters for the event channel (sent, dropped, lagged).
+    pub fn event_stats(&self) -> &EventChannelStats {
+        self.event_tx.stats()
+    }
+
     /// Get all current members.
     pub fn members(&self) -> Vec<Member> {
         self.members.read().values().cloned().collect()
@@ -412,6 +808,24 @@ impl GossipService {
             .collect()
     }
 
+    /// Count members by state as (alive, suspect, dead).
+    pub fn member_counts(&self) -> (usize, usize, usize) {
+        self.members
+            .read()
+            .values()
+            .fold((0, 0, 0), |(alive, suspect, dead), m| match m.state {
+                MemberState::Alive => (alive + 1, suspect, dead),
+                MemberState::Suspect => (alive, suspect + 1, dead),
+                MemberState::Dead => (alive, suspect, dead + 1),
+            })
+    }
+
+    /// Insert a member directly, bypassing the protocol.
+    #[cfg(test)]
+    pub(crate) fn insert_member(&self, member: Member) {
+        self.members.write().insert(member.node_id.0.clone(), member);
+    }
+
     /// Get a specific member by ID.
     pub fn get_member(&self, node_id: &str) -> Option<Member> {
         self.members.read().get(node_id).cloned()
@@ -438,22 +852,23 @@ impl GossipService {
         let event_tx = self.event_tx.clone();
         let shutdown = self.shutdown.clone();
         let gossip_interval = self.config.gossip_interval;
+        let failure_interval = self.config.failure_check_interval;
+        let failure_timeout = self.config.failure_timeout;
+        let jitter = self.config.timer_jitter;
         let node_id = self.config.node_id.clone();
         let gossip_addr = self.config.gossip_addr;
         let transport_addr = self.config.transport_addr;
         let bootstrap_peers = self.config.bootstrap_peers.clone();
+        let incarnation = self.incarnation.clone();
 
         // Send join messages to bootstrap peers
         let socket_clone = socket.clone();
+        let join_incarnation = incarnation.current();
         tokio::spawn(async move {
             for peer in &bootstrap_peers {
                 if let Ok(addr) = peer.parse::<SocketAddr>() {
-                    let join_msg = GossipMessage::Join {
-                        node_id: node_id.clone(),
-                        gossip_addr,
-                        transport_addr,
-                    };
-                    if let Ok(data) = bincode::serialize(&join_msg) {
+                    let join_msg = create_join(&node_id, gossip_addr, transport_addr, join_incarnation);
+                    if let Ok(data) = encode_message(&join_msg) {
                         let _ = socket_clone.send_to(&data, addr).await;
                         tracing::info!("sent join message to bootstrap peer {}", addr);
                     }
@@ -466,12 +881,15 @@ impl GossipService {
         let node_id_recv = self.config.node_id.clone();
         let gossip_addr_recv = self.config.gossip_addr;
         let transport_addr_recv = self.config.transport_addr;
+        let mut join_throttle = JoinThrottle::from_config(&self.config);
 
         tokio::spawn(async move {
             let mut buf = vec![0u8; 65535];
-            let mut gossip_timer = tokio::time::interval(gossip_interval);
-            let mut failure_timer = tokio::time::interval(Duration::from_secs(10));
-            let incarnation: u64 = 0;
+            // Jittered one-shot timers, re-armed on every tick, so nodes
+            // started together don't ping and sweep in lockstep
+            let gossip_timer = tokio::time::sleep(jittered(gossip_interval, jitter));
+            let failure_timer = tokio::time::sleep(jittered(failure_interval, jitter));
+            tokio::pin!(gossip_timer, failure_timer);
 
             loop {
                 if *shutdown.read() {
@@ -484,19 +902,25 @@ impl GossipService {
                     result = socket_recv.recv_from(&mut buf) => {
                         match result {
                             Ok((len, src)) => {
-                                let data = &buf[..len];
-                                if let Ok(msg) = bincode::deserialize::<GossipMessage>(data) {
-                                    Self::handle_message(
-                                        &msg,
-                                        src,
-                                        &members,
-                                        &event_tx,
-                                        &socket_recv,
-                                        &node_id_recv,
-                                        gossip_addr_recv,
-                                        transport_addr_recv,
-                                        incarnation,
-                                    ).await;
+                                match decode_message(&buf[..len]) {
+                                    Ok(Some(msg)) => {
+                                        Self::handle_message(
+                                            &msg,
+                                            src,
+                                            &members,
+                                            &event_tx,
+                                            &socket_recv,
+                                            &node_id_recv,
+                                            gossip_addr_recv,
+                                            transport_addr_recv,
+                                            &incarnation,
+                                            &mut join_throttle,
+                                        ).await;
+                                    }
+                                    Ok(None) => {}
+                                    Err(e) => {
+                                        tracing::warn!("dropping gossip datagram from {}: {}", src, e);
+                                    }
                                 }
                             }
                             Err(e) => {
@@ -506,57 +930,31 @@ impl GossipService {
                     }
 
                     // Periodic ping to random member
-                    _ = gossip_timer.tick() => {
-                        let member_addrs: Vec<SocketAddr> = members.read()
-                            .values()
-                            .filter(|m| m.state == MemberState::Alive)
-                            .map(|m| m.gossip_addr)
-                            .collect();
-
-                        if !member_addrs.is_empty() {
-                            let idx = rand::random::<usize>() % member_addrs.len();
-                            let target = member_addrs[idx];
-
-                            let ping = GossipMessage::Ping {
-                                sender_id: node_id_recv.clone(),
-                                sender_gossip_addr: gossip_addr_recv,
-                                sender_transport_addr: transport_addr_recv,
-                                incarnation,
-                            };
-
-                            if let Ok(data) = bincode::serialize(&ping) {
+                    () = &mut gossip_timer => {
+                        gossip_timer
+                            .as_mut()
+                            .reset(tokio::time::Instant::now() + jittered(gossip_interval, jitter));
+                        if let Some(target) = select_ping_target(&members) {
+                            let ping = create_ping(
+                                &node_id_recv,
+                                gossip_addr_recv,
+                                transport_addr_recv,
+                                incarnation.current(),
+                            );
+
+                            if let Ok(data) = encode_message(&ping) {
                                 let _ = socket_recv.send_to(&data, target).await;
                             }
                         }
                     }
 
                     // Check for dead members
-                    _ = failure_timer.tick() => {
-                        let now = Instant::now();
-                        let mut dead_members = Vec::new();
-
-                        {
-                            let mut guard = members.write();
-                            for (id, member) in guard.iter_mut() {
-                                if member.state == MemberState::Alive
-                                    && now.duration_since(member.last_seen) > Duration::from_secs(30)
-                                {
-                                    let old_state = member.state.clone();
-                                    member.state = MemberState::Dead;
-                                    dead_members.push((id.clone(), old_state));
-                                }
-                            }
-                        }
-
-                        for (id, old_state) in dead_members {
-                            let node_id = NodeId::new(&id);
-                            let _ = event_tx.send(GossipEvent::MemberStateChanged {
-                                node_id: node_id.clone(),
-                                old_state,
-                                new_state: MemberState::Dead,
-                            }).await;
-                            let _ = event_tx.send(GossipEvent::MemberLeft(node_id)).await;
-                        }
+                    () = &mut failure_timer => {
+                        failure_timer
+                            .as_mut()
+                            .reset(tokio::time::Instant::now() + jittered(failure_interval, jitter));
+                        let actions = check_member_failures(&members, failure_timeout);
+                        Self::execute_actions(actions, &socket_recv, &event_tx, &incarnation).await;
                     }
                 }
             }
@@ -571,17 +969,21 @@ impl GossipService {
     async fn execute_actions(
         actions: Vec<GossipAction>,
         socket: &UdpSocket,
-        event_tx: &mpsc::Sender<GossipEvent>,
+        event_tx: &EventSender<GossipEvent>,
+        incarnation: &Incarnation,
     ) {
         for action in actions {
             match action {
                 GossipAction::Send { to, message } => {
-                    if let Ok(data) = bincode::serialize(&message) {
+                    if let Ok(data) = encode_message(&message) {
                         let _ = socket.send_to(&data, to).await;
                     }
                 }
                 GossipAction::Emit(event) => {
-                    let _ = event_tx.send(event).await;
+                    event_tx.send(event).await;
+                }
+                GossipAction::Refute(observed) => {
+                    incarnation.refute(observed);
                 }
                 GossipAction::None => {}
             }
@@ -589,28 +991,31 @@ impl GossipService {
     }
 
     /// Handle incoming gossip message using Sans-IO pattern.
-    /// Delegates to process_message() and executes returned actions.
+    /// Delegates to process_message() via the join throttle and executes returned actions.
     #[cfg_attr(coverage_nightly, coverage(off))]
+    #[allow(clippy::too_many_arguments)]
     async fn handle_message(
         msg: &GossipMessage,
         src: SocketAddr,
         members: &RwLock<HashMap<String, Member>>,
-        event_tx: &mpsc::Sender<GossipEvent>,
+        event_tx: &EventSender<GossipEvent>,
         socket: &UdpSocket,
         local_node_id: &str,
         local_gossip_addr: SocketAddr,
         local_transport_addr: SocketAddr,
-        local_incarnation: u64,
+        incarnation: &Incarnation,
+        throttle: &mut JoinThrottle,
     ) {
-        // Use Sans-IO process_message to get actions
-        let result = process_message(
+        // Use Sans-IO process_message (behind the join throttle) to get actions
+        let result = throttle.process(
             msg,
             src,
             members,
             local_node_id,
             local_gossip_addr,
             local_transport_addr,
-            local_incarnation,
+            incarnation.current(),
+            Instant::now(),
         );
 
         // Log member discoveries
@@ -619,7 +1024,7 @@ impl GossipService {
         }
 
         // Execute all actions
-        Self::execute_actions(result.actions, socket, event_tx).await;
+        Self::execute_actions(result.actions, socket, event_tx, incarnation).await;
     }
 }
 
@@ -755,16 +1160,18 @@ mod tests {
             node_id: "new-node".to_string(),
             gossip_addr: "10.0.0.1:4001".parse().unwrap(),
             transport_addr: "10.0.0.1:4002".parse().unwrap(),
+            incarnation: 4,
         };
 
         let data = bincode::serialize(&msg).unwrap();
         let decoded: GossipMessage = bincode::deserialize(&data).unwrap();
 
         match decoded {
-            GossipMessage::Join { node_id, gossip_addr, transport_addr } => {
+            GossipMessage::Join { node_id, gossip_addr, transport_addr, incarnation } => {
                 assert_eq!(node_id, "new-node");
                 assert_eq!(gossip_addr, "10.0.0.1:4001".parse::<SocketAddr>().unwrap());
                 assert_eq!(transport_addr, "10.0.0.1:4002".parse::<SocketAddr>().unwrap());
+                assert_eq!(incarnation, 4);
             }
             _ => panic!("wrong message type"),
         }
@@ -812,6 +1219,7 @@ mod tests {
             node_id: "test".to_string(),
             gossip_addr: "127.0.0.1:4001".parse().unwrap(),
             transport_addr: "127.0.0.1:4002".parse().unwrap(),
+            incarnation: 1,
         };
 
         let cloned = msg.clone();
@@ -1222,6 +1630,191 @@ mod tests {
         assert!(service.is_shutdown());
     }
 
+    #[tokio::test]
+    async fn test_live_loop_declares_stale_member_dead_per_config() {
+        let config = ReplicationConfig::new("test-node")
+            .gossip_addr("127.0.0.1:0".parse().unwrap())
+            .failure_timeout(Duration::from_millis(500))
+            .failure_check_interval(Duration::from_millis(50))
+            .timer_jitter(0.0);
+        let mut service = GossipService::new(config);
+        let mut events = service.take_event_rx().unwrap();
+
+        let member = |id: &str, silent_for: Duration| Member {
+            node_id: NodeId::new(id),
+            gossip_addr: "127.0.0.1:9".parse().unwrap(),
+            transport_addr: "127.0.0.1:9".parse().unwrap(),
+            state: MemberState::Alive,
+            last_seen: Instant::now() - silent_for,
+            incarnation: 1,
+        };
+        service.insert_member(member("stale", Duration::from_secs(2)));
+        service.insert_member(member("recent", Duration::ZERO));
+
+        let service = Arc::new(service);
+        service.clone().start().await.unwrap();
+
+        // Well before the 30s default, the stale member is declared dead
+        let left = tokio::time::timeout(Duration::from_secs(2), async {
+            loop {
+                if let Some(GossipEvent::MemberLeft(node_id)) = events.recv().await {
+                    return node_id;
+                }
+            }
+        })
+        .await
+        .expect("stale member was never declared dead");
+        assert_eq!(left.as_str(), "stale");
+        assert_eq!(service.get_member("stale").unwrap().state, MemberState::Dead);
+        assert_eq!(service.get_member("recent").unwrap().state, MemberState::Alive);
+
+        service.shutdown();
+    }
+
+    // ==================== Wire Envelope Tests ====================
+
+    fn unknown_variant_datagram(tag: u32) -> Vec<u8> {
+        let mut data = vec![GOSSIP_WIRE_MAGIC, GOSSIP_WIRE_VERSION];
+        data.extend_from_slice(&tag.to_le_bytes());
+        data.extend_from_slice(b"payload from a newer node");
+        data
+    }
+
+    #[test]
+    fn test_encode_message_prepends_version() {
+        let msg = create_ping("node-1", "127.0.0.1:4001".parse().unwrap(), "127.0.0.1:4002".parse().unwrap(), 1);
+        let data = encode_message(&msg).unwrap();
+
+        assert_eq!(data[..2], [GOSSIP_WIRE_MAGIC, GOSSIP_WIRE_VERSION]);
+        assert_eq!(&data[2..], bincode::serialize(&msg).unwrap().as_slice());
+    }
+
+    #[test]
+    fn test_encode_decode_roundtrip_all_variants() {
+        let gossip_addr: SocketAddr = "127.0.0.1:4001".parse().unwrap();
+        let transport_addr: SocketAddr = "127.0.0.1:4002".parse().unwrap();
+        let messages = vec![
+            create_ping("node-1", gossip_addr, transport_addr, 3),
+            GossipMessage::Ack {
+                sender_id: "node-2".to_string(),
+                sender_gossip_addr: gossip_addr,
+                sender_transport_addr: transport_addr,
+                incarnation: 4,
+            },
+            create_join("node-3", gossip_addr, transport_addr, 1),
+            GossipMessage::MemberList {
+                members: vec![("node-4".to_string(), gossip_addr, transport_addr, 5)],
+            },
+        ];
+        assert_eq!(messages.len() as u32, KNOWN_MESSAGE_VARIANTS);
+
+        for msg in messages {
+            let data = encode_message(&msg).unwrap();
+            assert_eq!(decode_message(&data).unwrap(), Some(msg));
+        }
+    }
+
+    #[test]
+    fn test_decode_unknown_variant_is_skipped() {
+        let data = unknown_variant_datagram(KNOWN_MESSAGE_VARIANTS);
+        assert_eq!(decode_message(&data), Ok(None));
+
+        let data = unknown_variant_datagram(u32::MAX);
+        assert_eq!(decode_message(&data), Ok(None));
+    }
+
+    #[test]
+    fn test_decode_newer_wire_version_is_skipped() {
+        let msg = create_join("node-1", "127.0.0.1:4001".parse().unwrap(), "127.0.0.1:4002".parse().unwrap(), 1);
+        let mut data = encode_message(&msg).unwrap();
+        data[1] = GOSSIP_WIRE_VERSION + 1;
+
+        assert_eq!(decode_message(&data), Ok(None));
+    }
+
+    #[test]
+    fn test_decode_empty_datagram() {
+        assert_eq!(decode_message(&[]), Err(GossipDecodeError::Empty));
+    }
+
+    #[test]
+    fn test_decode_version_zero_rejected() {
+        assert_eq!(
+            decode_message(&[GOSSIP_WIRE_MAGIC, 0, 0, 0, 0, 0]),
+            Err(GossipDecodeError::InvalidVersion(0))
+        );
+    }
+
+    #[test]
+    fn test_decode_truncated_envelope() {
+        let result = decode_message(&[GOSSIP_WIRE_MAGIC]);
+        assert!(matches!(result, Err(GossipDecodeError::Malformed(_))));
+    }
+
+    #[test]
+    fn test_decode_truncated_tag() {
+        let result = decode_message(&[GOSSIP_WIRE_MAGIC, GOSSIP_WIRE_VERSION, 1, 0]);
+        assert!(matches!(result, Err(GossipDecodeError::Malformed(_))));
+    }
+
+    #[test]
+    fn test_decode_known_variant_with_corrupt_body() {
+        // Join tag (2) followed by a truncated body
+        let data = [GOSSIP_WIRE_MAGIC, GOSSIP_WIRE_VERSION, 2, 0, 0, 0, 0xff];
+        let result = decode_message(&data);
+        assert!(matches!(result, Err(GossipDecodeError::Malformed(_))));
+    }
+
+    /// A baseline-encoded datagram: bare bincode of the pre-envelope message.
+    fn legacy_datagram(tag: u32, body: impl serde::Serialize) -> Vec<u8> {
+        let mut data = tag.to_le_bytes().to_vec();
+        data.extend(bincode::serialize(&body).unwrap());
+        data
+    }
+
+    #[test]
+    fn test_decode_legacy_payloads() {
+        let gossip_addr: SocketAddr = "127.0.0.1:4001".parse().unwrap();
+        let transport_addr: SocketAddr = "127.0.0.1:4002".parse().unwrap();
+
+        let ping = create_ping("node-1", gossip_addr, transport_addr, 1);
+        let data = bincode::serialize(&ping).unwrap();
+        assert_eq!(data[0], 0);
+        assert_eq!(decode_message(&data), Ok(Some(ping)));
+
+        let data = legacy_datagram(1, ("node-2", gossip_addr, transport_addr, 4u64));
+        assert_eq!(
+            decode_message(&data),
+            Ok(Some(GossipMessage::Ack {
+                sender_id: "node-2".to_string(),
+                sender_gossip_addr: gossip_addr,
+                sender_transport_addr: transport_addr,
+                incarnation: 4,
+            }))
+        );
+
+        // Baseline joins carry no incarnation
+        let data = legacy_datagram(2, ("node-3", gossip_addr, transport_addr));
+        assert_eq!(decode_message(&data), Ok(Some(create_join("node-3", gossip_addr, transport_addr, 0))));
+
+        let members = vec![("node-4".to_string(), gossip_addr, transport_addr, 5u64)];
+        let data = legacy_datagram(3, &members);
+        assert_eq!(decode_message(&data), Ok(Some(GossipMessage::MemberList { members })));
+    }
+
+    #[test]
+    fn test_decode_corrupt_legacy_payload() {
+        let result = decode_message(&[2, 0, 0, 0, 0xff]);
+        assert!(matches!(result, Err(GossipDecodeError::Malformed(_))));
+    }
+
+    #[test]
+    fn test_gossip_decode_error_display() {
+        assert_eq!(GossipDecodeError::Empty.to_string(), "empty datagram");
+        assert_eq!(GossipDecodeError::InvalidVersion(0).to_string(), "unsupported wire version 0");
+        assert!(GossipDecodeError::Malformed("x".to_string()).to_string().contains("malformed"));
+    }
+
     // ==================== Sans-IO Tests ====================
 
     #[test]
@@ -1493,6 +2086,7 @@ mod tests {
             node_id: "new-peer".to_string(),
             gossip_addr: joiner_gossip,
             transport_addr: joiner_transport,
+            incarnation: 1,
         };
 
         let result = process_message(
@@ -1532,6 +2126,122 @@ mod tests {
         }
     }
 
+    // ===== Join Throttle Tests =====
+
+    fn join_from(node_id: &str, addr: &str) -> GossipMessage {
+        let gossip_addr: SocketAddr = format!("{}:4001", addr).parse().unwrap();
+        let transport_addr: SocketAddr = format!("{}:4002", addr).parse().unwrap();
+        create_join(node_id, gossip_addr, transport_addr, 1)
+    }
+
+    fn member_list_sends(result: &ProcessResult) -> usize {
+        result
+            .actions
+            .iter()
+            .filter(|a| matches!(a, GossipAction::Send { message: GossipMessage::MemberList { .. }, .. }))
+            .count()
+    }
+
+    fn throttled(
+        throttle: &mut JoinThrottle,
+        msg: &GossipMessage,
+        src: SocketAddr,
+        members: &RwLock<HashMap<String, Member>>,
+        now: Instant,
+    ) -> ProcessResult {
+        let local_gossip: SocketAddr = "127.0.0.1:4001".parse().unwrap();
+        let local_transport: SocketAddr = "127.0.0.1:4002".parse().unwrap();
+        throttle.process(msg, src, members, "seed", local_gossip, local_transport, 1, now)
+    }
+
+    #[test]
+    fn test_repeated_joins_get_one_member_list() {
+        let members = RwLock::new(HashMap::new());
+        let mut throttle = JoinThrottle::new(Duration::from_secs(5), Duration::from_secs(1));
+        let src: SocketAddr = "10.0.0.3:4001".parse().unwrap();
+        let join = join_from("joiner", "10.0.0.3");
+        let start = Instant::now();
+
+        let replies: usize = (0..10)
+            .map(|i| {
+                let now = start + Duration::from_millis(i * 100);
+                member_list_sends(&throttled(&mut throttle, &join, src, &members, now))
+            })
+            .sum();
+        assert_eq!(replies, 1);
+        assert_eq!(members.read().len(), 1);
+
+        // Once the window has passed the node may join again
+        let later = start + Duration::from_secs(6);
+        let result = throttled(&mut throttle, &join, src, &members, later);
+        assert_eq!(member_list_sends(&result), 1);
+    }
+
+    #[test]
+    fn test_duplicate_join_is_ignored_entirely() {
+        let members = RwLock::new(HashMap::new());
+        let mut throttle = JoinThrottle::new(Duration::from_secs(5), Duration::from_secs(1));
+        let src: SocketAddr = "10.0.0.3:4001".parse().unwrap();
+        let now = Instant::now();
+
+        let first = throttled(&mut throttle, &join_from("joiner", "10.0.0.3"), src, &members, now);
+        assert!(first.member_discovered);
+
+        let again = throttled(&mut throttle, &join_from("joiner", "10.0.0.3"), src, &members, now);
+        assert!(again.actions.is_empty());
+        assert!(!again.member_discovered);
+    }
+
+    #[test]
+    fn test_member_list_capped_per_peer() {
+        let members = RwLock::new(HashMap::new());
+        let mut throttle = JoinThrottle::new(Duration::from_secs(5), Duration::from_secs(1));
+        let src: SocketAddr = "10.0.0.3:4001".parse().unwrap();
+        let now = Instant::now();
+
+        // Distinct node ids behind one address still get one reply per interval
+        let a = throttled(&mut throttle, &join_from("node-a", "10.0.0.3"), src, &members, now);
+        let b = throttled(&mut throttle, &join_from("node-b", "10.0.0.3"), src, &members, now);
+        assert_eq!(member_list_sends(&a), 1);
+        assert_eq!(member_list_sends(&b), 0);
+        // The second join still updates membership and emits its event
+        assert!(b.member_discovered);
+        assert_eq!(members.read().len(), 2);
+
+        // Another peer is answered independently
+        let other: SocketAddr = "10.0.0.4:4001".parse().unwrap();
+        let c = throttled(&mut throttle, &join_from("node-c", "10.0.0.4"), other, &members, now);
+        assert_eq!(member_list_sends(&c), 1);
+
+        let later = now + Duration::from_secs(1);
+        let d = throttled(&mut throttle, &join_from("node-d", "10.0.0.3"), src, &members, later);
+        assert_eq!(member_list_sends(&d), 1);
+    }
+
+    #[test]
+    fn test_throttle_passes_other_messages() {
+        let members = RwLock::new(HashMap::new());
+        let mut throttle = JoinThrottle::new(Duration::from_secs(5), Duration::from_secs(1));
+        let src: SocketAddr = "10.0.0.5:4001".parse().unwrap();
+        let ping = create_ping("peer", src, "10.0.0.5:4002".parse().unwrap(), 0);
+        let now = Instant::now();
+
+        for _ in 0..3 {
+            let result = throttled(&mut throttle, &ping, src, &members, now);
+            assert!(matches!(result.actions[0], GossipAction::Send { message: GossipMessage::Ack { .. }, .. }));
+        }
+    }
+
+    #[test]
+    fn test_throttle_from_config() {
+        let config = ReplicationConfig::new("seed")
+            .join_dedup_window(Duration::from_secs(2))
+            .member_list_interval(Duration::from_millis(500));
+        let throttle = JoinThrottle::from_config(&config);
+        assert_eq!(throttle.join_window, Duration::from_secs(2));
+        assert_eq!(throttle.reply_interval, Duration::from_millis(500));
+    }
+
     #[test]
     fn test_process_message_member_list() {
         let members = Arc::new(RwLock::new(HashMap::new()));
@@ -1733,6 +2443,93 @@ mod tests {
         assert_eq!(target.unwrap(), alive_addr);
     }
 
+    fn insert_alive_members(members: &RwLock<HashMap<String, Member>>, count: u8) {
+        for i in 1..=count {
+            members.write().insert(format!("peer-{}", i), Member {
+                node_id: NodeId::new(format!("peer-{}", i)),
+                gossip_addr: format!("10.0.0.{}:4001", i).parse().unwrap(),
+                transport_addr: format!("10.0.0.{}:4002", i).parse().unwrap(),
+                state: MemberState::Alive,
+                last_seen: Instant::now(),
+                incarnation: 1,
+            });
+        }
+    }
+
+    #[test]
+    fn test_select_ping_target_with_rng_seeded_sequence() {
+        use rand::SeedableRng;
+
+        let members = Arc::new(RwLock::new(HashMap::new()));
+        insert_alive_members(&members, 3);
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+        let sequence: Vec<String> = (0..8)
+            .map(|_| select_ping_target_with_rng(&members, &mut rng).unwrap().to_string())
+            .collect();
+
+        assert_eq!(
+            sequence,
+            vec![
+                "10.0.0.2:4001", "10.0.0.2:4001", "10.0.0.2:4001", "10.0.0.1:4001",
+                "10.0.0.2:4001", "10.0.0.3:4001", "10.0.0.3:4001", "10.0.0.1:4001",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_select_ping_target_with_rng_reproducible() {
+        use rand::SeedableRng;
+
+        let members = Arc::new(RwLock::new(HashMap::new()));
+        insert_alive_members(&members, 5);
+
+        let mut rng_a = rand::rngs::StdRng::seed_from_u64(7);
+        let mut rng_b = rand::rngs::StdRng::seed_from_u64(7);
+
+        for _ in 0..32 {
+            assert_eq!(
+                select_ping_target_with_rng(&members, &mut rng_a),
+                select_ping_target_with_rng(&members, &mut rng_b)
+            );
+        }
+    }
+
+    #[test]
+    fn test_select_ping_target_with_rng_empty() {
+        use rand::SeedableRng;
+
+        let members = Arc::new(RwLock::new(HashMap::new()));
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+
+        assert!(select_ping_target_with_rng(&members, &mut rng).is_none());
+    }
+
+    #[test]
+    fn test_jittered_intervals_vary_within_bound() {
+        use rand::SeedableRng;
+
+        let base = Duration::from_millis(500);
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+        let intervals: Vec<Duration> = (0..1000)
+            .map(|_| jittered_with_rng(base, 0.2, &mut rng))
+            .collect();
+
+        let (min, max) = (base.mul_f64(0.8), base.mul_f64(1.2));
+        assert!(intervals.iter().all(|d| *d >= min && *d <= max));
+        // Successive ticks differ, and both halves of the range are used
+        assert!(intervals.windows(2).all(|w| w[0] != w[1]));
+        assert!(intervals.iter().any(|d| *d < base.mul_f64(0.9)));
+        assert!(intervals.iter().any(|d| *d > base.mul_f64(1.1)));
+    }
+
+    #[test]
+    fn test_jittered_zero_jitter_is_fixed() {
+        let base = Duration::from_secs(10);
+        assert_eq!(jittered(base, 0.0), base);
+        assert_eq!(jittered_with_rng(base, 0.0, &mut rand::thread_rng()), base);
+    }
+
     #[test]
     fn test_create_ping() {
         let gossip_addr: SocketAddr = "127.0.0.1:4001".parse().unwrap();
@@ -1756,13 +2553,14 @@ mod tests {
         let gossip_addr: SocketAddr = "127.0.0.1:4001".parse().unwrap();
         let transport_addr: SocketAddr = "127.0.0.1:4002".parse().unwrap();
 
-        let msg = create_join("joining-node", gossip_addr, transport_addr);
+        let msg = create_join("joining-node", gossip_addr, transport_addr, 9);
 
         match msg {
-            GossipMessage::Join { node_id, gossip_addr: ga, transport_addr: ta } => {
+            GossipMessage::Join { node_id, gossip_addr: ga, transport_addr: ta, incarnation } => {
                 assert_eq!(node_id, "joining-node");
                 assert_eq!(ga, gossip_addr);
                 assert_eq!(ta, transport_addr);
+                assert_eq!(incarnation, 9);
             }
             _ => panic!("expected Join"),
         }
@@ -1931,6 +2729,7 @@ mod tests {
             node_id: "node-1".to_string(),
             gossip_addr: "127.0.0.1:4001".parse().unwrap(),
             transport_addr: "127.0.0.1:4002".parse().unwrap(),
+            incarnation: 1,
         };
         let bytes = bincode::serialize(&join).unwrap();
         let decoded: GossipMessage = bincode::deserialize(&bytes).unwrap();
@@ -2006,4 +2805,121 @@ mod tests {
         // Suspect members are not marked as dead (only Alive -> Dead transition)
         assert!(actions.is_empty());
     }
+
+    // ===== Incarnation Tests =====
+
+    fn local_member_list(incarnation: u64) -> GossipMessage {
+        GossipMessage::MemberList {
+            members: vec![(
+                "local-node".to_string(),
+                "127.0.0.1:4001".parse().unwrap(),
+                "127.0.0.1:4002".parse().unwrap(),
+                incarnation,
+            )],
+        }
+    }
+
+    fn process_as_local(msg: &GossipMessage, members: &RwLock<HashMap<String, Member>>, incarnation: u64) -> ProcessResult {
+        let addr: SocketAddr = "127.0.0.1:4001".parse().unwrap();
+        process_message(msg, "10.0.0.5:5000".parse().unwrap(), members, "local-node", addr, addr, incarnation)
+    }
+
+    #[test]
+    fn test_incarnation_restart_is_higher() {
+        let temp = tempfile::NamedTempFile::new().unwrap();
+        let path = temp.path().to_str().unwrap();
+
+        let first = Incarnation::load(path, 0).unwrap().current();
+        let second = Incarnation::load(path, 0).unwrap().current();
+        assert_eq!(first, 1);
+        assert!(second > first);
+    }
+
+    #[test]
+    fn test_incarnation_seed_raises_floor() {
+        let temp = tempfile::NamedTempFile::new().unwrap();
+        let path = temp.path().to_str().unwrap();
+
+        assert_eq!(Incarnation::load(path, 10).unwrap().current(), 11);
+        // A lower seed never takes the incarnation backwards
+        assert_eq!(Incarnation::load(path, 3).unwrap().current(), 12);
+    }
+
+    #[test]
+    fn test_incarnation_refute_persists() {
+        let temp = tempfile::NamedTempFile::new().unwrap();
+        let path = temp.path().to_str().unwrap();
+
+        let incarnation = Incarnation::load(path, 0).unwrap();
+        assert_eq!(incarnation.refute(5), 6);
+        assert_eq!(incarnation.current(), 6);
+
+        // The refuted value survives a restart and is bumped past
+        assert_eq!(Incarnation::load(path, 0).unwrap().current(), 7);
+    }
+
+    #[test]
+    fn test_incarnation_refute_ignores_older() {
+        let incarnation = Incarnation::in_memory(8);
+        assert_eq!(incarnation.refute(3), 8);
+        assert_eq!(incarnation.refute(7), 8);
+        assert_eq!(incarnation.refute(8), 9);
+    }
+
+    #[test]
+    fn test_incarnation_load_bad_path() {
+        assert!(Incarnation::load("/nonexistent/dir/state.db", 0).is_err());
+    }
+
+    #[test]
+    fn test_gossip_service_incarnation() {
+        let service = GossipService::new(ReplicationConfig::new("test-node").incarnation_seed(4));
+        assert_eq!(service.incarnation(), 4);
+
+        let service = service.with_incarnation(Incarnation::in_memory(20));
+        assert_eq!(service.incarnation(), 20);
+    }
+
+    #[test]
+    fn test_member_list_with_newer_self_incarnation_refutes() {
+        let members = RwLock::new(HashMap::new());
+
+        let result = process_as_local(&local_member_list(7), &members, 3);
+        assert_eq!(result.actions, vec![GossipAction::Refute(7)]);
+        assert!(members.read().is_empty());
+    }
+
+    #[test]
+    fn test_member_list_with_current_self_incarnation_is_ignored() {
+        let members = RwLock::new(HashMap::new());
+
+        assert!(process_as_local(&local_member_list(3), &members, 3).actions.is_empty());
+        assert!(process_as_local(&local_member_list(2), &members, 3).actions.is_empty());
+    }
+
+    #[test]
+    fn test_member_list_keeps_newer_incarnation() {
+        let members = RwLock::new(HashMap::new());
+        let addr: SocketAddr = "10.0.0.1:4001".parse().unwrap();
+        let entry = |incarnation| GossipMessage::MemberList {
+            members: vec![("peer-1".to_string(), addr, addr, incarnation)],
+        };
+
+        process_as_local(&entry(5), &members, 1);
+        // Stale hearsay from before the peer restarted is dropped
+        process_as_local(&entry(2), &members, 1);
+        assert_eq!(members.read()["peer-1"].incarnation, 5);
+
+        process_as_local(&entry(6), &members, 1);
+        assert_eq!(members.read()["peer-1"].incarnation, 6);
+    }
+
+    #[test]
+    fn test_join_records_incarnation() {
+        let members = RwLock::new(HashMap::new());
+        let addr: SocketAddr = "10.0.0.1:4001".parse().unwrap();
+
+        process_as_local(&create_join("peer-1", addr, addr, 12), &members, 1);
+        assert_eq!(members.read()["peer-1"].incarnation, 12);
+    }
 }
@@ -35,15 +35,26 @@
 //! ```
 
 pub mod config;
+pub mod cluster_tls;
+pub mod conflict;
+pub mod corrosion;
+pub mod events;
 pub mod types;
 pub mod gossip;
+pub mod schema;
 pub mod sync;
 pub mod transport;
 pub mod agent;
+#[cfg(test)]
+pub mod testkit;
 
 pub use config::ReplicationConfig;
-pub use types::{Change, ChangeKind, ChangeSet, NodeId};
+pub use cluster_tls::ClusterTls;
+pub use conflict::{ConflictResolver, LastWriteWins};
+pub use corrosion::{ActorId, ChangeV1, CorrosionError, CrsqlChange, SqliteValue};
+pub use events::{EventChannelStats, OverflowPolicy};
+pub use types::{BackendRow, Change, ChangeKind, ChangeSet, NodeId, Snapshot};
 pub use gossip::{GossipService, Member, MemberState};
-pub use sync::{SyncService, VersionVector};
+pub use sync::{PeerProgress, ReplicationLag, SyncService, VersionVector};
 pub use transport::{TransportService, PeerConnection};
-pub use agent::ReplicationAgent;
+pub use agent::{ReplicationAgent, ReplicationStats};
@@ -0,0 +1,228 @@
+//! Backends Table Schema
+//!
+//! Column definitions for the replicated `backends` table, plus the startup
+//! check that brings databases created by older versions forward.
+
+use rusqlite::Connection;
+use std::collections::HashSet;
+
+/// A column of the `backends` table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Column {
+    pub name: &'static str,
+    /// Type and constraints, as used in `CREATE TABLE` / `ALTER TABLE`
+    pub definition: &'static str,
+    /// SQL literal used for the column when it is missing; `None` marks a
+    /// required column that can't be added to an existing table
+    pub default: Option<&'static str>,
+}
+
+impl Column {
+    const fn required(name: &'static str, definition: &'static str) -> Self {
+        Self { name, definition, default: None }
+    }
+
+    const fn optional(name: &'static str, definition: &'static str, default: &'static str) -> Self {
+        Self { name, definition, default: Some(default) }
+    }
+}
+
+/// Current `backends` columns, in table order.
+pub const BACKENDS_COLUMNS: &[Column] = &[
+    Column::required("id", "TEXT PRIMARY KEY"),
+    Column::required("app", "TEXT NOT NULL"),
+    Column::required("region", "TEXT NOT NULL"),
+    Column::optional("country", "TEXT", "NULL"),
+    Column::required("wg_ip", "TEXT NOT NULL"),
+    Column::required("port", "INTEGER NOT NULL"),
+    Column::optional("healthy", "INTEGER DEFAULT 1", "1"),
+    Column::optional("weight", "INTEGER DEFAULT 2", "2"),
+    Column::optional("soft_limit", "INTEGER DEFAULT 100", "100"),
+    Column::optional("hard_limit", "INTEGER DEFAULT 150", "150"),
+    Column::optional("deleted", "INTEGER DEFAULT 0", "0"),
+    Column::optional("draining", "INTEGER DEFAULT 0", "0"),
+    Column::optional("maintenance", "INTEGER DEFAULT 0", "0"),
+    Column::optional("priority", "INTEGER DEFAULT 0", "0"),
+];
+
+/// Errors checking the `backends` schema.
+#[derive(Debug, thiserror::Error)]
+pub enum SchemaError {
+    #[error("backends table is missing required column `{0}`; it can't be added in place, recreate the table or migrate it by hand")]
+    MissingColumn(&'static str),
+    #[error(transparent)]
+    Sqlite(#[from] rusqlite::Error),
+}
+
+/// `CREATE TABLE IF NOT EXISTS` statement for the current `backends` schema.
+pub fn create_backends_sql() -> String {
+    let columns: Vec<String> = BACKENDS_COLUMNS
+        .iter()
+        .map(|c| format!("{} {}", c.name, c.definition))
+        .collect();
+    format!("CREATE TABLE IF NOT EXISTS backends ({})", columns.join(", "))
+}
+
+/// Names of the columns `table` has (empty if the table doesn't exist).
+pub fn table_columns(conn: &Connection, table: &str) -> rusqlite::Result<HashSet<String>> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let names = stmt.query_map([], |row| row.get::<_, String>(1))?;
+    names.collect()
+}
+
+/// Create the `backends` table or add the columns an older schema lacks.
+///
+/// Returns the names of the columns that were added. Fails without
+/// changing anything if a required column is missing.
+pub fn migrate_backends(conn: &Connection) -> Result<Vec<&'static str>, SchemaError> {
+    let present = table_columns(conn, "backends")?;
+    if present.is_empty() {
+        conn.execute(&create_backends_sql(), [])?;
+        return Ok(Vec::new());
+    }
+
+    let missing: Vec<&Column> = BACKENDS_COLUMNS
+        .iter()
+        .filter(|c| !present.contains(c.name))
+        .collect();
+    if let Some(required) = missing.iter().find(|c| c.default.is_none()) {
+        return Err(SchemaError::MissingColumn(required.name));
+    }
+
+    for column in &missing {
+        conn.execute(
+            &format!("ALTER TABLE backends ADD COLUMN {} {}", column.name, column.definition),
+            [],
+        )?;
+    }
+    Ok(missing.iter().map(|c| c.name).collect())
+}
+
+/// Select expressions for `names`, substituting defaults for missing columns.
+///
+/// Lets read-only consumers load a database with an older schema without
+/// altering it. Fails if a required column is missing.
+pub fn backends_projection(conn: &Connection, names: &[&str]) -> Result<Vec<String>, SchemaError> {
+    let present = table_columns(conn, "backends")?;
+    names
+        .iter()
+        .map(|name| {
+            let column = BACKENDS_COLUMNS
+                .iter()
+                .find(|c| c.name == *name)
+                .expect("unknown backends column");
+            match column.default {
+                _ if present.contains(column.name) => Ok(column.name.to_string()),
+                Some(default) => Ok(format!("{} AS {}", default, column.name)),
+                None => Err(SchemaError::MissingColumn(column.name)),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+
+    /// Schema from before weights, soft deletes and draining existed.
+    const OLD_SCHEMA: &str = "CREATE TABLE backends (
+        id TEXT PRIMARY KEY,
+        app TEXT NOT NULL,
+        region TEXT NOT NULL,
+        country TEXT,
+        wg_ip TEXT NOT NULL,
+        port INTEGER NOT NULL,
+        healthy INTEGER DEFAULT 1
+    )";
+
+    fn old_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute(OLD_SCHEMA, []).unwrap();
+        conn.execute(
+            "INSERT INTO backends (id, app, region, country, wg_ip, port, healthy)
+             VALUES ('b1', 'myapp', 'eu', 'DE', '10.50.1.1', 8080, 1)",
+            [],
+        )
+        .unwrap();
+        conn
+    }
+
+    fn all_names() -> HashSet<String> {
+        BACKENDS_COLUMNS.iter().map(|c| c.name.to_string()).collect()
+    }
+
+    #[test]
+    fn test_create_on_empty_db() {
+        let conn = Connection::open_in_memory().unwrap();
+        assert!(migrate_backends(&conn).unwrap().is_empty());
+        assert_eq!(table_columns(&conn, "backends").unwrap(), all_names());
+    }
+
+    #[test]
+    fn test_current_schema_is_unchanged() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute(&create_backends_sql(), []).unwrap();
+        assert!(migrate_backends(&conn).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_old_schema_migrated_forward() {
+        let conn = old_db();
+
+        let added = migrate_backends(&conn).unwrap();
+        assert_eq!(
+            added,
+            ["weight", "soft_limit", "hard_limit", "deleted", "draining", "maintenance", "priority"]
+        );
+        assert_eq!(table_columns(&conn, "backends").unwrap(), all_names());
+
+        // Existing rows pick up the column defaults
+        let row: (i64, i64, i64, i64, i64, i64, i64) = conn
+            .query_row(
+                "SELECT weight, soft_limit, hard_limit, deleted, draining, maintenance, priority
+                 FROM backends WHERE id = 'b1'",
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?, r.get(5)?, r.get(6)?)),
+            )
+            .unwrap();
+        assert_eq!(row, (2, 100, 150, 0, 0, 0, 0));
+
+        // Running again is a no-op
+        assert!(migrate_backends(&conn).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_missing_required_column_is_an_error() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute("CREATE TABLE backends (id TEXT PRIMARY KEY, app TEXT NOT NULL, port INTEGER)", [])
+            .unwrap();
+
+        let err = migrate_backends(&conn).unwrap_err();
+        assert!(matches!(err, SchemaError::MissingColumn("region")));
+        assert!(err.to_string().contains("missing required column `region`"));
+        // Nothing was added
+        assert_eq!(table_columns(&conn, "backends").unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_projection_substitutes_defaults() {
+        let conn = old_db();
+        let projection = backends_projection(&conn, &["id", "healthy", "weight", "draining"]).unwrap();
+        assert_eq!(projection, ["id", "healthy", "2 AS weight", "0 AS draining"]);
+
+        let sql = format!("SELECT {} FROM backends", projection.join(", "));
+        let weight: i64 = conn.query_row(&sql, [], |r| r.get(2)).unwrap();
+        assert_eq!(weight, 2);
+    }
+
+    #[test]
+    fn test_projection_missing_required_column() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute("CREATE TABLE backends (id TEXT PRIMARY KEY)", []).unwrap();
+        assert!(matches!(
+            backends_projection(&conn, &["id", "wg_ip"]),
+            Err(SchemaError::MissingColumn("wg_ip"))
+        ));
+    }
+}
@@ -3,13 +3,24 @@
 //! Handles change detection, storage, and application using Last-Write-Wins (LWW)
 //! semantics for conflict resolution.
 
-use crate::replication::types::{Change, ChangeKind, ChangeSet, HLCTimestamp, NodeId};
+use crate::domain::ports::MetricsStore;
+use crate::replication::conflict::{ConflictResolver, LastWriteWins};
+use crate::replication::corrosion::ChangeV1;
+use crate::replication::events::{event_channel, EventChannelStats, EventSender, OverflowPolicy};
+use crate::replication::types::{
+    wall_clock_micros, BackendRow, Change, ChangeKind, ChangeSet, HLCTimestamp, NodeId, Snapshot,
+};
+use crate::replication::schema;
 use parking_lot::RwLock;
-use rusqlite::{Connection, params};
+use rusqlite::{Connection, OptionalExtension, params};
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicU64, Ordering};
-use tokio::sync::mpsc;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc, watch};
+
+/// Default maximum amount a remote HLC wall time may lead the local clock.
+pub const DEFAULT_MAX_CLOCK_SKEW: Duration = Duration::from_secs(60);
 
 /// Version vector for tracking per-node sequence numbers.
 #[derive(Debug, Clone, Default)]
@@ -36,6 +47,16 @@ impl VersionVector {
         }
     }
 
+    /// Number of nodes tracked.
+    pub fn len(&self) -> usize {
+        self.versions.len()
+    }
+
+    /// Whether no node has been tracked yet.
+    pub fn is_empty(&self) -> bool {
+        self.versions.is_empty()
+    }
+
     /// Check if we've seen this sequence.
     pub fn has_seen(&self, node_id: &str, seq: u64) -> bool {
         self.get(node_id) >= seq
@@ -49,6 +70,83 @@ impl VersionVector {
     }
 }
 
+/// How far replication with one peer is behind, in changesets.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReplicationLag {
+    /// Changesets the peer announced that haven't been applied locally
+    pub inbound: u64,
+    /// Local changesets the peer hasn't acked yet
+    pub outbound: u64,
+}
+
+/// Latest sequence numbers observed from each peer.
+///
+/// Compared against the local version vector to compute replication lag.
+#[derive(Debug, Clone, Default)]
+pub struct PeerProgress {
+    /// Map of peer -> highest sequence it has broadcast
+    announced: HashMap<String, u64>,
+    /// Map of peer -> highest local sequence it has acked
+    acked: HashMap<String, u64>,
+}
+
+impl PeerProgress {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a changeset broadcast by a peer.
+    pub fn observe_broadcast(&mut self, peer: &str, seq: u64) {
+        let current = self.announced.entry(peer.to_string()).or_insert(0);
+        *current = (*current).max(seq);
+    }
+
+    /// Record a peer acking one of our changesets.
+    pub fn observe_ack(&mut self, peer: &str, seq: u64) {
+        let current = self.acked.entry(peer.to_string()).or_insert(0);
+        *current = (*current).max(seq);
+    }
+
+    /// Lag of one peer against the local version vector.
+    ///
+    /// Outbound lag is only known once the peer has acked something; until
+    /// then it is reported as zero.
+    pub fn lag(&self, peer: &str, local: &VersionVector, local_node: &str) -> ReplicationLag {
+        let inbound = self
+            .announced
+            .get(peer)
+            .map_or(0, |seq| seq.saturating_sub(local.get(peer)));
+        let outbound = self
+            .acked
+            .get(peer)
+            .map_or(0, |seq| local.get(local_node).saturating_sub(*seq));
+        ReplicationLag { inbound, outbound }
+    }
+
+    /// Lag of every observed peer, sorted by peer.
+    pub fn lags(&self, local: &VersionVector, local_node: &str) -> Vec<(String, ReplicationLag)> {
+        let mut peers: Vec<&String> = self.announced.keys().chain(self.acked.keys()).collect();
+        peers.sort();
+        peers.dedup();
+        peers
+            .into_iter()
+            .map(|peer| (peer.clone(), self.lag(peer, local, local_node)))
+            .collect()
+    }
+
+    /// Highest local sequence acked by every observed peer.
+    ///
+    /// Peers that announced changesets but never acked count as 0. Returns
+    /// `None` when no peer has been observed.
+    pub fn ack_watermark(&self) -> Option<u64> {
+        self.announced
+            .keys()
+            .chain(self.acked.keys())
+            .map(|peer| self.acked.get(peer).copied().unwrap_or(0))
+            .min()
+    }
+}
+
 /// Events emitted by the sync service.
 #[derive(Debug, Clone)]
 pub enum SyncEvent {
@@ -58,6 +156,10 @@ pub enum SyncEvent {
     BroadcastReady(ChangeSet),
     /// Sync completed with a peer
     PeerSynced { node_id: NodeId, changes_applied: usize },
+    /// A change could not be applied and was skipped
+    ChangeFailed { change: Change, error: String },
+    /// Conflict resolution kept the local row over a remote change
+    ChangeRejected { change: Change, last_applied: Option<HLCTimestamp> },
 }
 
 /// Sync service for change management.
@@ -66,34 +168,127 @@ pub struct SyncService {
     db_path: String,
     sequence: Arc<AtomicU64>,
     version_vector: Arc<RwLock<VersionVector>>,
+    peer_progress: Arc<RwLock<PeerProgress>>,
     pending_changes: Arc<RwLock<Vec<Change>>>,
     last_timestamps: Arc<RwLock<HashMap<String, HLCTimestamp>>>,
-    event_tx: mpsc::Sender<SyncEvent>,
+    /// Per-table conflict resolvers; other tables use `default_resolver`
+    resolvers: HashMap<String, Arc<dyn ConflictResolver>>,
+    default_resolver: Arc<dyn ConflictResolver>,
+    clock: Arc<RwLock<HLCTimestamp>>,
+    max_clock_skew: Duration,
+    event_tx: EventSender<SyncEvent>,
     event_rx: Option<mpsc::Receiver<SyncEvent>>,
+    /// Bumped whenever the backends table changes
+    backends_changed: watch::Sender<u64>,
+    /// Where rejected changes are counted, if anywhere
+    metrics: RwLock<Option<Arc<dyn MetricsStore>>>,
+    /// Local changesets in Corrosion's format, for mixed clusters
+    corrosion_tx: broadcast::Sender<ChangeV1>,
 }
 
 impl SyncService {
     /// Create a new sync service.
     pub fn new(node_id: NodeId, db_path: String) -> Self {
-        let (event_tx, event_rx) = mpsc::channel(1024);
+        let (event_tx, event_rx) = event_channel(1024, OverflowPolicy::default());
 
         Self {
             node_id,
             db_path,
             sequence: Arc::new(AtomicU64::new(0)),
             version_vector: Arc::new(RwLock::new(VersionVector::new())),
+            peer_progress: Arc::new(RwLock::new(PeerProgress::new())),
             pending_changes: Arc::new(RwLock::new(Vec::new())),
             last_timestamps: Arc::new(RwLock::new(HashMap::new())),
+            resolvers: HashMap::new(),
+            default_resolver: Arc::new(LastWriteWins),
+            clock: Arc::new(RwLock::new(HLCTimestamp::default())),
+            max_clock_skew: DEFAULT_MAX_CLOCK_SKEW,
             event_tx,
             event_rx: Some(event_rx),
+            backends_changed: watch::channel(0).0,
+            metrics: RwLock::new(None),
+            corrosion_tx: broadcast::channel(1024).0,
         }
     }
 
+    /// Count changes rejected by conflict resolution in `metrics`.
+    pub fn set_metrics(&self, metrics: Arc<dyn MetricsStore>) {
+        *self.metrics.write() = Some(metrics);
+    }
+
+    /// Set the maximum clock skew tolerated for remote changes.
+    pub fn with_max_clock_skew(mut self, max_clock_skew: Duration) -> Self {
+        self.max_clock_skew = max_clock_skew;
+        self
+    }
+
+    /// Resolve conflicts on `table` with `resolver` instead of Last-Write-Wins.
+    pub fn with_conflict_resolver(
+        mut self,
+        table: impl Into<String>,
+        resolver: Arc<dyn ConflictResolver>,
+    ) -> Self {
+        self.resolvers.insert(table.into(), resolver);
+        self
+    }
+
+    /// Replace the event channel with one of the given capacity and overflow policy.
+    pub fn with_event_channel(mut self, capacity: usize, overflow: OverflowPolicy) -> Self {
+        let (event_tx, event_rx) = event_channel(capacity, overflow);
+        self.event_tx = event_tx;
+        self.event_rx = Some(event_rx);
+        self
+    }
+
+    /// Counters for the event channel (sent, dropped, lagged).
+    pub fn event_stats(&self) -> &EventChannelStats {
+        self.event_tx.stats()
+    }
+
+    /// Get the current value of the local hybrid logical clock.
+    pub fn clock(&self) -> HLCTimestamp {
+        *self.clock.read()
+    }
+
+    /// Advance the local HLC for a local event, or for a received remote timestamp.
+    ///
+    /// The result is always greater than both the previous local value and
+    /// `remote`, so the counter disambiguates events at equal wall times.
+    fn advance_clock(&self, remote: Option<&HLCTimestamp>) -> HLCTimestamp {
+        let mut clock = self.clock.write();
+        *clock = clock.tick(remote, &self.node_id);
+        *clock
+    }
+
     /// Get the event receiver.
     pub fn take_event_rx(&mut self) -> Option<mpsc::Receiver<SyncEvent>> {
         self.event_rx.take()
     }
 
+    /// Subscribe to backend table changes.
+    ///
+    /// The receiver is signalled each time applied changes touch the
+    /// backends table, so readers of the table can reload right away
+    /// instead of waiting for their next poll.
+    pub fn subscribe_backend_changes(&self) -> watch::Receiver<u64> {
+        self.backends_changed.subscribe()
+    }
+
+    /// Subscribe to local changesets in Corrosion's change format.
+    ///
+    /// Each flushed changeset is sent as a [`ChangeV1`], so a bridge can
+    /// feed changes made on this node to Corrosion nodes in a mixed cluster.
+    /// Changes applied from peers are not re-exported. Slow receivers lag
+    /// and skip changesets rather than holding up flushes.
+    pub fn subscribe_corrosion(&self) -> broadcast::Receiver<ChangeV1> {
+        self.corrosion_tx.subscribe()
+    }
+
+    /// Signal backend change subscribers.
+    fn notify_backends_changed(&self) {
+        self.backends_changed.send_modify(|generation| *generation += 1);
+    }
+
     /// Get current sequence number.
     pub fn sequence(&self) -> u64 {
         self.sequence.load(Ordering::SeqCst)
@@ -104,6 +299,31 @@ impl SyncService {
         self.version_vector.read().clone()
     }
 
+    /// Number of recorded changes waiting for the next flush.
+    pub fn pending_count(&self) -> usize {
+        self.pending_changes.read().len()
+    }
+
+    /// Record a changeset broadcast by a peer, before it is applied.
+    pub fn observe_broadcast(&self, source: &NodeId, seq: u64) {
+        self.peer_progress.write().observe_broadcast(&source.0, seq);
+    }
+
+    /// Record a peer acking one of our changesets.
+    ///
+    /// Acks for other sources' changesets are ignored.
+    pub fn observe_ack(&self, from: &NodeId, source: &NodeId, seq: u64) {
+        if *source == self.node_id {
+            self.peer_progress.write().observe_ack(&from.0, seq);
+        }
+    }
+
+    /// Replication lag of every peer heard from, sorted by peer.
+    pub fn replication_lag(&self) -> Vec<(String, ReplicationLag)> {
+        let vv = self.version_vector.read();
+        self.peer_progress.read().lags(&vv, &self.node_id.0)
+    }
+
     /// Initialize the database schema.
     pub fn init_db(&self) -> anyhow::Result<()> {
         let conn = Connection::open(&self.db_path)?;
@@ -121,11 +341,24 @@ impl SyncService {
                 timestamp_counter INTEGER NOT NULL,
                 timestamp_node INTEGER NOT NULL,
                 origin_node TEXT NOT NULL,
-                applied_at INTEGER NOT NULL
+                applied_at INTEGER NOT NULL,
+                seq INTEGER NOT NULL DEFAULT 0
             )",
             [],
         )?;
 
+        // Logs created before compaction existed lack the changeset sequence
+        if !schema::table_columns(&conn, "__replication_log")?.contains("seq") {
+            conn.execute(
+                "ALTER TABLE __replication_log ADD COLUMN seq INTEGER NOT NULL DEFAULT 0",
+                [],
+            )?;
+        }
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS __replication_log_applied_at ON __replication_log (applied_at)",
+            [],
+        )?;
+
         // Create version vector table
         conn.execute(
             "CREATE TABLE IF NOT EXISTS __replication_versions (
@@ -146,23 +379,11 @@ impl SyncService {
             [],
         )?;
 
-        // Create backends table if not exists
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS backends (
-                id TEXT PRIMARY KEY,
-                app TEXT NOT NULL,
-                region TEXT NOT NULL,
-                country TEXT,
-                wg_ip TEXT NOT NULL,
-                port INTEGER NOT NULL,
-                healthy INTEGER DEFAULT 1,
-                weight INTEGER DEFAULT 2,
-                soft_limit INTEGER DEFAULT 100,
-                hard_limit INTEGER DEFAULT 150,
-                deleted INTEGER DEFAULT 0
-            )",
-            [],
-        )?;
+        // Create the backends table, or bring an older schema forward
+        let added = schema::migrate_backends(&conn)?;
+        if !added.is_empty() {
+            tracing::info!("migrated backends table, added columns: {}", added.join(", "));
+        }
 
         // Load version vector from database
         let mut stmt = conn.prepare("SELECT node_id, sequence FROM __replication_versions")?;
@@ -187,12 +408,89 @@ impl SyncService {
 
     /// Record a local change.
     pub fn record_change(&self, table: &str, pk: &str, kind: ChangeKind, data: &str) -> Change {
-        let change = Change::new(table, pk, kind, data, &self.node_id);
+        let timestamp = self.advance_clock(None);
+        let change = Change::new(table, pk, kind, data, &self.node_id).with_timestamp(timestamp);
         self.pending_changes.write().push(change.clone());
         change
     }
 
+    /// Record a batch of local changes, e.g. a bulk backend import.
+    ///
+    /// The batch is appended to the pending changes in one step, so a
+    /// concurrent flush never splits it and the whole batch replicates in
+    /// the same changeset.
+    pub fn record_changes(&self, changes: Vec<(&str, &str, ChangeKind, &str)>) -> Vec<Change> {
+        let changes: Vec<Change> = changes
+            .into_iter()
+            .map(|(table, pk, kind, data)| {
+                let timestamp = self.advance_clock(None);
+                Change::new(table, pk, kind, data, &self.node_id).with_timestamp(timestamp)
+            })
+            .collect();
+        self.pending_changes.write().extend(changes.iter().cloned());
+        changes
+    }
+
+    /// Mark a backend as draining (or not) and replicate it.
+    ///
+    /// The flag is applied to the local backends table and recorded as an
+    /// Update carrying the full row, so peers converge on the same row
+    /// under LWW and their load balancers stop selecting the backend.
+    pub fn set_backend_draining(&self, backend_id: &str, draining: bool) -> anyhow::Result<Change> {
+        self.set_backend_flag(backend_id, "draining", draining)
+    }
+
+    /// Put a backend into maintenance (or take it out) and replicate it.
+    ///
+    /// Like [`SyncService::set_backend_draining`], but the backend is
+    /// reported as under maintenance rather than failed, and health checks
+    /// leave it alone until it comes back.
+    pub fn set_backend_maintenance(&self, backend_id: &str, maintenance: bool) -> anyhow::Result<Change> {
+        self.set_backend_flag(backend_id, "maintenance", maintenance)
+    }
+
+    /// Set one boolean column of a backend row and replicate the full row.
+    fn set_backend_flag(&self, backend_id: &str, flag: &str, value: bool) -> anyhow::Result<Change> {
+        let conn = Connection::open(&self.db_path)?;
+
+        let mut data = conn.query_row(
+            "SELECT app, region, country, wg_ip, port, healthy, weight, soft_limit, hard_limit,
+                    draining, maintenance, priority
+             FROM backends WHERE id = ? AND (deleted IS NULL OR deleted = 0)",
+            [backend_id],
+            |row| {
+                Ok(serde_json::json!({
+                    "app": row.get::<_, String>(0)?,
+                    "region": row.get::<_, String>(1)?,
+                    "country": row.get::<_, Option<String>>(2)?,
+                    "wg_ip": row.get::<_, String>(3)?,
+                    "port": row.get::<_, i64>(4)?,
+                    "healthy": row.get::<_, i64>(5)?,
+                    "weight": row.get::<_, i64>(6)?,
+                    "soft_limit": row.get::<_, i64>(7)?,
+                    "hard_limit": row.get::<_, i64>(8)?,
+                    "draining": row.get::<_, Option<i64>>(9)?.unwrap_or(0) != 0,
+                    "maintenance": row.get::<_, Option<i64>>(10)?.unwrap_or(0) != 0,
+                    "priority": row.get::<_, Option<i64>>(11)?.unwrap_or(0),
+                }))
+            },
+        )?;
+        data[flag] = serde_json::Value::from(value);
+
+        let change = self.record_change("backends", backend_id, ChangeKind::Update, &data.to_string());
+        // Logged under the changeset the next flush will assign
+        self.apply_single_change(&conn, &change, self.sequence() + 1)?;
+        self.remember_applied(&change_key(&change), change.timestamp);
+        self.notify_backends_changed();
+
+        tracing::info!("backend {} {}={}", backend_id, flag, value);
+        Ok(change)
+    }
+
     /// Flush pending changes as a changeset.
+    ///
+    /// Everything recorded since the last flush goes out in a single
+    /// changeset with one checksum over all of its changes.
     pub async fn flush(&self) -> Option<ChangeSet> {
         // Collect changes without holding lock across await
         let changes: Vec<Change> = {
@@ -216,8 +514,12 @@ impl SyncService {
             tracing::error!("failed to persist version: {:?}", e);
         }
 
+        if self.corrosion_tx.receiver_count() > 0 {
+            let _ = self.corrosion_tx.send(ChangeV1::from_changeset(&changeset));
+        }
+
         // Now we can await safely - no locks held
-        let _ = self.event_tx.send(SyncEvent::BroadcastReady(changeset.clone())).await;
+        self.event_tx.send(SyncEvent::BroadcastReady(changeset.clone())).await;
 
         Some(changeset)
     }
@@ -240,14 +542,97 @@ impl SyncService {
         }
 
         let mut applied = 0;
+        let mut backends_applied = 0;
         let conn = Connection::open(&self.db_path)?;
+        let now = wall_clock_micros();
 
         for change in &changeset.changes {
-            if self.should_apply_change(&conn, change)? {
-                self.apply_single_change(&conn, change)?;
-                applied += 1;
+            // A replayed change was fully applied already; doing it again
+            // would rewrite the row and report it a second time
+            if self.is_already_applied(&conn, change)? {
+                tracing::debug!(
+                    "skipping already-applied change {} ({}:{}) from {}",
+                    change.id,
+                    change.table,
+                    change.pk,
+                    change.origin
+                );
+                continue;
+            }
 
-                let _ = self.event_tx.send(SyncEvent::ChangeApplied(change.clone())).await;
+            // A far-future wall time would win every later conflict for this key
+            if change.timestamp.exceeds_skew(now, self.max_clock_skew) {
+                tracing::warn!(
+                    "rejecting change {}:{} from {}: timestamp {}us ahead of local clock (max skew {:?})",
+                    change.table,
+                    change.pk,
+                    change.origin,
+                    change.timestamp.wall_time - now,
+                    self.max_clock_skew
+                );
+                continue;
+            }
+
+            self.advance_clock(Some(&change.timestamp));
+
+            let last_applied = self.last_applied(&conn, change)?;
+            if self.should_apply_change(&conn, change, last_applied.as_ref())? {
+                // Each change gets its own transaction so a bad one leaves no LWW trace
+                let outcome = conn
+                    .unchecked_transaction()
+                    .map_err(anyhow::Error::from)
+                    .and_then(|tx| {
+                        self.apply_single_change(&tx, change, changeset.seq)?;
+                        Ok(tx.commit()?)
+                    });
+                match outcome {
+                    Ok(()) => {
+                        self.remember_applied(&change_key(change), change.timestamp);
+                        applied += 1;
+                        if change.table == "backends" {
+                            backends_applied += 1;
+                        }
+
+                        self.event_tx.send(SyncEvent::ChangeApplied(change.clone())).await;
+                    }
+                    // The database itself is unusable; leave the changeset unseen so it is resent
+                    Err(e) if is_storage_failure(&e) => return Err(e),
+                    Err(e) => {
+                        tracing::warn!(
+                            "failed to apply change {}:{} from {}: {}",
+                            change.table,
+                            change.pk,
+                            change.origin,
+                            e
+                        );
+                        self.event_tx
+                            .send(SyncEvent::ChangeFailed {
+                                change: change.clone(),
+                                error: e.to_string(),
+                            })
+                            .await;
+                    }
+                }
+            } else if last_applied != Some(change.timestamp) {
+                // A redelivered change isn't a conflict; anything else means
+                // the peer's version lost, usually to an older timestamp
+                tracing::debug!(
+                    "rejected change {}:{} from {} at {:?}, last applied {:?}",
+                    change.table,
+                    change.pk,
+                    change.origin,
+                    change.timestamp,
+                    last_applied
+                );
+                if let Some(metrics) = self.metrics.read().as_ref() {
+                    metrics.record_lww_rejected(&change.table);
+                }
+                self.event_tx
+                    .send(SyncEvent::ChangeRejected {
+                        change: change.clone(),
+                        last_applied,
+                    })
+                    .await;
             }
         }
 
@@ -255,6 +640,10 @@ impl SyncService {
         self.version_vector.write().update(&changeset.source.0, changeset.seq);
         self.persist_version(&changeset.source.0, changeset.seq)?;
 
+        if backends_applied > 0 {
+            self.notify_backends_changed();
+        }
+
         if applied > 0 {
             tracing::info!(
                 "applied {} changes from {} (seq={})",
@@ -267,15 +656,57 @@ impl SyncService {
         Ok(applied)
     }
 
-    /// Check if a change should be applied (LWW check).
-    fn should_apply_change(&self, conn: &Connection, change: &Change) -> anyhow::Result<bool> {
-        let key = format!("{}:{}", change.table, change.pk);
+    /// Whether a change with this id is already in the replication log.
+    ///
+    /// Log rows removed by compaction are forgotten; a change replayed after
+    /// that is left to the conflict resolver.
+    fn is_already_applied(&self, conn: &Connection, change: &Change) -> anyhow::Result<bool> {
+        let found = conn
+            .query_row(
+                "SELECT 1 FROM __replication_log WHERE change_id = ?",
+                [change.id as i64],
+                |_| Ok(()),
+            )
+            .optional()?;
+        Ok(found.is_some())
+    }
+
+    /// Check if a change should be applied, using the resolver for its table.
+    fn should_apply_change(
+        &self,
+        conn: &Connection,
+        change: &Change,
+        last_applied: Option<&HLCTimestamp>,
+    ) -> anyhow::Result<bool> {
+        let resolver = self
+            .resolvers
+            .get(&change.table)
+            .unwrap_or(&self.default_resolver);
+        resolver.should_apply(conn, change, last_applied)
+    }
+
+    /// Timestamp of the newest change applied to the row `change` targets.
+    fn last_applied(&self, conn: &Connection, change: &Change) -> anyhow::Result<Option<HLCTimestamp>> {
+        self.last_applied_key(conn, &change_key(change))
+    }
+
+    /// Cache `timestamp` as applied to the row `key`, once it is committed.
+    ///
+    /// Every path that writes a row's LWW entry must call this, or
+    /// [`SyncService::last_applied_key`] keeps answering an older timestamp.
+    fn remember_applied(&self, key: &str, timestamp: HLCTimestamp) {
+        self.last_timestamps
+            .write()
+            .entry(key.to_string())
+            .and_modify(|last| *last = (*last).max(timestamp))
+            .or_insert(timestamp);
+    }
 
+    /// Timestamp of the newest change applied to the row `table:pk`.
+    fn last_applied_key(&self, conn: &Connection, key: &str) -> anyhow::Result<Option<HLCTimestamp>> {
         // Check in-memory cache first
-        if let Some(last_ts) = self.last_timestamps.read().get(&key) {
-            if change.timestamp <= *last_ts {
-                return Ok(false);
-            }
+        if let Some(last_ts) = self.last_timestamps.read().get(key) {
+            return Ok(Some(*last_ts));
         }
 
         // Check database
@@ -284,36 +715,24 @@ impl SyncService {
              FROM __replication_lww WHERE table_pk = ?"
         )?;
 
-        let result: Option<HLCTimestamp> = stmt.query_row([&key], |row| {
+        Ok(stmt.query_row([key], |row| {
             Ok(HLCTimestamp {
                 wall_time: row.get(0)?,
                 counter: row.get(1)?,
                 node_hash: row.get(2)?,
             })
-        }).ok();
-
-        match result {
-            Some(existing_ts) => Ok(change.timestamp > existing_ts),
-            None => Ok(true), // No existing record, apply the change
-        }
+        }).ok())
     }
 
     /// Apply a single change to the database.
-    fn apply_single_change(&self, conn: &Connection, change: &Change) -> anyhow::Result<()> {
-        let key = format!("{}:{}", change.table, change.pk);
-
-        // Update LWW timestamp
-        conn.execute(
-            "INSERT OR REPLACE INTO __replication_lww
-             (table_pk, timestamp_wall, timestamp_counter, timestamp_node)
-             VALUES (?, ?, ?, ?)",
-            params![
-                key,
-                change.timestamp.wall_time as i64,
-                change.timestamp.counter as i64,
-                change.timestamp.node_hash as i64
-            ],
-        )?;
+    ///
+    /// `seq` is the sequence of the changeset carrying the change, recorded
+    /// in the log for compaction. The in-memory LWW cache is left to the
+    /// caller, which updates it only once the change has been committed.
+    fn apply_single_change(&self, conn: &Connection, change: &Change, seq: u64) -> anyhow::Result<()> {
+        // Update LWW timestamp; it only moves forward, even when a custom
+        // resolver applies an older change
+        record_lww(conn, &change_key(change), &change.timestamp)?;
 
         // Apply the actual change based on table
         match change.table.as_str() {
@@ -323,14 +742,11 @@ impl SyncService {
             }
         }
 
-        // Update in-memory cache
-        self.last_timestamps.write().insert(key, change.timestamp);
-
         // Log the change
         conn.execute(
             "INSERT OR IGNORE INTO __replication_log
-             (change_id, table_name, pk, kind, data, timestamp_wall, timestamp_counter, timestamp_node, origin_node, applied_at)
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+             (change_id, table_name, pk, kind, data, timestamp_wall, timestamp_counter, timestamp_node, origin_node, applied_at, seq)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
             params![
                 change.id as i64,
                 change.table,
@@ -344,7 +760,8 @@ impl SyncService {
                 std::time::SystemTime::now()
                     .duration_since(std::time::UNIX_EPOCH)
                     .unwrap()
-                    .as_secs() as i64
+                    .as_secs() as i64,
+                seq as i64
             ],
         )?;
 
@@ -358,10 +775,17 @@ impl SyncService {
                 // Parse the JSON data
                 let data: serde_json::Value = serde_json::from_str(&change.data)?;
 
+                // Accept flags as a JSON bool or 0/1
+                let flag = |name: &str| {
+                    data.get(name)
+                        .and_then(|v| v.as_bool().map(i64::from).or_else(|| v.as_i64()))
+                        .unwrap_or(0)
+                };
+
                 conn.execute(
                     "INSERT OR REPLACE INTO backends
-                     (id, app, region, country, wg_ip, port, healthy, weight, soft_limit, hard_limit, deleted)
-                     VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                     (id, app, region, country, wg_ip, port, healthy, weight, soft_limit, hard_limit, deleted, draining, maintenance, priority)
+                     VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
                     params![
                         change.pk,
                         data.get("app").and_then(|v| v.as_str()).unwrap_or(""),
@@ -373,7 +797,10 @@ impl SyncService {
                         data.get("weight").and_then(|v| v.as_i64()).unwrap_or(2),
                         data.get("soft_limit").and_then(|v| v.as_i64()).unwrap_or(100),
                         data.get("hard_limit").and_then(|v| v.as_i64()).unwrap_or(150),
-                        0
+                        0,
+                        flag("draining"),
+                        flag("maintenance"),
+                        data.get("priority").and_then(|v| v.as_i64()).unwrap_or(0)
                     ],
                 )?;
             }
@@ -388,6 +815,164 @@ impl SyncService {
         Ok(())
     }
 
+    /// Capture the backends table and version vector for a joining node.
+    ///
+    /// Everything is read in one transaction, so the rows are consistent
+    /// with each other. The version vector is persisted after the changes
+    /// it covers and may trail the rows; changesets redelivered on top of
+    /// the snapshot are then settled by LWW.
+    pub fn snapshot(&self) -> anyhow::Result<Snapshot> {
+        let mut conn = Connection::open(&self.db_path)?;
+        let tx = conn.transaction()?;
+
+        let backends = tx
+            .prepare(
+                "SELECT id, app, region, country, wg_ip, port, healthy, weight, soft_limit,
+                        hard_limit, deleted, draining, maintenance, priority
+                 FROM backends ORDER BY id",
+            )?
+            .query_map([], |row| {
+                Ok(BackendRow {
+                    id: row.get(0)?,
+                    app: row.get(1)?,
+                    region: row.get(2)?,
+                    country: row.get(3)?,
+                    wg_ip: row.get(4)?,
+                    port: row.get(5)?,
+                    healthy: row.get(6)?,
+                    weight: row.get(7)?,
+                    soft_limit: row.get(8)?,
+                    hard_limit: row.get(9)?,
+                    deleted: row.get(10)?,
+                    draining: row.get(11)?,
+                    maintenance: row.get(12)?,
+                    priority: row.get(13)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let timestamps = tx
+            .prepare(
+                "SELECT substr(table_pk, 10), timestamp_wall, timestamp_counter, timestamp_node
+                 FROM __replication_lww WHERE table_pk LIKE 'backends:%' ORDER BY table_pk",
+            )?
+            .query_map([], |row| {
+                let timestamp = HLCTimestamp {
+                    wall_time: row.get(1)?,
+                    counter: row.get(2)?,
+                    node_hash: row.get(3)?,
+                };
+                Ok((row.get::<_, String>(0)?, timestamp))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let versions = tx
+            .prepare("SELECT node_id, sequence FROM __replication_versions ORDER BY node_id")?
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, u64>(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Snapshot {
+            backends,
+            timestamps,
+            versions,
+        })
+    }
+
+    /// Apply a snapshot taken by a peer, after which incremental sync resumes.
+    ///
+    /// All rows are written in one transaction. A row is kept when the local
+    /// copy is newer than the snapshot's, so changes applied while the
+    /// snapshot was in flight aren't undone. The version vector is merged,
+    /// so changesets the snapshot covers are skipped when redelivered.
+    /// Returns the number of rows written.
+    pub fn apply_snapshot(&self, snapshot: &Snapshot) -> anyhow::Result<usize> {
+        let timestamps: HashMap<&str, HLCTimestamp> = snapshot
+            .timestamps
+            .iter()
+            .map(|(id, timestamp)| (id.as_str(), *timestamp))
+            .collect();
+
+        let mut conn = Connection::open(&self.db_path)?;
+        let tx = conn.transaction()?;
+        let mut written = Vec::new();
+
+        for row in &snapshot.backends {
+            let key = format!("backends:{}", row.id);
+            let remote = timestamps.get(row.id.as_str()).copied();
+            if self.last_applied_key(&tx, &key)? > remote {
+                continue;
+            }
+
+            tx.execute(
+                "INSERT OR REPLACE INTO backends
+                 (id, app, region, country, wg_ip, port, healthy, weight, soft_limit, hard_limit, deleted, draining, maintenance, priority)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                params![
+                    row.id,
+                    row.app,
+                    row.region,
+                    row.country,
+                    row.wg_ip,
+                    row.port,
+                    row.healthy,
+                    row.weight,
+                    row.soft_limit,
+                    row.hard_limit,
+                    row.deleted,
+                    row.draining,
+                    row.maintenance,
+                    row.priority
+                ],
+            )?;
+            if let Some(timestamp) = &remote {
+                record_lww(&tx, &key, timestamp)?;
+            }
+            written.push((key, remote));
+        }
+
+        for (node_id, seq) in &snapshot.versions {
+            tx.execute(
+                "INSERT INTO __replication_versions (node_id, sequence) VALUES (?, ?)
+                 ON CONFLICT(node_id) DO UPDATE SET sequence = MAX(sequence, excluded.sequence)",
+                params![node_id, *seq as i64],
+            )?;
+        }
+
+        tx.commit()?;
+
+        // In-memory state follows only once the rows are committed
+        for (key, timestamp) in &written {
+            if let Some(timestamp) = timestamp {
+                self.remember_applied(key, *timestamp);
+            }
+        }
+        {
+            let mut vv = self.version_vector.write();
+            for (node_id, seq) in &snapshot.versions {
+                vv.update(node_id, *seq);
+            }
+        }
+        // Peers skip sequences they've seen, so never reuse one of ours
+        if let Some((_, seq)) = snapshot.versions.iter().find(|(node_id, _)| *node_id == self.node_id.0) {
+            self.sequence.fetch_max(*seq, Ordering::SeqCst);
+        }
+        if let Some(newest) = timestamps.values().max() {
+            self.advance_clock(Some(newest));
+        }
+
+        if !written.is_empty() {
+            self.notify_backends_changed();
+        }
+
+        tracing::info!(
+            "applied snapshot: {} of {} backends, {} versions",
+            written.len(),
+            snapshot.backends.len(),
+            snapshot.versions.len()
+        );
+        Ok(written.len())
+    }
+
     /// Persist version vector to database.
     fn persist_version(&self, node_id: &str, seq: u64) -> anyhow::Result<()> {
         let conn = Connection::open(&self.db_path)?;
@@ -398,6 +983,34 @@ impl SyncService {
         Ok(())
     }
 
+    /// Delete replication log rows that are no longer needed.
+    ///
+    /// A row is pruned once it is older than `retention` and, if it records
+    /// a local change, every known peer has acked its changeset; remote
+    /// changes are re-served by their origin node. Returns the number of
+    /// rows deleted.
+    pub fn compact_log(&self, retention: Duration) -> anyhow::Result<usize> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let cutoff = now.saturating_sub(retention.as_secs()) as i64;
+        // With no peers there is nobody left to catch up
+        let watermark = self
+            .peer_progress
+            .read()
+            .ack_watermark()
+            .map_or(i64::MAX, |seq| seq as i64);
+
+        let conn = Connection::open(&self.db_path)?;
+        let deleted = conn.execute(
+            "DELETE FROM __replication_log
+             WHERE applied_at <= ? AND (origin_node != ? OR seq <= ?)",
+            params![cutoff, self.node_id.0, watermark],
+        )?;
+        Ok(deleted)
+    }
+
     /// Get changes since a given sequence for a node.
     pub fn get_changes_since(&self, node_id: &str, since_seq: u64) -> anyhow::Result<Vec<ChangeSet>> {
         let conn = Connection::open(&self.db_path)?;
@@ -409,6 +1022,56 @@ impl SyncService {
     }
 }
 
+/// LWW key of the row a change targets (`table:pk`).
+fn change_key(change: &Change) -> String {
+    format!("{}:{}", change.table, change.pk)
+}
+
+/// Record `timestamp` as the last write to `key` (`table:pk`).
+///
+/// The stored timestamp only moves forward.
+fn record_lww(conn: &Connection, key: &str, timestamp: &HLCTimestamp) -> rusqlite::Result<usize> {
+    conn.execute(
+        "INSERT INTO __replication_lww
+         (table_pk, timestamp_wall, timestamp_counter, timestamp_node)
+         VALUES (?, ?, ?, ?)
+         ON CONFLICT(table_pk) DO UPDATE SET
+            timestamp_wall = excluded.timestamp_wall,
+            timestamp_counter = excluded.timestamp_counter,
+            timestamp_node = excluded.timestamp_node
+         WHERE (excluded.timestamp_wall, excluded.timestamp_counter, excluded.timestamp_node)
+             > (timestamp_wall, timestamp_counter, timestamp_node)",
+        params![
+            key,
+            timestamp.wall_time as i64,
+            timestamp.counter as i64,
+            timestamp.node_hash as i64
+        ],
+    )
+}
+
+/// Whether an apply error means the database itself failed rather than the change.
+///
+/// Such errors abort the whole changeset; anything else only skips the change.
+fn is_storage_failure(err: &anyhow::Error) -> bool {
+    use rusqlite::ErrorCode;
+
+    match err.downcast_ref::<rusqlite::Error>() {
+        Some(rusqlite::Error::SqliteFailure(e, _)) => matches!(
+            e.code,
+            ErrorCode::DatabaseBusy
+                | ErrorCode::DatabaseLocked
+                | ErrorCode::SystemIoFailure
+                | ErrorCode::DiskFull
+                | ErrorCode::CannotOpen
+                | ErrorCode::OutOfMemory
+                | ErrorCode::ReadOnly
+                | ErrorCode::DatabaseCorrupt
+        ),
+        _ => false,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -472,6 +1135,18 @@ mod tests {
         assert_eq!(vv.get("node-1"), 15);
     }
 
+    #[test]
+    fn test_version_vector_len() {
+        let mut vv = VersionVector::new();
+        assert!(vv.is_empty());
+
+        vv.update("node1", 3);
+        vv.update("node2", 1);
+        vv.update("node1", 4);
+        assert_eq!(vv.len(), 2);
+        assert!(!vv.is_empty());
+    }
+
     #[test]
     fn test_version_vector_has_seen() {
         let mut vv = VersionVector::new();
@@ -520,6 +1195,142 @@ mod tests {
         assert_eq!(vv1.get("node-1"), 5);
     }
 
+    // ===== Replication Lag Tests =====
+
+    #[test]
+    fn test_peer_progress_inbound_gap() {
+        let mut local = VersionVector::new();
+        local.update("node-2", 3);
+
+        let mut progress = PeerProgress::new();
+        progress.observe_broadcast("node-2", 7);
+
+        // Peer is at 7, we have applied up to 3
+        let lag = progress.lag("node-2", &local, "node-1");
+        assert_eq!(lag, ReplicationLag { inbound: 4, outbound: 0 });
+
+        // Catching up closes the gap
+        local.update("node-2", 7);
+        assert_eq!(progress.lag("node-2", &local, "node-1").inbound, 0);
+    }
+
+    #[test]
+    fn test_peer_progress_outbound_gap() {
+        let mut local = VersionVector::new();
+        local.update("node-1", 10);
+
+        let mut progress = PeerProgress::new();
+        progress.observe_ack("node-2", 6);
+        // Acks can arrive out of order
+        progress.observe_ack("node-2", 4);
+
+        let lag = progress.lag("node-2", &local, "node-1");
+        assert_eq!(lag, ReplicationLag { inbound: 0, outbound: 4 });
+    }
+
+    #[test]
+    fn test_peer_progress_unknown_peer_has_no_lag() {
+        let mut local = VersionVector::new();
+        local.update("node-1", 10);
+        local.update("node-3", 2);
+
+        let progress = PeerProgress::new();
+        assert_eq!(progress.lag("node-3", &local, "node-1"), ReplicationLag::default());
+        assert!(progress.lags(&local, "node-1").is_empty());
+    }
+
+    #[test]
+    fn test_peer_progress_applied_ahead_of_announced() {
+        // Changesets can arrive through another peer before the source's own broadcast
+        let mut local = VersionVector::new();
+        local.update("node-2", 9);
+
+        let mut progress = PeerProgress::new();
+        progress.observe_broadcast("node-2", 5);
+        assert_eq!(progress.lag("node-2", &local, "node-1").inbound, 0);
+    }
+
+    #[test]
+    fn test_peer_progress_lags_sorted_by_peer() {
+        let mut local = VersionVector::new();
+        local.update("node-1", 5);
+        local.update("node-3", 1);
+
+        let mut progress = PeerProgress::new();
+        progress.observe_broadcast("node-3", 4);
+        progress.observe_ack("node-3", 5);
+        progress.observe_ack("node-2", 2);
+
+        assert_eq!(
+            progress.lags(&local, "node-1"),
+            vec![
+                ("node-2".to_string(), ReplicationLag { inbound: 0, outbound: 3 }),
+                ("node-3".to_string(), ReplicationLag { inbound: 3, outbound: 0 }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_peer_progress_ack_watermark() {
+        let mut progress = PeerProgress::new();
+        assert_eq!(progress.ack_watermark(), None);
+
+        progress.observe_ack("node-2", 8);
+        progress.observe_ack("node-3", 5);
+        assert_eq!(progress.ack_watermark(), Some(5));
+
+        // A peer that never acked holds the watermark at zero
+        progress.observe_broadcast("node-4", 3);
+        assert_eq!(progress.ack_watermark(), Some(0));
+    }
+
+    #[tokio::test]
+    async fn test_replication_lag_tracks_failed_apply() {
+        let temp = NamedTempFile::new().unwrap();
+        let service = SyncService::new(
+            NodeId::new("test-node"),
+            temp.path().to_str().unwrap().to_string(),
+        );
+        service.init_db().unwrap();
+
+        let ok = ChangeSet::new(NodeId::new("other-node"), 1, vec![]);
+        service.observe_broadcast(&ok.source, ok.seq);
+        service.apply_changeset(&ok).await.unwrap();
+
+        let mut bad = ChangeSet::new(NodeId::new("other-node"), 3, vec![]);
+        bad.checksum = 12345;
+        service.observe_broadcast(&bad.source, bad.seq);
+        assert!(service.apply_changeset(&bad).await.is_err());
+
+        assert_eq!(
+            service.replication_lag(),
+            vec![("other-node".to_string(), ReplicationLag { inbound: 2, outbound: 0 })]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_replication_lag_ignores_acks_for_other_sources() {
+        let temp = NamedTempFile::new().unwrap();
+        let service = SyncService::new(
+            NodeId::new("test-node"),
+            temp.path().to_str().unwrap().to_string(),
+        );
+        service.init_db().unwrap();
+
+        for i in 0..3 {
+            service.record_change("backends", &format!("b{}", i), ChangeKind::Insert, "{}");
+            service.flush().await.unwrap();
+        }
+
+        service.observe_ack(&NodeId::new("peer"), &NodeId::new("test-node"), 1);
+        service.observe_ack(&NodeId::new("peer"), &NodeId::new("elsewhere"), 3);
+
+        assert_eq!(
+            service.replication_lag(),
+            vec![("peer".to_string(), ReplicationLag { inbound: 0, outbound: 2 })]
+        );
+    }
+
     #[test]
     fn test_sync_event_change_applied_debug() {
         let change = Change::new("backends", "b1", ChangeKind::Insert, "{}", &NodeId::new("node-1"));
@@ -547,6 +1358,48 @@ mod tests {
         assert!(debug.contains("peer-1"));
     }
 
+    #[test]
+    fn test_sync_event_change_failed_debug() {
+        let change = Change::new("backends", "b1", ChangeKind::Insert, "{", &NodeId::new("node-1"));
+        let event = SyncEvent::ChangeFailed {
+            change,
+            error: "bad json".to_string(),
+        };
+        let debug = format!("{:?}", event);
+        assert!(debug.contains("ChangeFailed"));
+        assert!(debug.contains("bad json"));
+    }
+
+    #[test]
+    fn test_sync_event_change_rejected_debug() {
+        let change = Change::new("backends", "b1", ChangeKind::Update, "{}", &NodeId::new("node-1"));
+        let event = SyncEvent::ChangeRejected {
+            change,
+            last_applied: Some(HLCTimestamp::default()),
+        };
+        let debug = format!("{:?}", event);
+        assert!(debug.contains("ChangeRejected"));
+        assert!(debug.contains("last_applied"));
+    }
+
+    #[test]
+    fn test_is_storage_failure() {
+        let busy = rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_BUSY),
+            None,
+        );
+        assert!(is_storage_failure(&busy.into()));
+
+        let constraint = rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_CONSTRAINT),
+            None,
+        );
+        assert!(!is_storage_failure(&constraint.into()));
+
+        let parse = serde_json::from_str::<serde_json::Value>("{").unwrap_err();
+        assert!(!is_storage_failure(&parse.into()));
+    }
+
     #[test]
     fn test_sync_event_clone() {
         let cs = ChangeSet::new(NodeId::new("node-1"), 1, vec![]);
@@ -736,6 +1589,30 @@ mod tests {
         assert!(service.version_vector().has_seen("test-node", 1));
     }
 
+    #[tokio::test]
+    async fn test_flush_exports_corrosion_changes() {
+        let temp = NamedTempFile::new().unwrap();
+        let service = SyncService::new(
+            NodeId::new("test-node"),
+            temp.path().to_str().unwrap().to_string(),
+        );
+        service.init_db().unwrap();
+        let mut rx = service.subscribe_corrosion();
+
+        service.record_change("backends", "backend-1", ChangeKind::Insert, r#"{"port":8080}"#);
+        let cs = service.flush().await.unwrap();
+
+        let exported = rx.try_recv().unwrap();
+        let crate::replication::corrosion::Changeset::Full { version, changes, .. } = &exported.changeset;
+        assert_eq!(*version, cs.seq);
+        assert_eq!(changes.len(), 2);
+
+        let restored = exported.to_changeset().unwrap();
+        assert_eq!(restored.changes[0].pk, "backend-1");
+        assert_eq!(restored.changes[0].kind, ChangeKind::Insert);
+        assert_eq!(restored.changes[0].data, r#"{"port":8080}"#);
+    }
+
     #[tokio::test]
     async fn test_flush_increments_sequence() {
         let temp = NamedTempFile::new().unwrap();
@@ -765,42 +1642,82 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_flush_clears_pending() {
+    async fn test_record_changes_flushes_as_one_changeset() {
         let temp = NamedTempFile::new().unwrap();
         let service = SyncService::new(
             NodeId::new("test-node"),
             temp.path().to_str().unwrap().to_string(),
         );
-
         service.init_db().unwrap();
 
-        service.record_change("backends", "b1", ChangeKind::Insert, "{}");
-        service.record_change("backends", "b2", ChangeKind::Insert, "{}");
-
-        assert_eq!(service.pending_changes.read().len(), 2);
+        let recorded = service.record_changes(vec![
+            ("backends", "b1", ChangeKind::Insert, r#"{"app":"myapp"}"#),
+            ("backends", "b2", ChangeKind::Insert, r#"{"app":"myapp"}"#),
+            ("backends", "b3", ChangeKind::Insert, r#"{"app":"other"}"#),
+        ]);
+        assert_eq!(recorded.len(), 3);
+        assert!(recorded.windows(2).all(|w| w[1].timestamp > w[0].timestamp));
 
-        service.flush().await;
+        let cs = service.flush().await.unwrap();
+        assert_eq!(cs.seq, 1);
+        let pks: Vec<&str> = cs.changes.iter().map(|c| c.pk.as_str()).collect();
+        assert_eq!(pks, vec!["b1", "b2", "b3"]);
+        assert!(cs.verify());
+        assert_eq!(cs.checksum, ChangeSet::new(NodeId::new("test-node"), 1, recorded).checksum);
 
-        // Should be empty after flush
-        assert_eq!(service.pending_changes.read().len(), 0);
+        assert!(service.flush().await.is_none());
+        assert_eq!(service.sequence(), 1);
     }
 
-    #[tokio::test]
-    async fn test_apply_changeset_invalid_checksum() {
+    #[test]
+    fn test_record_changes_empty_batch() {
         let temp = NamedTempFile::new().unwrap();
         let service = SyncService::new(
             NodeId::new("test-node"),
             temp.path().to_str().unwrap().to_string(),
         );
-        service.init_db().unwrap();
-
-        // Create changeset with invalid checksum
-        let mut cs = ChangeSet::new(NodeId::new("other-node"), 1, vec![]);
-        cs.checksum = 12345; // Invalid checksum
 
-        let result = service.apply_changeset(&cs).await;
-        assert!(result.is_err());
-        assert!(result.unwrap_err().to_string().contains("checksum"));
+        assert!(service.record_changes(Vec::new()).is_empty());
+        assert_eq!(service.pending_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_flush_clears_pending() {
+        let temp = NamedTempFile::new().unwrap();
+        let service = SyncService::new(
+            NodeId::new("test-node"),
+            temp.path().to_str().unwrap().to_string(),
+        );
+
+        service.init_db().unwrap();
+
+        service.record_change("backends", "b1", ChangeKind::Insert, "{}");
+        service.record_change("backends", "b2", ChangeKind::Insert, "{}");
+
+        assert_eq!(service.pending_changes.read().len(), 2);
+
+        service.flush().await;
+
+        // Should be empty after flush
+        assert_eq!(service.pending_changes.read().len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_apply_changeset_invalid_checksum() {
+        let temp = NamedTempFile::new().unwrap();
+        let service = SyncService::new(
+            NodeId::new("test-node"),
+            temp.path().to_str().unwrap().to_string(),
+        );
+        service.init_db().unwrap();
+
+        // Create changeset with invalid checksum
+        let mut cs = ChangeSet::new(NodeId::new("other-node"), 1, vec![]);
+        cs.checksum = 12345; // Invalid checksum
+
+        let result = service.apply_changeset(&cs).await;
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("checksum"));
     }
 
     #[tokio::test]
@@ -1075,6 +1992,48 @@ mod tests {
         assert_eq!(app, "new");
     }
 
+    #[tokio::test]
+    async fn test_lww_rejection_counted_and_reported() {
+        use crate::adapters::outbound::DashMapMetricsStore;
+
+        let temp = NamedTempFile::new().unwrap();
+        let mut service = SyncService::new(
+            NodeId::new("test-node"),
+            temp.path().to_str().unwrap().to_string(),
+        );
+        service.init_db().unwrap();
+        let mut events = service.take_event_rx().unwrap();
+        let metrics = Arc::new(DashMapMetricsStore::new());
+        service.set_metrics(metrics.clone());
+
+        let source = NodeId::new("other-node");
+        let newer = backend_change("backend-1", 5, 2_000, &source);
+        let newer_ts = newer.timestamp;
+        service.apply_changeset(&ChangeSet::new(source.clone(), 1, vec![newer])).await.unwrap();
+        assert!(matches!(events.try_recv(), Ok(SyncEvent::ChangeApplied(_))));
+
+        let older = backend_change("backend-1", 3, 1_000, &source);
+        let applied = service
+            .apply_changeset(&ChangeSet::new(source.clone(), 2, vec![older]))
+            .await
+            .unwrap();
+        assert_eq!(applied, 0);
+        assert_eq!(metrics.get_lww_rejected("backends"), 1);
+        match events.try_recv() {
+            Ok(SyncEvent::ChangeRejected { change, last_applied }) => {
+                assert_eq!(change.timestamp.wall_time, 1_000);
+                assert_eq!(last_applied, Some(newer_ts));
+            }
+            other => panic!("expected ChangeRejected, got {:?}", other),
+        }
+
+        // Redelivering the applied change isn't a conflict
+        let again = backend_change("backend-1", 5, 2_000, &source);
+        service.apply_changeset(&ChangeSet::new(source, 3, vec![again])).await.unwrap();
+        assert_eq!(metrics.get_lww_rejected("backends"), 1);
+        assert!(events.try_recv().is_err());
+    }
+
     #[tokio::test]
     async fn test_apply_changeset_lww_from_cache() {
         let temp = NamedTempFile::new().unwrap();
@@ -1208,6 +2167,192 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_apply_changeset_skips_failed_change_and_applies_rest() {
+        let temp = NamedTempFile::new().unwrap();
+        let mut service = SyncService::new(
+            NodeId::new("test-node"),
+            temp.path().to_str().unwrap().to_string(),
+        );
+        let mut event_rx = service.take_event_rx().unwrap();
+
+        service.init_db().unwrap();
+
+        let source_node = NodeId::new("other-node");
+        let changes = vec![
+            Change::new("backends", "bad", ChangeKind::Insert, "not json", &source_node),
+            Change::new("backends", "good", ChangeKind::Insert, r#"{"app":"app1","region":"sa","wg_ip":"10.0.0.1","port":8080}"#, &source_node),
+        ];
+        let cs = ChangeSet::new(source_node, 1, changes);
+
+        let applied = service.apply_changeset(&cs).await.unwrap();
+        assert_eq!(applied, 1);
+        assert!(service.version_vector().has_seen("other-node", 1));
+
+        let conn = Connection::open(temp.path()).unwrap();
+        let ids: Vec<String> = conn
+            .prepare("SELECT id FROM backends")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(ids, vec!["good".to_string()]);
+
+        match event_rx.recv().await {
+            Some(SyncEvent::ChangeFailed { change, error }) => {
+                assert_eq!(change.pk, "bad");
+                assert!(!error.is_empty());
+            }
+            other => panic!("expected ChangeFailed event, got {:?}", other),
+        }
+        match event_rx.recv().await {
+            Some(SyncEvent::ChangeApplied(change)) => assert_eq!(change.pk, "good"),
+            other => panic!("expected ChangeApplied event, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_change_does_not_record_lww_timestamp() {
+        let temp = NamedTempFile::new().unwrap();
+        let service = SyncService::new(
+            NodeId::new("test-node"),
+            temp.path().to_str().unwrap().to_string(),
+        );
+        service.init_db().unwrap();
+
+        let source_node = NodeId::new("other-node");
+        let mut bad = Change::new("backends", "b1", ChangeKind::Insert, "not json", &source_node);
+        bad.timestamp = HLCTimestamp { wall_time: 2_000, counter: 0, node_hash: 1 };
+        let cs1 = ChangeSet::new(source_node.clone(), 1, vec![bad]);
+        assert_eq!(service.apply_changeset(&cs1).await.unwrap(), 0);
+
+        // An older but valid write for the same key must still win
+        let mut good = Change::new("backends", "b1", ChangeKind::Insert, r#"{"app":"app1","region":"sa","wg_ip":"10.0.0.1","port":8080}"#, &source_node);
+        good.timestamp = HLCTimestamp { wall_time: 1_000, counter: 0, node_hash: 1 };
+        let cs2 = ChangeSet::new(source_node, 2, vec![good]);
+        assert_eq!(service.apply_changeset(&cs2).await.unwrap(), 1);
+
+        let conn = Connection::open(temp.path()).unwrap();
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM __replication_lww WHERE table_pk = 'backends:b1' AND timestamp_wall = 1000", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 1);
+    }
+
+    // ===== Conflict Resolver Tests =====
+
+    /// Applies a backend change only if it raises the stored weight.
+    struct MaxWeight;
+
+    impl ConflictResolver for MaxWeight {
+        fn should_apply(
+            &self,
+            conn: &Connection,
+            change: &Change,
+            _last_applied: Option<&HLCTimestamp>,
+        ) -> anyhow::Result<bool> {
+            let incoming: serde_json::Value = serde_json::from_str(&change.data)?;
+            let incoming = incoming.get("weight").and_then(|v| v.as_i64()).unwrap_or(0);
+            let current: Option<i64> = conn
+                .query_row("SELECT weight FROM backends WHERE id = ?", [&change.pk], |row| row.get(0))
+                .ok();
+            Ok(current.is_none_or(|current| incoming > current))
+        }
+    }
+
+    /// Rejects everything, recording what it was asked about.
+    #[derive(Default)]
+    struct RejectAll {
+        seen: parking_lot::Mutex<Vec<(String, Option<HLCTimestamp>)>>,
+    }
+
+    impl ConflictResolver for RejectAll {
+        fn should_apply(
+            &self,
+            _conn: &Connection,
+            change: &Change,
+            last_applied: Option<&HLCTimestamp>,
+        ) -> anyhow::Result<bool> {
+            self.seen.lock().push((change.pk.clone(), last_applied.copied()));
+            Ok(false)
+        }
+    }
+
+    fn backend_change(pk: &str, weight: i64, wall_time: u64, origin: &NodeId) -> Change {
+        let data = format!(r#"{{"app":"app1","region":"eu","wg_ip":"10.0.0.1","port":8080,"weight":{}}}"#, weight);
+        let mut change = Change::new("backends", pk, ChangeKind::Insert, &data, origin);
+        change.timestamp = HLCTimestamp { wall_time, counter: 0, node_hash: 1 };
+        change
+    }
+
+    fn stored_weight(path: &std::path::Path, pk: &str) -> i64 {
+        Connection::open(path)
+            .unwrap()
+            .query_row("SELECT weight FROM backends WHERE id = ?", [pk], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_apply_changeset_honors_table_resolver() {
+        let temp = NamedTempFile::new().unwrap();
+        let service = SyncService::new(NodeId::new("test-node"), temp.path().to_str().unwrap().to_string())
+            .with_conflict_resolver("backends", Arc::new(MaxWeight));
+        service.init_db().unwrap();
+        let source = NodeId::new("other-node");
+
+        let cs = ChangeSet::new(source.clone(), 1, vec![backend_change("b1", 5, 1_000, &source)]);
+        assert_eq!(service.apply_changeset(&cs).await.unwrap(), 1);
+
+        // Newer but lower: LWW would apply it, the resolver doesn't
+        let cs = ChangeSet::new(source.clone(), 2, vec![backend_change("b1", 3, 2_000, &source)]);
+        assert_eq!(service.apply_changeset(&cs).await.unwrap(), 0);
+        assert_eq!(stored_weight(temp.path(), "b1"), 5);
+
+        // Older but higher: LWW would skip it, the resolver applies it
+        let cs = ChangeSet::new(source.clone(), 3, vec![backend_change("b1", 9, 500, &source)]);
+        assert_eq!(service.apply_changeset(&cs).await.unwrap(), 1);
+        assert_eq!(stored_weight(temp.path(), "b1"), 9);
+
+        // The recorded LWW timestamp never moves backwards
+        let conn = Connection::open(temp.path()).unwrap();
+        let wall: i64 = conn
+            .query_row("SELECT timestamp_wall FROM __replication_lww WHERE table_pk = 'backends:b1'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(wall, 1_000);
+        assert_eq!(service.last_timestamps.read()["backends:b1"].wall_time, 1_000);
+    }
+
+    #[tokio::test]
+    async fn test_resolver_sees_last_applied_and_only_its_table() {
+        let temp = NamedTempFile::new().unwrap();
+        let rejecting = Arc::new(RejectAll::default());
+        let service = SyncService::new(NodeId::new("test-node"), temp.path().to_str().unwrap().to_string())
+            .with_conflict_resolver("counters", rejecting.clone());
+        service.init_db().unwrap();
+        let source = NodeId::new("other-node");
+
+        // backends keeps the default Last-Write-Wins
+        let cs = ChangeSet::new(source.clone(), 1, vec![backend_change("b1", 5, 1_000, &source)]);
+        assert_eq!(service.apply_changeset(&cs).await.unwrap(), 1);
+        assert!(rejecting.seen.lock().is_empty());
+
+        let mut counter = Change::new("counters", "c1", ChangeKind::Update, "{}", &source);
+        counter.timestamp = HLCTimestamp { wall_time: 1_000, counter: 0, node_hash: 1 };
+        let cs = ChangeSet::new(source.clone(), 2, vec![counter.clone()]);
+        assert_eq!(service.apply_changeset(&cs).await.unwrap(), 0);
+
+        // Seed a previous write so the resolver is given its timestamp
+        service.last_timestamps.write().insert("counters:c1".to_string(), HLCTimestamp { wall_time: 900, counter: 0, node_hash: 1 });
+        let cs = ChangeSet::new(source, 3, vec![counter]);
+        assert_eq!(service.apply_changeset(&cs).await.unwrap(), 0);
+
+        let seen = rejecting.seen.lock();
+        assert_eq!(seen.len(), 2);
+        assert_eq!(seen[0], ("c1".to_string(), None));
+        assert_eq!(seen[1].1.unwrap().wall_time, 900);
+    }
+
     #[tokio::test]
     async fn test_backend_insert_with_all_fields() {
         let temp = NamedTempFile::new().unwrap();
@@ -1327,5 +2472,766 @@ mod tests {
         assert!(columns.contains(&"kind".to_string()));
         assert!(columns.contains(&"data".to_string()));
         assert!(columns.contains(&"origin_node".to_string()));
+        assert!(columns.contains(&"seq".to_string()));
+    }
+
+    // ===== Duplicate Change Tests =====
+
+    /// Applies every change, so only change-id dedup stops a replay.
+    struct ApplyAll;
+
+    impl ConflictResolver for ApplyAll {
+        fn should_apply(
+            &self,
+            _conn: &Connection,
+            _change: &Change,
+            _last_applied: Option<&HLCTimestamp>,
+        ) -> anyhow::Result<bool> {
+            Ok(true)
+        }
+    }
+
+    fn log_rows(path: &std::path::Path, change_id: u64) -> i64 {
+        Connection::open(path)
+            .unwrap()
+            .query_row(
+                "SELECT COUNT(*) FROM __replication_log WHERE change_id = ?",
+                [change_id as i64],
+                |row| row.get(0),
+            )
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_replayed_change_is_applied_once() {
+        let temp = NamedTempFile::new().unwrap();
+        let mut service = SyncService::new(NodeId::new("test-node"), temp.path().to_str().unwrap().to_string())
+            .with_conflict_resolver("backends", Arc::new(ApplyAll));
+        let mut event_rx = service.take_event_rx().unwrap();
+        service.init_db().unwrap();
+        let source = NodeId::new("other-node");
+        let change = backend_change("b1", 5, 1_000, &source);
+
+        let cs = ChangeSet::new(source.clone(), 1, vec![change.clone()]);
+        assert_eq!(service.apply_changeset(&cs).await.unwrap(), 1);
+
+        // Touch the row so a second data write would show
+        Connection::open(temp.path())
+            .unwrap()
+            .execute("UPDATE backends SET weight = 42 WHERE id = 'b1'", [])
+            .unwrap();
+
+        // Same change again in a later changeset
+        let cs = ChangeSet::new(source.clone(), 2, vec![change.clone()]);
+        assert_eq!(service.apply_changeset(&cs).await.unwrap(), 0);
+        assert!(service.version_vector().has_seen("other-node", 2));
+
+        assert_eq!(stored_weight(temp.path(), "b1"), 42);
+        assert_eq!(log_rows(temp.path(), change.id), 1);
+        match event_rx.try_recv() {
+            Ok(SyncEvent::ChangeApplied(applied)) => assert_eq!(applied.id, change.id),
+            other => panic!("expected ChangeApplied event, got {:?}", other),
+        }
+        assert!(event_rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_change_within_changeset_is_applied_once() {
+        let temp = NamedTempFile::new().unwrap();
+        let mut service = SyncService::new(NodeId::new("test-node"), temp.path().to_str().unwrap().to_string())
+            .with_conflict_resolver("backends", Arc::new(ApplyAll));
+        let mut event_rx = service.take_event_rx().unwrap();
+        service.init_db().unwrap();
+        let source = NodeId::new("other-node");
+        let change = backend_change("b1", 5, 1_000, &source);
+
+        let cs = ChangeSet::new(source, 1, vec![change.clone(), change.clone()]);
+        assert_eq!(service.apply_changeset(&cs).await.unwrap(), 1);
+
+        assert_eq!(log_rows(temp.path(), change.id), 1);
+        assert!(matches!(event_rx.try_recv(), Ok(SyncEvent::ChangeApplied(_))));
+        assert!(event_rx.try_recv().is_err());
+    }
+
+    // ===== Snapshot Tests =====
+
+    fn sync_service(node: &str) -> (NamedTempFile, SyncService) {
+        let temp = NamedTempFile::new().unwrap();
+        let service = SyncService::new(NodeId::new(node), temp.path().to_str().unwrap().to_string());
+        service.init_db().unwrap();
+        (temp, service)
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_round_trip() {
+        let (_source_db, source) = sync_service("source");
+        let other = NodeId::new("other");
+        let inserts = ChangeSet::new(
+            other.clone(),
+            3,
+            vec![backend_change("b1", 5, 1000, &other), backend_change("b2", 3, 2000, &other)],
+        );
+        source.apply_changeset(&inserts).await.unwrap();
+        let mut delete = Change::new("backends", "b2", ChangeKind::Delete, "", &other);
+        delete.timestamp = HLCTimestamp { wall_time: 3000, counter: 0, node_hash: 1 };
+        source.apply_changeset(&ChangeSet::new(other.clone(), 4, vec![delete])).await.unwrap();
+
+        let snapshot = source.snapshot().unwrap();
+        assert_eq!(snapshot.backends.len(), 2);
+        assert_eq!(snapshot.backends[1].deleted, 1);
+        assert_eq!(snapshot.timestamps[1], ("b2".to_string(), HLCTimestamp { wall_time: 3000, counter: 0, node_hash: 1 }));
+        assert_eq!(snapshot.versions, vec![("other".to_string(), 4)]);
+
+        let (_target_db, target) = sync_service("target");
+        let changes = target.subscribe_backend_changes();
+        assert_eq!(target.apply_snapshot(&snapshot).unwrap(), 2);
+        assert!(changes.has_changed().unwrap());
+        assert_eq!(target.snapshot().unwrap(), snapshot);
+        assert!(target.version_vector().has_seen("other", 4));
+        assert!(target.clock().wall_time >= 3000);
+
+        // Changesets the snapshot covers are skipped on redelivery
+        assert_eq!(target.apply_changeset(&inserts).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_apply_snapshot_keeps_newer_local_rows() {
+        let (temp, service) = sync_service("test-node");
+        let other = NodeId::new("other");
+        service
+            .apply_changeset(&ChangeSet::new(other.clone(), 9, vec![backend_change("b1", 9, 5000, &other)]))
+            .await
+            .unwrap();
+
+        let (_source_db, source) = sync_service("source");
+        source
+            .apply_changeset(&ChangeSet::new(
+                other.clone(),
+                2,
+                vec![backend_change("b1", 5, 1000, &other), backend_change("b2", 4, 1000, &other)],
+            ))
+            .await
+            .unwrap();
+
+        assert_eq!(service.apply_snapshot(&source.snapshot().unwrap()).unwrap(), 1);
+        assert_eq!(stored_weight(temp.path(), "b1"), 9);
+        assert_eq!(stored_weight(temp.path(), "b2"), 4);
+        // The version vector never moves backwards
+        assert_eq!(service.version_vector().get("other"), 9);
+    }
+
+    #[tokio::test]
+    async fn test_apply_snapshot_advances_own_sequence() {
+        let (_temp, service) = sync_service("test-node");
+        let snapshot = Snapshot {
+            versions: vec![("test-node".to_string(), 5)],
+            ..Default::default()
+        };
+
+        assert_eq!(service.apply_snapshot(&snapshot).unwrap(), 0);
+        assert_eq!(service.sequence(), 5);
+
+        // Peers already saw our earlier sequences, so the next one follows them
+        service.record_change("backends", "b1", ChangeKind::Insert, "{}");
+        assert_eq!(service.flush().await.unwrap().seq, 6);
+    }
+
+    // ===== Log Compaction Tests =====
+
+    fn now_secs() -> i64 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64
+    }
+
+    fn insert_log_row(conn: &Connection, change_id: i64, origin: &str, seq: i64, applied_at: i64) {
+        conn.execute(
+            "INSERT INTO __replication_log
+             (change_id, table_name, pk, kind, data, timestamp_wall, timestamp_counter, timestamp_node, origin_node, applied_at, seq)
+             VALUES (?, 'backends', 'b1', 'Update', '{}', 0, 0, 0, ?, ?, ?)",
+            params![change_id, origin, applied_at, seq],
+        )
+        .unwrap();
+    }
+
+    fn logged(conn: &Connection, origin: &str) -> Vec<i64> {
+        conn.prepare("SELECT seq FROM __replication_log WHERE origin_node = ? ORDER BY seq")
+            .unwrap()
+            .query_map([origin], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_replication_log_records_changeset_seq() {
+        let temp = NamedTempFile::new().unwrap();
+        let service = SyncService::new(
+            NodeId::new("test-node"),
+            temp.path().to_str().unwrap().to_string(),
+        );
+        service.init_db().unwrap();
+
+        let source = NodeId::new("other-node");
+        let data = r#"{"app":"myapp","region":"sa","wg_ip":"10.0.0.1","port":8080}"#;
+        let change = Change::new("backends", "b1", ChangeKind::Insert, data, &source);
+        service.apply_changeset(&ChangeSet::new(source, 7, vec![change])).await.unwrap();
+
+        let conn = Connection::open(temp.path()).unwrap();
+        assert_eq!(logged(&conn, "other-node"), [7]);
+    }
+
+    #[test]
+    fn test_compact_log_respects_ack_watermark() {
+        let temp = NamedTempFile::new().unwrap();
+        let service = SyncService::new(
+            NodeId::new("test-node"),
+            temp.path().to_str().unwrap().to_string(),
+        );
+        service.init_db().unwrap();
+
+        let conn = Connection::open(temp.path()).unwrap();
+        let old = now_secs() - 7200;
+        for seq in 1..=10 {
+            insert_log_row(&conn, seq, "test-node", seq, old);
+            insert_log_row(&conn, 100 + seq, "other-node", seq, old);
+        }
+        // Recent local change, already acked but still within retention
+        insert_log_row(&conn, 200, "test-node", 1, now_secs());
+
+        let local = NodeId::new("test-node");
+        service.observe_ack(&NodeId::new("peer-a"), &local, 8);
+        service.observe_ack(&NodeId::new("peer-b"), &local, 5);
+
+        let deleted = service.compact_log(Duration::from_secs(3600)).unwrap();
+        assert_eq!(deleted, 15);
+        assert_eq!(logged(&conn, "test-node"), [1, 6, 7, 8, 9, 10]);
+        assert!(logged(&conn, "other-node").is_empty());
+
+        // Once the slow peer catches up the watermark moves to the other one
+        service.observe_ack(&NodeId::new("peer-b"), &local, 10);
+        assert_eq!(service.compact_log(Duration::from_secs(3600)).unwrap(), 3);
+        assert_eq!(logged(&conn, "test-node"), [1, 9, 10]);
+    }
+
+    #[test]
+    fn test_compact_log_without_peers_prunes_by_age() {
+        let temp = NamedTempFile::new().unwrap();
+        let service = SyncService::new(
+            NodeId::new("test-node"),
+            temp.path().to_str().unwrap().to_string(),
+        );
+        service.init_db().unwrap();
+
+        let conn = Connection::open(temp.path()).unwrap();
+        for seq in 1..=500 {
+            let applied_at = if seq <= 400 { now_secs() - 7200 } else { now_secs() };
+            insert_log_row(&conn, seq, "test-node", seq, applied_at);
+        }
+
+        assert_eq!(service.compact_log(Duration::from_secs(3600)).unwrap(), 400);
+        assert_eq!(logged(&conn, "test-node"), (401..=500).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_init_db_adds_seq_to_old_log() {
+        let temp = NamedTempFile::new().unwrap();
+        let conn = Connection::open(temp.path()).unwrap();
+        conn.execute(
+            "CREATE TABLE __replication_log (
+                id INTEGER PRIMARY KEY,
+                change_id INTEGER UNIQUE,
+                table_name TEXT NOT NULL,
+                pk TEXT NOT NULL,
+                kind TEXT NOT NULL,
+                data TEXT NOT NULL,
+                timestamp_wall INTEGER NOT NULL,
+                timestamp_counter INTEGER NOT NULL,
+                timestamp_node INTEGER NOT NULL,
+                origin_node TEXT NOT NULL,
+                applied_at INTEGER NOT NULL
+            )",
+            [],
+        )
+        .unwrap();
+
+        let service = SyncService::new(
+            NodeId::new("test-node"),
+            temp.path().to_str().unwrap().to_string(),
+        );
+        service.init_db().unwrap();
+        // Running again leaves the migrated table alone
+        service.init_db().unwrap();
+
+        assert!(schema::table_columns(&conn, "__replication_log").unwrap().contains("seq"));
+    }
+
+    #[tokio::test]
+    async fn test_apply_changeset_rejects_far_future_timestamp() {
+        let temp = NamedTempFile::new().unwrap();
+        let service = SyncService::new(
+            NodeId::new("test-node"),
+            temp.path().to_str().unwrap().to_string(),
+        )
+        .with_max_clock_skew(Duration::from_secs(60));
+        service.init_db().unwrap();
+
+        let source_node = NodeId::new("skewed-node");
+        let data = r#"{"app":"future","region":"us","wg_ip":"10.0.0.9","port":9000}"#;
+        let mut change = Change::new("backends", "backend-1", ChangeKind::Insert, data, &source_node);
+        // One hour ahead of local time
+        change.timestamp.wall_time = wall_clock_micros() + 3_600_000_000;
+
+        let cs = ChangeSet::new(source_node, 1, vec![change]);
+        let applied = service.apply_changeset(&cs).await.unwrap();
+        assert_eq!(applied, 0);
+
+        // Rejected change must not poison LWW state or the local clock
+        let conn = Connection::open(temp.path()).unwrap();
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM backends WHERE id = 'backend-1'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 0);
+        assert!(service.clock().wall_time < wall_clock_micros() + 3_600_000_000);
+
+        // A normal change for the same key still applies afterwards
+        let change = Change::new("backends", "backend-1", ChangeKind::Insert, data, &NodeId::new("other"));
+        let cs = ChangeSet::new(NodeId::new("other"), 1, vec![change]);
+        assert_eq!(service.apply_changeset(&cs).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_apply_changeset_within_skew_accepted() {
+        let temp = NamedTempFile::new().unwrap();
+        let service = SyncService::new(
+            NodeId::new("test-node"),
+            temp.path().to_str().unwrap().to_string(),
+        )
+        .with_max_clock_skew(Duration::from_secs(60));
+        service.init_db().unwrap();
+
+        let source_node = NodeId::new("other-node");
+        let data = r#"{"app":"a","region":"us","wg_ip":"10.0.0.9","port":9000}"#;
+        let mut change = Change::new("backends", "backend-1", ChangeKind::Insert, data, &source_node);
+        change.timestamp.wall_time = wall_clock_micros() + 5_000_000;
+
+        let cs = ChangeSet::new(source_node, 1, vec![change]);
+        assert_eq!(service.apply_changeset(&cs).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_apply_changeset_equal_wall_time_counter_ordering() {
+        let temp = NamedTempFile::new().unwrap();
+        let service = SyncService::new(
+            NodeId::new("test-node"),
+            temp.path().to_str().unwrap().to_string(),
+        );
+        service.init_db().unwrap();
+
+        let wall = wall_clock_micros();
+        let node_a = NodeId::new("node-a");
+        let node_b = NodeId::new("node-b");
+
+        // node-b's change has the larger counter and must win regardless of node hash
+        let mut change_b = Change::new(
+            "backends",
+            "backend-1",
+            ChangeKind::Insert,
+            r#"{"app":"b","region":"us","wg_ip":"10.0.0.2","port":9000}"#,
+            &node_b,
+        );
+        change_b.timestamp = HLCTimestamp { wall_time: wall, counter: 2, node_hash: 1 };
+        let mut change_a = Change::new(
+            "backends",
+            "backend-1",
+            ChangeKind::Update,
+            r#"{"app":"a","region":"us","wg_ip":"10.0.0.1","port":9000}"#,
+            &node_a,
+        );
+        change_a.timestamp = HLCTimestamp { wall_time: wall, counter: 1, node_hash: u32::MAX };
+
+        service
+            .apply_changeset(&ChangeSet::new(node_b, 1, vec![change_b]))
+            .await
+            .unwrap();
+        let applied = service
+            .apply_changeset(&ChangeSet::new(node_a, 1, vec![change_a]))
+            .await
+            .unwrap();
+        assert_eq!(applied, 0);
+
+        let conn = Connection::open(temp.path()).unwrap();
+        let app: String = conn
+            .query_row("SELECT app FROM backends WHERE id = 'backend-1'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(app, "b");
+    }
+
+    #[tokio::test]
+    async fn test_receive_advances_local_clock() {
+        let temp = NamedTempFile::new().unwrap();
+        let service = SyncService::new(
+            NodeId::new("test-node"),
+            temp.path().to_str().unwrap().to_string(),
+        );
+        service.init_db().unwrap();
+
+        // Remote clock slightly ahead (within skew)
+        let remote_ts = HLCTimestamp {
+            wall_time: wall_clock_micros() + 10_000_000,
+            counter: 4,
+            node_hash: 1,
+        };
+        let mut change = Change::new("backends", "backend-1", ChangeKind::Insert, "{}", &NodeId::new("other"));
+        change.timestamp = remote_ts;
+        service
+            .apply_changeset(&ChangeSet::new(NodeId::new("other"), 1, vec![change]))
+            .await
+            .unwrap();
+
+        assert!(service.clock() > remote_ts);
+
+        // Later local writes order after the received change even though
+        // the local wall clock is behind it
+        let local = service.record_change("backends", "backend-1", ChangeKind::Update, "{}");
+        assert_eq!(local.timestamp.wall_time, remote_ts.wall_time);
+        assert!(local.timestamp > remote_ts);
+    }
+
+    #[test]
+    fn test_record_change_timestamps_strictly_increase() {
+        let temp = NamedTempFile::new().unwrap();
+        let service = SyncService::new(
+            NodeId::new("test-node"),
+            temp.path().to_str().unwrap().to_string(),
+        );
+
+        let c1 = service.record_change("backends", "b1", ChangeKind::Insert, "{}");
+        let c2 = service.record_change("backends", "b1", ChangeKind::Update, "{}");
+        assert!(c2.timestamp > c1.timestamp);
+    }
+
+    #[test]
+    fn test_init_db_adds_draining_column_to_old_schema() {
+        let temp = NamedTempFile::new().unwrap();
+        {
+            let conn = Connection::open(temp.path()).unwrap();
+            conn.execute(
+                "CREATE TABLE backends (
+                    id TEXT PRIMARY KEY, app TEXT, region TEXT, country TEXT, wg_ip TEXT,
+                    port INTEGER, healthy INTEGER, weight INTEGER, soft_limit INTEGER,
+                    hard_limit INTEGER, deleted INTEGER DEFAULT 0
+                )",
+                [],
+            )
+            .unwrap();
+        }
+
+        let service = SyncService::new(
+            NodeId::new("test-node"),
+            temp.path().to_str().unwrap().to_string(),
+        );
+        service.init_db().unwrap();
+        // Idempotent on an already migrated database
+        service.init_db().unwrap();
+
+        let conn = Connection::open(temp.path()).unwrap();
+        assert!(conn.prepare("SELECT draining FROM backends LIMIT 0").is_ok());
+    }
+
+    #[test]
+    fn test_init_db_migrates_old_schema_and_applies_changes() {
+        let temp = NamedTempFile::new().unwrap();
+        {
+            let conn = Connection::open(temp.path()).unwrap();
+            conn.execute(
+                "CREATE TABLE backends (
+                    id TEXT PRIMARY KEY, app TEXT NOT NULL, region TEXT NOT NULL,
+                    country TEXT, wg_ip TEXT NOT NULL, port INTEGER NOT NULL
+                )",
+                [],
+            )
+            .unwrap();
+        }
+
+        let service = SyncService::new(
+            NodeId::new("test-node"),
+            temp.path().to_str().unwrap().to_string(),
+        );
+        service.init_db().unwrap();
+
+        let conn = Connection::open(temp.path()).unwrap();
+        let columns = schema::table_columns(&conn, "backends").unwrap();
+        assert!(schema::BACKENDS_COLUMNS.iter().all(|c| columns.contains(c.name)));
+
+        // Changes that write every column now apply
+        let change = service.record_change(
+            "backends",
+            "b1",
+            ChangeKind::Insert,
+            r#"{"app":"myapp","region":"eu","wg_ip":"10.50.1.1","port":8080}"#,
+        );
+        let conn = Connection::open(temp.path()).unwrap();
+        service.apply_backend_change(&conn, &change).unwrap();
+        let weight: i64 = conn
+            .query_row("SELECT weight FROM backends WHERE id = 'b1'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(weight, 2);
+    }
+
+    #[test]
+    fn test_init_db_rejects_schema_missing_required_column() {
+        let temp = NamedTempFile::new().unwrap();
+        {
+            let conn = Connection::open(temp.path()).unwrap();
+            conn.execute("CREATE TABLE backends (id TEXT PRIMARY KEY, app TEXT)", [])
+                .unwrap();
+        }
+
+        let service = SyncService::new(
+            NodeId::new("test-node"),
+            temp.path().to_str().unwrap().to_string(),
+        );
+        let err = service.init_db().unwrap_err();
+        assert!(err.to_string().contains("missing required column `region`"));
+    }
+
+    #[test]
+    fn test_set_backend_draining_unknown_backend() {
+        let temp = NamedTempFile::new().unwrap();
+        let service = SyncService::new(
+            NodeId::new("test-node"),
+            temp.path().to_str().unwrap().to_string(),
+        );
+        service.init_db().unwrap();
+
+        assert!(service.set_backend_draining("missing", true).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_backend_draining_replicates_to_peer() {
+        use crate::adapters::outbound::SqliteBackendRepository;
+        use crate::domain::ports::BackendRepository;
+        use crate::domain::services::LoadBalancer;
+        use crate::domain::value_objects::RegionCode;
+
+        let temp_a = NamedTempFile::new().unwrap();
+        let temp_b = NamedTempFile::new().unwrap();
+        let node_a = SyncService::new(NodeId::new("node-a"), temp_a.path().to_str().unwrap().to_string());
+        let node_b = SyncService::new(NodeId::new("node-b"), temp_b.path().to_str().unwrap().to_string());
+        node_a.init_db().unwrap();
+        node_b.init_db().unwrap();
+
+        // Both nodes learn about the backend from the same origin
+        let origin = NodeId::new("origin");
+        let data = r#"{"app":"myapp","region":"sa","country":"BR","wg_ip":"10.0.0.1","port":8080,"healthy":1,"weight":2,"soft_limit":100,"hard_limit":150}"#;
+        let insert = ChangeSet::new(
+            origin.clone(),
+            1,
+            vec![Change::new("backends", "backend-1", ChangeKind::Insert, data, &origin)],
+        );
+        node_a.apply_changeset(&insert).await.unwrap();
+        node_b.apply_changeset(&insert).await.unwrap();
+
+        // Node A drains the backend and broadcasts the change
+        tokio::time::sleep(std::time::Duration::from_millis(2)).await;
+        let change = node_a.set_backend_draining("backend-1", true).unwrap();
+        assert_eq!(change.kind, ChangeKind::Update);
+        let changeset = node_a.flush().await.unwrap();
+        assert_eq!(node_b.apply_changeset(&changeset).await.unwrap(), 1);
+
+        // The full row survives the update on the peer
+        let conn = Connection::open(temp_b.path()).unwrap();
+        let (draining, weight): (i64, i64) = conn
+            .query_row(
+                "SELECT draining, weight FROM backends WHERE id = 'backend-1'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(draining, 1);
+        assert_eq!(weight, 2);
+
+        // Node B's routing view no longer selects the backend
+        let repo = SqliteBackendRepository::new();
+        repo.sync_once(temp_b.path().to_str().unwrap()).await.unwrap();
+        let backends = repo.get_all().await;
+        assert_eq!(backends.len(), 1);
+        assert!(backends[0].healthy);
+        assert!(backends[0].draining);
+        assert!(LoadBalancer::pick_backend(&backends, &RegionCode::SouthAmerica, None, |_| 0).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_local_drain_rejects_older_remote_update() {
+        let temp = NamedTempFile::new().unwrap();
+        let service = SyncService::new(NodeId::new("node-a"), temp.path().to_str().unwrap().to_string());
+        service.init_db().unwrap();
+
+        let origin = NodeId::new("origin");
+        let data = r#"{"app":"myapp","region":"sa","wg_ip":"10.0.0.1","port":8080,"healthy":1}"#;
+        let at = |wall_time| HLCTimestamp { wall_time, counter: 0, node_hash: 1 };
+        let now = wall_clock_micros();
+        let insert = Change::new("backends", "backend-1", ChangeKind::Insert, data, &origin)
+            .with_timestamp(at(now - 10_000_000));
+        service
+            .apply_changeset(&ChangeSet::new(origin.clone(), 1, vec![insert]))
+            .await
+            .unwrap();
+
+        let drain = service.set_backend_draining("backend-1", true).unwrap();
+        assert_eq!(service.last_timestamps.read()["backends:backend-1"], drain.timestamp);
+
+        // Written remotely before the drain, delivered after it
+        let stale = Change::new("backends", "backend-1", ChangeKind::Update, data, &origin)
+            .with_timestamp(at(now - 5_000_000));
+        let applied = service
+            .apply_changeset(&ChangeSet::new(origin, 2, vec![stale]))
+            .await
+            .unwrap();
+        assert_eq!(applied, 0);
+
+        let conn = Connection::open(temp.path()).unwrap();
+        let draining: i64 = conn
+            .query_row("SELECT draining FROM backends WHERE id = 'backend-1'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(draining, 1);
+    }
+
+    #[tokio::test]
+    async fn test_backend_maintenance_replicates_and_keeps_draining() {
+        let temp_a = NamedTempFile::new().unwrap();
+        let temp_b = NamedTempFile::new().unwrap();
+        let node_a = SyncService::new(NodeId::new("node-a"), temp_a.path().to_str().unwrap().to_string());
+        let node_b = SyncService::new(NodeId::new("node-b"), temp_b.path().to_str().unwrap().to_string());
+        node_a.init_db().unwrap();
+        node_b.init_db().unwrap();
+
+        let origin = NodeId::new("origin");
+        let data = r#"{"app":"myapp","region":"sa","wg_ip":"10.0.0.1","port":8080,"healthy":1}"#;
+        let insert = ChangeSet::new(
+            origin.clone(),
+            1,
+            vec![Change::new("backends", "backend-1", ChangeKind::Insert, data, &origin)],
+        );
+        node_a.apply_changeset(&insert).await.unwrap();
+        node_b.apply_changeset(&insert).await.unwrap();
+
+        // Setting one flag doesn't clear the other
+        tokio::time::sleep(std::time::Duration::from_millis(2)).await;
+        node_a.set_backend_draining("backend-1", true).unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(2)).await;
+        node_a.set_backend_maintenance("backend-1", true).unwrap();
+        let changeset = node_a.flush().await.unwrap();
+        node_b.apply_changeset(&changeset).await.unwrap();
+
+        let conn = Connection::open(temp_b.path()).unwrap();
+        let (draining, maintenance, healthy): (i64, i64, i64) = conn
+            .query_row(
+                "SELECT draining, maintenance, healthy FROM backends WHERE id = 'backend-1'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!((draining, maintenance, healthy), (1, 1, 1));
+
+        assert!(node_a.set_backend_maintenance("missing", true).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_apply_changeset_notifies_backend_subscribers() {
+        let temp = NamedTempFile::new().unwrap();
+        let service = SyncService::new(
+            NodeId::new("test-node"),
+            temp.path().to_str().unwrap().to_string(),
+        );
+        service.init_db().unwrap();
+        let mut changes = service.subscribe_backend_changes();
+
+        let source = NodeId::new("other-node");
+        let data = r#"{"app":"myapp","region":"sa","wg_ip":"10.0.0.1","port":8080}"#;
+        let cs = ChangeSet::new(
+            source.clone(),
+            1,
+            vec![Change::new("backends", "backend-1", ChangeKind::Insert, data, &source)],
+        );
+        service.apply_changeset(&cs).await.unwrap();
+        assert!(changes.has_changed().unwrap());
+        changes.mark_unchanged();
+
+        // Already-seen changesets don't apply anything, so no signal
+        service.apply_changeset(&cs).await.unwrap();
+        assert!(!changes.has_changed().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_replicated_backend_reaches_repository_before_reload_interval() {
+        use crate::adapters::outbound::SqliteBackendRepository;
+        use crate::domain::ports::BackendRepository;
+
+        let temp = NamedTempFile::new().unwrap();
+        let db_path = temp.path().to_str().unwrap().to_string();
+        let service = SyncService::new(NodeId::new("test-node"), db_path.clone());
+        service.init_db().unwrap();
+
+        // The periodic reload alone would not pick the change up within the test
+        let repo = SqliteBackendRepository::new();
+        repo.start_sync(db_path.clone(), 3600);
+        repo.start_notified_sync(db_path, service.subscribe_backend_changes());
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert!(repo.get_all().await.is_empty());
+
+        let source = NodeId::new("other-node");
+        let data = r#"{"app":"myapp","region":"sa","country":"BR","wg_ip":"10.0.0.1","port":8080}"#;
+        let cs = ChangeSet::new(
+            source.clone(),
+            1,
+            vec![Change::new("backends", "backend-1", ChangeKind::Insert, data, &source)],
+        );
+        assert_eq!(service.apply_changeset(&cs).await.unwrap(), 1);
+
+        for _ in 0..100 {
+            if !repo.get_all().await.is_empty() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        let backends = repo.get_all().await;
+        assert_eq!(backends.len(), 1);
+        assert_eq!(backends[0].id, "backend-1");
+    }
+
+    #[tokio::test]
+    async fn test_full_event_channel_drops_without_blocking_apply() {
+        let temp = NamedTempFile::new().unwrap();
+        let mut service = SyncService::new(
+            NodeId::new("test-node"),
+            temp.path().to_str().unwrap().to_string(),
+        )
+        .with_event_channel(1, OverflowPolicy::DropNewest);
+        service.init_db().unwrap();
+        // Keep the receiver alive but never read from it
+        let _events = service.take_event_rx().unwrap();
+
+        let source = NodeId::new("other-node");
+        let data = r#"{"app":"myapp","region":"sa","wg_ip":"10.0.0.1","port":8080}"#;
+        let changes = (1..=3)
+            .map(|i| Change::new("backends", format!("backend-{}", i), ChangeKind::Insert, data, &source))
+            .collect();
+        let cs = ChangeSet::new(source, 1, changes);
+
+        let applied = tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            service.apply_changeset(&cs),
+        )
+        .await
+        .expect("apply must not block on a full event channel")
+        .unwrap();
+        assert_eq!(applied, 3);
+
+        assert_eq!(service.event_stats().sent(), 1);
+        assert_eq!(service.event_stats().dropped(), 2);
+        assert_eq!(service.event_stats().lagged(), 0);
     }
 }
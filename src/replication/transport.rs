@@ -6,13 +6,32 @@
 //! Uses Sans-IO pattern: message encoding/decoding is separated from I/O for testability.
 
 use crate::replication::types::{ChangeSet, Message, NodeId};
+use crate::replication::cluster_tls::{ClusterTls, PEER_SERVER_NAME};
 use crate::replication::config::ReplicationConfig;
-use std::collections::HashMap;
+use crate::replication::events::{event_channel, EventChannelStats, EventSender};
+use std::collections::{BTreeMap, HashMap};
 use std::net::SocketAddr;
 use std::sync::Arc;
-use tokio::sync::{mpsc, RwLock};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::sync::{mpsc, oneshot, RwLock};
+use tokio::task::JoinSet;
 use quinn::{Endpoint, ServerConfig, ClientConfig, Connection as QuinnConnection};
 
+/// Largest message accepted from a peer (10 MB).
+pub const MAX_MESSAGE_SIZE: usize = 10 * 1024 * 1024;
+
+/// Errors reading a message frame from a peer stream.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum TransportError {
+    #[error("stream ended after {received} of {expected} bytes")]
+    Incomplete { expected: usize, received: usize },
+    #[error("message of {0} bytes exceeds the {MAX_MESSAGE_SIZE} byte limit")]
+    TooLarge(usize),
+    #[error("no response within {0:?}")]
+    Timeout(Duration),
+}
+
 // ==================== Sans-IO Functions ====================
 
 /// Encode a message for transport (Sans-IO pattern).
@@ -107,6 +126,8 @@ pub fn message_type_name(msg: &Message) -> &'static str {
         Message::Ack { .. } => "Ack",
         Message::Ping => "Ping",
         Message::Pong => "Pong",
+        Message::SnapshotRequest => "SnapshotRequest",
+        Message::SnapshotResponse(_) => "SnapshotResponse",
     }
 }
 
@@ -148,11 +169,260 @@ pub fn get_sync_request_from_seq(msg: &Message) -> Option<u64> {
     }
 }
 
+/// Create an ack message for an applied changeset (Sans-IO pattern).
+pub fn create_ack(changeset: &ChangeSet) -> Message {
+    Message::Ack {
+        source: changeset.source.clone(),
+        seq: changeset.seq,
+    }
+}
+
+/// Extract ack parameters (Sans-IO pattern).
+pub fn extract_ack(msg: &Message) -> Option<(&NodeId, u64)> {
+    match msg {
+        Message::Ack { source, seq } => Some((source, *seq)),
+        _ => None,
+    }
+}
+
+/// Build the reply to a request received on a bidirectional stream (Sans-IO pattern).
+///
+/// Returns None for messages that are not requests.
+pub fn reply_to_request(msg: &Message) -> Option<Message> {
+    match msg {
+        Message::Ping => Some(Message::Pong),
+        _ => None,
+    }
+}
+
+/// Whether a request is answered by the transport's owner rather than the
+/// transport itself (Sans-IO pattern).
+///
+/// Such requests are handed over as [`TransportEvent::RequestReceived`],
+/// since the reply depends on replicated state.
+pub fn needs_application_reply(msg: &Message) -> bool {
+    matches!(msg, Message::SnapshotRequest)
+}
+
+/// Keepalive state for a single peer.
+#[derive(Debug, Clone, Copy, Default)]
+struct PingState {
+    missed: u32,
+    last_rtt: Option<Duration>,
+}
+
+/// Tracks keepalive ping results per peer (Sans-IO pattern).
+///
+/// A peer is considered dead once it misses `max_missed` pings in a row;
+/// any pong resets the count.
+#[derive(Debug)]
+pub struct PingTracker {
+    max_missed: u32,
+    peers: HashMap<String, PingState>,
+}
+
+impl PingTracker {
+    /// Create a tracker that gives up on a peer after `max_missed` consecutive misses.
+    pub fn new(max_missed: u32) -> Self {
+        Self {
+            max_missed: max_missed.max(1),
+            peers: HashMap::new(),
+        }
+    }
+
+    /// Record a pong received from a peer after `rtt`.
+    pub fn record_pong(&mut self, peer: &str, rtt: Duration) {
+        let state = self.peers.entry(peer.to_string()).or_default();
+        state.missed = 0;
+        state.last_rtt = Some(rtt);
+    }
+
+    /// Record a ping the peer did not answer in time.
+    ///
+    /// Returns true once the peer has missed `max_missed` pings in a row.
+    pub fn record_miss(&mut self, peer: &str) -> bool {
+        let state = self.peers.entry(peer.to_string()).or_default();
+        state.missed += 1;
+        state.missed >= self.max_missed
+    }
+
+    /// Number of consecutive pings the peer has missed.
+    pub fn missed(&self, peer: &str) -> u32 {
+        self.peers.get(peer).map(|s| s.missed).unwrap_or(0)
+    }
+
+    /// Round trip time of the last answered ping.
+    pub fn last_rtt(&self, peer: &str) -> Option<Duration> {
+        self.peers.get(peer).and_then(|s| s.last_rtt)
+    }
+
+    /// Stop tracking a peer.
+    pub fn forget_peer(&mut self, peer: &str) {
+        self.peers.remove(peer);
+    }
+}
+
+/// A changeset sent to a peer that has not been acked yet.
+#[derive(Debug, Clone)]
+struct PendingChangeset {
+    changeset: ChangeSet,
+    last_sent: Instant,
+    attempts: u32,
+}
+
+/// Tracks un-acked changesets per peer for at-least-once delivery (Sans-IO pattern).
+///
+/// Time is passed in explicitly so retry behaviour can be tested without sleeping.
+/// A changeset a peer still hasn't acked after `max_attempts` sends is dropped,
+/// so a peer that is alive but never acks can't grow the pending set forever.
+#[derive(Debug)]
+pub struct AckTracker {
+    ack_timeout: Duration,
+    max_attempts: u32,
+    pending: HashMap<String, BTreeMap<u64, PendingChangeset>>,
+}
+
+impl AckTracker {
+    /// Create a tracker that re-sends changesets not acked within `ack_timeout`.
+    pub fn new(ack_timeout: Duration) -> Self {
+        Self {
+            ack_timeout,
+            max_attempts: 10,
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Give up on a changeset after `max_attempts` sends (at least one).
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Record that a changeset was sent to a peer.
+    pub fn track(&mut self, peer: &str, changeset: &ChangeSet, now: Instant) {
+        self.pending
+            .entry(peer.to_string())
+            .or_default()
+            .entry(changeset.seq)
+            .and_modify(|p| {
+                p.last_sent = now;
+                p.attempts += 1;
+            })
+            .or_insert_with(|| PendingChangeset {
+                changeset: changeset.clone(),
+                last_sent: now,
+                attempts: 1,
+            });
+    }
+
+    /// Mark a changeset as acked by a peer.
+    ///
+    /// Returns true if a matching pending changeset was removed.
+    pub fn acknowledge(&mut self, peer: &str, source: &NodeId, seq: u64) -> bool {
+        let Some(peer_pending) = self.pending.get_mut(peer) else {
+            return false;
+        };
+
+        let matches = peer_pending
+            .get(&seq)
+            .is_some_and(|p| p.changeset.source == *source);
+        if matches {
+            peer_pending.remove(&seq);
+            if peer_pending.is_empty() {
+                self.pending.remove(peer);
+            }
+        }
+        matches
+    }
+
+    /// Take changesets whose ack timed out, marking them as re-sent at `now`.
+    ///
+    /// Changesets that already used up their send attempts are dropped
+    /// instead. Returns (peer, changeset) pairs in ascending seq order per peer.
+    pub fn take_due(&mut self, now: Instant) -> Vec<(String, ChangeSet)> {
+        let mut due = Vec::new();
+
+        for (peer, peer_pending) in self.pending.iter_mut() {
+            peer_pending.retain(|seq, pending| {
+                if now.duration_since(pending.last_sent) < self.ack_timeout {
+                    return true;
+                }
+                if pending.attempts >= self.max_attempts {
+                    tracing::warn!(
+                        "giving up on changeset seq={} from {} to {} after {} attempts",
+                        seq,
+                        pending.changeset.source,
+                        peer,
+                        pending.attempts
+                    );
+                    return false;
+                }
+                pending.last_sent = now;
+                pending.attempts += 1;
+                due.push((peer.clone(), pending.changeset.clone()));
+                true
+            });
+        }
+        self.pending.retain(|_, peer_pending| !peer_pending.is_empty());
+
+        due
+    }
+
+    /// Number of un-acked changesets for a peer.
+    pub fn pending_count(&self, peer: &str) -> usize {
+        self.pending.get(peer).map(|p| p.len()).unwrap_or(0)
+    }
+
+    /// Number of send attempts for a pending changeset.
+    pub fn attempts(&self, peer: &str, seq: u64) -> Option<u32> {
+        self.pending.get(peer)?.get(&seq).map(|p| p.attempts)
+    }
+
+    /// Stop tracking a peer (e.g. when it is gone for good).
+    pub fn forget_peer(&mut self, peer: &str) -> usize {
+        self.pending.remove(peer).map(|p| p.len()).unwrap_or(0)
+    }
+}
+
+/// Read one length-prefixed frame, rejecting lengths over `MAX_MESSAGE_SIZE`
+/// before allocating.
+pub async fn read_frame<R: AsyncRead + Unpin>(recv: &mut R) -> Result<Vec<u8>, TransportError> {
+    let mut len_buf = [0u8; 4];
+    read_full(recv, &mut len_buf).await?;
+    let len = decode_length(&len_buf) as usize;
+    if len > MAX_MESSAGE_SIZE {
+        return Err(TransportError::TooLarge(len));
+    }
+
+    let mut data = vec![0u8; len];
+    read_full(recv, &mut data).await?;
+    Ok(data)
+}
+
+/// Fill `buf`, treating a finished, reset or lost stream as `Incomplete`.
+async fn read_full<R: AsyncRead + Unpin>(recv: &mut R, buf: &mut [u8]) -> Result<(), TransportError> {
+    let mut received = 0;
+    while received < buf.len() {
+        match recv.read(&mut buf[received..]).await {
+            Ok(n) if n > 0 => received += n,
+            _ => {
+                return Err(TransportError::Incomplete {
+                    expected: buf.len(),
+                    received,
+                })
+            }
+        }
+    }
+    Ok(())
+}
+
 /// A connection to a peer node.
 pub struct PeerConnection {
     pub node_id: NodeId,
     pub addr: SocketAddr,
     connection: QuinnConnection,
+    /// Bound on waiting for the response to `request`
+    request_timeout: Duration,
 }
 
 impl PeerConnection {
@@ -183,15 +453,29 @@ impl PeerConnection {
         send.finish()?;
 
         // Read response
-        let mut len_buf = [0u8; 4];
-        recv.read_exact(&mut len_buf).await?;
-        let len = u32::from_be_bytes(len_buf) as usize;
-
-        let mut data = vec![0u8; len];
-        recv.read_exact(&mut data).await?;
-
-        let response: Message = bincode::deserialize(&data)?;
-        Ok(response)
+        let data = tokio::time::timeout(self.request_timeout, read_frame(&mut recv))
+            .await
+            .map_err(|_| TransportError::Timeout(self.request_timeout))??;
+
+        decode_message(&data)
+    }
+
+    /// Ping the peer and wait up to `timeout` for its pong.
+    ///
+    /// Returns the round trip time.
+    pub async fn ping(&self, timeout: Duration) -> anyhow::Result<Duration> {
+        let start = Instant::now();
+        match tokio::time::timeout(timeout, self.request(&Message::Ping)).await {
+            // The timer is coarser than the timeout, so a late pong can still
+            // win the race; it is a miss all the same
+            Ok(Ok(Message::Pong)) if start.elapsed() > timeout => {
+                anyhow::bail!("pong after {:?}, timeout {:?}", start.elapsed(), timeout)
+            }
+            Ok(Ok(Message::Pong)) => Ok(start.elapsed()),
+            Ok(Ok(other)) => anyhow::bail!("expected Pong, got {}", message_type_name(&other)),
+            Ok(Err(e)) => Err(e),
+            Err(_) => anyhow::bail!("no pong within {:?}", timeout),
+        }
     }
 
     /// Check if the connection is still alive.
@@ -208,6 +492,13 @@ pub enum TransportEvent {
         from: NodeId,
         message: Message,
     },
+    /// Received a request the peer is waiting on; dropping `reply` closes
+    /// the stream without an answer
+    RequestReceived {
+        from: NodeId,
+        message: Message,
+        reply: oneshot::Sender<Message>,
+    },
     /// A peer connected
     PeerConnected(NodeId),
     /// A peer disconnected
@@ -219,15 +510,20 @@ pub struct TransportService {
     config: ReplicationConfig,
     endpoint: Option<Endpoint>,
     peers: Arc<RwLock<HashMap<String, Arc<PeerConnection>>>>,
-    event_tx: mpsc::Sender<TransportEvent>,
+    event_tx: EventSender<TransportEvent>,
     event_rx: Option<mpsc::Receiver<TransportEvent>>,
     shutdown: Arc<std::sync::atomic::AtomicBool>,
+    acks: Arc<parking_lot::Mutex<AckTracker>>,
+    pings: Arc<parking_lot::Mutex<PingTracker>>,
 }
 
 impl TransportService {
     /// Create a new transport service.
     pub fn new(config: ReplicationConfig) -> Self {
-        let (event_tx, event_rx) = mpsc::channel(1024);
+        let (event_tx, event_rx) =
+            event_channel(config.event_channel_capacity, config.event_overflow);
+        let acks = Arc::new(parking_lot::Mutex::new(AckTracker::new(config.ack_timeout).with_max_attempts(config.max_send_attempts)));
+        let pings = Arc::new(parking_lot::Mutex::new(PingTracker::new(config.max_missed_pings)));
 
         Self {
             config,
@@ -236,6 +532,8 @@ impl TransportService {
             event_tx,
             event_rx: Some(event_rx),
             shutdown: Arc::new(std::sync::atomic::AtomicBool::new(false)),
+            acks,
+            pings,
         }
     }
 
@@ -244,11 +542,21 @@ impl TransportService {
         self.event_rx.take()
     }
 
+    /// Counters for the event channel (sent, dropped, lagged).
+    pub fn event_stats(&self) -> &EventChannelStats {
+        self.event_tx.stats()
+    }
+
     /// Get all connected peers.
     pub async fn peers(&self) -> Vec<Arc<PeerConnection>> {
         self.peers.read().await.values().cloned().collect()
     }
 
+    /// Number of connected peers.
+    pub async fn peer_count(&self) -> usize {
+        self.peers.read().await.len()
+    }
+
     /// Get a specific peer connection.
     pub async fn get_peer(&self, node_id: &str) -> Option<Arc<PeerConnection>> {
         self.peers.read().await.get(node_id).cloned()
@@ -264,37 +572,51 @@ impl TransportService {
         self.shutdown.load(std::sync::atomic::Ordering::SeqCst)
     }
 
-    /// Start the transport service.
-    #[cfg_attr(coverage_nightly, coverage(off))]
-    pub async fn start(&mut self) -> anyhow::Result<()> {
-        // Generate self-signed certificate for QUIC
-        let cert = rcgen::generate_simple_self_signed(vec![
-            self.config.node_id.clone(),
-            "localhost".to_string(),
-        ])?;
-
-        let cert_der = cert.cert.der().to_vec();
-        let key_der = cert.key_pair.serialize_der();
+    /// TLS configs for both sides of peer connections.
+    ///
+    /// With a cluster secret, peers must present a certificate issued by the
+    /// derived cluster CA. Without one, `insecure_transport` must be set
+    /// explicitly to accept any certificate.
+    fn crypto_configs(&self) -> anyhow::Result<(quinn::rustls::ServerConfig, quinn::rustls::ClientConfig)> {
+        if let Some(secret) = &self.config.cluster_secret {
+            let tls = ClusterTls::new(&self.config.cluster_name, secret, &self.config.node_id)?;
+            return Ok((tls.server_config()?, tls.client_config()?));
+        }
+        if !self.config.insecure_transport {
+            anyhow::bail!(
+                "replication transport needs a cluster secret to authenticate peers \
+                 (set insecure_transport to accept any peer, for development only)"
+            );
+        }
 
-        let cert_chain = vec![rustls::pki_types::CertificateDer::from(cert_der.clone())];
-        let private_key = rustls::pki_types::PrivateKeyDer::try_from(key_der)
+        tracing::warn!("replication transport is insecure: accepting any peer certificate");
+        let cert = rcgen::generate_simple_self_signed(vec![PEER_SERVER_NAME.to_string()])?;
+        let cert_chain = vec![cert.cert.der().clone()];
+        let private_key = rustls::pki_types::PrivateKeyDer::try_from(cert.key_pair.serialize_der())
             .map_err(|e| anyhow::anyhow!("failed to parse private key: {:?}", e))?;
 
-        // Server config using quinn's rustls
-        let server_crypto = quinn::rustls::ServerConfig::builder()
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let server_crypto = quinn::rustls::ServerConfig::builder_with_provider(provider.clone())
+            .with_protocol_versions(&[&rustls::version::TLS13])?
             .with_no_client_auth()
-            .with_single_cert(cert_chain.clone(), private_key.clone_key())?;
+            .with_single_cert(cert_chain, private_key)?;
+        let client_crypto = quinn::rustls::ClientConfig::builder_with_provider(provider)
+            .with_protocol_versions(&[&rustls::version::TLS13])?
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(SkipServerVerification))
+            .with_no_client_auth();
+        Ok((server_crypto, client_crypto))
+    }
+
+    /// Start the transport service.
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    pub async fn start(&mut self) -> anyhow::Result<()> {
+        let (server_crypto, client_crypto) = self.crypto_configs()?;
 
         let server_config = ServerConfig::with_crypto(Arc::new(
             quinn::crypto::rustls::QuicServerConfig::try_from(server_crypto)?
         ));
 
-        // Client config - use SkipServerVerification for self-signed certs in cluster
-        let client_crypto = quinn::rustls::ClientConfig::builder()
-            .dangerous()
-            .with_custom_certificate_verifier(Arc::new(SkipServerVerification))
-            .with_no_client_auth();
-
         let client_config = ClientConfig::new(Arc::new(
             quinn::crypto::rustls::QuicClientConfig::try_from(client_crypto)?
         ));
@@ -313,6 +635,7 @@ impl TransportService {
         let event_tx = self.event_tx.clone();
         let shutdown = self.shutdown.clone();
         let node_id = self.config.node_id.clone();
+        let request_timeout = self.config.request_timeout;
 
         tokio::spawn(async move {
             loop {
@@ -336,11 +659,12 @@ impl TransportService {
                                         node_id: peer_node_id.clone(),
                                         addr: remote_addr,
                                         connection: conn.clone(),
+                                        request_timeout,
                                     });
 
                                     peers.write().await.insert(peer_node_id.0.clone(), peer);
 
-                                    let _ = event_tx
+                                    event_tx
                                         .send(TransportEvent::PeerConnected(peer_node_id.clone()))
                                         .await;
 
@@ -373,22 +697,31 @@ impl TransportService {
             anyhow::anyhow!("transport not started")
         })?;
 
-        let conn = endpoint.connect(addr, &self.config.node_id)?
+        let conn = endpoint.connect(addr, PEER_SERVER_NAME)?
             .await?;
 
         let peer_node_id = NodeId::new(node_id);
         let peer = Arc::new(PeerConnection {
             node_id: peer_node_id.clone(),
             addr,
-            connection: conn,
+            connection: conn.clone(),
+            request_timeout: self.config.request_timeout,
         });
 
         self.peers.write().await.insert(node_id.to_string(), peer.clone());
 
-        let _ = self.event_tx
-            .send(TransportEvent::PeerConnected(peer_node_id))
+        self.event_tx
+            .send(TransportEvent::PeerConnected(peer_node_id.clone()))
             .await;
 
+        // Read streams opened by the peer on this connection (e.g. acks)
+        tokio::spawn(Self::handle_connection(
+            conn,
+            peer_node_id,
+            self.config.node_id.clone(),
+            self.event_tx.clone(),
+        ));
+
         Ok(peer)
     }
 
@@ -410,9 +743,152 @@ impl TransportService {
     }
 
     /// Broadcast a changeset to all peers.
+    ///
+    /// Every alive peer is tracked as owing an ack for the changeset, even if
+    /// the send failed, so `resend_unacked` retries it (at-least-once delivery).
     #[cfg_attr(coverage_nightly, coverage(off))]
     pub async fn broadcast_changeset(&self, changeset: &ChangeSet) -> usize {
-        self.broadcast(&Message::Broadcast(changeset.clone())).await
+        let peers: Vec<_> = self.peers.read().await.values().cloned().collect();
+        let msg = Message::Broadcast(changeset.clone());
+        let mut sent = 0;
+
+        for peer in peers {
+            if !peer.is_alive() {
+                continue;
+            }
+            if peer.send(&msg).await.is_ok() {
+                sent += 1;
+            }
+            self.acks.lock().track(&peer.node_id.0, changeset, Instant::now());
+        }
+
+        sent
+    }
+
+    /// Send a message to a specific peer.
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    pub async fn send_to(&self, node_id: &str, msg: &Message) -> anyhow::Result<()> {
+        let peer = self
+            .get_peer(node_id)
+            .await
+            .ok_or_else(|| anyhow::anyhow!("unknown peer {}", node_id))?;
+        peer.send(msg).await
+    }
+
+    /// Record an ack received from a peer.
+    ///
+    /// Returns true if it matched an un-acked changeset.
+    pub fn handle_ack(&self, from: &NodeId, source: &NodeId, seq: u64) -> bool {
+        self.acks.lock().acknowledge(&from.0, source, seq)
+    }
+
+    /// Number of changesets still waiting for an ack from a peer.
+    pub fn pending_acks(&self, node_id: &str) -> usize {
+        self.acks.lock().pending_count(node_id)
+    }
+
+    /// Re-send changesets whose ack timed out.
+    ///
+    /// Peers that are no longer known are forgotten; peers whose connection
+    /// is down keep their pending changesets for the next attempt.
+    /// Returns the number of changesets re-sent.
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    pub async fn resend_unacked(&self) -> usize {
+        let due = self.acks.lock().take_due(Instant::now());
+        let mut resent = 0;
+
+        for (node_id, changeset) in due {
+            let Some(peer) = self.get_peer(&node_id).await else {
+                let dropped = self.acks.lock().forget_peer(&node_id);
+                tracing::debug!("dropping {} un-acked changesets for gone peer {}", dropped, node_id);
+                continue;
+            };
+            if !peer.is_alive() {
+                continue;
+            }
+
+            tracing::debug!(
+                "re-sending un-acked changeset seq={} from {} to {}",
+                changeset.seq,
+                changeset.source,
+                node_id
+            );
+            if peer.send(&Message::Broadcast(changeset)).await.is_ok() {
+                resent += 1;
+            }
+        }
+
+        resent
+    }
+
+    /// Ping every connected peer once, disconnecting peers that have
+    /// missed `max_missed_pings` pings in a row.
+    ///
+    /// Detects dead QUIC connections sooner than waiting for a stream
+    /// error or the idle timeout. Returns the peers that were disconnected.
+    pub async fn ping_peers(&self) -> Vec<NodeId> {
+        let timeout = self.config.ping_timeout;
+        let mut pings = JoinSet::new();
+        for peer in self.peers().await {
+            pings.spawn(async move {
+                let result = peer.ping(timeout).await;
+                (peer, result)
+            });
+        }
+
+        let mut disconnected = Vec::new();
+        while let Some(joined) = pings.join_next().await {
+            let Ok((peer, result)) = joined else {
+                continue;
+            };
+
+            let dead = match result {
+                Ok(rtt) => {
+                    tracing::trace!("pong from {} in {:?}", peer.node_id, rtt);
+                    self.pings.lock().record_pong(&peer.node_id.0, rtt);
+                    false
+                }
+                Err(e) => {
+                    tracing::debug!("ping to {} failed: {:?}", peer.node_id, e);
+                    self.pings.lock().record_miss(&peer.node_id.0)
+                }
+            };
+
+            if dead {
+                self.disconnect_peer(&peer).await;
+                disconnected.push(peer.node_id.clone());
+            }
+        }
+
+        disconnected
+    }
+
+    /// Round trip time of the last keepalive ping answered by a peer.
+    pub fn peer_rtt(&self, node_id: &str) -> Option<Duration> {
+        self.pings.lock().last_rtt(node_id)
+    }
+
+    /// Drop a peer whose keepalive failed and close its connection.
+    async fn disconnect_peer(&self, peer: &Arc<PeerConnection>) {
+        tracing::warn!(
+            "peer {} missed {} pings, disconnecting",
+            peer.node_id,
+            self.pings.lock().missed(&peer.node_id.0)
+        );
+
+        {
+            // Leave a newer connection to the same node alone
+            let mut peers = self.peers.write().await;
+            if peers.get(&peer.node_id.0).is_some_and(|p| Arc::ptr_eq(p, peer)) {
+                peers.remove(&peer.node_id.0);
+            }
+        }
+        self.pings.lock().forget_peer(&peer.node_id.0);
+        peer.connection.close(0u32.into(), b"ping timeout");
+
+        self.event_tx
+            .send(TransportEvent::PeerDisconnected(peer.node_id.clone()))
+            .await;
     }
 
     #[cfg_attr(coverage_nightly, coverage(off))]
@@ -420,68 +896,113 @@ impl TransportService {
         conn: QuinnConnection,
         peer_node_id: NodeId,
         _local_node_id: String,
-        event_tx: mpsc::Sender<TransportEvent>,
+        event_tx: EventSender<TransportEvent>,
     ) {
-        loop {
-            // Accept uni streams
-            match conn.accept_uni().await {
-                Ok(mut recv) => {
-                    let event_tx = event_tx.clone();
-                    let peer_id = peer_node_id.clone();
-
-                    tokio::spawn(async move {
-                        // Read length prefix
-                        let mut len_buf = [0u8; 4];
-                        if recv.read_exact(&mut len_buf).await.is_err() {
-                            return;
-                        }
-                        let len = u32::from_be_bytes(len_buf) as usize;
-
-                        if len > 10 * 1024 * 1024 {
-                            // Max 10MB message
-                            tracing::warn!("message too large: {} bytes", len);
-                            return;
-                        }
-
-                        // Read message
-                        let mut data = vec![0u8; len];
-                        if recv.read_exact(&mut data).await.is_err() {
-                            return;
-                        }
-
-                        // Deserialize
-                        match bincode::deserialize::<Message>(&data) {
-                            Ok(msg) => {
-                                let _ = event_tx
+        let error = loop {
+            tokio::select! {
+                // One-way messages
+                stream = conn.accept_uni() => match stream {
+                    Ok(mut recv) => {
+                        let event_tx = event_tx.clone();
+                        let peer_id = peer_node_id.clone();
+
+                        tokio::spawn(async move {
+                            if let Some(msg) = Self::read_message(&mut recv).await {
+                                event_tx
                                     .send(TransportEvent::MessageReceived {
                                         from: peer_id,
                                         message: msg,
                                     })
                                     .await;
                             }
-                            Err(e) => {
-                                tracing::debug!("failed to deserialize message: {:?}", e);
+                        });
+                    }
+                    Err(e) => break e,
+                },
+                // Requests expecting a reply (e.g. Ping)
+                stream = conn.accept_bi() => match stream {
+                    Ok((mut send, mut recv)) => {
+                        let event_tx = event_tx.clone();
+                        let peer_id = peer_node_id.clone();
+
+                        tokio::spawn(async move {
+                            let Some(msg) = Self::read_message(&mut recv).await else {
+                                return;
+                            };
+                            let reply = if needs_application_reply(&msg) {
+                                let (reply_tx, reply_rx) = oneshot::channel();
+                                event_tx
+                                    .send(TransportEvent::RequestReceived {
+                                        from: peer_id,
+                                        message: msg,
+                                        reply: reply_tx,
+                                    })
+                                    .await;
+                                match reply_rx.await {
+                                    Ok(reply) => reply,
+                                    Err(_) => return,
+                                }
+                            } else if let Some(reply) = reply_to_request(&msg) {
+                                reply
+                            } else {
+                                event_tx
+                                    .send(TransportEvent::MessageReceived {
+                                        from: peer_id,
+                                        message: msg,
+                                    })
+                                    .await;
+                                return;
+                            };
+
+                            let Ok(data) = encode_message(&reply) else {
+                                return;
+                            };
+                            if send.write_all(&data).await.is_ok() {
+                                let _ = send.finish();
                             }
-                        }
-                    });
-                }
-                Err(quinn::ConnectionError::ApplicationClosed(_)) => {
-                    break;
-                }
-                Err(e) => {
-                    tracing::debug!("connection error: {:?}", e);
-                    break;
-                }
+                        });
+                    }
+                    Err(e) => break e,
+                },
             }
+        };
+
+        match error {
+            // Closed on our side (e.g. failed keepalive), which reports the disconnect itself
+            quinn::ConnectionError::LocallyClosed => return,
+            quinn::ConnectionError::ApplicationClosed(_) => {}
+            e => tracing::debug!("connection error: {:?}", e),
         }
 
-        let _ = event_tx
+        event_tx
             .send(TransportEvent::PeerDisconnected(peer_node_id))
             .await;
     }
+
+    /// Read one length-prefixed message from a stream.
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    async fn read_message(recv: &mut quinn::RecvStream) -> Option<Message> {
+        let data = match read_frame(recv).await {
+            Ok(data) => data,
+            Err(e @ TransportError::TooLarge(_)) => {
+                tracing::warn!("dropping peer message: {}", e);
+                return None;
+            }
+            Err(_) => return None,
+        };
+
+        // Deserialize
+        match decode_message(&data) {
+            Ok(msg) => Some(msg),
+            Err(e) => {
+                tracing::debug!("failed to deserialize message: {:?}", e);
+                None
+            }
+        }
+    }
 }
 
-/// Skip server certificate verification for self-signed certs in cluster.
+/// Skip server certificate verification (`insecure_transport` only).
 #[derive(Debug)]
 struct SkipServerVerification;
 
@@ -538,10 +1059,18 @@ mod tests {
     use crate::replication::types::{Change, ChangeKind};
     use quinn::rustls::client::danger::ServerCertVerifier;
 
+    const TEST_SECRET: &str = "test-cluster-secret";
+
+    /// Loopback config of a node in the test cluster.
+    fn local_config(node_id: &str) -> ReplicationConfig {
+        ReplicationConfig::new(node_id)
+            .transport_addr("127.0.0.1:0".parse().unwrap())
+            .cluster_secret(TEST_SECRET)
+    }
+
     #[test]
     fn test_transport_service_creation() {
-        let config = ReplicationConfig::new("test-node")
-            .transport_addr("127.0.0.1:0".parse().unwrap());
+        let config = local_config("test-node");
 
         let service = TransportService::new(config);
         assert!(!service.is_shutdown());
@@ -699,8 +1228,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_transport_service_start() {
-        let config = ReplicationConfig::new("test-node")
-            .transport_addr("127.0.0.1:0".parse().unwrap());
+        let config = local_config("test-node");
 
         let mut service = TransportService::new(config);
         let result = service.start().await;
@@ -714,8 +1242,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_transport_service_broadcast_no_peers() {
-        let config = ReplicationConfig::new("test-node")
-            .transport_addr("127.0.0.1:0".parse().unwrap());
+        let config = local_config("test-node");
 
         let mut service = TransportService::new(config);
         service.start().await.unwrap();
@@ -758,11 +1285,80 @@ mod tests {
         assert!(debug.contains("SkipServerVerification"));
     }
 
+    // ===== Peer Trust Tests =====
+
+    #[tokio::test]
+    async fn test_transport_start_requires_cluster_secret() {
+        let config = ReplicationConfig::new("test-node")
+            .transport_addr("127.0.0.1:0".parse().unwrap());
+        let mut service = TransportService::new(config);
+
+        let err = service.start().await.unwrap_err();
+        assert!(err.to_string().contains("cluster secret"));
+        assert!(service.endpoint.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_transport_insecure_peers_communicate() {
+        let insecure = |node_id: &str| {
+            ReplicationConfig::new(node_id)
+                .transport_addr("127.0.0.1:0".parse().unwrap())
+                .insecure_transport(true)
+        };
+        let mut service1 = TransportService::new(insecure("node-1"));
+        service1.start().await.unwrap();
+        let addr1 = service1.endpoint.as_ref().unwrap().local_addr().unwrap();
+        let mut service2 = TransportService::new(insecure("node-2"));
+        service2.start().await.unwrap();
+
+        assert!(service2.connect(addr1, "node-1").await.is_ok());
+
+        service1.shutdown();
+        service2.shutdown();
+    }
+
+    #[tokio::test]
+    async fn test_transport_rejects_server_from_other_cluster() {
+        let mut service1 = TransportService::new(local_config("node-1"));
+        service1.start().await.unwrap();
+        let addr1 = service1.endpoint.as_ref().unwrap().local_addr().unwrap();
+
+        let config2 = local_config("node-2").cluster_secret("another-secret");
+        let mut service2 = TransportService::new(config2);
+        service2.start().await.unwrap();
+
+        assert!(service2.connect(addr1, "node-1").await.is_err());
+        assert!(service2.get_peer("node-1").await.is_none());
+
+        service1.shutdown();
+        service2.shutdown();
+    }
+
+    #[tokio::test]
+    async fn test_transport_rejects_client_without_cluster_cert() {
+        let mut service1 = TransportService::new(local_config("node-1"));
+        service1.start().await.unwrap();
+        let addr1 = service1.endpoint.as_ref().unwrap().local_addr().unwrap();
+
+        // Trusts anything, but has no certificate from the cluster CA to present
+        let config2 = ReplicationConfig::new("node-2")
+            .transport_addr("127.0.0.1:0".parse().unwrap())
+            .insecure_transport(true);
+        let mut service2 = TransportService::new(config2);
+        service2.start().await.unwrap();
+
+        let _ = service2.connect(addr1, "node-1").await;
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(service1.peer_count().await, 0);
+
+        service1.shutdown();
+        service2.shutdown();
+    }
+
     #[tokio::test]
     async fn test_transport_two_services_communicate() {
         // Start first service
-        let config1 = ReplicationConfig::new("node-1")
-            .transport_addr("127.0.0.1:0".parse().unwrap());
+        let config1 = local_config("node-1");
         let mut service1 = TransportService::new(config1);
         service1.start().await.unwrap();
 
@@ -770,8 +1366,7 @@ mod tests {
         let addr1 = service1.endpoint.as_ref().unwrap().local_addr().unwrap();
 
         // Start second service
-        let config2 = ReplicationConfig::new("node-2")
-            .transport_addr("127.0.0.1:0".parse().unwrap());
+        let config2 = local_config("node-2");
         let mut service2 = TransportService::new(config2);
         service2.start().await.unwrap();
 
@@ -795,15 +1390,13 @@ mod tests {
     #[tokio::test]
     async fn test_peer_connection_is_alive() {
         // Start first service
-        let config1 = ReplicationConfig::new("node-1")
-            .transport_addr("127.0.0.1:0".parse().unwrap());
+        let config1 = local_config("node-1");
         let mut service1 = TransportService::new(config1);
         service1.start().await.unwrap();
         let addr1 = service1.endpoint.as_ref().unwrap().local_addr().unwrap();
 
         // Start second service and connect
-        let config2 = ReplicationConfig::new("node-2")
-            .transport_addr("127.0.0.1:0".parse().unwrap());
+        let config2 = local_config("node-2");
         let mut service2 = TransportService::new(config2);
         service2.start().await.unwrap();
 
@@ -817,15 +1410,13 @@ mod tests {
     #[tokio::test]
     async fn test_peer_connection_send() {
         // Start receiver service
-        let config1 = ReplicationConfig::new("receiver")
-            .transport_addr("127.0.0.1:0".parse().unwrap());
+        let config1 = local_config("receiver");
         let mut service1 = TransportService::new(config1);
         service1.start().await.unwrap();
         let addr1 = service1.endpoint.as_ref().unwrap().local_addr().unwrap();
 
         // Start sender service and connect
-        let config2 = ReplicationConfig::new("sender")
-            .transport_addr("127.0.0.1:0".parse().unwrap());
+        let config2 = local_config("sender");
         let mut service2 = TransportService::new(config2);
         service2.start().await.unwrap();
 
@@ -843,15 +1434,13 @@ mod tests {
     #[tokio::test]
     async fn test_broadcast_to_connected_peers() {
         // Start receiver
-        let config1 = ReplicationConfig::new("receiver")
-            .transport_addr("127.0.0.1:0".parse().unwrap());
+        let config1 = local_config("receiver");
         let mut service1 = TransportService::new(config1);
         service1.start().await.unwrap();
         let addr1 = service1.endpoint.as_ref().unwrap().local_addr().unwrap();
 
         // Start broadcaster and connect
-        let config2 = ReplicationConfig::new("broadcaster")
-            .transport_addr("127.0.0.1:0".parse().unwrap());
+        let config2 = local_config("broadcaster");
         let mut service2 = TransportService::new(config2);
         service2.start().await.unwrap();
 
@@ -866,8 +1455,273 @@ mod tests {
         service2.shutdown();
     }
 
+    #[tokio::test]
+    async fn test_broadcast_acked_by_receiver() {
+        let config1 = local_config("receiver");
+        let mut service1 = TransportService::new(config1);
+        let mut rx1 = service1.take_event_rx().unwrap();
+        service1.start().await.unwrap();
+        let addr1 = service1.endpoint.as_ref().unwrap().local_addr().unwrap();
+
+        let config2 = local_config("sender");
+        let mut service2 = TransportService::new(config2);
+        let mut rx2 = service2.take_event_rx().unwrap();
+        service2.start().await.unwrap();
+        service2.connect(addr1, "receiver").await.unwrap();
+
+        let changeset = ChangeSet::new(NodeId::new("sender"), 7, vec![]);
+        assert_eq!(service2.broadcast_changeset(&changeset).await, 1);
+        assert_eq!(service2.pending_acks("receiver"), 1);
+
+        // Receiver gets the broadcast and acks it on the same connection
+        let (from, cs) = next_broadcast(&mut rx1).await;
+        service1.send_to(&from.0, &create_ack(&cs)).await.unwrap();
+
+        // Sender reads the ack from its outbound connection
+        let (from, source, seq) = loop {
+            let event = tokio::time::timeout(Duration::from_secs(5), rx2.recv())
+                .await
+                .unwrap()
+                .unwrap();
+            if let TransportEvent::MessageReceived { from, message: Message::Ack { source, seq } } = event {
+                break (from, source, seq);
+            }
+        };
+        assert_eq!(from.as_str(), "receiver");
+        assert!(service2.handle_ack(&from, &source, seq));
+        assert_eq!(service2.pending_acks("receiver"), 0);
+
+        service1.shutdown();
+        service2.shutdown();
+    }
+
+    #[tokio::test]
+    async fn test_missing_ack_triggers_resend() {
+        let config1 = local_config("receiver");
+        let mut service1 = TransportService::new(config1);
+        let mut rx1 = service1.take_event_rx().unwrap();
+        service1.start().await.unwrap();
+        let addr1 = service1.endpoint.as_ref().unwrap().local_addr().unwrap();
+
+        let config2 = local_config("sender")
+            .ack_timeout(Duration::ZERO);
+        let mut service2 = TransportService::new(config2);
+        service2.start().await.unwrap();
+        service2.connect(addr1, "receiver").await.unwrap();
+
+        let changeset = ChangeSet::new(NodeId::new("sender"), 3, vec![]);
+        service2.broadcast_changeset(&changeset).await;
+        let (_, first) = next_broadcast(&mut rx1).await;
+        assert_eq!(first.seq, 3);
+
+        // No ack sent: the changeset is delivered again
+        assert_eq!(service2.resend_unacked().await, 1);
+        let (_, second) = next_broadcast(&mut rx1).await;
+        assert_eq!(second.seq, 3);
+        assert_eq!(service2.pending_acks("receiver"), 1);
+
+        service1.shutdown();
+        service2.shutdown();
+    }
+
+    #[tokio::test]
+    async fn test_resend_forgets_unknown_peer() {
+        let config = ReplicationConfig::new("sender").ack_timeout(Duration::ZERO);
+        let service = TransportService::new(config);
+
+        let changeset = ChangeSet::new(NodeId::new("sender"), 1, vec![]);
+        service.acks.lock().track("gone", &changeset, Instant::now());
+
+        assert_eq!(service.resend_unacked().await, 0);
+        assert_eq!(service.pending_acks("gone"), 0);
+    }
+
+    async fn next_broadcast(rx: &mut mpsc::Receiver<TransportEvent>) -> (NodeId, ChangeSet) {
+        loop {
+            let event = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+                .await
+                .unwrap()
+                .unwrap();
+            if let TransportEvent::MessageReceived { from, message: Message::Broadcast(cs) } = event {
+                return (from, cs);
+            }
+        }
+    }
+
+    // ===== Framing Tests =====
+
+    #[tokio::test]
+    async fn test_read_frame_roundtrip() {
+        let data = encode_message(&Message::Ping).unwrap();
+        let frame = read_frame(&mut data.as_slice()).await.unwrap();
+        assert!(matches!(decode_message(&frame).unwrap(), Message::Ping));
+        assert_eq!(frame.len(), data.len() - 4);
+    }
+
+    #[tokio::test]
+    async fn test_read_frame_rejects_oversized_length() {
+        // Only the prefix is sent: the declared length alone is refused
+        let len = (MAX_MESSAGE_SIZE as u32 + 1).to_be_bytes();
+        let result = read_frame(&mut len.as_slice()).await;
+        assert_eq!(result, Err(TransportError::TooLarge(MAX_MESSAGE_SIZE + 1)));
+
+        let len = u32::MAX.to_be_bytes();
+        let result = read_frame(&mut len.as_slice()).await;
+        assert_eq!(result, Err(TransportError::TooLarge(u32::MAX as usize)));
+    }
+
+    #[tokio::test]
+    async fn test_read_frame_stream_closed_mid_body() {
+        let (mut writer, mut reader) = tokio::io::duplex(64);
+        tokio::io::AsyncWriteExt::write_all(&mut writer, &100u32.to_be_bytes()).await.unwrap();
+        tokio::io::AsyncWriteExt::write_all(&mut writer, &[0u8; 10]).await.unwrap();
+        drop(writer);
+
+        let result = read_frame(&mut reader).await;
+        assert_eq!(result, Err(TransportError::Incomplete { expected: 100, received: 10 }));
+    }
+
+    #[tokio::test]
+    async fn test_read_frame_stream_closed_mid_length() {
+        let result = read_frame(&mut [0u8, 0].as_slice()).await;
+        assert_eq!(result, Err(TransportError::Incomplete { expected: 4, received: 2 }));
+    }
+
+    #[tokio::test]
+    async fn test_request_without_reply_is_incomplete() {
+        let config1 = local_config("node-1");
+        let mut service1 = TransportService::new(config1);
+        service1.start().await.unwrap();
+        let addr1 = service1.endpoint.as_ref().unwrap().local_addr().unwrap();
+
+        let mut service2 = TransportService::new(local_config("node-2"));
+        service2.start().await.unwrap();
+        let peer = service2.connect(addr1, "node-1").await.unwrap();
+
+        // Pong has no reply, so node-1 closes the stream without writing
+        let err = peer.request(&Message::Pong).await.unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<TransportError>(),
+            Some(&TransportError::Incomplete { expected: 4, received: 0 })
+        );
+
+        service1.shutdown();
+        service2.shutdown();
+    }
+
     // ==================== Sans-IO Tests ====================
 
+    #[test]
+    fn test_create_and_extract_ack() {
+        let cs = ChangeSet::new(NodeId::new("node-1"), 9, vec![]);
+        let msg = create_ack(&cs);
+
+        assert_eq!(message_type_name(&msg), "Ack");
+        let (source, seq) = extract_ack(&msg).unwrap();
+        assert_eq!(source.as_str(), "node-1");
+        assert_eq!(seq, 9);
+        assert!(extract_ack(&Message::Ping).is_none());
+    }
+
+    #[test]
+    fn test_ack_tracker_acknowledge() {
+        let mut tracker = AckTracker::new(Duration::from_secs(1));
+        let source = NodeId::new("node-1");
+        let now = Instant::now();
+
+        tracker.track("peer-a", &ChangeSet::new(source.clone(), 1, vec![]), now);
+        tracker.track("peer-a", &ChangeSet::new(source.clone(), 2, vec![]), now);
+        tracker.track("peer-b", &ChangeSet::new(source.clone(), 1, vec![]), now);
+        assert_eq!(tracker.pending_count("peer-a"), 2);
+
+        assert!(tracker.acknowledge("peer-a", &source, 1));
+        assert_eq!(tracker.pending_count("peer-a"), 1);
+        assert_eq!(tracker.pending_count("peer-b"), 1);
+
+        // Duplicate ack, wrong source and unknown peer are ignored
+        assert!(!tracker.acknowledge("peer-a", &source, 1));
+        assert!(!tracker.acknowledge("peer-a", &NodeId::new("other"), 2));
+        assert!(!tracker.acknowledge("peer-c", &source, 2));
+        assert_eq!(tracker.pending_count("peer-a"), 1);
+    }
+
+    #[test]
+    fn test_ack_tracker_take_due_after_timeout() {
+        let mut tracker = AckTracker::new(Duration::from_secs(2));
+        let source = NodeId::new("node-1");
+        let start = Instant::now();
+
+        tracker.track("peer-a", &ChangeSet::new(source.clone(), 5, vec![]), start);
+
+        // Not due before the timeout
+        assert!(tracker.take_due(start + Duration::from_secs(1)).is_empty());
+
+        let due = tracker.take_due(start + Duration::from_secs(2));
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].0, "peer-a");
+        assert_eq!(due[0].1.seq, 5);
+        assert_eq!(tracker.attempts("peer-a", 5), Some(2));
+
+        // Re-send restarts the timeout
+        assert!(tracker.take_due(start + Duration::from_secs(3)).is_empty());
+        assert_eq!(tracker.take_due(start + Duration::from_secs(4)).len(), 1);
+
+        // Acked changesets are never re-sent
+        assert!(tracker.acknowledge("peer-a", &source, 5));
+        assert!(tracker.take_due(start + Duration::from_secs(10)).is_empty());
+    }
+
+    #[test]
+    fn test_ack_tracker_gives_up_after_max_attempts() {
+        let mut tracker = AckTracker::new(Duration::from_secs(1)).with_max_attempts(3);
+        let source = NodeId::new("node-1");
+        let start = Instant::now();
+
+        tracker.track("peer-a", &ChangeSet::new(source.clone(), 1, vec![]), start);
+        tracker.track("peer-a", &ChangeSet::new(source, 2, vec![]), start + Duration::from_secs(1));
+
+        // Seq 1 is re-sent twice (3 attempts in total), then dropped
+        assert_eq!(tracker.take_due(start + Duration::from_secs(1)).len(), 1);
+        assert_eq!(tracker.take_due(start + Duration::from_secs(2)).len(), 2);
+        assert_eq!(tracker.attempts("peer-a", 1), Some(3));
+        let due = tracker.take_due(start + Duration::from_secs(3));
+        assert_eq!(due.iter().map(|(_, cs)| cs.seq).collect::<Vec<_>>(), vec![2]);
+        assert_eq!(tracker.attempts("peer-a", 1), None);
+        assert_eq!(tracker.pending_count("peer-a"), 1);
+
+        // Once everything is given up on, the peer has nothing pending
+        assert!(tracker.take_due(start + Duration::from_secs(4)).is_empty());
+        assert_eq!(tracker.pending_count("peer-a"), 0);
+        assert!(tracker.pending.is_empty());
+    }
+
+    #[test]
+    fn test_ack_tracker_track_again_counts_attempt() {
+        let mut tracker = AckTracker::new(Duration::from_secs(1));
+        let cs = ChangeSet::new(NodeId::new("node-1"), 1, vec![]);
+        let now = Instant::now();
+
+        tracker.track("peer-a", &cs, now);
+        tracker.track("peer-a", &cs, now);
+
+        assert_eq!(tracker.pending_count("peer-a"), 1);
+        assert_eq!(tracker.attempts("peer-a", 1), Some(2));
+    }
+
+    #[test]
+    fn test_ack_tracker_forget_peer() {
+        let mut tracker = AckTracker::new(Duration::from_secs(1));
+        let source = NodeId::new("node-1");
+        let now = Instant::now();
+
+        tracker.track("peer-a", &ChangeSet::new(source.clone(), 1, vec![]), now);
+        tracker.track("peer-a", &ChangeSet::new(source, 2, vec![]), now);
+
+        assert_eq!(tracker.forget_peer("peer-a"), 2);
+        assert_eq!(tracker.pending_count("peer-a"), 0);
+        assert_eq!(tracker.forget_peer("peer-a"), 0);
+    }
+
     #[test]
     fn test_encode_message_broadcast() {
         let cs = ChangeSet::new(NodeId::new("node-1"), 1, vec![]);
@@ -1182,6 +2036,19 @@ mod tests {
         assert_eq!(message_type_name(&ack), "Ack");
         assert_eq!(message_type_name(&ping), "Ping");
         assert_eq!(message_type_name(&pong), "Pong");
+        assert_eq!(message_type_name(&Message::SnapshotRequest), "SnapshotRequest");
+        assert_eq!(
+            message_type_name(&Message::SnapshotResponse(Default::default())),
+            "SnapshotResponse"
+        );
+    }
+
+    #[test]
+    fn test_needs_application_reply() {
+        assert!(needs_application_reply(&Message::SnapshotRequest));
+        assert!(!needs_application_reply(&Message::Ping));
+        assert!(!needs_application_reply(&Message::Pong));
+        assert!(reply_to_request(&Message::SnapshotRequest).is_none());
     }
 
     #[test]
@@ -1270,4 +2137,206 @@ mod tests {
         assert!(debug.contains("MessageReceived"));
         assert!(debug.contains("sender"));
     }
+
+    // ===== Keepalive Tests =====
+
+    #[test]
+    fn test_reply_to_request_ping() {
+        assert!(matches!(reply_to_request(&Message::Ping), Some(Message::Pong)));
+        assert!(reply_to_request(&Message::Pong).is_none());
+        assert!(reply_to_request(&create_sync_request(0, None)).is_none());
+    }
+
+    #[test]
+    fn test_ping_tracker_misses_until_threshold() {
+        let mut tracker = PingTracker::new(3);
+
+        assert!(!tracker.record_miss("peer-1"));
+        assert!(!tracker.record_miss("peer-1"));
+        assert_eq!(tracker.missed("peer-1"), 2);
+        assert!(tracker.record_miss("peer-1"));
+
+        // Other peers are tracked independently
+        assert_eq!(tracker.missed("peer-2"), 0);
+    }
+
+    #[test]
+    fn test_ping_tracker_pong_resets_misses() {
+        let mut tracker = PingTracker::new(2);
+
+        assert!(!tracker.record_miss("peer-1"));
+        tracker.record_pong("peer-1", Duration::from_millis(7));
+        assert_eq!(tracker.missed("peer-1"), 0);
+        assert_eq!(tracker.last_rtt("peer-1"), Some(Duration::from_millis(7)));
+        assert!(!tracker.record_miss("peer-1"));
+    }
+
+    #[test]
+    fn test_ping_tracker_zero_threshold_is_one() {
+        let mut tracker = PingTracker::new(0);
+        assert!(tracker.record_miss("peer-1"));
+    }
+
+    #[test]
+    fn test_ping_tracker_forget_peer() {
+        let mut tracker = PingTracker::new(3);
+        tracker.record_pong("peer-1", Duration::from_millis(1));
+        tracker.record_miss("peer-1");
+
+        tracker.forget_peer("peer-1");
+        assert_eq!(tracker.missed("peer-1"), 0);
+        assert!(tracker.last_rtt("peer-1").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_peer_ping_returns_pong() {
+        let config1 = local_config("node-1");
+        let mut service1 = TransportService::new(config1);
+        service1.start().await.unwrap();
+        let addr1 = service1.endpoint.as_ref().unwrap().local_addr().unwrap();
+
+        let config2 = local_config("node-2");
+        let mut service2 = TransportService::new(config2);
+        service2.start().await.unwrap();
+
+        let peer = service2.connect(addr1, "node-1").await.unwrap();
+
+        // The request gets a Pong back, not just any reply
+        let reply = peer.request(&Message::Ping).await.unwrap();
+        assert!(matches!(reply, Message::Pong));
+
+        let rtt = peer.ping(Duration::from_secs(5)).await.unwrap();
+        assert!(rtt < Duration::from_secs(5));
+
+        service1.shutdown();
+        service2.shutdown();
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_request_answered_by_event_handler() {
+        let mut service1 = TransportService::new(local_config("node-1"));
+        service1.start().await.unwrap();
+        let addr1 = service1.endpoint.as_ref().unwrap().local_addr().unwrap();
+        let mut events = service1.take_event_rx().unwrap();
+
+        let mut service2 = TransportService::new(local_config("node-2"));
+        service2.start().await.unwrap();
+        let peer = service2.connect(addr1, "node-1").await.unwrap();
+
+        let handler = tokio::spawn(async move {
+            while let Some(event) = events.recv().await {
+                if let TransportEvent::RequestReceived { message, reply, .. } = event {
+                    assert!(matches!(message, Message::SnapshotRequest));
+                    let snapshot = crate::replication::types::Snapshot {
+                        versions: vec![("node-1".to_string(), 3)],
+                        ..Default::default()
+                    };
+                    let _ = reply.send(Message::SnapshotResponse(snapshot));
+                    return;
+                }
+            }
+        });
+
+        match peer.request(&Message::SnapshotRequest).await.unwrap() {
+            Message::SnapshotResponse(snapshot) => {
+                assert_eq!(snapshot.versions, vec![("node-1".to_string(), 3)]);
+            }
+            other => panic!("expected SnapshotResponse, got {}", message_type_name(&other)),
+        }
+        handler.await.unwrap();
+
+        service1.shutdown();
+        service2.shutdown();
+    }
+
+    #[tokio::test]
+    async fn test_ping_peers_keeps_responsive_peer() {
+        let config1 = local_config("node-1");
+        let mut service1 = TransportService::new(config1);
+        service1.start().await.unwrap();
+        let addr1 = service1.endpoint.as_ref().unwrap().local_addr().unwrap();
+
+        let config2 = local_config("node-2")
+            .ping_timeout(Duration::from_secs(5))
+            .max_missed_pings(1);
+        let mut service2 = TransportService::new(config2);
+        service2.start().await.unwrap();
+        service2.connect(addr1, "node-1").await.unwrap();
+
+        assert!(service2.ping_peers().await.is_empty());
+        assert!(service2.get_peer("node-1").await.is_some());
+        assert!(service2.peer_rtt("node-1").is_some());
+
+        service1.shutdown();
+        service2.shutdown();
+    }
+
+    #[tokio::test]
+    async fn test_ping_peers_disconnects_after_missed_pongs() {
+        let config1 = local_config("node-1");
+        let mut service1 = TransportService::new(config1);
+        service1.start().await.unwrap();
+        let addr1 = service1.endpoint.as_ref().unwrap().local_addr().unwrap();
+
+        // A pong can never arrive within a nanosecond, so every ping is missed
+        let config2 = local_config("node-2")
+            .ping_timeout(Duration::from_nanos(1))
+            .max_missed_pings(2);
+        let mut service2 = TransportService::new(config2);
+        let mut events = service2.take_event_rx().unwrap();
+        service2.start().await.unwrap();
+        let peer = service2.connect(addr1, "node-1").await.unwrap();
+
+        // First miss is tolerated
+        assert!(service2.ping_peers().await.is_empty());
+        assert!(service2.get_peer("node-1").await.is_some());
+
+        // Second consecutive miss disconnects the peer
+        let disconnected = service2.ping_peers().await;
+        assert_eq!(disconnected, vec![NodeId::new("node-1")]);
+        assert!(service2.get_peer("node-1").await.is_none());
+        assert!(!peer.is_alive());
+
+        assert!(matches!(events.recv().await, Some(TransportEvent::PeerConnected(_))));
+        match events.recv().await {
+            Some(TransportEvent::PeerDisconnected(node_id)) => assert_eq!(node_id.0, "node-1"),
+            other => panic!("expected PeerDisconnected, got {:?}", other),
+        }
+
+        // The closed connection's reader doesn't report it a second time
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(events.try_recv().is_err());
+
+        service1.shutdown();
+        service2.shutdown();
+    }
+
+    #[tokio::test]
+    async fn test_full_event_channel_counts_dropped_events() {
+        let config1 = local_config("node-1");
+        let mut service1 = TransportService::new(config1);
+        service1.start().await.unwrap();
+        let addr1 = service1.endpoint.as_ref().unwrap().local_addr().unwrap();
+
+        let config2 = local_config("node-2")
+            .event_channel_capacity(1);
+        let mut service2 = TransportService::new(config2);
+        let mut events = service2.take_event_rx().unwrap();
+        service2.start().await.unwrap();
+
+        // The second PeerConnected finds the channel full and is dropped
+        service2.connect(addr1, "node-1").await.unwrap();
+        service2.connect(addr1, "node-1b").await.unwrap();
+
+        assert_eq!(service2.event_stats().sent(), 1);
+        assert_eq!(service2.event_stats().dropped(), 1);
+        match events.recv().await {
+            Some(TransportEvent::PeerConnected(node_id)) => assert_eq!(node_id.0, "node-1"),
+            other => panic!("expected PeerConnected, got {:?}", other),
+        }
+        assert!(events.try_recv().is_err());
+
+        service1.shutdown();
+        service2.shutdown();
+    }
 }
@@ -51,13 +51,18 @@ pub struct HLCTimestamp {
     pub node_hash: u32,
 }
 
+/// Current wall clock time in microseconds since UNIX epoch.
+pub fn wall_clock_micros() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_micros() as u64
+}
+
 impl HLCTimestamp {
     /// Create a new timestamp for the current time.
     pub fn now(node_id: &NodeId) -> Self {
-        let wall_time = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_micros() as u64;
+        let wall_time = wall_clock_micros();
 
         Self {
             wall_time,
@@ -66,12 +71,14 @@ impl HLCTimestamp {
         }
     }
 
+    /// Check whether this timestamp is further than `max_skew` ahead of `now_micros`.
+    pub fn exceeds_skew(&self, now_micros: u64, max_skew: std::time::Duration) -> bool {
+        self.wall_time > now_micros.saturating_add(max_skew.as_micros() as u64)
+    }
+
     /// Create a timestamp that is greater than self and other.
     pub fn tick(&self, other: Option<&HLCTimestamp>, node_id: &NodeId) -> Self {
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_micros() as u64;
+        let now = wall_clock_micros();
 
         let node_hash = crc32fast::hash(node_id.0.as_bytes());
 
@@ -172,6 +179,12 @@ impl Change {
         }
     }
 
+    /// Replace the timestamp (e.g. with one from a node's HLC).
+    pub fn with_timestamp(mut self, timestamp: HLCTimestamp) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+
     /// Check if this change wins over another for the same key.
     pub fn wins_over(&self, other: &Change) -> bool {
         self.timestamp > other.timestamp
@@ -214,6 +227,38 @@ impl ChangeSet {
     }
 }
 
+/// A row of the `backends` table, as carried in a [`Snapshot`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackendRow {
+    pub id: String,
+    pub app: String,
+    pub region: String,
+    pub country: Option<String>,
+    pub wg_ip: String,
+    pub port: i64,
+    pub healthy: i64,
+    pub weight: i64,
+    pub soft_limit: i64,
+    pub hard_limit: i64,
+    /// Soft-deleted rows are kept so a stale insert can't resurrect them
+    pub deleted: i64,
+    pub draining: i64,
+    pub maintenance: i64,
+    pub priority: i64,
+}
+
+/// Full replicated state of a node, used to bootstrap a new node
+/// without replaying the replication log.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// Every `backends` row, including soft-deleted ones
+    pub backends: Vec<BackendRow>,
+    /// Last-write timestamp of each row, by backend id
+    pub timestamps: Vec<(String, HLCTimestamp)>,
+    /// Version vector the rows reflect (node id, latest sequence)
+    pub versions: Vec<(String, u64)>,
+}
+
 /// Message types for peer communication.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Message {
@@ -235,6 +280,10 @@ pub enum Message {
     Ping,
     /// Pong response
     Pong,
+    /// Request a full state snapshot
+    SnapshotRequest,
+    /// Response with the full state snapshot
+    SnapshotResponse(Snapshot),
 }
 
 /// Generate a random ID using timestamp and random bits.
@@ -392,6 +441,34 @@ mod tests {
         assert_eq!(result.wall_time, wall);
     }
 
+    #[test]
+    fn test_hlc_exceeds_skew() {
+        let now = 1_000_000_000;
+        let max_skew = std::time::Duration::from_secs(1);
+
+        let within = HLCTimestamp { wall_time: now + 1_000_000, counter: 0, node_hash: 1 };
+        let beyond = HLCTimestamp { wall_time: now + 1_000_001, counter: 0, node_hash: 1 };
+        let past = HLCTimestamp { wall_time: 1, counter: 0, node_hash: 1 };
+
+        assert!(!within.exceeds_skew(now, max_skew));
+        assert!(beyond.exceeds_skew(now, max_skew));
+        assert!(!past.exceeds_skew(now, max_skew));
+    }
+
+    #[test]
+    fn test_hlc_exceeds_skew_no_overflow() {
+        let ts = HLCTimestamp { wall_time: u64::MAX, counter: 0, node_hash: 1 };
+        assert!(!ts.exceeds_skew(u64::MAX - 1, std::time::Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn test_change_with_timestamp() {
+        let node = NodeId::new("node-1");
+        let ts = HLCTimestamp { wall_time: 42, counter: 7, node_hash: 1 };
+        let change = Change::new("backends", "b1", ChangeKind::Insert, "{}", &node).with_timestamp(ts);
+        assert_eq!(change.timestamp, ts);
+    }
+
     #[test]
     fn test_node_id_from_owned_string() {
         let s = String::from("node-test");
@@ -497,6 +574,39 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_message_snapshot_response() {
+        let snapshot = Snapshot {
+            backends: vec![BackendRow {
+                id: "b1".to_string(),
+                app: "myapp".to_string(),
+                region: "eu".to_string(),
+                country: Some("DE".to_string()),
+                wg_ip: "10.0.0.1".to_string(),
+                port: 8080,
+                healthy: 1,
+                weight: 2,
+                soft_limit: 100,
+                hard_limit: 150,
+                deleted: 0,
+                draining: 0,
+                maintenance: 0,
+                priority: 1,
+            }],
+            timestamps: vec![("b1".to_string(), HLCTimestamp::now(&NodeId::new("node-1")))],
+            versions: vec![("node-1".to_string(), 7)],
+        };
+        let msg = Message::SnapshotResponse(snapshot.clone());
+
+        let bytes = bincode::serialize(&msg).unwrap();
+        let decoded: Message = bincode::deserialize(&bytes).unwrap();
+
+        match decoded {
+            Message::SnapshotResponse(decoded) => assert_eq!(decoded, snapshot),
+            _ => panic!("wrong message type"),
+        }
+    }
+
     #[test]
     fn test_message_ack() {
         let node = NodeId::new("node-1");
@@ -95,7 +95,7 @@ async fn test_gossip_join_message_over_udp() {
     let addr2 = socket2.local_addr().unwrap();
 
     // Create and send a Join message
-    let join = create_join("new-node", addr1, addr1);
+    let join = create_join("new-node", addr1, addr1, 2);
     let data = bincode::serialize(&join).unwrap();
     socket1.send_to(&data, addr2).await.unwrap();
 
@@ -105,10 +105,11 @@ async fn test_gossip_join_message_over_udp() {
     let received: GossipMessage = bincode::deserialize(&buf[..len]).unwrap();
 
     match received {
-        GossipMessage::Join { node_id, gossip_addr, transport_addr } => {
+        GossipMessage::Join { node_id, gossip_addr, transport_addr, incarnation } => {
             assert_eq!(node_id, "new-node");
             assert_eq!(gossip_addr, addr1);
             assert_eq!(transport_addr, addr1);
+            assert_eq!(incarnation, 2);
         }
         _ => panic!("Expected Join message"),
     }
@@ -294,3 +295,46 @@ async fn test_two_gossip_services_discovery() {
     service1.shutdown();
     service2.shutdown();
 }
+
+/// Test that a datagram with an unknown message variant doesn't disturb a running node
+#[tokio::test]
+async fn test_gossip_service_skips_unknown_variant() {
+    use edge_proxy::replication::config::ReplicationConfig;
+    use edge_proxy::replication::gossip::{
+        GossipService, create_join, encode_message, GOSSIP_WIRE_VERSION,
+    };
+
+    // Reserve an ephemeral port for the service
+    let gossip_addr = std::net::UdpSocket::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+
+    let config = ReplicationConfig::new("upgrade-node")
+        .gossip_addr(gossip_addr)
+        .transport_addr("127.0.0.1:0".parse().unwrap());
+    let service = Arc::new(GossipService::new(config));
+    service.clone().start().await.unwrap();
+
+    let peer = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let peer_addr = peer.local_addr().unwrap();
+
+    // Variant tag 99 is unknown to this node
+    let mut unknown = vec![GOSSIP_WIRE_VERSION];
+    unknown.extend_from_slice(&99u32.to_le_bytes());
+    unknown.extend_from_slice(b"future message");
+    peer.send_to(&unknown, gossip_addr).await.unwrap();
+
+    // A regular join afterwards must still be processed
+    let join = create_join("new-peer", peer_addr, "127.0.0.1:4002".parse().unwrap(), 1);
+    peer.send_to(&encode_message(&join).unwrap(), gossip_addr).await.unwrap();
+
+    let mut buf = [0u8; 1024];
+    let result = tokio::time::timeout(Duration::from_secs(2), peer.recv_from(&mut buf)).await;
+    assert!(result.is_ok(), "node should answer the join after the unknown datagram");
+
+    assert_eq!(service.members().len(), 1);
+    assert!(service.get_member("new-peer").is_some());
+
+    service.shutdown();
+}
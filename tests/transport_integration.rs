@@ -25,7 +25,8 @@ async fn test_transport_service_lifecycle() {
 
     let config = ReplicationConfig::new("transport-test-node")
         .gossip_addr("127.0.0.1:0".parse().unwrap())
-        .transport_addr("127.0.0.1:0".parse().unwrap());
+        .transport_addr("127.0.0.1:0".parse().unwrap())
+        .cluster_secret("integration-secret");
 
     let mut service = TransportService::new(config);
 
@@ -53,11 +54,13 @@ async fn test_two_transport_services() {
 
     let config1 = ReplicationConfig::new("node-1")
         .gossip_addr("127.0.0.1:0".parse().unwrap())
-        .transport_addr("127.0.0.1:0".parse().unwrap());
+        .transport_addr("127.0.0.1:0".parse().unwrap())
+        .cluster_secret("integration-secret");
 
     let config2 = ReplicationConfig::new("node-2")
         .gossip_addr("127.0.0.1:0".parse().unwrap())
-        .transport_addr("127.0.0.1:0".parse().unwrap());
+        .transport_addr("127.0.0.1:0".parse().unwrap())
+        .cluster_secret("integration-secret");
 
     let mut service1 = TransportService::new(config1);
     let mut service2 = TransportService::new(config2);
@@ -316,6 +319,7 @@ async fn test_replication_agent_integration() {
     let config = ReplicationConfig::new("agent-test")
         .gossip_addr("127.0.0.1:0".parse().unwrap())
         .transport_addr("127.0.0.1:0".parse().unwrap())
+        .cluster_secret("integration-secret")
         .db_path(temp.path().to_str().unwrap());
 
     let mut agent = ReplicationAgent::new(config).unwrap();
@@ -360,11 +364,13 @@ async fn test_two_replication_agents() {
     let config1 = ReplicationConfig::new("agent-1")
         .gossip_addr("127.0.0.1:0".parse().unwrap())
         .transport_addr("127.0.0.1:0".parse().unwrap())
+        .cluster_secret("integration-secret")
         .db_path(temp1.path().to_str().unwrap());
 
     let config2 = ReplicationConfig::new("agent-2")
         .gossip_addr("127.0.0.1:0".parse().unwrap())
         .transport_addr("127.0.0.1:0".parse().unwrap())
+        .cluster_secret("integration-secret")
         .db_path(temp2.path().to_str().unwrap());
 
     let mut agent1 = ReplicationAgent::new(config1).unwrap();
@@ -399,7 +405,8 @@ async fn test_transport_broadcast_no_peers() {
 
     let config = ReplicationConfig::new("broadcast-test")
         .gossip_addr("127.0.0.1:0".parse().unwrap())
-        .transport_addr("127.0.0.1:0".parse().unwrap());
+        .transport_addr("127.0.0.1:0".parse().unwrap())
+        .cluster_secret("integration-secret");
 
     let mut service = TransportService::new(config);
     service.start().await.unwrap();